@@ -0,0 +1,106 @@
+//! Baseline timings for the rendering and animation hot paths.
+//!
+//! Run with `cargo bench`; scenes are shared with the golden-image tests.
+
+#[path = "../tests/scenes/mod.rs"]
+mod scenes;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use matterhorn_core::{
+    blit_tile, build_palette, render_tile_cpu, set_render_threads, tile_iterator, Easing,
+    FractalKind, FractalParams, Keyframe, Keyframes, TileInfo,
+};
+use scenes::{default_view, golden_scenes, GOLDEN_SIZE};
+
+const VIEW: (u32, u32) = (256, 256);
+
+/// Single-threaded so the numbers measure the per-pixel kernel, not the scheduler.
+fn cpu_kernel(c: &mut Criterion) {
+    set_render_threads(1);
+    let tile = TileInfo::full(VIEW.0, VIEW.1);
+    let camera = default_view(VIEW.0);
+    let mut group = c.benchmark_group("cpu_kernel");
+    for max_iter in [100, 500, 2000] {
+        let params = FractalParams::new(FractalKind::Mandelbrot).with_max_iter(max_iter);
+        let palette = build_palette(&params, 2048);
+        group.bench_with_input(BenchmarkId::from_parameter(max_iter), &params, |b, p| {
+            b.iter(|| render_tile_cpu(&tile, p, &camera, &palette))
+        });
+    }
+    group.finish();
+    set_render_threads(0);
+}
+
+fn golden_scene_renders(c: &mut Criterion) {
+    set_render_threads(1);
+    let tile = TileInfo::full(GOLDEN_SIZE, GOLDEN_SIZE);
+    let mut group = c.benchmark_group("golden_scenes");
+    for scene in golden_scenes() {
+        let palette = build_palette(&scene.params, 2048);
+        group.bench_function(scene.name, |b| {
+            b.iter(|| render_tile_cpu(&tile, &scene.params, &scene.camera, &palette))
+        });
+    }
+    group.finish();
+    set_render_threads(0);
+}
+
+fn palette(c: &mut Criterion) {
+    let params = FractalParams::default();
+    c.bench_function("build_palette_2048", |b| {
+        b.iter(|| build_palette(black_box(&params), 2048))
+    });
+}
+
+/// Tiling and blitting at export sizes, with pre-filled tiles so only the copy is timed.
+fn tiling(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tile_and_blit");
+    group.sample_size(10);
+    for (w, h) in [(3840, 2160), (7680, 4320)] {
+        let tiles = tile_iterator(w, h, 2048);
+        let pixels: Vec<Vec<u8>> = tiles
+            .iter()
+            .map(|t| vec![0x7f; (t.tile_w * t.tile_h * 4) as usize])
+            .collect();
+        let mut frame = vec![0u8; (w as usize) * (h as usize) * 4];
+        group.bench_function(format!("{w}x{h}"), |b| {
+            b.iter(|| {
+                for (tile, px) in tile_iterator(w, h, 2048).iter().zip(&pixels) {
+                    blit_tile(&mut frame, w, tile, px);
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+fn keyframe_sampling(c: &mut Criterion) {
+    let keys = Keyframes {
+        keys: (0..1000)
+            .map(|i| Keyframe {
+                t: i as f32 * 0.1,
+                v: (i as f32 * 0.37).sin(),
+                easing: Easing::ALL[i % Easing::ALL.len()],
+            })
+            .collect(),
+    };
+    c.bench_function("keyframes_sample_1000", |b| {
+        b.iter(|| {
+            let mut acc = 0.0;
+            for i in 0..1000 {
+                acc += keys.sample(black_box(i as f32 * 0.1 + 0.05), 0.0);
+            }
+            acc
+        })
+    });
+}
+
+criterion_group!(
+    benches,
+    cpu_kernel,
+    golden_scene_renders,
+    palette,
+    tiling,
+    keyframe_sampling
+);
+criterion_main!(benches);
@@ -0,0 +1,68 @@
+/* Renders one frame of a project through the C API and writes it as a binary PPM.
+ *
+ *   cargo build --release --no-default-features --features headless,ffi
+ *   cc examples/c/render.c -Iinclude -Ltarget/release -lmatterhorn_core -o render
+ *   LD_LIBRARY_PATH=target/release ./render project.json frame.ppm 2.5
+ */
+#include <stdio.h>
+#include <stdlib.h>
+
+#include "matterhorn.h"
+
+#define WIDTH 640
+#define HEIGHT 360
+
+static char *read_file(const char *path) {
+  FILE *f = fopen(path, "rb");
+  if (!f) return NULL;
+  fseek(f, 0, SEEK_END);
+  long size = ftell(f);
+  rewind(f);
+  char *text = malloc(size + 1);
+  if (text && fread(text, 1, size, f) == (size_t)size) {
+    text[size] = '\0';
+  } else {
+    free(text);
+    text = NULL;
+  }
+  fclose(f);
+  return text;
+}
+
+static int check(MhStatus status) {
+  if (status != MH_STATUS_OK) {
+    const char *message = mh_last_error();
+    fprintf(stderr, "error %d: %s\n", status, message ? message : "unknown");
+  }
+  return status == MH_STATUS_OK;
+}
+
+int main(int argc, char **argv) {
+  if (argc < 3) {
+    fprintf(stderr, "usage: %s project.json out.ppm [seconds]\n", argv[0]);
+    return 2;
+  }
+  char *json = read_file(argv[1]);
+  if (!json) {
+    fprintf(stderr, "could not read %s\n", argv[1]);
+    return 1;
+  }
+
+  static uint8_t rgba[WIDTH * HEIGHT * 4];
+  MhRenderer *renderer = mh_renderer_new();
+  int ok = check(mh_renderer_load_project_json(renderer, json)) &&
+           check(mh_renderer_set_time(renderer, argc > 3 ? (float)atof(argv[3]) : 0.0f)) &&
+           check(mh_renderer_render(renderer, WIDTH, HEIGHT, rgba, sizeof rgba));
+  mh_renderer_free(renderer);
+  free(json);
+  if (!ok) return 1;
+
+  FILE *out = fopen(argv[2], "wb");
+  if (!out) return 1;
+  fprintf(out, "P6\n%d %d\n255\n", WIDTH, HEIGHT);
+  for (size_t i = 0; i < WIDTH * HEIGHT; i++) {
+    fwrite(&rgba[i * 4], 1, 3, out);
+  }
+  fclose(out);
+  return 0;
+}
@@ -0,0 +1,74 @@
+#ifndef MATTERHORN_H
+#define MATTERHORN_H
+
+/* Generated by cbindgen from src/ffi.rs; do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Result of an FFI call.
+typedef enum MhStatus {
+  MH_STATUS_OK = 0,
+  MH_STATUS_NULL_ARGUMENT = 1,
+  MH_STATUS_INVALID_UTF8 = 2,
+  MH_STATUS_INVALID_PROJECT = 3,
+  MH_STATUS_INVALID_ARGUMENT = 4,
+  MH_STATUS_BUFFER_TOO_SMALL = 5,
+  MH_STATUS_SCRIPT_ERROR = 6,
+  MH_STATUS_PANIC = 7,
+} MhStatus;
+
+// A project, its compiled script, and the time frames are evaluated at.
+typedef struct MhRenderer MhRenderer;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Creates a renderer holding the default project at time 0. Free it with
+// [`mh_renderer_free`].
+MhRenderer *mh_renderer_new(void);
+
+// Destroys a renderer. Passing NULL does nothing.
+//
+// # Safety
+// `renderer` must be NULL or come from [`mh_renderer_new`], and must not be used again.
+void mh_renderer_free(MhRenderer *renderer);
+
+// Replaces the renderer's project with one parsed from JSON text, as saved by the app.
+// On error the previous project is kept.
+//
+// # Safety
+// `renderer` must come from [`mh_renderer_new`]; `json` must be a NUL-terminated string.
+MhStatus mh_renderer_load_project_json(MhRenderer *renderer, const char *json);
+
+// Sets the playback time, in seconds, that frames are evaluated at.
+//
+// # Safety
+// `renderer` must come from [`mh_renderer_new`].
+MhStatus mh_renderer_set_time(MhRenderer *renderer, float seconds);
+
+// Renders the frame at the current time on the CPU into `rgba`, which holds
+// `width * height` tightly packed RGBA8 pixels, rows top to bottom. Sizes over 65536 px
+// per side, or too large to render in memory, fail with `MH_STATUS_INVALID_ARGUMENT`.
+//
+// # Safety
+// `renderer` must come from [`mh_renderer_new`]; `rgba` must be writable for `len` bytes.
+MhStatus mh_renderer_render(MhRenderer *renderer,
+                            uint32_t width,
+                            uint32_t height,
+                            uint8_t *rgba,
+                            size_t len);
+
+// Message for the last failed call on this thread, or NULL if none failed yet. The string
+// stays valid until the next failing call on the same thread.
+const char *mh_last_error(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* MATTERHORN_H */
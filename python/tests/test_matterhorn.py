@@ -0,0 +1,85 @@
+"""Tests for the `matterhorn` module. Build it first with `maturin develop`, then run `pytest python/tests`."""
+
+import json
+import os
+import shutil
+
+import numpy as np
+import pytest
+
+import matterhorn
+
+
+def small_julia():
+    proj = matterhorn.Project()
+    proj.fractal.kind = "julia"
+    proj.fractal.max_iter = 100
+    return proj
+
+
+def test_render_returns_rgba_array():
+    img = small_julia().render(64, 48, t=0.5)
+    assert img.shape == (48, 64, 4)
+    assert img.dtype == np.uint8
+    assert (img[..., 3] == 255).all()
+    # The array owns its pixels and can be edited in place.
+    img[0, 0] = 0
+
+
+def test_properties_edit_the_project_in_place():
+    proj = small_julia()
+    fractal = proj.fractal
+    fractal.max_iter = 5000
+    proj.camera.center = (-0.75, 0.1)
+    assert proj.fractal.max_iter == 5000
+
+    saved = json.loads(proj.to_json())
+    assert saved["fractal"]["kind"] == "julia"
+    assert saved["fractal"]["max_iter"] == 5000
+    assert saved["camera"]["center"] == {"re": -0.75, "im": pytest.approx(0.1)}
+
+
+def test_render_depends_on_parameters():
+    proj = small_julia()
+    before = proj.render(32, 32)
+    proj.fractal.palette_phase = 0.5
+    assert (proj.render(32, 32) != before).any()
+
+
+def test_save_and_load_round_trip(tmp_path):
+    proj = small_julia()
+    proj.name = "sweep"
+    proj.export_settings.codec = "vp9"
+    path = tmp_path / "sweep.mahproj"
+    proj.save(path)
+
+    loaded = matterhorn.Project.load(path)
+    assert loaded.name == "sweep"
+    assert loaded.fractal.kind == "julia"
+    assert loaded.export_settings.codec == "vp9"
+    assert (loaded.render(16, 16) == proj.render(16, 16)).all()
+
+
+def test_invalid_values_raise():
+    proj = matterhorn.Project()
+    with pytest.raises(ValueError):
+        proj.fractal.kind = "sierpinski"
+    with pytest.raises(ValueError):
+        proj.camera.scale = 0.0
+    with pytest.raises(ValueError):
+        proj.render(0, 10)
+    with pytest.raises(OSError):
+        matterhorn.Project.load("does-not-exist.json")
+
+
+@pytest.mark.skipif(shutil.which("ffmpeg") is None, reason="needs ffmpeg on PATH")
+def test_export_reports_progress(tmp_path):
+    proj = small_julia()
+    settings = proj.export_settings
+    settings.width, settings.height = 64, 48
+    settings.fps, settings.duration = 10, 0.5
+
+    calls = []
+    out = proj.export(tmp_path / "clip.mp4", progress_callback=lambda done, total: calls.append((done, total)))
+    assert os.path.exists(out)
+    assert calls[-1] == (5, 5)
@@ -0,0 +1,231 @@
+//! Adaptive anti-aliasing: a frame rendered at one sample per pixel gets more samples
+//! only where neighboring pixels disagree, leaving smooth gradients alone.
+
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    build_palette, sample_offsets, sample_points_cpu, shade_samples, Camera, FractalParams,
+    SamplePattern,
+};
+
+/// Samples every refined pixel takes first.
+pub const MIN_REFINE_SAMPLES: u32 = 4;
+
+/// Most samples a refined pixel can take.
+pub const MAX_REFINE_SAMPLES: u32 = 16;
+
+/// How exports refine edges with [`refine_edges`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdaptiveAa {
+    pub enabled: bool,
+    /// Largest difference in any channel, 0..1, a pixel can have from a neighbor without
+    /// being refined.
+    pub threshold: f32,
+    /// Samples a refined pixel can take, [`MIN_REFINE_SAMPLES`] to [`MAX_REFINE_SAMPLES`].
+    /// Pixels whose first samples agree stop at the minimum.
+    pub max_samples: u32,
+}
+
+impl Default for AdaptiveAa {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold: 0.1,
+            max_samples: MAX_REFINE_SAMPLES,
+        }
+    }
+}
+
+impl AdaptiveAa {
+    /// Whether frames of `params` get refined. Band contours are meant to be hard edges,
+    /// and frames supersampled at least as finely have nothing to gain.
+    pub fn applies_to(&self, params: &FractalParams) -> bool {
+        self.enabled
+            && !params.has_band_contours()
+            && self.samples() > params.supersample_side().pow(2)
+    }
+
+    /// [`AdaptiveAa::max_samples`] within its bounds.
+    pub fn samples(&self) -> u32 {
+        self.max_samples
+            .clamp(MIN_REFINE_SAMPLES, MAX_REFINE_SAMPLES)
+    }
+}
+
+/// Renders the edges in `rows` of a `size` frame again on the CPU, averaging jittered
+/// samples, and returns how many pixels it refined. `pixels` holds those rows as RGBA8,
+/// rendered from `params`; edges are only looked for within them.
+pub fn refine_edges(
+    pixels: &mut [u8],
+    size: (u32, u32),
+    rows: Range<u32>,
+    params: &FractalParams,
+    cam: &Camera,
+    aa: &AdaptiveAa,
+) -> u64 {
+    if !aa.applies_to(params) {
+        return 0;
+    }
+    let edges = edge_pixels(pixels, size.0, aa.threshold);
+    if edges.is_empty() {
+        return 0;
+    }
+    // The frame's own jitter when it has one, so refined pixels change every frame too.
+    let pattern = match params.sampling.pattern {
+        pattern if pattern.is_jittered() => pattern,
+        _ => SamplePattern::R2,
+    };
+    let offsets = sample_offsets(pattern, aa.samples(), params.sampling.seed);
+    let (first, rest) = offsets.split_at(MIN_REFINE_SAMPLES as usize);
+    let palette = build_palette(params, 2048);
+    let width = size.0 as usize;
+    let shade = |at: &[usize], offsets: &[[f32; 2]]| {
+        let points: Vec<[f32; 2]> = at
+            .iter()
+            .flat_map(|&i| {
+                let x = (i % width) as f32;
+                let y = (rows.start as usize + i / width) as f32;
+                offsets
+                    .iter()
+                    .map(move |[dx, dy]| [x + dx - 0.5, y + dy - 0.5])
+            })
+            .collect();
+        shade_samples(
+            &sample_points_cpu(&points, size, params, cam),
+            params,
+            &palette,
+        )
+    };
+
+    let mut sums = vec![([0u32; 4], 0u32); edges.len()];
+    let mut unsettled = Vec::new();
+    let colors = shade(&edges, first);
+    for (k, colors) in colors.chunks_exact(first.len() * 4).enumerate() {
+        add_colors(&mut sums[k], colors);
+        if spread(colors) > aa.threshold {
+            unsettled.push(k);
+        }
+    }
+    if !rest.is_empty() && !unsettled.is_empty() {
+        let at: Vec<usize> = unsettled.iter().map(|&k| edges[k]).collect();
+        let colors = shade(&at, rest);
+        for (&k, colors) in unsettled.iter().zip(colors.chunks_exact(rest.len() * 4)) {
+            add_colors(&mut sums[k], colors);
+        }
+    }
+    for (&i, (sum, count)) in edges.iter().zip(sums) {
+        let average = sum.map(|channel| ((channel + count / 2) / count) as u8);
+        pixels[i * 4..i * 4 + 4].copy_from_slice(&average);
+    }
+    edges.len() as u64
+}
+
+/// Indices of the pixels of `width`-wide RGBA8 rows that differ from a 4-neighbor by more
+/// than `threshold` in any channel, in order.
+fn edge_pixels(pixels: &[u8], width: u32, threshold: f32) -> Vec<usize> {
+    let width = width as usize;
+    let count = pixels.len() / 4;
+    let limit = threshold.max(0.0) * 255.0;
+    let differ = |a: usize, b: usize| {
+        pixels[a * 4..a * 4 + 4]
+            .iter()
+            .zip(&pixels[b * 4..b * 4 + 4])
+            .any(|(&x, &y)| f32::from(x.abs_diff(y)) > limit)
+    };
+    let mut edge = vec![false; count];
+    for i in 0..count {
+        let right = (i % width + 1 < width).then_some(i + 1);
+        let below = (i + width < count).then_some(i + width);
+        for n in [right, below].into_iter().flatten() {
+            if differ(i, n) {
+                edge[i] = true;
+                edge[n] = true;
+            }
+        }
+    }
+    (0..count).filter(|&i| edge[i]).collect()
+}
+
+/// Adds RGBA8 `colors` to a running sum and count of colors.
+fn add_colors((sum, count): &mut ([u32; 4], u32), colors: &[u8]) {
+    for color in colors.chunks_exact(4) {
+        for (channel, &value) in sum.iter_mut().zip(color) {
+            *channel += u32::from(value);
+        }
+        *count += 1;
+    }
+}
+
+/// Largest spread, 0..1, of any channel across RGBA8 `colors`.
+fn spread(colors: &[u8]) -> f32 {
+    (0..4)
+        .map(|c| {
+            let channel = colors.iter().skip(c).step_by(4);
+            let (min, max) = channel.fold((u8::MAX, 0), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+            f32::from(max.saturating_sub(min)) / 255.0
+        })
+        .fold(0.0, f32::max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{render_cpu, Complex, ExteriorMode, FractalKind};
+
+    #[test]
+    fn edges_are_found_on_both_sides() {
+        // A 4×2 frame, black on the left and white on the right.
+        let mut pixels = Vec::new();
+        for _ in 0..2 {
+            pixels.extend([[0, 0, 0, 255], [0, 0, 0, 255], [255; 4], [255; 4]].concat());
+        }
+        assert_eq!(edge_pixels(&pixels, 4, 0.1), [1, 2, 5, 6]);
+        assert!(edge_pixels(&pixels, 4, 1.0).is_empty());
+        assert_eq!(spread(&pixels[..8]), 0.0);
+        assert_eq!(spread(&pixels[4..12]), 1.0);
+    }
+
+    #[test]
+    fn only_edge_pixels_change() {
+        let params = FractalParams::new(FractalKind::Mandelbrot)
+            .with_max_iter(200)
+            .with_exterior(ExteriorMode::Solid, [1.0; 3]);
+        let cam = Camera::new(Complex::new(-0.75, 0.0), 200.0);
+        let size = (64, 48);
+        let plain = render_cpu(size, &params, &cam);
+        let aa = AdaptiveAa {
+            enabled: true,
+            ..AdaptiveAa::default()
+        };
+
+        let mut refined = plain.clone();
+        let count = refine_edges(&mut refined, size, 0..size.1, &params, &cam, &aa);
+        let edges = edge_pixels(&plain, size.0, aa.threshold);
+        assert_eq!(count, edges.len() as u64);
+        assert!(count > 0 && count < u64::from(size.0 * size.1) / 2);
+        let changed: Vec<usize> = (0..plain.len() / 4)
+            .filter(|&i| plain[i * 4..i * 4 + 4] != refined[i * 4..i * 4 + 4])
+            .collect();
+        assert!(!changed.is_empty());
+        assert!(changed.iter().all(|i| edges.contains(i)));
+
+        // A band finds the same edges away from its first and last rows.
+        let row = size.0 as usize * 4;
+        let mut band = plain[10 * row..20 * row].to_vec();
+        refine_edges(&mut band, size, 10..20, &params, &cam, &aa);
+        assert_eq!(band[row..9 * row], refined[11 * row..19 * row]);
+
+        let off = AdaptiveAa::default();
+        let mut untouched = plain.clone();
+        assert_eq!(
+            refine_edges(&mut untouched, size, 0..size.1, &params, &cam, &off),
+            0
+        );
+        assert_eq!(untouched, plain);
+        assert!(!aa.applies_to(&params.clone().with_supersample(4)));
+        assert!(!aa.applies_to(&params.with_dwell_bands(8).with_band_contours([0.0; 3])));
+    }
+}
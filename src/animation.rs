@@ -0,0 +1,1739 @@
+//! Keyframed animation tracks and evaluation of a project at a point in time.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{formula, Camera, Complex, Complex64, FractalParams, Project, SEAHORSE_REPEAT_SPOT};
+
+/// Interpolation curve from one key to the next.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    SmoothStep,
+    /// Keeps this key's value until the next key, then jumps to it.
+    Hold,
+}
+
+impl Default for Easing {
+    fn default() -> Self {
+        Easing::Linear
+    }
+}
+
+impl Easing {
+    pub const ALL: [Easing; 6] = [
+        Easing::Linear,
+        Easing::EaseIn,
+        Easing::EaseOut,
+        Easing::EaseInOut,
+        Easing::SmoothStep,
+        Easing::Hold,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Easing::Linear => "Linear",
+            Easing::EaseIn => "EaseIn",
+            Easing::EaseOut => "EaseOut",
+            Easing::EaseInOut => "EaseInOut",
+            Easing::SmoothStep => "SmoothStep",
+            Easing::Hold => "Hold",
+        }
+    }
+
+    pub fn apply(&self, t: f32) -> f32 {
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::EaseInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+            Easing::SmoothStep => t * t * (3.0 - 2.0 * t),
+            Easing::Hold => {
+                if t < 1.0 {
+                    0.0
+                } else {
+                    1.0
+                }
+            }
+        }
+    }
+}
+
+/// Playback state and keyframe tracks of a project.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Animation {
+    pub fps: u32,
+    pub duration: f32, // seconds
+    pub playing: bool,
+    #[serde(default)]
+    pub looping: bool,
+    /// Playback steps through export frames one at a time instead of following the
+    /// clock, so the preview shows exactly the frames an export renders.
+    #[serde(default)]
+    pub frame_locked: bool,
+    pub t: f32, // current time
+    pub kf_zoom: Keyframes<f32>,
+    pub kf_palette: Keyframes<f32>,
+    pub kf_center_x: Keyframes<f32>,
+    pub kf_center_y: Keyframes<f32>,
+    /// Camera rotation in radians.
+    #[serde(default)]
+    pub kf_rotation: Keyframes<f32>,
+    /// Zoom keys blend in log space, so the view zooms at a steady apparent speed.
+    #[serde(default)]
+    pub log_zoom: bool,
+    /// Real part of the Julia constant `c`.
+    #[serde(default)]
+    pub kf_julia_re: Keyframes<f32>,
+    /// Imaginary part of the Julia constant `c`.
+    #[serde(default)]
+    pub kf_julia_im: Keyframes<f32>,
+    /// Orbit-trap tint, blended in `color_space`.
+    #[serde(default)]
+    pub kf_trap_color: Keyframes<[f32; 3]>,
+    /// Space every color track blends in between keys.
+    #[serde(default)]
+    pub color_space: ColorSpace,
+    pub selection: Option<SelectedKey>,
+    #[serde(default)]
+    pub zoom_forever: Option<EndlessZoom>,
+    #[serde(default)]
+    pub julia_orbit: Option<CardioidOrbit>,
+}
+
+impl Default for Animation {
+    fn default() -> Self {
+        Self {
+            fps: 30,
+            duration: 5.0,
+            playing: false,
+            looping: false,
+            frame_locked: false,
+            t: 0.0,
+            kf_zoom: Keyframes::default(),
+            kf_palette: Keyframes::default(),
+            kf_center_x: Keyframes::default(),
+            kf_center_y: Keyframes::default(),
+            kf_rotation: Keyframes::default(),
+            log_zoom: false,
+            kf_julia_re: Keyframes::default(),
+            kf_julia_im: Keyframes::default(),
+            kf_trap_color: Keyframes::default(),
+            color_space: ColorSpace::default(),
+            selection: None,
+            zoom_forever: None,
+            julia_orbit: None,
+        }
+    }
+}
+
+/// Open-ended exponential zoom that replaces the zoom track.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EndlessZoom {
+    pub start_scale: f64,
+    pub speed: f32,
+    #[serde(default)]
+    pub reverse: bool,
+    #[serde(default)]
+    pub lock_repeating_spot: bool,
+    /// Continuous camera rotation in radians per second; 0 keeps the view upright.
+    #[serde(default)]
+    pub rotation_speed: f32,
+    /// Point picked in the viewport that the zoom stays centered on, in place of the
+    /// repeating spot.
+    #[serde(default)]
+    pub target: Option<Complex64>,
+}
+
+/// Julia constant moving along the boundary of the Mandelbrot main cardioid,
+/// `c = e^{iθ}/2 − e^{2iθ}/4`, so the Julia set morphs through the connected shapes.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CardioidOrbit {
+    /// Loops around the cardioid per second; negative runs the other way.
+    pub speed: f32,
+    /// Starting angle, in turns.
+    #[serde(default)]
+    pub phase: f32,
+}
+
+impl Default for CardioidOrbit {
+    fn default() -> Self {
+        Self {
+            speed: 0.1,
+            phase: 0.0,
+        }
+    }
+}
+
+impl CardioidOrbit {
+    /// The point on the cardioid `turns` of a loop past `phase`.
+    pub fn point(self, turns: f32) -> Complex {
+        let theta = std::f32::consts::TAU * (self.phase + turns).rem_euclid(1.0);
+        let (sin, cos) = theta.sin_cos();
+        let (sin2, cos2) = (2.0 * theta).sin_cos();
+        Complex::new(cos / 2.0 - cos2 / 4.0, sin / 2.0 - sin2 / 4.0)
+    }
+}
+
+impl Animation {
+    pub fn advance(&mut self, dt: f32) {
+        if !self.playing {
+            return;
+        }
+        self.t += dt;
+        if self.duration > 0.0 && self.t >= self.duration {
+            if self.looping {
+                self.t = self.t % self.duration;
+            } else {
+                self.t = self.duration;
+                self.playing = false;
+            }
+        }
+    }
+
+    /// Advances playback by exactly one frame at `fps`, wherever the clock is, and
+    /// wraps or stops at the end like [`Animation::advance`].
+    pub fn step_frame(&mut self, fps: u32) {
+        if !self.playing {
+            return;
+        }
+        let next = frame_time(frame_at(self.t, fps) + 1, fps);
+        if self.duration > 0.0 && next >= self.duration {
+            if self.looping {
+                self.t = 0.0;
+            } else {
+                self.t = frame_time(frame_at(self.duration, fps), fps).min(self.duration);
+                self.playing = false;
+            }
+        } else {
+            self.t = next;
+        }
+    }
+
+    /// Jumps to the time of frame `frame` at `fps`, within the timeline.
+    pub fn go_to_frame(&mut self, frame: u32, fps: u32) {
+        self.set_timeline_time(frame_time(frame, fps));
+    }
+
+    pub fn track(&self, track: TrackKind) -> &Keyframes<f32> {
+        match track {
+            TrackKind::Zoom => &self.kf_zoom,
+            TrackKind::Palette => &self.kf_palette,
+            TrackKind::CenterX => &self.kf_center_x,
+            TrackKind::CenterY => &self.kf_center_y,
+            TrackKind::Rotation => &self.kf_rotation,
+            TrackKind::JuliaRe => &self.kf_julia_re,
+            TrackKind::JuliaIm => &self.kf_julia_im,
+        }
+    }
+
+    pub fn track_mut(&mut self, track: TrackKind) -> &mut Keyframes<f32> {
+        match track {
+            TrackKind::Zoom => &mut self.kf_zoom,
+            TrackKind::Palette => &mut self.kf_palette,
+            TrackKind::CenterX => &mut self.kf_center_x,
+            TrackKind::CenterY => &mut self.kf_center_y,
+            TrackKind::Rotation => &mut self.kf_rotation,
+            TrackKind::JuliaRe => &mut self.kf_julia_re,
+            TrackKind::JuliaIm => &mut self.kf_julia_im,
+        }
+    }
+
+    /// Keys every track in `group` at `t` with the current value of its parameter.
+    pub fn key_group(
+        &mut self,
+        group: TrackGroup,
+        t: f32,
+        camera: &Camera,
+        fractal: &FractalParams,
+    ) {
+        for track in group.tracks() {
+            self.track_mut(track)
+                .upsert(t, track.value(camera, fractal));
+        }
+        if group.has_trap_color() {
+            self.kf_trap_color.upsert(t, fractal.orbit.color);
+        }
+    }
+
+    /// Removes every key of `track`, dropping the selection if it was on that track.
+    pub fn clear_track(&mut self, track: TrackKind) {
+        self.track_mut(track).keys.clear();
+        if self
+            .selection
+            .as_ref()
+            .is_some_and(|sel| sel.track == track)
+        {
+            self.selection = None;
+        }
+    }
+
+    pub fn sample_zoom(&self, t: f32, default: f64) -> f64 {
+        if let Some(zoom) = self.zoom_forever {
+            return zoom.value_at(t);
+        }
+        if self.kf_zoom.keys.is_empty() {
+            return default;
+        }
+        let default = default as f32;
+        f64::from(if self.log_zoom {
+            self.kf_zoom.sample_with(t, default, log_lerp)
+        } else {
+            self.kf_zoom.sample(t, default)
+        })
+    }
+
+    /// Camera rotation at `t`: `default` plus the endless zoom's drift.
+    pub fn sample_rotation(&self, t: f32, default: f32) -> f32 {
+        default + self.zoom_forever.map_or(0.0, |zoom| zoom.rotation_at(t))
+    }
+
+    pub fn apply_endless_zoom_preset(&mut self, start_scale: f64) {
+        self.zoom_forever = Some(EndlessZoom::with_defaults(start_scale));
+        self.kf_zoom.keys.clear();
+        self.playing = true;
+        self.looping = true;
+        self.t = 0.0;
+    }
+
+    /// Maps playback time to (keyframe time, zoom time); both are finite and non-negative.
+    pub fn resolve_times(&self, absolute_time: f32) -> (f32, f32) {
+        let absolute_time = if absolute_time.is_nan() {
+            0.0
+        } else {
+            absolute_time.clamp(0.0, f32::MAX)
+        };
+        if self.duration.is_nan() || self.duration <= 0.0 {
+            let zoom = if self.zoom_forever.is_some() {
+                absolute_time
+            } else {
+                0.0
+            };
+            return (0.0, zoom);
+        }
+        let key_time = if self.looping && self.duration.is_finite() {
+            absolute_time % self.duration
+        } else {
+            absolute_time.min(self.duration)
+        };
+        let zoom_time = if self.zoom_forever.is_some() {
+            absolute_time
+        } else {
+            key_time
+        };
+        (key_time, zoom_time)
+    }
+
+    pub fn current_times(&self) -> (f32, f32) {
+        self.resolve_times(self.t)
+    }
+
+    pub fn timeline_time(&self) -> f32 {
+        self.resolve_times(self.t).0
+    }
+
+    pub fn set_timeline_time(&mut self, timeline_time: f32) {
+        if self.duration <= 0.0 {
+            self.t = timeline_time.max(0.0);
+        } else {
+            self.t = timeline_time.clamp(0.0, self.duration);
+        }
+    }
+
+    /// Palette cycles per second actually applied for a requested `speed`. A looping
+    /// animation rounds it to a whole number of cycles per loop (at least one) so the
+    /// colors line up again where the loop restarts.
+    pub fn palette_cycle_rate(&self, speed: f32) -> f32 {
+        self.cycle_rate(speed)
+    }
+
+    /// Cardioid loops per second actually applied for `orbit`, rounded like
+    /// [`Animation::palette_cycle_rate`] so a looping animation morphs seamlessly.
+    pub fn julia_orbit_rate(&self, orbit: CardioidOrbit) -> f32 {
+        self.cycle_rate(orbit.speed)
+    }
+
+    /// Adds a cardioid orbit doing one loop over the timeline and starts looping playback.
+    pub fn apply_julia_orbit_preset(&mut self) {
+        let speed = if self.duration.is_finite() && self.duration > 0.0 {
+            1.0 / self.duration
+        } else {
+            CardioidOrbit::default().speed
+        };
+        self.julia_orbit = Some(CardioidOrbit { speed, phase: 0.0 });
+        self.playing = true;
+        self.looping = true;
+        self.t = 0.0;
+    }
+
+    fn cycle_rate(&self, speed: f32) -> f32 {
+        if !speed.is_finite() || speed == 0.0 {
+            return 0.0;
+        }
+        if !self.looping || !self.duration.is_finite() || self.duration <= 0.0 {
+            return speed;
+        }
+        let cycles = (speed * self.duration).round().abs().max(1.0);
+        cycles.copysign(speed) / self.duration
+    }
+
+    pub fn is_repeating_spot_locked(&self) -> bool {
+        self.zoom_forever
+            .map_or(false, |zoom| zoom.lock_repeating_spot)
+    }
+}
+
+impl EndlessZoom {
+    pub fn with_defaults(scale: f64) -> Self {
+        Self {
+            start_scale: scale.max(0.0001),
+            speed: 0.9,
+            reverse: false,
+            lock_repeating_spot: false,
+            rotation_speed: 0.0,
+            target: None,
+        }
+    }
+
+    /// Centers the zoom on `target` from `scale` onwards, replacing the repeating spot.
+    /// Playback should restart from 0 so the zoom continues from the current view.
+    pub fn retarget(&mut self, target: Complex64, scale: f64) {
+        self.target = Some(target);
+        self.start_scale = scale.max(0.0001);
+        self.lock_repeating_spot = false;
+    }
+
+    /// Scale at `t`, kept finite and positive however long the zoom runs.
+    pub fn value_at(self, t: f32) -> f64 {
+        let clamped_speed = f64::from(self.speed.clamp(0.5, 0.995));
+        let factor = if self.reverse {
+            1.0 / clamped_speed
+        } else {
+            clamped_speed
+        };
+        let scale = self.start_scale * factor.powf(f64::from(t.max(0.0)));
+        if scale.is_nan() {
+            Camera::default().scale
+        } else {
+            scale.clamp(f64::MIN_POSITIVE, f64::MAX)
+        }
+    }
+
+    /// Drift rotation accumulated by `t`, on the same clock as [`EndlessZoom::value_at`].
+    pub fn rotation_at(self, t: f32) -> f32 {
+        self.rotation_speed * t.max(0.0)
+    }
+}
+
+/// Pins the camera center and base rotation to the Seahorse Valley repeating spot; drift
+/// rotation is added on top by [`evaluate_frame`].
+pub fn enforce_repeating_spot(camera: &mut Camera) {
+    camera.center = SEAHORSE_REPEAT_SPOT.center.into();
+    camera.rotation = SEAHORSE_REPEAT_SPOT.rotation;
+}
+
+/// Moves the camera onto the repeating spot and restarts the endless zoom there.
+pub fn snap_camera_to_repeating_spot(camera: &mut Camera, zoom: &mut EndlessZoom) {
+    enforce_repeating_spot(camera);
+    camera.scale = f64::from(SEAHORSE_REPEAT_SPOT.start_scale);
+    zoom.start_scale = camera.scale.max(0.0001);
+    zoom.target = None;
+}
+
+/// Smooth move of the view between two cameras, so preview jumps do not cut.
+#[derive(Debug, Clone)]
+pub struct CameraTransition {
+    pub from: Camera,
+    pub to: Camera,
+    /// Seconds.
+    pub duration: f32,
+}
+
+impl CameraTransition {
+    pub fn new(from: Camera, to: Camera, duration: f32) -> Self {
+        Self { from, to, duration }
+    }
+
+    /// The view `elapsed` seconds in: centers and rotations eased with
+    /// [`Easing::SmoothStep`], scales blended geometrically. Holds `to` once done.
+    pub fn camera_at(&self, elapsed: f32) -> Camera {
+        if self.is_done(elapsed) {
+            return self.to.clone();
+        }
+        let u = Easing::SmoothStep.apply((elapsed / self.duration).clamp(0.0, 1.0));
+        Camera {
+            center: Complex64::new(
+                f64::lerp(self.from.center.re, self.to.center.re, u),
+                f64::lerp(self.from.center.im, self.to.center.im, u),
+            ),
+            scale: log_lerp_f64(self.from.scale, self.to.scale, u),
+            rotation: f32::lerp(self.from.rotation, self.to.rotation, u),
+            deep_center: None,
+        }
+    }
+
+    pub fn is_done(&self, elapsed: f32) -> bool {
+        elapsed >= self.duration
+    }
+}
+
+/// Animatable parameters whose tracks are bypassed so the authored slider value wins.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct ParamLocks {
+    pub zoom: bool,
+    pub palette: bool,
+    pub center_x: bool,
+    pub center_y: bool,
+    pub rotation: bool,
+    pub julia_re: bool,
+    pub julia_im: bool,
+    pub trap_color: bool,
+    /// Exports honor keys on locked tracks unless this is set.
+    pub apply_to_export: bool,
+}
+
+impl ParamLocks {
+    pub fn flag_mut(&mut self, track: TrackKind) -> &mut bool {
+        match track {
+            TrackKind::Zoom => &mut self.zoom,
+            TrackKind::Palette => &mut self.palette,
+            TrackKind::CenterX => &mut self.center_x,
+            TrackKind::CenterY => &mut self.center_y,
+            TrackKind::Rotation => &mut self.rotation,
+            TrackKind::JuliaRe => &mut self.julia_re,
+            TrackKind::JuliaIm => &mut self.julia_im,
+        }
+    }
+
+    pub fn any(&self) -> bool {
+        self.zoom
+            || self.palette
+            || self.center_x
+            || self.center_y
+            || self.rotation
+            || self.julia_re
+            || self.julia_im
+            || self.trap_color
+    }
+
+    /// Whether every track in `group` is locked; an empty group never is.
+    pub fn group_locked(&self, group: TrackGroup) -> bool {
+        let mut locks = group
+            .tracks()
+            .into_iter()
+            .map(|track| match track {
+                TrackKind::Zoom => self.zoom,
+                TrackKind::Palette => self.palette,
+                TrackKind::CenterX => self.center_x,
+                TrackKind::CenterY => self.center_y,
+                TrackKind::Rotation => self.rotation,
+                TrackKind::JuliaRe => self.julia_re,
+                TrackKind::JuliaIm => self.julia_im,
+            })
+            .chain(group.has_trap_color().then_some(self.trap_color))
+            .peekable();
+        locks.peek().is_some() && locks.all(|locked| locked)
+    }
+
+    /// Locks or unlocks every track in `group`.
+    pub fn set_group(&mut self, group: TrackGroup, locked: bool) {
+        for track in group.tracks() {
+            *self.flag_mut(track) = locked;
+        }
+        if group.has_trap_color() {
+            self.trap_color = locked;
+        }
+    }
+}
+
+/// Whether a frame is evaluated for the live preview or for export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvalTarget {
+    Preview,
+    Export,
+}
+
+/// Parameters after sampling the animation at one instant: what actually gets rendered.
+/// The authored values in `Project` are never overwritten by playback.
+#[derive(Debug, Clone)]
+pub struct EvaluatedFrame {
+    pub fractal: FractalParams,
+    pub camera: Camera,
+}
+
+/// Time of frame `frame` at `fps`, computed from the index alone so frame times never
+/// accumulate rounding error.
+pub fn frame_time(frame: u32, fps: u32) -> f32 {
+    (f64::from(frame) / f64::from(fps.max(1))) as f32
+}
+
+/// Frame nearest `time` at `fps`, so every [`frame_time`] maps back to its own frame.
+pub fn frame_at(time: f32, fps: u32) -> u32 {
+    (f64::from(time.max(0.0)) * f64::from(fps.max(1))).round() as u32
+}
+
+/// Frame `frame` of the export as the exporter evaluates it before running the script:
+/// locks only when they apply to export, Max Iter for the export width, and the export's
+/// quality profile with the frame's sample pattern.
+pub fn evaluate_export_frame(proj: &Project, frame: u32) -> EvaluatedFrame {
+    let mut eval = evaluate_frame(proj, frame_time(frame, proj.export.fps), EvalTarget::Export);
+    eval.fractal
+        .apply_auto_iter(&eval.camera, proj.export.width as f32);
+    proj.export
+        .quality
+        .apply(&mut eval.fractal, proj.seed, frame);
+    eval
+}
+
+/// Samples every track of `proj` at `time` seconds, honoring parameter locks for `target`.
+pub fn evaluate_frame(proj: &Project, time: f32, target: EvalTarget) -> EvaluatedFrame {
+    let anim = &proj.anim;
+    let no_locks = ParamLocks::default();
+    let locks = if target == EvalTarget::Preview || proj.locks.apply_to_export {
+        &proj.locks
+    } else {
+        &no_locks
+    };
+    let (key_t, zoom_t) = anim.resolve_times(time);
+    let mut fractal = proj.fractal.clone();
+    fractal.precision = proj.precision;
+    let mut camera = proj.camera.clone();
+    if !locks.zoom {
+        camera.scale = anim.sample_zoom(zoom_t, camera.scale);
+    }
+    if !locks.palette {
+        fractal.palette_phase = anim.kf_palette.sample(key_t, fractal.palette_phase);
+    }
+    // Cycling runs on unwrapped time so it keeps flowing past the end of the keys.
+    let cycle_t = if time.is_finite() { time.max(0.0) } else { 0.0 };
+    let cycles = anim.palette_cycle_rate(fractal.palette_cycle_speed) * cycle_t;
+    fractal.palette_phase += cycles.rem_euclid(1.0);
+    if !locks.julia_re {
+        fractal.c.re = anim.kf_julia_re.sample(key_t, fractal.c.re);
+    }
+    if !locks.julia_im {
+        fractal.c.im = anim.kf_julia_im.sample(key_t, fractal.c.im);
+    }
+    // The cardioid orbit, when on, replaces the keyed constant.
+    if let Some(orbit) = anim.julia_orbit {
+        if formula(fractal.kind).julia_mode() {
+            fractal.c = orbit.point(anim.julia_orbit_rate(orbit) * cycle_t);
+        }
+    }
+    if !locks.center_x {
+        camera.center.re = anim.kf_center_x.sample_f64(key_t, camera.center.re);
+    }
+    if !locks.center_y {
+        camera.center.im = anim.kf_center_y.sample_f64(key_t, camera.center.im);
+    }
+    if !locks.rotation {
+        camera.rotation = anim.kf_rotation.sample(key_t, camera.rotation);
+    }
+    if !locks.trap_color {
+        fractal.orbit.color =
+            anim.kf_trap_color
+                .sample_color(key_t, fractal.orbit.color, anim.color_space);
+    }
+    if anim.is_repeating_spot_locked() {
+        enforce_repeating_spot(&mut camera);
+    } else if let Some(target) = anim.zoom_forever.and_then(|zoom| zoom.target) {
+        camera.center = target;
+    }
+    // After the spot lock, so a locked endless zoom keeps its drift.
+    if !locks.zoom {
+        camera.rotation = anim.sample_rotation(zoom_t, camera.rotation);
+    }
+    EvaluatedFrame { fractal, camera }
+}
+
+/// The animatable parameters.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TrackKind {
+    Zoom,
+    Palette,
+    CenterX,
+    CenterY,
+    Rotation,
+    /// Real part of the Julia constant.
+    JuliaRe,
+    /// Imaginary part of the Julia constant.
+    JuliaIm,
+}
+
+impl TrackKind {
+    pub const ALL: [TrackKind; 7] = [
+        TrackKind::Zoom,
+        TrackKind::Palette,
+        TrackKind::CenterX,
+        TrackKind::CenterY,
+        TrackKind::Rotation,
+        TrackKind::JuliaRe,
+        TrackKind::JuliaIm,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TrackKind::Zoom => "Zoom",
+            TrackKind::Palette => "Palette",
+            TrackKind::CenterX => "Center X",
+            TrackKind::CenterY => "Center Y",
+            TrackKind::Rotation => "Rotation",
+            TrackKind::JuliaRe => "Julia Re",
+            TrackKind::JuliaIm => "Julia Im",
+        }
+    }
+
+    pub fn group(&self) -> TrackGroup {
+        match self {
+            TrackKind::Zoom | TrackKind::CenterX | TrackKind::CenterY | TrackKind::Rotation => {
+                TrackGroup::Camera
+            }
+            TrackKind::Palette => TrackGroup::Color,
+            TrackKind::JuliaRe | TrackKind::JuliaIm => TrackGroup::Fractal,
+        }
+    }
+
+    /// The authored value of the parameter the track animates, rounded to the `f32` keys
+    /// hold.
+    pub fn value(&self, camera: &Camera, fractal: &FractalParams) -> f32 {
+        match self {
+            TrackKind::Zoom => camera.scale as f32,
+            TrackKind::Palette => fractal.palette_phase,
+            TrackKind::CenterX => camera.center.re as f32,
+            TrackKind::CenterY => camera.center.im as f32,
+            TrackKind::Rotation => camera.rotation,
+            TrackKind::JuliaRe => fractal.c.re,
+            TrackKind::JuliaIm => fractal.c.im,
+        }
+    }
+
+    /// Puts the parameter the track animates back to its value in a new project.
+    pub fn reset_value(&self, camera: &mut Camera, fractal: &mut FractalParams) {
+        let (default_camera, default_fractal) = (Camera::default(), FractalParams::default());
+        match self {
+            TrackKind::Zoom => camera.scale = default_camera.scale,
+            TrackKind::Palette => fractal.palette_phase = default_fractal.palette_phase,
+            TrackKind::CenterX => camera.center.re = default_camera.center.re,
+            TrackKind::CenterY => camera.center.im = default_camera.center.im,
+            TrackKind::Rotation => camera.rotation = default_camera.rotation,
+            TrackKind::JuliaRe => fractal.c.re = default_fractal.c.re,
+            TrackKind::JuliaIm => fractal.c.im = default_fractal.c.im,
+        }
+    }
+
+    /// The parameter's value in a new project.
+    pub fn default_value(&self) -> f32 {
+        self.value(&Camera::default(), &FractalParams::default())
+    }
+}
+
+/// Sections of the timeline. Which tracks belong where is fixed here; collapsing is a
+/// view preference and not saved with the project.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TrackGroup {
+    Camera,
+    Color,
+    Fractal,
+    Traps,
+}
+
+impl TrackGroup {
+    pub const ALL: [TrackGroup; 4] = [
+        TrackGroup::Camera,
+        TrackGroup::Color,
+        TrackGroup::Fractal,
+        TrackGroup::Traps,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TrackGroup::Camera => "Camera",
+            TrackGroup::Color => "Color",
+            TrackGroup::Fractal => "Fractal",
+            TrackGroup::Traps => "Traps",
+        }
+    }
+
+    /// Value tracks in the group, in timeline order.
+    pub fn tracks(&self) -> Vec<TrackKind> {
+        TrackKind::ALL
+            .into_iter()
+            .filter(|track| track.group() == *self)
+            .collect()
+    }
+
+    /// Whether the trap color track is in the group.
+    pub fn has_trap_color(&self) -> bool {
+        *self == TrackGroup::Traps
+    }
+
+    /// Whether the group has no tracks yet.
+    pub fn is_empty(&self) -> bool {
+        self.tracks().is_empty() && !self.has_trap_color()
+    }
+}
+
+/// Key selected in the timeline.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SelectedKey {
+    pub track: TrackKind,
+    pub index: usize,
+}
+
+/// A value at time `t` (seconds) and the easing toward the next key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Keyframe<T> {
+    pub t: f32,
+    pub v: T,
+    pub easing: Easing,
+}
+
+/// Time-sorted keys of one track.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Keyframes<T> {
+    pub keys: Vec<Keyframe<T>>,
+}
+
+impl<T> Keyframes<T> {
+    /// Re-sorts keys by time after a drag and returns where `index` ended up.
+    pub fn resort(&mut self, index: usize) -> usize {
+        let mut order: Vec<usize> = (0..self.keys.len()).collect();
+        order.sort_by(|&a, &b| self.keys[a].t.total_cmp(&self.keys[b].t));
+        self.keys.sort_by(|a, b| a.t.total_cmp(&b.t));
+        order.iter().position(|&i| i == index).unwrap_or(index)
+    }
+}
+
+impl<T> Keyframes<T> {
+    /// The segment starting at key `index`: that key and the next one on the same track.
+    pub fn segment(&self, index: usize) -> Option<(&Keyframe<T>, &Keyframe<T>)> {
+        Some((self.keys.get(index)?, self.keys.get(index.checked_add(1)?)?))
+    }
+}
+
+impl<T: Copy + Interp> Keyframes<T> {
+    /// Like [`Keyframes::sample`], blending neighbouring keys with `lerp`. A NaN or
+    /// infinite result is rejected for `default`.
+    pub fn sample_with(&self, t: f32, default: T, lerp: impl Fn(T, T, f32) -> T) -> T {
+        let value = self.blend(t, default, lerp);
+        if value.is_finite() {
+            value
+        } else {
+            default
+        }
+    }
+
+    fn blend(&self, t: f32, default: T, lerp: impl Fn(T, T, f32) -> T) -> T {
+        if self.keys.is_empty() {
+            return default;
+        }
+        if self.keys.len() == 1 {
+            return self.keys[0].v;
+        }
+        let mut prev = &self.keys[0];
+        for k in &self.keys[1..] {
+            if t <= k.t {
+                // Exact values on keys and across holds, so neither picks up blending
+                // error and densely baked keys play back as they were written.
+                if t == k.t {
+                    return k.v;
+                }
+                if prev.easing == Easing::Hold || t <= prev.t {
+                    return prev.v;
+                }
+                let denom = (k.t - prev.t).max(1e-4);
+                let mut u = ((t - prev.t) / denom).clamp(0.0, 1.0);
+                u = prev.easing.apply(u);
+                return lerp(prev.v, k.v, u);
+            }
+            prev = k;
+        }
+        prev.v
+    }
+
+    pub fn sample(&self, t: f32, default: T) -> T {
+        self.sample_with(t, default, T::lerp)
+    }
+
+    pub fn upsert(&mut self, t: f32, v: T) {
+        if let Some(existing) = self.keys.iter_mut().find(|key| (key.t - t).abs() < 1e-4) {
+            existing.v = v;
+            return;
+        }
+        self.keys.push(Keyframe {
+            t,
+            v,
+            easing: Easing::Linear,
+        });
+        self.keys.sort_by(|a, b| a.t.total_cmp(&b.t));
+    }
+
+    pub fn clamp_all(&mut self, duration: f32) {
+        for k in &mut self.keys {
+            k.t = k.t.clamp(0.0, duration);
+        }
+    }
+}
+
+/// Linear interpolation for keyframe values.
+pub trait Interp {
+    fn lerp(a: Self, b: Self, u: f32) -> Self;
+    /// Whether every component is a finite number.
+    fn is_finite(&self) -> bool;
+}
+impl Interp for f32 {
+    fn lerp(a: Self, b: Self, u: f32) -> Self {
+        a + (b - a) * u
+    }
+    fn is_finite(&self) -> bool {
+        f32::is_finite(*self)
+    }
+}
+impl Interp for f64 {
+    fn lerp(a: Self, b: Self, u: f32) -> Self {
+        a + (b - a) * f64::from(u)
+    }
+    fn is_finite(&self) -> bool {
+        f64::is_finite(*self)
+    }
+}
+/// Geometric blend of two scales, so each step multiplies the zoom by the same factor.
+/// Blends linearly unless both are positive.
+pub fn log_lerp(a: f32, b: f32, u: f32) -> f32 {
+    if a > 0.0 && b > 0.0 {
+        a * (b / a).powf(u)
+    } else {
+        f32::lerp(a, b, u)
+    }
+}
+
+/// [`log_lerp`] for camera scales.
+pub fn log_lerp_f64(a: f64, b: f64, u: f32) -> f64 {
+    if a > 0.0 && b > 0.0 {
+        a * (b / a).powf(f64::from(u))
+    } else {
+        f64::lerp(a, b, u)
+    }
+}
+
+impl Keyframes<f32> {
+    /// Samples the track for an `f64` parameter. Keys hold `f32`, so only a track without
+    /// keys returns `default` at full precision.
+    pub fn sample_f64(&self, t: f32, default: f64) -> f64 {
+        if self.keys.is_empty() {
+            return default;
+        }
+        f64::from(self.sample(t, default as f32))
+    }
+}
+
+/// Component-wise, so `[f32; 3]` colors blend channel by channel.
+impl<T: Interp + Copy, const N: usize> Interp for [T; N] {
+    fn lerp(a: Self, b: Self, u: f32) -> Self {
+        std::array::from_fn(|i| T::lerp(a[i], b[i], u))
+    }
+    fn is_finite(&self) -> bool {
+        self.iter().all(T::is_finite)
+    }
+}
+
+impl Keyframes<[f32; 3]> {
+    /// Samples a linear-RGB color track, blending in `space`.
+    pub fn sample_color(&self, t: f32, default: [f32; 3], space: ColorSpace) -> [f32; 3] {
+        self.sample_with(t, default, |a, b, u| space.lerp(a, b, u))
+    }
+}
+
+// ------------------------- Color interpolation -------------------------
+
+/// Space colors are blended in between keys.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorSpace {
+    /// Channel by channel in linear RGB.
+    #[default]
+    Rgb,
+    /// Perceptually even steps in lightness and hue.
+    Oklab,
+}
+
+impl ColorSpace {
+    pub const ALL: [ColorSpace; 2] = [ColorSpace::Rgb, ColorSpace::Oklab];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ColorSpace::Rgb => "RGB",
+            ColorSpace::Oklab => "Oklab",
+        }
+    }
+
+    /// Blends two linear-RGB colors `u` of the way from `a` to `b`.
+    pub fn lerp(self, a: [f32; 3], b: [f32; 3], u: f32) -> [f32; 3] {
+        match self {
+            ColorSpace::Rgb => Interp::lerp(a, b, u),
+            ColorSpace::Oklab => oklab_to_rgb(Interp::lerp(rgb_to_oklab(a), rgb_to_oklab(b), u)),
+        }
+    }
+}
+
+fn rgb_to_oklab([r, g, b]: [f32; 3]) -> [f32; 3] {
+    let l = (0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b).cbrt();
+    let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+    let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+    [
+        0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+        1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+        0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+    ]
+}
+
+fn oklab_to_rgb([l, a, b]: [f32; 3]) -> [f32; 3] {
+    let l_ = (l + 0.396_337_78 * a + 0.215_803_76 * b).powi(3);
+    let m_ = (l - 0.105_561_346 * a - 0.063_854_17 * b).powi(3);
+    let s_ = (l - 0.089_484_18 * a - 1.291_485_5 * b).powi(3);
+    [
+        4.076_741_7 * l_ - 3.307_711_6 * m_ + 0.230_969_94 * s_,
+        -1.268_438 * l_ + 2.609_757_4 * m_ - 0.341_319_38 * s_,
+        -0.004_196_086_3 * l_ - 0.703_418_6 * m_ + 1.707_614_7 * s_,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FractalKind;
+
+    fn track(times: &[f32]) -> Keyframes<f32> {
+        let mut keys = Keyframes::default();
+        for (i, t) in times.iter().enumerate() {
+            keys.upsert(*t, i as f32);
+        }
+        keys
+    }
+
+    #[test]
+    fn segment_pairs_key_with_its_successor() {
+        let keys = track(&[2.0, 0.0, 1.0]);
+        let (a, b) = keys.segment(0).unwrap();
+        assert_eq!((a.t, b.t), (0.0, 1.0));
+        let (a, b) = keys.segment(1).unwrap();
+        assert_eq!((a.t, b.t), (1.0, 2.0));
+    }
+
+    #[test]
+    fn segment_is_none_past_the_last_key() {
+        let keys = track(&[0.0, 1.0]);
+        assert!(keys.segment(1).is_none());
+        assert!(keys.segment(5).is_none());
+        assert!(keys.segment(usize::MAX).is_none());
+        assert!(track(&[]).segment(0).is_none());
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() <= 1e-4 * (1.0 + expected.abs()),
+            "{actual} != {expected}"
+        );
+    }
+
+    #[test]
+    fn easing_endpoints_are_exact() {
+        for easing in Easing::ALL {
+            assert_eq!(easing.apply(0.0), 0.0, "{easing:?}");
+            assert_eq!(easing.apply(1.0), 1.0, "{easing:?}");
+        }
+    }
+
+    #[test]
+    fn easings_are_monotone_within_unit_range() {
+        for easing in Easing::ALL {
+            let mut prev = 0.0;
+            for i in 1..=100 {
+                let v = easing.apply(i as f32 / 100.0);
+                assert!((0.0..=1.0).contains(&v), "{easing:?} left 0..1 at {v}");
+                assert!(v >= prev, "{easing:?} decreased at step {i}");
+                prev = v;
+            }
+        }
+    }
+
+    #[test]
+    fn sample_empty_track_returns_default() {
+        assert_eq!(Keyframes::<f32>::default().sample(1.0, 7.5), 7.5);
+    }
+
+    #[test]
+    fn sample_single_key_holds_its_value() {
+        let keys = track(&[2.0]);
+        for t in [-1.0, 0.0, 2.0, 10.0] {
+            assert_eq!(keys.sample(t, 99.0), 0.0);
+        }
+    }
+
+    #[test]
+    fn sample_at_key_times_returns_key_values() {
+        let keys = track(&[0.0, 1.0, 3.0]);
+        assert_close(keys.sample(0.0, 0.0), 0.0);
+        assert_close(keys.sample(1.0, 0.0), 1.0);
+        assert_close(keys.sample(3.0, 0.0), 2.0);
+    }
+
+    #[test]
+    fn sample_holds_end_values_outside_the_keys() {
+        let keys = track(&[1.0, 2.0]);
+        assert_eq!(keys.sample(-5.0, 0.0), 0.0);
+        assert_eq!(keys.sample(9.0, 0.0), 1.0);
+    }
+
+    #[test]
+    fn sample_uses_the_easing_of_the_segment_start() {
+        let mut keys = track(&[0.0, 1.0]);
+        keys.keys[0].easing = Easing::EaseIn;
+        assert_close(keys.sample(0.5, 0.0), 0.25);
+        keys.keys[0].easing = Easing::EaseOut;
+        assert_close(keys.sample(0.5, 0.0), 0.75);
+    }
+
+    #[test]
+    fn hold_keys_jump_exactly_at_the_next_key() {
+        let mut keys = track(&[0.0, 1.0, 2.0]);
+        keys.keys[0].easing = Easing::Hold;
+        assert_eq!(keys.sample(-1.0, 9.0), 0.0);
+        assert_eq!(keys.sample(0.0, 9.0), 0.0);
+        assert_eq!(keys.sample(0.999, 9.0), 0.0);
+        assert_eq!(keys.sample(1.0, 9.0), 1.0);
+        assert_close(keys.sample(1.5, 9.0), 1.5);
+
+        keys.keys[1].easing = Easing::Hold;
+        keys.keys[2].easing = Easing::Hold;
+        assert_eq!(keys.sample(1.5, 9.0), 1.0);
+        assert_eq!(keys.sample(2.0, 9.0), 2.0);
+        assert_eq!(keys.sample(5.0, 9.0), 2.0);
+
+        // Held values come through untouched by the blend.
+        let mut zoom = Keyframes::default();
+        zoom.upsert(0.0, 37.3);
+        zoom.upsert(1.0, 4.1e6);
+        zoom.keys[0].easing = Easing::Hold;
+        assert_eq!(zoom.sample_with(0.5, 1.0, log_lerp), 37.3);
+        assert_eq!(zoom.sample_with(1.0, 1.0, log_lerp), 4.1e6);
+    }
+
+    #[test]
+    fn upsert_merges_keys_within_epsilon() {
+        let mut keys = track(&[0.0, 1.0]);
+        keys.upsert(1.0 + 5e-5, 42.0);
+        assert_eq!(keys.keys.len(), 2);
+        assert_eq!(keys.keys[1].t, 1.0);
+        assert_eq!(keys.keys[1].v, 42.0);
+        keys.upsert(1.0 + 2e-4, 43.0);
+        assert_eq!(keys.keys.len(), 3);
+    }
+
+    #[test]
+    fn upsert_inserts_in_time_order() {
+        let keys = track(&[3.0, 1.0, 2.0, 0.0]);
+        let times: Vec<f32> = keys.keys.iter().map(|k| k.t).collect();
+        assert_eq!(times, [0.0, 1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn clamp_all_pins_keys_into_the_duration() {
+        let mut keys = track(&[-1.0, 2.0, 8.0]);
+        keys.clamp_all(5.0);
+        let times: Vec<f32> = keys.keys.iter().map(|k| k.t).collect();
+        assert_eq!(times, [0.0, 2.0, 5.0]);
+    }
+
+    #[test]
+    fn endless_zoom_starts_at_its_scale_and_moves_with_direction() {
+        let mut zoom = EndlessZoom::with_defaults(100.0);
+        assert_eq!(zoom.value_at(0.0), 100.0);
+        assert_eq!(zoom.value_at(-3.0), 100.0);
+        assert!(zoom.value_at(1.0) < 100.0);
+        zoom.reverse = true;
+        assert!(zoom.value_at(1.0) > 100.0);
+    }
+
+    #[test]
+    fn drift_rotation_survives_the_repeating_spot_lock() {
+        let mut proj = Project::default();
+        proj.camera.rotation = 0.3;
+        proj.anim.apply_endless_zoom_preset(100.0);
+        let rotation =
+            |proj: &Project, t: f32, target| evaluate_frame(proj, t, target).camera.rotation;
+        assert_close(rotation(&proj, 2.0, EvalTarget::Export), 0.3);
+
+        let zoom = proj.anim.zoom_forever.as_mut().unwrap();
+        zoom.rotation_speed = 0.25;
+        for target in [EvalTarget::Preview, EvalTarget::Export] {
+            assert_close(rotation(&proj, 2.0, target), 0.3 + 0.5);
+        }
+
+        proj.anim.zoom_forever.as_mut().unwrap().lock_repeating_spot = true;
+        let frame = evaluate_frame(&proj, 2.0, EvalTarget::Export);
+        assert_eq!(frame.camera.center, SEAHORSE_REPEAT_SPOT.center.into());
+        assert_close(frame.camera.rotation, SEAHORSE_REPEAT_SPOT.rotation + 0.5);
+    }
+
+    #[test]
+    fn endless_zoom_clamps_speed() {
+        let mut zoom = EndlessZoom::with_defaults(1.0);
+        zoom.speed = 0.0;
+        assert_close(zoom.value_at(1.0) as f32, 0.5);
+        zoom.speed = 2.0;
+        assert_close(zoom.value_at(1.0) as f32, 0.995);
+    }
+
+    #[test]
+    fn endless_zoom_stays_finite() {
+        let mut zoom = EndlessZoom::with_defaults(1e30);
+        zoom.reverse = true;
+        assert_eq!(zoom.value_at(1e6), f64::MAX);
+        zoom.reverse = false;
+        assert_eq!(zoom.value_at(1e6), f64::MIN_POSITIVE);
+        zoom.start_scale = f64::NAN;
+        assert_eq!(zoom.value_at(1.0), Camera::default().scale);
+        assert_eq!(zoom.value_at(f32::NAN), Camera::default().scale);
+    }
+
+    #[test]
+    fn degenerate_keys_fall_back_to_the_authored_value() {
+        let key = |t, v| Keyframe {
+            t,
+            v,
+            easing: Easing::Linear,
+        };
+        // Keys dragged onto one time, or holding infinities, blend to NaN.
+        let stacked = Keyframes {
+            keys: vec![
+                key(1.0, f32::INFINITY),
+                key(1.0, 2.0),
+                key(2.0, f32::INFINITY),
+            ],
+        };
+        assert_eq!(stacked.sample(0.5, 7.0), 7.0);
+        assert_eq!(stacked.sample(1.5, 7.0), 7.0);
+        let mut nan_times = track(&[0.0, 1.0]);
+        nan_times.keys[1].t = f32::NAN;
+        assert!(nan_times.sample(0.5, 7.0).is_finite());
+        // Scales too far apart for a log blend.
+        let zoom = Keyframes {
+            keys: vec![key(0.0, 1e-30), key(1.0, 1e30)],
+        };
+        assert_eq!(zoom.sample_with(0.5, 7.0, log_lerp), 7.0);
+        let colors = Keyframes {
+            keys: vec![
+                Keyframe {
+                    t: 0.0,
+                    v: [0.0, f32::NAN, 0.0],
+                    easing: Easing::Linear,
+                },
+                Keyframe {
+                    t: 1.0,
+                    v: [1.0; 3],
+                    easing: Easing::Linear,
+                },
+            ],
+        };
+        assert_eq!(
+            colors.sample_color(0.5, [0.2; 3], ColorSpace::Oklab),
+            [0.2; 3]
+        );
+
+        let mut proj = Project::default();
+        proj.anim.log_zoom = true;
+        proj.anim.kf_zoom = zoom;
+        proj.anim.kf_center_x = stacked;
+        let frame = evaluate_frame(&proj, 0.5, EvalTarget::Export);
+        assert_eq!(frame.camera.scale, proj.camera.scale);
+        assert_eq!(frame.camera.center, proj.camera.center);
+    }
+
+    #[test]
+    fn resolve_times_with_zero_duration() {
+        let mut anim = Animation {
+            duration: 0.0,
+            ..Animation::default()
+        };
+        assert_eq!(anim.resolve_times(3.0), (0.0, 0.0));
+        anim.zoom_forever = Some(EndlessZoom::with_defaults(1.0));
+        assert_eq!(anim.resolve_times(3.0), (0.0, 3.0));
+    }
+
+    #[test]
+    fn frame_steps_land_on_exact_frame_times() {
+        let mut anim = Animation {
+            duration: 1.0,
+            playing: true,
+            ..Animation::default()
+        };
+        for frame in 1..24 {
+            anim.step_frame(24);
+            assert_eq!(anim.t, frame_time(frame, 24));
+            assert_eq!(frame_at(anim.t, 24), frame);
+        }
+        anim.step_frame(24);
+        assert_eq!((anim.t, anim.playing), (1.0, false));
+
+        anim.looping = true;
+        anim.playing = true;
+        anim.go_to_frame(23, 24);
+        anim.step_frame(24);
+        assert_eq!(anim.t, 0.0);
+        anim.go_to_frame(500, 24);
+        assert_eq!(anim.t, 1.0);
+    }
+
+    #[test]
+    fn picked_zoom_targets_pin_the_center_instead_of_the_repeating_spot() {
+        let mut proj = Project::default();
+        proj.anim.apply_endless_zoom_preset(200.0);
+        let zoom = proj.anim.zoom_forever.as_mut().unwrap();
+        snap_camera_to_repeating_spot(&mut proj.camera, zoom);
+        zoom.lock_repeating_spot = true;
+        let target = Complex64::new(-1.25, 0.02);
+        zoom.retarget(target, 850.0);
+        assert!(!zoom.lock_repeating_spot);
+        assert_eq!(zoom.start_scale, 850.0);
+        proj.anim.kf_center_x.upsert(0.0, 0.3);
+        let frame = evaluate_frame(&proj, 2.0, EvalTarget::Export);
+        assert_eq!(frame.camera.center, target);
+        assert_close(frame.camera.scale as f32, 850.0 * 0.9f32.powf(2.0));
+
+        let zoom = proj.anim.zoom_forever.as_mut().unwrap();
+        snap_camera_to_repeating_spot(&mut proj.camera, zoom);
+        assert_eq!(zoom.target, None);
+    }
+
+    #[test]
+    fn camera_transitions_ease_between_the_views() {
+        let from = Camera::new(Complex::new(0.0, 0.0), 100.0);
+        let to = Camera::new(Complex::new(1.0, -2.0), 400.0);
+        let move_to = CameraTransition::new(from.clone(), to.clone(), 0.5);
+        let start = move_to.camera_at(0.0);
+        assert_eq!((start.center, start.scale), (from.center, from.scale));
+        let mid = move_to.camera_at(0.25);
+        assert_eq!(mid.center, Complex64::new(0.5, -1.0));
+        assert_close(mid.scale as f32, 200.0);
+        assert!(!move_to.is_done(0.49) && move_to.is_done(0.5));
+        let end = move_to.camera_at(3.0);
+        assert_eq!((end.center, end.scale), (to.center, to.scale));
+    }
+
+    #[test]
+    fn resolve_times_wraps_or_clamps_to_the_duration() {
+        let mut anim = Animation {
+            duration: 4.0,
+            ..Animation::default()
+        };
+        assert_eq!(anim.resolve_times(6.0), (4.0, 4.0));
+        anim.looping = true;
+        assert_eq!(anim.resolve_times(6.0), (2.0, 2.0));
+        anim.zoom_forever = Some(EndlessZoom::with_defaults(1.0));
+        assert_eq!(anim.resolve_times(6.0), (2.0, 6.0));
+        assert_eq!(anim.resolve_times(-1.0), (0.0, 0.0));
+    }
+
+    #[test]
+    fn palette_cycle_rounds_to_whole_cycles_per_loop() {
+        let mut anim = Animation {
+            duration: 4.0,
+            ..Animation::default()
+        };
+        assert_eq!(anim.palette_cycle_rate(0.3), 0.3);
+        anim.looping = true;
+        assert_eq!(anim.palette_cycle_rate(0.3), 0.25);
+        assert_eq!(anim.palette_cycle_rate(0.1), 0.25);
+        assert_eq!(anim.palette_cycle_rate(-0.6), -0.5);
+        assert_eq!(anim.palette_cycle_rate(0.0), 0.0);
+        assert_eq!(anim.palette_cycle_rate(f32::NAN), 0.0);
+    }
+
+    #[test]
+    fn palette_cycle_adds_on_top_of_the_palette_track() {
+        let mut proj = Project::default();
+        proj.fractal.palette_cycle_speed = 0.5;
+        proj.anim.duration = 4.0;
+        proj.anim.kf_palette.upsert(0.0, 0.0);
+        proj.anim.kf_palette.upsert(4.0, 0.4);
+        let phase = |proj: &Project, t: f32| {
+            evaluate_frame(proj, t, EvalTarget::Export)
+                .fractal
+                .palette_phase
+        };
+        assert_close(phase(&proj, 0.0), 0.0);
+        assert_close(phase(&proj, 1.0), 0.1 + 0.5);
+        // Past the last key the track holds while the cycle keeps going.
+        assert_close(phase(&proj, 5.0), 0.4 + 0.5);
+
+        // A locked track still cycles from the slider value.
+        proj.locks.palette = true;
+        proj.fractal.palette_phase = 0.2;
+        assert_close(
+            evaluate_frame(&proj, 1.0, EvalTarget::Preview)
+                .fractal
+                .palette_phase,
+            0.7,
+        );
+
+        // Looping: the first and last frame of the loop have the same colors.
+        proj.locks.palette = false;
+        proj.anim.kf_palette.keys.clear();
+        proj.anim.looping = true;
+        proj.fractal.palette_cycle_speed = 0.3;
+        assert_close(phase(&proj, 4.0).fract(), phase(&proj, 0.0).fract());
+    }
+
+    #[test]
+    fn color_spaces_keep_the_keyed_colors() {
+        let (orange, cyan) = ([1.0, 0.5, 0.0], [0.0, 0.75, 1.0]);
+        assert_eq!(ColorSpace::Rgb.lerp(orange, cyan, 0.5), [0.5, 0.625, 0.5]);
+        for (u, expected) in [(0.0, orange), (1.0, cyan)] {
+            let blended = ColorSpace::Oklab.lerp(orange, cyan, u);
+            for (actual, expected) in blended.into_iter().zip(expected) {
+                assert!((actual - expected).abs() < 1e-4, "{blended:?}");
+            }
+        }
+        // Oklab steps evenly in perceived lightness; linear RGB overshoots toward the brighter key.
+        let (blue, yellow) = ([0.0, 0.0, 1.0], [1.0, 1.0, 0.0]);
+        let lightness = |c| rgb_to_oklab(c)[0];
+        let even = (lightness(blue) + lightness(yellow)) / 2.0;
+        assert!((lightness(ColorSpace::Oklab.lerp(blue, yellow, 0.5)) - even).abs() < 1e-4);
+        assert!(lightness(ColorSpace::Rgb.lerp(blue, yellow, 0.5)) > even + 0.05);
+    }
+
+    #[test]
+    fn trap_color_track_tints_the_evaluated_frame() {
+        let mut proj = Project::default();
+        let authored = proj.fractal.orbit.color;
+        assert_eq!(
+            evaluate_frame(&proj, 1.0, EvalTarget::Preview)
+                .fractal
+                .orbit
+                .color,
+            authored
+        );
+        proj.anim.kf_trap_color.upsert(0.0, [1.0, 0.5, 0.0]);
+        proj.anim.kf_trap_color.upsert(2.0, [0.0, 0.5, 1.0]);
+        for target in [EvalTarget::Preview, EvalTarget::Export] {
+            let color = evaluate_frame(&proj, 1.0, target).fractal.orbit.color;
+            assert_eq!(color, [0.5, 0.5, 0.5]);
+        }
+        proj.anim.color_space = ColorSpace::Oklab;
+        let color = evaluate_frame(&proj, 1.0, EvalTarget::Export)
+            .fractal
+            .orbit
+            .color;
+        assert_ne!(color, [0.5, 0.5, 0.5]);
+        assert_eq!(proj.fractal.orbit.color, authored);
+    }
+
+    #[test]
+    fn cardioid_orbit_traces_the_main_cardioid() {
+        let orbit = CardioidOrbit {
+            speed: 1.0,
+            phase: 0.0,
+        };
+        let at = |turns: f32| orbit.point(turns);
+        assert_close(at(0.0).re, 0.25);
+        assert_close(at(0.0).im, 0.0);
+        assert_close(at(0.5).re, -0.75);
+        assert_close(at(0.25).re, 0.25);
+        assert_close(at(0.25).im, 0.5);
+        // Phase shifts the start along the same curve.
+        let shifted = CardioidOrbit {
+            phase: 0.25,
+            ..orbit
+        };
+        assert_close(shifted.point(0.0).im, 0.5);
+    }
+
+    #[test]
+    fn cardioid_orbit_drives_julia_c_only_in_julia_mode() {
+        let mut proj = Project::default();
+        proj.anim.duration = 4.0;
+        proj.anim.julia_orbit = Some(CardioidOrbit {
+            speed: 0.25,
+            phase: 0.0,
+        });
+        let c = |proj: &Project, t: f32| evaluate_frame(proj, t, EvalTarget::Export).fractal.c;
+        proj.fractal.kind = FractalKind::Mandelbrot;
+        let authored = proj.fractal.c;
+        assert_eq!(c(&proj, 2.0), authored);
+
+        proj.fractal.kind = FractalKind::Julia;
+        assert_close(c(&proj, 2.0).re, -0.75);
+        // Past the timeline the orbit keeps going, and a looping animation rounds it to
+        // whole loops so the last frame meets the first.
+        assert_close(c(&proj, 5.0).im, 0.5);
+        proj.anim.julia_orbit = Some(CardioidOrbit {
+            speed: 0.3,
+            phase: 0.1,
+        });
+        proj.anim.looping = true;
+        assert_close(c(&proj, 4.0).re, c(&proj, 0.0).re);
+        assert_close(c(&proj, 4.0).im, c(&proj, 0.0).im);
+    }
+
+    #[test]
+    fn julia_c_tracks_key_the_constant() {
+        let mut proj = Project::default();
+        proj.anim.duration = 4.0;
+        proj.fractal.kind = FractalKind::Julia;
+        // Key c around a circle, a quarter turn per second.
+        for k in 0..=4 {
+            let theta = std::f32::consts::FRAC_PI_2 * k as f32;
+            proj.anim.kf_julia_re.upsert(k as f32, 0.7885 * theta.cos());
+            proj.anim.kf_julia_im.upsert(k as f32, 0.7885 * theta.sin());
+        }
+        let c = |proj: &Project, t: f32, target| evaluate_frame(proj, t, target).fractal.c;
+        assert_close(c(&proj, 1.0, EvalTarget::Export).im, 0.7885);
+        assert_close(c(&proj, 2.0, EvalTarget::Export).re, -0.7885);
+        for t in [0.0, 0.5, 1.3, 3.9] {
+            assert_eq!(
+                c(&proj, t, EvalTarget::Preview),
+                c(&proj, t, EvalTarget::Export)
+            );
+        }
+        assert_eq!(
+            evaluate_export_frame(&proj, proj.export.fps).fractal.c,
+            c(&proj, 1.0, EvalTarget::Export)
+        );
+
+        // A lock holds the authored value in the preview only.
+        let authored = proj.fractal.c;
+        proj.locks.julia_re = true;
+        assert!(!proj.locks.group_locked(TrackGroup::Fractal));
+        assert_eq!(c(&proj, 2.0, EvalTarget::Preview).re, authored.re);
+        assert_close(c(&proj, 2.0, EvalTarget::Preview).im, 0.0);
+        assert_close(c(&proj, 2.0, EvalTarget::Export).re, -0.7885);
+        proj.locks.julia_re = false;
+
+        // The cardioid orbit replaces the keyed constant.
+        proj.anim.julia_orbit = Some(CardioidOrbit {
+            speed: 0.25,
+            phase: 0.0,
+        });
+        assert_close(c(&proj, 2.0, EvalTarget::Export).re, -0.75);
+
+        let mut anim = Animation::default();
+        let fractal = FractalParams::default();
+        anim.key_group(TrackGroup::Fractal, 0.5, &Camera::default(), &fractal);
+        assert_eq!(anim.kf_julia_re.keys[0].v, fractal.c.re);
+        assert_eq!(anim.kf_julia_im.keys[0].v, fractal.c.im);
+        assert!(anim.kf_zoom.keys.is_empty());
+    }
+
+    #[test]
+    fn frame_times_come_from_the_index() {
+        assert_eq!(frame_time(0, 30), 0.0);
+        assert_eq!(frame_time(90, 30), 3.0);
+        assert_eq!(frame_time(7, 24), (7.0f64 / 24.0) as f32);
+        // Summing 1/30 s steps in f32 is already off by the 90th frame.
+        let summed = (0..90).fold(0.0f32, |t, _| t + 1.0 / 30.0);
+        assert_ne!(summed, frame_time(90, 30));
+    }
+
+    #[test]
+    fn every_track_has_one_group() {
+        let grouped: Vec<TrackKind> = TrackGroup::ALL
+            .iter()
+            .flat_map(|group| group.tracks())
+            .collect();
+        assert_eq!(grouped.len(), TrackKind::ALL.len());
+        for track in TrackKind::ALL {
+            assert!(grouped.contains(&track));
+            assert!(track.group().tracks().contains(&track));
+        }
+        assert_eq!(
+            TrackGroup::Fractal.tracks(),
+            [TrackKind::JuliaRe, TrackKind::JuliaIm]
+        );
+        assert!(!TrackGroup::Traps.is_empty());
+    }
+
+    #[test]
+    fn key_group_keys_only_its_tracks() {
+        let mut anim = Animation::default();
+        let camera = Camera::new(Complex::new(0.25, -0.5), 900.0).with_rotation(0.4);
+        let fractal = FractalParams::default().with_palette_phase(0.3);
+        anim.key_group(TrackGroup::Camera, 1.5, &camera, &fractal);
+        for track in TrackGroup::Camera.tracks() {
+            let keys = &anim.track(track).keys;
+            assert_eq!(keys.len(), 1, "{track:?}");
+            assert_eq!(
+                (keys[0].t, keys[0].v),
+                (1.5, track.value(&camera, &fractal))
+            );
+        }
+        assert!(anim.kf_palette.keys.is_empty());
+        assert!(anim.kf_trap_color.keys.is_empty());
+
+        // Keying again at the same time updates the keys in place.
+        let moved = Camera::new(Complex::new(0.3, -0.5), 900.0);
+        anim.key_group(TrackGroup::Camera, 1.5, &moved, &fractal);
+        assert_eq!(anim.kf_center_x.keys.len(), 1);
+        assert_eq!(anim.kf_center_x.keys[0].v, 0.3);
+
+        anim.key_group(TrackGroup::Traps, 2.0, &camera, &fractal);
+        assert_eq!(anim.kf_trap_color.keys.len(), 1);
+        assert_eq!(anim.kf_trap_color.keys[0].v, fractal.orbit.color);
+        anim.key_group(TrackGroup::Color, 2.0, &camera, &fractal);
+        assert_eq!(anim.kf_palette.keys[0].v, 0.3);
+    }
+
+    #[test]
+    fn clear_track_drops_its_keys_and_selection() {
+        let mut anim = Animation::default();
+        anim.kf_zoom.upsert(0.0, 100.0);
+        anim.kf_zoom.upsert(1.0, 200.0);
+        anim.kf_palette.upsert(0.5, 0.2);
+        anim.selection = Some(SelectedKey {
+            track: TrackKind::Palette,
+            index: 0,
+        });
+        anim.clear_track(TrackKind::Zoom);
+        assert!(anim.kf_zoom.keys.is_empty());
+        assert!(
+            anim.selection.is_some(),
+            "other tracks keep their selection"
+        );
+        anim.clear_track(TrackKind::Palette);
+        assert!(anim.kf_palette.keys.is_empty());
+        assert!(anim.selection.is_none());
+    }
+
+    #[test]
+    fn reset_and_group_locks() {
+        let mut camera = Camera::new(Complex::new(0.25, -0.5), 900.0);
+        let mut fractal = FractalParams::default().with_palette_phase(0.3);
+        for track in TrackKind::ALL {
+            track.reset_value(&mut camera, &mut fractal);
+        }
+        let framing = |cam: &Camera| (cam.center, cam.scale, cam.rotation);
+        assert_eq!(framing(&camera), framing(&Camera::default()));
+        assert_eq!(fractal, FractalParams::default());
+
+        let mut proj = Project::default();
+        proj.fractal.orbit.enabled = true;
+        proj.anim.kf_center_x.upsert(0.0, 0.5);
+        proj.anim.kf_zoom.upsert(0.0, 1000.0);
+        proj.anim.kf_trap_color.upsert(0.0, [0.0, 1.0, 0.0]);
+        let mut locks = ParamLocks::default();
+        assert!(!locks.group_locked(TrackGroup::Camera));
+        assert!(!locks.group_locked(TrackGroup::Fractal));
+        locks.set_group(TrackGroup::Camera, true);
+        locks.set_group(TrackGroup::Traps, true);
+        assert!(locks.group_locked(TrackGroup::Camera) && locks.group_locked(TrackGroup::Traps));
+        assert!(!locks.palette && locks.any());
+        proj.locks = locks;
+        let frame = evaluate_frame(&proj, 0.0, EvalTarget::Preview);
+        assert_eq!(framing(&frame.camera), framing(&proj.camera));
+        assert_eq!(frame.fractal.orbit.color, proj.fractal.orbit.color);
+        // Exports still play muted tracks unless the locks apply to them.
+        let frame = evaluate_frame(&proj, 0.0, EvalTarget::Export);
+        assert_eq!(frame.camera.center.re, 0.5);
+        assert_eq!(frame.fractal.orbit.color, [0.0, 1.0, 0.0]);
+
+        proj.locks.zoom = false;
+        assert!(!proj.locks.group_locked(TrackGroup::Camera));
+    }
+
+    mod properties {
+        use proptest::prelude::*;
+
+        use super::super::*;
+
+        fn easing() -> impl Strategy<Value = Easing> {
+            prop::sample::select(Easing::ALL.to_vec())
+        }
+
+        /// Keys at least 0.1 s apart so slopes stay bounded.
+        fn spaced_track() -> impl Strategy<Value = Keyframes<f32>> {
+            prop::collection::vec((0.1f32..2.0, -100.0f32..100.0, easing()), 2..12).prop_map(
+                |steps| {
+                    let mut t = 0.0;
+                    let keys = steps
+                        .into_iter()
+                        .map(|(gap, v, easing)| {
+                            t += gap;
+                            Keyframe { t, v, easing }
+                        })
+                        .collect();
+                    Keyframes { keys }
+                },
+            )
+        }
+
+        proptest! {
+            #[test]
+            fn sample_is_bounded_by_neighbouring_keys(keys in spaced_track(), t in -1.0f32..30.0) {
+                let v = keys.sample(t, 0.0);
+                let before = keys.keys.iter().rev().find(|k| k.t <= t).unwrap_or(&keys.keys[0]);
+                let after = keys.keys.iter().find(|k| k.t >= t).unwrap_or(keys.keys.last().unwrap());
+                let tol = 1e-3;
+                prop_assert!(v >= before.v.min(after.v) - tol && v <= before.v.max(after.v) + tol,
+                    "{v} outside {}..{}", before.v, after.v);
+            }
+
+            #[test]
+            fn sample_is_continuous_across_keys(keys in spaced_track()) {
+                for (i, key) in keys.keys.iter().enumerate() {
+                    let left = keys.sample(key.t - 1e-5, 0.0);
+                    let right = keys.sample(key.t + 1e-5, 0.0);
+                    // Only a held previous key jumps on arrival.
+                    let held = i > 0 && keys.keys[i - 1].easing == Easing::Hold;
+                    prop_assert!(held || (left - key.v).abs() < 0.1, "left {left} vs {}", key.v);
+                    prop_assert!((right - key.v).abs() < 0.1, "right {right} vs {}", key.v);
+                }
+            }
+
+            #[test]
+            fn upsert_keeps_keys_sorted(inserts in prop::collection::vec((-10.0f32..10.0, -1.0f32..1.0), 0..40)) {
+                let mut keys = Keyframes::default();
+                for (t, v) in inserts {
+                    keys.upsert(t, v);
+                }
+                prop_assert!(keys.keys.windows(2).all(|w| w[0].t < w[1].t));
+            }
+
+            #[test]
+            fn resolve_times_is_finite_and_non_negative(
+                time in any::<f32>(),
+                duration in any::<f32>(),
+                looping in any::<bool>(),
+                endless in any::<bool>(),
+            ) {
+                let anim = Animation {
+                    duration,
+                    looping,
+                    zoom_forever: endless.then(|| EndlessZoom::with_defaults(1.0)),
+                    ..Animation::default()
+                };
+                let (key_t, zoom_t) = anim.resolve_times(time);
+                prop_assert!(key_t.is_finite() && key_t >= 0.0, "key time {key_t}");
+                prop_assert!(zoom_t.is_finite() && zoom_t >= 0.0, "zoom time {zoom_t}");
+            }
+        }
+    }
+}
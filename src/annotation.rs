@@ -0,0 +1,261 @@
+//! Text annotations: labels pinned to the frame or to a point of the complex plane, drawn
+//! over the preview and stamped into exported frames.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{text_size, Camera, Complex, Keyframes, TextStamp};
+
+/// Plane-anchored text shorter than this many pixels fades out: one pixel per glyph row.
+pub const ANNOTATION_FADE_PX: f32 = 7.0;
+
+/// Plane-anchored text shorter than this many pixels is not drawn at all.
+pub const ANNOTATION_HIDE_PX: f32 = 3.0;
+
+/// What an annotation's position and size are measured in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnotationAnchor {
+    /// Fractions of the frame; the text stays put as the camera moves.
+    #[default]
+    Screen,
+    /// Complex-plane units; the text follows its point and grows as the camera zooms in.
+    Plane,
+}
+
+impl AnnotationAnchor {
+    pub fn label(self) -> &'static str {
+        match self {
+            AnnotationAnchor::Screen => "Screen",
+            AnnotationAnchor::Plane => "Plane",
+        }
+    }
+}
+
+/// A text label shown between `start` and `end`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct Annotation {
+    pub text: String,
+    pub anchor: AnnotationAnchor,
+    /// Center of the text: `[x, y]` fractions of the frame from its top-left corner for
+    /// screen anchors, `[re, im]` for plane anchors.
+    pub position: [f32; 2],
+    /// Text height: a fraction of the frame height for screen anchors, complex-plane units
+    /// for plane anchors.
+    pub size: f32,
+    pub color: [f32; 3],
+    /// Seconds the annotation appears at.
+    pub start: f32,
+    /// Seconds it is gone by; `None` keeps it to the end.
+    pub end: Option<f32>,
+    /// Seconds spent fading in after `start`.
+    pub fade_in: f32,
+    /// Seconds spent fading out before `end`.
+    pub fade_out: f32,
+    /// Opacity over time, multiplied with the fades; without keys it is opaque.
+    pub opacity: Keyframes<f32>,
+}
+
+impl Default for Annotation {
+    fn default() -> Self {
+        Self {
+            text: "Label".into(),
+            anchor: AnnotationAnchor::Screen,
+            position: [0.5, 0.9],
+            size: 0.05,
+            color: [1.0, 1.0, 1.0],
+            start: 0.0,
+            end: None,
+            fade_in: 0.0,
+            fade_out: 0.0,
+            opacity: Keyframes::default(),
+        }
+    }
+}
+
+/// Where an annotation lands on a frame at one moment.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlacedAnnotation {
+    /// Center of the text in pixels.
+    pub center: (f32, f32),
+    /// Text height in pixels.
+    pub height: f32,
+    /// Opacity in `0..=1`, after fades and the size fade of small plane text.
+    pub alpha: f32,
+}
+
+impl Annotation {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Pins the text to the frame, centered `x`, `y` of the way across and down.
+    pub fn on_screen(mut self, x: f32, y: f32, size: f32) -> Self {
+        self.anchor = AnnotationAnchor::Screen;
+        self.position = [x, y];
+        self.size = size;
+        self
+    }
+
+    /// Pins the text to `point`, `size` complex-plane units tall.
+    pub fn on_plane(mut self, point: Complex, size: f32) -> Self {
+        self.anchor = AnnotationAnchor::Plane;
+        self.position = [point.re, point.im];
+        self.size = size;
+        self
+    }
+
+    pub fn with_color(mut self, color: [f32; 3]) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_times(mut self, start: f32, end: Option<f32>) -> Self {
+        self.start = start;
+        self.end = end;
+        self
+    }
+
+    pub fn with_fades(mut self, fade_in: f32, fade_out: f32) -> Self {
+        self.fade_in = fade_in;
+        self.fade_out = fade_out;
+        self
+    }
+
+    /// Opacity at `t` seconds: the keyed opacity times the fades, 0 outside `start..end`.
+    pub fn opacity_at(&self, t: f32) -> f32 {
+        let end = self.end.unwrap_or(f32::INFINITY);
+        if !(self.start..end).contains(&t) {
+            return 0.0;
+        }
+        let ramp = |elapsed: f32, length: f32| {
+            if length > 0.0 {
+                (elapsed / length).min(1.0)
+            } else {
+                1.0
+            }
+        };
+        let fade = ramp(t - self.start, self.fade_in) * ramp(end - t, self.fade_out);
+        (self.opacity.sample(t, 1.0) * fade).clamp(0.0, 1.0)
+    }
+
+    /// Where the annotation is drawn at `t` on a `width`×`height` view of `cam`, or `None`
+    /// when it is hidden then.
+    pub fn placed(
+        &self,
+        t: f32,
+        cam: &Camera,
+        width: f32,
+        height: f32,
+    ) -> Option<PlacedAnnotation> {
+        if self.text.trim().is_empty() {
+            return None;
+        }
+        let [x, y] = self.position;
+        let (center, text_height, size_fade) = match self.anchor {
+            AnnotationAnchor::Screen => ((x * width, y * height), self.size * height, 1.0),
+            AnnotationAnchor::Plane => {
+                let px = (f64::from(self.size) * cam.scale) as f32;
+                let fade = ((px - ANNOTATION_HIDE_PX) / (ANNOTATION_FADE_PX - ANNOTATION_HIDE_PX))
+                    .clamp(0.0, 1.0);
+                (
+                    cam.plane_to_pixel(Complex::new(x, y), width, height),
+                    px,
+                    fade,
+                )
+            }
+        };
+        let alpha = self.opacity_at(t) * size_fade;
+        (alpha > 0.0 && text_height.is_finite() && center.0.is_finite() && center.1.is_finite())
+            .then_some(PlacedAnnotation {
+                center,
+                height: text_height,
+                alpha,
+            })
+    }
+
+    /// The annotation as drawn into a `size` frame of `cam` at `t`, if it shows then.
+    pub fn stamp(&self, t: f32, cam: &Camera, size: (u32, u32)) -> Option<TextStamp> {
+        let placed = self.placed(t, cam, size.0 as f32, size.1 as f32)?;
+        // Never taller than the frame, so a camera zoomed far past the text stays cheap.
+        let dot = (placed.height / 7.0)
+            .round()
+            .clamp(1.0, size.1.max(1) as f32) as u32;
+        let (w, h) = text_size(&self.text, dot);
+        let origin = |center: f32, extent: u32| {
+            (center - extent as f32 / 2.0)
+                .round()
+                .clamp(i32::MIN as f32, i32::MAX as f32) as i32
+        };
+        Some(TextStamp {
+            text: self.text.clone(),
+            origin: (origin(placed.center.0, w), origin(placed.center.1, h)),
+            dot,
+            color: self
+                .color
+                .map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8),
+            alpha: (placed.alpha * 255.0).round() as u8,
+        })
+    }
+}
+
+/// Stamps of the `annotations` showing at `t` in a `size` frame of `cam`, in drawing order.
+pub fn annotation_stamps(
+    annotations: &[Annotation],
+    t: f32,
+    cam: &Camera,
+    size: (u32, u32),
+) -> Vec<TextStamp> {
+    annotations
+        .iter()
+        .filter_map(|a| a.stamp(t, cam, size))
+        .filter(|stamp| stamp.alpha > 0)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fades_and_keyed_opacity_multiply() {
+        let mut label = Annotation::new("minibrot")
+            .with_times(1.0, Some(5.0))
+            .with_fades(1.0, 2.0);
+        assert_eq!(label.opacity_at(0.5), 0.0);
+        assert_eq!(label.opacity_at(1.5), 0.5);
+        assert_eq!(label.opacity_at(2.5), 1.0);
+        assert_eq!(label.opacity_at(4.0), 0.5);
+        assert_eq!(label.opacity_at(5.0), 0.0);
+
+        label.opacity.upsert(2.0, 0.5);
+        assert_eq!(label.opacity_at(3.0), 0.5);
+    }
+
+    #[test]
+    fn plane_text_follows_the_camera_and_fades_when_small() {
+        let point = Complex::new(-0.75, 0.1);
+        let label = Annotation::new("minibrot").on_plane(point, 0.01);
+        let size = (400, 300);
+
+        let close = Camera::new(point, 2000.0);
+        let stamp = label.stamp(0.0, &close, size).unwrap();
+        let (w, h) = text_size("minibrot", stamp.dot);
+        assert_eq!(stamp.dot, 3, "20 px tall");
+        assert_eq!(stamp.alpha, 255);
+        assert_eq!(stamp.origin, (200 - w as i32 / 2, 150 - h as i32 / 2));
+
+        let far = Camera::new(point, 500.0);
+        let faded = label.stamp(0.0, &far, size).unwrap();
+        assert_eq!((faded.dot, faded.alpha), (1, 128), "5 px tall");
+        assert!(label.stamp(0.0, &Camera::new(point, 250.0), size).is_none());
+
+        // Screen text ignores the camera.
+        let fixed = Annotation::new("title").on_screen(0.5, 0.1, 0.1);
+        assert_eq!(fixed.stamp(0.0, &close, size), fixed.stamp(0.0, &far, size));
+    }
+}
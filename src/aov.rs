@@ -0,0 +1,359 @@
+//! Compositing passes (AOVs): per-pixel data such as the interior mask, written as grayscale
+//! image sequences next to an export's video.
+
+use std::io;
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use image::{ImageBuffer, Luma};
+use serde::{Deserialize, Serialize};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::ExportError;
+use crate::{FractalParams, PixelSample};
+
+/// One grayscale pass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AovPass {
+    /// 1 where the orbit never escaped, 0 elsewhere.
+    Interior,
+    /// Smooth iteration count at escape.
+    Iteration,
+    /// How much of the orbit-trap color went into the pixel.
+    Trap,
+}
+
+impl AovPass {
+    pub const ALL: [AovPass; 3] = [AovPass::Interior, AovPass::Iteration, AovPass::Trap];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AovPass::Interior => "Interior mask",
+            AovPass::Iteration => "Smooth iterations",
+            AovPass::Trap => "Orbit trap",
+        }
+    }
+
+    /// File name part, as in `frame_000001.iter.exr`.
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            AovPass::Interior => "interior",
+            AovPass::Iteration => "iter",
+            AovPass::Trap => "trap",
+        }
+    }
+
+    /// The pass value of one pixel: raw smooth iterations, 0–1 for the other passes.
+    pub fn value(&self, sample: &PixelSample, params: &FractalParams) -> f32 {
+        match self {
+            AovPass::Interior => f32::from(u8::from(sample.interior)),
+            AovPass::Iteration => sample.smooth_iter,
+            AovPass::Trap => sample.trap_weight(params),
+        }
+    }
+
+    /// [`AovPass::value`] scaled to 0–1 for integer formats; iterations are divided by
+    /// `max_iter`.
+    pub fn normalized(&self, sample: &PixelSample, params: &FractalParams) -> f32 {
+        let value = match self {
+            AovPass::Iteration => sample.smooth_iter / params.max_iter.max(1) as f32,
+            _ => self.value(sample, params),
+        };
+        value.clamp(0.0, 1.0)
+    }
+}
+
+/// File format of exported passes.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AovFormat {
+    /// 32-bit float OpenEXR with a single `Y` channel; values are stored unscaled.
+    #[default]
+    Exr,
+    /// 16-bit grayscale PNG; values are scaled to 0–1 first.
+    Png,
+}
+
+impl AovFormat {
+    pub fn label(&self) -> &'static str {
+        match self {
+            AovFormat::Exr => "EXR (float)",
+            AovFormat::Png => "PNG (16-bit)",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AovFormat::Exr => "exr",
+            AovFormat::Png => "png",
+        }
+    }
+}
+
+/// Which passes an export writes besides the video.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct AovPasses {
+    pub interior: bool,
+    pub iteration: bool,
+    pub trap: bool,
+    pub format: AovFormat,
+}
+
+impl AovPasses {
+    pub fn includes(&self, pass: AovPass) -> bool {
+        match pass {
+            AovPass::Interior => self.interior,
+            AovPass::Iteration => self.iteration,
+            AovPass::Trap => self.trap,
+        }
+    }
+
+    pub fn flag_mut(&mut self, pass: AovPass) -> &mut bool {
+        match pass {
+            AovPass::Interior => &mut self.interior,
+            AovPass::Iteration => &mut self.iteration,
+            AovPass::Trap => &mut self.trap,
+        }
+    }
+
+    /// The passes to write, in [`AovPass::ALL`] order.
+    pub fn selected(&self) -> Vec<AovPass> {
+        AovPass::ALL
+            .into_iter()
+            .filter(|&pass| self.includes(pass))
+            .collect()
+    }
+}
+
+/// File name of `pass` for export frame `frame`, e.g. `frame_000001.iter.exr`.
+pub fn pass_file_name(frame: u32, pass: AovPass, format: AovFormat) -> String {
+    format!("frame_{frame:06}.{}.{}", pass.suffix(), format.extension())
+}
+
+/// Writes `pass` of a `width`×`height` frame's samples (row-major) to `path`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_pass(
+    path: &Path,
+    (width, height): (u32, u32),
+    samples: &[PixelSample],
+    params: &FractalParams,
+    pass: AovPass,
+    format: AovFormat,
+) -> Result<(), ExportError> {
+    match format {
+        AovFormat::Exr => {
+            let values: Vec<f32> = samples.iter().map(|s| pass.value(s, params)).collect();
+            let mut out = BufWriter::new(File::create(path)?);
+            write_exr(&mut out, width, height, &values)?;
+            out.flush()?;
+        }
+        AovFormat::Png => {
+            let values: Vec<u16> = samples
+                .iter()
+                .map(|s| (pass.normalized(s, params) * 65535.0).round() as u16)
+                .collect();
+            ImageBuffer::<Luma<u16>, _>::from_raw(width, height, values)
+                .expect("one sample per pixel")
+                .save(path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Grayscale RGBA8 pixels of a pass for the preview. Iterations are scaled so the highest
+/// count in the frame is white.
+pub fn pass_preview(pass: AovPass, samples: &[PixelSample], params: &FractalParams) -> Vec<u8> {
+    let scale = match pass {
+        AovPass::Iteration => {
+            1.0 / samples
+                .iter()
+                .map(|s| s.smooth_iter)
+                .fold(0.0f32, f32::max)
+                .max(1.0)
+        }
+        _ => 1.0,
+    };
+    samples
+        .iter()
+        .flat_map(|s| {
+            let v = ((pass.value(s, params) * scale).clamp(0.0, 1.0) * 255.0).round() as u8;
+            [v, v, v, 255]
+        })
+        .collect()
+}
+
+/// Writes an uncompressed scanline OpenEXR image with one 32-bit float `Y` channel.
+pub fn write_exr(
+    out: &mut impl io::Write,
+    width: u32,
+    height: u32,
+    values: &[f32],
+) -> io::Result<()> {
+    assert_eq!(
+        values.len(),
+        width as usize * height as usize,
+        "one value per pixel"
+    );
+    let mut header = Vec::new();
+    header.extend(20000630u32.to_le_bytes());
+    header.extend(2u32.to_le_bytes());
+
+    let mut channels = b"Y\0".to_vec();
+    channels.extend(2i32.to_le_bytes()); // FLOAT
+    channels.extend([0, 0, 0, 0]); // pLinear and reserved
+    channels.extend(1i32.to_le_bytes());
+    channels.extend(1i32.to_le_bytes());
+    channels.push(0);
+    let window: Vec<u8> = [0, 0, width as i32 - 1, height as i32 - 1]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect();
+    let attributes: [(&str, &str, Vec<u8>); 8] = [
+        ("channels", "chlist", channels),
+        ("compression", "compression", vec![0]),
+        ("dataWindow", "box2i", window.clone()),
+        ("displayWindow", "box2i", window),
+        ("lineOrder", "lineOrder", vec![0]),
+        ("pixelAspectRatio", "float", 1f32.to_le_bytes().to_vec()),
+        ("screenWindowCenter", "v2f", [0u8; 8].to_vec()),
+        ("screenWindowWidth", "float", 1f32.to_le_bytes().to_vec()),
+    ];
+    for (name, kind, value) in attributes {
+        header.extend(name.as_bytes());
+        header.push(0);
+        header.extend(kind.as_bytes());
+        header.push(0);
+        header.extend((value.len() as i32).to_le_bytes());
+        header.extend(value);
+    }
+    header.push(0);
+    out.write_all(&header)?;
+
+    // One chunk per scanline: y, byte count, then the row.
+    let row_bytes = width as u64 * 4;
+    let chunk = 8 + row_bytes;
+    let first = header.len() as u64 + height as u64 * 8;
+    for y in 0..height as u64 {
+        out.write_all(&(first + y * chunk).to_le_bytes())?;
+    }
+    for (y, row) in values.chunks(width.max(1) as usize).enumerate() {
+        out.write_all(&(y as i32).to_le_bytes())?;
+        out.write_all(&(row_bytes as i32).to_le_bytes())?;
+        for v in row {
+            out.write_all(&v.to_le_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FractalKind;
+
+    fn i32_at(bytes: &[u8], at: usize) -> i32 {
+        i32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn exr_layout_follows_the_spec() {
+        let values = [0.0, 0.5, 1.0, 2.0, 3.5, -1.0];
+        let mut bytes = Vec::new();
+        write_exr(&mut bytes, 3, 2, &values).unwrap();
+        assert_eq!(&bytes[..8], &[0x76, 0x2f, 0x31, 0x01, 2, 0, 0, 0]);
+
+        // Header attributes up to the terminating empty name.
+        let mut at = 8;
+        let mut names = Vec::new();
+        while bytes[at] != 0 {
+            let name_end = at + bytes[at..].iter().position(|&b| b == 0).unwrap();
+            let kind_end =
+                name_end + 1 + bytes[name_end + 1..].iter().position(|&b| b == 0).unwrap();
+            let size = i32_at(&bytes, kind_end + 1) as usize;
+            let name = std::str::from_utf8(&bytes[at..name_end]).unwrap();
+            if name == "dataWindow" {
+                let v = kind_end + 5;
+                let window: Vec<i32> = (0..4).map(|i| i32_at(&bytes, v + i * 4)).collect();
+                assert_eq!(window, vec![0, 0, 2, 1]);
+            }
+            names.push(name.to_string());
+            at = kind_end + 5 + size;
+        }
+        assert!(names.iter().any(|n| n == "channels"));
+        assert_eq!(names.len(), 8);
+
+        // Offsets point at chunks holding each row.
+        let table = at + 1;
+        for y in 0..2 {
+            let offset =
+                u64::from_le_bytes(bytes[table + y * 8..table + y * 8 + 8].try_into().unwrap())
+                    as usize;
+            assert_eq!(i32_at(&bytes, offset), y as i32);
+            assert_eq!(i32_at(&bytes, offset + 4), 12);
+            let row: Vec<f32> = (0..3)
+                .map(|x| f32::from_le_bytes(bytes[offset + 8 + x * 4..][..4].try_into().unwrap()))
+                .collect();
+            assert_eq!(row, values[y * 3..y * 3 + 3]);
+        }
+        assert_eq!(bytes.len(), table + 16 + 2 * 20);
+    }
+
+    #[test]
+    fn passes_read_the_samples() {
+        let mut params = FractalParams::new(FractalKind::Mandelbrot).with_max_iter(100);
+        let inside = PixelSample {
+            smooth_iter: 0.0,
+            interior: true,
+            trap_min: 0.0,
+            interior_shade: 0.0,
+            distance: None,
+            stripe: None,
+            root: None,
+            lyapunov: None,
+        };
+        let outside = PixelSample {
+            smooth_iter: 25.0,
+            interior: false,
+            trap_min: 0.2,
+            interior_shade: 0.0,
+            distance: None,
+            stripe: None,
+            root: None,
+            lyapunov: None,
+        };
+        assert_eq!(AovPass::Interior.value(&inside, &params), 1.0);
+        assert_eq!(AovPass::Interior.value(&outside, &params), 0.0);
+        assert_eq!(AovPass::Iteration.value(&outside, &params), 25.0);
+        assert_eq!(AovPass::Iteration.normalized(&outside, &params), 0.25);
+        assert_eq!(AovPass::Trap.value(&outside, &params), 0.0);
+        params.orbit.enabled = true;
+        assert!((AovPass::Trap.value(&outside, &params) - (-0.2f32 * 5.0).exp()).abs() < 1e-6);
+
+        let preview = pass_preview(AovPass::Iteration, &[inside, outside], &params);
+        assert_eq!(preview, vec![0, 0, 0, 255, 255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn selection_and_file_names() {
+        let passes = AovPasses {
+            interior: true,
+            trap: true,
+            ..AovPasses::default()
+        };
+        assert_eq!(passes.selected(), vec![AovPass::Interior, AovPass::Trap]);
+        assert_eq!(
+            pass_file_name(1, AovPass::Iteration, AovFormat::Exr),
+            "frame_000001.iter.exr"
+        );
+        assert_eq!(
+            pass_file_name(42, AovPass::Interior, AovFormat::Png),
+            "frame_000042.interior.png"
+        );
+    }
+}
@@ -0,0 +1,299 @@
+//! Audio-reactive animation: loudness and frequency-band envelopes of an audio file,
+//! baked into keyframe tracks so exports stay deterministic.
+
+use std::{fs::File, path::Path, sync::Arc};
+
+use rustfft::{num_complex::Complex32, Fft, FftPlanner};
+use symphonia::core::{
+    audio::SampleBuffer, codecs::DecoderOptions, errors::Error as SymphoniaError,
+    formats::FormatOptions, io::MediaSourceStream, meta::MetadataOptions, probe::Hint,
+};
+
+use crate::{Animation, Keyframe, TrackKind};
+
+/// Smallest FFT window, so low bands still get a few bins at high frame rates.
+const MIN_FFT_SIZE: usize = 2048;
+
+/// What part of the signal an envelope follows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioBand {
+    /// RMS of the whole signal.
+    Level,
+    Bass,
+    LowMid,
+    Mid,
+    High,
+}
+
+impl AudioBand {
+    pub const ALL: [AudioBand; 5] = [
+        AudioBand::Level,
+        AudioBand::Bass,
+        AudioBand::LowMid,
+        AudioBand::Mid,
+        AudioBand::High,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            AudioBand::Level => "Level (RMS)",
+            AudioBand::Bass => "Bass 20-150 Hz",
+            AudioBand::LowMid => "Low mid 150-600 Hz",
+            AudioBand::Mid => "Mid 600-2500 Hz",
+            AudioBand::High => "High 2.5-10 kHz",
+        }
+    }
+
+    /// Frequency range in Hz; `None` for the broadband level.
+    pub fn range(self) -> Option<(f32, f32)> {
+        match self {
+            AudioBand::Level => None,
+            AudioBand::Bass => Some((20.0, 150.0)),
+            AudioBand::LowMid => Some((150.0, 600.0)),
+            AudioBand::Mid => Some((600.0, 2500.0)),
+            AudioBand::High => Some((2500.0, 10_000.0)),
+        }
+    }
+}
+
+/// Per-frame envelopes of an audio file, one value per video frame.
+#[derive(Debug, Clone)]
+pub struct AudioAnalysis {
+    pub fps: u32,
+    /// Seconds.
+    pub duration: f32,
+    /// Indexed like [`AudioBand::ALL`]; each is scaled so its loudest frame is 1.
+    envelopes: [Vec<f32>; 5],
+}
+
+impl AudioAnalysis {
+    pub fn envelope(&self, band: AudioBand) -> &[f32] {
+        &self.envelopes[band as usize]
+    }
+
+    pub fn frames(&self) -> usize {
+        self.envelopes[0].len()
+    }
+}
+
+/// Decodes a WAV or MP3 file and analyzes it at `fps` frames per second.
+pub fn analyze_audio(path: &Path, fps: u32) -> Result<AudioAnalysis, String> {
+    let (samples, sample_rate) = decode_audio(path)?;
+    if samples.is_empty() {
+        return Err(format!("{} contains no audio", path.display()));
+    }
+    Ok(analyze_samples(&samples, sample_rate, fps))
+}
+
+/// Decodes the first audio track of `path`, mixed down to mono. Returns the samples and
+/// their sample rate.
+pub fn decode_audio(path: &Path) -> Result<(Vec<f32>, u32), String> {
+    let file = File::open(path).map_err(|e| format!("Could not open {}: {e}", path.display()))?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(ext);
+    }
+    let probed = symphonia::default::get_probe()
+        .format(
+            &hint,
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(|e| format!("{} is not a supported audio file: {e}", path.display()))?;
+    let mut format = probed.format;
+    let track = format
+        .default_track()
+        .ok_or_else(|| format!("{} has no audio track", path.display()))?;
+    let track_id = track.id;
+    let sample_rate = track
+        .codec_params
+        .sample_rate
+        .ok_or_else(|| format!("{} does not declare a sample rate", path.display()))?;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| e.to_string())?;
+
+    let mut mono = Vec::new();
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                break
+            }
+            Err(e) => return Err(e.to_string()),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // A corrupt frame is skipped, as players do.
+            Err(SymphoniaError::DecodeError(_)) => continue,
+            Err(e) => return Err(e.to_string()),
+        };
+        let spec = *decoded.spec();
+        let channels = spec.channels.count().max(1);
+        let mut buf = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+        buf.copy_interleaved_ref(decoded);
+        mono.extend(
+            buf.samples()
+                .chunks(channels)
+                .map(|frame| frame.iter().sum::<f32>() / channels as f32),
+        );
+    }
+    Ok((mono, sample_rate))
+}
+
+/// Envelopes of mono `samples` with one value per `1/fps` seconds.
+pub fn analyze_samples(samples: &[f32], sample_rate: u32, fps: u32) -> AudioAnalysis {
+    let fps = fps.max(1);
+    // Fractional hop so frame `i` starts at exactly `i / fps` seconds.
+    let hop = sample_rate.max(1) as f64 / fps as f64;
+    let size = (hop.ceil() as usize).next_power_of_two().max(MIN_FFT_SIZE);
+    let fft: Arc<dyn Fft<f32>> = FftPlanner::new().plan_fft_forward(size);
+    let hann: Vec<f32> = (0..size)
+        .map(|i| 0.5 - 0.5 * (std::f32::consts::TAU * i as f32 / size as f32).cos())
+        .collect();
+    let bin_hz = sample_rate as f32 / size as f32;
+
+    let frames = (samples.len() as f64 / hop).ceil() as usize;
+    let mut envelopes: [Vec<f32>; 5] = Default::default();
+    let mut buf = vec![Complex32::default(); size];
+    for frame in 0..frames {
+        let start = (frame as f64 * hop).round() as usize;
+        let end = (((frame + 1) as f64 * hop).round() as usize).clamp(start, samples.len());
+        let chunk = &samples[start..end];
+        let rms = (chunk.iter().map(|s| s * s).sum::<f32>() / chunk.len().max(1) as f32).sqrt();
+        envelopes[AudioBand::Level as usize].push(rms);
+
+        // The FFT window is centered on the frame and zero-padded past either end.
+        let offset = ((start + end) / 2) as isize - (size / 2) as isize;
+        for (i, c) in buf.iter_mut().enumerate() {
+            let s = usize::try_from(offset + i as isize)
+                .ok()
+                .and_then(|j| samples.get(j))
+                .copied()
+                .unwrap_or(0.0);
+            *c = Complex32::new(s * hann[i], 0.0);
+        }
+        fft.process(&mut buf);
+        for band in &AudioBand::ALL[1..] {
+            let (lo, hi) = band.range().unwrap_or_default();
+            let bins = &buf[((lo / bin_hz).ceil() as usize).min(size / 2)
+                ..((hi / bin_hz).floor() as usize + 1).min(size / 2)];
+            let power = if bins.is_empty() {
+                0.0
+            } else {
+                (bins.iter().map(|c| c.norm_sqr()).sum::<f32>() / bins.len() as f32).sqrt()
+            };
+            envelopes[*band as usize].push(power);
+        }
+    }
+    for envelope in &mut envelopes {
+        let peak = envelope.iter().copied().fold(0.0, f32::max);
+        if peak > 0.0 {
+            envelope.iter_mut().for_each(|v| *v /= peak);
+        }
+    }
+    AudioAnalysis {
+        fps,
+        duration: samples.len() as f32 / sample_rate.max(1) as f32,
+        envelopes,
+    }
+}
+
+/// How an envelope becomes keyframe values on a track.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioBinding {
+    pub band: AudioBand,
+    /// Release time in seconds; rises follow the audio immediately.
+    pub smoothing: f32,
+    pub gain: f32,
+    /// Track value at silence. For the zoom track this is the scale.
+    pub offset: f32,
+    /// Integrates the envelope, so loudness sets the rate of change instead of the value.
+    pub accumulate: bool,
+    pub target: TrackKind,
+}
+
+impl Default for AudioBinding {
+    fn default() -> Self {
+        Self {
+            band: AudioBand::Bass,
+            smoothing: 0.15,
+            gain: 0.25,
+            offset: 0.0,
+            accumulate: false,
+            target: TrackKind::Palette,
+        }
+    }
+}
+
+impl AudioBinding {
+    /// One track value per analysis frame. On the zoom track, `gain` is in doublings of
+    /// the scale so the zoom stays smooth at any depth.
+    pub fn values(&self, analysis: &AudioAnalysis) -> Vec<f32> {
+        let dt = 1.0 / analysis.fps as f32;
+        let release = if self.smoothing > 0.0 {
+            1.0 - (-dt / self.smoothing).exp()
+        } else {
+            1.0
+        };
+        let mut level = 0.0f32;
+        let mut total = 0.0f32;
+        analysis
+            .envelope(self.band)
+            .iter()
+            .map(|&x| {
+                level = if x > level {
+                    x
+                } else {
+                    level + (x - level) * release
+                };
+                let v = if self.accumulate {
+                    total += level * dt;
+                    total
+                } else {
+                    level
+                };
+                match self.target {
+                    TrackKind::Zoom => self.offset * (self.gain * v).exp2(),
+                    _ => self.offset + self.gain * v,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Replaces the keys of the binding's target track with one key per frame, up to the
+/// animation's duration. Returns the number of keys written.
+pub fn bake_audio(
+    anim: &mut Animation,
+    analysis: &AudioAnalysis,
+    binding: &AudioBinding,
+) -> Result<usize, String> {
+    if binding.target == TrackKind::Zoom && anim.zoom_forever.is_some() {
+        return Err("endless zoom replaces the zoom track; disable it before baking".into());
+    }
+    if binding.target == TrackKind::Zoom && binding.offset <= 0.0 {
+        return Err("the zoom track needs a positive base scale".into());
+    }
+    let last = (anim.duration.max(0.0) * analysis.fps as f32).floor() as usize;
+    let keys: Vec<Keyframe<f32>> = binding
+        .values(analysis)
+        .into_iter()
+        .take(last + 1)
+        .enumerate()
+        .map(|(i, v)| Keyframe {
+            t: i as f32 / analysis.fps as f32,
+            v,
+            easing: Default::default(),
+        })
+        .collect();
+    let written = keys.len();
+    anim.track_mut(binding.target).keys = keys;
+    anim.selection = None;
+    Ok(written)
+}
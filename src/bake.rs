@@ -0,0 +1,547 @@
+//! Animation baking: every animated parameter sampled once per export frame, written as
+//! CSV or JSON for other tools, and read back as dense keyframes.
+
+use std::{fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    evaluate_export_frame, frame_time, Easing, EvaluatedFrame, Keyframe, Project, ScriptError,
+    ScriptRunner, TrackKind,
+};
+
+/// CSV columns in the order they are written. `max_iter` is for reference and is not
+/// imported.
+pub const BAKE_COLUMNS: [&str; 13] = [
+    "frame",
+    "time",
+    "scale",
+    "center_re",
+    "center_im",
+    "rotation",
+    "palette_phase",
+    "trap_r",
+    "trap_g",
+    "trap_b",
+    "c_re",
+    "c_im",
+    "max_iter",
+];
+
+/// How many of [`BAKE_COLUMNS`], from the first, an imported CSV must have.
+const REQUIRED_COLUMNS: usize = 10;
+
+#[derive(Error, Debug)]
+pub enum BakeError {
+    #[error("IO: {0}")]
+    Io(#[from] io::Error),
+    #[error("JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Script: {0}")]
+    Script(#[from] ScriptError),
+    #[error("line {line}: {message}")]
+    Parse { line: usize, message: String },
+    #[error("no frames to import")]
+    Empty,
+}
+
+/// File layout of a [`BakedAnimation`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BakeFormat {
+    /// A header row, then one row per frame.
+    Csv,
+    /// One object holding the frame rate and every frame.
+    Json,
+}
+
+impl BakeFormat {
+    /// JSON for `.json`, CSV for anything else.
+    pub fn of(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => BakeFormat::Json,
+            _ => BakeFormat::Csv,
+        }
+    }
+}
+
+/// One exported frame's animated parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BakedFrame {
+    pub frame: u32,
+    /// Seconds.
+    pub time: f32,
+    pub scale: f32,
+    pub center_re: f32,
+    pub center_im: f32,
+    /// Radians.
+    pub rotation: f32,
+    pub palette_phase: f32,
+    /// Orbit trap color, linear RGB.
+    pub trap_r: f32,
+    pub trap_g: f32,
+    pub trap_b: f32,
+    /// Julia constant; `None` when the file has no `c` columns.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub c_re: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub c_im: Option<f32>,
+    #[serde(default)]
+    pub max_iter: u32,
+}
+
+impl BakedFrame {
+    pub fn new(frame: u32, time: f32, eval: &EvaluatedFrame) -> Self {
+        let (camera, fractal) = (&eval.camera, &eval.fractal);
+        let [trap_r, trap_g, trap_b] = fractal.orbit.color;
+        Self {
+            frame,
+            time,
+            // Rounded to the f32 the tracks they import into hold.
+            scale: camera.scale as f32,
+            center_re: camera.center.re as f32,
+            center_im: camera.center.im as f32,
+            rotation: camera.rotation,
+            palette_phase: fractal.palette_phase,
+            trap_r,
+            trap_g,
+            trap_b,
+            c_re: Some(fractal.c.re),
+            c_im: Some(fractal.c.im),
+            max_iter: fractal.max_iter,
+        }
+    }
+
+    /// The value a track takes at this frame, if the file has it.
+    pub fn track(&self, kind: TrackKind) -> Option<f32> {
+        match kind {
+            TrackKind::Zoom => Some(self.scale),
+            TrackKind::Palette => Some(self.palette_phase),
+            TrackKind::CenterX => Some(self.center_re),
+            TrackKind::CenterY => Some(self.center_im),
+            TrackKind::Rotation => Some(self.rotation),
+            TrackKind::JuliaRe => self.c_re,
+            TrackKind::JuliaIm => self.c_im,
+        }
+    }
+
+    fn csv_row(&self) -> String {
+        let optional = |v: Option<f32>| v.map(|v| v.to_string()).unwrap_or_default();
+        format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            self.frame,
+            self.time,
+            self.scale,
+            self.center_re,
+            self.center_im,
+            self.rotation,
+            self.palette_phase,
+            self.trap_r,
+            self.trap_g,
+            self.trap_b,
+            optional(self.c_re),
+            optional(self.c_im),
+            self.max_iter
+        )
+    }
+}
+
+/// Every frame of an export, sampled the way the exporter samples it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BakedAnimation {
+    /// Frames per second the frames were sampled at; 0 when unknown.
+    pub fps: u32,
+    pub frames: Vec<BakedFrame>,
+}
+
+/// Samples each frame of `proj`'s export at `fps`, including the script, exactly as
+/// [`crate::export_video_blocking`] evaluates them before rendering.
+pub fn bake_animation(proj: &Project, fps: u32) -> Result<BakedAnimation, BakeError> {
+    let mut proj = proj.clone();
+    proj.export.fps = fps.max(1);
+    let mut frames = Vec::new();
+    for_each_export_frame(&proj, |frame, time, eval| {
+        frames.push(BakedFrame::new(frame, time, eval));
+    })?;
+    Ok(BakedAnimation {
+        fps: proj.export.fps,
+        frames,
+    })
+}
+
+/// Calls `visit` with the index, time and parameters of each frame of `proj`'s export,
+/// script included, as the exporter evaluates them before rendering.
+pub fn for_each_export_frame(
+    proj: &Project,
+    mut visit: impl FnMut(u32, f32, &EvaluatedFrame),
+) -> Result<(), ScriptError> {
+    let script = ScriptRunner::for_project(proj)?;
+    for frame in 0..proj.export.frame_count() {
+        let time = frame_time(frame, proj.export.fps);
+        let mut eval = evaluate_export_frame(proj, frame);
+        if let Some(script) = &script {
+            script.apply(time, &mut eval)?;
+        }
+        visit(frame, time, &eval);
+    }
+    Ok(())
+}
+
+impl BakedAnimation {
+    /// A header row naming [`BAKE_COLUMNS`], then one row per frame. Values are written
+    /// in full, so reading them back gives the same `f32`s.
+    pub fn to_csv(&self) -> String {
+        let mut out = BAKE_COLUMNS.join(",");
+        out.push('\n');
+        for frame in &self.frames {
+            out.push_str(&frame.csv_row());
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Reads CSV with a header row. Columns are matched by name and may come in any
+    /// order; unknown columns are skipped and only `c` and the informational ones may be
+    /// missing or empty.
+    pub fn from_csv(text: &str) -> Result<Self, BakeError> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty());
+        let Some((header_line, header)) = lines.next() else {
+            return Err(BakeError::Empty);
+        };
+        let header: Vec<&str> = header.split(',').map(str::trim).collect();
+        let columns: Vec<Option<usize>> = BAKE_COLUMNS
+            .iter()
+            .map(|name| header.iter().position(|h| h.eq_ignore_ascii_case(name)))
+            .collect();
+        if let Some(missing) = (0..REQUIRED_COLUMNS).find(|&i| columns[i].is_none()) {
+            return Err(BakeError::Parse {
+                line: header_line + 1,
+                message: format!("missing the {} column", BAKE_COLUMNS[missing]),
+            });
+        }
+
+        let mut frames = Vec::new();
+        for (n, line) in lines {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let bad = |message: String| BakeError::Parse {
+                line: n + 1,
+                message,
+            };
+            let value = |i: usize| -> Result<Option<f32>, BakeError> {
+                let Some(column) = columns[i] else {
+                    return Ok(None);
+                };
+                let field = fields.get(column).copied().unwrap_or_default();
+                match field.parse::<f32>() {
+                    Ok(v) if v.is_finite() => Ok(Some(v)),
+                    _ => Err(bad(format!(
+                        "{} is not a number: {field:?}",
+                        BAKE_COLUMNS[i]
+                    ))),
+                }
+            };
+            let number = |i: usize| value(i).map(Option::unwrap_or_default);
+            let optional = |i: usize| match columns[i].and_then(|c| fields.get(c)) {
+                Some(field) if !field.is_empty() => value(i),
+                _ => Ok(None),
+            };
+            let frame = number(0)?;
+            if frame < 0.0 || frame.fract() != 0.0 {
+                return Err(bad(format!("frame is not a frame number: {frame}")));
+            }
+            let max_iter = match columns[12].and_then(|c| fields.get(c)) {
+                Some(field) => field
+                    .parse()
+                    .map_err(|_| bad(format!("max_iter is not an iteration count: {field:?}")))?,
+                None => 0,
+            };
+            let time = number(1)?;
+            if frames
+                .last()
+                .is_some_and(|prev: &BakedFrame| prev.time >= time)
+            {
+                return Err(bad("times must increase from row to row".into()));
+            }
+            frames.push(BakedFrame {
+                frame: frame as u32,
+                time,
+                scale: number(2)?,
+                center_re: number(3)?,
+                center_im: number(4)?,
+                rotation: number(5)?,
+                palette_phase: number(6)?,
+                trap_r: number(7)?,
+                trap_g: number(8)?,
+                trap_b: number(9)?,
+                c_re: optional(10)?,
+                c_im: optional(11)?,
+                max_iter,
+            });
+        }
+        // CSV carries no frame rate; any frame past the first gives it.
+        let fps = frames
+            .iter()
+            .find(|f| f.frame > 0 && f.time > 0.0)
+            .map_or(0, |f| {
+                (f64::from(f.frame) / f64::from(f.time)).round() as u32
+            });
+        Ok(Self { fps, frames })
+    }
+
+    /// Writes CSV or JSON, picked by `path`'s extension.
+    pub fn save(&self, path: &Path) -> Result<(), BakeError> {
+        let text = match BakeFormat::of(path) {
+            BakeFormat::Csv => self.to_csv(),
+            BakeFormat::Json => serde_json::to_string_pretty(self)?,
+        };
+        fs::write(path, text)?;
+        Ok(())
+    }
+
+    /// Reads CSV or JSON, picked by `path`'s extension.
+    pub fn load(path: &Path) -> Result<Self, BakeError> {
+        let text = fs::read_to_string(path)?;
+        match BakeFormat::of(path) {
+            BakeFormat::Csv => Self::from_csv(&text),
+            BakeFormat::Json => Ok(serde_json::from_str(&text)?),
+        }
+    }
+
+    /// Replaces the keyframes of every baked parameter with one Linear key per frame, so
+    /// the project plays back these values. Whatever would move the parameters on top of
+    /// the keys is turned off; returns what the user should know about.
+    pub fn apply(&self, proj: &mut Project) -> Result<Vec<String>, BakeError> {
+        let (Some(first), Some(last)) = (self.frames.first(), self.frames.last()) else {
+            return Err(BakeError::Empty);
+        };
+        let mut warnings = Vec::new();
+        let anim = &mut proj.anim;
+        for kind in TrackKind::ALL {
+            let track = anim.track_mut(kind);
+            if self.frames.iter().all(|f| f.track(kind).is_some()) {
+                track.keys = self.keys(|f| f.track(kind).unwrap_or_default());
+            } else if !track.keys.is_empty() {
+                // Old keys would play against the imported ones.
+                track.keys.clear();
+                warnings.push(format!(
+                    "the file has no {} values; that track's keys were cleared",
+                    kind.label()
+                ));
+            }
+        }
+        anim.kf_trap_color.keys = self.keys(|f| [f.trap_r, f.trap_g, f.trap_b]);
+        anim.selection = None;
+        if anim.zoom_forever.take().is_some() {
+            warnings.push("the endless zoom was replaced by the imported zoom keys".into());
+        }
+        // Keys past the end of the timeline would be clamped or wrapped away.
+        if last.time >= anim.duration {
+            let fps = if self.fps > 0 {
+                self.fps
+            } else {
+                proj.export.fps
+            };
+            anim.duration = frame_time(last.frame + 1, fps).max(last.time);
+            warnings.push(format!(
+                "the timeline was extended to {:.2} s to hold every frame",
+                anim.duration
+            ));
+        }
+        if first.time > 0.0 {
+            warnings.push(format!(
+                "the imported frames start at {:.2} s; earlier times hold the first frame",
+                first.time
+            ));
+        }
+        if proj.fractal.palette_cycle_speed != 0.0 {
+            proj.fractal.palette_cycle_speed = 0.0;
+            warnings.push("palette cycling was turned off; the palette keys include it".into());
+        }
+        if proj.script.enabled {
+            warnings.push("the frame script still runs on top of the imported keys".into());
+        }
+        Ok(warnings)
+    }
+
+    fn keys<T>(&self, value: impl Fn(&BakedFrame) -> T) -> Vec<Keyframe<T>> {
+        self.frames
+            .iter()
+            .map(|f| Keyframe {
+                t: f.time,
+                v: value(f),
+                easing: Easing::Linear,
+            })
+            .collect()
+    }
+}
+
+/// Reads a baked animation and applies it to `proj`, returning its warnings.
+pub fn import_baked_animation(proj: &mut Project, path: &Path) -> Result<Vec<String>, BakeError> {
+    BakedAnimation::load(path)?.apply(proj)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColorSpace, Complex64, EndlessZoom};
+
+    fn animated_project() -> Project {
+        let mut proj = Project::default();
+        proj.export.duration = 2.0;
+        proj.export.fps = 12;
+        let anim = &mut proj.anim;
+        anim.duration = 2.0;
+        anim.log_zoom = true;
+        anim.color_space = ColorSpace::Oklab;
+        let key = |t: f32, v: f32, easing: Easing| Keyframe { t, v, easing };
+        anim.kf_zoom.keys = vec![
+            key(0.0, 100.0, Easing::EaseInOut),
+            key(2.0, 5e4, Easing::Linear),
+        ];
+        anim.kf_center_x.keys = vec![
+            key(0.0, -0.5, Easing::SmoothStep),
+            key(1.5, -0.74, Easing::Linear),
+        ];
+        anim.kf_center_y.keys = vec![
+            key(0.25, 0.0, Easing::EaseOut),
+            key(2.0, 0.13, Easing::Linear),
+        ];
+        anim.kf_rotation.keys = vec![key(0.0, 0.0, Easing::Hold), key(1.0, 0.7, Easing::Linear)];
+        anim.kf_palette.keys = vec![key(0.0, 0.1, Easing::EaseIn), key(2.0, 0.9, Easing::Linear)];
+        anim.kf_trap_color.keys = vec![
+            Keyframe {
+                t: 0.0,
+                v: [1.0, 0.2, 0.0],
+                easing: Easing::Linear,
+            },
+            Keyframe {
+                t: 2.0,
+                v: [0.0, 0.3, 1.0],
+                easing: Easing::Linear,
+            },
+        ];
+        proj.fractal.palette_cycle_speed = 0.3;
+        proj
+    }
+
+    fn round_trip(proj: &Project, file: &str) {
+        let baked = bake_animation(proj, 24).unwrap();
+        assert_eq!(baked.frames.len(), 48);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(file);
+        baked.save(&path).unwrap();
+
+        let mut imported = proj.clone();
+        import_baked_animation(&mut imported, &path).unwrap();
+        assert_eq!(imported.anim.kf_zoom.keys.len(), 48);
+        assert_eq!(bake_animation(&imported, 24).unwrap(), baked);
+    }
+
+    #[test]
+    fn bake_import_bake_is_lossless() {
+        let proj = animated_project();
+        round_trip(&proj, "anim.csv");
+        round_trip(&proj, "anim.json");
+
+        let mut endless = animated_project();
+        endless.anim.apply_endless_zoom_preset(100.0);
+        endless.anim.zoom_forever.as_mut().unwrap().target = Some(Complex64::new(-0.75, 0.1));
+        round_trip(&endless, "endless.csv");
+        round_trip(&endless, "endless.json");
+    }
+
+    #[test]
+    fn keyed_julia_constant_round_trips() {
+        let mut proj = animated_project();
+        proj.fractal.kind = crate::FractalKind::Julia;
+        let key = |t: f32, v: f32| Keyframe {
+            t,
+            v,
+            easing: Easing::Linear,
+        };
+        proj.anim.kf_julia_re.keys = vec![key(0.0, -0.8), key(2.0, 0.285)];
+        proj.anim.kf_julia_im.keys = vec![key(0.0, 0.156), key(1.0, 0.01)];
+        round_trip(&proj, "julia.csv");
+        round_trip(&proj, "julia.json");
+
+        // The sweep comes back from the file alone.
+        let baked = bake_animation(&proj, 24).unwrap();
+        let mut fresh = proj.clone();
+        fresh.anim.kf_julia_re.keys.clear();
+        fresh.anim.kf_julia_im.keys.clear();
+        baked.apply(&mut fresh).unwrap();
+        assert_eq!(fresh.anim.kf_julia_re.keys.len(), 48);
+        assert_eq!(bake_animation(&fresh, 24).unwrap(), baked);
+
+        // Files without `c` leave no stale keys to mix with the imported ones.
+        let csv = baked
+            .to_csv()
+            .lines()
+            .map(|line| {
+                let fields: Vec<&str> = line.split(',').collect();
+                [&fields[..10], &fields[12..]].concat().join(",")
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let without_c = BakedAnimation::from_csv(&csv).unwrap();
+        assert_eq!(without_c.frames[0].c_re, None);
+        let mut imported = proj.clone();
+        let warnings = without_c.apply(&mut imported).unwrap();
+        assert!(imported.anim.kf_julia_re.keys.is_empty());
+        assert!(imported.anim.kf_julia_im.keys.is_empty());
+        assert!(
+            warnings.iter().any(|w| w.contains("Julia Re")),
+            "{warnings:?}"
+        );
+        let json = serde_json::to_string(&without_c).unwrap();
+        assert!(!json.contains("c_re"), "{json}");
+    }
+
+    #[test]
+    fn csv_columns_are_matched_by_name() {
+        let text = "time,frame,extra,scale,center_re,center_im,rotation,palette_phase,trap_r,trap_g,trap_b\n\
+                    0,0,x,100,-0.5,0,0,0.1,1,0,0\n\
+                    0.5,30,y,200,-0.6,0.1,0.2,0.3,0,1,0\n";
+        let baked = BakedAnimation::from_csv(text).unwrap();
+        assert_eq!(baked.fps, 60);
+        assert_eq!(baked.frames[1].scale, 200.0);
+        assert_eq!(baked.frames[1].trap_g, 1.0);
+        assert_eq!(baked.frames[1].max_iter, 0);
+
+        let missing = BakedAnimation::from_csv("frame,time,scale\n0,0,1\n").unwrap_err();
+        assert!(missing.to_string().contains("center_re"), "{missing}");
+        let backwards = text.replace("0.5,30", "0,30");
+        let err = BakedAnimation::from_csv(&backwards).unwrap_err();
+        assert!(err.to_string().starts_with("line 3"), "{err}");
+
+        let mut proj = Project::default();
+        let warnings = BakedAnimation::from_csv(text)
+            .unwrap()
+            .apply(&mut proj)
+            .unwrap();
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert_eq!(evaluate_export_frame(&proj, 0).camera.scale, 100.0);
+    }
+
+    #[test]
+    fn apply_reports_what_it_turned_off() {
+        let mut proj = animated_project();
+        proj.anim.zoom_forever = Some(EndlessZoom::with_defaults(1.0));
+        let baked = bake_animation(&proj, 24).unwrap();
+        let warnings = baked.apply(&mut proj).unwrap();
+        assert_eq!(warnings.len(), 2, "{warnings:?}");
+        assert!(proj.anim.zoom_forever.is_none());
+        assert_eq!(proj.fractal.palette_cycle_speed, 0.0);
+        assert!(BakedAnimation {
+            fps: 24,
+            frames: vec![]
+        }
+        .apply(&mut proj)
+        .is_err());
+    }
+}
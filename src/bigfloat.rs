@@ -0,0 +1,526 @@
+//! Fixed-point reals with as many bits as a deep zoom needs: the part of a camera center
+//! finer than `f64` resolves, and the reference orbit of perturbation rendering.
+
+use std::{
+    cmp::Ordering,
+    ops::{Add, Mul, Neg, Sub},
+};
+
+use crate::Complex64;
+
+/// Limbs of integer part; magnitudes must stay below 2^64.
+const INT_LIMBS: usize = 2;
+
+/// A real number as sign and fixed-point magnitude: two 32-bit limbs of integer part and
+/// any number of fraction limbs. Numbers with different fraction lengths mix freely, and
+/// results keep the longer one. Products are truncated toward zero.
+#[derive(Debug, Clone, Default)]
+pub struct BigFloat {
+    negative: bool,
+    /// Magnitude, least significant limb first; the last [`INT_LIMBS`] are the integer
+    /// part. Empty is zero.
+    limbs: Vec<u32>,
+}
+
+impl BigFloat {
+    pub fn zero() -> Self {
+        Self::default()
+    }
+
+    /// Fraction limbs that place a point to well under a pixel at `scale` pixels per unit.
+    pub fn limbs_for_scale(scale: f64) -> usize {
+        let bits = scale.max(1.0).log2().min(4096.0) + 64.0;
+        (bits / 32.0).ceil() as usize
+    }
+
+    /// `x` exactly, if `frac_limbs` hold its fraction bits, else truncated. Non-finite
+    /// values and magnitudes of 2^64 or more give zero.
+    pub fn from_f64(x: f64, frac_limbs: usize) -> Self {
+        let len = frac_limbs + INT_LIMBS;
+        let mut limbs = vec![0u32; len];
+        if !x.is_finite() || x.abs() >= 2f64.powi(64) {
+            return Self::zero();
+        }
+        let bits = x.abs().to_bits();
+        let exponent = ((bits >> 52) & 0x7ff) as i64;
+        let (mut mantissa, exponent) = if exponent == 0 {
+            (bits & ((1 << 52) - 1), -1074)
+        } else {
+            ((bits & ((1 << 52) - 1)) | (1 << 52), exponent - 1075)
+        };
+        // The magnitude in units of the last fraction limb's lowest bit.
+        let mut shift = exponent + 32 * frac_limbs as i64;
+        if shift < 0 {
+            mantissa = if shift <= -64 { 0 } else { mantissa >> -shift };
+            shift = 0;
+        }
+        let wide = u128::from(mantissa) << (shift % 32);
+        let first = (shift / 32) as usize;
+        for (i, limb) in limbs.iter_mut().enumerate().skip(first).take(3) {
+            *limb = (wide >> (32 * (i - first))) as u32;
+        }
+        Self {
+            negative: x < 0.0,
+            limbs,
+        }
+        .trimmed()
+    }
+
+    /// The nearest `f64`.
+    pub fn to_f64(&self) -> f64 {
+        let Some(top) = self.limbs.iter().rposition(|&limb| limb != 0) else {
+            return 0.0;
+        };
+        // The top three limbs hold more than an `f64`'s bits; any below only break ties.
+        let low = top.saturating_sub(2);
+        let mut wide = 0u128;
+        for &limb in self.limbs[low..=top].iter().rev() {
+            wide = wide << 32 | u128::from(limb);
+        }
+        wide <<= 32 * (2 - (top - low));
+        if self.limbs[..low].iter().any(|&limb| limb != 0) {
+            wide |= 1;
+        }
+        let exponent = 32 * (top as i32 - 2 - self.frac_limbs() as i32);
+        // In two steps, as 2^exponent alone may underflow where the value does not.
+        let value = wide as f64 * 2f64.powi(exponent / 2) * 2f64.powi(exponent - exponent / 2);
+        if self.negative {
+            -value
+        } else {
+            value
+        }
+    }
+
+    /// Fraction limbs this number carries.
+    pub fn frac_limbs(&self) -> usize {
+        self.limbs.len().saturating_sub(INT_LIMBS)
+    }
+
+    /// This number with `frac_limbs` fraction limbs, truncating or extending with zeros.
+    pub fn with_frac_limbs(&self, frac_limbs: usize) -> Self {
+        let frac = self.frac_limbs();
+        let mut limbs = vec![0u32; frac_limbs + INT_LIMBS];
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            if let Some(&own) = (i + frac)
+                .checked_sub(frac_limbs)
+                .and_then(|j| self.limbs.get(j))
+            {
+                *limb = own;
+            }
+        }
+        Self {
+            negative: self.negative,
+            limbs,
+        }
+        .trimmed()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.iter().all(|&limb| limb == 0)
+    }
+
+    /// Parses a decimal such as `-0.7436438870371587` or `1.5e-7`, rounded to the nearest
+    /// number with `frac_limbs` fraction limbs. `None` if `text` is not a decimal or its
+    /// magnitude is 2^64 or more.
+    pub fn parse_decimal(text: &str, frac_limbs: usize) -> Option<Self> {
+        let (negative, int_digits, frac_digits) = split_decimal(text)?;
+        let int_digits = int_digits.trim_start_matches('0');
+        if int_digits.len() > 20 {
+            return None;
+        }
+        let int = if int_digits.is_empty() {
+            0
+        } else {
+            int_digits.parse::<u64>().ok()?
+        };
+        // Digits past these cannot move the rounding.
+        let frac_digits = &frac_digits[..frac_digits.len().min(frac_limbs * 10 + 20)];
+
+        // Horner from the last digit, (digit + f) / 10 each step, with a guard limb.
+        let mut frac = vec![0u32; frac_limbs + 1];
+        for digit in frac_digits.bytes().rev() {
+            let mut rem = u64::from(digit - b'0');
+            for limb in frac.iter_mut().rev() {
+                let cur = (rem << 32) | u64::from(*limb);
+                *limb = (cur / 10) as u32;
+                rem = cur % 10;
+            }
+        }
+        let round_up = frac[0] >= 1 << 31;
+        let mut limbs = frac[1..].to_vec();
+        limbs.extend([int as u32, (int >> 32) as u32]);
+        if round_up {
+            let mut ulp = vec![0u32; limbs.len()];
+            ulp[0] = 1;
+            if limbs.iter().all(|&limb| limb == u32::MAX) {
+                return None;
+            }
+            limbs = add_limbs(&limbs, &ulp);
+        }
+        Some(Self { negative, limbs }.trimmed())
+    }
+
+    /// The shortest decimal that [`BigFloat::parse_decimal`] reads back as this number
+    /// at its own precision, without an exponent.
+    pub fn to_decimal(&self) -> String {
+        let frac_limbs = self.frac_limbs();
+        let padded = self.with_frac_limbs(frac_limbs).padded(frac_limbs);
+        let int = u64::from(padded[frac_limbs]) | u64::from(padded[frac_limbs + 1]) << 32;
+        // Enough digits to place any number to within half a fraction limb's last bit.
+        let max_digits = (frac_limbs as f64 * 32.0 * std::f64::consts::LOG10_2).ceil() as usize + 2;
+        let mut frac = padded[..frac_limbs].to_vec();
+        let mut digits = Vec::with_capacity(max_digits + 1);
+        while digits.len() <= max_digits && frac.iter().any(|&limb| limb != 0) {
+            let mut carry = 0u64;
+            for limb in &mut frac {
+                let cur = u64::from(*limb) * 10 + carry;
+                *limb = cur as u32;
+                carry = cur >> 32;
+            }
+            digits.push(carry as u8);
+        }
+        let sign = if self.negative { "-" } else { "" };
+        for len in 0..=digits.len() {
+            let (int, kept) = round_digits(int, &digits, len);
+            let text = match kept.is_empty() {
+                true => format!("{sign}{int}"),
+                false => {
+                    let kept: String = kept.iter().map(|&d| char::from(b'0' + d)).collect();
+                    format!("{sign}{int}.{kept}")
+                }
+            };
+            if len == digits.len() || Self::parse_decimal(&text, frac_limbs).as_ref() == Some(self)
+            {
+                return text;
+            }
+        }
+        unreachable!("the last length keeps every digit")
+    }
+
+    /// Both magnitudes with the same number of limbs.
+    fn aligned(&self, other: &Self) -> (Vec<u32>, Vec<u32>, usize) {
+        let frac = self.frac_limbs().max(other.frac_limbs());
+        (
+            self.with_frac_limbs(frac).padded(frac),
+            other.with_frac_limbs(frac).padded(frac),
+            frac,
+        )
+    }
+
+    fn padded(mut self, frac: usize) -> Vec<u32> {
+        self.limbs.resize(frac + INT_LIMBS, 0);
+        self.limbs
+    }
+
+    /// Zero is never negative.
+    fn trimmed(mut self) -> Self {
+        if self.is_zero() {
+            self.negative = false;
+        }
+        self
+    }
+
+    fn signed_sum(&self, other: &Self, other_negative: bool) -> Self {
+        let (a, b, _) = self.aligned(other);
+        if self.negative == other_negative {
+            return Self {
+                negative: self.negative,
+                limbs: add_limbs(&a, &b),
+            }
+            .trimmed();
+        }
+        match cmp_limbs(&a, &b) {
+            Ordering::Less => Self {
+                negative: other_negative,
+                limbs: sub_limbs(&b, &a),
+            },
+            _ => Self {
+                negative: self.negative,
+                limbs: sub_limbs(&a, &b),
+            },
+        }
+        .trimmed()
+    }
+}
+
+/// Sign, integer digits and fraction digits of a decimal with an optional exponent, the
+/// point moved by the exponent.
+fn split_decimal(text: &str) -> Option<(bool, String, String)> {
+    let text = text.trim();
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let (mantissa, exponent) = match text.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<i32>().ok()?),
+        None => (text, 0),
+    };
+    let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if int.len() + frac.len() == 0 || !all_digits(int) || !all_digits(frac) {
+        return None;
+    }
+    // Exponents past these only shift the digits out of range either way.
+    let exponent = exponent.clamp(-100_000, 100);
+    let digits = format!("{int}{frac}");
+    let point = int.len() as i64 + i64::from(exponent);
+    Some(if point <= 0 {
+        let zeros = "0".repeat(point.unsigned_abs() as usize);
+        (negative, String::new(), format!("{zeros}{digits}"))
+    } else if point as usize >= digits.len() {
+        let zeros = "0".repeat(point as usize - digits.len());
+        (negative, format!("{digits}{zeros}"), String::new())
+    } else {
+        let (int, frac) = digits.split_at(point as usize);
+        (negative, int.to_owned(), frac.to_owned())
+    })
+}
+
+/// `int` and the first `len` of the fraction `digits`, rounded half up on the next one.
+fn round_digits(int: u64, digits: &[u8], len: usize) -> (u128, Vec<u8>) {
+    let mut kept = digits[..len].to_vec();
+    let mut int = u128::from(int);
+    if digits.get(len).is_some_and(|&next| next >= 5) {
+        let mut carry = true;
+        for d in kept.iter_mut().rev() {
+            if *d == 9 {
+                *d = 0;
+            } else {
+                *d += 1;
+                carry = false;
+                break;
+            }
+        }
+        if carry {
+            int += 1;
+        }
+    }
+    while kept.last() == Some(&0) {
+        kept.pop();
+    }
+    (int, kept)
+}
+
+fn cmp_limbs(a: &[u32], b: &[u32]) -> Ordering {
+    a.iter().rev().cmp(b.iter().rev())
+}
+
+/// `a + b`; a carry out of the integer part is dropped.
+fn add_limbs(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut carry = 0u64;
+    a.iter()
+        .zip(b)
+        .map(|(&x, &y)| {
+            let sum = u64::from(x) + u64::from(y) + carry;
+            carry = sum >> 32;
+            sum as u32
+        })
+        .collect()
+}
+
+/// `a - b` for `a >= b`.
+fn sub_limbs(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut borrow = 0i64;
+    a.iter()
+        .zip(b)
+        .map(|(&x, &y)| {
+            let mut diff = i64::from(x) - i64::from(y) - borrow;
+            borrow = i64::from(diff < 0);
+            if diff < 0 {
+                diff += 1 << 32;
+            }
+            diff as u32
+        })
+        .collect()
+}
+
+impl PartialEq for BigFloat {
+    fn eq(&self, other: &Self) -> bool {
+        let (a, b, _) = self.aligned(other);
+        a == b && (self.negative == other.negative || self.is_zero())
+    }
+}
+
+impl Add for &BigFloat {
+    type Output = BigFloat;
+
+    fn add(self, other: &BigFloat) -> BigFloat {
+        self.signed_sum(other, other.negative)
+    }
+}
+
+impl Sub for &BigFloat {
+    type Output = BigFloat;
+
+    fn sub(self, other: &BigFloat) -> BigFloat {
+        self.signed_sum(other, !other.negative)
+    }
+}
+
+impl Neg for &BigFloat {
+    type Output = BigFloat;
+
+    fn neg(self) -> BigFloat {
+        BigFloat {
+            negative: !self.negative,
+            limbs: self.limbs.clone(),
+        }
+        .trimmed()
+    }
+}
+
+impl Mul for &BigFloat {
+    type Output = BigFloat;
+
+    fn mul(self, other: &BigFloat) -> BigFloat {
+        let (a, b, frac) = self.aligned(other);
+        let len = a.len();
+        // The full product has 2 * frac fraction limbs; keep the top `frac` of them.
+        let mut product = vec![0u64; 2 * len + 1];
+        for (i, &x) in a.iter().enumerate() {
+            if x == 0 {
+                continue;
+            }
+            let mut carry = 0u64;
+            for (j, &y) in b.iter().enumerate() {
+                let t = product[i + j] + u64::from(x) * u64::from(y) + carry;
+                product[i + j] = t & 0xffff_ffff;
+                carry = t >> 32;
+            }
+            product[i + len] = carry;
+        }
+        BigFloat {
+            negative: self.negative != other.negative,
+            limbs: product[frac..frac + len]
+                .iter()
+                .map(|&l| l as u32)
+                .collect(),
+        }
+        .trimmed()
+    }
+}
+
+/// A complex number of [`BigFloat`]s.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BigComplex {
+    pub re: BigFloat,
+    pub im: BigFloat,
+}
+
+impl BigComplex {
+    pub fn new(re: BigFloat, im: BigFloat) -> Self {
+        Self { re, im }
+    }
+
+    /// `c` with `frac_limbs` fraction limbs; see [`BigFloat::from_f64`].
+    pub fn from_complex64(c: Complex64, frac_limbs: usize) -> Self {
+        Self::new(
+            BigFloat::from_f64(c.re, frac_limbs),
+            BigFloat::from_f64(c.im, frac_limbs),
+        )
+    }
+
+    /// Rounded to `f64`.
+    pub fn to_complex64(&self) -> Complex64 {
+        Complex64::new(self.re.to_f64(), self.im.to_f64())
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.re.is_zero() && self.im.is_zero()
+    }
+
+    pub fn with_frac_limbs(&self, frac_limbs: usize) -> Self {
+        Self::new(
+            self.re.with_frac_limbs(frac_limbs),
+            self.im.with_frac_limbs(frac_limbs),
+        )
+    }
+}
+
+impl Add for &BigComplex {
+    type Output = BigComplex;
+
+    fn add(self, other: &BigComplex) -> BigComplex {
+        BigComplex::new(&self.re + &other.re, &self.im + &other.im)
+    }
+}
+
+impl Sub for &BigComplex {
+    type Output = BigComplex;
+
+    fn sub(self, other: &BigComplex) -> BigComplex {
+        BigComplex::new(&self.re - &other.re, &self.im - &other.im)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn f64_values_round_trip_exactly() {
+        for x in [0.0, 1.0, -0.75, 0.1, -1.25e-9, 3.5e12, -2.5e-200] {
+            let big = BigFloat::from_f64(x, 40);
+            assert_eq!(big.to_f64(), x, "{x}");
+        }
+        // Below the last fraction bit rounds toward zero.
+        assert!(BigFloat::from_f64(1e-30, 2).is_zero());
+        assert!(BigFloat::from_f64(f64::NAN, 4).is_zero());
+    }
+
+    #[test]
+    fn arithmetic_keeps_bits_f64_loses() {
+        let frac = 8;
+        let one = BigFloat::from_f64(1.0, frac);
+        let tiny = BigFloat::from_f64(1e-40, frac);
+        let sum = &one + &tiny;
+        assert_eq!(sum.to_f64(), 1.0);
+        assert_eq!((&sum - &one).to_f64(), 1e-40);
+        assert_eq!((&tiny - &one).to_f64(), -1.0);
+        assert_eq!((&(&tiny - &one) + &one).to_f64(), 1e-40);
+
+        let a = BigFloat::from_f64(-1.5, frac);
+        let b = BigFloat::from_f64(2.25, frac);
+        assert_eq!((&a * &b).to_f64(), -3.375);
+        assert_eq!((&a * &a).to_f64(), 2.25);
+        let product = (&(&a * &tiny) * &b).to_f64();
+        assert!((product / -3.375e-40 - 1.0).abs() < 1e-15);
+        assert_eq!(-&a, BigFloat::from_f64(1.5, 2));
+        assert_eq!(&a + &BigFloat::from_f64(1.5, 3), BigFloat::zero());
+    }
+
+    #[test]
+    fn decimals_round_trip_at_their_precision() {
+        for text in [
+            "0",
+            "-0.5",
+            "3",
+            "0.1",
+            "-12.000125",
+            "0.000000000000000000000000003",
+        ] {
+            let big = BigFloat::parse_decimal(text, 4).unwrap();
+            assert_eq!(big.to_decimal(), text);
+        }
+        let digits = "0.1234567890123456789012345678901234567891";
+        let big = BigFloat::parse_decimal(digits, 6).unwrap();
+        assert_eq!(big.to_decimal(), digits);
+        assert_eq!(big.to_f64(), 0.123_456_789_012_345_68);
+        assert_eq!(
+            BigFloat::parse_decimal("-1.5e-3", 2).unwrap().to_f64(),
+            -0.0015
+        );
+        assert_eq!(BigFloat::parse_decimal("25e1", 2).unwrap().to_f64(), 250.0);
+        for bad in [
+            "",
+            ".",
+            "1.2.3",
+            "0x10",
+            "NaN",
+            "1e",
+            "99999999999999999999999",
+        ] {
+            assert!(BigFloat::parse_decimal(bad, 2).is_none(), "{bad}");
+        }
+    }
+}
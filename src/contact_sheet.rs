@@ -0,0 +1,322 @@
+//! Contact sheets: one image of frames spread evenly over an animation, in a grid with
+//! each frame's time under it, to look over before committing to a full export.
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    fs,
+    io::{self, BufWriter, Write},
+    path::Path,
+    sync::atomic::Ordering as AtomicOrdering,
+    time::Instant,
+};
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{
+    check_frame_size, export_views, frame_time, render_image_stats, render_side_by_side, text_size,
+    Camera, EvaluatedFrame, ExportError, ExportProgress, ExportReport, Project, Renderer,
+    ScriptRunner, TextStamp,
+};
+
+/// Pixels between cells and around the sheet.
+pub const CONTACT_SHEET_GUTTER: u32 = 8;
+/// Side of one glyph pixel of the labels.
+pub const CONTACT_SHEET_LABEL_DOT: u32 = 2;
+/// Height of the label strip under each cell.
+pub const CONTACT_SHEET_LABEL_HEIGHT: u32 = 7 * CONTACT_SHEET_LABEL_DOT + 8;
+
+#[cfg(not(target_arch = "wasm32"))]
+const SHEET_BACKGROUND: [u8; 4] = [24, 24, 28, 255];
+#[cfg(not(target_arch = "wasm32"))]
+const LABEL_COLOR: [u8; 3] = [220, 220, 220];
+
+/// Layout of a contact sheet: a `cols`×`rows` grid of `cell`-sized frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ContactSheet {
+    pub cols: u32,
+    pub rows: u32,
+    /// Width and height of one frame. Frames are framed like the export scaled down to
+    /// the cell width.
+    pub cell: (u32, u32),
+}
+
+impl Default for ContactSheet {
+    fn default() -> Self {
+        Self {
+            cols: 6,
+            rows: 4,
+            cell: (480, 270),
+        }
+    }
+}
+
+impl ContactSheet {
+    /// Frames the grid holds.
+    pub fn cells(&self) -> u32 {
+        self.cols.saturating_mul(self.rows)
+    }
+
+    /// Size of the whole sheet, gutters and labels included; `None` if it overflows.
+    pub fn size(&self) -> Option<(u32, u32)> {
+        let (cols, rows) = (u64::from(self.cols), u64::from(self.rows));
+        let gutter = u64::from(CONTACT_SHEET_GUTTER);
+        let width = cols * u64::from(self.cell.0) + (cols + 1) * gutter;
+        let height = rows * (u64::from(self.cell.1) + u64::from(CONTACT_SHEET_LABEL_HEIGHT))
+            + (rows + 1) * gutter;
+        Some((width.try_into().ok()?, height.try_into().ok()?))
+    }
+
+    /// Top-left corner of cell `index`, counted along the rows.
+    pub fn cell_origin(&self, index: u32) -> (u32, u32) {
+        let (col, row) = (index % self.cols.max(1), index / self.cols.max(1));
+        (
+            CONTACT_SHEET_GUTTER + col * (self.cell.0 + CONTACT_SHEET_GUTTER),
+            CONTACT_SHEET_GUTTER
+                + row * (self.cell.1 + CONTACT_SHEET_LABEL_HEIGHT + CONTACT_SHEET_GUTTER),
+        )
+    }
+
+    /// Frames of an animation of `total` frames shown in the cells, from the first to the
+    /// last evenly spaced. Shorter animations show every frame and leave the rest empty.
+    pub fn frames(&self, total: u32) -> Vec<u32> {
+        let count = self.cells();
+        if total <= count {
+            return (0..total).collect();
+        }
+        if count == 1 {
+            return vec![0];
+        }
+        let (last, spans) = (u64::from(total - 1), u64::from(count - 1));
+        (0..u64::from(count))
+            .map(|i| ((i * last + spans / 2) / spans) as u32)
+            .collect()
+    }
+}
+
+/// Reads a `WIDTHxHEIGHT` size such as `480x270`, as `contact-sheet --cell` takes.
+pub fn parse_cell_size(text: &str) -> Result<(u32, u32), String> {
+    let invalid = || format!("expected WIDTHxHEIGHT, like 480x270, not \"{text}\"");
+    let (w, h) = text.split_once(['x', 'X', '×']).ok_or_else(invalid)?;
+    match (w.trim().parse::<u32>(), h.trim().parse::<u32>()) {
+        (Ok(w), Ok(h)) if w > 0 && h > 0 => Ok((w, h)),
+        _ => Err(invalid()),
+    }
+}
+
+/// `seconds` as minutes, seconds and hundredths, e.g. `1:02.50`.
+pub fn format_timestamp(seconds: f32) -> String {
+    let hundredths = (f64::from(seconds.max(0.0)) * 100.0).round() as u64;
+    format!(
+        "{}:{:02}.{:02}",
+        hundredths / 6000,
+        hundredths / 100 % 60,
+        hundredths % 100
+    )
+}
+
+/// Label under the cell of `frame`: its time, and its number if the cell is wide enough.
+#[cfg(not(target_arch = "wasm32"))]
+fn cell_label(frame: u32, fps: u32, cell_width: u32) -> String {
+    let time = format_timestamp(frame_time(frame, fps));
+    let long = format!("{time}  frame {frame}");
+    if text_size(&long, CONTACT_SHEET_LABEL_DOT).0 <= cell_width {
+        long
+    } else {
+        time
+    }
+}
+
+/// `eval` with its camera scaled from the export width to `width` pixels.
+#[cfg(not(target_arch = "wasm32"))]
+fn scaled(eval: &EvaluatedFrame, proj: &Project, width: u32) -> EvaluatedFrame {
+    EvaluatedFrame {
+        camera: Camera {
+            scale: eval.camera.scale * f64::from(width) / f64::from(proj.export.width.max(1)),
+            ..eval.camera.clone()
+        },
+        ..eval.clone()
+    }
+}
+
+/// Renders the frames [`ContactSheet::frames`] picks from `proj`'s export to a PNG at
+/// `path`, evaluated as [`crate::export_video_blocking`] evaluates them. The sheet is
+/// written one row of cells at a time, so only that row is in memory however large the
+/// sheet. `progress` counts cells and can cancel between them.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_contact_sheet(
+    proj: &Project,
+    sheet: &ContactSheet,
+    path: &Path,
+    progress: &ExportProgress,
+    renderer: &mut dyn Renderer,
+) -> Result<ExportReport, ExportError> {
+    let started = Instant::now();
+    let proj = &proj.authored();
+    let sheet = ContactSheet {
+        cols: sheet.cols.max(1),
+        rows: sheet.rows.max(1),
+        cell: (sheet.cell.0.max(1), sheet.cell.1.max(1)),
+    };
+    let size = sheet.size().unwrap_or((u32::MAX, u32::MAX));
+    check_frame_size(size)?;
+    let frames = sheet.frames(proj.export.frame_count());
+    progress.total.store(frames.len(), AtomicOrdering::Relaxed);
+    let script = ScriptRunner::for_project(proj)?;
+
+    let mut encoder = png::Encoder::new(BufWriter::new(fs::File::create(path)?), size.0, size.1);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(io::Error::from)?;
+    let mut stream = writer.stream_writer().map_err(io::Error::from)?;
+    let row_width = size.0 as usize * 4;
+    let band_height = CONTACT_SHEET_GUTTER + sheet.cell.1 + CONTACT_SHEET_LABEL_HEIGHT;
+    for row in 0..sheet.rows {
+        // The gutter above the row, its cells and their labels.
+        let top = row * band_height;
+        let band = top..top + band_height;
+        let mut pixels = SHEET_BACKGROUND.repeat(size.0 as usize * band_height as usize);
+        let first = (row * sheet.cols) as usize;
+        for (i, &frame) in frames
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(sheet.cols as usize)
+        {
+            if progress.cancel.load(AtomicOrdering::Relaxed) {
+                return Err(ExportError::Cancelled);
+            }
+            let (main, julia) = export_views(proj, script.as_ref(), frame)?;
+            let main = scaled(&main, proj, sheet.cell.0);
+            let (cell, _) = match julia {
+                Some(julia) => {
+                    let julia = scaled(&julia, proj, sheet.cell.0);
+                    render_side_by_side(sheet.cell, &main, &julia, renderer, 0)?
+                }
+                None => render_image_stats(sheet.cell, &main.fractal, &main.camera, renderer, 0)?,
+            };
+            let (x, y) = sheet.cell_origin(i as u32);
+            let cell_row = sheet.cell.0 as usize * 4;
+            for (cy, src) in cell.chunks_exact(cell_row).enumerate() {
+                let at = (y - top) as usize + cy;
+                let start = at * row_width + x as usize * 4;
+                pixels[start..start + cell_row].copy_from_slice(src);
+            }
+            TextStamp {
+                text: cell_label(frame, proj.export.fps, sheet.cell.0),
+                origin: (
+                    x as i32,
+                    (y + sheet.cell.1 + CONTACT_SHEET_GUTTER / 2) as i32,
+                ),
+                dot: CONTACT_SHEET_LABEL_DOT,
+                color: LABEL_COLOR,
+                alpha: 255,
+            }
+            .draw(&mut pixels, size, band.clone());
+            progress.done.store(i + 1, AtomicOrdering::Relaxed);
+        }
+        stream.write_all(&pixels)?;
+    }
+    stream.write_all(&SHEET_BACKGROUND.repeat(size.0 as usize * CONTACT_SHEET_GUTTER as usize))?;
+    stream.finish().map_err(io::Error::from)?;
+    writer.finish().map_err(io::Error::from)?;
+
+    Ok(ExportReport {
+        path: path.to_path_buf(),
+        bytes: fs::metadata(path)?.len(),
+        elapsed: started.elapsed(),
+        passes: None,
+        reused_frames: 0,
+        poster: None,
+        calibration: None,
+        refined_pixels: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Complex, CpuRenderer, ExportSettings, FractalKind, FractalParams};
+
+    #[test]
+    fn cells_spread_from_the_first_frame_to_the_last() {
+        let sheet = ContactSheet {
+            cols: 3,
+            rows: 2,
+            cell: (40, 30),
+        };
+        assert_eq!(sheet.frames(101), vec![0, 20, 40, 60, 80, 100]);
+        assert_eq!(sheet.frames(4), vec![0, 1, 2, 3]);
+        assert_eq!(sheet.frames(0), Vec::<u32>::new());
+        let single = ContactSheet {
+            cols: 1,
+            rows: 1,
+            ..sheet
+        };
+        assert_eq!(single.frames(50), vec![0]);
+
+        assert_eq!(sheet.size(), Some((3 * 40 + 4 * 8, 2 * (30 + 22) + 3 * 8)));
+        assert_eq!(sheet.cell_origin(0), (8, 8));
+        assert_eq!(sheet.cell_origin(4), (8 + 48, 8 + 60));
+        let huge = ContactSheet {
+            cols: u32::MAX,
+            ..sheet
+        };
+        assert_eq!(huge.size(), None);
+    }
+
+    #[test]
+    fn timestamps_read_minutes_seconds_and_hundredths() {
+        assert_eq!(format_timestamp(0.0), "0:00.00");
+        assert_eq!(format_timestamp(3.25), "0:03.25");
+        assert_eq!(format_timestamp(62.5), "1:02.50");
+        assert_eq!(format_timestamp(-1.0), "0:00.00");
+    }
+
+    #[test]
+    fn cell_sizes_parse_as_width_by_height() {
+        assert_eq!(parse_cell_size("480x270"), Ok((480, 270)));
+        assert_eq!(parse_cell_size("64×48"), Ok((64, 48)));
+        assert!(parse_cell_size("480").is_err());
+        assert!(parse_cell_size("0x270").is_err());
+    }
+
+    #[test]
+    fn sheet_holds_each_cell_as_the_export_frames_it() {
+        let mut proj = Project::new("sheet")
+            .with_fractal(FractalParams::new(FractalKind::Mandelbrot).with_max_iter(60))
+            .with_camera(Camera::new(Complex::new(-0.5, 0.0), 40.0));
+        proj.export = ExportSettings::new(128, 96).with_fps(4).with_duration(2.0);
+        proj.anim.duration = 2.0;
+        proj.anim.kf_zoom.upsert(0.0, 40.0);
+        proj.anim.kf_zoom.upsert(2.0, 400.0);
+        let sheet = ContactSheet {
+            cols: 3,
+            rows: 2,
+            cell: (64, 48),
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sheet.png");
+        let progress = ExportProgress::default();
+        write_contact_sheet(&proj, &sheet, &path, &progress, &mut CpuRenderer).unwrap();
+        assert_eq!(progress.fraction(), (6, 6));
+
+        let image = image::open(&path).unwrap().to_rgba8();
+        assert_eq!((image.width(), image.height()), sheet.size().unwrap());
+        // The last cell is the last frame, rendered at half the export width.
+        let (x, y) = sheet.cell_origin(5);
+        let (main, _) = export_views(&proj, None, 7).unwrap();
+        let main = scaled(&main, &proj, 64);
+        let (expected, _) =
+            render_image_stats((64, 48), &main.fractal, &main.camera, &mut CpuRenderer, 0).unwrap();
+        let cell = image::imageops::crop_imm(&image, x, y, 64, 48).to_image();
+        assert_eq!(cell.into_raw(), expected);
+        // Its label is drawn under it.
+        let label = (y + 48..y + 48 + CONTACT_SHEET_LABEL_HEIGHT)
+            .flat_map(|ly| (x..x + 64).map(move |lx| (lx, ly)))
+            .filter(|&(lx, ly)| image.get_pixel(lx, ly).0[..3] == LABEL_COLOR)
+            .count();
+        assert!(label > 20, "{label} label pixels");
+    }
+}
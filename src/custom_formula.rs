@@ -0,0 +1,771 @@
+//! Iteration formulas typed in for [`FractalKind::Custom`]: a small expression language
+//! over complex numbers, parsed once into an [`Expr`] and evaluated every step.
+//!
+//! `sqr(z) + c`, `z^3 - 0.5i*z + c` and `sin(z) * c` are all formulas. The variables are
+//! `z` and `c`; the constants numbers, `i`, `2.5i`, `pi` and `e`; the operators `+ - * / ^`
+//! with the usual precedence, `^` binding tightest and to the right; and the functions
+//! those in [`Func`]. Names are case-insensitive.
+
+use std::{borrow::Cow, sync::Arc};
+
+use thiserror::Error;
+
+use crate::{Complex64, FractalKind, FractalParams};
+
+/// A function of one complex argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Func {
+    /// `z·z`.
+    Sqr,
+    /// Both parts made positive, `|Re z| + i·|Im z|`, as the Burning Ship folds them.
+    Abs,
+    Conj,
+    Sin,
+    Cos,
+    Exp,
+    /// Principal natural logarithm.
+    Log,
+    /// The real part, as a real number.
+    Re,
+    /// The imaginary part, as a real number.
+    Im,
+}
+
+impl Func {
+    pub const ALL: [Func; 9] = [
+        Func::Sqr,
+        Func::Abs,
+        Func::Conj,
+        Func::Sin,
+        Func::Cos,
+        Func::Exp,
+        Func::Log,
+        Func::Re,
+        Func::Im,
+    ];
+
+    /// The name formulas call it by.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Func::Sqr => "sqr",
+            Func::Abs => "abs",
+            Func::Conj => "conj",
+            Func::Sin => "sin",
+            Func::Cos => "cos",
+            Func::Exp => "exp",
+            Func::Log => "log",
+            Func::Re => "re",
+            Func::Im => "im",
+        }
+    }
+
+    fn apply(&self, z: Complex64) -> Complex64 {
+        match self {
+            Func::Sqr => mul(z, z),
+            Func::Abs => Complex64::new(z.re.abs(), z.im.abs()),
+            Func::Conj => Complex64::new(z.re, -z.im),
+            Func::Sin => Complex64::new(z.re.sin() * z.im.cosh(), z.re.cos() * z.im.sinh()),
+            Func::Cos => Complex64::new(z.re.cos() * z.im.cosh(), -z.re.sin() * z.im.sinh()),
+            Func::Exp => exp(z),
+            Func::Log => log(z),
+            Func::Re => Complex64::new(z.re, 0.0),
+            Func::Im => Complex64::new(z.im, 0.0),
+        }
+    }
+}
+
+/// A parsed formula.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    /// The iterate.
+    Z,
+    /// The pixel.
+    C,
+    Const(Complex64),
+    Neg(Box<Expr>),
+    Add(Box<Expr>, Box<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+    Div(Box<Expr>, Box<Expr>),
+    Pow(Box<Expr>, Box<Expr>),
+    Call(Func, Box<Expr>),
+}
+
+impl Expr {
+    /// The value for iterate `z` and pixel `c`.
+    pub fn eval(&self, z: Complex64, c: Complex64) -> Complex64 {
+        match self {
+            Expr::Z => z,
+            Expr::C => c,
+            Expr::Const(k) => *k,
+            Expr::Neg(a) => {
+                let a = a.eval(z, c);
+                Complex64::new(-a.re, -a.im)
+            }
+            Expr::Add(a, b) => {
+                let (a, b) = (a.eval(z, c), b.eval(z, c));
+                Complex64::new(a.re + b.re, a.im + b.im)
+            }
+            Expr::Sub(a, b) => {
+                let (a, b) = (a.eval(z, c), b.eval(z, c));
+                Complex64::new(a.re - b.re, a.im - b.im)
+            }
+            Expr::Mul(a, b) => mul(a.eval(z, c), b.eval(z, c)),
+            Expr::Div(a, b) => div(a.eval(z, c), b.eval(z, c)),
+            Expr::Pow(base, exponent) => {
+                let base = base.eval(z, c);
+                match **exponent {
+                    // Whole powers by multiplication: exact, and far faster than exp·log.
+                    Expr::Const(n) if n.im == 0.0 && n.re.fract() == 0.0 && n.re.abs() <= 64.0 => {
+                        powi(base, n.re as i32)
+                    }
+                    _ => pow(base, exponent.eval(z, c)),
+                }
+            }
+            Expr::Call(func, a) => func.apply(a.eval(z, c)),
+        }
+    }
+
+    /// How `|z|` grows with the formula once it is large: `n` for a formula that goes as
+    /// `z^n`. `None` where it does not grow as a power, as under `exp`, or depends on `c`.
+    fn degree(&self) -> Option<f32> {
+        match self {
+            Expr::Z => Some(1.0),
+            Expr::C | Expr::Const(_) => Some(0.0),
+            Expr::Neg(a) => a.degree(),
+            Expr::Add(a, b) | Expr::Sub(a, b) => Some(a.degree()?.max(b.degree()?)),
+            Expr::Mul(a, b) => Some(a.degree()? + b.degree()?),
+            Expr::Div(a, b) => (b.degree()? == 0.0).then(|| a.degree()).flatten(),
+            Expr::Pow(base, exponent) => match (base.degree()?, &**exponent) {
+                (0.0, _) => (exponent.degree()? == 0.0).then_some(0.0),
+                (d, Expr::Const(n)) if n.im == 0.0 => Some(d * n.re as f32),
+                _ => None,
+            },
+            Expr::Call(Func::Sqr, a) => Some(2.0 * a.degree()?),
+            Expr::Call(Func::Abs | Func::Conj | Func::Re | Func::Im, a) => a.degree(),
+            Expr::Call(_, a) => (a.degree()? == 0.0).then_some(0.0),
+        }
+    }
+}
+
+/// Why a formula did not parse.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("{message} at column {column}")]
+pub struct FormulaError {
+    /// Character the problem starts at, from 1.
+    pub column: usize,
+    pub message: String,
+}
+
+/// A formula parsed from its source, ready to evaluate every step.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomFormula {
+    source: String,
+    expr: Expr,
+}
+
+impl CustomFormula {
+    pub fn parse(source: &str) -> Result<Self, FormulaError> {
+        let tokens = lex(source)?;
+        let mut parser = Parser {
+            tokens: &tokens,
+            pos: 0,
+            end: source.chars().count() + 1,
+            depth: 0,
+        };
+        let expr = parser.formula()?;
+        Ok(Self {
+            source: source.to_string(),
+            expr,
+        })
+    }
+
+    /// The text it was parsed from.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn expr(&self) -> &Expr {
+        &self.expr
+    }
+
+    /// Degree of the formula in `z` for smooth coloring, e.g. 3 for `z^3 + c`. Formulas
+    /// that do not grow as a power of `z` count as quadratic.
+    pub fn degree(&self) -> f32 {
+        self.expr
+            .degree()
+            .filter(|d| *d > 1.0 && d.is_finite())
+            .unwrap_or(2.0)
+    }
+
+    /// The next `z` after `z` for pixel `c`.
+    pub fn eval(&self, z: Complex64, c: Complex64) -> Complex64 {
+        self.expr.eval(z, c)
+    }
+}
+
+/// The compiled [`FractalParams::custom_formula`] attached to `p`, if it is current.
+pub fn attached_formula(p: &FractalParams) -> Option<&CustomFormula> {
+    p.compiled_formula
+        .as_deref()
+        .filter(|compiled| compiled.source == p.custom_formula)
+}
+
+/// Whether `p`'s custom formula is marked as not parsing; see
+/// [`FractalParams::unparsed_formula`].
+pub fn known_unparsed(p: &FractalParams) -> bool {
+    p.unparsed_formula.as_deref() == Some(p.custom_formula.as_str())
+}
+
+/// `params` with their custom formula compiled and attached, unless they are not
+/// [`FractalKind::Custom`] or the attached one is current. A formula that does not parse
+/// attaches nothing and is marked, so it is not tried again.
+pub fn with_compiled_formula(params: &FractalParams) -> Cow<'_, FractalParams> {
+    if params.kind != FractalKind::Custom
+        || attached_formula(params).is_some()
+        || known_unparsed(params)
+    {
+        return Cow::Borrowed(params);
+    }
+    let mut params = params.clone();
+    let compiled = CustomFormula::parse(&params.custom_formula).map(Arc::new);
+    attach_compiled(&mut params, compiled);
+    Cow::Owned(params)
+}
+
+/// Attaches `compiled`, or marks the formula as not parsing.
+fn attach_compiled(params: &mut FractalParams, compiled: Result<Arc<CustomFormula>, FormulaError>) {
+    match compiled {
+        Ok(formula) => {
+            params.compiled_formula = Some(formula);
+            params.unparsed_formula = None;
+        }
+        Err(_) => {
+            params.compiled_formula = None;
+            params.unparsed_formula = Some(params.custom_formula.as_str().into());
+        }
+    }
+}
+
+/// The last formula compiled, so an editor parses it again only when the text changes.
+#[derive(Debug, Default)]
+pub struct FormulaCache {
+    last: Option<(String, Result<Arc<CustomFormula>, FormulaError>)>,
+}
+
+impl FormulaCache {
+    /// `source` compiled, or why it does not parse.
+    pub fn compile(&mut self, source: &str) -> &Result<Arc<CustomFormula>, FormulaError> {
+        if !matches!(&self.last, Some((last, _)) if last == source) {
+            let compiled = CustomFormula::parse(source).map(Arc::new);
+            self.last = Some((source.to_string(), compiled));
+        }
+        &self.last.as_ref().expect("compiled above").1
+    }
+
+    /// Attaches the compiled formula to `params` when they are [`FractalKind::Custom`].
+    pub fn attach(&mut self, params: &mut FractalParams) {
+        if params.kind != FractalKind::Custom {
+            return;
+        }
+        let compiled = self.compile(&params.custom_formula).clone();
+        attach_compiled(params, compiled);
+    }
+}
+
+// ------------------------- Parsing -------------------------
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    /// A number followed by `i`, as in `2.5i`.
+    Imaginary(f64),
+    Name(String),
+    Op(char),
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Number(_) | Token::Imaginary(_) => "number".into(),
+            Token::Name(name) => format!("{name:?}"),
+            Token::Op(op) => format!("'{op}'"),
+        }
+    }
+}
+
+/// Tokens of `source`, each with the column it starts at.
+fn lex(source: &str) -> Result<Vec<(Token, usize)>, FormulaError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let ch = chars[i];
+        let column = i + 1;
+        if ch.is_whitespace() {
+            i += 1;
+        } else if ch.is_ascii_digit() || ch == '.' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            // An exponent only where digits follow, so `2e` stays `2` times `e`.
+            if i < chars.len() && matches!(chars[i], 'e' | 'E') {
+                let sign = usize::from(matches!(chars.get(i + 1), Some('+' | '-')));
+                if chars.get(i + 1 + sign).is_some_and(char::is_ascii_digit) {
+                    i += 1 + sign;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let text: String = chars[start..i].iter().collect();
+            let value = text.parse::<f64>().map_err(|_| FormulaError {
+                column,
+                message: format!("{text:?} is not a number"),
+            })?;
+            let imaginary = matches!(chars.get(i), Some('i' | 'I'))
+                && !chars
+                    .get(i + 1)
+                    .is_some_and(|next| next.is_alphanumeric() || *next == '_');
+            if imaginary {
+                i += 1;
+                tokens.push((Token::Imaginary(value), column));
+            } else {
+                tokens.push((Token::Number(value), column));
+            }
+        } else if ch.is_alphabetic() {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let name: String = chars[start..i].iter().collect();
+            tokens.push((Token::Name(name.to_lowercase()), column));
+        } else if "+-*/^()".contains(ch) {
+            i += 1;
+            tokens.push((Token::Op(ch), column));
+        } else {
+            return Err(FormulaError {
+                column,
+                message: format!("unexpected {ch:?}"),
+            });
+        }
+    }
+    Ok(tokens)
+}
+
+/// Deepest nesting of parentheses, calls, signs and powers a formula may have, with each
+/// operator of a sum or product chain counting as a level too. Each level recurses in the
+/// parser and in evaluation, so deeper input would overflow the stack.
+const MAX_DEPTH: usize = 64;
+
+/// Recursive descent over the tokens, one method per precedence level.
+struct Parser<'a> {
+    tokens: &'a [(Token, usize)],
+    pos: usize,
+    /// Column just past the source, where running out of tokens is reported.
+    end: usize,
+    /// Levels currently open; see [`MAX_DEPTH`].
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn formula(&mut self) -> Result<Expr, FormulaError> {
+        if self.tokens.is_empty() {
+            return Err(self.error_here("the formula is empty"));
+        }
+        let expr = self.sum()?;
+        match self.peek() {
+            None => Ok(expr),
+            Some(token) => {
+                let message = format!("expected an operator before {}", token.describe());
+                Err(self.error_here(message))
+            }
+        }
+    }
+
+    /// `term (('+' | '-') term)*`
+    fn sum(&mut self) -> Result<Expr, FormulaError> {
+        let outer = self.depth;
+        let mut expr = self.term()?;
+        loop {
+            if self.eat('+') {
+                self.deepen()?;
+                expr = Expr::Add(Box::new(expr), Box::new(self.term()?));
+            } else if self.eat('-') {
+                self.deepen()?;
+                expr = Expr::Sub(Box::new(expr), Box::new(self.term()?));
+            } else {
+                self.depth = outer;
+                return Ok(expr);
+            }
+        }
+    }
+
+    /// `unary (('*' | '/') unary)*`
+    fn term(&mut self) -> Result<Expr, FormulaError> {
+        let outer = self.depth;
+        let mut expr = self.unary()?;
+        loop {
+            if self.eat('*') {
+                self.deepen()?;
+                expr = Expr::Mul(Box::new(expr), Box::new(self.unary()?));
+            } else if self.eat('/') {
+                self.deepen()?;
+                expr = Expr::Div(Box::new(expr), Box::new(self.unary()?));
+            } else {
+                self.depth = outer;
+                return Ok(expr);
+            }
+        }
+    }
+
+    /// `('-' | '+') unary | power`; below `^`, so `-z^2` is `-(z^2)`. Parentheses, calls
+    /// and powers all nest through here.
+    fn unary(&mut self) -> Result<Expr, FormulaError> {
+        self.deepen()?;
+        let expr = if self.eat('-') {
+            self.unary().map(|expr| Expr::Neg(Box::new(expr)))
+        } else if self.eat('+') {
+            self.unary()
+        } else {
+            self.power()
+        };
+        self.depth -= 1;
+        expr
+    }
+
+    /// `atom ('^' unary)?`, right-associative: `z^2^3` is `z^(2^3)`.
+    fn power(&mut self) -> Result<Expr, FormulaError> {
+        let base = self.atom()?;
+        if self.eat('^') {
+            return Ok(Expr::Pow(Box::new(base), Box::new(self.unary()?)));
+        }
+        Ok(base)
+    }
+
+    /// A number, name, call or parenthesized formula.
+    fn atom(&mut self) -> Result<Expr, FormulaError> {
+        let Some((token, column)) = self.tokens.get(self.pos).cloned() else {
+            return Err(self.error_here("the formula ends early"));
+        };
+        self.pos += 1;
+        match token {
+            Token::Number(value) => Ok(Expr::Const(Complex64::new(value, 0.0))),
+            Token::Imaginary(value) => Ok(Expr::Const(Complex64::new(0.0, value))),
+            Token::Op('(') => {
+                let expr = self.sum()?;
+                self.expect_close()?;
+                Ok(expr)
+            }
+            Token::Name(name) => {
+                if let Some(func) = Func::ALL.into_iter().find(|f| f.name() == name) {
+                    if !self.eat('(') {
+                        return Err(self.error_here(format!("expected '(' after {name}")));
+                    }
+                    let argument = self.sum()?;
+                    self.expect_close()?;
+                    return Ok(Expr::Call(func, Box::new(argument)));
+                }
+                match name.as_str() {
+                    "z" => Ok(Expr::Z),
+                    "c" => Ok(Expr::C),
+                    "i" => Ok(Expr::Const(Complex64::new(0.0, 1.0))),
+                    "pi" => Ok(Expr::Const(Complex64::new(std::f64::consts::PI, 0.0))),
+                    "e" => Ok(Expr::Const(Complex64::new(std::f64::consts::E, 0.0))),
+                    _ => Err(FormulaError {
+                        column,
+                        message: format!("unknown name {name:?}"),
+                    }),
+                }
+            }
+            Token::Op(op) => Err(FormulaError {
+                column,
+                message: format!("expected a number, name or '(' instead of '{op}'"),
+            }),
+        }
+    }
+
+    /// Opens one more level, failing past [`MAX_DEPTH`].
+    fn deepen(&mut self) -> Result<(), FormulaError> {
+        if self.depth == MAX_DEPTH {
+            let message = format!("the formula nests deeper than {MAX_DEPTH} levels");
+            return Err(self.error_here(message));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn expect_close(&mut self) -> Result<(), FormulaError> {
+        if self.eat(')') {
+            Ok(())
+        } else {
+            Err(self.error_here("expected ')'"))
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    /// Consumes the next token if it is `op`.
+    fn eat(&mut self, op: char) -> bool {
+        let found = self.peek() == Some(&Token::Op(op));
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    /// An error at the next token, or at the end of the source.
+    fn error_here(&self, message: impl Into<String>) -> FormulaError {
+        FormulaError {
+            column: self
+                .tokens
+                .get(self.pos)
+                .map_or(self.end, |(_, column)| *column),
+            message: message.into(),
+        }
+    }
+}
+
+// ------------------------- Complex arithmetic -------------------------
+
+fn mul(a: Complex64, b: Complex64) -> Complex64 {
+    Complex64::new(a.re * b.re - a.im * b.im, a.re * b.im + a.im * b.re)
+}
+
+fn div(a: Complex64, b: Complex64) -> Complex64 {
+    let d = b.re * b.re + b.im * b.im;
+    Complex64::new(
+        (a.re * b.re + a.im * b.im) / d,
+        (a.im * b.re - a.re * b.im) / d,
+    )
+}
+
+fn exp(z: Complex64) -> Complex64 {
+    let r = z.re.exp();
+    Complex64::new(r * z.im.cos(), r * z.im.sin())
+}
+
+fn log(z: Complex64) -> Complex64 {
+    Complex64::new(z.re.hypot(z.im).ln(), z.im.atan2(z.re))
+}
+
+/// `z^n` by repeated squaring.
+fn powi(z: Complex64, n: i32) -> Complex64 {
+    let mut result = Complex64::new(1.0, 0.0);
+    let mut square = z;
+    let mut k = n.unsigned_abs();
+    while k > 0 {
+        if k & 1 == 1 {
+            result = mul(result, square);
+        }
+        square = mul(square, square);
+        k >>= 1;
+    }
+    if n < 0 {
+        div(Complex64::new(1.0, 0.0), result)
+    } else {
+        result
+    }
+}
+
+/// Principal `z^w`, with `0^w` taken as 0.
+fn pow(z: Complex64, w: Complex64) -> Complex64 {
+    if z.re == 0.0 && z.im == 0.0 {
+        return Complex64::new(0.0, 0.0);
+    }
+    exp(mul(w, log(z)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Expr {
+        CustomFormula::parse(source).unwrap().expr
+    }
+
+    fn error(source: &str) -> FormulaError {
+        CustomFormula::parse(source).unwrap_err()
+    }
+
+    fn real(value: f64) -> Box<Expr> {
+        Box::new(Expr::Const(Complex64::new(value, 0.0)))
+    }
+
+    #[test]
+    fn operators_bind_by_precedence() {
+        // z + c*2, not (z + c)*2.
+        assert_eq!(
+            parse("z + c*2"),
+            Expr::Add(
+                Box::new(Expr::Z),
+                Box::new(Expr::Mul(Box::new(Expr::C), real(2.0)))
+            )
+        );
+        // Unary minus below ^, and ^ to the right.
+        assert_eq!(
+            parse("-z^2^3"),
+            Expr::Neg(Box::new(Expr::Pow(
+                Box::new(Expr::Z),
+                Box::new(Expr::Pow(real(2.0), real(3.0)))
+            )))
+        );
+        // Left to right within a level.
+        assert_eq!(
+            parse("z - c - 1"),
+            Expr::Sub(
+                Box::new(Expr::Sub(Box::new(Expr::Z), Box::new(Expr::C))),
+                real(1.0)
+            )
+        );
+        assert_eq!(
+            parse("(z + c) / 2"),
+            Expr::Div(
+                Box::new(Expr::Add(Box::new(Expr::Z), Box::new(Expr::C))),
+                real(2.0)
+            )
+        );
+    }
+
+    #[test]
+    fn complex_literals() {
+        let i = |im| Expr::Const(Complex64::new(0.0, im));
+        assert_eq!(parse("2.5i"), i(2.5));
+        assert_eq!(parse("i"), i(1.0));
+        assert_eq!(parse("1e-3I"), i(1e-3));
+        assert_eq!(parse("0.5 - 2i"), Expr::Sub(real(0.5), Box::new(i(2.0))));
+        // `im` after a number is a name, not an imaginary literal.
+        assert_eq!(error("2im(z)").column, 2);
+        let value = CustomFormula::parse("(1 + 2i) * (3 - i)")
+            .unwrap()
+            .eval(Complex64::default(), Complex64::default());
+        assert_eq!(value, Complex64::new(5.0, 5.0));
+    }
+
+    #[test]
+    fn evaluates_the_builtin_kinds() {
+        let z = Complex64::new(0.3, -0.7);
+        let c = Complex64::new(-0.8, 0.156);
+        let mandelbrot = Complex64::new(z.re * z.re - z.im * z.im + c.re, 2.0 * z.re * z.im + c.im);
+        for source in ["sqr(z) + c", "z^2 + c", "z*z + c", "Z ^ 2.0 + C"] {
+            let next = CustomFormula::parse(source).unwrap().eval(z, c);
+            assert!((next.re - mandelbrot.re).abs() < 1e-12, "{source}");
+            assert!((next.im - mandelbrot.im).abs() < 1e-12, "{source}");
+        }
+        let ship = CustomFormula::parse("sqr(abs(z)) + c").unwrap().eval(z, c);
+        assert!((ship.im - (2.0 * 0.3 * 0.7 + c.im)).abs() < 1e-12);
+        // A fractional power goes through exp and log, and agrees with the whole one.
+        let whole = CustomFormula::parse("z^3").unwrap().eval(z, c);
+        let general = CustomFormula::parse("z^(3 + 0i)").unwrap().eval(z, c);
+        assert!((whole.re - general.re).abs() < 1e-12);
+        assert!((whole.im - general.im).abs() < 1e-12);
+    }
+
+    #[test]
+    fn degree_follows_the_leading_power() {
+        let degree = |source| CustomFormula::parse(source).unwrap().degree();
+        assert_eq!(degree("z^3 - 0.5*z + c"), 3.0);
+        assert_eq!(degree("sqr(sqr(z)) + c"), 4.0);
+        assert_eq!(degree("z*z*z*z*z / 2 + c"), 5.0);
+        assert_eq!(degree("sin(z) * c"), 2.0);
+        assert_eq!(degree("z + c"), 2.0);
+    }
+
+    #[test]
+    fn bad_input_reports_where() {
+        let cases = [
+            ("", 1, "the formula is empty"),
+            ("   ", 4, "the formula is empty"),
+            ("z^2 +", 6, "the formula ends early"),
+            ("sqr(z + c", 10, "expected ')'"),
+            ("sqr z", 5, "expected '(' after sqr"),
+            ("z^2 + w", 7, "unknown name \"w\""),
+            ("z # c", 3, "unexpected '#'"),
+            ("z c", 3, "expected an operator before \"c\""),
+            ("1.2.3", 1, "\"1.2.3\" is not a number"),
+            (
+                "z * * c",
+                5,
+                "expected a number, name or '(' instead of '*'",
+            ),
+        ];
+        for (source, column, message) in cases {
+            assert_eq!(
+                error(source),
+                FormulaError {
+                    column,
+                    message: message.into()
+                },
+                "{source:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn deep_nesting_is_an_error() {
+        let nested = |depth: usize| format!("{}z{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(CustomFormula::parse(&nested(MAX_DEPTH - 1)).is_ok());
+        assert!(CustomFormula::parse(&("z + ".repeat(60) + "c")).is_ok());
+        assert_eq!(
+            error(&nested(MAX_DEPTH)).message,
+            "the formula nests deeper than 64 levels"
+        );
+        for source in [
+            nested(100_000),
+            format!("{}z", "-".repeat(100_000)),
+            format!("{}z", "sin(".repeat(100_000)),
+            "z^".repeat(100_000) + "z",
+            "z + ".repeat(100_000) + "z",
+            "z * ".repeat(100_000) + "z",
+        ] {
+            assert!(CustomFormula::parse(&source).is_err());
+        }
+    }
+
+    #[test]
+    fn cache_parses_only_new_text() {
+        let mut cache = FormulaCache::default();
+        let first = cache.compile("z^2 + c").clone().unwrap();
+        let again = cache.compile("z^2 + c").clone().unwrap();
+        assert!(Arc::ptr_eq(&first, &again));
+        assert!(cache.compile("z^2 +").is_err());
+
+        let mut params = FractalParams::new(FractalKind::Custom);
+        params.custom_formula = "z^3 + c".into();
+        cache.attach(&mut params);
+        assert_eq!(attached_formula(&params).unwrap().source(), "z^3 + c");
+        // Edited text leaves the attached formula stale until the next attach.
+        params.custom_formula = "z^4 + c".into();
+        assert!(attached_formula(&params).is_none());
+        assert_eq!(
+            attached_formula(&with_compiled_formula(&params))
+                .unwrap()
+                .degree(),
+            4.0
+        );
+    }
+
+    #[test]
+    fn unparsed_formulas_are_marked_once() {
+        let mut params = FractalParams::new(FractalKind::Custom);
+        params.custom_formula = "z^2 +".into();
+        let marked = with_compiled_formula(&params);
+        assert!(matches!(marked, Cow::Owned(_)));
+        assert!(known_unparsed(&marked) && attached_formula(&marked).is_none());
+        assert!(matches!(with_compiled_formula(&marked), Cow::Borrowed(_)));
+
+        // Fixing the text clears the mark on the next attach.
+        let mut fixed = marked.into_owned();
+        fixed.custom_formula = "z^2 + c".into();
+        assert!(!known_unparsed(&fixed));
+        let mut cache = FormulaCache::default();
+        cache.attach(&mut fixed);
+        assert!(attached_formula(&fixed).is_some() && fixed.unparsed_formula.is_none());
+        fixed.custom_formula = "z^".into();
+        cache.attach(&mut fixed);
+        assert!(known_unparsed(&fixed) && fixed.compiled_formula.is_none());
+    }
+}
@@ -0,0 +1,405 @@
+//! Deep zoom movies: keys that fly from a starting view down to a destination at a steady
+//! apparent zoom speed, with the destination gliding into the center as the view zooms.
+
+use crate::{
+    log_lerp_f64, parse_share_string, Animation, Camera, Complex64, Easing, FractalParams, Keyframe,
+};
+
+/// Center keys per doubling of the scale. Center keys are blended linearly, so they have
+/// to follow the exponential path closely; at this density the blend stays well within a
+/// pixel of it.
+const CENTER_KEYS_PER_DOUBLING: f32 = 8.0;
+
+/// Fewest center keys, so movies that barely zoom still follow the speed profile.
+const MIN_CENTER_KEYS: usize = 32;
+
+const MAX_CENTER_KEYS: usize = 4096;
+
+/// How the zoom speed changes over a movie.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ZoomProfile {
+    /// The same number of doublings every second.
+    #[default]
+    Constant,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl ZoomProfile {
+    pub const ALL: [ZoomProfile; 4] = [
+        ZoomProfile::Constant,
+        ZoomProfile::EaseIn,
+        ZoomProfile::EaseOut,
+        ZoomProfile::EaseInOut,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ZoomProfile::Constant => "Constant",
+            ZoomProfile::EaseIn => "Ease in",
+            ZoomProfile::EaseOut => "Ease out",
+            ZoomProfile::EaseInOut => "Ease in/out",
+        }
+    }
+
+    /// Easing of the zoom key, applied to the log of the scale.
+    pub fn easing(&self) -> Easing {
+        match self {
+            ZoomProfile::Constant => Easing::Linear,
+            ZoomProfile::EaseIn => Easing::EaseIn,
+            ZoomProfile::EaseOut => Easing::EaseOut,
+            ZoomProfile::EaseInOut => Easing::EaseInOut,
+        }
+    }
+}
+
+/// A movie from a starting view to `destination` at `end_scale`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DeepZoomPlan {
+    pub destination: Complex64,
+    /// Scale at the end of the movie, in pixels per unit like [`Camera::scale`].
+    pub end_scale: f64,
+    /// Seconds.
+    pub duration: f32,
+    pub profile: ZoomProfile,
+    /// Radians the camera turns over the movie; 0 adds no rotation keys.
+    pub rotation: f32,
+}
+
+impl DeepZoomPlan {
+    pub fn new(destination: impl Into<Complex64>, end_scale: f64, duration: f32) -> Self {
+        Self {
+            destination: destination.into(),
+            end_scale,
+            duration,
+            profile: ZoomProfile::default(),
+            rotation: 0.0,
+        }
+    }
+
+    pub fn with_profile(mut self, profile: ZoomProfile) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    pub fn with_rotation(mut self, radians: f32) -> Self {
+        self.rotation = radians;
+        self
+    }
+
+    /// Share of the zoom, counted in doublings, done `t` seconds in.
+    pub fn progress(&self, t: f32) -> f32 {
+        let u = if self.duration > 0.0 {
+            (t / self.duration).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        self.profile.easing().apply(u)
+    }
+
+    /// The exact camera `t` seconds into the movie from `start`. The scale blends in log
+    /// space, and the destination's offset from the view center, in pixels, shrinks in
+    /// step with the zoom so it arrives centered.
+    pub fn camera_at(&self, start: &Camera, t: f32) -> Camera {
+        let p = self.progress(t);
+        let scale = log_lerp_f64(start.scale, self.end_scale, p);
+        // Offset in the plane, as a share of the starting offset.
+        let shrink = f64::from(1.0 - p) * start.scale / scale;
+        let dest = self.destination;
+        Camera {
+            center: Complex64::new(
+                dest.re - (dest.re - start.center.re) * shrink,
+                dest.im - (dest.im - start.center.im) * shrink,
+            ),
+            scale,
+            rotation: start.rotation + self.rotation * p,
+            deep_center: None,
+        }
+    }
+
+    /// Doublings of the scale from `start` to the destination; negative zooms out.
+    pub fn doublings(&self, start: &Camera) -> f32 {
+        (self.end_scale / start.scale).log2() as f32
+    }
+
+    /// Replaces the zoom, center and rotation keys of `anim` with the movie from `start`
+    /// and fits the timeline to it. Max Iter follows the depth from then on through
+    /// [`FractalParams::auto_iter`]. Keys hold `f32`, so the centers are rounded to it.
+    pub fn apply(&self, anim: &mut Animation, fractal: &mut FractalParams, start: &Camera) {
+        let duration = self.duration.max(0.0);
+        let easing = self.profile.easing();
+        anim.duration = duration;
+        anim.zoom_forever = None;
+        anim.log_zoom = true;
+        anim.selection = None;
+        anim.kf_zoom.keys = vec![
+            Keyframe {
+                t: 0.0,
+                v: start.scale as f32,
+                easing,
+            },
+            Keyframe {
+                t: duration,
+                v: self.end_scale as f32,
+                easing: Easing::Linear,
+            },
+        ];
+
+        // Halfway between even steps in time and even steps in progress, so neither the
+        // slow ends nor the fast middle of an eased zoom are left with sparse keys.
+        let count = ((self.doublings(start).abs() * CENTER_KEYS_PER_DOUBLING).ceil() as usize)
+            .clamp(MIN_CENTER_KEYS, MAX_CENTER_KEYS);
+        anim.kf_center_x.keys.clear();
+        anim.kf_center_y.keys.clear();
+        for i in 0..=count {
+            let u = i as f32 / count as f32;
+            let t = duration * (u + invert_easing(easing, u)) / 2.0;
+            let cam = self.camera_at(start, t);
+            anim.kf_center_x.keys.push(Keyframe {
+                t,
+                v: cam.center.re as f32,
+                easing: Easing::Linear,
+            });
+            anim.kf_center_y.keys.push(Keyframe {
+                t,
+                v: cam.center.im as f32,
+                easing: Easing::Linear,
+            });
+        }
+
+        anim.kf_rotation.keys.clear();
+        if self.rotation != 0.0 {
+            anim.kf_rotation.keys = vec![
+                Keyframe {
+                    t: 0.0,
+                    v: start.rotation,
+                    easing,
+                },
+                Keyframe {
+                    t: duration,
+                    v: start.rotation + self.rotation,
+                    easing: Easing::Linear,
+                },
+            ];
+        }
+        fractal.auto_iter = true;
+    }
+}
+
+/// Where `easing` reaches `p`, by bisection; every easing rises from 0 to 1.
+fn invert_easing(easing: Easing, p: f32) -> f32 {
+    if p <= 0.0 || p >= 1.0 {
+        return p.clamp(0.0, 1.0);
+    }
+    let (mut lo, mut hi) = (0.0f32, 1.0f32);
+    for _ in 0..32 {
+        let mid = (lo + hi) / 2.0;
+        if easing.apply(mid) < p {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    (lo + hi) / 2.0
+}
+
+/// A destination read from text: a point, and the scale when the text carried one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Destination {
+    pub center: Complex64,
+    pub scale: Option<f64>,
+}
+
+/// Reads `-0.7436, 0.1318`, `-0.7436 0.1318i`, `-0.7436+0.1318i` or a `matterhorn:v1`
+/// view string.
+pub fn parse_destination(text: &str) -> Result<Destination, String> {
+    let text = text.trim();
+    if text.starts_with("matterhorn:") {
+        let view = parse_share_string(text)?;
+        return Ok(Destination {
+            center: view.center,
+            scale: Some(view.scale),
+        });
+    }
+    let parts: Vec<&str> = text
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|p| !p.is_empty())
+        .collect();
+    let (re, im) = match parts.as_slice() {
+        [re, im] => (*re, *im),
+        // `a+bi` or `a-bi`: split at the sign that is not the leading one or an exponent's.
+        [joined] => {
+            let split = joined
+                .char_indices()
+                .skip(1)
+                .filter(|&(i, c)| (c == '+' || c == '-') && !joined[..i].ends_with(['e', 'E']))
+                .last()
+                .map(|(i, _)| i)
+                .ok_or_else(|| format!("expected two coordinates, got \"{text}\""))?;
+            joined.split_at(split)
+        }
+        _ => return Err(format!("expected two coordinates, got \"{text}\"")),
+    };
+    let number = |s: &str| {
+        let s = s.trim_start_matches('+').trim_end_matches(['i', 'I']);
+        s.parse::<f64>()
+            .ok()
+            .filter(|v| v.is_finite())
+            .ok_or_else(|| format!("\"{s}\" is not a number"))
+    };
+    Ok(Destination {
+        center: Complex64::new(number(re)?, number(im)?),
+        scale: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{evaluate_frame, Complex, EvalTarget, FractalKind, Project};
+
+    const VIEW: (f32, f32) = (800.0, 600.0);
+
+    fn project(plan: &DeepZoomPlan, start: &Camera) -> Project {
+        let mut proj = Project::new("deep").with_camera(start.clone());
+        plan.apply(&mut proj.anim, &mut proj.fractal, start);
+        proj
+    }
+
+    fn start() -> Camera {
+        Camera::new(Complex::new(-0.5, 0.0), 250.0).with_rotation(0.3)
+    }
+
+    fn destination() -> Complex {
+        Complex::new(-0.743_64, 0.131_82)
+    }
+
+    #[test]
+    fn constant_profile_zooms_by_the_same_factor_every_second() {
+        let plan = DeepZoomPlan::new(destination(), 250.0 * 2f64.powi(16), 16.0);
+        let proj = project(&plan, &start());
+        let scale = |t: f32| proj.anim.sample_zoom(t, 0.0);
+        assert_eq!(scale(0.0), 250.0);
+        assert!((scale(16.0) / plan.end_scale - 1.0).abs() < 1e-5);
+        for step in 0..64 {
+            let t = step as f32 * 0.25;
+            let doublings = (scale(t + 0.25) / scale(t)).log2();
+            assert!((doublings - 0.25).abs() < 1e-3, "{doublings} at {t}s");
+        }
+    }
+
+    #[test]
+    fn eased_profiles_slow_the_zoom_at_their_ends() {
+        let plan = DeepZoomPlan::new(destination(), 250.0 * 1e5, 10.0)
+            .with_profile(ZoomProfile::EaseInOut);
+        let proj = project(&plan, &start());
+        let rate =
+            |t: f32| (proj.anim.sample_zoom(t + 0.1, 0.0) / proj.anim.sample_zoom(t, 0.0)).log2();
+        assert!(rate(0.0) < rate(4.9) / 10.0);
+        assert!(rate(9.9) < rate(4.9) / 10.0);
+        assert!((rate(2.0) - rate(7.9)).abs() < 1e-2, "symmetric");
+    }
+
+    #[test]
+    fn destination_glides_into_the_center() {
+        let start = start();
+        for profile in ZoomProfile::ALL {
+            let plan =
+                DeepZoomPlan::new(destination(), 250.0 * 2f64.powi(14), 12.0).with_profile(profile);
+            let proj = project(&plan, &start);
+            let pixel = |cam: &Camera| {
+                let (x, y) = cam.plane_to_pixel(plan.destination, VIEW.0, VIEW.1);
+                (x - VIEW.0 / 2.0, y - VIEW.1 / 2.0)
+            };
+            let (x0, y0) = pixel(&start);
+            for step in 0..=240 {
+                let t = step as f32 * 0.05;
+                let cam = evaluate_frame(&proj, t, EvalTarget::Export).camera;
+                let exact = plan.camera_at(&start, t);
+                assert!((cam.scale / exact.scale - 1.0).abs() < 1e-4);
+                assert!((cam.rotation - start.rotation).abs() < 1e-6);
+                // The keyed path stays within a pixel of the exact one...
+                let (x, y) = pixel(&cam);
+                let (ex, ey) = pixel(&exact);
+                assert!(
+                    (x - ex).hypot(y - ey) < 1.0,
+                    "{profile:?} off by {} px at {t}s",
+                    (x - ex).hypot(y - ey)
+                );
+                // ...whose offset shrinks with the zoom's progress.
+                let left = 1.0 - plan.progress(t);
+                assert!((ex - x0 * left).hypot(ey - y0 * left) < 0.5);
+            }
+            let end = evaluate_frame(&proj, 12.0, EvalTarget::Export).camera;
+            assert_eq!(end.center, plan.destination);
+        }
+    }
+
+    #[test]
+    fn rotation_keys_turn_with_the_zoom() {
+        let start = start();
+        let plan = DeepZoomPlan::new(destination(), 250.0 * 1e3, 6.0).with_rotation(1.5);
+        let mut proj = project(&plan, &start);
+        let rotation = |proj: &Project, t| evaluate_frame(proj, t, EvalTarget::Export).camera;
+        assert_eq!(rotation(&proj, 0.0).rotation, start.rotation);
+        assert!((rotation(&proj, 6.0).rotation - (start.rotation + 1.5)).abs() < 1e-6);
+        assert!(
+            (rotation(&proj, 3.0).rotation - plan.camera_at(&start, 3.0).rotation).abs() < 1e-5
+        );
+        assert!(proj.fractal.auto_iter);
+        assert_eq!(proj.anim.duration, 6.0);
+
+        // Planning again without rotation clears the old rotation keys.
+        DeepZoomPlan::new(destination(), 250.0 * 1e3, 6.0).apply(
+            &mut proj.anim,
+            &mut proj.fractal,
+            &start,
+        );
+        assert!(proj.anim.kf_rotation.keys.is_empty());
+    }
+
+    #[test]
+    fn center_keys_follow_the_depth() {
+        let start = start();
+        let shallow = project(&DeepZoomPlan::new(destination(), 500.0, 5.0), &start);
+        assert_eq!(shallow.anim.kf_center_x.keys.len(), MIN_CENTER_KEYS + 1);
+        let deep = project(&DeepZoomPlan::new(destination(), 250.0 * 1e9, 5.0), &start);
+        let doublings = 1e9f64.log2() as f32;
+        assert_eq!(
+            deep.anim.kf_center_y.keys.len(),
+            (doublings * CENTER_KEYS_PER_DOUBLING).ceil() as usize + 1
+        );
+        assert!(deep
+            .anim
+            .kf_center_x
+            .keys
+            .windows(2)
+            .all(|pair| pair[0].t < pair[1].t));
+        assert_eq!(deep.anim.kf_center_x.keys[0].t, 0.0);
+        assert_eq!(deep.anim.kf_center_x.keys.last().unwrap().t, 5.0);
+    }
+
+    #[test]
+    fn destinations_parse_from_pasted_text() {
+        let point = |re, im| {
+            Ok(Destination {
+                center: Complex64::new(re, im),
+                scale: None,
+            })
+        };
+        assert_eq!(parse_destination("-0.75, 0.1"), point(-0.75, 0.1));
+        assert_eq!(parse_destination(" -0.75 0.1i "), point(-0.75, 0.1));
+        assert_eq!(parse_destination("-0.75+0.1i"), point(-0.75, 0.1));
+        assert_eq!(parse_destination("-7.5e-1-1e-2i"), point(-0.75, -0.01));
+        assert!(parse_destination("-0.75").is_err());
+        assert!(parse_destination("a, b").is_err());
+
+        let fractal = FractalParams::new(FractalKind::Mandelbrot);
+        let cam = Camera::new(destination(), 1e6);
+        let shared = crate::to_share_string(&fractal, &cam, false);
+        let parsed = parse_destination(&shared).unwrap();
+        assert_eq!(parsed.scale, Some(1e6));
+        assert!((parsed.center.re - cam.center.re).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,60 @@
+//! The demo project the app opens on its first start: a known-good location, a palette
+//! that shows it off, and a short animation to play with.
+
+use crate::{
+    apply_palette_preset, palette_presets, Camera, FractalKind, FractalParams, Project,
+    SEAHORSE_REPEAT_SPOT,
+};
+
+/// Palette preset the demo uses.
+const DEMO_PALETTE: &str = "Cyber Sunset";
+
+/// Length of the demo animation in seconds.
+pub const DEMO_DURATION: f32 = 8.0;
+
+/// An eight second zoom into the seahorse valley with the palette turning once.
+pub fn demo_project() -> Project {
+    let mut fractal = FractalParams::new(FractalKind::Mandelbrot)
+        .with_max_iter(1200)
+        .with_palette_repeat(48.0);
+    if let Some(preset) = palette_presets().iter().find(|p| p.name == DEMO_PALETTE) {
+        apply_palette_preset(&mut fractal.palette, preset);
+    }
+    let mut proj = Project::new("Welcome to Matterhorn")
+        .with_fractal(fractal)
+        .with_camera(Camera::new(SEAHORSE_REPEAT_SPOT.center, 250.0));
+    let anim = &mut proj.anim;
+    anim.duration = DEMO_DURATION;
+    anim.looping = true;
+    anim.log_zoom = true;
+    anim.kf_zoom.upsert(0.0, 250.0);
+    anim.kf_zoom.upsert(DEMO_DURATION, 40_000.0);
+    anim.kf_palette.upsert(0.0, 0.0);
+    anim.kf_palette.upsert(DEMO_DURATION, 1.0);
+    anim.kf_rotation.upsert(0.0, 0.0);
+    anim.kf_rotation.upsert(DEMO_DURATION, 0.6);
+    proj.export.duration = DEMO_DURATION;
+    proj
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{diagnose, evaluate_frame, render_cpu, EvalTarget, FrameStats};
+
+    #[test]
+    fn demo_frames_are_not_flat() {
+        let proj = demo_project();
+        assert_eq!(
+            proj.fractal.palette[0].color,
+            palette_presets()[1].stops[0].color
+        );
+        for t in [0.0, DEMO_DURATION / 2.0, DEMO_DURATION] {
+            let frame = evaluate_frame(&proj, t, EvalTarget::Export);
+            let rgba = render_cpu((96, 64), &frame.fractal, &frame.camera);
+            let stats = FrameStats::of_rgba(&rgba).unwrap();
+            assert!(!stats.is_flat(), "flat at {t}s");
+            assert!(diagnose(&frame.fractal, &frame.camera, 96.0, Some(&stats)).is_empty());
+        }
+    }
+}
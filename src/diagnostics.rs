@@ -0,0 +1,407 @@
+//! "Black screen" diagnostics: rules that recognise parameters rendering a black or flat
+//! image, each with a one-click fix. The rules only look at values, so they run on the
+//! evaluated frame and on hand-built parameter sets alike.
+
+use std::{fmt, mem};
+
+use crate::{default_palette, formula, Camera, FractalParams, Keyframes, OrbitTrap, Project};
+
+/// Below this escape radius orbits that will diverge are counted as escaping too early or
+/// never, and the smooth coloring breaks down.
+pub const MIN_ESCAPE_RADIUS: f32 = 2.0;
+
+/// Max Iter this many times below the suggestion for the zoom leaves the view one flat
+/// band of color.
+const MAX_ITER_SHORTFALL: u32 = 5;
+
+/// Largest per-channel spread across the palette stops that still counts as one color.
+const FLAT_PALETTE_SPREAD: f32 = 0.02;
+
+/// Exposure below which every color tone maps to near black.
+pub const MIN_EXPOSURE: f32 = 0.05;
+
+/// Below this softness, orbits passing a whole unit from the trap still take 90% of its
+/// color, so the trap tints every pixel.
+pub const MIN_TRAP_SOFTNESS: f32 = 0.1;
+
+/// Luminance variance, on a 0..1 scale, below which a frame reads as a single color.
+const FLAT_FRAME_VARIANCE: f32 = 1e-4;
+
+/// Most pixels [`FrameStats::of_rgba`] looks at.
+const FRAME_SAMPLES: usize = 4096;
+
+/// Luminance statistics of a rendered frame, from a sparse sample of its pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameStats {
+    /// Mean luminance, 0..1.
+    pub mean: f32,
+    pub variance: f32,
+}
+
+impl FrameStats {
+    /// Statistics of RGBA8 `pixels`, or `None` for an empty frame.
+    pub fn of_rgba(pixels: &[u8]) -> Option<Self> {
+        let count = pixels.len() / 4;
+        if count == 0 {
+            return None;
+        }
+        // An odd stride, so the sample does not line up with the columns of the frame.
+        let stride = (count / FRAME_SAMPLES) | 1;
+        let (mut sum, mut sum_sq, mut n) = (0.0f64, 0.0f64, 0.0f64);
+        for px in pixels.chunks_exact(4).step_by(stride) {
+            let luma =
+                (0.2126 * f64::from(px[0]) + 0.7152 * f64::from(px[1]) + 0.0722 * f64::from(px[2]))
+                    / 255.0;
+            sum += luma;
+            sum_sq += luma * luma;
+            n += 1.0;
+        }
+        let mean = sum / n;
+        Some(Self {
+            mean: mean as f32,
+            variance: (sum_sq / n - mean * mean).max(0.0) as f32,
+        })
+    }
+
+    /// Whether the frame is one flat color.
+    pub fn is_flat(&self) -> bool {
+        self.variance < FLAT_FRAME_VARIANCE
+    }
+}
+
+/// A reason the image may be black or flat.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Diagnosis {
+    /// The camera has a NaN or infinite value, or a scale that is not positive.
+    BadCamera,
+    EscapeRadius {
+        radius: f32,
+    },
+    MaxIter {
+        max_iter: u32,
+        suggested: u32,
+    },
+    FlatPalette,
+    Exposure {
+        exposure: f32,
+    },
+    TrapCoversAll {
+        softness: f32,
+    },
+    /// The frame is flat and none of the other rules explain it.
+    FlatFrame {
+        mean: f32,
+    },
+}
+
+/// Every rule that applies to `fractal` seen through `camera` in a view `view_width`
+/// pixels wide. Pass the evaluated frame, so keys and scripts are accounted for, and the
+/// stats of the frame last rendered from it, if any.
+pub fn diagnose(
+    fractal: &FractalParams,
+    camera: &Camera,
+    view_width: f32,
+    frame: Option<&FrameStats>,
+) -> Vec<Diagnosis> {
+    let mut found = Vec::new();
+    let camera_ok = [
+        camera.center.re,
+        camera.center.im,
+        f64::from(camera.rotation),
+    ]
+    .iter()
+    .all(|v| v.is_finite())
+        && camera.scale.is_finite()
+        && camera.scale > 0.0;
+    if !camera_ok {
+        found.push(Diagnosis::BadCamera);
+    }
+    if below(fractal.escape_radius, MIN_ESCAPE_RADIUS) {
+        found.push(Diagnosis::EscapeRadius {
+            radius: fractal.escape_radius,
+        });
+    }
+    if camera_ok {
+        let suggested = fractal.suggested_max_iter(camera, view_width);
+        if fractal.max_iter.saturating_mul(MAX_ITER_SHORTFALL) < suggested {
+            found.push(Diagnosis::MaxIter {
+                max_iter: fractal.max_iter,
+                suggested,
+            });
+        }
+    }
+    if is_flat_palette(fractal) {
+        found.push(Diagnosis::FlatPalette);
+    }
+    if below(fractal.exposure, MIN_EXPOSURE) {
+        found.push(Diagnosis::Exposure {
+            exposure: fractal.exposure,
+        });
+    }
+    if fractal.orbit.enabled && below(fractal.orbit.softness, MIN_TRAP_SOFTNESS) {
+        found.push(Diagnosis::TrapCoversAll {
+            softness: fractal.orbit.softness,
+        });
+    }
+    if let Some(frame) = frame.filter(|f| f.is_flat() && found.is_empty()) {
+        found.push(Diagnosis::FlatFrame { mean: frame.mean });
+    }
+    found
+}
+
+/// `value < min`, counting NaN as below.
+fn below(value: f32, min: f32) -> bool {
+    value.is_nan() || value < min
+}
+
+/// Whether every palette stop is the same color, give or take rounding.
+fn is_flat_palette(fractal: &FractalParams) -> bool {
+    let Some(first) = fractal.palette.first() else {
+        return true;
+    };
+    fractal.palette.iter().all(|stop| {
+        stop.color
+            .iter()
+            .zip(first.color)
+            .all(|(a, b)| (a - b).abs() <= FLAT_PALETTE_SPREAD)
+    })
+}
+
+impl Diagnosis {
+    /// Whether `other` is the same rule, whatever the values it found.
+    pub fn same_rule(&self, other: &Diagnosis) -> bool {
+        mem::discriminant(self) == mem::discriminant(other)
+    }
+
+    /// Button text for [`Diagnosis::fix`].
+    pub fn fix_label(&self) -> String {
+        match self {
+            Diagnosis::BadCamera => "Reset the view".into(),
+            Diagnosis::EscapeRadius { .. } => "Set it to 4".into(),
+            Diagnosis::MaxIter { suggested, .. } => format!("Set Max Iter to {suggested}"),
+            Diagnosis::FlatPalette => "Use the default palette".into(),
+            Diagnosis::Exposure { .. } => "Set exposure to 1".into(),
+            Diagnosis::TrapCoversAll { .. } => {
+                format!("Set softness to {}", OrbitTrap::default().softness)
+            }
+            Diagnosis::FlatFrame { .. } => "Show the overview".into(),
+        }
+    }
+
+    /// Fixes the project the diagnosis was made for. Camera fixes frame the formula's
+    /// overview across `view_width` pixels.
+    pub fn fix(&self, proj: &mut Project, view_width: f32) {
+        let overview = formula(proj.fractal.kind).default_camera(view_width);
+        match self {
+            Diagnosis::BadCamera => {
+                let anim = &mut proj.anim;
+                drop_broken_keys(&mut anim.kf_zoom, |v| v > 0.0);
+                for track in [
+                    &mut anim.kf_center_x,
+                    &mut anim.kf_center_y,
+                    &mut anim.kf_rotation,
+                ] {
+                    drop_broken_keys(track, |_| true);
+                }
+                proj.camera = overview;
+            }
+            Diagnosis::EscapeRadius { .. } => {
+                proj.fractal.escape_radius = FractalParams::default().escape_radius;
+            }
+            Diagnosis::MaxIter { suggested, .. } => proj.fractal.max_iter = *suggested,
+            Diagnosis::FlatPalette => proj.fractal.palette = default_palette(),
+            Diagnosis::Exposure { .. } => proj.fractal.exposure = 1.0,
+            Diagnosis::TrapCoversAll { .. } => {
+                proj.fractal.orbit.softness = OrbitTrap::default().softness;
+            }
+            Diagnosis::FlatFrame { .. } => proj.camera = overview,
+        }
+    }
+}
+
+/// Removes keys whose time or value is not finite, or whose value fails `valid`.
+fn drop_broken_keys(track: &mut Keyframes<f32>, valid: impl Fn(f32) -> bool) {
+    track
+        .keys
+        .retain(|k| k.t.is_finite() && k.v.is_finite() && valid(k.v));
+}
+
+/// `Max Iter 50 is far too low for this zoom; about 3400 is needed`.
+impl fmt::Display for Diagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Diagnosis::BadCamera => write!(
+                f,
+                "The camera is invalid (NaN, infinite or zero scale), usually after a bad zoom or center key"
+            ),
+            Diagnosis::EscapeRadius { radius } => write!(
+                f,
+                "Escape radius {radius} is below {MIN_ESCAPE_RADIUS}, so points escape too early to color"
+            ),
+            Diagnosis::MaxIter {
+                max_iter,
+                suggested,
+            } => write!(
+                f,
+                "Max Iter {max_iter} is far too low for this zoom; about {suggested} is needed"
+            ),
+            Diagnosis::FlatPalette => write!(f, "Every palette stop is the same color"),
+            Diagnosis::Exposure { exposure } => {
+                write!(f, "Exposure {exposure} darkens every color to black")
+            }
+            Diagnosis::TrapCoversAll { softness } => write!(
+                f,
+                "Orbit trap softness {softness} tints every pixel with the trap color"
+            ),
+            Diagnosis::FlatFrame { mean } => write!(
+                f,
+                "The frame is {}; the view may lie wholly inside or outside the set",
+                if *mean < 0.05 {
+                    "solid black"
+                } else {
+                    "a single flat color"
+                }
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Complex, FractalKind, Keyframe, PaletteStop};
+
+    const WIDTH: f32 = 800.0;
+
+    fn overview() -> Camera {
+        formula(FractalKind::Mandelbrot).default_camera(WIDTH)
+    }
+
+    fn fixed(diagnosis: Diagnosis, mut proj: Project) -> Project {
+        diagnosis.fix(&mut proj, WIDTH);
+        proj
+    }
+
+    #[test]
+    fn default_parameters_are_healthy() {
+        let params = FractalParams::default();
+        assert!(diagnose(&params, &overview(), WIDTH, None).is_empty());
+        let deep = Camera::new(Complex::new(-0.75, 0.1), overview().scale * 1e4);
+        let params = params.with_max_iter(5000);
+        assert!(diagnose(&params, &deep, WIDTH, None).is_empty());
+    }
+
+    #[test]
+    fn each_rule_finds_its_problem_and_fixes_it() {
+        let base = Project::new("diag").with_camera(overview());
+        let deep = Camera::new(Complex::new(-0.75, 0.1), overview().scale * 1e6);
+        let mut cases = Vec::new();
+
+        let mut proj = base.clone();
+        proj.fractal.escape_radius = 1.5;
+        cases.push((proj, Diagnosis::EscapeRadius { radius: 1.5 }));
+        let mut proj = base.clone().with_camera(deep.clone());
+        proj.fractal.max_iter = 200;
+        let suggested = proj.fractal.suggested_max_iter(&deep, WIDTH);
+        cases.push((
+            proj,
+            Diagnosis::MaxIter {
+                max_iter: 200,
+                suggested,
+            },
+        ));
+        let mut proj = base.clone();
+        proj.fractal.palette = vec![
+            PaletteStop::new(0.0, [0.2, 0.3, 0.4]),
+            PaletteStop::new(0.5, [0.21, 0.3, 0.39]),
+            PaletteStop::new(1.0, [0.2, 0.31, 0.4]),
+        ];
+        cases.push((proj, Diagnosis::FlatPalette));
+        let mut proj = base.clone();
+        proj.fractal.palette.clear();
+        cases.push((proj, Diagnosis::FlatPalette));
+        let mut proj = base.clone();
+        proj.fractal.exposure = 0.01;
+        cases.push((proj, Diagnosis::Exposure { exposure: 0.01 }));
+        let mut proj = base.clone();
+        proj.fractal.orbit.enabled = true;
+        proj.fractal.orbit.softness = 0.02;
+        cases.push((proj, Diagnosis::TrapCoversAll { softness: 0.02 }));
+        let mut proj = base.clone();
+        proj.camera.scale = f64::NAN;
+        cases.push((proj, Diagnosis::BadCamera));
+
+        for (proj, expected) in cases {
+            let found = diagnose(&proj.fractal, &proj.camera, WIDTH, None);
+            assert_eq!(found, vec![expected]);
+            let proj = fixed(expected, proj);
+            assert_eq!(
+                diagnose(&proj.fractal, &proj.camera, WIDTH, None),
+                vec![],
+                "{} did not fix it",
+                expected.fix_label()
+            );
+        }
+    }
+
+    #[test]
+    fn nan_keys_are_dropped_by_the_camera_fix() {
+        let mut proj = Project::new("diag").with_camera(overview());
+        let key = |t, v| Keyframe {
+            t,
+            v,
+            easing: Default::default(),
+        };
+        proj.anim.kf_zoom.keys = vec![key(0.0, 200.0), key(1.0, f32::NAN), key(2.0, 0.0)];
+        proj.anim.kf_center_x.keys = vec![key(0.0, -0.5), key(f32::INFINITY, 0.0)];
+        // Sampling rejects what the bad keys blend to...
+        let frame = crate::evaluate_frame(&proj, 1.0, crate::EvalTarget::Preview);
+        assert_eq!(frame.camera.scale, overview().scale);
+        // ...but a camera poisoned any other way is still caught, and the fix drops them.
+        let mut camera = frame.camera.clone();
+        camera.scale = f64::NAN;
+        let found = diagnose(&frame.fractal, &camera, WIDTH, None);
+        assert_eq!(found, vec![Diagnosis::BadCamera]);
+
+        let proj = fixed(Diagnosis::BadCamera, proj);
+        let values = |track: &Keyframes<f32>| -> Vec<(f32, f32)> {
+            track.keys.iter().map(|k| (k.t, k.v)).collect()
+        };
+        assert_eq!(values(&proj.anim.kf_zoom), vec![(0.0, 200.0)]);
+        assert_eq!(values(&proj.anim.kf_center_x), vec![(0.0, -0.5)]);
+        let frame = crate::evaluate_frame(&proj, 1.0, crate::EvalTarget::Preview);
+        assert!(diagnose(&frame.fractal, &frame.camera, WIDTH, None).is_empty());
+    }
+
+    #[test]
+    fn flat_frames_are_reported_only_when_unexplained() {
+        let black = vec![0u8; 64 * 48 * 4];
+        let stats = FrameStats::of_rgba(&black).unwrap();
+        assert!(stats.is_flat());
+        assert_eq!(stats.mean, 0.0);
+        let checker: Vec<u8> = (0..64 * 48)
+            .flat_map(|i| {
+                if (i + i / 64) % 2 == 0 {
+                    [0, 0, 0, 255]
+                } else {
+                    [255; 4]
+                }
+            })
+            .collect();
+        assert!(!FrameStats::of_rgba(&checker).unwrap().is_flat());
+        assert_eq!(FrameStats::of_rgba(&[]), None);
+
+        let params = FractalParams::default();
+        assert_eq!(
+            diagnose(&params, &overview(), WIDTH, Some(&stats)),
+            vec![Diagnosis::FlatFrame { mean: 0.0 }]
+        );
+        let dark = FractalParams {
+            exposure: 0.0,
+            ..params
+        };
+        let found = diagnose(&dark, &overview(), WIDTH, Some(&stats));
+        assert_eq!(found, vec![Diagnosis::Exposure { exposure: 0.0 }]);
+        assert!(found[0].to_string().contains("black"));
+    }
+}
@@ -0,0 +1,282 @@
+//! Histogram-equalized palette mapping: a frame's smooth iteration counts are spread
+//! evenly over the palette, so deep zooms whose pixels crowd into a narrow band of counts
+//! still use all of its colors.
+
+use std::borrow::Cow;
+use std::collections::{btree_map::Entry, BTreeMap};
+use std::sync::Arc;
+
+use crate::{sample_points_cpu, Camera, FractalParams, PixelSample};
+
+/// Quantiles of the smooth iteration count a [`PaletteEqualizer`] keeps.
+pub const EQUALIZER_LEVELS: usize = 256;
+
+/// Most pixels per side [`measure_equalizer`] samples a frame at.
+pub const EQUALIZER_GRID: u32 = 128;
+
+/// The cumulative histogram of a frame's smooth iteration counts, as the counts at
+/// [`EQUALIZER_LEVELS`] evenly spaced ranks. Attached to [`FractalParams::equalizer`], it
+/// replaces [`FractalParams::palette_repeat`] in placing counts in the palette.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaletteEqualizer {
+    levels: Vec<f32>,
+}
+
+impl PaletteEqualizer {
+    /// The equalizer of the escaped points among `samples`; `None` when none escaped.
+    pub fn from_samples<'a>(samples: impl IntoIterator<Item = &'a PixelSample>) -> Option<Self> {
+        let mut counts: Vec<f32> = samples
+            .into_iter()
+            .filter(|s| !s.interior && s.smooth_iter.is_finite())
+            .map(|s| s.smooth_iter)
+            .collect();
+        if counts.is_empty() {
+            return None;
+        }
+        counts.sort_by(f32::total_cmp);
+        let last = (counts.len() - 1) as f32;
+        let levels = (0..EQUALIZER_LEVELS)
+            .map(|k| counts[(k as f32 / (EQUALIZER_LEVELS - 1) as f32 * last).round() as usize])
+            .collect();
+        Some(Self { levels })
+    }
+
+    /// The level-by-level mean of `equalizers`, for smoothing them over time; `None` when
+    /// there are none.
+    pub fn average<'a>(equalizers: impl IntoIterator<Item = &'a PaletteEqualizer>) -> Option<Self> {
+        let mut sum = vec![0.0f64; EQUALIZER_LEVELS];
+        let mut count = 0;
+        for equalizer in equalizers {
+            for (sum, &level) in sum.iter_mut().zip(&equalizer.levels) {
+                *sum += f64::from(level);
+            }
+            count += 1;
+        }
+        (count > 0).then(|| Self {
+            levels: sum
+                .into_iter()
+                .map(|s| (s / f64::from(count)) as f32)
+                .collect(),
+        })
+    }
+
+    /// An equalizer with `levels` as from [`PaletteEqualizer::levels`]; `None` unless they
+    /// are [`EQUALIZER_LEVELS`] finite counts, lowest first.
+    pub fn from_levels(levels: Vec<f32>) -> Option<Self> {
+        let valid = levels.len() == EQUALIZER_LEVELS
+            && levels.iter().all(|level| level.is_finite())
+            && levels.windows(2).all(|pair| pair[0] <= pair[1]);
+        valid.then_some(Self { levels })
+    }
+
+    /// The smooth iteration counts at each level, lowest first.
+    pub fn levels(&self) -> &[f32] {
+        &self.levels
+    }
+
+    /// Position of `smooth_iter` in the palette, from 0 up to just below 1: its rank in
+    /// the frame, interpolated between levels.
+    pub fn position(&self, smooth_iter: f32) -> f32 {
+        let levels = &self.levels;
+        let above = levels.partition_point(|&level| level <= smooth_iter);
+        if above == 0 {
+            return 0.0;
+        }
+        if above == levels.len() {
+            return (levels.len() - 1) as f32 / levels.len() as f32;
+        }
+        let (low, high) = (levels[above - 1], levels[above]);
+        let within = if high > low {
+            (smooth_iter - low) / (high - low)
+        } else {
+            0.0
+        };
+        ((above - 1) as f32 + within) / levels.len() as f32
+    }
+}
+
+impl FractalParams {
+    /// Whether the palette is equalized: asked for, and placed by the iteration count
+    /// rather than a distance estimate.
+    pub fn equalizes(&self) -> bool {
+        self.equalize && !self.estimates_distance()
+    }
+}
+
+/// Pixels of a `size` frame an equalizer is measured at: every pixel up to
+/// [`EQUALIZER_GRID`] per side, and an evenly spread grid of them beyond.
+fn equalizer_grid(size: (u32, u32)) -> Vec<[u32; 2]> {
+    let axis = |len: u32| -> Vec<u32> {
+        let n = len.min(EQUALIZER_GRID);
+        (0..n)
+            .map(|i| ((u64::from(i) * 2 + 1) * u64::from(len) / (u64::from(n) * 2)) as u32)
+            .collect()
+    };
+    let (xs, ys) = (axis(size.0), axis(size.1));
+    ys.iter()
+        .flat_map(|&y| xs.iter().map(move |&x| [x, y]))
+        .collect()
+}
+
+/// Samples `params` at [`equalizer_grid`] pixels of a `size` frame on the CPU and returns
+/// their equalizer.
+pub fn measure_equalizer(
+    size: (u32, u32),
+    params: &FractalParams,
+    cam: &Camera,
+) -> Option<PaletteEqualizer> {
+    let points: Vec<[f32; 2]> = equalizer_grid(size)
+        .into_iter()
+        .map(|[x, y]| [x as f32, y as f32])
+        .collect();
+    PaletteEqualizer::from_samples(&sample_points_cpu(&points, size, params, cam))
+}
+
+/// The equalizer of a whole frame's row-major `samples`, taken at the same pixels
+/// [`measure_equalizer`] samples, so it matches without iterating again.
+pub fn frame_equalizer(size: (u32, u32), samples: &[PixelSample]) -> Option<PaletteEqualizer> {
+    let at = |[x, y]: [u32; 2]| &samples[y as usize * size.0 as usize + x as usize];
+    PaletteEqualizer::from_samples(equalizer_grid(size).into_iter().map(at))
+}
+
+/// `params` with the equalizer of its `size` frame attached, when it equalizes the
+/// palette and the caller has not attached one already.
+pub fn with_equalizer<'a>(
+    params: &'a FractalParams,
+    cam: &Camera,
+    size: (u32, u32),
+) -> Cow<'a, FractalParams> {
+    if !params.equalizes() || params.equalizer.is_some() {
+        return Cow::Borrowed(params);
+    }
+    let mut params = params.clone();
+    params.equalizer = measure_equalizer(size, &params, cam).map(Arc::new);
+    Cow::Owned(params)
+}
+
+/// The measured equalizers of an export's recent frames, for averaging them over
+/// [`ExportSettings::equalize_smoothing`](crate::ExportSettings) frames so the colors do
+/// not pump as the histogram shifts.
+#[derive(Debug, Default)]
+pub struct EqualizerHistory {
+    measured: BTreeMap<u32, Option<PaletteEqualizer>>,
+}
+
+impl EqualizerHistory {
+    /// The mean equalizer of `frame` and the `window - 1` frames before it, measuring
+    /// each with `measure` the first time it is needed. A frame depends only on those
+    /// frames, so exports resumed partway match uninterrupted ones.
+    pub fn smoothed<E>(
+        &mut self,
+        frame: u32,
+        window: u32,
+        mut measure: impl FnMut(u32) -> Result<Option<PaletteEqualizer>, E>,
+    ) -> Result<Option<Arc<PaletteEqualizer>>, E> {
+        let first = (frame + 1).saturating_sub(window.max(1));
+        self.measured.retain(|&f, _| f >= first);
+        for f in first..=frame {
+            if let Entry::Vacant(entry) = self.measured.entry(f) {
+                entry.insert(measure(f)?);
+            }
+        }
+        let window = self
+            .measured
+            .range(first..=frame)
+            .filter_map(|(_, e)| e.as_ref());
+        Ok(PaletteEqualizer::average(window).map(Arc::new))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{render_cpu, Complex64, FractalKind};
+
+    fn escaped(counts: &[f32]) -> Vec<PixelSample> {
+        counts
+            .iter()
+            .map(|&smooth_iter| PixelSample {
+                smooth_iter,
+                ..PixelSample::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn positions_follow_the_rank_of_the_count() {
+        // Counts crowded near 0 spread over the palette as evenly as sparse ones.
+        let counts: Vec<f32> = (0..1000)
+            .map(|i| 100.0 * (i as f32 / 1000.0).powi(4))
+            .collect();
+        let equalizer = PaletteEqualizer::from_samples(&escaped(&counts)).unwrap();
+        for (i, &count) in counts.iter().enumerate().step_by(50) {
+            let rank = i as f32 / counts.len() as f32;
+            assert!((equalizer.position(count) - rank).abs() < 0.01, "{i}");
+        }
+        assert_eq!(equalizer.position(0.0), 0.0);
+        assert!(equalizer.position(1e6) < 1.0);
+        let positions: Vec<f32> = (0..=100)
+            .map(|i| equalizer.position(i as f32 * 0.01))
+            .collect();
+        assert!(positions.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn interior_points_do_not_count() {
+        let mut samples = escaped(&[10.0, 20.0]);
+        assert!(PaletteEqualizer::from_samples(&samples[..0]).is_none());
+        samples.push(PixelSample {
+            interior: true,
+            ..PixelSample::default()
+        });
+        let equalizer = PaletteEqualizer::from_samples(&samples).unwrap();
+        assert_eq!(equalizer.levels()[0], 10.0);
+        assert_eq!(equalizer.levels()[EQUALIZER_LEVELS - 1], 20.0);
+    }
+
+    #[test]
+    fn frame_samples_give_the_measured_equalizer() {
+        let params = FractalParams::new(FractalKind::Mandelbrot).with_max_iter(300);
+        let cam = Camera::new(Complex64::new(-0.75, 0.1), 400.0);
+        for size in [(40, 30), (300, 200)] {
+            let (_, _, samples) =
+                crate::render_image_samples(size, &params, &cam, &mut crate::CpuRenderer, 0)
+                    .unwrap();
+            assert_eq!(
+                frame_equalizer(size, &samples),
+                measure_equalizer(size, &params, &cam)
+            );
+        }
+    }
+
+    #[test]
+    fn equalizing_spreads_a_deep_zoom_over_the_palette() {
+        // Deep in the seahorse valley the counts share a narrow band, which covers a
+        // sliver of the palette unless equalized.
+        let cam = Camera::new(Complex64::new(-0.743_643_9, 0.131_825_9), 2e6);
+        let params = FractalParams::new(FractalKind::Mandelbrot).with_max_iter(2000);
+        let distinct = |params: &FractalParams| {
+            let pixels = render_cpu((64, 64), params, &cam);
+            let mut colors: Vec<&[u8]> = pixels.chunks_exact(4).collect();
+            colors.sort_unstable();
+            colors.dedup();
+            colors.len()
+        };
+        let equalized = params.clone().with_equalized_palette();
+        assert!(distinct(&equalized) > 2 * distinct(&params));
+    }
+
+    #[test]
+    fn history_averages_the_window_whatever_frame_it_starts_at() {
+        let measure = |f: u32| Ok::<_, ()>(PaletteEqualizer::from_samples(&escaped(&[f as f32])));
+        let mut history = EqualizerHistory::default();
+        let smoothed: Vec<_> = (0..6)
+            .map(|f| history.smoothed(f, 3, measure).unwrap().unwrap().levels()[0])
+            .collect();
+        assert_eq!(smoothed, [0.0, 0.5, 1.0, 2.0, 3.0, 4.0]);
+        // A resumed export measures the frames before its first one.
+        let mut resumed = EqualizerHistory::default();
+        let frame = resumed.smoothed(4, 3, measure).unwrap().unwrap();
+        assert_eq!(frame.levels()[0], smoothed[4]);
+    }
+}
@@ -0,0 +1,1123 @@
+//! Video export: frame rendering to PNGs and encoding with ffmpeg.
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    convert::Infallible,
+    fs,
+    io::{self, BufWriter, Write},
+    ops::Range,
+    path::Path,
+    sync::Arc,
+    time::Instant,
+};
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering as AtomicOrdering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use image::ImageError;
+use serde::{Deserialize, Serialize};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{
+    annotation_stamps, burn_in_text, calibrate_tile_size, evaluate_export_frame, frame_file_name,
+    frame_time, measure_equalizer, needs_reference, pass_file_name, poster_frame, refine_edges,
+    render_band, render_image_samples, render_image_stats, render_side_by_side,
+    side_by_side_camera, side_by_side_widths, stats::millis, with_equalizer, write_pass, Camera,
+    EqualizerHistory, EvaluatedFrame, ExportViewport, FrameHashes, FrameManifest, FrameSnapshot,
+    OrbitCache, OrbitKey, PaletteEqualizer, Precision, Project, ReferenceOrbit, Renderer,
+    ScriptRunner, ZoomReadout,
+};
+use crate::{
+    AdaptiveAa, AovPasses, ContactSheet, ExteriorMode, FractalParams, FrameMode, ManifestError,
+    QualityProfile, RenderError, RenderStats, SamplePattern, ScriptError, StatsLog, Stylize,
+    TextStamp, TileCalibration, TileTuning,
+};
+
+/// Output codec; each maps to a set of ffmpeg arguments.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum VideoCodec {
+    H264,
+    ProRes,
+    /// ProRes 4444 with an alpha channel.
+    #[serde(rename = "prores_4444")]
+    ProRes4444,
+    Vp9,
+    Av1,
+}
+
+impl Default for VideoCodec {
+    fn default() -> Self {
+        VideoCodec::H264
+    }
+}
+
+impl VideoCodec {
+    pub fn label(&self) -> &'static str {
+        match self {
+            VideoCodec::H264 => "H.264",
+            VideoCodec::ProRes => "ProRes 422",
+            VideoCodec::ProRes4444 => "ProRes 4444",
+            VideoCodec::Vp9 => "VP9",
+            VideoCodec::Av1 => "AV1",
+        }
+    }
+
+    pub fn ffmpeg_args(&self, crf: u8) -> Vec<String> {
+        match self {
+            VideoCodec::H264 => vec![
+                "-c:v".into(),
+                "libx264".into(),
+                "-pix_fmt".into(),
+                "yuv420p".into(),
+                "-crf".into(),
+                crf.to_string(),
+            ],
+            VideoCodec::ProRes => vec![
+                "-c:v".into(),
+                "prores_ks".into(),
+                "-profile:v".into(),
+                "3".into(),
+                "-pix_fmt".into(),
+                "yuv422p10le".into(),
+            ],
+            VideoCodec::ProRes4444 => vec![
+                "-c:v".into(),
+                "prores_ks".into(),
+                "-profile:v".into(),
+                "4".into(),
+                "-pix_fmt".into(),
+                "yuva444p10le".into(),
+            ],
+            VideoCodec::Vp9 => vec![
+                "-c:v".into(),
+                "libvpx-vp9".into(),
+                "-b:v".into(),
+                "0".into(),
+                "-crf".into(),
+                crf.to_string(),
+            ],
+            VideoCodec::Av1 => vec![
+                "-c:v".into(),
+                "libaom-av1".into(),
+                "-b:v".into(),
+                "0".into(),
+                "-crf".into(),
+                crf.to_string(),
+            ],
+        }
+    }
+
+    /// Audio encoder that fits the codec's usual containers.
+    pub fn audio_codec(&self) -> &'static str {
+        match self {
+            VideoCodec::H264 | VideoCodec::ProRes | VideoCodec::ProRes4444 => "aac",
+            VideoCodec::Vp9 | VideoCodec::Av1 => "libopus",
+        }
+    }
+
+    /// Whether the encoded video keeps the frames' alpha channel.
+    pub fn keeps_alpha(&self) -> bool {
+        *self == VideoCodec::ProRes4444
+    }
+
+    /// Warning for exporting `fractal` with this codec when its transparent exterior
+    /// would be lost; such frames are composited over the exterior color instead.
+    pub fn alpha_warning(&self, fractal: &FractalParams) -> Option<String> {
+        (fractal.exterior_mode == ExteriorMode::Transparent && !self.keeps_alpha()).then(|| {
+            format!(
+                "{} has no alpha channel; the transparent exterior is filled with the \
+                 exterior color. Use ProRes 4444 to keep it.",
+                self.label()
+            )
+        })
+    }
+}
+
+/// Output video parameters.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExportSettings {
+    pub width: u32,
+    pub height: u32,
+    pub fps: u32,
+    /// Seconds.
+    pub duration: f32,
+    /// Constant rate factor for codecs that use one; lower is better quality.
+    pub crf: u8,
+    pub codec: VideoCodec,
+    /// Iterations, sampling and tiling the frames are rendered with.
+    #[serde(default = "QualityProfile::final_quality")]
+    pub quality: QualityProfile,
+    /// Tile size of files before version 4, moved into [`ExportSettings::quality`] on load.
+    #[serde(default, rename = "tile_size", skip_serializing)]
+    pub(crate) legacy_tile_size: Option<u32>,
+    pub out_path: PathBuf,
+    /// Soundtrack muxed into the video, padded with silence or cut to its length.
+    #[serde(default)]
+    pub audio: Option<PathBuf>,
+    /// Compositing passes written to [`ExportSettings::passes_dir`] alongside the video.
+    #[serde(default)]
+    pub passes: AovPasses,
+    /// Frames whose RGBA8 pixels exceed this many MiB are rendered in bands and streamed to
+    /// disk instead of being held in memory.
+    #[serde(default = "default_frame_memory_mb")]
+    pub frame_memory_mb: u32,
+    /// Sample pattern of files before version 4, moved into [`ExportSettings::quality`]
+    /// on load.
+    #[serde(default, rename = "sample_pattern", skip_serializing)]
+    pub(crate) legacy_sample_pattern: Option<SamplePattern>,
+    /// Burns the zoom depth readout into the bottom-left corner of every frame.
+    #[serde(default)]
+    pub burn_in_zoom: bool,
+    /// Keeps the rendered frames and their [`FrameManifest`] here instead of in a temporary
+    /// folder, so an interrupted or edited export re-renders only the frames that changed.
+    #[serde(default)]
+    pub work_dir: Option<PathBuf>,
+    /// Seconds into the video of the poster written next to it; `None` picks the most
+    /// detailed of a few candidate frames.
+    #[serde(default)]
+    pub poster_time: Option<f32>,
+    /// Which of the main and Julia viewports the frames show.
+    #[serde(default)]
+    pub viewport: ExportViewport,
+    /// Grid of [`write_contact_sheet`], for looking over the animation before exporting.
+    #[serde(default)]
+    pub contact_sheet: ContactSheet,
+    /// Low-color, chunky-pixel look applied to every frame before encoding.
+    #[serde(default)]
+    pub stylize: Stylize,
+    /// Extra samples for the pixels on edges, taken after each frame renders.
+    #[serde(default)]
+    pub adaptive_aa: AdaptiveAa,
+    /// Frames an equalized palette is averaged over, this one and those before it; 1
+    /// equalizes every frame on its own.
+    #[serde(default = "default_equalize_smoothing")]
+    pub equalize_smoothing: u32,
+}
+
+fn default_frame_memory_mb() -> u32 {
+    512
+}
+
+fn default_equalize_smoothing() -> u32 {
+    1
+}
+
+impl Default for ExportSettings {
+    fn default() -> Self {
+        Self {
+            width: 1920,
+            height: 1080,
+            fps: 30,
+            duration: 5.0,
+            crf: 20,
+            codec: VideoCodec::default(),
+            quality: QualityProfile::final_quality(),
+            legacy_tile_size: None,
+            out_path: PathBuf::from("output.mp4"),
+            audio: None,
+            passes: AovPasses::default(),
+            frame_memory_mb: default_frame_memory_mb(),
+            legacy_sample_pattern: None,
+            burn_in_zoom: false,
+            work_dir: None,
+            poster_time: None,
+            viewport: ExportViewport::Main,
+            contact_sheet: ContactSheet::default(),
+            stylize: Stylize::default(),
+            adaptive_aa: AdaptiveAa::default(),
+            equalize_smoothing: default_equalize_smoothing(),
+        }
+    }
+}
+
+impl ExportSettings {
+    /// Defaults at `width`×`height`.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_fps(mut self, fps: u32) -> Self {
+        self.fps = fps;
+        self
+    }
+
+    pub fn with_duration(mut self, seconds: f32) -> Self {
+        self.duration = seconds;
+        self
+    }
+
+    pub fn with_codec(mut self, codec: VideoCodec) -> Self {
+        self.codec = codec;
+        self
+    }
+
+    pub fn with_out_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.out_path = path.into();
+        self
+    }
+
+    pub fn with_audio(mut self, path: impl Into<PathBuf>) -> Self {
+        self.audio = Some(path.into());
+        self
+    }
+
+    pub fn with_passes(mut self, passes: AovPasses) -> Self {
+        self.passes = passes;
+        self
+    }
+
+    pub fn with_frame_memory_mb(mut self, mb: u32) -> Self {
+        self.frame_memory_mb = mb;
+        self
+    }
+
+    pub fn with_quality(mut self, quality: QualityProfile) -> Self {
+        self.quality = quality;
+        self
+    }
+
+    pub fn with_sample_pattern(mut self, pattern: SamplePattern) -> Self {
+        self.quality.sample_pattern = pattern;
+        self
+    }
+
+    pub fn with_burn_in_zoom(mut self, burn_in: bool) -> Self {
+        self.burn_in_zoom = burn_in;
+        self
+    }
+
+    pub fn with_stylize(mut self, stylize: Stylize) -> Self {
+        self.stylize = stylize;
+        self
+    }
+
+    pub fn with_adaptive_aa(mut self, adaptive_aa: AdaptiveAa) -> Self {
+        self.adaptive_aa = adaptive_aa;
+        self
+    }
+
+    pub fn with_equalize_smoothing(mut self, frames: u32) -> Self {
+        self.equalize_smoothing = frames;
+        self
+    }
+
+    pub fn with_work_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.work_dir = Some(dir.into());
+        self
+    }
+
+    pub fn with_poster_time(mut self, seconds: f32) -> Self {
+        self.poster_time = Some(seconds);
+        self
+    }
+
+    pub fn with_viewport(mut self, viewport: ExportViewport) -> Self {
+        self.viewport = viewport;
+        self
+    }
+
+    /// Frames in the video: the duration at the frame rate, rounded.
+    pub fn frame_count(&self) -> u32 {
+        (f64::from(self.duration) * f64::from(self.fps)).round() as u32
+    }
+
+    /// [`ExportSettings::frame_memory_mb`] in bytes.
+    pub fn frame_memory(&self) -> u64 {
+        u64::from(self.frame_memory_mb) << 20
+    }
+
+    /// How each frame is rendered, or why frames of this size cannot be exported.
+    /// Compositing passes need whole frames in memory.
+    pub fn frame_mode(&self) -> Result<FrameMode, RenderError> {
+        let mode = FrameMode::for_size((self.width, self.height), self.frame_memory())?;
+        if mode != FrameMode::InMemory && !self.passes.selected().is_empty() {
+            return Err(RenderError::FrameSize(format!(
+                "compositing passes need whole frames in memory, and a {}×{} frame is over the {} MB frame memory",
+                self.width, self.height, self.frame_memory_mb
+            )));
+        }
+        Ok(mode)
+    }
+
+    /// Folder the compositing passes go to: `render_passes` for `render.mp4`.
+    pub fn passes_dir(&self) -> PathBuf {
+        let stem = self
+            .out_path
+            .file_stem()
+            .map_or("output".into(), |s| s.to_string_lossy());
+        self.out_path.with_file_name(format!("{stem}_passes"))
+    }
+
+    /// Poster image written alongside the video: `render_poster.png` for `render.mp4`.
+    pub fn poster_path(&self) -> PathBuf {
+        let stem = self
+            .out_path
+            .file_stem()
+            .map_or("output".into(), |s| s.to_string_lossy());
+        self.out_path.with_file_name(format!("{stem}_poster.png"))
+    }
+}
+
+// ------------------------- Export (blocking) -------------------------
+
+/// Why an export stopped.
+#[derive(thiserror::Error, Debug)]
+pub enum ExportError {
+    #[error("IO: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Image: {0}")]
+    Image(ImageError),
+    #[error("Render: {0}")]
+    Render(#[from] RenderError),
+    #[error("FFmpeg failed: {0}")]
+    Ffmpeg(String),
+    #[error("Script: {0}")]
+    Script(#[from] ScriptError),
+    #[error("Manifest: {0}")]
+    Manifest(#[from] ManifestError),
+    #[error("Cancelled")]
+    Cancelled,
+    #[error("Export worker stopped unexpectedly")]
+    Worker,
+    #[error("Compositing passes need a single viewport; export the main or Julia view")]
+    SideBySidePasses,
+    #[error("Compositing passes cannot be written for stylized frames; turn one of them off")]
+    StylizedPasses,
+}
+
+impl From<ImageError> for ExportError {
+    fn from(err: ImageError) -> Self {
+        ExportError::Image(err)
+    }
+}
+
+/// Frame counters shared between an export and whoever is watching it.
+#[derive(Default)]
+pub struct ExportProgress {
+    pub done: AtomicUsize,
+    pub total: AtomicUsize,
+    pub cancel: AtomicBool,
+    /// Receives one record per frame when set. Dropped after a failed write.
+    pub stats_log: Mutex<Option<StatsLog>>,
+    /// Tile sizes learned so far. Read before an auto-tiled export and updated if it has
+    /// to calibrate.
+    pub tile_sizes: Mutex<TileTuning>,
+}
+
+impl ExportProgress {
+    pub fn fraction(&self) -> (usize, usize) {
+        (
+            self.done.load(AtomicOrdering::Relaxed),
+            self.total.load(AtomicOrdering::Relaxed),
+        )
+    }
+
+    /// Writes `stats` to the stats log, if any. A failed write is reported once and ends
+    /// logging rather than the export.
+    pub fn record(&self, stats: &RenderStats) {
+        let mut log = self.stats_log.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(Err(e)) = log.as_mut().map(|log| log.record(stats)) {
+            eprintln!("Stopped writing render stats: {e}");
+            *log = None;
+        }
+    }
+}
+
+/// Summary of a finished export.
+#[derive(Debug)]
+pub struct ExportReport {
+    pub path: PathBuf,
+    pub bytes: u64,
+    pub elapsed: Duration,
+    /// Folder the compositing passes were written to, if any were.
+    pub passes: Option<PathBuf>,
+    /// Frames taken from the work directory instead of being rendered again.
+    pub reused_frames: u32,
+    /// Copy of the poster frame, unless the video has no frames.
+    pub poster: Option<PathBuf>,
+    /// Calibration run to pick auto tiles, if none had been learned for the renderer and size.
+    pub calibration: Option<TileCalibration>,
+    /// Pixels adaptive anti-aliasing refined, and all the pixels of the frames it ran on.
+    pub refined_pixels: Option<(u64, u64)>,
+}
+
+impl std::fmt::Display for ExportReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let secs = self.elapsed.as_secs();
+        write!(
+            f,
+            "{} ({}, {}m {:02}s)",
+            self.path.display(),
+            format_bytes(self.bytes),
+            secs / 60,
+            secs % 60
+        )?;
+        if let Some(dir) = &self.passes {
+            write!(f, ", passes in {}", dir.display())?;
+        }
+        if self.reused_frames > 0 {
+            write!(f, ", {} frames reused", self.reused_frames)?;
+        }
+        if let Some(calibration) = &self.calibration {
+            write!(f, ", calibrated {} px tiles", calibration.tile_size)?;
+        }
+        if let Some((refined, of)) = self.refined_pixels {
+            let percent = 100.0 * refined as f64 / of.max(1) as f64;
+            write!(f, ", {percent:.1}% of pixels refined")?;
+        }
+        Ok(())
+    }
+}
+
+/// Human-readable file size, e.g. `12.3 MB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+/// Last few non-empty lines of ffmpeg's stderr; the tail is where the actual error is.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn ffmpeg_error_summary(stderr: &[u8]) -> String {
+    let text = String::from_utf8_lossy(stderr);
+    let lines: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+    let tail = &lines[lines.len().saturating_sub(4)..];
+    if tail.is_empty() {
+        "no output".into()
+    } else {
+        tail.join("\n")
+    }
+}
+
+/// How [`write_png`] writes a frame: whole or in bands, and what is burned into it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameOptions<'a> {
+    pub mode: FrameMode,
+    /// Stamps the [`ZoomReadout`] for the frame into its bottom-left corner.
+    pub zoom_readout: bool,
+    /// Annotations drawn over the frame, from [`annotation_stamps`].
+    pub annotations: &'a [TextStamp],
+    /// Reduces the frame to a few colors in large pixels when enabled. Overlays are drawn
+    /// after it, at full resolution.
+    pub stylize: Stylize,
+    /// Refines the edges of each frame, or of each band of a banded one. Stylized frames
+    /// are left as they are.
+    pub adaptive_aa: AdaptiveAa,
+}
+
+impl From<FrameMode> for FrameOptions<'_> {
+    fn from(mode: FrameMode) -> Self {
+        Self {
+            mode,
+            zoom_readout: false,
+            annotations: &[],
+            stylize: Stylize::default(),
+            adaptive_aa: AdaptiveAa::default(),
+        }
+    }
+}
+
+/// Renders a `size` frame to the PNG at `path` in `quality`'s tiles, whole or in bands as
+/// `frame` says. `params` are expected to have had the profile applied already with
+/// [`QualityProfile::apply`]. Banded frames are encoded row by row as they render, so only
+/// one band is in memory at a time. The stats' encode time is the time spent writing the
+/// file.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_png<'a>(
+    path: &Path,
+    size: (u32, u32),
+    params: &FractalParams,
+    cam: &Camera,
+    renderer: &mut dyn Renderer,
+    quality: &QualityProfile,
+    frame: impl Into<FrameOptions<'a>>,
+) -> Result<RenderStats, ExportError> {
+    write_png_hashed(path, size, params, cam, renderer, quality, frame).map(|(stats, _)| stats)
+}
+
+/// [`write_png`], also returning the hex BLAKE3 hash of the pixels written, as
+/// [`png_pixel_hash`] reads it back.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_png_hashed<'a>(
+    path: &Path,
+    size: (u32, u32),
+    params: &FractalParams,
+    cam: &Camera,
+    renderer: &mut dyn Renderer,
+    quality: &QualityProfile,
+    frame: impl Into<FrameOptions<'a>>,
+) -> Result<(RenderStats, String), ExportError> {
+    let tile_size = quality.tile_size;
+    let frame = frame.into();
+    // Measured once for the whole frame, however it is rendered.
+    let equalized = with_equalizer(params, cam, size);
+    let params = &*equalized;
+    let readout = frame
+        .zoom_readout
+        .then(|| ZoomReadout::new(params.kind, cam, size.0 as f32).to_string());
+    if frame.stylize.enabled {
+        let style = frame.stylize;
+        let small_size = style.render_size(size);
+        let (mut small, mut stats) = render_image_stats(
+            small_size,
+            params,
+            &style.render_camera(cam),
+            renderer,
+            tile_size,
+        )?;
+        style.quantize(&mut small, small_size.0, params);
+        let rows = match frame.mode {
+            FrameMode::InMemory => size.1,
+            FrameMode::Banded(rows) => rows,
+        };
+        let (encode_ms, hash) = write_png_bands(path, size, rows, &frame, &readout, |band| {
+            Ok(style.upscale_rows(&small, small_size, size, band))
+        })?;
+        stats.encode_wait_ms = Some(encode_ms);
+        return Ok((stats, hash));
+    }
+    let FrameMode::Banded(rows) = frame.mode else {
+        let (mut pixels, mut stats) = render_image_stats(size, params, cam, renderer, tile_size)?;
+        let aa = &frame.adaptive_aa;
+        refine_rows(aa, &mut pixels, size, 0..size.1, params, cam, &mut stats);
+        for stamp in frame.annotations {
+            stamp.draw(&mut pixels, size, 0..size.1);
+        }
+        if let Some(text) = &readout {
+            burn_in_text(&mut pixels, size, 0..size.1, text);
+        }
+        let hash = blake3::hash(&pixels).to_hex().to_string();
+        let encode_started = Instant::now();
+        image::save_buffer(path, &pixels, size.0, size.1, image::ColorType::Rgba8)?;
+        stats.encode_wait_ms = Some(millis(encode_started.elapsed()));
+        return Ok((stats, hash));
+    };
+
+    let started = Instant::now();
+    let mut stats = RenderStats::new(renderer, size, params);
+    let (encode_ms, hash) = write_png_bands(path, size, rows, &frame, &readout, |band| {
+        let (mut pixels, band_stats) =
+            render_band(size, band.clone(), params, cam, renderer, tile_size)?;
+        stats.tile_ms.extend(band_stats.tile_ms);
+        let aa = &frame.adaptive_aa;
+        refine_rows(aa, &mut pixels, size, band, params, cam, &mut stats);
+        Ok(pixels)
+    })?;
+    stats.encode_wait_ms = Some(encode_ms);
+    stats.total_ms = millis(started.elapsed());
+    Ok((stats, hash))
+}
+
+/// Runs `aa` over `rows` of a `size` frame, adding the pixels it refined and the time it
+/// took to `stats`.
+#[cfg(not(target_arch = "wasm32"))]
+fn refine_rows(
+    aa: &AdaptiveAa,
+    pixels: &mut [u8],
+    size: (u32, u32),
+    rows: Range<u32>,
+    params: &FractalParams,
+    cam: &Camera,
+    stats: &mut RenderStats,
+) {
+    if !aa.applies_to(params) {
+        return;
+    }
+    let started = Instant::now();
+    *stats.refined_pixels.get_or_insert(0) += refine_edges(pixels, size, rows, params, cam, aa);
+    stats.total_ms += millis(started.elapsed());
+}
+
+/// Encodes a `size` frame to the PNG at `path` `rows` at a time, as `band` produces them,
+/// with `frame`'s annotations and zoom `readout` drawn over each band. Returns the
+/// milliseconds spent encoding and the hex BLAKE3 hash of the pixels.
+#[cfg(not(target_arch = "wasm32"))]
+fn write_png_bands(
+    path: &Path,
+    size: (u32, u32),
+    rows: u32,
+    frame: &FrameOptions,
+    readout: &Option<String>,
+    mut band: impl FnMut(Range<u32>) -> Result<Vec<u8>, ExportError>,
+) -> Result<(f64, String), ExportError> {
+    let mut encoder = png::Encoder::new(BufWriter::new(fs::File::create(path)?), size.0, size.1);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(io::Error::from)?;
+    let mut stream = writer.stream_writer().map_err(io::Error::from)?;
+    let mut hasher = blake3::Hasher::new();
+    let mut encode_ms = 0.0;
+    for top in (0..size.1).step_by(rows.max(1) as usize) {
+        let rows = top..(top + rows.max(1)).min(size.1);
+        let mut pixels = band(rows.clone())?;
+        for stamp in frame.annotations {
+            stamp.draw(&mut pixels, size, rows.clone());
+        }
+        if let Some(text) = readout {
+            burn_in_text(&mut pixels, size, rows, text);
+        }
+        hasher.update(&pixels);
+        let encode_started = Instant::now();
+        stream.write_all(&pixels)?;
+        encode_ms += millis(encode_started.elapsed());
+    }
+    let encode_started = Instant::now();
+    stream.finish().map_err(io::Error::from)?;
+    writer.finish().map_err(io::Error::from)?;
+    Ok((
+        encode_ms + millis(encode_started.elapsed()),
+        hasher.finalize().to_hex().to_string(),
+    ))
+}
+
+/// Renders `left` and `right` side by side into the PNG at `path`, as
+/// [`render_side_by_side`] lays them out, and returns the stats and pixel hash like
+/// [`write_png_hashed`]. Overlays are placed for the whole frame, and the zoom readout
+/// reads the left view. The frame is always rendered whole.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_side_by_side_png<'a>(
+    path: &Path,
+    size: (u32, u32),
+    left: &EvaluatedFrame,
+    right: &EvaluatedFrame,
+    renderer: &mut dyn Renderer,
+    quality: &QualityProfile,
+    frame: impl Into<FrameOptions<'a>>,
+) -> Result<(RenderStats, String), ExportError> {
+    let frame = frame.into();
+    let (mut pixels, mut stats) = if frame.stylize.enabled {
+        let style = frame.stylize;
+        let small_size = style.render_size(size);
+        let small_view = |view: &EvaluatedFrame| EvaluatedFrame {
+            camera: style.render_camera(&view.camera),
+            ..view.clone()
+        };
+        let (mut small, stats) = render_side_by_side(
+            small_size,
+            &small_view(left),
+            &small_view(right),
+            renderer,
+            quality.tile_size,
+        )?;
+        style.quantize(&mut small, small_size.0, &left.fractal);
+        (
+            style.upscale_rows(&small, small_size, size, 0..size.1),
+            stats,
+        )
+    } else {
+        render_side_by_side(size, left, right, renderer, quality.tile_size)?
+    };
+    for stamp in frame.annotations {
+        stamp.draw(&mut pixels, size, 0..size.1);
+    }
+    if frame.zoom_readout {
+        let width = side_by_side_widths(size.0).0 as f32;
+        let readout = ZoomReadout::new(left.fractal.kind, &left.camera, width);
+        burn_in_text(&mut pixels, size, 0..size.1, &readout.to_string());
+    }
+    let hash = blake3::hash(&pixels).to_hex().to_string();
+    let encode_started = Instant::now();
+    image::save_buffer(path, &pixels, size.0, size.1, image::ColorType::Rgba8)?;
+    stats.encode_wait_ms = Some(millis(encode_started.elapsed()));
+    Ok((stats, hash))
+}
+
+/// The views export frame `frame` of `proj` shows, with `script` applied: the main or
+/// Julia view, and for a side-by-side frame also the Julia view of its right half.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn export_views(
+    proj: &Project,
+    script: Option<&ScriptRunner>,
+    frame: u32,
+) -> Result<(EvaluatedFrame, Option<EvaluatedFrame>), ExportError> {
+    let mut p = evaluate_export_frame(proj, frame);
+    if let Some(script) = script {
+        script.apply(frame_time(frame, proj.export.fps), &mut p)?;
+    }
+    // A solid exterior is the transparent one composited over the exterior color.
+    if proj.export.codec.alpha_warning(&p.fractal).is_some() {
+        p.fractal.exterior_mode = ExteriorMode::Solid;
+    }
+    Ok(match proj.export.viewport {
+        ExportViewport::Main => (p, None),
+        ExportViewport::Julia => (proj.julia_view.frame(&p), None),
+        ExportViewport::SideBySide => {
+            let julia = proj.julia_view.frame(&p);
+            (p, Some(julia))
+        }
+    })
+}
+
+/// Renders every frame of `proj` to temporary PNGs and encodes them with the `ffmpeg`
+/// executable, rendering with `renderer`. Blocks until done; poll `progress` from another
+/// thread to watch or cancel. Frames depend only on the authored project (see
+/// [`Project::authored`]) and their index, never on where the preview was left.
+/// Not available in the browser build.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn export_video_blocking(
+    proj: &Project,
+    ffmpeg: &Path,
+    progress: &ExportProgress,
+    renderer: &mut dyn Renderer,
+) -> Result<ExportReport, ExportError> {
+    let started = Instant::now();
+    let proj = &proj.authored();
+    let tmp;
+    let dir = match &proj.export.work_dir {
+        Some(dir) => {
+            fs::create_dir_all(dir)?;
+            dir.as_path()
+        }
+        None => {
+            tmp = tempfile::tempdir()?;
+            tmp.path()
+        }
+    };
+    let total = proj.export.frame_count();
+    // Only a work directory outlives the export, so only it gets a manifest.
+    let mut manifest = match &proj.export.work_dir {
+        Some(_) => Some(FrameManifest::load_for_resume(dir)?),
+        None => None,
+    };
+    if let Some(manifest) = &mut manifest {
+        prune_frames(dir, total, manifest)?;
+    }
+    let mut manifest_saved = Instant::now();
+    let mut reused_frames = 0;
+    progress
+        .total
+        .store(total as usize, AtomicOrdering::Relaxed);
+
+    let frame_options = FrameOptions {
+        mode: proj.export.frame_mode()?,
+        zoom_readout: proj.export.burn_in_zoom,
+        annotations: &[],
+        stylize: proj.export.stylize,
+        adaptive_aa: proj.export.adaptive_aa,
+    };
+    let script = ScriptRunner::for_project(proj)?;
+    let passes = proj.export.passes.selected();
+    let passes_dir = proj.export.passes_dir();
+    let side_by_side = proj.export.viewport == ExportViewport::SideBySide;
+    if side_by_side && !passes.is_empty() {
+        return Err(ExportError::SideBySidePasses);
+    }
+    if proj.export.stylize.enabled && !passes.is_empty() {
+        return Err(ExportError::StylizedPasses);
+    }
+    if !passes.is_empty() {
+        fs::create_dir_all(&passes_dir)?;
+    }
+    let mut quality = proj.export.quality.clone();
+    let mut calibration = None;
+    let mut refined_pixels = None;
+    // Consecutive frames of a deep zoom usually share a reference orbit.
+    let mut reference_orbits = OrbitCache::default();
+    let mut equalizers = EqualizerHistory::default();
+
+    for frame in 0..total {
+        let time = frame_time(frame, proj.export.fps);
+        let (mut p, julia) = export_views(proj, script.as_ref(), frame)?;
+
+        let size = (proj.export.width, proj.export.height);
+        // Side-by-side frames equalize each view on its own as it renders.
+        if julia.is_none() && p.fractal.equalizes() {
+            let window = proj.export.equalize_smoothing;
+            p.fractal.equalizer = equalizers.smoothed(frame, window, |f| {
+                let (mut view, _) = export_views(proj, script.as_ref(), f)?;
+                if needs_reference(&view.fractal, Precision::Perturbation) {
+                    let reference = cached_reference(&mut reference_orbits, &view);
+                    view.fractal.reference = Some(reference);
+                }
+                Ok::<_, ExportError>(measure_equalizer(size, &view.fractal, &view.camera))
+            })?;
+        }
+        let path = dir.join(frame_file_name(frame));
+        let stamps = match julia {
+            // Annotations follow the main view, on the left half.
+            Some(_) => {
+                let camera = side_by_side_camera(&p.camera, size);
+                annotation_stamps(&proj.annotations, time, &camera, size)
+            }
+            None => annotation_stamps(&proj.annotations, time, &p.camera, size),
+        };
+        let refines = julia.is_none()
+            && !proj.export.stylize.enabled
+            && proj.export.adaptive_aa.applies_to(&p.fractal);
+        let params_hash = FrameSnapshot {
+            size,
+            fractal: &p.fractal,
+            sampling: p.fractal.sampling,
+            precision: p.fractal.precision,
+            camera: &p.camera,
+            zoom_readout: frame_options.zoom_readout,
+            annotations: &stamps,
+            side_by_side: julia.as_ref().map(|j| (&j.fractal, &j.camera)),
+            stylize: proj.export.stylize.enabled.then_some(&proj.export.stylize),
+            adaptive_aa: refines.then_some(&proj.export.adaptive_aa),
+            equalizer: p.fractal.equalizer.as_deref().map(PaletteEqualizer::levels),
+        }
+        .hash();
+        let reusable = manifest
+            .as_ref()
+            .is_some_and(|m| m.is_current(dir, frame, &params_hash))
+            && passes.iter().all(|&pass| {
+                passes_dir
+                    .join(pass_file_name(frame, pass, proj.export.passes.format))
+                    .is_file()
+            });
+        if reusable {
+            reused_frames += 1;
+            progress
+                .done
+                .store(frame as usize + 1, AtomicOrdering::Relaxed);
+            continue;
+        }
+
+        // The main view of a side-by-side frame renders with its own camera on its half.
+        let orbit_counts = needs_reference(&p.fractal, renderer.precision()).then(|| {
+            p.fractal.reference = Some(cached_reference(&mut reference_orbits, &p));
+            reference_orbits.take_counts()
+        });
+        if quality.tile_size == 0 {
+            quality.tile_size = auto_tile_size(progress, size, &p, renderer, &mut calibration)?;
+        }
+
+        let (mut stats, pixels_hash) = if let Some(julia) = &julia {
+            write_side_by_side_png(
+                &path,
+                size,
+                &p,
+                julia,
+                renderer,
+                &quality,
+                FrameOptions {
+                    annotations: &stamps,
+                    ..frame_options
+                },
+            )?
+        } else if passes.is_empty() {
+            write_png_hashed(
+                &path,
+                size,
+                &p.fractal,
+                &p.camera,
+                renderer,
+                &quality,
+                FrameOptions {
+                    annotations: &stamps,
+                    ..frame_options
+                },
+            )?
+        } else {
+            let (mut pixels, mut stats, samples) =
+                render_image_samples(size, &p.fractal, &p.camera, renderer, quality.tile_size)?;
+            let aa = &proj.export.adaptive_aa;
+            refine_rows(
+                aa,
+                &mut pixels,
+                size,
+                0..size.1,
+                &p.fractal,
+                &p.camera,
+                &mut stats,
+            );
+            for &pass in &passes {
+                let path = passes_dir.join(pass_file_name(frame, pass, proj.export.passes.format));
+                write_pass(
+                    &path,
+                    size,
+                    &samples,
+                    &p.fractal,
+                    pass,
+                    proj.export.passes.format,
+                )?;
+            }
+            for stamp in &stamps {
+                stamp.draw(&mut pixels, size, 0..size.1);
+            }
+            if proj.export.burn_in_zoom {
+                let readout = ZoomReadout::new(p.fractal.kind, &p.camera, size.0 as f32);
+                burn_in_text(&mut pixels, size, 0..size.1, &readout.to_string());
+            }
+            let pixels_hash = blake3::hash(&pixels).to_hex().to_string();
+            let encode_started = Instant::now();
+            image::save_buffer(&path, &pixels, size.0, size.1, image::ColorType::Rgba8)?;
+            stats.encode_wait_ms = Some(millis(encode_started.elapsed()));
+            (stats, pixels_hash)
+        };
+        stats.frame = Some(frame);
+        stats.tile_size = Some(quality.tile_size);
+        stats.cache_hits = orbit_counts.map(|(hits, _)| hits);
+        stats.cache_misses = orbit_counts.map(|(_, misses)| misses);
+        if let Some(refined) = stats.refined_pixels {
+            let (total, of) = refined_pixels.get_or_insert((0, 0));
+            *total += refined;
+            *of += u64::from(size.0) * u64::from(size.1);
+        }
+        progress.record(&stats);
+        progress
+            .done
+            .store(frame as usize + 1, AtomicOrdering::Relaxed);
+        let cancelled = progress.cancel.load(AtomicOrdering::Relaxed);
+        if let Some(manifest) = &mut manifest {
+            manifest.frames.insert(
+                frame,
+                FrameHashes {
+                    params: params_hash,
+                    pixels: pixels_hash,
+                },
+            );
+            // Saved about once a second; frames missing after a crash just render again.
+            if cancelled || manifest_saved.elapsed() >= Duration::from_secs(1) {
+                manifest.save(dir)?;
+                manifest_saved = Instant::now();
+            }
+        }
+        if cancelled {
+            return Err(ExportError::Cancelled);
+        }
+    }
+    if let Some(manifest) = &manifest {
+        manifest.save(dir)?;
+    }
+    let poster = if total > 0 {
+        let frame = poster_frame(proj, renderer)?;
+        let path = proj.export.poster_path();
+        fs::copy(dir.join(frame_file_name(frame)), &path)?;
+        Some(path)
+    } else {
+        None
+    };
+
+    let mut args = vec![
+        "-y".into(),
+        "-framerate".into(),
+        proj.export.fps.to_string(),
+        "-i".into(),
+        format!("{}/frame_%06d.png", dir.display()),
+    ];
+    if let Some(audio) = &proj.export.audio {
+        args.extend(["-i".into(), audio.display().to_string()]);
+    }
+    args.extend(proj.export.codec.ffmpeg_args(proj.export.crf));
+    if proj.export.audio.is_some() {
+        args.extend(
+            [
+                "-map",
+                "0:v",
+                "-map",
+                "1:a",
+                "-c:a",
+                proj.export.codec.audio_codec(),
+                "-af",
+                "apad",
+                "-shortest",
+            ]
+            .map(String::from),
+        );
+    }
+    args.push(proj.export.out_path.display().to_string());
+
+    let output = std::process::Command::new(ffmpeg)
+        .args(args)
+        .output()
+        .map_err(|e| ExportError::Ffmpeg(format!("could not run {}: {e}", ffmpeg.display())))?;
+    if !output.status.success() {
+        return Err(ExportError::Ffmpeg(ffmpeg_error_summary(&output.stderr)));
+    }
+    Ok(ExportReport {
+        bytes: fs::metadata(&proj.export.out_path)
+            .map(|m| m.len())
+            .unwrap_or(0),
+        path: proj.export.out_path.clone(),
+        elapsed: started.elapsed(),
+        passes: (!passes.is_empty()).then_some(passes_dir),
+        reused_frames,
+        poster,
+        calibration,
+        refined_pixels,
+    })
+}
+
+/// The reference orbit of `view` from `cache`, computed there when it has none that serves.
+#[cfg(not(target_arch = "wasm32"))]
+fn cached_reference(
+    cache: &mut OrbitCache<Arc<ReferenceOrbit>>,
+    view: &EvaluatedFrame,
+) -> Arc<ReferenceOrbit> {
+    let cam = &view.camera;
+    cache
+        .get_or_compute(OrbitKey::new(&view.fractal, cam), |_| {
+            Ok::<_, Infallible>(Arc::new(ReferenceOrbit::compute(&view.fractal, cam)))
+        })
+        .expect("computing a reference orbit cannot fail")
+        .clone()
+}
+
+/// Tile size for auto-tiled frames of `size` with `renderer`: the one learned in
+/// `progress`, or else one calibrated on `frame` now, learned and kept in `calibration`.
+#[cfg(not(target_arch = "wasm32"))]
+fn auto_tile_size(
+    progress: &ExportProgress,
+    size: (u32, u32),
+    frame: &EvaluatedFrame,
+    renderer: &mut dyn Renderer,
+    calibration: &mut Option<TileCalibration>,
+) -> Result<u32, ExportError> {
+    let learned = progress
+        .tile_sizes
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(renderer.name(), size);
+    if let Some(tile_size) = learned {
+        return Ok(tile_size);
+    }
+    let found = calibrate_tile_size(size, &frame.fractal, &frame.camera, renderer)?;
+    progress
+        .tile_sizes
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .learn(&found);
+    let tile_size = found.tile_size;
+    *calibration = Some(found);
+    Ok(tile_size)
+}
+
+/// Deletes frames at or past `total` from a work directory and its manifest, so an export
+/// that got shorter does not encode the old tail.
+#[cfg(not(target_arch = "wasm32"))]
+fn prune_frames(dir: &Path, total: u32, manifest: &mut FrameManifest) -> Result<(), ExportError> {
+    manifest.frames.retain(|&frame, _| frame < total);
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let index = path
+            .file_name()
+            .and_then(|name| name.to_str()?.strip_prefix("frame_")?.strip_suffix(".png"))
+            .and_then(|index| index.parse::<u32>().ok());
+        if index.is_some_and(|index| index >= total) {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,303 @@
+//! Guards against NaN and infinite parameters. One bad value turns the whole frame black
+//! and, written to JSON as `null`, makes the project unreadable; these find such values and
+//! put sane ones back.
+
+use std::fmt;
+
+use crate::{Camera, EvaluatedFrame, FractalParams, Keyframe, Keyframes, Project};
+
+/// A NaN or infinite value that was found and dealt with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NonFinite {
+    /// Where the value was, e.g. `camera.scale` or `anim.kf_zoom`.
+    pub param: String,
+    pub value: f64,
+    /// What it was reset to; `None` when the key holding it was removed.
+    pub replaced_with: Option<f64>,
+}
+
+impl fmt::Display for NonFinite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.replaced_with {
+            Some(v) => write!(f, "{} was {}, reset to {v}", self.param, self.value),
+            None => write!(f, "{} key with {} removed", self.param, self.value),
+        }
+    }
+}
+
+/// One line listing `repairs`, for a warning.
+pub fn non_finite_warning(repairs: &[NonFinite]) -> String {
+    let repairs: Vec<String> = repairs.iter().map(ToString::to_string).collect();
+    format!("NaN or infinite values repaired: {}", repairs.join("; "))
+}
+
+/// A parameter of either float width.
+enum Float<'a> {
+    F32(&'a mut f32),
+    F64(&'a mut f64),
+}
+
+impl Float<'_> {
+    fn get(&self) -> f64 {
+        match self {
+            Float::F32(v) => f64::from(**v),
+            Float::F64(v) => **v,
+        }
+    }
+
+    fn set(&mut self, value: f64) {
+        match self {
+            Float::F32(v) => **v = value as f32,
+            Float::F64(v) => **v = value,
+        }
+    }
+}
+
+impl<'a> From<&'a mut f32> for Float<'a> {
+    fn from(v: &'a mut f32) -> Self {
+        Float::F32(v)
+    }
+}
+
+impl<'a> From<&'a mut f64> for Float<'a> {
+    fn from(v: &'a mut f64) -> Self {
+        Float::F64(v)
+    }
+}
+
+/// The floats of a frame's fractal and camera, by name.
+fn frame_floats<'a>(
+    fractal: &'a mut FractalParams,
+    camera: &'a mut Camera,
+) -> [(&'static str, Float<'a>); 21] {
+    let [r, g, b] = &mut fractal.orbit.color;
+    [
+        ("camera.center.re", (&mut camera.center.re).into()),
+        ("camera.center.im", (&mut camera.center.im).into()),
+        ("camera.scale", (&mut camera.scale).into()),
+        ("camera.rotation", (&mut camera.rotation).into()),
+        ("fractal.escape_radius", (&mut fractal.escape_radius).into()),
+        ("fractal.power", (&mut fractal.power).into()),
+        ("fractal.c.re", (&mut fractal.c.re).into()),
+        ("fractal.c.im", (&mut fractal.c.im).into()),
+        ("fractal.phoenix_p.re", (&mut fractal.phoenix_p.re).into()),
+        ("fractal.phoenix_p.im", (&mut fractal.phoenix_p.im).into()),
+        ("fractal.palette_phase", (&mut fractal.palette_phase).into()),
+        (
+            "fractal.palette_cycle_speed",
+            (&mut fractal.palette_cycle_speed).into(),
+        ),
+        (
+            "fractal.palette_repeat",
+            (&mut fractal.palette_repeat).into(),
+        ),
+        ("fractal.exposure", (&mut fractal.exposure).into()),
+        ("fractal.gamma", (&mut fractal.gamma).into()),
+        ("fractal.stripe_mix", (&mut fractal.stripe_mix).into()),
+        ("fractal.orbit.radius", (&mut fractal.orbit.radius).into()),
+        (
+            "fractal.orbit.softness",
+            (&mut fractal.orbit.softness).into(),
+        ),
+        ("fractal.orbit.color.r", r.into()),
+        ("fractal.orbit.color.g", g.into()),
+        ("fractal.orbit.color.b", b.into()),
+    ]
+}
+
+/// Resets each non-finite float of `fractal` and `camera` to `fallback`'s, or to the
+/// default project's where that is not finite either.
+fn repair_floats(
+    fractal: &mut FractalParams,
+    camera: &mut Camera,
+    fallback: (&FractalParams, &Camera),
+) -> Vec<NonFinite> {
+    let (mut fallback_fractal, mut fallback_camera) = (fallback.0.clone(), fallback.1.clone());
+    let defaults = Project::default();
+    let (mut default_fractal, mut default_camera) = (defaults.fractal, defaults.camera);
+    let fallback = frame_floats(&mut fallback_fractal, &mut fallback_camera);
+    let defaults = frame_floats(&mut default_fractal, &mut default_camera);
+    let mut repairs = Vec::new();
+    for (((param, mut value), (_, fallback)), (_, default)) in frame_floats(fractal, camera)
+        .into_iter()
+        .zip(fallback)
+        .zip(defaults)
+    {
+        if value.get().is_finite() {
+            continue;
+        }
+        let replacement = if fallback.get().is_finite() {
+            fallback.get()
+        } else {
+            default.get()
+        };
+        repairs.push(NonFinite {
+            param: param.into(),
+            value: value.get(),
+            replaced_with: Some(replacement),
+        });
+        value.set(replacement);
+    }
+    repairs
+}
+
+/// Resets NaN and infinite values of an evaluated frame to the authored ones in `proj`.
+pub fn repair_frame(frame: &mut EvaluatedFrame, proj: &Project) -> Vec<NonFinite> {
+    repair_floats(
+        &mut frame.fractal,
+        &mut frame.camera,
+        (&proj.fractal, &proj.camera),
+    )
+}
+
+/// Removes keys whose time or value is not finite; `values` lists a key's numbers.
+fn repair_keys<T>(
+    param: &str,
+    keys: &mut Keyframes<T>,
+    values: impl Fn(&Keyframe<T>) -> Vec<f32>,
+    repairs: &mut Vec<NonFinite>,
+) {
+    keys.keys.retain(|key| {
+        let bad = values(key).into_iter().find(|v| !v.is_finite());
+        if let Some(value) = bad {
+            repairs.push(NonFinite {
+                param: param.into(),
+                value: f64::from(value),
+                replaced_with: None,
+            });
+        }
+        bad.is_none()
+    });
+}
+
+/// Resets `value` to `default` if it is not finite.
+fn repair_value<'a>(
+    param: &str,
+    value: impl Into<Float<'a>>,
+    default: f64,
+    repairs: &mut Vec<NonFinite>,
+) {
+    let mut value = value.into();
+    if !value.get().is_finite() {
+        repairs.push(NonFinite {
+            param: param.into(),
+            value: value.get(),
+            replaced_with: Some(default),
+        });
+        value.set(default);
+    }
+}
+
+/// Resets NaN and infinite parameters of `proj` to their defaults and removes keyframes
+/// holding one.
+pub fn repair_project(proj: &mut Project) -> Vec<NonFinite> {
+    let defaults = Project::default();
+    let mut repairs = repair_floats(
+        &mut proj.fractal,
+        &mut proj.camera,
+        (&defaults.fractal, &defaults.camera),
+    );
+    let anim = &mut proj.anim;
+    repair_value("anim.t", &mut anim.t, 0.0, &mut repairs);
+    repair_value(
+        "anim.duration",
+        &mut anim.duration,
+        f64::from(defaults.anim.duration),
+        &mut repairs,
+    );
+    repair_value(
+        "export.duration",
+        &mut proj.export.duration,
+        f64::from(defaults.export.duration),
+        &mut repairs,
+    );
+    for (param, keys) in [
+        ("anim.kf_zoom", &mut anim.kf_zoom),
+        ("anim.kf_palette", &mut anim.kf_palette),
+        ("anim.kf_center_x", &mut anim.kf_center_x),
+        ("anim.kf_center_y", &mut anim.kf_center_y),
+        ("anim.kf_rotation", &mut anim.kf_rotation),
+        ("anim.kf_julia_re", &mut anim.kf_julia_re),
+        ("anim.kf_julia_im", &mut anim.kf_julia_im),
+    ] {
+        repair_keys(param, keys, |key| vec![key.t, key.v], &mut repairs);
+    }
+    repair_keys(
+        "anim.kf_trap_color",
+        &mut anim.kf_trap_color,
+        |key| vec![key.t, key.v[0], key.v[1], key.v[2]],
+        &mut repairs,
+    );
+    if let Some(zoom) = &mut anim.zoom_forever {
+        let camera_scale = proj.camera.scale;
+        repair_value(
+            "anim.zoom_forever.start_scale",
+            &mut zoom.start_scale,
+            camera_scale,
+            &mut repairs,
+        );
+        repair_value(
+            "anim.zoom_forever.speed",
+            &mut zoom.speed,
+            0.9,
+            &mut repairs,
+        );
+        repair_value(
+            "anim.zoom_forever.rotation_speed",
+            &mut zoom.rotation_speed,
+            0.0,
+            &mut repairs,
+        );
+    }
+    if let Some(orbit) = &mut anim.julia_orbit {
+        repair_value(
+            "anim.julia_orbit.speed",
+            &mut orbit.speed,
+            0.1,
+            &mut repairs,
+        );
+        repair_value(
+            "anim.julia_orbit.phase",
+            &mut orbit.phase,
+            0.0,
+            &mut repairs,
+        );
+    }
+    repairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn non_finite_project_values_are_reset_or_removed() {
+        let mut proj = Project::default();
+        proj.camera.scale = f64::NAN;
+        proj.fractal.orbit.color[1] = f32::INFINITY;
+        proj.anim.kf_zoom.upsert(0.0, 100.0);
+        proj.anim.kf_zoom.upsert(1.0, f32::INFINITY);
+        proj.anim.kf_palette.keys.push(Keyframe {
+            t: f32::NAN,
+            v: 0.5,
+            easing: Default::default(),
+        });
+        let repairs = repair_project(&mut proj);
+        let params: Vec<&str> = repairs.iter().map(|r| r.param.as_str()).collect();
+        assert_eq!(
+            params,
+            [
+                "camera.scale",
+                "fractal.orbit.color.g",
+                "anim.kf_zoom",
+                "anim.kf_palette"
+            ]
+        );
+        assert_eq!(proj.camera.scale, Camera::default().scale);
+        assert_eq!(proj.anim.kf_zoom.keys.len(), 1);
+        assert!(proj.anim.kf_palette.keys.is_empty());
+        assert_eq!(repairs[0].to_string(), "camera.scale was NaN, reset to 300");
+        assert_eq!(repairs[2].to_string(), "anim.kf_zoom key with inf removed");
+        assert!(repair_project(&mut proj).is_empty());
+    }
+}
@@ -0,0 +1,1610 @@
+//! Fractal formulas behind one trait, and the registry that resolves a [`FractalKind`].
+
+use std::borrow::Cow;
+
+use crate::{
+    attached_formula, escape_smooth_iteration, with_compiled_formula, Camera, ColoringMode,
+    Complex, Complex64, CustomFormula, FractalKind, FractalParams, OrbitResult, OrbitTrapKind,
+    TrackKind, ZoomReadout,
+};
+
+/// One iteration formula. Implementations are stateless and registered in [`formulas`].
+pub trait FractalFormula: Sync {
+    /// Serialized identifier this formula is resolved from.
+    fn kind(&self) -> FractalKind;
+
+    fn name(&self) -> &'static str;
+
+    /// Julia mode: the pixel seeds `z` and `params.c` is the constant.
+    fn julia_mode(&self) -> bool {
+        false
+    }
+
+    /// Center and visible plane width that frame the whole set.
+    fn overview(&self) -> (Complex, f32) {
+        (Complex::new(-0.5, 0.0), 3.5)
+    }
+
+    /// Formula-specific parameters, in the order the UI shows them. Shared parameters
+    /// (`max_iter`, `escape_radius`) are not listed.
+    fn params(&self) -> &'static [FormulaParam] {
+        &[]
+    }
+
+    /// Max Iter at the overview, and how much to add per tenfold zoom past it; see
+    /// [`suggested_max_iter`].
+    fn iteration_budget(&self) -> (f32, f32) {
+        (300.0, 1000.0)
+    }
+
+    /// Camera showing [`FractalFormula::overview`] across `view_width` pixels.
+    fn default_camera(&self, view_width: f32) -> Camera {
+        let (center, width) = self.overview();
+        Camera::new(center, f64::from(view_width / width))
+    }
+
+    /// Degree of the step's leading term, `|z|` growing to about `|z|^degree` per
+    /// iteration once it escapes; smooth coloring normalizes by it.
+    fn degree(&self, _p: &FractalParams) -> f32 {
+        2.0
+    }
+
+    /// Continuous iteration count of an orbit that escaped, or for formulas that
+    /// converge instead, one that converged; see [`crate::smooth_iteration`].
+    fn smooth_iteration(&self, p: &FractalParams, orbit: &OrbitResult) -> f32 {
+        escape_smooth_iteration(p, orbit, self.degree(p))
+    }
+
+    /// Derivative of the next `z` from the derivative `dz` of `z`, taken with respect to
+    /// the pixel: `c`, or the seed `z` in Julia mode. `None` leaves the formula without a
+    /// distance estimate, and [`ColoringMode::DistanceEstimate`](crate::ColoringMode) falls
+    /// back to the iteration count.
+    fn derivative(&self, _p: &FractalParams, _z: Complex64, _dz: Complex64) -> Option<Complex64> {
+        None
+    }
+
+    /// One iteration: `z` to the next `z` for constant `c`.
+    fn step(&self, p: &FractalParams, z: Complex, c: Complex) -> Complex;
+
+    /// [`FractalFormula::step`] for formulas that also read `prev`, the `z` of the step
+    /// before, which is 0 on the first step. The default ignores it.
+    fn step_with_previous(
+        &self,
+        p: &FractalParams,
+        z: Complex,
+        _prev: Complex,
+        c: Complex,
+    ) -> Complex {
+        self.step(p, z, c)
+    }
+
+    /// WGSL statements assigning the next `z`. In scope: `z`, `prev`, `c`, `zx`, `zy`, `x2`,
+    /// `y2`, `params`. `None` keeps the formula CPU-only.
+    fn wgsl_step(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Whether the WGSL step renders `p`; formulas with CPU-only variants override this.
+    fn gpu_supports(&self, _p: &FractalParams) -> bool {
+        self.wgsl_step().is_some()
+    }
+
+    /// Full escape-time evaluation of plane point (`rx`, `ry`). Override for formulas that
+    /// are not a plain `step` loop.
+    fn iterate(&self, p: &FractalParams, rx: f32, ry: f32) -> OrbitResult {
+        iterate_orbit(self, p, rx, ry)
+    }
+
+    /// [`FractalFormula::step`] in double precision. The default goes through `step`, so
+    /// a formula without its own renders in [`Precision::F64`](crate::Precision) at `f32`
+    /// depth.
+    fn step_f64(&self, p: &FractalParams, z: Complex64, c: Complex64) -> Complex64 {
+        self.step(p, z.to_f32(), c.to_f32()).into()
+    }
+
+    /// [`FractalFormula::step_with_previous`] in double precision.
+    fn step_with_previous_f64(
+        &self,
+        p: &FractalParams,
+        z: Complex64,
+        _prev: Complex64,
+        c: Complex64,
+    ) -> Complex64 {
+        self.step_f64(p, z, c)
+    }
+
+    /// [`FractalFormula::iterate`] in double precision; override it together with
+    /// `iterate`.
+    fn iterate_f64(&self, p: &FractalParams, rx: f64, ry: f64) -> OrbitResult {
+        iterate_orbit_f64(self, p, rx, ry)
+    }
+}
+
+/// The standard escape-time loop with orbit-trap tracking, driving
+/// `formula.step_with_previous`.
+pub fn iterate_orbit<F: FractalFormula + ?Sized>(
+    formula: &F,
+    p: &FractalParams,
+    rx: f32,
+    ry: f32,
+) -> OrbitResult {
+    let er2 = p.escape_radius * p.escape_radius;
+    let pixel = Complex::new(rx, ry);
+    let (mut z, c) = if formula.julia_mode() {
+        (pixel, p.c)
+    } else {
+        (Complex::new(0.0, 0.0), pixel)
+    };
+
+    let mut prev = Complex::new(0.0, 0.0);
+    let mut dz = p.estimates_distance().then(|| derivative_seed(formula));
+    let mut stripes = StripeSum::new(p);
+    let mut i = 0u32;
+    let mut trap_min = f32::MAX;
+    while i < p.max_iter {
+        if z.re * z.re + z.im * z.im > er2 {
+            break;
+        }
+
+        if let Some(d) = dz {
+            dz = formula.derivative(p, z.into(), d);
+        }
+        let next = formula.step_with_previous(p, z, prev, c);
+        prev = z;
+        z = next;
+        if let Some(stripes) = &mut stripes {
+            stripes.add(z.into());
+        }
+
+        if p.orbit.enabled {
+            let dist = match p.orbit.kind {
+                OrbitTrapKind::Point => (z.re - p.orbit.point.re).hypot(z.im - p.orbit.point.im),
+                OrbitTrapKind::Circle => {
+                    ((z.re * z.re + z.im * z.im).sqrt() - p.orbit.radius).abs()
+                }
+                OrbitTrapKind::Cross => (z.re - p.orbit.point.re)
+                    .abs()
+                    .min((z.im - p.orbit.point.im).abs()),
+            };
+            trap_min = trap_min.min(dist);
+        }
+
+        i += 1;
+    }
+
+    OrbitResult {
+        iter: i,
+        zx: z.re,
+        zy: z.im,
+        trap_min,
+        distance: dz.map(|dz| distance_estimate(z.into(), dz)),
+        stripe: stripes.map(|stripes| stripes.averages()),
+        root: None,
+        lyapunov: None,
+    }
+}
+
+/// [`iterate_orbit`] in double precision, driving `formula.step_with_previous_f64`.
+pub fn iterate_orbit_f64<F: FractalFormula + ?Sized>(
+    formula: &F,
+    p: &FractalParams,
+    rx: f64,
+    ry: f64,
+) -> OrbitResult {
+    let er2 = f64::from(p.escape_radius) * f64::from(p.escape_radius);
+    let pixel = Complex64::new(rx, ry);
+    let (mut z, c) = if formula.julia_mode() {
+        (pixel, p.c.into())
+    } else {
+        (Complex64::new(0.0, 0.0), pixel)
+    };
+
+    let mut prev = Complex64::new(0.0, 0.0);
+    let mut dz = p.estimates_distance().then(|| derivative_seed(formula));
+    let mut stripes = StripeSum::new(p);
+    let mut i = 0u32;
+    let mut trap_min = f64::MAX;
+    while i < p.max_iter {
+        if z.re * z.re + z.im * z.im > er2 {
+            break;
+        }
+
+        if let Some(d) = dz {
+            dz = formula.derivative(p, z, d);
+        }
+        let next = formula.step_with_previous_f64(p, z, prev, c);
+        prev = z;
+        z = next;
+        if let Some(stripes) = &mut stripes {
+            stripes.add(z);
+        }
+
+        if p.orbit.enabled {
+            trap_min = trap_min.min(trap_distance_f64(p, z));
+        }
+
+        i += 1;
+    }
+
+    OrbitResult {
+        iter: i,
+        zx: z.re as f32,
+        zy: z.im as f32,
+        trap_min: trap_min as f32,
+        distance: dz.map(|dz| distance_estimate(z, dz)),
+        stripe: stripes.map(|stripes| stripes.averages()),
+        root: None,
+        lyapunov: None,
+    }
+}
+
+/// Derivative of the starting `z` with respect to the pixel: 1 in Julia mode, where the
+/// pixel is the seed, and 0 otherwise.
+fn derivative_seed<F: FractalFormula + ?Sized>(formula: &F) -> Complex64 {
+    let seed = if formula.julia_mode() { 1.0 } else { 0.0 };
+    Complex64::new(seed, 0.0)
+}
+
+/// Exterior distance estimate, in plane units, of an orbit that stopped at `z` with
+/// derivative `dz`: `|z| ln|z| / 2|dz|`, a lower bound on the distance to the set.
+pub(crate) fn distance_estimate(z: Complex64, dz: Complex64) -> f64 {
+    let r = z.re.hypot(z.im);
+    0.5 * r * r.max(1.0).ln() / dz.re.hypot(dz.im).max(1e-300)
+}
+
+/// Running sum of the stripe function `(1 + sin(k·arg z)) / 2` over an orbit's iterates,
+/// for [`ColoringMode::StripeAverage`]. The first iterate is left out: for the Mandelbrot
+/// set it is the pixel itself, whose angle would draw rays out from the origin.
+pub(crate) struct StripeSum {
+    density: f64,
+    sum: f64,
+    last: f64,
+    iterates: u32,
+}
+
+impl StripeSum {
+    /// A sum for `p`, when it colors by the stripe average.
+    pub(crate) fn new(p: &FractalParams) -> Option<Self> {
+        (p.coloring == ColoringMode::StripeAverage).then(|| Self {
+            density: f64::from(p.stripe_density),
+            sum: 0.0,
+            last: 0.0,
+            iterates: 0,
+        })
+    }
+
+    /// Adds the next iterate.
+    pub(crate) fn add(&mut self, z: Complex64) {
+        self.iterates += 1;
+        if self.iterates > 1 {
+            self.last = 0.5 + 0.5 * (self.density * z.im.atan2(z.re)).sin();
+            self.sum += self.last;
+        }
+    }
+
+    /// The average with the final iterate and without it, which
+    /// [`stripe_average`](crate::stripe_average) interpolates between.
+    pub(crate) fn averages(&self) -> [f32; 2] {
+        let terms = f64::from(self.iterates.saturating_sub(1));
+        if terms < 1.0 {
+            return [0.0; 2];
+        }
+        let with = self.sum / terms;
+        let without = if terms > 1.0 {
+            (self.sum - self.last) / (terms - 1.0)
+        } else {
+            with
+        };
+        [with as f32, without as f32]
+    }
+}
+
+/// Distance from `z` to the orbit trap, in double precision.
+pub(crate) fn trap_distance_f64(p: &FractalParams, z: Complex64) -> f64 {
+    let trap = Complex64::from(p.orbit.point);
+    match p.orbit.kind {
+        OrbitTrapKind::Point => (z.re - trap.re).hypot(z.im - trap.im),
+        OrbitTrapKind::Circle => {
+            ((z.re * z.re + z.im * z.im).sqrt() - f64::from(p.orbit.radius)).abs()
+        }
+        OrbitTrapKind::Cross => (z.re - trap.re).abs().min((z.im - trap.im).abs()),
+    }
+}
+
+// ------------------------- Parameters -------------------------
+
+/// A [`FractalParams`] field that only some formulas read.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParamField {
+    Power,
+    JuliaRe,
+    JuliaIm,
+    PhoenixRe,
+    PhoenixIm,
+}
+
+impl ParamField {
+    pub fn get(self, p: &FractalParams) -> f32 {
+        match self {
+            ParamField::Power => p.power,
+            ParamField::JuliaRe => p.c.re,
+            ParamField::JuliaIm => p.c.im,
+            ParamField::PhoenixRe => p.phoenix_p.re,
+            ParamField::PhoenixIm => p.phoenix_p.im,
+        }
+    }
+
+    pub fn get_mut(self, p: &mut FractalParams) -> &mut f32 {
+        match self {
+            ParamField::Power => &mut p.power,
+            ParamField::JuliaRe => &mut p.c.re,
+            ParamField::JuliaIm => &mut p.c.im,
+            ParamField::PhoenixRe => &mut p.phoenix_p.re,
+            ParamField::PhoenixIm => &mut p.phoenix_p.im,
+        }
+    }
+
+    /// The keyframe track that animates the field, if it has one.
+    pub fn track(self) -> Option<TrackKind> {
+        match self {
+            ParamField::JuliaRe => Some(TrackKind::JuliaRe),
+            ParamField::JuliaIm => Some(TrackKind::JuliaIm),
+            ParamField::Power | ParamField::PhoenixRe | ParamField::PhoenixIm => None,
+        }
+    }
+}
+
+/// Editing widget for a [`FormulaParam`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParamControl {
+    Slider,
+    /// Slider in whole steps, for counts stored in a float field.
+    Steps,
+    /// Unbounded drag with this speed per pixel; the range only clamps.
+    Drag(f32),
+}
+
+/// Metadata for one formula-specific parameter: what the UI shows and what switching
+/// to the formula resets it to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FormulaParam {
+    pub field: ParamField,
+    pub label: &'static str,
+    pub min: f32,
+    pub max: f32,
+    pub default: f32,
+    pub control: ParamControl,
+}
+
+impl FractalParams {
+    /// Switches to `kind`, keeping shared parameters and resetting the new formula's own
+    /// parameters to their defaults.
+    pub fn switch_kind(&mut self, kind: FractalKind) {
+        if self.kind == kind {
+            return;
+        }
+        self.kind = kind;
+        for param in formula(kind).params() {
+            *param.field.get_mut(self) = param.default;
+        }
+    }
+
+    /// Whether the formula reads `field`.
+    pub fn uses(&self, field: ParamField) -> bool {
+        formula(self.kind)
+            .params()
+            .iter()
+            .any(|param| param.field == field)
+    }
+
+    /// Whether escaped points are colored by their distance estimate: asked for, and the
+    /// formula has a derivative to estimate it from.
+    pub fn estimates_distance(&self) -> bool {
+        let zero = Complex64::default();
+        self.coloring == ColoringMode::DistanceEstimate
+            && formula(self.kind).derivative(self, zero, zero).is_some()
+    }
+}
+
+// ------------------------- Iteration suggestion -------------------------
+
+/// Largest Max Iter [`suggested_max_iter`] returns.
+pub const MAX_SUGGESTED_ITER: u32 = 200_000;
+
+/// How many times `cam` magnifies `kind`'s overview when shown `view_width` pixels wide.
+pub fn zoom_depth(kind: FractalKind, cam: &Camera, view_width: f32) -> f32 {
+    ZoomReadout::new(kind, cam, view_width).magnification as f32
+}
+
+/// Max Iter that keeps detail visible at `zoom` times `kind`'s overview: the formula's
+/// [`FractalFormula::iteration_budget`] per decade of zoom, rounded to a hundred.
+pub fn suggested_max_iter(kind: FractalKind, zoom: f32) -> u32 {
+    let (base, per_decade) = formula(kind).iteration_budget();
+    let decades = if zoom > 1.0 {
+        zoom.log10()
+    } else {
+        0.0
+    };
+    let iter = ((base + per_decade * decades) / 100.0).round() * 100.0;
+    (iter as u32).clamp(100, MAX_SUGGESTED_ITER)
+}
+
+impl FractalParams {
+    /// Max Iter suggested for this view; see [`suggested_max_iter`].
+    pub fn suggested_max_iter(&self, cam: &Camera, view_width: f32) -> u32 {
+        suggested_max_iter(self.kind, zoom_depth(self.kind, cam, view_width))
+    }
+
+    /// In [`FractalParams::auto_iter`] mode, sets Max Iter to the suggestion for this view.
+    pub fn apply_auto_iter(&mut self, cam: &Camera, view_width: f32) {
+        if self.auto_iter {
+            self.max_iter = self.suggested_max_iter(cam, view_width);
+        }
+    }
+}
+
+// ------------------------- Built-in formulas -------------------------
+
+struct Mandelbrot;
+
+impl FractalFormula for Mandelbrot {
+    fn kind(&self) -> FractalKind {
+        FractalKind::Mandelbrot
+    }
+
+    fn name(&self) -> &'static str {
+        "Mandelbrot"
+    }
+
+    /// `dz' = 2·z·dz + 1`.
+    fn derivative(&self, _p: &FractalParams, z: Complex64, dz: Complex64) -> Option<Complex64> {
+        Some(quadratic_derivative(z, dz, 1.0))
+    }
+
+    fn step(&self, _p: &FractalParams, z: Complex, c: Complex) -> Complex {
+        quadratic(z, c)
+    }
+
+    fn step_f64(&self, _p: &FractalParams, z: Complex64, c: Complex64) -> Complex64 {
+        quadratic_f64(z, c)
+    }
+
+    fn wgsl_step(&self) -> Option<&'static str> {
+        Some("z = vec2<f32>(x2 - y2 + c.x, 2.0 * zx * zy + c.y);")
+    }
+}
+
+struct Julia;
+
+impl FractalFormula for Julia {
+    fn kind(&self) -> FractalKind {
+        FractalKind::Julia
+    }
+
+    fn name(&self) -> &'static str {
+        "Julia"
+    }
+
+    fn julia_mode(&self) -> bool {
+        true
+    }
+
+    fn overview(&self) -> (Complex, f32) {
+        (Complex::new(0.0, 0.0), 3.6)
+    }
+
+    fn params(&self) -> &'static [FormulaParam] {
+        &[
+            FormulaParam {
+                field: ParamField::JuliaRe,
+                label: "Julia c Re",
+                min: -2.0,
+                max: 2.0,
+                default: -0.8,
+                control: ParamControl::Drag(0.01),
+            },
+            FormulaParam {
+                field: ParamField::JuliaIm,
+                label: "Julia c Im",
+                min: -2.0,
+                max: 2.0,
+                default: 0.156,
+                control: ParamControl::Drag(0.01),
+            },
+        ]
+    }
+
+    /// Julia sets are connected around the cardioid and need less depth to resolve.
+    fn iteration_budget(&self) -> (f32, f32) {
+        (300.0, 600.0)
+    }
+
+    /// `dz' = 2·z·dz`, as `c` is fixed.
+    fn derivative(&self, _p: &FractalParams, z: Complex64, dz: Complex64) -> Option<Complex64> {
+        Some(quadratic_derivative(z, dz, 0.0))
+    }
+
+    fn step(&self, _p: &FractalParams, z: Complex, c: Complex) -> Complex {
+        quadratic(z, c)
+    }
+
+    fn step_f64(&self, _p: &FractalParams, z: Complex64, c: Complex64) -> Complex64 {
+        quadratic_f64(z, c)
+    }
+
+    fn wgsl_step(&self) -> Option<&'static str> {
+        Mandelbrot.wgsl_step()
+    }
+}
+
+struct BurningShip;
+
+impl FractalFormula for BurningShip {
+    fn kind(&self) -> FractalKind {
+        FractalKind::BurningShip
+    }
+
+    fn name(&self) -> &'static str {
+        "Burning Ship"
+    }
+
+    fn overview(&self) -> (Complex, f32) {
+        (Complex::new(-0.5, -0.5), 4.0)
+    }
+
+    /// The antennas and the mast stay thin filaments far into a zoom.
+    fn iteration_budget(&self) -> (f32, f32) {
+        (300.0, 1200.0)
+    }
+
+    /// `z = (|Re z| + i|Im z|)² + c`. The legacy step also folded the result into the first
+    /// quadrant: escape times are the same, but orbit traps see the folded points.
+    fn step(&self, p: &FractalParams, z: Complex, c: Complex) -> Complex {
+        let new_x = z.re * z.re - z.im * z.im + c.re;
+        let new_y = 2.0 * (z.re * z.im).abs() + c.im;
+        if p.legacy_burning_ship {
+            Complex::new(new_x.abs(), new_y.abs())
+        } else {
+            Complex::new(new_x, new_y)
+        }
+    }
+
+    fn step_f64(&self, p: &FractalParams, z: Complex64, c: Complex64) -> Complex64 {
+        let new_x = z.re * z.re - z.im * z.im + c.re;
+        let new_y = 2.0 * (z.re * z.im).abs() + c.im;
+        if p.legacy_burning_ship {
+            Complex64::new(new_x.abs(), new_y.abs())
+        } else {
+            Complex64::new(new_x, new_y)
+        }
+    }
+
+    fn wgsl_step(&self) -> Option<&'static str> {
+        Some("z = vec2<f32>(x2 - y2 + c.x, 2.0 * abs(zx * zy) + c.y);")
+    }
+
+    /// The legacy fold only shows through orbit traps, and is CPU-only.
+    fn gpu_supports(&self, p: &FractalParams) -> bool {
+        !(p.legacy_burning_ship && p.orbit.enabled)
+    }
+}
+
+struct Multibrot;
+
+impl FractalFormula for Multibrot {
+    fn kind(&self) -> FractalKind {
+        FractalKind::Multibrot
+    }
+
+    fn name(&self) -> &'static str {
+        "Multibrot"
+    }
+
+    fn params(&self) -> &'static [FormulaParam] {
+        &[FormulaParam {
+            field: ParamField::Power,
+            label: "Power",
+            min: 2.0,
+            max: 12.0,
+            default: 2.0,
+            control: ParamControl::Slider,
+        }]
+    }
+
+    /// Higher powers escape faster, so each zoom step needs fewer iterations.
+    fn iteration_budget(&self) -> (f32, f32) {
+        (300.0, 800.0)
+    }
+
+    fn degree(&self, p: &FractalParams) -> f32 {
+        p.power
+    }
+
+    /// Power 2 takes the quadratic step, so it renders exactly as Mandelbrot does.
+    fn step(&self, p: &FractalParams, z: Complex, c: Complex) -> Complex {
+        if p.power == 2.0 {
+            return quadratic(z, c);
+        }
+        let r = (z.re * z.re + z.im * z.im).sqrt();
+        let theta = z.im.atan2(z.re);
+        let r_p = r.powf(p.power);
+        let th_p = theta * p.power;
+        Complex::new(r_p * th_p.cos() + c.re, r_p * th_p.sin() + c.im)
+    }
+
+    fn step_f64(&self, p: &FractalParams, z: Complex64, c: Complex64) -> Complex64 {
+        if p.power == 2.0 {
+            return quadratic_f64(z, c);
+        }
+        let r = (z.re * z.re + z.im * z.im).sqrt();
+        let theta = z.im.atan2(z.re);
+        let power = f64::from(p.power);
+        let r_p = r.powf(power);
+        let th_p = theta * power;
+        Complex64::new(r_p * th_p.cos() + c.re, r_p * th_p.sin() + c.im)
+    }
+
+    fn wgsl_step(&self) -> Option<&'static str> {
+        Some(
+            "if (params.power == 2.0) {
+            z = vec2<f32>(x2 - y2 + c.x, 2.0 * zx * zy + c.y);
+        } else {
+            let r = sqrt(x2 + y2);
+            let theta = atan2(zy, zx);
+            let rp = pow(r, params.power);
+            let th = theta * params.power;
+            z = vec2<f32>(rp * cos(th) + c.x, rp * sin(th) + c.y);
+        }",
+        )
+    }
+}
+
+/// `z = |Re z²| + i·Im z² + c`: the Mandelbrot step with the real part folded.
+struct Celtic;
+
+impl FractalFormula for Celtic {
+    fn kind(&self) -> FractalKind {
+        FractalKind::Celtic
+    }
+
+    fn name(&self) -> &'static str {
+        "Celtic"
+    }
+
+    fn iteration_budget(&self) -> (f32, f32) {
+        BurningShip.iteration_budget()
+    }
+
+    fn step(&self, _p: &FractalParams, z: Complex, c: Complex) -> Complex {
+        Complex::new(
+            (z.re * z.re - z.im * z.im).abs() + c.re,
+            2.0 * z.re * z.im + c.im,
+        )
+    }
+
+    fn step_f64(&self, _p: &FractalParams, z: Complex64, c: Complex64) -> Complex64 {
+        Complex64::new(
+            (z.re * z.re - z.im * z.im).abs() + c.re,
+            2.0 * z.re * z.im + c.im,
+        )
+    }
+
+    fn wgsl_step(&self) -> Option<&'static str> {
+        Some("z = vec2<f32>(abs(x2 - y2) + c.x, 2.0 * zx * zy + c.y);")
+    }
+}
+
+/// `z = |Re z²| + i·|Im z²| + c`: both parts of the square folded, which mirrors the
+/// Burning Ship's fold of `z` itself.
+struct Buffalo;
+
+impl FractalFormula for Buffalo {
+    fn kind(&self) -> FractalKind {
+        FractalKind::Buffalo
+    }
+
+    fn name(&self) -> &'static str {
+        "Buffalo"
+    }
+
+    fn overview(&self) -> (Complex, f32) {
+        BurningShip.overview()
+    }
+
+    fn iteration_budget(&self) -> (f32, f32) {
+        BurningShip.iteration_budget()
+    }
+
+    fn step(&self, _p: &FractalParams, z: Complex, c: Complex) -> Complex {
+        Complex::new(
+            (z.re * z.re - z.im * z.im).abs() + c.re,
+            (2.0 * z.re * z.im).abs() + c.im,
+        )
+    }
+
+    fn step_f64(&self, _p: &FractalParams, z: Complex64, c: Complex64) -> Complex64 {
+        Complex64::new(
+            (z.re * z.re - z.im * z.im).abs() + c.re,
+            (2.0 * z.re * z.im).abs() + c.im,
+        )
+    }
+
+    fn wgsl_step(&self) -> Option<&'static str> {
+        Some("z = vec2<f32>(abs(x2 - y2) + c.x, abs(2.0 * zx * zy) + c.y);")
+    }
+}
+
+struct Tricorn;
+
+impl FractalFormula for Tricorn {
+    fn kind(&self) -> FractalKind {
+        FractalKind::Tricorn
+    }
+
+    fn name(&self) -> &'static str {
+        "Tricorn"
+    }
+
+    /// `dz' = 2·conj(z·dz) + 1`: the step is antiholomorphic, but the conjugate has the
+    /// same length, which is all the estimate reads.
+    fn derivative(&self, _p: &FractalParams, z: Complex64, dz: Complex64) -> Option<Complex64> {
+        let d = quadratic_derivative(z, dz, 0.0);
+        Some(Complex64::new(d.re + 1.0, -d.im))
+    }
+
+    /// `z = conj(z)² + c`.
+    fn step(&self, _p: &FractalParams, z: Complex, c: Complex) -> Complex {
+        Complex::new(z.re * z.re - z.im * z.im + c.re, -2.0 * z.re * z.im + c.im)
+    }
+
+    fn step_f64(&self, _p: &FractalParams, z: Complex64, c: Complex64) -> Complex64 {
+        Complex64::new(z.re * z.re - z.im * z.im + c.re, -2.0 * z.re * z.im + c.im)
+    }
+
+    fn wgsl_step(&self) -> Option<&'static str> {
+        Some("z = vec2<f32>(x2 - y2 + c.x, -2.0 * zx * zy + c.y);")
+    }
+}
+
+/// Ushiki's Phoenix, a Julia-style set: `z = z² + c + p·z_prev`, where `z_prev` is the `z`
+/// of the step before and `p` is [`FractalParams::phoenix_p`].
+struct Phoenix;
+
+impl FractalFormula for Phoenix {
+    fn kind(&self) -> FractalKind {
+        FractalKind::Phoenix
+    }
+
+    fn name(&self) -> &'static str {
+        "Phoenix"
+    }
+
+    fn julia_mode(&self) -> bool {
+        true
+    }
+
+    fn overview(&self) -> (Complex, f32) {
+        (Complex::new(0.0, 0.0), 3.2)
+    }
+
+    fn params(&self) -> &'static [FormulaParam] {
+        &[
+            FormulaParam {
+                field: ParamField::JuliaRe,
+                label: "c Re",
+                min: -2.0,
+                max: 2.0,
+                default: 0.5667,
+                control: ParamControl::Drag(0.01),
+            },
+            FormulaParam {
+                field: ParamField::JuliaIm,
+                label: "c Im",
+                min: -2.0,
+                max: 2.0,
+                default: 0.0,
+                control: ParamControl::Drag(0.01),
+            },
+            FormulaParam {
+                field: ParamField::PhoenixRe,
+                label: "p Re",
+                min: -2.0,
+                max: 2.0,
+                default: -0.5,
+                control: ParamControl::Drag(0.01),
+            },
+            FormulaParam {
+                field: ParamField::PhoenixIm,
+                label: "p Im",
+                min: -2.0,
+                max: 2.0,
+                default: 0.0,
+                control: ParamControl::Drag(0.01),
+            },
+        ]
+    }
+
+    fn iteration_budget(&self) -> (f32, f32) {
+        Julia.iteration_budget()
+    }
+
+    /// The first step, where the previous `z` is 0.
+    fn step(&self, _p: &FractalParams, z: Complex, c: Complex) -> Complex {
+        quadratic(z, c)
+    }
+
+    fn step_f64(&self, _p: &FractalParams, z: Complex64, c: Complex64) -> Complex64 {
+        quadratic_f64(z, c)
+    }
+
+    fn step_with_previous(
+        &self,
+        p: &FractalParams,
+        z: Complex,
+        prev: Complex,
+        c: Complex,
+    ) -> Complex {
+        let w = p.phoenix_p;
+        quadratic(
+            z,
+            Complex::new(
+                c.re + w.re * prev.re - w.im * prev.im,
+                c.im + w.re * prev.im + w.im * prev.re,
+            ),
+        )
+    }
+
+    fn step_with_previous_f64(
+        &self,
+        p: &FractalParams,
+        z: Complex64,
+        prev: Complex64,
+        c: Complex64,
+    ) -> Complex64 {
+        let w = Complex64::from(p.phoenix_p);
+        quadratic_f64(
+            z,
+            Complex64::new(
+                c.re + w.re * prev.re - w.im * prev.im,
+                c.im + w.re * prev.im + w.im * prev.re,
+            ),
+        )
+    }
+
+    fn wgsl_step(&self) -> Option<&'static str> {
+        Some(
+            "let w = params.phoenix_p;
+        z = vec2<f32>(
+            x2 - y2 + c.x + w.x * prev.x - w.y * prev.y,
+            2.0 * zx * zy + c.y + w.x * prev.y + w.y * prev.x,
+        );",
+        )
+    }
+}
+
+/// Distance from a Newton iterate to its root below which the orbit counts as converged.
+pub const NEWTON_TOLERANCE: f64 = 1e-6;
+
+/// Iterations of convergence over which a basin's color [halves in
+/// brightness](newton_brightness).
+pub const NEWTON_HALF_LIFE: f32 = 16.0;
+
+/// Newton's method on `z^n - 1`, whose `n` roots of unity each draw a basin. Orbits
+/// converge rather than escape: one that reaches a root within [`NEWTON_TOLERANCE`] counts
+/// as escaped, with [`OrbitResult::root`] naming the root, and one that does not within
+/// Max Iter is interior.
+struct Newton;
+
+impl FractalFormula for Newton {
+    fn kind(&self) -> FractalKind {
+        FractalKind::Newton
+    }
+
+    fn name(&self) -> &'static str {
+        "Newton"
+    }
+
+    fn overview(&self) -> (Complex, f32) {
+        (Complex::new(0.0, 0.0), 4.0)
+    }
+
+    fn params(&self) -> &'static [FormulaParam] {
+        &[FormulaParam {
+            field: ParamField::Power,
+            label: "Roots",
+            min: 2.0,
+            max: 12.0,
+            default: 3.0,
+            control: ParamControl::Steps,
+        }]
+    }
+
+    /// Basins converge in a few dozen steps almost everywhere, at any depth.
+    fn iteration_budget(&self) -> (f32, f32) {
+        (100.0, 50.0)
+    }
+
+    fn degree(&self, p: &FractalParams) -> f32 {
+        newton_roots(p) as f32
+    }
+
+    /// Quadratic convergence squares the distance to the root every step, so the count
+    /// falls by one as that distance goes from the tolerance to its square.
+    fn smooth_iteration(&self, _p: &FractalParams, orbit: &OrbitResult) -> f32 {
+        let Some((_, distance)) = orbit.root else {
+            return orbit.iter as f32;
+        };
+        let ratio = distance.max(1e-300).ln() / NEWTON_TOLERANCE.ln();
+        orbit.iter as f32 - ratio.max(1.0).log2() as f32
+    }
+
+    /// `z - (z^n - 1) / (n·z^(n-1))`, for plotting orbits; rendering goes through
+    /// [`FractalFormula::iterate`].
+    fn step(&self, p: &FractalParams, z: Complex, _c: Complex) -> Complex {
+        newton_step(newton_roots(p), z.into())
+            .unwrap_or(z.into())
+            .to_f32()
+    }
+
+    fn step_f64(&self, p: &FractalParams, z: Complex64, _c: Complex64) -> Complex64 {
+        newton_step(newton_roots(p), z).unwrap_or(z)
+    }
+
+    fn iterate(&self, p: &FractalParams, rx: f32, ry: f32) -> OrbitResult {
+        newton_orbit(p, Complex64::new(rx.into(), ry.into()))
+    }
+
+    fn iterate_f64(&self, p: &FractalParams, rx: f64, ry: f64) -> OrbitResult {
+        newton_orbit(p, Complex64::new(rx, ry))
+    }
+}
+
+/// Roots of the Newton polynomial: [`FractalParams::power`], rounded.
+pub fn newton_roots(p: &FractalParams) -> u32 {
+    p.power.round().clamp(2.0, 12.0) as u32
+}
+
+/// How bright a basin's color is after `smooth_iter` iterations of convergence: 1 at
+/// once, halving every [`NEWTON_HALF_LIFE`] iterations, so basin edges darken.
+pub fn newton_brightness(smooth_iter: f32) -> f32 {
+    0.5f32.powf(smooth_iter.max(0.0) / NEWTON_HALF_LIFE)
+}
+
+/// One Newton step on `z^n - 1`; `None` at `z = 0`, where the derivative vanishes.
+fn newton_step(n: u32, z: Complex64) -> Option<Complex64> {
+    let mut below = Complex64::new(1.0, 0.0);
+    for _ in 1..n {
+        below = Complex64::new(
+            below.re * z.re - below.im * z.im,
+            below.re * z.im + below.im * z.re,
+        );
+    }
+    // z - (z^n - 1) / (n z^(n-1)) = ((n-1) z^n + 1) / (n z^(n-1))
+    let power = Complex64::new(
+        below.re * z.re - below.im * z.im,
+        below.re * z.im + below.im * z.re,
+    );
+    let n = f64::from(n);
+    let top = Complex64::new((n - 1.0) * power.re + 1.0, (n - 1.0) * power.im);
+    let bottom = Complex64::new(n * below.re, n * below.im);
+    let norm = bottom.re * bottom.re + bottom.im * bottom.im;
+    (norm > 0.0 && norm.is_finite()).then(|| {
+        Complex64::new(
+            (top.re * bottom.re + top.im * bottom.im) / norm,
+            (top.im * bottom.re - top.re * bottom.im) / norm,
+        )
+    })
+}
+
+/// The root of `z^n - 1` nearest `z`, and its distance from it.
+fn nearest_root(n: u32, z: Complex64) -> (u32, f64) {
+    let turn = std::f64::consts::TAU / f64::from(n);
+    let k = (z.im.atan2(z.re) / turn).round().rem_euclid(f64::from(n));
+    let angle = k * turn;
+    let distance = (z.re - angle.cos()).hypot(z.im - angle.sin());
+    (k as u32, distance)
+}
+
+/// Newton's method from `z`, with orbit-trap tracking, until it converges or runs out of
+/// iterations.
+fn newton_orbit(p: &FractalParams, mut z: Complex64) -> OrbitResult {
+    let n = newton_roots(p);
+    let mut i = 0u32;
+    let mut trap_min = f64::MAX;
+    let mut root = None;
+    while i < p.max_iter {
+        let Some(next) = newton_step(n, z) else {
+            break;
+        };
+        z = next;
+        i += 1;
+        if p.orbit.enabled {
+            trap_min = trap_min.min(trap_distance_f64(p, z));
+        }
+        let (k, distance) = nearest_root(n, z);
+        if distance < NEWTON_TOLERANCE {
+            root = Some((k, distance));
+            break;
+        }
+    }
+    OrbitResult {
+        // Orbits stuck at the origin never converge.
+        iter: if root.is_some() { i } else { p.max_iter },
+        zx: z.re as f32,
+        zy: z.im as f32,
+        trap_min: trap_min as f32,
+        distance: None,
+        stripe: None,
+        root,
+        lyapunov: None,
+    }
+}
+
+/// The Lyapunov fractal: the pixel `(a, b)` holds two rates of the logistic map
+/// `x = r·x·(1 - x)`, which take turns as [`FractalParams::lyapunov_sequence`] spells out.
+/// After [`FractalParams::lyapunov_warmup`] steps, Max Iter more measure the exponent
+/// `λ = mean ln|r·(1 - 2x)|`: negative where the map settles into a cycle, positive where it
+/// is chaotic. Every point counts as escaped and is colored by
+/// [`lyapunov_position`](crate::lyapunov_position).
+struct Lyapunov;
+
+impl FractalFormula for Lyapunov {
+    fn kind(&self) -> FractalKind {
+        FractalKind::Lyapunov
+    }
+
+    fn name(&self) -> &'static str {
+        "Lyapunov"
+    }
+
+    /// Rates from 2 to 4, where the map goes from settling to chaos.
+    fn overview(&self) -> (Complex, f32) {
+        (Complex::new(3.0, 3.0), 2.0)
+    }
+
+    /// The exponent converges as well at any zoom; deeper views only show finer detail.
+    fn iteration_budget(&self) -> (f32, f32) {
+        (200.0, 100.0)
+    }
+
+    /// There is no escape to count; shading goes by [`OrbitResult::lyapunov`].
+    fn smooth_iteration(&self, _p: &FractalParams, _orbit: &OrbitResult) -> f32 {
+        0.0
+    }
+
+    /// One logistic-map step of `x = Re z` at rate `Re c`, for plotting orbits; rendering
+    /// goes through [`FractalFormula::iterate`].
+    fn step(&self, _p: &FractalParams, z: Complex, c: Complex) -> Complex {
+        Complex::new(c.re * z.re * (1.0 - z.re), 0.0)
+    }
+
+    fn iterate(&self, p: &FractalParams, rx: f32, ry: f32) -> OrbitResult {
+        lyapunov_orbit(p, rx.into(), ry.into())
+    }
+
+    fn iterate_f64(&self, p: &FractalParams, rx: f64, ry: f64) -> OrbitResult {
+        lyapunov_orbit(p, rx, ry)
+    }
+}
+
+/// The rates a Lyapunov sequence steps through, repeating: `false` for `A`, `true` for `B`,
+/// in either case. A sequence with neither letter counts as `AB`.
+fn lyapunov_rates(sequence: &str) -> impl Iterator<Item = bool> + '_ {
+    let rate = |ch: char| match ch.to_ascii_uppercase() {
+        'A' => Some(false),
+        'B' => Some(true),
+        _ => None,
+    };
+    let sequence = if sequence.chars().any(|ch| rate(ch).is_some()) {
+        sequence
+    } else {
+        "AB"
+    };
+    sequence.chars().filter_map(rate).cycle()
+}
+
+/// The Lyapunov exponent of the logistic map at rates `a` and `b`, with orbit-trap tracking
+/// of `x` on the real axis.
+fn lyapunov_orbit(p: &FractalParams, a: f64, b: f64) -> OrbitResult {
+    let mut rates = lyapunov_rates(&p.lyapunov_sequence);
+    let mut rate = || if rates.next() == Some(true) { b } else { a };
+    let mut x = 0.5;
+    let mut sum = 0.0;
+    let mut trap_min = f64::MAX;
+    // Rates outside 0..4 throw `x` out of 0..1, after which it diverges: unbounded, so as
+    // chaotic as it gets.
+    let mut diverged = false;
+    for i in 0..p.lyapunov_warmup + p.max_iter {
+        let r = rate();
+        if i >= p.lyapunov_warmup {
+            sum += (r * (1.0 - 2.0 * x)).abs().max(1e-300).ln();
+            if p.orbit.enabled {
+                trap_min = trap_min.min(trap_distance_f64(p, Complex64::new(x, 0.0)));
+            }
+        }
+        x = r * x * (1.0 - x);
+        if !x.is_finite() {
+            diverged = true;
+            break;
+        }
+    }
+    let exponent = if diverged {
+        f64::INFINITY
+    } else {
+        sum / f64::from(p.max_iter.max(1))
+    };
+    OrbitResult {
+        iter: 0,
+        zx: x as f32,
+        zy: 0.0,
+        trap_min: trap_min as f32,
+        distance: None,
+        stripe: None,
+        root: None,
+        lyapunov: Some(exponent as f32),
+    }
+}
+
+/// The formula typed into [`FractalParams::custom_formula`], iterated from `z = 0` with the
+/// pixel as `c`. CPU only. A formula that does not parse leaves every point inside the set.
+struct Custom;
+
+impl FractalFormula for Custom {
+    fn kind(&self) -> FractalKind {
+        FractalKind::Custom
+    }
+
+    fn name(&self) -> &'static str {
+        "Custom"
+    }
+
+    fn degree(&self, p: &FractalParams) -> f32 {
+        compiled_formula(p).map_or(2.0, |formula| formula.degree())
+    }
+
+    fn step(&self, p: &FractalParams, z: Complex, c: Complex) -> Complex {
+        self.step_f64(p, z.into(), c.into()).to_f32()
+    }
+
+    /// An orbit the formula takes to NaN, as `0/0` does, counts as escaped.
+    fn step_f64(&self, p: &FractalParams, z: Complex64, c: Complex64) -> Complex64 {
+        let next = compiled_formula(p).map_or(z, |formula| formula.eval(z, c));
+        if next.re.is_nan() || next.im.is_nan() {
+            return Complex64::new(f64::INFINITY, f64::INFINITY);
+        }
+        next
+    }
+
+    fn iterate(&self, p: &FractalParams, rx: f32, ry: f32) -> OrbitResult {
+        let p = with_compiled_formula(p);
+        match attached_formula(&p) {
+            Some(_) => iterate_orbit(self, &p, rx, ry),
+            None => unparsed_orbit(&p),
+        }
+    }
+
+    fn iterate_f64(&self, p: &FractalParams, rx: f64, ry: f64) -> OrbitResult {
+        let p = with_compiled_formula(p);
+        match attached_formula(&p) {
+            Some(_) => iterate_orbit_f64(self, &p, rx, ry),
+            None => unparsed_orbit(&p),
+        }
+    }
+}
+
+/// `p`'s custom formula: the attached one, or else parsed on the spot.
+fn compiled_formula(p: &FractalParams) -> Option<Cow<'_, CustomFormula>> {
+    match attached_formula(p) {
+        Some(formula) => Some(Cow::Borrowed(formula)),
+        None => CustomFormula::parse(&p.custom_formula).ok().map(Cow::Owned),
+    }
+}
+
+/// An orbit that never escapes, for formulas that do not parse.
+fn unparsed_orbit(p: &FractalParams) -> OrbitResult {
+    OrbitResult {
+        iter: p.max_iter,
+        zx: 0.0,
+        zy: 0.0,
+        trap_min: f32::MAX,
+        distance: None,
+        stripe: None,
+        root: None,
+        lyapunov: None,
+    }
+}
+
+fn quadratic(z: Complex, c: Complex) -> Complex {
+    Complex::new(z.re * z.re - z.im * z.im + c.re, 2.0 * z.re * z.im + c.im)
+}
+
+fn quadratic_f64(z: Complex64, c: Complex64) -> Complex64 {
+    Complex64::new(z.re * z.re - z.im * z.im + c.re, 2.0 * z.re * z.im + c.im)
+}
+
+/// `2·z·dz + dc`, the derivative of a quadratic step.
+fn quadratic_derivative(z: Complex64, dz: Complex64, dc: f64) -> Complex64 {
+    Complex64::new(
+        2.0 * (z.re * dz.re - z.im * dz.im) + dc,
+        2.0 * (z.re * dz.im + z.im * dz.re),
+    )
+}
+
+// ------------------------- Registry -------------------------
+
+static FORMULAS: [&dyn FractalFormula; 11] = [
+    &Mandelbrot,
+    &Julia,
+    &BurningShip,
+    &Multibrot,
+    &Tricorn,
+    &Newton,
+    &Phoenix,
+    &Celtic,
+    &Buffalo,
+    &Lyapunov,
+    &Custom,
+];
+
+/// Every registered formula, in UI order.
+pub fn formulas() -> &'static [&'static dyn FractalFormula] {
+    &FORMULAS
+}
+
+/// The formula registered for `kind`.
+pub fn formula(kind: FractalKind) -> &'static dyn FractalFormula {
+    *formulas()
+        .iter()
+        .find(|f| f.kind() == kind)
+        .expect("every FractalKind has a registered formula")
+}
+
+/// Index of `kind` in [`formulas`]; the shader switches on it.
+pub fn formula_index(kind: FractalKind) -> u32 {
+    formulas()
+        .iter()
+        .position(|f| f.kind() == kind)
+        .expect("every FractalKind has a registered formula") as u32
+}
+
+impl FractalKind {
+    pub fn label(&self) -> &'static str {
+        formula(*self).name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{iterate_point, iterate_point_f64, smooth_iteration};
+
+    #[test]
+    fn distance_estimates_bound_the_true_distance() {
+        // The set ends at 0.25 on the real axis; the Julia set of c = 0 is the unit
+        // circle. With a large escape radius the estimate lies between a quarter of the
+        // true distance and all of it.
+        let mandelbrot = FractalParams {
+            escape_radius: 1000.0,
+            ..FractalParams::new(FractalKind::Mandelbrot)
+                .with_coloring(ColoringMode::DistanceEstimate)
+        };
+        let julia = FractalParams {
+            kind: FractalKind::Julia,
+            c: Complex::new(0.0, 0.0),
+            ..mandelbrot.clone()
+        };
+        for (p, x, truth) in [
+            (&mandelbrot, 1.0, 0.75),
+            (&mandelbrot, -2.5, 0.5),
+            (&julia, 2.0, 1.0),
+        ] {
+            for distance in [
+                iterate_point(p, x as f32, 0.0).distance,
+                iterate_point_f64(p, x, 0.0).distance,
+            ] {
+                let distance = distance.expect("estimated");
+                let kind = p.kind;
+                assert!(
+                    (truth / 4.0..=truth).contains(&distance),
+                    "{kind:?} at {x}: {distance}"
+                );
+            }
+        }
+
+        // Without a derivative, or without asking, there is none.
+        let ship = FractalParams {
+            kind: FractalKind::BurningShip,
+            ..mandelbrot.clone()
+        };
+        assert!(!ship.estimates_distance());
+        assert_eq!(iterate_point(&ship, 1.0, 0.0).distance, None);
+        let plain = FractalParams::new(FractalKind::Mandelbrot);
+        assert_eq!(iterate_point(&plain, 1.0, 0.0).distance, None);
+    }
+
+    #[test]
+    fn newton_converges_to_the_nearest_root() {
+        for roots in [3u32, 5] {
+            let p = FractalParams::new(FractalKind::Newton)
+                .with_power(roots as f32)
+                .with_max_iter(100);
+            for k in 0..roots {
+                // Just off each root of unity, the orbit falls straight into it.
+                let angle = std::f64::consts::TAU * f64::from(k) / f64::from(roots) + 0.05;
+                let orbit = iterate_point_f64(&p, 0.9 * angle.cos(), 0.9 * angle.sin());
+                assert!(orbit.escaped(&p), "{roots} roots, root {k}");
+                assert_eq!(orbit.root.map(|(root, _)| root), Some(k), "{roots} roots");
+                let smooth = smooth_iteration(&p, &orbit);
+                assert!(smooth > orbit.iter as f32 - 1.0 && smooth <= orbit.iter as f32);
+            }
+        }
+        // The origin, where the derivative vanishes, never converges.
+        let p = FractalParams::new(FractalKind::Newton).with_max_iter(100);
+        let orbit = iterate_point_f64(&p, 0.0, 0.0);
+        assert!(!orbit.escaped(&p));
+        assert_eq!(orbit.root, None);
+    }
+
+    #[test]
+    fn phoenix_weights_the_previous_iterate() {
+        // Without the previous iterate it is the Julia set of the same c.
+        let c = Complex::new(-0.8, 0.156);
+        let julia = FractalParams::new(FractalKind::Julia)
+            .with_julia_c(c)
+            .with_max_iter(300);
+        let plain = FractalParams::new(FractalKind::Phoenix)
+            .with_julia_c(c)
+            .with_phoenix_p(Complex::new(0.0, 0.0))
+            .with_max_iter(300);
+        for (x, y) in [(0.1, 0.1), (0.4, -0.2), (-1.2, 0.3), (1.5, 0.0)] {
+            assert_eq!(
+                iterate_point(&plain, x, y).iter,
+                iterate_point(&julia, x, y).iter
+            );
+        }
+
+        // The classic Phoenix has two lobes, mirror images across the real axis, and the
+        // second step is where p first counts.
+        let p = FractalParams::new(FractalKind::Phoenix).with_max_iter(300);
+        assert!(!iterate_point_f64(&p, 0.0, 0.65).escaped(&p));
+        assert!(!iterate_point_f64(&p, 0.0, -0.65).escaped(&p));
+        assert!(iterate_point_f64(&p, 0.0, 0.0).escaped(&p));
+        let (z0, c) = (Complex64::new(0.3, 0.2), Complex64::from(p.c));
+        let z1 = Phoenix.step_with_previous_f64(&p, z0, Complex64::new(0.0, 0.0), c);
+        let z2 = Phoenix.step_with_previous_f64(&p, z1, z0, c);
+        let expected = quadratic_f64(z1, c);
+        assert!((z2.re - (expected.re - 0.5 * z0.re)).abs() < 1e-12);
+        assert!((z2.im - (expected.im - 0.5 * z0.im)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn celtic_and_buffalo_fold_the_square() {
+        // Both take the Mandelbrot step wherever the parts they fold are already positive,
+        // and the trap follows the same iterates.
+        let c = Complex::new(0.1, 0.2);
+        for z in [Complex::new(0.5, 0.2), Complex::new(-0.6, -0.1)] {
+            let square = quadratic(z, c);
+            let p = FractalParams::default();
+            assert_eq!(Celtic.step(&p, z, c), square);
+            assert_eq!(Buffalo.step(&p, z, c), square);
+        }
+        let z = Complex::new(0.2, 0.5);
+        let square = quadratic(z, c);
+        let p = FractalParams::default();
+        assert_eq!(Celtic.step(&p, z, c).re, (square.re - c.re).abs() + c.re);
+        assert_eq!(Celtic.step(&p, z, c).im, square.im);
+        let z = Complex::new(0.2, -0.5);
+        assert_eq!(
+            Buffalo.step(&p, z, c).im,
+            -(quadratic(z, c).im - c.im) + c.im
+        );
+
+        for kind in [FractalKind::Celtic, FractalKind::Buffalo] {
+            let mut p = FractalParams::new(kind).with_max_iter(100);
+            p.orbit.enabled = true;
+            let orbit = iterate_point(&p, -0.3, 0.1);
+            assert!(!orbit.escaped(&p), "{kind:?}");
+            assert!(orbit.trap_min < f32::MAX, "{kind:?}");
+            let wide = iterate_point_f64(&p, -0.3, 0.1).trap_min;
+            assert!((orbit.trap_min - wide).abs() < 1e-4, "{kind:?}");
+        }
+    }
+
+    #[test]
+    fn lyapunov_exponents_tell_cycles_from_chaos() {
+        let p = FractalParams::new(FractalKind::Lyapunov)
+            .with_lyapunov_sequence("A")
+            .with_max_iter(2000);
+        let exponent = |p: &FractalParams, a: f64, b: f64| {
+            let orbit = iterate_point_f64(p, a, b);
+            assert!(orbit.escaped(p));
+            orbit.lyapunov.unwrap()
+        };
+        // With only A, b does nothing: r = 3.2 settles into a 2-cycle, r = 3.9 is chaotic,
+        // and rates past 4 diverge.
+        assert!(exponent(&p, 3.2, 0.0) < -0.5);
+        assert_eq!(exponent(&p, 3.2, 3.9), exponent(&p, 3.2, 0.0));
+        assert!(exponent(&p, 3.9, 0.0) > 0.3);
+        assert_eq!(exponent(&p, 4.5, 0.0), f32::INFINITY);
+
+        // Swapping the letters swaps the axes; anything but A and B is skipped, and a
+        // sequence without either counts as AB.
+        let ab = p.clone().with_lyapunov_sequence("AB");
+        let ba = p.clone().with_lyapunov_sequence("b-A");
+        let blank = p.clone().with_lyapunov_sequence("");
+        for (a, b) in [(3.4, 3.8), (2.5, 3.9), (3.9, 3.1)] {
+            assert_eq!(exponent(&ab, a, b), exponent(&ba, b, a));
+            assert_eq!(exponent(&ab, a, b), exponent(&blank, a, b));
+        }
+    }
+
+    #[test]
+    fn tricorn_is_three_fold_symmetric() {
+        // Rotating c by a third of a turn rotates the whole orbit with it, and on the real
+        // axis the conjugate changes nothing, so it matches the Mandelbrot set there.
+        let p = FractalParams::new(FractalKind::Tricorn).with_max_iter(500);
+        let third = Complex64::new(-0.5, 3f64.sqrt() / 2.0);
+        for c in [
+            Complex64::new(-1.2, 0.3),
+            Complex64::new(0.1, 0.9),
+            Complex64::new(-0.4, -0.6),
+        ] {
+            let turned = Complex64::new(
+                c.re * third.re - c.im * third.im,
+                c.re * third.im + c.im * third.re,
+            );
+            let a = iterate_point_f64(&p, c.re, c.im);
+            let b = iterate_point_f64(&p, turned.re, turned.im);
+            assert_eq!(a.iter, b.iter, "{c:?}");
+        }
+        let mandelbrot = FractalParams::new(FractalKind::Mandelbrot).with_max_iter(500);
+        for x in [-1.9, -1.0, 0.2, 0.3] {
+            assert_eq!(
+                iterate_point_f64(&p, x, 0.0).iter,
+                iterate_point_f64(&mandelbrot, x, 0.0).iter
+            );
+        }
+        assert_ne!(
+            iterate_point_f64(&p, -1.2, 0.3).iter,
+            iterate_point_f64(&mandelbrot, -1.2, 0.3).iter
+        );
+    }
+
+    #[test]
+    fn registry_resolves_every_formula_to_itself() {
+        for (i, f) in formulas().iter().enumerate() {
+            assert_eq!(formula(f.kind()).name(), f.name());
+            assert_eq!(formula_index(f.kind()), i as u32);
+        }
+    }
+
+    #[test]
+    fn param_defaults_match_fresh_params_and_ranges() {
+        for f in formulas() {
+            let fresh = FractalParams::new(f.kind());
+            for param in f.params() {
+                assert_eq!(param.field.get(&fresh), param.default, "{}", param.label);
+                assert!((param.min..=param.max).contains(&param.default));
+            }
+        }
+    }
+
+    #[test]
+    fn switching_kind_keeps_shared_and_resets_specific_params() {
+        let mut p = FractalParams::new(FractalKind::Julia)
+            .with_max_iter(1234)
+            .with_power(5.0)
+            .with_julia_c(Complex::new(0.3, 0.5));
+        p.escape_radius = 16.0;
+        p.switch_kind(FractalKind::Multibrot);
+        assert_eq!((p.max_iter, p.escape_radius), (1234, 16.0));
+        assert_eq!(p.power, 2.0);
+
+        p.power = 7.0;
+        p.switch_kind(FractalKind::Multibrot);
+        assert_eq!(p.power, 7.0, "re-selecting the current kind is a no-op");
+
+        p.switch_kind(FractalKind::Julia);
+        assert_eq!((p.c.re, p.c.im), (-0.8, 0.156));
+        assert_eq!(p.max_iter, 1234);
+    }
+
+    #[test]
+    fn iteration_suggestion_grows_with_each_decade_of_zoom() {
+        use FractalKind::*;
+        assert_eq!(suggested_max_iter(Mandelbrot, 1.0), 300);
+        assert_eq!(suggested_max_iter(Mandelbrot, 0.25), 300);
+        assert_eq!(suggested_max_iter(Mandelbrot, f32::NAN), 300);
+        assert_eq!(suggested_max_iter(Mandelbrot, 10.0), 1300);
+        assert_eq!(suggested_max_iter(Mandelbrot, 10_000.0), 4300);
+        assert_eq!(suggested_max_iter(Mandelbrot, 31_623.0), 4800);
+        assert_eq!(suggested_max_iter(Julia, 10_000.0), 2700);
+        assert_eq!(suggested_max_iter(BurningShip, 10_000.0), 5100);
+        assert_eq!(suggested_max_iter(Multibrot, 10_000.0), 3500);
+        assert_eq!(suggested_max_iter(Mandelbrot, f32::INFINITY), MAX_SUGGESTED_ITER);
+    }
+
+    #[test]
+    fn auto_iter_follows_the_view_depth() {
+        let overview = formula(FractalKind::Mandelbrot).default_camera(1000.0);
+        assert!((zoom_depth(FractalKind::Mandelbrot, &overview, 1000.0) - 1.0).abs() < 1e-6);
+        let deep = Camera {
+            scale: overview.scale * 10_000.0,
+            ..overview.clone()
+        };
+        // The same view at twice the pixels is the same depth.
+        let deep_4k = Camera {
+            scale: deep.scale * 2.0,
+            ..deep.clone()
+        };
+        assert!((zoom_depth(FractalKind::Mandelbrot, &deep_4k, 2000.0) - 10_000.0).abs() < 1.0);
+
+        let mut params = FractalParams::default();
+        params.apply_auto_iter(&deep, 1000.0);
+        assert_eq!(params.max_iter, 800, "manual mode keeps Max Iter");
+        assert_eq!(params.suggested_max_iter(&deep, 1000.0), 4300);
+        params.auto_iter = true;
+        params.apply_auto_iter(&deep, 1000.0);
+        assert_eq!(params.max_iter, 4300);
+    }
+
+    #[test]
+    fn julia_mode_seeds_z_with_the_pixel() {
+        let p = FractalParams::new(FractalKind::Julia).with_julia_c(Complex::new(0.0, 0.0));
+        // With c = 0 the Julia set is the unit disk.
+        assert!(!formula(FractalKind::Julia).iterate(&p, 0.5, 0.0).escaped(&p));
+        assert!(formula(FractalKind::Julia).iterate(&p, 1.5, 0.0).escaped(&p));
+    }
+
+    #[test]
+    fn burning_ship_squares_the_folded_point() {
+        let p = FractalParams::new(FractalKind::BurningShip);
+        let (z, c) = (Complex::new(0.2, 0.9), Complex::new(-1.0, -1.0));
+        let next = BurningShip.step(&p, z, c);
+        assert!((next.re - -1.77).abs() < 1e-6 && (next.im - -0.64).abs() < 1e-6);
+        // The same point for z mirrored in either axis.
+        assert_eq!(BurningShip.step(&p, Complex::new(-0.2, 0.9), c), next);
+        assert_eq!(BurningShip.step(&p, Complex::new(0.2, -0.9), c), next);
+
+        let legacy = FractalParams {
+            legacy_burning_ship: true,
+            ..p.clone()
+        };
+        let folded = BurningShip.step(&legacy, z, c);
+        assert!((folded.re - 1.77).abs() < 1e-6 && (folded.im - 0.64).abs() < 1e-6);
+
+        // On the real axis the ship is the Mandelbrot set: c = -1.754 is in the period-3
+        // window, -1.4 escapes slowly and -2.1 at once.
+        let mandelbrot = FractalParams::new(FractalKind::Mandelbrot).with_max_iter(500);
+        let ship = FractalParams::new(FractalKind::BurningShip).with_max_iter(500);
+        for re in [-1.754, -1.4, -0.5, 0.2, 0.3, -2.1] {
+            assert_eq!(
+                iterate_point(&ship, re, 0.0).iter,
+                iterate_point(&mandelbrot, re, 0.0).iter,
+                "c = {re}"
+            );
+        }
+        assert_eq!(iterate_point(&ship, -1.754, 0.0).iter, 500);
+    }
+
+    #[test]
+    fn legacy_fold_only_changes_orbit_traps() {
+        let mut p = FractalParams::new(FractalKind::BurningShip).with_max_iter(300);
+        p.orbit.enabled = true;
+        p.orbit.kind = OrbitTrapKind::Point;
+        p.orbit.point = Complex::new(-0.5, -0.5);
+        let legacy = FractalParams {
+            legacy_burning_ship: true,
+            ..p.clone()
+        };
+        let mut traps_differ = false;
+        for (re, im) in [(-1.76, -0.02), (-1.75, -0.05), (-0.4, -0.6), (0.3, 0.1)] {
+            let (a, b) = (iterate_point(&p, re, im), iterate_point(&legacy, re, im));
+            assert_eq!(a.iter, b.iter, "({re}, {im})");
+            traps_differ |= a.trap_min != b.trap_min;
+        }
+        assert!(traps_differ);
+        assert!(BurningShip.gpu_supports(&p));
+        assert!(!BurningShip.gpu_supports(&legacy));
+    }
+}
@@ -0,0 +1,169 @@
+//! Frame-time budget for interactive previews: an iteration cap and a resolution scale,
+//! adjusted from what the last frames cost so the preview keeps its frame rate.
+
+/// Frame time aimed at when none is chosen, in milliseconds (about 30 fps).
+pub const DEFAULT_FRAME_BUDGET_MS: f32 = 33.0;
+
+/// The iteration cap never drops below this.
+pub const MIN_BUDGET_ITER: u32 = 32;
+
+/// The resolution scale never drops below this; smaller previews are too coarse to steer by.
+pub const MIN_BUDGET_SCALE: f32 = 0.25;
+
+/// Iteration cap before the first frame is measured.
+const START_ITER: u32 = 1000;
+
+/// Frames within this factor of the budget either way change nothing, so the controls
+/// settle instead of hunting around the target.
+const DEADBAND: f32 = 1.25;
+
+/// Share of each correction applied, in log terms. Render time is not proportional to
+/// the iteration cap, so taking the whole correction at once overshoots.
+const GAIN: f32 = 0.5;
+
+/// Largest factor the work per frame changes by from one frame to the next.
+const MAX_STEP: f32 = 2.0;
+
+/// Proportional controller trading iterations, then resolution, for frame time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameBudget {
+    /// Milliseconds per frame to aim for.
+    pub target_ms: f32,
+    cap: u32,
+    scale: f32,
+}
+
+impl Default for FrameBudget {
+    fn default() -> Self {
+        Self::new(DEFAULT_FRAME_BUDGET_MS)
+    }
+}
+
+impl FrameBudget {
+    pub fn new(target_ms: f32) -> Self {
+        Self {
+            target_ms,
+            cap: START_ITER,
+            scale: 1.0,
+        }
+    }
+
+    /// Current iteration cap.
+    pub fn cap(&self) -> u32 {
+        self.cap
+    }
+
+    /// Fraction of the preview resolution to render, in `MIN_BUDGET_SCALE..=1`.
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Iterations to render a frame that asks for `max_iter` with.
+    pub fn limit(&self, max_iter: u32) -> u32 {
+        max_iter.min(self.cap)
+    }
+
+    /// Adjusts the cap and scale after a frame took `frame_ms` at the current scale with
+    /// `max_iter` iterations, as returned by [`FrameBudget::limit`]. Slow frames give up
+    /// iterations before resolution; fast ones win resolution back first.
+    pub fn observe(&mut self, frame_ms: f32, max_iter: u32) {
+        if !(frame_ms.is_finite() && frame_ms > 0.0 && self.target_ms > 0.0) {
+            return;
+        }
+        let ratio = self.target_ms / frame_ms;
+        if (1.0 / DEADBAND..=DEADBAND).contains(&ratio) {
+            return;
+        }
+        let step = ratio.powf(GAIN).clamp(1.0 / MAX_STEP, MAX_STEP);
+        // From what the frame used, so a cap far above the frames' iterations cannot
+        // build up while they are cheap.
+        let cap = self.cap.min(max_iter).max(MIN_BUDGET_ITER) as f32;
+        if step < 1.0 {
+            let new_cap = (cap * step).max(MIN_BUDGET_ITER as f32);
+            let rest = step * cap / new_cap;
+            self.cap = new_cap.round() as u32;
+            if rest < 1.0 {
+                self.scale = (self.scale * rest.sqrt()).max(MIN_BUDGET_SCALE);
+            }
+        } else {
+            let new_scale = (self.scale * step.sqrt()).min(1.0);
+            let rest = step / (new_scale / self.scale).powi(2);
+            self.scale = new_scale;
+            if rest > 1.0 {
+                self.cap = (cap * rest).round() as u32;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Drives `budget` for `frames` frames of a renderer costing `ms_per_iter` per
+    /// iteration at full resolution, returning the last frame time.
+    fn run(budget: &mut FrameBudget, max_iter: u32, ms_per_iter: f32, frames: usize) -> f32 {
+        let mut ms = 0.0;
+        for _ in 0..frames {
+            let iter = budget.limit(max_iter);
+            ms = ms_per_iter * iter as f32 * budget.scale().powi(2);
+            budget.observe(ms, iter);
+        }
+        ms
+    }
+
+    #[test]
+    fn slow_frames_settle_within_the_deadband() {
+        let mut budget = FrameBudget::new(33.0);
+        let ms = run(&mut budget, 20_000, 0.01, 40);
+        assert!((33.0 / DEADBAND..=33.0 * DEADBAND).contains(&ms), "{ms} ms");
+        assert_eq!(budget.scale(), 1.0, "iterations give way before resolution");
+
+        // Settled: the same frames change nothing.
+        let settled = budget.clone();
+        run(&mut budget, 20_000, 0.01, 20);
+        assert_eq!(budget, settled);
+    }
+
+    #[test]
+    fn resolution_drops_only_at_the_lowest_cap() {
+        let mut budget = FrameBudget::new(33.0);
+        let ms = run(&mut budget, 20_000, 2.0, 60);
+        assert_eq!(budget.cap(), MIN_BUDGET_ITER);
+        assert!(budget.scale() < 1.0);
+        assert!(ms <= 33.0 * DEADBAND, "{ms} ms");
+
+        // Hopelessly slow frames stop at the floors.
+        run(&mut budget, 20_000, 1000.0, 60);
+        assert_eq!(
+            (budget.cap(), budget.scale()),
+            (MIN_BUDGET_ITER, MIN_BUDGET_SCALE)
+        );
+    }
+
+    #[test]
+    fn cheap_frames_win_back_resolution_then_iterations() {
+        let mut budget = FrameBudget::new(33.0);
+        run(&mut budget, 20_000, 2.0, 60);
+        run(&mut budget, 5000, 0.0001, 60);
+        assert_eq!(budget.scale(), 1.0);
+        assert_eq!(budget.limit(5000), 5000);
+        assert!(
+            budget.cap() <= 5000 * MAX_STEP as u32,
+            "cap wound up to {}",
+            budget.cap()
+        );
+    }
+
+    #[test]
+    fn steps_are_bounded_and_bad_timings_ignored() {
+        let mut budget = FrameBudget::new(33.0);
+        budget.observe(10_000.0, 1000);
+        assert_eq!(budget.cap(), 500);
+        let before = budget.clone();
+        for ms in [f32::NAN, f32::INFINITY, 0.0, -1.0, 33.0] {
+            budget.observe(ms, 500);
+        }
+        assert_eq!(budget, before);
+    }
+}
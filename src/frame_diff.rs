@@ -0,0 +1,549 @@
+//! Frame-to-frame differences of an export, for finding the single frame that pops: each
+//! frame is compared with the one before it and frames that change far more than their
+//! neighbors are flagged.
+
+use std::{
+    fs,
+    io::{self, BufRead},
+    path::Path,
+};
+
+use image::{ImageEncoder, RgbImage, Rgba, RgbaImage};
+
+use crate::{frame_file_name, FrameManifest, ManifestError};
+
+/// Side of the square blocks [`FrameDiff::max_block`] is measured over.
+pub const DIFF_BLOCK: u32 = 16;
+/// Score a frame needs to be flagged by [`find_pops`] unless told otherwise.
+pub const DEFAULT_POP_THRESHOLD: f32 = 8.0;
+/// Frames on each side of a frame whose changes make up its usual change.
+pub const POP_WINDOW: usize = 15;
+/// Smallest spread of the usual change scores are measured in, so the dithering noise of
+/// a still shot is not flagged.
+const MIN_SPREAD: f32 = 1.0 / 255.0;
+/// Size of the plot `analyze-export --plot` writes.
+pub const DIFF_PLOT_SIZE: (u32, u32) = (1200, 300);
+
+#[derive(thiserror::Error, Debug)]
+pub enum AnalyzeError {
+    #[error("IO: {0}")]
+    Io(#[from] io::Error),
+    #[error("Image: {0}")]
+    Image(#[from] image::ImageError),
+    #[error("Manifest: {0}")]
+    Manifest(#[from] ManifestError),
+    #[error("FFmpeg failed: {0}")]
+    Ffmpeg(String),
+    #[error("frame {frame} is {found:?}, not {expected:?} like the frames before it")]
+    SizeChanged {
+        frame: u32,
+        expected: (u32, u32),
+        found: (u32, u32),
+    },
+    #[error("need at least two frames to compare, found {0}")]
+    TooFewFrames(usize),
+}
+
+/// How much frame `frame` changed from the frame before it, in 0..=1 of full scale.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameDiff {
+    pub frame: u32,
+    /// Mean absolute difference over every RGB channel of every pixel.
+    pub mean_abs: f32,
+    /// Largest mean absolute difference of any [`DIFF_BLOCK`]-sized block, which shows
+    /// pops confined to part of the frame.
+    pub max_block: f32,
+}
+
+/// [`FrameDiff`] of `cur` against `prev`, or `None` when their sizes differ.
+pub fn frame_diff(frame: u32, prev: &RgbImage, cur: &RgbImage) -> Option<FrameDiff> {
+    if prev.dimensions() != cur.dimensions() {
+        return None;
+    }
+    let (width, height) = cur.dimensions();
+    let blocks_x = width.div_ceil(DIFF_BLOCK) as usize;
+    let mut total = 0u64;
+    let mut max_block = 0.0f32;
+    let mut block_sums = vec![0u64; blocks_x];
+    for y in 0..height {
+        let row = (y * width * 3) as usize..((y + 1) * width * 3) as usize;
+        let (a, b) = (&prev.as_raw()[row.clone()], &cur.as_raw()[row]);
+        for (x, (a, b)) in a.chunks(3).zip(b.chunks(3)).enumerate() {
+            let diff: u64 = a
+                .iter()
+                .zip(b)
+                .map(|(&a, &b)| u64::from(a.abs_diff(b)))
+                .sum();
+            block_sums[x / DIFF_BLOCK as usize] += diff;
+        }
+        // Close the row of blocks on its last line.
+        if (y + 1) % DIFF_BLOCK == 0 || y + 1 == height {
+            let rows = y % DIFF_BLOCK + 1;
+            for (bx, sum) in block_sums.iter_mut().enumerate() {
+                let cols = (width - bx as u32 * DIFF_BLOCK).min(DIFF_BLOCK);
+                let mean = *sum as f32 / (f64::from(rows * cols) * 3.0 * 255.0) as f32;
+                max_block = max_block.max(mean);
+                total += *sum;
+                *sum = 0;
+            }
+        }
+    }
+    let channels = f64::from(width) * f64::from(height) * 3.0 * 255.0;
+    Some(FrameDiff {
+        frame,
+        mean_abs: (total as f64 / channels.max(1.0)) as f32,
+        max_block,
+    })
+}
+
+/// A frame that changed far more than the frames around it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Suspect {
+    pub frame: u32,
+    /// How many spreads past the usual change the frame's change is, the larger of the
+    /// two metrics.
+    pub score: f32,
+}
+
+fn median(values: &mut [f32]) -> f32 {
+    values.sort_by(f32::total_cmp);
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+/// Robust scores of `values`: each one's distance above the median of up to
+/// [`POP_WINDOW`] values on either side, in median absolute deviations. Zooms and pans
+/// change at a varying rate, so frames are measured against their neighbors rather than
+/// the whole export.
+fn local_scores(values: &[f32]) -> Vec<f32> {
+    (0..values.len())
+        .map(|i| {
+            let lo = i.saturating_sub(POP_WINDOW);
+            let hi = (i + POP_WINDOW + 1).min(values.len());
+            let mut window: Vec<f32> = values[lo..i]
+                .iter()
+                .chain(&values[i + 1..hi])
+                .copied()
+                .collect();
+            if window.is_empty() {
+                return 0.0;
+            }
+            let center = median(&mut window);
+            let mut deviations: Vec<f32> = window.iter().map(|v| (v - center).abs()).collect();
+            // 1.4826 scales the deviation to a standard deviation for normal noise.
+            let spread = (1.4826 * median(&mut deviations)).max(MIN_SPREAD);
+            (values[i] - center) / spread
+        })
+        .collect()
+}
+
+/// Frames of `diffs` scoring at least `threshold` on either metric, in frame order. A
+/// single bad frame usually shows as two suspects: the change into it and back out.
+pub fn find_pops(diffs: &[FrameDiff], threshold: f32) -> Vec<Suspect> {
+    let mean: Vec<f32> = diffs.iter().map(|d| d.mean_abs).collect();
+    let block: Vec<f32> = diffs.iter().map(|d| d.max_block).collect();
+    local_scores(&mean)
+        .into_iter()
+        .zip(local_scores(&block))
+        .zip(diffs)
+        .filter_map(|((a, b), diff)| {
+            let score = a.max(b);
+            (score >= threshold).then_some(Suspect {
+                frame: diff.frame,
+                score,
+            })
+        })
+        .collect()
+}
+
+/// Differences between consecutive frames of an export and the frames that pop.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportAnalysis {
+    /// One per frame after the first.
+    pub diffs: Vec<FrameDiff>,
+    pub suspects: Vec<Suspect>,
+    /// Frame rate read from the video, if any.
+    pub fps: Option<f64>,
+}
+
+impl ExportAnalysis {
+    /// Scores the `diffs` of frames read from somewhere.
+    pub fn new(diffs: Vec<FrameDiff>, threshold: f32, fps: Option<f64>) -> Self {
+        Self {
+            suspects: find_pops(&diffs, threshold),
+            diffs,
+            fps,
+        }
+    }
+}
+
+/// Compares each of `frames` with the one before it, collecting the diffs. A frame
+/// without an image is known to equal the frame before it.
+fn diff_sequence(
+    frames: impl Iterator<Item = Result<(u32, Option<RgbImage>), AnalyzeError>>,
+) -> Result<Vec<FrameDiff>, AnalyzeError> {
+    let mut prev: Option<RgbImage> = None;
+    let mut diffs = Vec::new();
+    let mut count = 0;
+    for next in frames {
+        let (frame, image) = next?;
+        count += 1;
+        match (&prev, image) {
+            (Some(_), None) => diffs.push(FrameDiff {
+                frame,
+                mean_abs: 0.0,
+                max_block: 0.0,
+            }),
+            (Some(last), Some(image)) => {
+                diffs.push(
+                    frame_diff(frame, last, &image).ok_or(AnalyzeError::SizeChanged {
+                        frame,
+                        expected: last.dimensions(),
+                        found: image.dimensions(),
+                    })?,
+                );
+                prev = Some(image);
+            }
+            (None, image) => prev = image,
+        }
+    }
+    if count < 2 {
+        return Err(AnalyzeError::TooFewFrames(count));
+    }
+    Ok(diffs)
+}
+
+/// Frame numbers of the `frame_NNNNNN.png` files in `dir`, in order.
+pub fn frame_numbers(dir: &Path) -> io::Result<Vec<u32>> {
+    let mut frames: Vec<u32> = fs::read_dir(dir)?
+        .filter_map(|entry| {
+            let name = entry.ok()?.file_name().into_string().ok()?;
+            let frame: u32 = name
+                .strip_prefix("frame_")?
+                .strip_suffix(".png")?
+                .parse()
+                .ok()?;
+            (name == frame_file_name(frame)).then_some(frame)
+        })
+        .collect();
+    frames.sort_unstable();
+    Ok(frames)
+}
+
+/// Analyzes the frames of an export work directory (see `ExportSettings::work_dir`). When
+/// the directory's manifest says a frame's pixels equal the previous frame's, the frame
+/// is not decoded.
+pub fn analyze_frames_dir(dir: &Path, threshold: f32) -> Result<ExportAnalysis, AnalyzeError> {
+    let manifest = FrameManifest::load(dir)?;
+    let pixels = |frame: u32| {
+        manifest
+            .as_ref()
+            .and_then(|m| m.frames.get(&frame))
+            .map(|h| h.pixels.as_str())
+    };
+    let frames = frame_numbers(dir)?;
+    let mut last = None;
+    let diffs = diff_sequence(frames.iter().map(|&frame| {
+        let same =
+            last.is_some_and(|last| pixels(frame).is_some() && pixels(frame) == pixels(last));
+        last = Some(frame);
+        if same {
+            return Ok((frame, None));
+        }
+        let image = image::open(dir.join(frame_file_name(frame)))?.to_rgb8();
+        Ok((frame, Some(image)))
+    }))?;
+    Ok(ExportAnalysis::new(diffs, threshold, None))
+}
+
+/// Reads one binary PPM (P6, 8-bit) image, or `None` at the end of the stream.
+pub fn read_ppm(reader: &mut impl BufRead) -> io::Result<Option<RgbImage>> {
+    if reader.fill_buf()?.is_empty() {
+        return Ok(None);
+    }
+    let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, format!("PPM: {msg}"));
+    let mut fields = [0u32; 3];
+    let mut magic = [0u8; 2];
+    reader.read_exact(&mut magic)?;
+    if &magic != b"P6" {
+        return Err(invalid("not a binary PPM"));
+    }
+    for field in &mut fields {
+        let mut digits = String::new();
+        loop {
+            let mut byte = [0u8];
+            reader.read_exact(&mut byte)?;
+            match byte[0] {
+                b'#' if digits.is_empty() => {
+                    reader.read_until(b'\n', &mut Vec::new())?;
+                }
+                b if b.is_ascii_whitespace() && digits.is_empty() => {}
+                b if b.is_ascii_whitespace() => break,
+                b if b.is_ascii_digit() => digits.push(b as char),
+                _ => return Err(invalid("bad header")),
+            }
+        }
+        *field = digits.parse().map_err(|_| invalid("bad header"))?;
+    }
+    let [width, height, max] = fields;
+    if max != 255 {
+        return Err(invalid("only 8-bit images are supported"));
+    }
+    let mut data = vec![0u8; width as usize * height as usize * 3];
+    reader.read_exact(&mut data)?;
+    RgbImage::from_raw(width, height, data)
+        .map(Some)
+        .ok_or_else(|| invalid("bad size"))
+}
+
+/// Frame rate of the first video stream in ffmpeg's log, from `Video: ..., 30 fps, ...`.
+pub fn ffmpeg_log_fps(log: &str) -> Option<f64> {
+    log.lines()
+        .filter(|line| line.contains("Video:"))
+        .flat_map(|line| line.split(", "))
+        .find_map(|part| part.trim().strip_suffix(" fps")?.parse().ok())
+}
+
+/// Analyzes a video, decoded by `ffmpeg` to a pipe of PPM images.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn analyze_video(
+    ffmpeg: &Path,
+    video: &Path,
+    threshold: f32,
+) -> Result<ExportAnalysis, AnalyzeError> {
+    use std::process::{Command, Stdio};
+
+    let mut child = Command::new(ffmpeg)
+        .args(["-hide_banner", "-nostdin", "-i"])
+        .arg(video)
+        .args(["-f", "image2pipe", "-vcodec", "ppm", "-"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| AnalyzeError::Ffmpeg(format!("could not run {}: {e}", ffmpeg.display())))?;
+    // Drained on its own thread so a chatty ffmpeg never blocks on a full pipe.
+    let mut stderr = child.stderr.take().expect("stderr is piped");
+    let log = std::thread::spawn(move || {
+        let mut log = Vec::new();
+        io::Read::read_to_end(&mut stderr, &mut log).map(|_| log)
+    });
+    let mut stdout = io::BufReader::new(child.stdout.take().expect("stdout is piped"));
+    let mut frame = 0;
+    let diffs = diff_sequence(std::iter::from_fn(|| {
+        let image = read_ppm(&mut stdout)
+            .map_err(AnalyzeError::from)
+            .transpose()?;
+        frame += 1;
+        Some(image.map(|image| (frame - 1, Some(image))))
+    }));
+    // Too few frames is more likely ffmpeg failing, and reported as such below.
+    let diffs = match diffs {
+        Err(e) if !matches!(e, AnalyzeError::TooFewFrames(_)) => {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(e);
+        }
+        diffs => diffs,
+    };
+    drop(stdout);
+    let status = child.wait()?;
+    let log = log.join().expect("ffmpeg log reader panicked")?;
+    if !status.success() {
+        return Err(AnalyzeError::Ffmpeg(crate::export::ffmpeg_error_summary(
+            &log,
+        )));
+    }
+    Ok(ExportAnalysis::new(
+        diffs?,
+        threshold,
+        ffmpeg_log_fps(&String::from_utf8_lossy(&log)),
+    ))
+}
+
+const PLOT_BACKGROUND: Rgba<u8> = Rgba([24, 24, 28, 255]);
+const PLOT_MEAN: Rgba<u8> = Rgba([90, 170, 255, 255]);
+const PLOT_BLOCK: Rgba<u8> = Rgba([255, 170, 60, 255]);
+const PLOT_SUSPECT: Rgba<u8> = Rgba([220, 40, 40, 255]);
+
+/// Plot of the metrics over the frames, `size` pixels: mean difference in blue, largest
+/// block difference in orange, both to the scale of the largest block difference, over
+/// red lines at the suspects.
+pub fn diff_plot(analysis: &ExportAnalysis, size: (u32, u32)) -> RgbaImage {
+    let (width, height) = (size.0.max(2), size.1.max(2));
+    let mut plot = RgbaImage::from_pixel(width, height, PLOT_BACKGROUND);
+    let diffs = &analysis.diffs;
+    let Some(last) = diffs.len().checked_sub(1) else {
+        return plot;
+    };
+    let x_of = |i: usize| {
+        if last == 0 {
+            0
+        } else {
+            (i as u64 * u64::from(width - 1) / last as u64) as u32
+        }
+    };
+    for suspect in &analysis.suspects {
+        if let Some(i) = diffs.iter().position(|d| d.frame == suspect.frame) {
+            for y in 0..height {
+                plot.put_pixel(x_of(i), y, PLOT_SUSPECT);
+            }
+        }
+    }
+    let peak = diffs.iter().map(|d| d.max_block).fold(0.0, f32::max);
+    let y_of = |v: f32| {
+        let v = if peak > 0.0 { v / peak } else { 0.0 };
+        ((1.0 - v) * (height - 1) as f32).round() as u32
+    };
+    let block: Vec<f32> = diffs.iter().map(|d| d.max_block).collect();
+    let mean: Vec<f32> = diffs.iter().map(|d| d.mean_abs).collect();
+    for (values, color) in [(block, PLOT_BLOCK), (mean, PLOT_MEAN)] {
+        for (i, pair) in values.windows(2).enumerate() {
+            let (x0, x1) = (x_of(i), x_of(i + 1));
+            let (y0, y1) = (y_of(pair[0]), y_of(pair[1]));
+            // Each column is joined to the one before so steep steps stay unbroken.
+            let mut last_y = y0;
+            for x in x0..=x1 {
+                let t = if x1 > x0 {
+                    (x - x0) as f32 / (x1 - x0) as f32
+                } else {
+                    1.0
+                };
+                let y = (y0 as f32 + (y1 as f32 - y0 as f32) * t).round() as u32;
+                for y in y.min(last_y)..=y.max(last_y) {
+                    plot.put_pixel(x, y, color);
+                }
+                last_y = y;
+            }
+        }
+        if let [only] = values[..] {
+            plot.put_pixel(0, y_of(only), color);
+        }
+    }
+    plot
+}
+
+/// Writes [`diff_plot`] to a PNG.
+pub fn write_diff_plot(
+    path: &Path,
+    analysis: &ExportAnalysis,
+    size: (u32, u32),
+) -> Result<(), AnalyzeError> {
+    let plot = diff_plot(analysis, size);
+    let file = io::BufWriter::new(fs::File::create(path)?);
+    image::codecs::png::PngEncoder::new(file).write_image(
+        plot.as_raw(),
+        plot.width(),
+        plot.height(),
+        image::ColorType::Rgba8,
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(size: (u32, u32), value: u8) -> RgbImage {
+        RgbImage::from_pixel(size.0, size.1, image::Rgb([value; 3]))
+    }
+
+    #[test]
+    fn block_diff_finds_local_changes() {
+        let prev = solid((64, 40), 100);
+        assert_eq!(
+            frame_diff(3, &prev, &prev),
+            Some(FrameDiff {
+                frame: 3,
+                mean_abs: 0.0,
+                max_block: 0.0
+            })
+        );
+        let mut cur = prev.clone();
+        // One full block, in the ragged bottom row of blocks.
+        for y in 32..40 {
+            for x in 16..32 {
+                cur.put_pixel(x, y, image::Rgb([100 + 51; 3]));
+            }
+        }
+        let diff = frame_diff(4, &prev, &cur).unwrap();
+        assert!((diff.max_block - 0.2).abs() < 1e-6, "{diff:?}");
+        assert!(
+            (diff.mean_abs - 0.2 * 128.0 / 2560.0).abs() < 1e-6,
+            "{diff:?}"
+        );
+        assert_eq!(frame_diff(5, &prev, &solid((64, 41), 100)), None);
+    }
+
+    #[test]
+    fn pops_stand_out_from_a_steady_zoom() {
+        // A zoom whose change per frame grows steadily, with frame 40 out of place.
+        let diffs: Vec<FrameDiff> = (1..80)
+            .map(|frame| {
+                let usual = 0.02 + frame as f32 * 0.0005 + (frame % 3) as f32 * 0.001;
+                let pop = if frame == 40 || frame == 41 {
+                    0.08
+                } else {
+                    0.0
+                };
+                FrameDiff {
+                    frame,
+                    mean_abs: usual + pop,
+                    max_block: 2.0 * usual + pop,
+                }
+            })
+            .collect();
+        let frames: Vec<u32> = find_pops(&diffs, DEFAULT_POP_THRESHOLD)
+            .iter()
+            .map(|s| s.frame)
+            .collect();
+        assert_eq!(frames, [40, 41]);
+
+        // Dithering noise in a still shot is not a pop.
+        let still: Vec<FrameDiff> = (1..30)
+            .map(|frame| FrameDiff {
+                frame,
+                mean_abs: if frame == 12 { 0.5 / 255.0 } else { 0.0 },
+                max_block: if frame == 12 { 2.0 / 255.0 } else { 0.0 },
+            })
+            .collect();
+        assert!(find_pops(&still, DEFAULT_POP_THRESHOLD).is_empty());
+    }
+
+    #[test]
+    fn frames_dirs_and_ppm_streams_are_read() {
+        let dir = tempfile::tempdir().unwrap();
+        for (frame, value) in [(0, 10), (1, 20), (2, 30), (3, 200), (4, 50)] {
+            solid((24, 16), value)
+                .save(dir.path().join(frame_file_name(frame)))
+                .unwrap();
+        }
+        fs::write(dir.path().join("frame_5.png"), b"not ours").unwrap();
+        let analysis = analyze_frames_dir(dir.path(), 3.0).unwrap();
+        let frames: Vec<u32> = analysis.diffs.iter().map(|d| d.frame).collect();
+        assert_eq!(frames, [1, 2, 3, 4]);
+        assert!((analysis.diffs[2].mean_abs - 170.0 / 255.0).abs() < 1e-6);
+        assert_eq!(analysis.suspects.first().map(|s| s.frame), Some(3));
+        let plot = diff_plot(&analysis, (100, 40));
+        assert_eq!(plot.dimensions(), (100, 40));
+        assert!(plot.pixels().any(|&px| px == PLOT_SUSPECT));
+
+        let mut stream = Vec::new();
+        for value in [1u8, 2] {
+            stream.extend_from_slice(b"P6\n# ffmpeg\n3 2\n255\n");
+            stream.extend(std::iter::repeat_n(value, 18));
+        }
+        let mut reader = io::Cursor::new(stream);
+        assert_eq!(read_ppm(&mut reader).unwrap(), Some(solid((3, 2), 1)));
+        assert_eq!(read_ppm(&mut reader).unwrap(), Some(solid((3, 2), 2)));
+        assert_eq!(read_ppm(&mut reader).unwrap(), None);
+
+        let log = "  Stream #0:0(und): Video: h264 (High), yuv420p, 1280x720, 2 kb/s, 29.97 fps, 29.97 tbr";
+        assert_eq!(ffmpeg_log_fps(log), Some(29.97));
+        assert_eq!(ffmpeg_log_fps("Audio: aac, 48000 Hz"), None);
+    }
+}
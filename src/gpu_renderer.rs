@@ -0,0 +1,1037 @@
+//! wgpu compute path used by [`RenderBackend::Gpu`](crate::RenderBackend::Gpu).
+
+use crate::{
+    bake_palette, central_sample, formula, formula_index, formulas, palette_key, Camera,
+    ColoringMode, ExteriorMode, FractalParams, InteriorColoring, OrbitTrapKind, PixelSample,
+    RenderError, Renderer, TileInfo, MAX_SUPERSAMPLE,
+};
+use bytemuck::{Pod, Zeroable};
+use std::borrow::Cow;
+use std::num::NonZeroU64;
+use wgpu::util::DeviceExt;
+
+/// Shader template; `{{JULIA_CASES}}` and `{{FORMULA_CASES}}` are filled from the formula
+/// registry by [`shader_source`].
+const SHADER_TEMPLATE: &str = r#"
+struct VertexOut {
+@builtin(position) pos: vec4<f32>;
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) idx: u32) -> VertexOut {
+var positions = array<vec2<f32>, 3>(
+    vec2<f32>(-1.0, -3.0),
+    vec2<f32>(-1.0, 1.0),
+    vec2<f32>(3.0, 1.0),
+);
+var output: VertexOut;
+let pos = positions[idx];
+output.pos = vec4<f32>(pos, 0.0, 1.0);
+return output;
+}
+
+struct Params {
+full: vec2<f32>;
+offset: vec2<f32>;
+tile: vec2<f32>;
+center: vec2<f32>;
+julia_c: vec2<f32>;
+trap_point: vec2<f32>;
+orbit_color: vec3<f32>;
+orbit_enabled: f32;
+scale: f32;
+rotation: f32;
+max_iter: u32;
+fractal_kind: u32;
+escape_radius: f32;
+power: f32;
+orbit_kind: u32;
+orbit_radius: f32;
+orbit_softness: f32;
+exposure: f32;
+gamma: f32;
+palette_repeat: f32;
+exterior_mode: u32;
+palette_phase: f32;
+sample_count: u32;
+sample_central: u32;
+exterior_color: vec4<f32>;
+contour_color: vec4<f32>;
+band_count: f32;
+band_contours: f32;
+// Formula degree for smooth coloring, as `FractalFormula::degree`.
+degree: f32;
+interior_coloring: u32;
+// Sub-pixel offsets, two to an entry, as `FractalParams::sample_offsets`.
+sample_offsets: array<vec4<f32>, 8>;
+interior_color: vec4<f32>;
+// Stripe average coloring, as `StripeSum`; a mix of 0 leaves it off.
+stripe_density: f32;
+stripe_mix: f32;
+// Weight of the previous `z`, as `FractalParams::phoenix_p`.
+phoenix_p: vec2<f32>;
+};
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var palette_tex: texture_2d<f32>;
+@group(0) @binding(2) var palette_sampler: sampler;
+
+fn is_julia(kind: u32) -> bool {
+switch kind {
+    {{JULIA_CASES}}
+    default: { return false; }
+}
+}
+
+fn palette_sample(t: f32) -> vec3<f32> {
+return textureSample(palette_tex, palette_sampler, vec2<f32>(fract(t), 0.5)).rgb;
+}
+
+// Escape-time pass for one framebuffer pixel: (smooth iteration count, trap distance,
+// interior flag, interior shade or stripe average), the GPU counterpart of `PixelSample`.
+fn sample_pixel(pixel: vec2<f32>) -> vec4<f32> {
+let screen = pixel - params.full * 0.5;
+let cos_r = cos(params.rotation);
+let sin_r = sin(params.rotation);
+let coord = vec2<f32>(
+    (screen.x * cos_r - screen.y * sin_r) / params.scale + params.center.x,
+    (screen.x * sin_r + screen.y * cos_r) / params.scale + params.center.y,
+);
+
+var z = vec2<f32>(0.0, 0.0);
+// The `z` of the step before, for formulas that read it.
+var prev = vec2<f32>(0.0, 0.0);
+var c = coord;
+if (is_julia(params.fractal_kind)) {
+    z = coord;
+    c = params.julia_c;
+}
+
+let escape = params.escape_radius * params.escape_radius;
+var iter: u32 = 0u;
+var smooth: f32 = 0.0;
+var trap: f32 = 1e6;
+var stripe_sum = 0.0;
+var stripe_last = 0.0;
+
+loop {
+    if (iter >= params.max_iter) {
+        break;
+    }
+    var zx = z.x;
+    var zy = z.y;
+    var x2 = zx * zx;
+    var y2 = zy * zy;
+
+    if (x2 + y2 > escape) {
+        // Renormalized against the actual escape radius, as `smooth_iteration` does.
+        // From |z|'s larger component, as x2 + y2 overflows at high powers and radii.
+        let big = max(abs(zx), abs(zy));
+        let small = min(abs(zx), abs(zy)) / max(big, 1e-10);
+        let log_r = min(log(max(big, 1e-10)) + 0.5 * log(1.0 + small * small), 88.72);
+        let log_bailout = log(max(params.escape_radius, 1.01));
+        let log_degree = log(max(params.degree, 1.01));
+        smooth = f32(iter) + 1.0 - log(max(log_r / log_bailout, 1.0)) / log_degree;
+        break;
+    }
+
+    let last = z;
+    switch params.fractal_kind {
+        {{FORMULA_CASES}}
+        default: {}
+    }
+    prev = last;
+
+    // Stripe function of every iterate but the first, as `StripeSum::add`.
+    if (params.stripe_mix > 0.0 && iter > 0u) {
+        stripe_last = 0.5 + 0.5 * sin(params.stripe_density * atan2(z.y, z.x));
+        stripe_sum = stripe_sum + stripe_last;
+    }
+
+    if (params.orbit_enabled > 0.5) {
+        let dist = switch params.orbit_kind {
+            case 0u => length(z - params.trap_point),
+            case 1u => abs(length(z) - params.orbit_radius),
+            default => min(abs(z.x - params.trap_point.x), abs(z.y - params.trap_point.y)),
+        };
+        trap = min(trap, dist);
+    }
+
+    iter = iter + 1u;
+}
+
+// Interior palette position, as `interior_shade`: 2 final |z|, 3 final angle, 4 trap
+// distance.
+var interior_shade = 0.0;
+if (iter >= params.max_iter) {
+    let radius = max(params.escape_radius, 1e-3);
+    switch params.interior_coloring {
+        case 2u: { interior_shade = min(length(z) / radius, 1.0); }
+        case 3u: { interior_shade = atan2(z.y, z.x) / 6.2831855 + 0.5; }
+        case 4u: { interior_shade = min(trap / radius, 1.0); }
+        default: {}
+    }
+}
+
+// Stripe average of an escaped orbit, as `stripe_average`: the averages with and without
+// the final iterate, interpolated by the smooth count's fraction.
+var stripe = 0.0;
+if (params.stripe_mix > 0.0 && iter < params.max_iter && iter > 1u) {
+    let terms = f32(iter - 1u);
+    let with_last = stripe_sum / terms;
+    let without_last = select(with_last, (stripe_sum - stripe_last) / (terms - 1.0), iter > 2u);
+    stripe = mix(without_last, with_last, clamp(smooth - f32(iter), 0.0, 1.0));
+}
+
+let interior = iter >= params.max_iter;
+return vec4<f32>(smooth, trap, select(0.0, 1.0, interior), select(stripe, interior_shade, interior));
+}
+
+// Palette lookup, tone mapping and trap tint of a sample from `sample_pixel`. The palette
+// texture is baked without the phase, so cycling it only changes the uniforms.
+fn shade(orbit: vec4<f32>) -> vec4<f32> {
+var t = orbit.x / max(params.palette_repeat, 1e-3);
+if (params.band_count > 0.0) {
+    // Dwell bands: the start of the band, as `palette_position`.
+    t = floor(t * params.band_count) / params.band_count;
+}
+if (orbit.z > 0.5) {
+    t = orbit.w;
+}
+var color = palette_sample(t + params.palette_phase);
+// Stripe average mixed over the iteration count's color, as `shade_samples`.
+if (orbit.z < 0.5 && params.stripe_mix > 0.0) {
+    color = mix(color, palette_sample(orbit.w + params.palette_phase), params.stripe_mix);
+}
+color = 1.0 - exp(-color * params.exposure);
+color = pow(color, vec3<f32>(1.0 / params.gamma));
+// Interior coloring 1 is a solid color, as `InteriorColoring::Solid`.
+if (orbit.z > 0.5 && params.interior_coloring == 1u) {
+    color = params.interior_color.rgb;
+}
+
+// Exterior modes, as `ExteriorMode`: 1 solid color, 2 transparent.
+var alpha = 1.0;
+if (orbit.z < 0.5 && params.exterior_mode != 0u) {
+    color = params.exterior_color.rgb;
+    alpha = select(1.0, 0.0, params.exterior_mode == 2u);
+}
+
+if (params.orbit_enabled > 0.5) {
+    var trap_mix = clamp(exp(-orbit.y * params.orbit_softness), 0.0, 1.0);
+    if (alpha < 1.0) {
+        // Over a transparent exterior the tint keeps its color and its weight becomes coverage.
+        let over = trap_mix + alpha * (1.0 - trap_mix);
+        trap_mix = select(0.0, trap_mix / over, over > 0.0);
+        alpha = over;
+    }
+    color = color + (params.orbit_color - color) * trap_mix;
+}
+
+return vec4<f32>(color, alpha);
+}
+
+// Dwell band of a sample, as `dwell_band`; -1 inside the set.
+fn dwell_band(orbit: vec4<f32>) -> f32 {
+return select(floor(orbit.x / max(params.palette_repeat, 1e-3) * params.band_count), -1.0, orbit.z > 0.5);
+}
+
+// Band contours from screen-space derivatives: an escaped pixel is on an edge when its 2×2
+// quad spans two bands. `band_step` is `fwidth` of `dwell_band`, taken in uniform control
+// flow. Lines come out up to two pixels wide and also outline the set, unlike the CPU's
+// exact neighbor comparison in `paint_band_contours`.
+fn contour(color: vec4<f32>, orbit: vec4<f32>, band_step: f32) -> vec4<f32> {
+if (params.band_contours > 0.5 && orbit.z < 0.5 && band_step > 0.0) {
+    return vec4<f32>(params.contour_color.rgb, 1.0);
+}
+return color;
+}
+
+// Whole framebuffer pixels plus the tile offset: the same integer grid the CPU path uses,
+// so a pixel maps to the same point whichever tile it lands in.
+fn frame_pixel(pos: vec4<f32>) -> vec2<f32> {
+return params.offset + floor(pos.xy);
+}
+
+struct Supersampled {
+color: vec4<f32>,
+orbit: vec4<f32>,
+};
+
+// Every sample of a pixel shaded and averaged, with the one nearest its middle kept as
+// the pixel's sample, as `render_band_cpu` does.
+fn supersample(pixel: vec2<f32>) -> Supersampled {
+var out: Supersampled;
+out.color = vec4<f32>(0.0);
+for (var i = 0u; i < params.sample_count; i = i + 1u) {
+    let pair = params.sample_offsets[i / 2u];
+    let offset = select(pair.xy, pair.zw, i % 2u == 1u);
+    let orbit = sample_pixel(pixel + offset - 0.5);
+    out.color = out.color + shade(orbit);
+    if (i == params.sample_central) {
+        out.orbit = orbit;
+    }
+}
+out.color = out.color / f32(max(params.sample_count, 1u));
+return out;
+}
+
+@fragment
+fn fs_main(input: VertexOut) -> @location(0) vec4<f32> {
+let pixel = supersample(frame_pixel(input.pos));
+return contour(pixel.color, pixel.orbit, fwidth(dwell_band(pixel.orbit)));
+}
+
+struct SampledOut {
+@location(0) color: vec4<f32>,
+@location(1) orbit: vec4<f32>,
+};
+
+@fragment
+fn fs_samples(input: VertexOut) -> SampledOut {
+var out: SampledOut;
+let pixel = supersample(frame_pixel(input.pos));
+out.orbit = pixel.orbit;
+out.color = contour(pixel.color, pixel.orbit, fwidth(dwell_band(pixel.orbit)));
+return out;
+}
+
+@group(0) @binding(3) var samples_tex: texture_2d<f32>;
+
+// Recolors samples kept from an earlier `fs_samples` pass over the same tile.
+@fragment
+fn fs_shade(input: VertexOut) -> @location(0) vec4<f32> {
+let orbit = textureLoad(samples_tex, vec2<i32>(floor(input.pos.xy)), 0);
+return contour(shade(orbit), orbit, fwidth(dwell_band(orbit)));
+}
+"#;
+
+/// The fractal shader with one `switch` case per registered formula that has a WGSL step.
+fn shader_source() -> String {
+    let julia: Vec<String> = formulas()
+        .iter()
+        .filter(|f| f.julia_mode() && f.wgsl_step().is_some())
+        .map(|f| format!("{}u", formula_index(f.kind())))
+        .collect();
+    let julia_cases = if julia.is_empty() {
+        String::new()
+    } else {
+        format!("case {}: {{ return true; }}", julia.join(", "))
+    };
+    let formula_cases: String = formulas()
+        .iter()
+        .filter_map(|f| {
+            let step = f.wgsl_step()?;
+            Some(format!(
+                "case {}u: {{\n        {step}\n        }}\n        ",
+                formula_index(f.kind())
+            ))
+        })
+        .collect();
+    SHADER_TEMPLATE
+        .replace("{{JULIA_CASES}}", &julia_cases)
+        .replace("{{FORMULA_CASES}}", &formula_cases)
+}
+
+pub struct GpuRenderer {
+    /// Largest texture side the device allows, which bounds the tile size.
+    max_texture_size: u32,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::RenderPipeline,
+    /// [`GpuRenderer::pipeline`] that also writes each pixel's sample to a float target.
+    samples_pipeline: wgpu::RenderPipeline,
+    /// Recolors a samples texture without iterating.
+    shade_pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    shade_bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    /// Palette texture of the last draw, rebuilt only when the stops change.
+    palette: Option<PaletteTexture>,
+}
+
+/// A palette baked at phase 0 and uploaded once.
+struct PaletteTexture {
+    /// [`palette_key`] of the stops it was baked from.
+    key: u64,
+    view: wgpu::TextureView,
+}
+
+/// Entries in the palette texture, as many as the CPU lookup tables.
+const PALETTE_SIZE: usize = 2048;
+
+/// Entries of `sample_offsets` in the uniforms, each holding two sub-pixel offsets.
+const SAMPLE_PAIRS: usize = (MAX_SUPERSAMPLE * MAX_SUPERSAMPLE / 2) as usize;
+
+/// Float render target holding one sample per pixel.
+const SAMPLE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba32Float;
+
+impl GpuRenderer {
+    pub fn new() -> Result<Self, String> {
+        let instance = wgpu::Instance::default();
+        let adapter =
+            pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+                .ok_or_else(|| "No GPU adapter available".to_string())?;
+        let (device, queue) =
+            pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None))
+                .map_err(|e| format!("Failed to create device: {e}"))?;
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("fractal_shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Owned(shader_source())),
+        });
+
+        let mut entries = vec![
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: Some(
+                        NonZeroU64::new(std::mem::size_of::<GpuUniform>() as u64).unwrap(),
+                    ),
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+        ];
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("fractal_bind"),
+            entries: &entries,
+        });
+        entries.push(wgpu::BindGroupLayoutEntry {
+            binding: 3,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        });
+        let shade_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("shade_bind"),
+                entries: &entries,
+            });
+
+        let color = Some(wgpu::ColorTargetState {
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            blend: Some(wgpu::BlendState::REPLACE),
+            write_mask: wgpu::ColorWrites::ALL,
+        });
+        let sample = Some(wgpu::ColorTargetState {
+            format: SAMPLE_FORMAT,
+            blend: None,
+            write_mask: wgpu::ColorWrites::ALL,
+        });
+        let pipeline = create_pipeline(
+            &device,
+            &shader,
+            &bind_group_layout,
+            "fs_main",
+            std::slice::from_ref(&color),
+        );
+        let samples_pipeline = create_pipeline(
+            &device,
+            &shader,
+            &bind_group_layout,
+            "fs_samples",
+            &[color.clone(), sample],
+        );
+        let shade_pipeline = create_pipeline(
+            &device,
+            &shader,
+            &shade_bind_group_layout,
+            "fs_shade",
+            &[color],
+        );
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor::default());
+
+        Ok(Self {
+            max_texture_size: device.limits().max_texture_dimension_2d,
+            device,
+            queue,
+            pipeline,
+            samples_pipeline,
+            shade_pipeline,
+            bind_group_layout,
+            shade_bind_group_layout,
+            sampler,
+            palette: None,
+        })
+    }
+
+    pub fn render(
+        &mut self,
+        tile: &TileInfo,
+        params: &FractalParams,
+        cam: &Camera,
+    ) -> Result<Vec<u8>, String> {
+        self.upload_palette(params);
+        let target = self.target(tile, wgpu::TextureFormat::Rgba8Unorm);
+        let bind_group = self.bind_group(tile, params, cam, None);
+        self.draw(
+            &self.pipeline,
+            &bind_group,
+            &[&target.create_view(&wgpu::TextureViewDescriptor::default())],
+        );
+        self.read_back(&target, tile, 4)
+    }
+
+    /// [`GpuRenderer::render`], also reading back every pixel's [`PixelSample`].
+    pub fn render_samples(
+        &mut self,
+        tile: &TileInfo,
+        params: &FractalParams,
+        cam: &Camera,
+    ) -> Result<(Vec<u8>, Vec<PixelSample>), String> {
+        self.upload_palette(params);
+        let target = self.target(tile, wgpu::TextureFormat::Rgba8Unorm);
+        let sample_target = self.target(tile, SAMPLE_FORMAT);
+        let bind_group = self.bind_group(tile, params, cam, None);
+        self.draw(
+            &self.samples_pipeline,
+            &bind_group,
+            &[
+                &target.create_view(&wgpu::TextureViewDescriptor::default()),
+                &sample_target.create_view(&wgpu::TextureViewDescriptor::default()),
+            ],
+        );
+        let pixels = self.read_back(&target, tile, 4)?;
+        let raw = self.read_back(&sample_target, tile, 16)?;
+        let samples = raw
+            .chunks_exact(16)
+            .map(|texel| {
+                let [smooth_iter, trap_min, interior, shade]: [f32; 4] =
+                    bytemuck::pod_read_unaligned(texel);
+                let interior = interior > 0.5;
+                PixelSample {
+                    smooth_iter,
+                    interior,
+                    trap_min,
+                    interior_shade: if interior { shade } else { 0.0 },
+                    distance: None,
+                    stripe: (!interior && params.coloring == ColoringMode::StripeAverage)
+                        .then_some(shade),
+                    root: None,
+                    lyapunov: None,
+                }
+            })
+            .collect();
+        Ok((pixels, samples))
+    }
+
+    /// Recolors `samples` of `tile` with the same shading [`GpuRenderer::render`] uses.
+    pub fn shade(
+        &mut self,
+        tile: &TileInfo,
+        samples: &[PixelSample],
+        params: &FractalParams,
+    ) -> Result<Vec<u8>, String> {
+        self.upload_palette(params);
+        let texels: Vec<[f32; 4]> = samples
+            .iter()
+            .map(|s| {
+                let interior = if s.interior { 1.0 } else { 0.0 };
+                [
+                    s.smooth_iter,
+                    s.trap_min,
+                    interior,
+                    s.stripe.unwrap_or(s.interior_shade),
+                ]
+            })
+            .collect();
+        let samples_texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("fractal_samples"),
+            size: extent(tile),
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: SAMPLE_FORMAT,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        self.queue.write_texture(
+            samples_texture.as_image_copy(),
+            bytemuck::cast_slice(&texels),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(tile.tile_w * 16),
+                rows_per_image: Some(tile.tile_h),
+            },
+            extent(tile),
+        );
+        let samples_view = samples_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let target = self.target(tile, wgpu::TextureFormat::Rgba8Unorm);
+        let bind_group = self.bind_group(tile, params, &Camera::default(), Some(&samples_view));
+        self.draw(
+            &self.shade_pipeline,
+            &bind_group,
+            &[&target.create_view(&wgpu::TextureViewDescriptor::default())],
+        );
+        self.read_back(&target, tile, 4)
+    }
+
+    /// A tile-sized render target that can be copied out.
+    fn target(&self, tile: &TileInfo, format: wgpu::TextureFormat) -> wgpu::Texture {
+        self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("fractal_target"),
+            size: extent(tile),
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        })
+    }
+
+    /// Bakes and uploads the palette of `params` unless the texture already holds its
+    /// stops. The phase is a uniform, so cycling never re-uploads.
+    fn upload_palette(&mut self, params: &FractalParams) {
+        let key = palette_key(&params.palette);
+        if self.palette.as_ref().is_some_and(|p| p.key == key) {
+            return;
+        }
+        let palette = bake_palette(&params.palette, 0.0, PALETTE_SIZE);
+        let size = wgpu::Extent3d {
+            width: PALETTE_SIZE as u32,
+            height: 1,
+            depth_or_array_layers: 1,
+        };
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("palette"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let rgba: Vec<u8> = palette
+            .iter()
+            .flat_map(|&[r, g, b]| [r, g, b, 255])
+            .collect();
+        self.queue.write_texture(
+            texture.as_image_copy(),
+            &rgba,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(PALETTE_SIZE as u32 * 4),
+                rows_per_image: Some(1),
+            },
+            size,
+        );
+        self.palette = Some(PaletteTexture {
+            key,
+            view: texture.create_view(&wgpu::TextureViewDescriptor::default()),
+        });
+    }
+
+    /// Uniforms and the uploaded palette for one draw; `samples` selects the shading
+    /// layout.
+    fn bind_group(
+        &self,
+        tile: &TileInfo,
+        params: &FractalParams,
+        cam: &Camera,
+        samples: Option<&wgpu::TextureView>,
+    ) -> wgpu::BindGroup {
+        let palette_view = &self
+            .palette
+            .as_ref()
+            .expect("palette uploaded before drawing")
+            .view;
+        let uniforms = GpuUniform::new(tile, params, cam);
+        let uniform_buffer = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("fractal_uniform"),
+                contents: bytemuck::bytes_of(&uniforms),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+
+        let mut entries = vec![
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(palette_view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::Sampler(&self.sampler),
+            },
+        ];
+        let layout = match samples {
+            Some(view) => {
+                entries.push(wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(view),
+                });
+                &self.shade_bind_group_layout
+            }
+            None => &self.bind_group_layout,
+        };
+        self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("fractal_bind"),
+            layout,
+            entries: &entries,
+        })
+    }
+
+    /// Runs `pipeline` over the whole of `targets` once.
+    fn draw(
+        &self,
+        pipeline: &wgpu::RenderPipeline,
+        bind_group: &wgpu::BindGroup,
+        targets: &[&wgpu::TextureView],
+    ) {
+        let color_attachments: Vec<_> = targets
+            .iter()
+            .map(|view| {
+                Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })
+            })
+            .collect();
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("fractal_encoder"),
+            });
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("fractal_pass"),
+                color_attachments: &color_attachments,
+                depth_stencil_attachment: None,
+            });
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(0, bind_group, &[]);
+            pass.draw(0..3, 0..1);
+        }
+        self.queue.submit(Some(encoder.finish()));
+    }
+
+    /// Copies `texture` back as tightly packed rows of `bytes_per_pixel`.
+    fn read_back(
+        &self,
+        texture: &wgpu::Texture,
+        tile: &TileInfo,
+        bytes_per_pixel: u32,
+    ) -> Result<Vec<u8>, String> {
+        let bytes_per_row = align_to(
+            tile.tile_w * bytes_per_pixel,
+            wgpu::COPY_BYTES_PER_ROW_ALIGNMENT,
+        );
+        let buffer_size = bytes_per_row as u64 * tile.tile_h as u64;
+        let output_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("fractal_readback"),
+            size: buffer_size,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("copy_encoder"),
+            });
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &output_buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(bytes_per_row),
+                    rows_per_image: Some(tile.tile_h),
+                },
+            },
+            extent(tile),
+        );
+        self.queue.submit(Some(encoder.finish()));
+
+        let slice = output_buffer.slice(..);
+        let map_future = slice.map_async(wgpu::MapMode::Read);
+        self.device.poll(wgpu::Maintain::Wait);
+        pollster::block_on(map_future).map_err(|e| format!("Map error: {e}"))?;
+        let data = slice.get_mapped_range();
+        let row_bytes = (tile.tile_w * bytes_per_pixel) as usize;
+        let mut pixels = vec![0u8; row_bytes * tile.tile_h as usize];
+        let padded = bytes_per_row as usize;
+        for (row_idx, chunk) in pixels.chunks_mut(row_bytes).enumerate() {
+            let start = row_idx * padded;
+            chunk.copy_from_slice(&data[start..start + row_bytes]);
+        }
+        drop(data);
+        output_buffer.unmap();
+        Ok(pixels)
+    }
+}
+
+fn create_pipeline(
+    device: &wgpu::Device,
+    shader: &wgpu::ShaderModule,
+    bind_group_layout: &wgpu::BindGroupLayout,
+    entry_point: &str,
+    targets: &[Option<wgpu::ColorTargetState>],
+) -> wgpu::RenderPipeline {
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("fractal_layout"),
+        bind_group_layouts: &[bind_group_layout],
+        push_constant_ranges: &[],
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("fractal_pipeline"),
+        layout: Some(&pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: "vs_main",
+            buffers: &[],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point,
+            targets,
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+    })
+}
+
+fn extent(tile: &TileInfo) -> wgpu::Extent3d {
+    wgpu::Extent3d {
+        width: tile.tile_w,
+        height: tile.tile_h,
+        depth_or_array_layers: 1,
+    }
+}
+
+impl Renderer for GpuRenderer {
+    fn name(&self) -> &'static str {
+        "GPU"
+    }
+
+    fn render_tile(
+        &mut self,
+        tile: &TileInfo,
+        params: &FractalParams,
+        cam: &Camera,
+        _palette: &[[u8; 3]],
+    ) -> Result<Vec<u8>, RenderError> {
+        if !self.supports(params) {
+            return Err(RenderError::Unsupported(format!(
+                "{} has no GPU shader",
+                formula(params.kind).name()
+            )));
+        }
+        self.render(tile, params, cam).map_err(gpu_error)
+    }
+
+    fn render_tile_samples(
+        &mut self,
+        tile: &TileInfo,
+        params: &FractalParams,
+        cam: &Camera,
+        _palette: &[[u8; 3]],
+    ) -> Result<(Vec<u8>, Vec<PixelSample>), RenderError> {
+        if !self.supports(params) {
+            return Err(RenderError::Unsupported(format!(
+                "{} has no GPU shader",
+                formula(params.kind).name()
+            )));
+        }
+        self.render_samples(tile, params, cam).map_err(gpu_error)
+    }
+
+    fn shade_tile(
+        &mut self,
+        tile: &TileInfo,
+        samples: &[PixelSample],
+        params: &FractalParams,
+        _palette: &[[u8; 3]],
+    ) -> Result<Vec<u8>, RenderError> {
+        self.shade(tile, samples, params).map_err(gpu_error)
+    }
+
+    /// The shader approximates them per tile from screen-space derivatives.
+    fn draws_band_contours(&self, _params: &FractalParams) -> bool {
+        true
+    }
+
+    fn max_tile_size(&self) -> u32 {
+        self.max_texture_size
+    }
+
+    /// Distance estimates are CPU-only, as the shader tracks no derivative, and so are
+    /// equalized palettes, which it has no histogram for.
+    fn supports(&self, params: &FractalParams) -> bool {
+        formula(params.kind).gpu_supports(params)
+            && !params.estimates_distance()
+            && !params.equalizes()
+    }
+}
+
+/// Classifies a GPU error message. Read-back buffers only fail to map once the device is
+/// gone, so those are reported as a lost device.
+fn gpu_error(message: String) -> RenderError {
+    if message.starts_with("Map error") {
+        RenderError::DeviceLost(message)
+    } else {
+        RenderError::Failed(message)
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct GpuUniform {
+    full: [f32; 2],
+    offset: [f32; 2],
+    tile: [f32; 2],
+    center: [f32; 2],
+    julia_c: [f32; 2],
+    trap_point: [f32; 2],
+    orbit_color: [f32; 3],
+    orbit_enabled: f32,
+    scale: f32,
+    rotation: f32,
+    max_iter: u32,
+    fractal_kind: u32,
+    escape_radius: f32,
+    power: f32,
+    orbit_kind: u32,
+    orbit_radius: f32,
+    orbit_softness: f32,
+    exposure: f32,
+    gamma: f32,
+    palette_repeat: f32,
+    exterior_mode: u32,
+    palette_phase: f32,
+    sample_count: u32,
+    sample_central: u32,
+    exterior_color: [f32; 4],
+    contour_color: [f32; 4],
+    band_count: f32,
+    band_contours: f32,
+    degree: f32,
+    interior_coloring: u32,
+    sample_offsets: [[f32; 4]; SAMPLE_PAIRS],
+    interior_color: [f32; 4],
+    stripe_density: f32,
+    stripe_mix: f32,
+    phoenix_p: [f32; 2],
+}
+
+impl GpuUniform {
+    fn new(tile: &TileInfo, params: &FractalParams, cam: &Camera) -> Self {
+        let offsets = params.sample_offsets();
+        let mut sample_offsets = [[0.0; 4]; SAMPLE_PAIRS];
+        for (packed, pair) in sample_offsets.iter_mut().zip(offsets.chunks(2)) {
+            packed[..pair.len() * 2].copy_from_slice(pair.as_flattened());
+        }
+        Self {
+            full: [tile.full_w as f32, tile.full_h as f32],
+            offset: [tile.offset_x as f32, tile.offset_y as f32],
+            tile: [tile.tile_w as f32, tile.tile_h as f32],
+            center: [cam.center.re as f32, cam.center.im as f32],
+            julia_c: [params.c.re, params.c.im],
+            trap_point: [params.orbit.point.re, params.orbit.point.im],
+            orbit_color: params.orbit.color,
+            orbit_enabled: if params.orbit.enabled { 1.0 } else { 0.0 },
+            scale: cam.scale as f32,
+            rotation: cam.rotation,
+            max_iter: params.max_iter,
+            fractal_kind: formula_index(params.kind),
+            escape_radius: params.escape_radius,
+            power: params.power,
+            orbit_kind: match params.orbit.kind {
+                OrbitTrapKind::Point => 0,
+                OrbitTrapKind::Circle => 1,
+                OrbitTrapKind::Cross => 2,
+            },
+            orbit_radius: params.orbit.radius,
+            orbit_softness: params.orbit.softness,
+            exposure: params.exposure,
+            gamma: params.gamma,
+            palette_repeat: params.palette_repeat,
+            exterior_mode: match params.exterior_mode {
+                ExteriorMode::Palette => 0,
+                ExteriorMode::Solid => 1,
+                ExteriorMode::Transparent => 2,
+            },
+            palette_phase: params.palette_phase,
+            exterior_color: [
+                params.exterior_color[0],
+                params.exterior_color[1],
+                params.exterior_color[2],
+                1.0,
+            ],
+            contour_color: [
+                params.contour_color[0],
+                params.contour_color[1],
+                params.contour_color[2],
+                1.0,
+            ],
+            band_count: params.band_count as f32,
+            band_contours: if params.has_band_contours() { 1.0 } else { 0.0 },
+            degree: formula(params.kind).degree(params),
+            interior_coloring: match params.interior_coloring {
+                InteriorColoring::Palette => 0,
+                InteriorColoring::Solid => 1,
+                InteriorColoring::Magnitude => 2,
+                InteriorColoring::Argument => 3,
+                InteriorColoring::TrapDistance => 4,
+            },
+            sample_count: offsets.len() as u32,
+            sample_central: central_sample(&offsets) as u32,
+            sample_offsets,
+            interior_color: [
+                params.interior_color[0],
+                params.interior_color[1],
+                params.interior_color[2],
+                1.0,
+            ],
+            stripe_density: params.stripe_density as f32,
+            stripe_mix: if params.coloring == ColoringMode::StripeAverage {
+                params.stripe_mix
+            } else {
+                0.0
+            },
+            phoenix_p: [params.phoenix_p.re, params.phoenix_p.im],
+        }
+    }
+}
+
+fn align_to(value: u32, alignment: u32) -> u32 {
+    ((value + alignment - 1) / alignment) * alignment
+}
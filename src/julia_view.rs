@@ -0,0 +1,192 @@
+//! Julia viewport: a second view beside the main one showing the Julia set for a point
+//! marked on the main view, colored like it, and what exports render of the two.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    formula, render_image_stats, Camera, Complex, EvaluatedFrame, FractalKind, RenderError,
+    RenderStats, Renderer,
+};
+
+/// The Julia viewport of a project.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct JuliaView {
+    /// Shows the viewport beside the main one.
+    pub enabled: bool,
+    /// Julia constant, marked on the main view.
+    pub c: Complex,
+    /// Camera of the Julia viewport, moved independently of the main one.
+    pub camera: Camera,
+}
+
+impl Default for JuliaView {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            c: Complex::new(-0.8, 0.156),
+            camera: formula(FractalKind::Julia).default_camera(640.0),
+        }
+    }
+}
+
+impl JuliaView {
+    pub fn with_c(mut self, c: Complex) -> Self {
+        self.c = c;
+        self
+    }
+
+    pub fn with_camera(mut self, camera: Camera) -> Self {
+        self.camera = camera;
+        self
+    }
+
+    /// What the viewport shows next to `main`: the Julia set for `c` through its own
+    /// camera, with `main`'s palette, coloring, Max Iter and sampling.
+    pub fn frame(&self, main: &EvaluatedFrame) -> EvaluatedFrame {
+        let mut fractal = main.fractal.clone();
+        fractal.kind = FractalKind::Julia;
+        fractal.c = self.c;
+        EvaluatedFrame {
+            fractal,
+            camera: self.camera.clone(),
+        }
+    }
+}
+
+/// Which viewport exports render.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportViewport {
+    #[default]
+    Main,
+    /// The project's [`JuliaView`].
+    Julia,
+    /// The main view on the left half of the frame and the Julia view on the right.
+    SideBySide,
+}
+
+impl ExportViewport {
+    pub const ALL: [ExportViewport; 3] = [
+        ExportViewport::Main,
+        ExportViewport::Julia,
+        ExportViewport::SideBySide,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ExportViewport::Main => "Main view",
+            ExportViewport::Julia => "Julia view",
+            ExportViewport::SideBySide => "Side by side",
+        }
+    }
+}
+
+/// Widths of the left and right halves of a side-by-side frame `width` pixels wide; the
+/// left one gets the odd pixel.
+pub fn side_by_side_widths(width: u32) -> (u32, u32) {
+    (width - width / 2, width / 2)
+}
+
+/// A camera for a whole `size` frame whose left half frames the plane as `main` does in
+/// the left half of a side-by-side frame, for placing overlays that follow the main view.
+pub fn side_by_side_camera(main: &Camera, size: (u32, u32)) -> Camera {
+    let (left, _) = side_by_side_widths(size.0);
+    let (w, h) = (size.0 as f32, size.1 as f32);
+    let mut camera = main.clone();
+    camera.translate(main.pixel_offset(w / 2.0, h / 2.0, left as f32, h));
+    camera
+}
+
+/// Renders `left` and `right` into the two halves of a `size` frame, each framed as a view
+/// of its own width. Returns RGBA8 pixels and the stats of both halves together.
+pub fn render_side_by_side(
+    size: (u32, u32),
+    left: &EvaluatedFrame,
+    right: &EvaluatedFrame,
+    renderer: &mut dyn Renderer,
+    tile_size: u32,
+) -> Result<(Vec<u8>, RenderStats), RenderError> {
+    let (left_w, right_w) = side_by_side_widths(size.0);
+    let (left_px, mut stats) = render_image_stats(
+        (left_w, size.1),
+        &left.fractal,
+        &left.camera,
+        renderer,
+        tile_size,
+    )?;
+    let (right_px, right_stats) = if right_w > 0 {
+        render_image_stats(
+            (right_w, size.1),
+            &right.fractal,
+            &right.camera,
+            renderer,
+            tile_size,
+        )?
+    } else {
+        (
+            Vec::new(),
+            RenderStats::new(renderer, (0, size.1), &right.fractal),
+        )
+    };
+    let (left_row, right_row) = (left_w as usize * 4, right_w as usize * 4);
+    let mut pixels = Vec::with_capacity((left_row + right_row) * size.1 as usize);
+    for y in 0..size.1 as usize {
+        pixels.extend_from_slice(&left_px[y * left_row..(y + 1) * left_row]);
+        pixels.extend_from_slice(&right_px[y * right_row..(y + 1) * right_row]);
+    }
+    stats.width = size.0;
+    stats.max_iter = stats.max_iter.max(right_stats.max_iter);
+    stats.tile_ms.extend(right_stats.tile_ms);
+    stats.total_ms += right_stats.total_ms;
+    Ok((pixels, stats))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{render_image, CpuRenderer, FractalParams};
+
+    #[test]
+    fn julia_frame_keeps_the_coloring_and_swaps_formula_and_camera() {
+        let main = EvaluatedFrame {
+            fractal: FractalParams::new(FractalKind::Mandelbrot).with_max_iter(123),
+            camera: Camera::new(Complex::new(-0.75, 0.1), 900.0),
+        };
+        let view = JuliaView::default().with_c(Complex::new(-0.75, 0.1));
+        let julia = view.frame(&main);
+        assert_eq!(julia.fractal.kind, FractalKind::Julia);
+        assert_eq!(julia.fractal.c, view.c);
+        assert_eq!(julia.fractal.max_iter, 123);
+        assert_eq!(julia.fractal.palette, main.fractal.palette);
+        assert_eq!(julia.camera.center, view.camera.center);
+        assert_eq!(julia.camera.scale, view.camera.scale);
+    }
+
+    #[test]
+    fn side_by_side_halves_match_their_own_renders() {
+        let main = EvaluatedFrame {
+            fractal: FractalParams::new(FractalKind::Mandelbrot).with_max_iter(40),
+            camera: Camera::new(Complex::new(-0.5, 0.0), 30.0),
+        };
+        let julia = JuliaView::default()
+            .with_camera(Camera::new(Complex::new(0.0, 0.0), 25.0))
+            .frame(&main);
+        let size = (81, 40);
+        let (pixels, stats) =
+            render_side_by_side(size, &main, &julia, &mut CpuRenderer, 0).unwrap();
+        assert_eq!(pixels.len(), 81 * 40 * 4);
+        assert_eq!((stats.width, stats.height), size);
+
+        let left =
+            render_image((41, 40), &main.fractal, &main.camera, &mut CpuRenderer, 0).unwrap();
+        let right =
+            render_image((40, 40), &julia.fractal, &julia.camera, &mut CpuRenderer, 0).unwrap();
+        for y in 0..40 {
+            let row = &pixels[y * 81 * 4..(y + 1) * 81 * 4];
+            assert_eq!(&row[..41 * 4], &left[y * 41 * 4..(y + 1) * 41 * 4]);
+            assert_eq!(&row[41 * 4..], &right[y * 40 * 4..(y + 1) * 40 * 4]);
+        }
+    }
+}
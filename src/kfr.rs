@@ -0,0 +1,217 @@
+//! Import of Kalles Fraktaler `.kfr` location files.
+
+use std::{fs, path::Path};
+
+use crate::{perturbation_supported, FractalKind, PaletteStop, Precision, Project, TrackKind};
+
+/// Fields read from a `.kfr` file. Coordinates keep their full decimal strings.
+#[derive(Debug, Clone, Default)]
+pub struct KfrLocation {
+    pub re: String,
+    pub im: String,
+    /// KF zoom: 1 shows a view 4 units tall.
+    pub zoom: f64,
+    pub iterations: Option<u32>,
+    /// Degrees.
+    pub rotate: Option<f32>,
+    pub power: Option<u32>,
+    pub fractal_type: Option<i32>,
+    /// KF color table, RGB.
+    pub colors: Vec<[u8; 3]>,
+    /// Fields that were present but are not imported.
+    pub warnings: Vec<String>,
+}
+
+/// Keys that are understood but have no equivalent here; reported once each.
+const IGNORED_KEYS: [&str; 12] = [
+    "IterDiv",
+    "ColorOffset",
+    "Smooth",
+    "Ratio",
+    "Slopes",
+    "SlopePower",
+    "SlopeRatio",
+    "SlopeAngle",
+    "ColorMethod",
+    "Differences",
+    "Multiplier",
+    "Real",
+];
+
+/// Parses the `Key: value` lines of a `.kfr` file. `Re`, `Im` and `Zoom` are required.
+pub fn parse_kfr(text: &str) -> Result<KfrLocation, String> {
+    let mut loc = KfrLocation::default();
+    let mut zoom = None;
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            loc.warnings
+                .push(format!("line {}: ignored, not a `Key: value` pair", n + 1));
+            continue;
+        };
+        let (key, value) = (key.trim(), value.trim());
+        let bad = |what: &str| format!("line {}: {key} is not {what}: {value:?}", n + 1);
+        match key {
+            "Re" => loc.re = parse_decimal(value).ok_or_else(|| bad("a decimal number"))?,
+            "Im" => loc.im = parse_decimal(value).ok_or_else(|| bad("a decimal number"))?,
+            "Zoom" => zoom = Some(parse_zoom(value).ok_or_else(|| bad("a positive number"))?),
+            "Iterations" => {
+                loc.iterations = Some(value.parse().map_err(|_| bad("an iteration count"))?)
+            }
+            "Rotate" => loc.rotate = Some(value.parse().map_err(|_| bad("an angle"))?),
+            "Power" => loc.power = Some(value.parse().map_err(|_| bad("an integer power"))?),
+            "FractalType" => {
+                loc.fractal_type = Some(value.parse().map_err(|_| bad("a fractal type"))?)
+            }
+            "Colors" => match parse_colors(value) {
+                Some(colors) => loc.colors = colors,
+                None => loc
+                    .warnings
+                    .push(format!("line {}: color table ignored, unreadable", n + 1)),
+            },
+            _ if IGNORED_KEYS.contains(&key) => loc
+                .warnings
+                .push(format!("{key} is not supported, ignored")),
+            _ => loc.warnings.push(format!("unknown field {key}, ignored")),
+        }
+    }
+    if loc.re.is_empty() || loc.im.is_empty() {
+        return Err("missing Re or Im".into());
+    }
+    loc.zoom = zoom.ok_or("missing Zoom")?;
+    Ok(loc)
+}
+
+/// Reads and parses a `.kfr` file.
+pub fn load_kfr(path: &Path) -> Result<KfrLocation, String> {
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    parse_kfr(&text)
+}
+
+/// Validates a decimal string like `-0.7436438870371587522` or `1.2E-3`, keeping every digit.
+fn parse_decimal(value: &str) -> Option<String> {
+    value.parse::<f64>().ok().filter(|v| v.is_finite())?;
+    Some(value.to_string())
+}
+
+/// KF zooms can exceed `f64`; those saturate to `f64::MAX`.
+fn parse_zoom(value: &str) -> Option<f64> {
+    let zoom = value.parse::<f64>().ok()?;
+    (zoom > 0.0).then(|| zoom.min(f64::MAX))
+}
+
+fn parse_colors(value: &str) -> Option<Vec<[u8; 3]>> {
+    let channels: Vec<u8> = value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse().ok())
+        .collect::<Option<_>>()?;
+    if channels.is_empty() || !channels.len().is_multiple_of(3) {
+        return None;
+    }
+    Some(channels.chunks(3).map(|c| [c[0], c[1], c[2]]).collect())
+}
+
+impl KfrLocation {
+    /// Pixels per unit for a view `view_height` pixels tall.
+    pub fn scale(&self, view_height: f32) -> f64 {
+        f64::from(view_height) * self.zoom / 4.0
+    }
+
+    /// The KF color table as evenly spaced stops, wrapping back to the first color.
+    pub fn palette(&self) -> Vec<PaletteStop> {
+        let n = self.colors.len();
+        let stop = |i: usize, rgb: [u8; 3]| {
+            PaletteStop::new(
+                i as f32 / n as f32,
+                [
+                    rgb[0] as f32 / 255.0,
+                    rgb[1] as f32 / 255.0,
+                    rgb[2] as f32 / 255.0,
+                ],
+            )
+        };
+        let mut stops: Vec<PaletteStop> = self
+            .colors
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| stop(i, c))
+            .collect();
+        if let Some(&first) = self.colors.first() {
+            stops.push(stop(n, first));
+        }
+        stops
+    }
+
+    /// Moves `proj` to this location, framed for its export height. Returns warnings for
+    /// anything that could not be carried over, including the parse warnings.
+    pub fn apply(&self, proj: &mut Project, import_palette: bool) -> Vec<String> {
+        let mut warnings = self.warnings.clone();
+        proj.camera.scale = self.scale(proj.export.height as f32);
+        // Both were validated as finite decimals while parsing.
+        if let Err(e) = proj.camera.set_decimal_center(&self.re, &self.im) {
+            warnings.push(e);
+        }
+        proj.camera.rotation = self.rotate.map_or(0.0, f32::to_radians);
+        let anim = &proj.anim;
+        if anim.zoom_forever.is_some()
+            || [
+                TrackKind::Zoom,
+                TrackKind::CenterX,
+                TrackKind::CenterY,
+                TrackKind::Rotation,
+            ]
+            .iter()
+            .any(|&kind| !anim.track(kind).keys.is_empty())
+        {
+            warnings.push(
+                "existing zoom, center or rotation keyframes still override the imported camera"
+                    .into(),
+            );
+        }
+
+        match (self.fractal_type.unwrap_or(0), self.power.unwrap_or(2)) {
+            (0, 2) => proj.fractal.kind = FractalKind::Mandelbrot,
+            (0, power) => {
+                proj.fractal.kind = FractalKind::Multibrot;
+                proj.fractal.power = power as f32;
+            }
+            (other, _) => warnings.push(format!(
+                "KF fractal type {other} has no equivalent; kept {}",
+                proj.fractal.kind.label()
+            )),
+        }
+        if let Some(iterations) = self.iterations {
+            proj.fractal.max_iter = iterations.max(1);
+        }
+        let needed = Precision::ALL
+            .into_iter()
+            .find(|precision| self.zoom <= precision.zoom_limit())
+            .unwrap_or(Precision::Perturbation);
+        if needed > proj.precision {
+            proj.precision = needed;
+            warnings.push(format!(
+                "zoom {:.3e} needs {} precision, which is now on; the GPU renders in f32 and will break up",
+                self.zoom,
+                needed.label()
+            ));
+        }
+        let limit = Precision::F64.zoom_limit();
+        if self.zoom > limit && !perturbation_supported(proj.fractal.kind) {
+            warnings.push(format!(
+                "zoom {:.3e} is deeper than f64 resolves (about {limit:.0e}) and {} has no perturbation iteration; the image will break up",
+                self.zoom,
+                proj.fractal.kind.label()
+            ));
+        }
+        if import_palette && !self.colors.is_empty() {
+            proj.fractal.palette = self.palette();
+            proj.fractal.palette_phase = 0.0;
+        }
+        warnings
+    }
+}
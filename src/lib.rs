@@ -0,0 +1,115 @@
+//! Matterhorn core: fractal parameters, keyframed animation, CPU/GPU rendering,
+//! palettes, tiling, and video export, without any UI dependencies.
+//!
+//! Rendering a frame into an RGBA buffer:
+//!
+//! ```
+//! use matterhorn_core::{render_cpu, Camera, Complex, FractalKind, FractalParams};
+//!
+//! let params = FractalParams::new(FractalKind::Mandelbrot).with_max_iter(200);
+//! let camera = Camera::new(Complex::new(-0.5, 0.0), 100.0);
+//! let rgba = render_cpu((320, 240), &params, &camera);
+//! assert_eq!(rgba.len(), 320 * 240 * 4);
+//! assert!(rgba.chunks(4).all(|px| px[3] == 255));
+//! ```
+//!
+//! Animated projects are sampled with [`evaluate_frame`] and exported with
+//! [`export_video_blocking`].
+
+pub mod adaptive_aa;
+pub mod animation;
+pub mod annotation;
+pub mod aov;
+pub mod audio;
+pub mod bake;
+pub mod bigfloat;
+pub mod contact_sheet;
+pub mod custom_formula;
+pub mod deep_zoom;
+pub mod demo;
+pub mod diagnostics;
+pub mod equalize;
+pub mod export;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod finite;
+pub mod formula;
+pub mod frame_budget;
+pub mod frame_diff;
+#[cfg(feature = "gpu")]
+pub mod gpu_renderer;
+pub mod julia_view;
+pub mod kfr;
+pub mod live_input;
+pub mod manifest;
+pub mod orbit_cache;
+pub mod palette;
+pub mod perturbation;
+pub mod poster;
+pub mod project;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod quality;
+pub mod ranges;
+pub mod readout;
+pub mod reload;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod remote;
+pub mod render;
+pub mod report;
+pub mod sampling;
+pub mod script;
+pub mod seed;
+pub mod share;
+pub mod snippet;
+pub mod stats;
+pub mod stylize;
+pub mod thumbnail;
+pub mod tile_tuning;
+pub mod uf;
+
+pub use adaptive_aa::*;
+pub use animation::*;
+pub use annotation::*;
+pub use aov::*;
+pub use audio::*;
+pub use bake::*;
+pub use bigfloat::*;
+pub use contact_sheet::*;
+pub use custom_formula::*;
+pub use deep_zoom::*;
+pub use demo::*;
+pub use diagnostics::*;
+pub use equalize::*;
+pub use export::*;
+pub use finite::*;
+pub use formula::*;
+pub use frame_budget::*;
+pub use frame_diff::*;
+pub use julia_view::*;
+pub use kfr::*;
+pub use live_input::*;
+pub use manifest::*;
+pub use orbit_cache::*;
+pub use palette::*;
+pub use perturbation::*;
+pub use poster::*;
+pub use project::*;
+pub use quality::*;
+pub use ranges::*;
+pub use readout::*;
+pub use reload::*;
+#[cfg(not(target_arch = "wasm32"))]
+pub use remote::*;
+pub use render::*;
+pub use report::*;
+pub use sampling::*;
+pub use script::*;
+pub use seed::*;
+pub use share::*;
+pub use snippet::*;
+pub use stats::*;
+pub use stylize::*;
+pub use thumbnail::*;
+pub use tile_tuning::*;
+pub use uf::*;
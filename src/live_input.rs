@@ -0,0 +1,425 @@
+//! Hardware controls for live performance: MIDI control changes and OSC messages mapped
+//! onto project parameters.
+//!
+//! Incoming values are normalized to 0..1 ([`LiveEvent`]) and pushed through an
+//! [`InputMapping`]'s range and curve before they are written to the authored parameter.
+//! Listening on ports is left to the front end; this module only decodes and applies.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Project, RangedParam};
+
+/// A parameter that live input can drive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LiveTarget {
+    PalettePhase,
+    /// Speed of the endless zoom; ignored while endless zoom is off.
+    ZoomSpeed,
+    JuliaRe,
+    JuliaIm,
+    Exposure,
+}
+
+impl LiveTarget {
+    pub const ALL: [LiveTarget; 5] = [
+        LiveTarget::PalettePhase,
+        LiveTarget::ZoomSpeed,
+        LiveTarget::JuliaRe,
+        LiveTarget::JuliaIm,
+        LiveTarget::Exposure,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            LiveTarget::PalettePhase => "Palette phase",
+            LiveTarget::ZoomSpeed => "Zoom speed",
+            LiveTarget::JuliaRe => "Julia c (re)",
+            LiveTarget::JuliaIm => "Julia c (im)",
+            LiveTarget::Exposure => "Exposure",
+        }
+    }
+
+    /// Range a new mapping starts with, matching the UI controls for the parameter.
+    pub fn default_range(self) -> (f32, f32) {
+        let slider = |param: RangedParam| {
+            let (lo, hi) = param.range().soft;
+            (lo as f32, hi as f32)
+        };
+        match self {
+            LiveTarget::PalettePhase => slider(RangedParam::PalettePhase),
+            LiveTarget::ZoomSpeed => (0.5, 0.995),
+            LiveTarget::JuliaRe | LiveTarget::JuliaIm => (-2.0, 2.0),
+            LiveTarget::Exposure => slider(RangedParam::Exposure),
+        }
+    }
+
+    /// Writes `value` to the parameter. Returns false if the parameter is not in use.
+    pub fn write(self, proj: &mut Project, value: f32) -> bool {
+        let slot = match self {
+            LiveTarget::PalettePhase => &mut proj.fractal.palette_phase,
+            LiveTarget::ZoomSpeed => match &mut proj.anim.zoom_forever {
+                Some(zoom) => &mut zoom.speed,
+                None => return false,
+            },
+            LiveTarget::JuliaRe => &mut proj.fractal.c.re,
+            LiveTarget::JuliaIm => &mut proj.fractal.c.im,
+            LiveTarget::Exposure => &mut proj.fractal.exposure,
+        };
+        *slot = value;
+        true
+    }
+}
+
+/// Response from control position to parameter value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum InputCurve {
+    #[default]
+    Linear,
+    /// Finer control near the start of the range.
+    Squared,
+    /// Equal ratios per step; falls back to linear unless both ends share a sign.
+    Exponential,
+}
+
+impl InputCurve {
+    pub const ALL: [InputCurve; 3] = [
+        InputCurve::Linear,
+        InputCurve::Squared,
+        InputCurve::Exponential,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            InputCurve::Linear => "Linear",
+            InputCurve::Squared => "Squared",
+            InputCurve::Exponential => "Exponential",
+        }
+    }
+}
+
+/// A physical control: a MIDI control change or an OSC address.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InputSource {
+    /// Controller number `cc` on MIDI channel `channel` (1-16).
+    Midi { channel: u8, cc: u8 },
+    /// The first numeric argument of messages sent to `address`.
+    Osc { address: String },
+}
+
+impl fmt::Display for InputSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputSource::Midi { channel, cc } => write!(f, "MIDI ch {channel} CC {cc}"),
+            InputSource::Osc { address } => write!(f, "OSC {address}"),
+        }
+    }
+}
+
+/// One row of the mapping table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InputMapping {
+    /// `None` until a control is bound, e.g. by learn mode.
+    pub source: Option<InputSource>,
+    pub target: LiveTarget,
+    /// Parameter value at control position 0.
+    pub min: f32,
+    /// Parameter value at control position 1.
+    pub max: f32,
+    #[serde(default)]
+    pub curve: InputCurve,
+}
+
+impl InputMapping {
+    /// An unbound mapping over the target's default range.
+    pub fn new(target: LiveTarget) -> Self {
+        let (min, max) = target.default_range();
+        Self {
+            source: None,
+            target,
+            min,
+            max,
+            curve: InputCurve::Linear,
+        }
+    }
+
+    /// Parameter value for control position `x` in 0..1.
+    pub fn value(&self, x: f32) -> f32 {
+        let x = x.clamp(0.0, 1.0);
+        match self.curve {
+            InputCurve::Linear => self.min + (self.max - self.min) * x,
+            InputCurve::Squared => self.min + (self.max - self.min) * x * x,
+            InputCurve::Exponential if self.min * self.max > 0.0 => {
+                self.min * (self.max / self.min).powf(x)
+            }
+            InputCurve::Exponential => self.min + (self.max - self.min) * x,
+        }
+    }
+}
+
+/// A control moved to `value`, normalized to 0..1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiveEvent {
+    pub source: InputSource,
+    pub value: f32,
+}
+
+/// Applies `event` through every mapping bound to its source. Returns how many
+/// parameters changed.
+pub fn apply_live_event(proj: &mut Project, mappings: &[InputMapping], event: &LiveEvent) -> usize {
+    mappings
+        .iter()
+        .filter(|m| m.source.as_ref() == Some(&event.source))
+        .filter(|m| m.target.write(proj, m.value(event.value)))
+        .count()
+}
+
+/// Decodes a MIDI control change; every other message yields `None`.
+pub fn decode_midi_cc(message: &[u8]) -> Option<LiveEvent> {
+    match *message {
+        [status, cc, value, ..] if status & 0xF0 == 0xB0 => Some(LiveEvent {
+            source: InputSource::Midi {
+                channel: (status & 0x0F) + 1,
+                cc: cc & 0x7F,
+            },
+            value: (value & 0x7F) as f32 / 127.0,
+        }),
+        _ => None,
+    }
+}
+
+/// Decodes an OSC packet (a message or a bundle of them) into events.
+///
+/// Floats are expected in 0..1, integers in MIDI's 0..127, and `T`/`F` map to 1 and 0.
+/// Messages without a numeric argument are skipped; malformed packets are errors.
+pub fn decode_osc(packet: &[u8]) -> Result<Vec<LiveEvent>, String> {
+    let mut events = Vec::new();
+    decode_osc_into(packet, &mut events)?;
+    Ok(events)
+}
+
+fn decode_osc_into(packet: &[u8], events: &mut Vec<LiveEvent>) -> Result<(), String> {
+    if let Some(mut rest) = packet.strip_prefix(b"#bundle\0") {
+        // Time tag: values apply on arrival.
+        rest = rest.get(8..).ok_or("OSC bundle is truncated")?;
+        while !rest.is_empty() {
+            let len = u32::from_be_bytes(take4(&mut rest)?) as usize;
+            let element = rest.get(..len).ok_or("OSC bundle element is truncated")?;
+            decode_osc_into(element, events)?;
+            rest = &rest[len..];
+        }
+        return Ok(());
+    }
+    let mut rest = packet;
+    let address = osc_string(&mut rest)?;
+    if !address.starts_with('/') {
+        return Err(format!("OSC address {address:?} does not start with /"));
+    }
+    // Type tags are optional in old senders; without them there is nothing to read.
+    let Ok(tags) = osc_string(&mut rest) else {
+        return Ok(());
+    };
+    let Some(tags) = tags.strip_prefix(',') else {
+        return Ok(());
+    };
+    for tag in tags.chars() {
+        let value = match tag {
+            'f' => Some(f32::from_be_bytes(take4(&mut rest)?)),
+            'i' => Some(i32::from_be_bytes(take4(&mut rest)?) as f32 / 127.0),
+            'd' => Some(f64::from_be_bytes(take8(&mut rest)?) as f32),
+            'h' => Some(i64::from_be_bytes(take8(&mut rest)?) as f32 / 127.0),
+            'T' => Some(1.0),
+            'F' => Some(0.0),
+            's' | 'S' => {
+                osc_string(&mut rest)?;
+                None
+            }
+            'N' | 'I' => None,
+            // Blobs and rarer types: stop rather than misread the remaining arguments.
+            _ => break,
+        };
+        if let Some(value) = value.filter(|v| v.is_finite()) {
+            events.push(LiveEvent {
+                source: InputSource::Osc {
+                    address: address.to_string(),
+                },
+                value: value.clamp(0.0, 1.0),
+            });
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Reads a NUL-terminated string padded to a multiple of four bytes.
+fn osc_string<'a>(rest: &mut &'a [u8]) -> Result<&'a str, String> {
+    let end = rest
+        .iter()
+        .position(|&b| b == 0)
+        .ok_or("OSC string is not terminated")?;
+    let s = std::str::from_utf8(&rest[..end]).map_err(|_| "OSC string is not UTF-8")?;
+    let padded = (end + 4) & !3;
+    *rest = rest
+        .get(padded..)
+        .ok_or("OSC string padding is truncated")?;
+    Ok(s)
+}
+
+fn take4(rest: &mut &[u8]) -> Result<[u8; 4], String> {
+    let (head, tail) = rest
+        .split_first_chunk::<4>()
+        .ok_or("OSC argument is truncated")?;
+    *rest = tail;
+    Ok(*head)
+}
+
+fn take8(rest: &mut &[u8]) -> Result<[u8; 8], String> {
+    let (head, tail) = rest
+        .split_first_chunk::<8>()
+        .ok_or("OSC argument is truncated")?;
+    *rest = tail;
+    Ok(*head)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EndlessZoom;
+
+    fn padded(s: &str) -> Vec<u8> {
+        let mut out = s.as_bytes().to_vec();
+        out.push(0);
+        while !out.len().is_multiple_of(4) {
+            out.push(0);
+        }
+        out
+    }
+
+    fn message(address: &str, tags: &str, args: &[u8]) -> Vec<u8> {
+        let mut out = padded(address);
+        out.extend(padded(tags));
+        out.extend_from_slice(args);
+        out
+    }
+
+    fn osc(address: &str) -> InputSource {
+        InputSource::Osc {
+            address: address.into(),
+        }
+    }
+
+    #[test]
+    fn decodes_midi_control_changes_only() {
+        let event = decode_midi_cc(&[0xB3, 74, 127]).unwrap();
+        assert_eq!(event.source, InputSource::Midi { channel: 4, cc: 74 });
+        assert_eq!(event.value, 1.0);
+        assert_eq!(decode_midi_cc(&[0xB0, 1, 0]).unwrap().value, 0.0);
+        // Note on, and a truncated control change.
+        assert!(decode_midi_cc(&[0x90, 60, 100]).is_none());
+        assert!(decode_midi_cc(&[0xB0, 1]).is_none());
+    }
+
+    #[test]
+    fn decodes_osc_messages_and_bundles() {
+        let fader = message("/1/fader1", ",f", &0.25f32.to_be_bytes());
+        assert_eq!(
+            decode_osc(&fader).unwrap(),
+            vec![LiveEvent {
+                source: osc("/1/fader1"),
+                value: 0.25
+            }]
+        );
+
+        // A label before the value, and an integer in MIDI range.
+        let mut args = padded("knob");
+        args.extend(127i32.to_be_bytes());
+        let labelled = message("/knob", ",si", &args);
+        assert_eq!(decode_osc(&labelled).unwrap()[0].value, 1.0);
+
+        let mut bundle = padded("#bundle");
+        bundle.extend(1u64.to_be_bytes());
+        for element in [&fader, &labelled] {
+            bundle.extend((element.len() as u32).to_be_bytes());
+            bundle.extend_from_slice(element);
+        }
+        let events = decode_osc(&bundle).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].source, osc("/knob"));
+
+        assert!(decode_osc(&message("/ping", ",", &[])).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_malformed_osc() {
+        assert!(decode_osc(b"").is_err());
+        assert!(decode_osc(&padded("no-slash")).is_err());
+        assert!(decode_osc(&message("/x", ",f", &[0, 0])).is_err());
+        let mut bundle = padded("#bundle");
+        bundle.extend(1u64.to_be_bytes());
+        bundle.extend(64u32.to_be_bytes());
+        assert!(decode_osc(&bundle).is_err());
+    }
+
+    #[test]
+    fn curves_span_the_range() {
+        let mut m = InputMapping::new(LiveTarget::Exposure);
+        m.min = 0.5;
+        m.max = 8.0;
+        for curve in InputCurve::ALL {
+            m.curve = curve;
+            assert!((m.value(0.0) - 0.5).abs() < 1e-6, "{curve:?}");
+            assert!((m.value(1.0) - 8.0).abs() < 1e-5, "{curve:?}");
+            assert_eq!(m.value(2.0), m.value(1.0));
+        }
+        m.curve = InputCurve::Squared;
+        assert_eq!(m.value(0.5), 0.5 + 7.5 * 0.25);
+        m.curve = InputCurve::Exponential;
+        assert!((m.value(0.5) - 2.0).abs() < 1e-5);
+        // Ranges through zero have no geometric middle.
+        m.min = -1.0;
+        assert_eq!(m.value(0.5), 3.5);
+    }
+
+    #[test]
+    fn events_drive_bound_parameters() {
+        let mut proj = Project::default();
+        let mut phase = InputMapping::new(LiveTarget::PalettePhase);
+        phase.source = Some(osc("/phase"));
+        let mut zoom = InputMapping::new(LiveTarget::ZoomSpeed);
+        zoom.source = Some(osc("/phase"));
+        let unbound = InputMapping::new(LiveTarget::Exposure);
+        let mappings = [phase, zoom, unbound];
+        let event = LiveEvent {
+            source: osc("/phase"),
+            value: 0.5,
+        };
+
+        // Zoom speed only exists while endless zoom is on.
+        assert_eq!(apply_live_event(&mut proj, &mappings, &event), 1);
+        assert_eq!(proj.fractal.palette_phase, 0.5);
+        assert_eq!(proj.fractal.exposure, 1.0);
+
+        proj.anim.zoom_forever = Some(EndlessZoom::with_defaults(1.0));
+        assert_eq!(apply_live_event(&mut proj, &mappings, &event), 2);
+        assert!((proj.anim.zoom_forever.unwrap().speed - 0.7475).abs() < 1e-6);
+    }
+
+    #[test]
+    fn mappings_persist_as_toml() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Table {
+            mappings: Vec<InputMapping>,
+        }
+        let mut midi = InputMapping::new(LiveTarget::JuliaRe);
+        midi.source = Some(InputSource::Midi { channel: 1, cc: 21 });
+        midi.curve = InputCurve::Squared;
+        let mut osc_map = InputMapping::new(LiveTarget::PalettePhase);
+        osc_map.source = Some(osc("/1/rotary1"));
+        let table = Table {
+            mappings: vec![midi, osc_map, InputMapping::new(LiveTarget::Exposure)],
+        };
+        let text = toml::to_string_pretty(&table).unwrap();
+        assert_eq!(toml::from_str::<Table>(&text).unwrap(), table);
+    }
+}
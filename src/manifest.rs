@@ -0,0 +1,352 @@
+//! Frame manifests: BLAKE3 hashes of the parameters and pixels of every frame in an
+//! export's work directory, so a resumed export re-renders only stale frames and a frame
+//! folder can be checked for corruption later.
+
+use std::{
+    collections::BTreeMap,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{AdaptiveAa, Camera, FractalParams, Precision, SampleJitter, Stylize, TextStamp};
+
+/// File name of the manifest inside a work directory.
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Bumped whenever the hashed snapshot or the manifest layout changes, so frames hashed
+/// the old way are re-rendered rather than trusted.
+pub const MANIFEST_VERSION: u32 = 3;
+
+/// File name of exported frame `frame`.
+pub fn frame_file_name(frame: u32) -> String {
+    format!("frame_{frame:06}.png")
+}
+
+/// Everything a frame's pixels depend on: the evaluated parameters after scripts, auto
+/// iterations and sampling, the size, and what is burned in.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct FrameSnapshot<'a> {
+    pub size: (u32, u32),
+    pub fractal: &'a FractalParams,
+    /// Not part of the saved parameters, so listed on its own, as is `precision`.
+    pub sampling: SampleJitter,
+    pub precision: Precision,
+    pub camera: &'a Camera,
+    pub zoom_readout: bool,
+    /// Left out while empty, so frames without annotations keep their hashes.
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    pub annotations: &'a [TextStamp],
+    /// The Julia view of a side-by-side frame; left out otherwise.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub side_by_side: Option<(&'a FractalParams, &'a Camera)>,
+    /// The stylized look, when it is on; left out otherwise.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stylize: Option<&'a Stylize>,
+    /// Adaptive anti-aliasing, when it refines the frame; left out otherwise.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub adaptive_aa: Option<&'a AdaptiveAa>,
+    /// The levels of the equalized palette, which may come from earlier frames too; left
+    /// out without one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub equalizer: Option<&'a [f32]>,
+}
+
+impl FrameSnapshot<'_> {
+    /// Hex BLAKE3 hash of the snapshot's canonical JSON.
+    pub fn hash(&self) -> String {
+        let json = canonical_json(self).expect("frame parameters always serialize");
+        blake3::hash(&json).to_hex().to_string()
+    }
+}
+
+/// `value` as compact JSON with every object's keys sorted, so equal values always give
+/// the same bytes whatever order their maps were built in.
+pub fn canonical_json<T: Serialize + ?Sized>(value: &T) -> serde_json::Result<Vec<u8>> {
+    let mut value = serde_json::to_value(value)?;
+    value.sort_all_objects();
+    serde_json::to_vec(&value)
+}
+
+/// Hashes of one rendered frame.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FrameHashes {
+    /// [`FrameSnapshot::hash`] of the parameters it was rendered with.
+    pub params: String,
+    /// Hex BLAKE3 hash of its RGBA8 pixels, row by row.
+    pub pixels: String,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ManifestError {
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    #[error("{0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Frame index → hashes for the frames in a work directory.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FrameManifest {
+    pub version: u32,
+    pub frames: BTreeMap<u32, FrameHashes>,
+}
+
+impl Default for FrameManifest {
+    fn default() -> Self {
+        Self {
+            version: MANIFEST_VERSION,
+            frames: BTreeMap::new(),
+        }
+    }
+}
+
+impl FrameManifest {
+    pub fn path(dir: &Path) -> PathBuf {
+        dir.join(MANIFEST_FILE)
+    }
+
+    /// The manifest in `dir`, or `None` when there is none.
+    pub fn load(dir: &Path) -> Result<Option<Self>, ManifestError> {
+        match fs::read(Self::path(dir)) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// The manifest in `dir` for resuming an export: empty when there is none, or when it
+    /// was written by another manifest version and so cannot be trusted.
+    pub fn load_for_resume(dir: &Path) -> Result<Self, ManifestError> {
+        Ok(Self::load(dir)?
+            .filter(|m| m.version == MANIFEST_VERSION)
+            .unwrap_or_default())
+    }
+
+    /// Writes the manifest to `dir`, replacing the old one only once the new one is
+    /// complete.
+    pub fn save(&self, dir: &Path) -> Result<(), ManifestError> {
+        let tmp = dir.join(format!("{MANIFEST_FILE}.tmp"));
+        fs::write(&tmp, serde_json::to_vec_pretty(self)?)?;
+        fs::rename(tmp, Self::path(dir))?;
+        Ok(())
+    }
+
+    /// Whether frame `frame` in `dir` was rendered with parameters hashing to `params`
+    /// and is still there.
+    pub fn is_current(&self, dir: &Path, frame: u32, params: &str) -> bool {
+        self.frames.get(&frame).is_some_and(|h| h.params == params)
+            && dir.join(frame_file_name(frame)).is_file()
+    }
+
+    /// Re-hashes the pixels of every frame listed, comparing them with the manifest.
+    pub fn verify(&self, dir: &Path) -> VerifyReport {
+        let mut report = VerifyReport::default();
+        for (&frame, hashes) in &self.frames {
+            match png_pixel_hash(&dir.join(frame_file_name(frame))) {
+                Ok(hash) if hash == hashes.pixels => report.matching += 1,
+                Ok(_) => report.changed.push(frame),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => report.missing.push(frame),
+                Err(_) => report.changed.push(frame),
+            }
+        }
+        report
+    }
+}
+
+/// Outcome of [`FrameManifest::verify`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerifyReport {
+    pub matching: u32,
+    pub missing: Vec<u32>,
+    /// Frames whose pixels differ from the manifest or no longer decode.
+    pub changed: Vec<u32>,
+}
+
+impl VerifyReport {
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// `14 frames match`, or `12 of 14 frames match; missing 3; changed 5, 6`.
+impl fmt::Display for VerifyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.matching as usize + self.missing.len() + self.changed.len();
+        if self.is_ok() {
+            return write!(f, "{total} frames match");
+        }
+        write!(f, "{} of {total} frames match", self.matching)?;
+        for (label, frames) in [("missing", &self.missing), ("changed", &self.changed)] {
+            if !frames.is_empty() {
+                let list: Vec<String> = frames.iter().map(u32::to_string).collect();
+                write!(f, "; {label} {}", list.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Hex BLAKE3 hash of the RGBA8 rows of the PNG at `path`, decoded a row at a time so
+/// frames too large for memory can be checked too.
+pub fn png_pixel_hash(path: &Path) -> io::Result<String> {
+    let decoder = png::Decoder::new(io::BufReader::new(fs::File::open(path)?));
+    let mut reader = decoder.read_info().map_err(io::Error::from)?;
+    let info = reader.info();
+    if info.color_type != png::ColorType::Rgba || info.bit_depth != png::BitDepth::Eight {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not an RGBA8 frame",
+        ));
+    }
+    let mut hasher = blake3::Hasher::new();
+    while let Some(row) = reader.next_row().map_err(io::Error::from)? {
+        hasher.update(row.data());
+    }
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{write_png_hashed, Complex, CpuRenderer, FractalKind, FrameMode, QualityProfile};
+
+    #[test]
+    fn snapshot_hashes_follow_the_parameters() {
+        let params = FractalParams::new(FractalKind::Mandelbrot).with_max_iter(80);
+        let cam = Camera::new(Complex::new(-0.5, 0.0), 40.0);
+        let snapshot = FrameSnapshot {
+            size: (64, 48),
+            fractal: &params,
+            sampling: SampleJitter::default(),
+            precision: Precision::F32,
+            camera: &cam,
+            zoom_readout: false,
+            annotations: &[],
+            side_by_side: None,
+            stylize: None,
+            adaptive_aa: None,
+            equalizer: None,
+        };
+        assert_eq!(snapshot.hash(), snapshot.hash());
+        assert_eq!(snapshot.hash().len(), 64);
+        let deeper = params.clone().with_max_iter(81);
+        let label = [TextStamp {
+            text: "minibrot".into(),
+            origin: (8, 8),
+            dot: 1,
+            color: [255; 3],
+            alpha: 255,
+        }];
+        let retro = Stylize {
+            enabled: true,
+            ..Stylize::default()
+        };
+        let refined = AdaptiveAa {
+            enabled: true,
+            ..AdaptiveAa::default()
+        };
+        let changed = [
+            FrameSnapshot {
+                fractal: &deeper,
+                ..snapshot
+            },
+            FrameSnapshot {
+                size: (64, 49),
+                ..snapshot
+            },
+            FrameSnapshot {
+                zoom_readout: true,
+                ..snapshot
+            },
+            FrameSnapshot {
+                annotations: &label,
+                ..snapshot
+            },
+            FrameSnapshot {
+                precision: Precision::F64,
+                ..snapshot
+            },
+            FrameSnapshot {
+                side_by_side: Some((&params, &cam)),
+                ..snapshot
+            },
+            FrameSnapshot {
+                stylize: Some(&retro),
+                ..snapshot
+            },
+            FrameSnapshot {
+                adaptive_aa: Some(&refined),
+                ..snapshot
+            },
+            FrameSnapshot {
+                equalizer: Some(&[1.0, 2.0]),
+                ..snapshot
+            },
+            FrameSnapshot {
+                sampling: SampleJitter {
+                    seed: 1,
+                    ..snapshot.sampling
+                },
+                ..snapshot
+            },
+        ];
+        for other in changed {
+            assert_ne!(other.hash(), snapshot.hash());
+        }
+
+        let json = canonical_json(&serde_json::json!({"b": 1, "a": {"d": 2, "c": 3}})).unwrap();
+        assert_eq!(json, br#"{"a":{"c":3,"d":2},"b":1}"#);
+    }
+
+    #[test]
+    fn verify_finds_missing_and_changed_frames() {
+        let dir = tempfile::tempdir().unwrap();
+        let params = FractalParams::new(FractalKind::Mandelbrot).with_max_iter(40);
+        let cam = Camera::new(Complex::new(-0.5, 0.0), 20.0);
+        let mut manifest = FrameManifest::default();
+        for frame in 0..4 {
+            let path = dir.path().join(frame_file_name(frame));
+            let size = (40 + frame, 30);
+            let (_, pixels) = write_png_hashed(
+                &path,
+                size,
+                &params,
+                &cam,
+                &mut CpuRenderer,
+                &QualityProfile::preview(),
+                FrameMode::InMemory,
+            )
+            .unwrap();
+            assert_eq!(png_pixel_hash(&path).unwrap(), pixels);
+            manifest.frames.insert(
+                frame,
+                FrameHashes {
+                    params: String::new(),
+                    pixels,
+                },
+            );
+        }
+        manifest.save(dir.path()).unwrap();
+        let loaded = FrameManifest::load(dir.path()).unwrap().unwrap();
+        assert_eq!(loaded, manifest);
+        assert_eq!(loaded.verify(dir.path()).to_string(), "4 frames match");
+
+        fs::remove_file(dir.path().join(frame_file_name(1))).unwrap();
+        fs::write(dir.path().join(frame_file_name(2)), b"not a png").unwrap();
+        fs::copy(
+            dir.path().join(frame_file_name(0)),
+            dir.path().join(frame_file_name(3)),
+        )
+        .unwrap();
+        let report = loaded.verify(dir.path());
+        assert!(!report.is_ok());
+        assert_eq!(
+            report.to_string(),
+            "1 of 4 frames match; missing 1; changed 2, 3"
+        );
+    }
+}
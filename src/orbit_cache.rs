@@ -0,0 +1,158 @@
+//! Reuse of center-derived data, such as a reference orbit, across the frames of an
+//! export. Consecutive frames of a zoom share almost the same center, so data computed
+//! for one frame usually serves the next ones too.
+
+use crate::{BigComplex, BigFloat, Camera, Complex, Complex64, FractalKind, FractalParams};
+
+/// How far the center may drift, in pixels of the frame asking, before cached data is
+/// computed again.
+pub const REUSE_DRIFT_PIXELS: f64 = 0.5;
+
+/// What center-derived data was computed for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrbitKey {
+    pub kind: FractalKind,
+    pub power: f32,
+    /// Julia constant; ignored by formulas that do not use it, but part of the key anyway.
+    pub c: Complex,
+    pub max_iter: u32,
+    pub center: Complex64,
+    /// What the exact center adds to `center`, below what `f64` resolves there; see
+    /// [`Camera::deep_center`].
+    pub center_fine: Complex64,
+    /// Width of one pixel in the complex plane.
+    pub pixel_size: f64,
+    /// Fraction limbs of [`BigFloat`] precision the frame needs.
+    pub limbs: usize,
+}
+
+impl OrbitKey {
+    pub fn new(params: &FractalParams, cam: &Camera) -> Self {
+        let limbs = BigFloat::limbs_for_scale(cam.scale);
+        let center_fine =
+            (&cam.exact_center() - &BigComplex::from_complex64(cam.center, limbs)).to_complex64();
+        Self {
+            kind: params.kind,
+            power: params.power,
+            c: params.c,
+            max_iter: params.max_iter,
+            center: cam.center,
+            center_fine,
+            pixel_size: 1.0 / cam.scale,
+            limbs,
+        }
+    }
+
+    /// Whether data computed for `self` serves a frame at `other`: the same formula, at
+    /// least as many iterations and limbs, and a center within [`REUSE_DRIFT_PIXELS`] of
+    /// `other`'s pixels. Zooming in shrinks the pixels, so deeper frames tolerate less
+    /// drift.
+    pub fn covers(&self, other: &OrbitKey) -> bool {
+        let dx = (self.center.re - other.center.re) + (self.center_fine.re - other.center_fine.re);
+        let dy = (self.center.im - other.center.im) + (self.center_fine.im - other.center_fine.im);
+        self.kind == other.kind
+            && self.power == other.power
+            && self.c == other.c
+            && self.max_iter >= other.max_iter
+            && self.limbs >= other.limbs
+            && dx.hypot(dy) <= REUSE_DRIFT_PIXELS * other.pixel_size
+    }
+}
+
+/// One cached value keyed by [`OrbitKey`], with hit and miss counts for the stats log.
+#[derive(Debug)]
+pub struct OrbitCache<T> {
+    entry: Option<(OrbitKey, T)>,
+    pub hits: u32,
+    pub misses: u32,
+}
+
+impl<T> Default for OrbitCache<T> {
+    fn default() -> Self {
+        Self {
+            entry: None,
+            hits: 0,
+            misses: 0,
+        }
+    }
+}
+
+impl<T> OrbitCache<T> {
+    /// The data for `key`: the cached value if its key covers `key`, else a new one from
+    /// `compute`, which then replaces it.
+    pub fn get_or_compute<E>(
+        &mut self,
+        key: OrbitKey,
+        compute: impl FnOnce(&OrbitKey) -> Result<T, E>,
+    ) -> Result<&T, E> {
+        let hit = self
+            .entry
+            .as_ref()
+            .is_some_and(|(cached, _)| cached.covers(&key));
+        if hit {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+            self.entry = Some((key, compute(&key)?));
+        }
+        Ok(&self.entry.as_ref().expect("filled above").1)
+    }
+
+    /// Hits and misses since the last call, for one frame's stats.
+    pub fn take_counts(&mut self) -> (u32, u32) {
+        (
+            std::mem::take(&mut self.hits),
+            std::mem::take(&mut self.misses),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cached_data_serves_frames_until_the_center_drifts_half_a_pixel() {
+        let params = FractalParams::new(FractalKind::Mandelbrot).with_max_iter(500);
+        let mut cam = Camera::new(Complex::new(-0.75, 0.1), 1000.0);
+        let mut cache = OrbitCache::default();
+        let mut computed = 0;
+        let mut frame = |cache: &mut OrbitCache<u32>, params: &FractalParams, cam: &Camera| {
+            *cache
+                .get_or_compute(OrbitKey::new(params, cam), |_| {
+                    computed += 1;
+                    Ok::<_, ()>(computed)
+                })
+                .unwrap()
+        };
+        assert_eq!(frame(&mut cache, &params, &cam), 1);
+        // A zoom toward the same center keeps hitting.
+        cam.scale = 2000.0;
+        assert_eq!(frame(&mut cache, &params, &cam), 1);
+        cam.center.re += 0.2 / cam.scale;
+        assert_eq!(frame(&mut cache, &params, &cam), 1);
+        assert_eq!(cache.take_counts(), (2, 1));
+
+        // Past half a pixel of the newer frame, or with more iterations, it is recomputed.
+        cam.center.re += 0.4 / cam.scale;
+        assert_eq!(frame(&mut cache, &params, &cam), 2);
+        let deeper = params.clone().with_max_iter(800);
+        assert_eq!(frame(&mut cache, &deeper, &cam), 3);
+        assert_eq!(frame(&mut cache, &params, &cam), 3);
+        let other = FractalParams::new(FractalKind::BurningShip).with_max_iter(500);
+        assert_eq!(frame(&mut cache, &other, &cam), 4);
+        assert_eq!(cache.take_counts(), (1, 3));
+
+        // Depths that need more bits of center, and moves finer than `f64` resolves there.
+        cam.scale = 1e30;
+        assert_eq!(frame(&mut cache, &other, &cam), 5);
+        let rounded = cam.center;
+        cam.translate(Complex64::new(0.4 / cam.scale, 0.0));
+        assert_eq!(cam.center, rounded);
+        assert_eq!(frame(&mut cache, &other, &cam), 5);
+        cam.translate(Complex64::new(0.2 / cam.scale, 0.0));
+        assert_eq!(frame(&mut cache, &other, &cam), 6);
+        assert_eq!(cache.take_counts(), (1, 2));
+        assert_eq!(cache.take_counts(), (0, 0));
+    }
+}
@@ -0,0 +1,463 @@
+//! Palette stops, built-in presets, and lookup-table construction.
+
+use std::{
+    cmp::Ordering,
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+use crate::{FractalParams, Interp, PaletteStop, StopDrag};
+
+/// The default blue, white and orange gradient.
+pub fn default_palette() -> Vec<PaletteStop> {
+    vec![
+        PaletteStop {
+            pos: 0.0,
+            color: [0.0, 0.03, 0.39],
+        },
+        PaletteStop {
+            pos: 0.16,
+            color: [0.13, 0.42, 0.8],
+        },
+        PaletteStop {
+            pos: 0.42,
+            color: [0.93, 1.0, 1.0],
+        },
+        PaletteStop {
+            pos: 0.6425,
+            color: [1.0, 0.67, 0.0],
+        },
+        PaletteStop {
+            pos: 0.8575,
+            color: [0.0, 0.01, 0.0],
+        },
+        PaletteStop {
+            pos: 1.0,
+            color: [0.0, 0.03, 0.39],
+        },
+    ]
+}
+
+/// `const`-friendly palette stop used by presets.
+#[derive(Clone, Copy)]
+pub struct PaletteStopDef {
+    pub pos: f32,
+    pub color: [f32; 3],
+}
+
+/// A named built-in palette.
+pub struct PalettePreset {
+    pub name: &'static str,
+    pub stops: &'static [PaletteStopDef],
+}
+
+/// All built-in palettes.
+pub fn palette_presets() -> &'static [PalettePreset] {
+    const NEON_PULSE: &[PaletteStopDef] = &[
+        PaletteStopDef {
+            pos: 0.0,
+            color: [1.0, 0.0, 0.6],
+        },
+        PaletteStopDef {
+            pos: 0.2,
+            color: [0.0, 1.0, 0.9],
+        },
+        PaletteStopDef {
+            pos: 0.4,
+            color: [1.0, 1.0, 0.0],
+        },
+        PaletteStopDef {
+            pos: 0.6,
+            color: [0.0, 0.8, 0.2],
+        },
+        PaletteStopDef {
+            pos: 0.8,
+            color: [0.0, 0.2, 1.0],
+        },
+        PaletteStopDef {
+            pos: 1.0,
+            color: [1.0, 0.0, 0.0],
+        },
+    ];
+    const CYBER_SUNSET: &[PaletteStopDef] = &[
+        PaletteStopDef {
+            pos: 0.0,
+            color: [0.05, 0.0, 0.2],
+        },
+        PaletteStopDef {
+            pos: 0.15,
+            color: [0.4, 0.0, 0.5],
+        },
+        PaletteStopDef {
+            pos: 0.35,
+            color: [1.0, 0.0, 0.4],
+        },
+        PaletteStopDef {
+            pos: 0.6,
+            color: [1.0, 0.6, 0.0],
+        },
+        PaletteStopDef {
+            pos: 0.85,
+            color: [1.0, 0.95, 0.5],
+        },
+        PaletteStopDef {
+            pos: 1.0,
+            color: [0.05, 0.0, 0.2],
+        },
+    ];
+    const LASER_GRID: &[PaletteStopDef] = &[
+        PaletteStopDef {
+            pos: 0.0,
+            color: [0.0, 0.0, 0.0],
+        },
+        PaletteStopDef {
+            pos: 0.2,
+            color: [0.0, 1.0, 0.0],
+        },
+        PaletteStopDef {
+            pos: 0.4,
+            color: [1.0, 0.0, 0.0],
+        },
+        PaletteStopDef {
+            pos: 0.6,
+            color: [0.0, 0.0, 1.0],
+        },
+        PaletteStopDef {
+            pos: 0.8,
+            color: [1.0, 1.0, 1.0],
+        },
+        PaletteStopDef {
+            pos: 1.0,
+            color: [1.0, 0.0, 1.0],
+        },
+    ];
+    const ULTRAVIOLET: &[PaletteStopDef] = &[
+        PaletteStopDef {
+            pos: 0.0,
+            color: [0.2, 0.0, 0.4],
+        },
+        PaletteStopDef {
+            pos: 0.25,
+            color: [0.5, 0.0, 0.9],
+        },
+        PaletteStopDef {
+            pos: 0.5,
+            color: [0.1, 0.6, 1.0],
+        },
+        PaletteStopDef {
+            pos: 0.75,
+            color: [0.9, 0.6, 0.0],
+        },
+        PaletteStopDef {
+            pos: 1.0,
+            color: [0.1, 0.0, 0.2],
+        },
+    ];
+    &[
+        PalettePreset {
+            name: "Neon Pulse",
+            stops: NEON_PULSE,
+        },
+        PalettePreset {
+            name: "Cyber Sunset",
+            stops: CYBER_SUNSET,
+        },
+        PalettePreset {
+            name: "Laser Grid",
+            stops: LASER_GRID,
+        },
+        PalettePreset {
+            name: "Ultraviolet",
+            stops: ULTRAVIOLET,
+        },
+    ]
+}
+
+/// Replaces `palette` with the stops of `preset`.
+pub fn apply_palette_preset(palette: &mut Vec<PaletteStop>, preset: &PalettePreset) {
+    palette.clear();
+    palette.extend(preset.stops.iter().map(|stop| PaletteStop {
+        pos: stop.pos,
+        color: stop.color,
+    }));
+    palette.sort_by(stop_order);
+}
+
+/// Mirrors stop positions so the gradient runs backwards.
+pub fn flip_palette(palette: &mut Vec<PaletteStop>) {
+    for stop in palette.iter_mut() {
+        stop.pos = 1.0 - stop.pos;
+    }
+    palette.sort_by(stop_order);
+}
+
+/// Shifts each color to the next stop, keeping positions.
+pub fn cycle_palette_colors(palette: &mut Vec<PaletteStop>) {
+    if palette.len() > 1 {
+        let mut colors: Vec<[f32; 3]> = palette.iter().map(|stop| stop.color).collect();
+        colors.rotate_right(1);
+        for (stop, color) in palette.iter_mut().zip(colors.into_iter()) {
+            stop.color = color;
+        }
+    }
+}
+
+/// Smallest gap the editor keeps between two palette stops, so none share a position.
+pub const MIN_STOP_GAP: f32 = 1e-3;
+
+/// Gradient order: by position, then by color, so stops sharing a position come out the
+/// same whatever order they were given in.
+fn stop_order(a: &PaletteStop, b: &PaletteStop) -> Ordering {
+    a.pos.total_cmp(&b.pos).then_with(|| {
+        a.color
+            .iter()
+            .zip(&b.color)
+            .map(|(x, y)| x.total_cmp(y))
+            .find(|o| o.is_ne())
+            .unwrap_or(Ordering::Equal)
+    })
+}
+
+/// Sorts `palette` and moves stops apart until each is [`MIN_STOP_GAP`] past the one
+/// before, all within 0..1. Returns whether any position changed.
+pub fn tidy_palette(palette: &mut [PaletteStop]) -> bool {
+    palette.sort_by(stop_order);
+    spread_stops(palette)
+}
+
+/// [`tidy_palette`] for stops already in order.
+fn spread_stops(palette: &mut [PaletteStop]) -> bool {
+    let before: Vec<f32> = palette.iter().map(|stop| stop.pos).collect();
+    for i in 0..palette.len() {
+        let low = i
+            .checked_sub(1)
+            .map_or(0.0, |prev| palette[prev].pos + MIN_STOP_GAP);
+        palette[i].pos = palette[i].pos.max(low);
+    }
+    // Stops pushed past 1 come back, pushing their predecessors down in turn.
+    for i in (0..palette.len()).rev() {
+        let high = palette
+            .get(i + 1)
+            .map_or(1.0, |next| next.pos - MIN_STOP_GAP);
+        palette[i].pos = palette[i].pos.min(high).max(0.0);
+    }
+    palette
+        .iter()
+        .zip(before)
+        .any(|(stop, pos)| stop.pos != pos)
+}
+
+/// Whether stop `index` of a tidy palette sits at the minimum gap from a neighbor, as
+/// stops nudged off a shared position do.
+pub fn stop_is_crowded(palette: &[PaletteStop], index: usize) -> bool {
+    let pos = palette[index].pos;
+    let near = |other: &PaletteStop| (other.pos - pos).abs() < MIN_STOP_GAP * 1.5;
+    index
+        .checked_sub(1)
+        .is_some_and(|prev| near(&palette[prev]))
+        || palette.get(index + 1).is_some_and(near)
+}
+
+/// Moves stop `index` of a tidy `palette` to `pos`, clamped to 0..1. Reaching a neighbor,
+/// it passes or stops short of it as `drag` says, and the palette stays tidy. Returns the
+/// stop's index afterwards.
+pub fn move_palette_stop(
+    palette: &mut Vec<PaletteStop>,
+    index: usize,
+    pos: f32,
+    drag: StopDrag,
+) -> usize {
+    let pos = pos.clamp(0.0, 1.0);
+    let mut stop = palette.remove(index);
+    let at = match drag {
+        StopDrag::Clamp => index,
+        // Landing exactly on a neighbor does not pass it yet.
+        StopDrag::Swap if pos > stop.pos => palette.partition_point(|s| s.pos < pos),
+        StopDrag::Swap => palette.partition_point(|s| s.pos <= pos),
+    };
+    let low = at
+        .checked_sub(1)
+        .map_or(0.0, |prev| palette[prev].pos + MIN_STOP_GAP);
+    let high = palette.get(at).map_or(1.0, |next| next.pos - MIN_STOP_GAP);
+    if low <= high {
+        stop.pos = pos.clamp(low, high);
+        palette.insert(at, stop);
+    } else {
+        // No room between the neighbors; spread them out.
+        stop.pos = pos.max(low - MIN_STOP_GAP).min(high + MIN_STOP_GAP);
+        palette.insert(at, stop);
+        spread_stops(palette);
+    }
+    at
+}
+
+/// Stops in gradient order, padded with end stops so the gradient spans 0..1. Stops
+/// sharing a position make a hard edge, ordered by color.
+pub fn normalized_stops(stops: &[PaletteStop]) -> Vec<PaletteStop> {
+    if stops.is_empty() {
+        return default_palette();
+    }
+    let mut sorted = stops.to_vec();
+    sorted.sort_by(stop_order);
+    let first_color = sorted.first().unwrap().color;
+    if sorted.first().unwrap().pos > 0.0 {
+        sorted.insert(
+            0,
+            PaletteStop {
+                pos: 0.0,
+                color: first_color,
+            },
+        );
+    }
+    let last_color = sorted.last().unwrap().color;
+    if (sorted.last().unwrap().pos - 1.0).abs() > f32::EPSILON {
+        sorted.push(PaletteStop {
+            pos: 1.0,
+            color: last_color,
+        });
+    }
+    sorted
+}
+
+/// Bakes the palette of `params` (with its phase) into a `size`-entry RGB lookup table.
+pub fn build_palette(params: &FractalParams, size: usize) -> Vec<[u8; 3]> {
+    bake_palette(&params.palette, params.palette_phase, size)
+}
+
+/// Bakes `stops` shifted by `phase` into a `size`-entry RGB lookup table.
+pub fn bake_palette(stops: &[PaletteStop], phase: f32, size: usize) -> Vec<[u8; 3]> {
+    let stops = normalized_stops(stops);
+    let mut lut = Vec::with_capacity(size);
+    for i in 0..size {
+        let mut t = i as f32 / (size as f32 - 1.0);
+        t = (t + phase).fract();
+        let mut prev = stops.first().unwrap();
+        let mut color = prev.color;
+        for stop in stops.iter().skip(1) {
+            if t <= stop.pos {
+                let span = (stop.pos - prev.pos).max(1e-4);
+                let u = ((t - prev.pos) / span).clamp(0.0, 1.0);
+                color = [
+                    Interp::lerp(prev.color[0], stop.color[0], u),
+                    Interp::lerp(prev.color[1], stop.color[1], u),
+                    Interp::lerp(prev.color[2], stop.color[2], u),
+                ];
+                break;
+            }
+            prev = stop;
+        }
+        lut.push([
+            (color[0].clamp(0.0, 1.0) * 255.0) as u8,
+            (color[1].clamp(0.0, 1.0) * 255.0) as u8,
+            (color[2].clamp(0.0, 1.0) * 255.0) as u8,
+        ]);
+    }
+    lut
+}
+
+/// Hash of the stops' positions and colors, bit for bit, identifying a baked table
+/// whatever the phase.
+pub fn palette_key(stops: &[PaletteStop]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for stop in stops {
+        stop.pos.to_bits().hash(&mut hasher);
+        stop.color.map(f32::to_bits).hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Looks up `t` in 0..1 in a table from [`build_palette`].
+pub fn sample_palette(lut: &[[u8; 3]], t: f32) -> [u8; 3] {
+    let idx = ((lut.len() - 1) as f32 * t.clamp(0.0, 1.0)) as usize;
+    lut[idx]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: [f32; 3] = [1.0, 0.0, 0.0];
+    const GREEN: [f32; 3] = [0.0, 1.0, 0.0];
+    const BLUE: [f32; 3] = [0.0, 0.0, 1.0];
+
+    fn positions(stops: &[PaletteStop]) -> Vec<f32> {
+        stops.iter().map(|stop| stop.pos).collect()
+    }
+
+    #[test]
+    fn normalized_stops_ignore_input_order() {
+        let stops = [
+            PaletteStop::new(0.8, BLUE),
+            PaletteStop::new(0.5, GREEN),
+            PaletteStop::new(0.5, RED),
+            PaletteStop::new(0.2, RED),
+        ];
+        let mut reversed = stops;
+        reversed.reverse();
+        let mut swapped = stops;
+        swapped.swap(1, 2);
+        let normalized = normalized_stops(&stops);
+        assert_eq!(normalized_stops(&reversed), normalized);
+        assert_eq!(normalized_stops(&swapped), normalized);
+        assert_eq!(positions(&normalized), [0.0, 0.2, 0.5, 0.5, 0.8, 1.0]);
+        // A hard edge at 0.5, the same way round every time.
+        assert_eq!((normalized[2].color, normalized[3].color), (GREEN, RED));
+        let lut = bake_palette(&stops, 0.0, 101);
+        assert_eq!(bake_palette(&swapped, 0.0, 101), lut);
+        assert_eq!(bake_palette(&reversed, 0.0, 101), lut);
+
+        // All stops at 0 fill the gradient with the last of them.
+        let at_zero = [PaletteStop::new(0.0, RED), PaletteStop::new(0.0, BLUE)];
+        let normalized = normalized_stops(&at_zero);
+        assert_eq!(positions(&normalized), [0.0, 0.0, 1.0]);
+        assert_eq!(normalized[2].color, RED);
+        assert_eq!(normalized_stops(&[at_zero[1], at_zero[0]]), normalized);
+    }
+
+    #[test]
+    fn tidying_nudges_shared_positions_apart() {
+        let mut palette = vec![
+            PaletteStop::new(1.0, RED),
+            PaletteStop::new(0.5, GREEN),
+            PaletteStop::new(0.5, RED),
+            PaletteStop::new(1.0, BLUE),
+        ];
+        assert!(tidy_palette(&mut palette));
+        assert_eq!(
+            positions(&palette),
+            [0.5, 0.5 + MIN_STOP_GAP, 1.0 - MIN_STOP_GAP, 1.0]
+        );
+        assert!((0..4).all(|i| stop_is_crowded(&palette, i)));
+        assert!(!tidy_palette(&mut palette));
+
+        let mut at_zero = vec![PaletteStop::new(0.0, RED); 3];
+        tidy_palette(&mut at_zero);
+        assert_eq!(positions(&at_zero), [0.0, MIN_STOP_GAP, 2.0 * MIN_STOP_GAP]);
+    }
+
+    #[test]
+    fn dragged_stops_pass_or_stop_at_neighbors() {
+        let palette = vec![
+            PaletteStop::new(0.0, RED),
+            PaletteStop::new(0.4, GREEN),
+            PaletteStop::new(0.6, BLUE),
+        ];
+        assert!(!stop_is_crowded(&palette, 1));
+        let mut swap = palette.clone();
+        assert_eq!(move_palette_stop(&mut swap, 0, 0.5, StopDrag::Swap), 1);
+        assert_eq!(positions(&swap), [0.4, 0.5, 0.6]);
+        assert_eq!(swap[1].color, RED);
+        // Onto a neighbor's position stops short of it.
+        assert_eq!(move_palette_stop(&mut swap, 1, 0.6, StopDrag::Swap), 1);
+        assert_eq!(swap[1].pos, 0.6 - MIN_STOP_GAP);
+        assert_eq!(move_palette_stop(&mut swap, 1, 2.0, StopDrag::Swap), 2);
+        assert_eq!(positions(&swap), [0.4, 0.6, 1.0]);
+
+        let mut clamp = palette.clone();
+        assert_eq!(move_palette_stop(&mut clamp, 0, 0.5, StopDrag::Clamp), 0);
+        assert_eq!(positions(&clamp), [0.4 - MIN_STOP_GAP, 0.4, 0.6]);
+        assert_eq!(move_palette_stop(&mut clamp, 2, -1.0, StopDrag::Clamp), 2);
+        assert_eq!(clamp[2].pos, 0.4 + MIN_STOP_GAP);
+        assert!(!tidy_palette(&mut clamp));
+    }
+}
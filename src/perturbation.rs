@@ -0,0 +1,221 @@
+//! Perturbation rendering for zooms deeper than `f64` resolves: one reference orbit per
+//! frame iterated in [`BigFloat`](crate::BigFloat) precision, and every pixel as an `f64`
+//! delta from it.
+
+use std::{borrow::Cow, fmt, sync::Arc};
+
+use crate::{
+    formula,
+    formula::{distance_estimate, trap_distance_f64, StripeSum},
+    BigComplex, Camera, Complex64, FractalKind, FractalParams, OrbitKey, OrbitResult, Precision,
+};
+
+/// Formulas with a delta iteration; the others render at [`Precision::F64`] instead.
+pub fn perturbation_supported(kind: FractalKind) -> bool {
+    kind == FractalKind::Mandelbrot
+}
+
+/// Whether a frame of `params` drawn at `precision` iterates against a reference orbit.
+pub fn needs_reference(params: &FractalParams, precision: Precision) -> bool {
+    precision.min(params.precision) == Precision::Perturbation
+        && perturbation_supported(params.kind)
+}
+
+/// The orbit of one point iterated at full precision, rounded to `f64` at every step.
+#[derive(Clone, PartialEq)]
+pub struct ReferenceOrbit {
+    /// The frame it was computed for; see [`OrbitKey::covers`].
+    pub key: OrbitKey,
+    /// The point iterated, the exact center of that frame.
+    pub point: BigComplex,
+    /// Iterates from `z0 = 0` up to the first that escapes, or `max_iter` steps.
+    pub orbit: Vec<Complex64>,
+}
+
+impl fmt::Debug for ReferenceOrbit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReferenceOrbit")
+            .field("key", &self.key)
+            .field("point", &self.point.to_complex64())
+            .field("len", &self.orbit.len())
+            .finish()
+    }
+}
+
+impl ReferenceOrbit {
+    /// The orbit of `cam`'s exact center under the Mandelbrot iteration.
+    pub fn compute(params: &FractalParams, cam: &Camera) -> Self {
+        let key = OrbitKey::new(params, cam);
+        let point = cam.exact_center().with_frac_limbs(key.limbs);
+        // Squares of the iterates must fit the integer part of a BigFloat. A reference
+        // that stops short is no error: pixels rebase onto its start.
+        let er2 = (f64::from(params.escape_radius).powi(2)).min(2f64.powi(60));
+        let mut orbit = Vec::with_capacity(params.max_iter.min(1 << 16) as usize + 1);
+        let mut z = BigComplex::default();
+        orbit.push(Complex64::default());
+        for _ in 0..params.max_iter {
+            let xy = &z.re * &z.im;
+            z = BigComplex::new(
+                &(&(&z.re * &z.re) - &(&z.im * &z.im)) + &point.re,
+                &(&xy + &xy) + &point.im,
+            );
+            let rounded = z.to_complex64();
+            orbit.push(rounded);
+            if rounded.re * rounded.re + rounded.im * rounded.im > er2 {
+                break;
+            }
+        }
+        Self { key, point, orbit }
+    }
+
+    /// Where `cam`'s center lies relative to [`ReferenceOrbit::point`].
+    pub fn center_offset(&self, cam: &Camera) -> Complex64 {
+        (&cam.exact_center() - &self.point).to_complex64()
+    }
+}
+
+/// `params` with a reference orbit for `cam` attached, unless perturbation is off at
+/// `precision` or the attached one already serves the frame.
+pub fn with_reference<'a>(
+    params: &'a FractalParams,
+    cam: &Camera,
+    precision: Precision,
+) -> Cow<'a, FractalParams> {
+    if !needs_reference(params, precision) {
+        return Cow::Borrowed(params);
+    }
+    let key = OrbitKey::new(params, cam);
+    if params
+        .reference
+        .as_ref()
+        .is_some_and(|reference| reference.key.covers(&key))
+    {
+        return Cow::Borrowed(params);
+    }
+    let mut params = params.clone();
+    params.reference = Some(Arc::new(ReferenceOrbit::compute(&params, cam)));
+    Cow::Owned(params)
+}
+
+/// Iterates the point `dc` away from the reference point, like
+/// [`crate::formula::iterate_orbit_f64`] iterates it directly, but as a delta from the
+/// reference orbit, which stays accurate however small `dc` is.
+///
+/// A single reference leaves glitches where a pixel's orbit parts from it: the delta
+/// grows past the orbit itself, or the reference escapes first. Such pixels rebase,
+/// continuing from the start of the reference with their whole value as the delta.
+pub fn iterate_perturbed(
+    p: &FractalParams,
+    reference: &ReferenceOrbit,
+    dc: Complex64,
+) -> OrbitResult {
+    let er2 = f64::from(p.escape_radius) * f64::from(p.escape_radius);
+    let orbit = &reference.orbit;
+    let mut dz = Complex64::default();
+    let mut z = Complex64::default();
+    let mut m = 0;
+
+    // Derivative of the full z with respect to c, for the distance estimate.
+    let formula = formula(p.kind);
+    let mut derivative = p.estimates_distance().then(Complex64::default);
+    let mut stripes = StripeSum::new(p);
+    let mut i = 0u32;
+    let mut trap_min = f64::MAX;
+    while i < p.max_iter {
+        if z.re * z.re + z.im * z.im > er2 {
+            break;
+        }
+
+        if let Some(d) = derivative {
+            derivative = formula.derivative(p, z, d);
+        }
+        // dz' = 2·Z·dz + dz² + dc
+        let (ax, ay) = (2.0 * orbit[m].re + dz.re, 2.0 * orbit[m].im + dz.im);
+        dz = Complex64::new(
+            ax * dz.re - ay * dz.im + dc.re,
+            ax * dz.im + ay * dz.re + dc.im,
+        );
+        m += 1;
+        z = Complex64::new(orbit[m].re + dz.re, orbit[m].im + dz.im);
+        if let Some(stripes) = &mut stripes {
+            stripes.add(z);
+        }
+
+        if p.orbit.enabled {
+            trap_min = trap_min.min(trap_distance_f64(p, z));
+        }
+
+        i += 1;
+
+        if m + 1 == orbit.len() || z.re * z.re + z.im * z.im < dz.re * dz.re + dz.im * dz.im {
+            dz = z;
+            m = 0;
+        }
+    }
+
+    OrbitResult {
+        iter: i,
+        zx: z.re as f32,
+        zy: z.im as f32,
+        trap_min: trap_min as f32,
+        distance: derivative.map(|d| distance_estimate(z, d)),
+        stripe: stripes.map(|stripes| stripes.averages()),
+        root: None,
+        lyapunov: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{sample_tile_cpu, ColoringMode, TileInfo};
+
+    /// Share of pixels whose perturbed samples, distance estimates included, match direct
+    /// `f64` iteration.
+    fn agreement(cam: &Camera) -> f32 {
+        let direct = FractalParams {
+            precision: Precision::F64,
+            ..FractalParams::new(FractalKind::Mandelbrot)
+                .with_max_iter(1000)
+                .with_coloring(ColoringMode::DistanceEstimate)
+        };
+        let perturbed = FractalParams {
+            precision: Precision::Perturbation,
+            ..direct.clone()
+        };
+        let tile = TileInfo::full(48, 48);
+        let expected = sample_tile_cpu(&tile, &direct, cam);
+        let actual = sample_tile_cpu(&tile, &perturbed, cam);
+        let matching = expected
+            .iter()
+            .zip(&actual)
+            .filter(|(a, b)| {
+                let distance = match (a.distance, b.distance) {
+                    (Some(a), Some(b)) => (a - b).abs() <= 1e-2 * a.max(b),
+                    _ => false,
+                };
+                a.interior == b.interior
+                    && (a.smooth_iter - b.smooth_iter).abs() < 1e-2
+                    && (a.interior || distance)
+            })
+            .count();
+        matching as f32 / expected.len() as f32
+    }
+
+    #[test]
+    fn deltas_agree_with_direct_iteration() {
+        let cam = Camera::new(Complex64::new(-0.743_643_887, 0.131_825_904), 1e9);
+        let share = agreement(&cam);
+        assert!(share >= 0.98, "{share}");
+    }
+
+    #[test]
+    fn pixels_rebase_where_the_reference_escapes() {
+        // The center escapes within a few iterations; much of the view never does.
+        let cam = Camera::new(Complex64::new(0.4, 0.0), 20.0);
+        let reference = ReferenceOrbit::compute(&FractalParams::default(), &cam);
+        assert!(reference.orbit.len() < 20);
+        let share = agreement(&cam);
+        assert!(share >= 0.98, "{share}");
+    }
+}
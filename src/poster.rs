@@ -0,0 +1,142 @@
+//! Poster frames: the one frame a video or a project file is represented by, either at a
+//! chosen time or the most detailed of a few evenly spread candidates.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use image::ImageEncoder;
+
+use crate::{
+    evaluate_export_frame, frame_at, frame_time, render_image_stats, Camera, EvaluatedFrame,
+    ExportError, FrameStats, Project, Renderer, ScriptRunner,
+};
+
+/// Frames scored when no poster time is set.
+pub const POSTER_CANDIDATES: u32 = 8;
+/// Width candidates are rendered at for scoring.
+pub const POSTER_SCAN_WIDTH: u32 = 96;
+/// Width of the thumbnail embedded in saved projects.
+pub const PROJECT_THUMBNAIL_WIDTH: u32 = 160;
+
+/// Up to `count` of the `total` frames, spread evenly at the middle of equal spans.
+pub fn poster_candidates(total: u32, count: u32) -> Vec<u32> {
+    if total <= count {
+        return (0..total).collect();
+    }
+    let (total, count) = (u64::from(total), u64::from(count));
+    (0..count)
+        .map(|i| ((2 * i + 1) * total / (2 * count)) as u32)
+        .collect()
+}
+
+/// Export frame `frame` with the script applied, as the exporter renders it.
+fn poster_eval(
+    proj: &Project,
+    script: Option<&ScriptRunner>,
+    frame: u32,
+) -> Result<EvaluatedFrame, ExportError> {
+    let mut eval = evaluate_export_frame(proj, frame);
+    if let Some(script) = script {
+        script.apply(frame_time(frame, proj.export.fps), &mut eval)?;
+    }
+    Ok(eval)
+}
+
+/// `eval` rendered `width` pixels wide with the export's aspect and framing.
+fn render_scaled(
+    proj: &Project,
+    eval: &EvaluatedFrame,
+    width: u32,
+    renderer: &mut dyn Renderer,
+) -> Result<((u32, u32), Vec<u8>), ExportError> {
+    let export = &proj.export;
+    let height = (u64::from(width) * u64::from(export.height) / u64::from(export.width.max(1)))
+        .max(1) as u32;
+    let camera = Camera {
+        scale: eval.camera.scale * f64::from(width) / f64::from(export.width.max(1)),
+        ..eval.camera.clone()
+    };
+    let (pixels, _) = render_image_stats((width, height), &eval.fractal, &camera, renderer, 0)?;
+    Ok(((width, height), pixels))
+}
+
+/// Export frame used as the poster: the one at `export.poster_time` when set, otherwise
+/// the candidate whose coarse render has the highest luminance variance, the earliest on
+/// a tie.
+pub fn poster_frame(proj: &Project, renderer: &mut dyn Renderer) -> Result<u32, ExportError> {
+    let last = proj.export.frame_count().saturating_sub(1);
+    if let Some(time) = proj.export.poster_time {
+        return Ok(frame_at(time, proj.export.fps).min(last));
+    }
+    let script = ScriptRunner::for_project(proj)?;
+    let mut best = (0, f32::NEG_INFINITY);
+    for frame in poster_candidates(last + 1, POSTER_CANDIDATES) {
+        let eval = poster_eval(proj, script.as_ref(), frame)?;
+        let (_, pixels) = render_scaled(proj, &eval, POSTER_SCAN_WIDTH, renderer)?;
+        let variance = FrameStats::of_rgba(&pixels).map_or(0.0, |s| s.variance);
+        if variance > best.1 {
+            best = (frame, variance);
+        }
+    }
+    Ok(best.0)
+}
+
+/// Base64 PNG of the poster frame at [`PROJECT_THUMBNAIL_WIDTH`], for
+/// [`Project::thumbnail`].
+pub fn project_thumbnail(
+    proj: &Project,
+    renderer: &mut dyn Renderer,
+) -> Result<String, ExportError> {
+    let proj = &proj.authored();
+    let frame = poster_frame(proj, renderer)?;
+    let script = ScriptRunner::for_project(proj)?;
+    let eval = poster_eval(proj, script.as_ref(), frame)?;
+    let (size, pixels) = render_scaled(proj, &eval, PROJECT_THUMBNAIL_WIDTH, renderer)?;
+    let mut png = Vec::new();
+    image::codecs::png::PngEncoder::new(&mut png).write_image(
+        &pixels,
+        size.0,
+        size.1,
+        image::ColorType::Rgba8,
+    )?;
+    Ok(STANDARD.encode(png))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Complex, CpuRenderer, ExportSettings, FractalKind, FractalParams};
+
+    #[test]
+    fn candidates_spread_over_the_frames() {
+        assert_eq!(poster_candidates(5, 8), vec![0, 1, 2, 3, 4]);
+        assert_eq!(poster_candidates(0, 8), Vec::<u32>::new());
+        assert_eq!(
+            poster_candidates(80, 8),
+            vec![5, 15, 25, 35, 45, 55, 65, 75]
+        );
+    }
+
+    #[test]
+    fn poster_is_the_most_detailed_frame_unless_chosen() {
+        // Zooms out from deep inside the main cardioid, a flat black frame, to the whole
+        // set by frame 8.
+        let mut proj = Project::new("poster")
+            .with_fractal(FractalParams::new(FractalKind::Mandelbrot).with_max_iter(80))
+            .with_camera(Camera::new(Complex::new(-0.1, 0.0), 40.0));
+        proj.export = ExportSettings::new(64, 48).with_fps(4).with_duration(4.0);
+        proj.anim.duration = 4.0;
+        proj.anim.kf_zoom.upsert(0.0, 5000.0);
+        proj.anim.kf_zoom.upsert(2.0, 20.0);
+        let frame = poster_frame(&proj, &mut CpuRenderer).unwrap();
+        assert!(frame >= 8, "picked frame {frame}");
+
+        proj.export.poster_time = Some(0.5);
+        assert_eq!(poster_frame(&proj, &mut CpuRenderer).unwrap(), 2);
+        proj.export.poster_time = Some(99.0);
+        assert_eq!(poster_frame(&proj, &mut CpuRenderer).unwrap(), 15);
+
+        let thumbnail = project_thumbnail(&proj, &mut CpuRenderer).unwrap();
+        let png = STANDARD.decode(thumbnail).unwrap();
+        let image = image::load_from_memory(&png).unwrap();
+        assert_eq!((image.width(), image.height()), (160, 120));
+    }
+}
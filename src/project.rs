@@ -0,0 +1,1371 @@
+//! Scene description: fractal parameters, camera, and the project file format.
+
+use std::{borrow::Cow, fs, ops::Range, path::Path, sync::Arc};
+
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+
+use crate::{
+    default_palette, repair_project, Animation, Annotation, BigComplex, BigFloat, CustomFormula,
+    ExportSettings, FrameScript, JuliaView, NonFinite, PaletteEqualizer, ParamLocks, Precision,
+    QualityProfile, ReferenceOrbit, SampleJitter,
+};
+
+/// Which renderer produces pixels. `Gpu` only exists with the `gpu` feature.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RenderBackend {
+    Cpu,
+    #[cfg(feature = "gpu")]
+    Gpu,
+}
+
+impl Default for RenderBackend {
+    fn default() -> Self {
+        RenderBackend::Cpu
+    }
+}
+
+impl RenderBackend {
+    pub fn label(&self) -> &'static str {
+        match self {
+            RenderBackend::Cpu => "CPU",
+            #[cfg(feature = "gpu")]
+            RenderBackend::Gpu => "GPU",
+        }
+    }
+}
+
+/// Iteration formula.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FractalKind {
+    Mandelbrot,
+    Julia,
+    BurningShip,
+    Multibrot,
+    /// The Mandelbar: `z` is conjugated before it is squared.
+    Tricorn,
+    /// Newton's method on `z^n - 1`, colored by the root each point converges to.
+    Newton,
+    /// Ushiki's Phoenix: `z² + c + p·z` of the step before, seeded by the pixel.
+    Phoenix,
+    /// `z²` with its real part made positive, then `+ c`.
+    Celtic,
+    /// `z²` with both parts made positive, then `+ c`.
+    Buffalo,
+    /// Lyapunov exponents of the logistic map, with rates `a` and `b` taken from the
+    /// pixel in the order [`FractalParams::lyapunov_sequence`] gives.
+    Lyapunov,
+    /// The formula typed into [`FractalParams::custom_formula`].
+    Custom,
+}
+
+/// Shape the orbit is measured against for trap coloring.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OrbitTrapKind {
+    Point,
+    Circle,
+    Cross,
+}
+
+/// How escaped (exterior) pixels are colored.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExteriorMode {
+    /// The escape-time palette.
+    #[default]
+    Palette,
+    /// [`FractalParams::exterior_color`].
+    Solid,
+    /// Fully transparent, for compositing; the orbit trap still tints it.
+    Transparent,
+}
+
+impl ExteriorMode {
+    pub const ALL: [ExteriorMode; 3] = [
+        ExteriorMode::Palette,
+        ExteriorMode::Solid,
+        ExteriorMode::Transparent,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExteriorMode::Palette => "Palette",
+            ExteriorMode::Solid => "Solid color",
+            ExteriorMode::Transparent => "Transparent",
+        }
+    }
+}
+
+/// What escaped points are placed in the palette by.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ColoringMode {
+    /// The smooth iteration count at escape.
+    #[default]
+    SmoothIteration,
+    /// The estimated distance to the set in pixels, which keeps filaments a hairline at
+    /// any zoom. Formulas without a derivative fall back to the iteration count; see
+    /// [`FractalParams::estimates_distance`].
+    DistanceEstimate,
+    /// The average of `sin(k·arg z)` over the orbit, which traces stripes along the
+    /// filaments; `k` is [`FractalParams::stripe_density`], and
+    /// [`FractalParams::stripe_mix`] blends them over the iteration count's colors.
+    StripeAverage,
+}
+
+impl ColoringMode {
+    pub const ALL: [ColoringMode; 3] = [
+        ColoringMode::SmoothIteration,
+        ColoringMode::DistanceEstimate,
+        ColoringMode::StripeAverage,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ColoringMode::SmoothIteration => "Iteration count",
+            ColoringMode::DistanceEstimate => "Distance estimate",
+            ColoringMode::StripeAverage => "Stripe average",
+        }
+    }
+}
+
+/// How interior points, which never escape, are colored.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum InteriorColoring {
+    /// The palette's color at its start, flat across the set; how projects before format
+    /// version 7 colored it.
+    Palette,
+    /// [`FractalParams::interior_color`].
+    #[default]
+    Solid,
+    /// The palette by the orbit's final `|z|`, in escape radii.
+    Magnitude,
+    /// The palette by the angle of the orbit's final `z`, once around per trip.
+    Argument,
+    /// The palette by the orbit's closest approach to the orbit trap, in escape radii.
+    /// Needs the trap enabled.
+    TrapDistance,
+}
+
+impl InteriorColoring {
+    pub const ALL: [InteriorColoring; 5] = [
+        InteriorColoring::Palette,
+        InteriorColoring::Solid,
+        InteriorColoring::Magnitude,
+        InteriorColoring::Argument,
+        InteriorColoring::TrapDistance,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            InteriorColoring::Palette => "Palette start",
+            InteriorColoring::Solid => "Solid color",
+            InteriorColoring::Magnitude => "Final |z|",
+            InteriorColoring::Argument => "Final angle",
+            InteriorColoring::TrapDistance => "Trap distance",
+        }
+    }
+}
+
+/// What the palette stop pickers show and edit. Palette colors go through exposure and
+/// gamma on their way to the screen, so a stored color rarely shows as picked.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PaletteEditing {
+    /// The stored colors.
+    #[default]
+    Stored,
+    /// The stored colors, each with a swatch of how it shows on screen.
+    Preview,
+    /// The colors as they show on screen, solved back through the tone curve when picked.
+    Output,
+}
+
+impl PaletteEditing {
+    pub const ALL: [PaletteEditing; 3] = [
+        PaletteEditing::Stored,
+        PaletteEditing::Preview,
+        PaletteEditing::Output,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PaletteEditing::Stored => "Stored colors",
+            PaletteEditing::Preview => "Stored + on-screen swatch",
+            PaletteEditing::Output => "On-screen colors",
+        }
+    }
+}
+
+/// How a palette stop dragged into a neighbor behaves.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StopDrag {
+    /// Passes the neighbor, which takes its place in the order.
+    #[default]
+    Swap,
+    /// Stops [`MIN_STOP_GAP`](crate::MIN_STOP_GAP) short of the neighbor.
+    Clamp,
+}
+
+impl StopDrag {
+    pub const ALL: [StopDrag; 2] = [StopDrag::Swap, StopDrag::Clamp];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            StopDrag::Swap => "Pass neighbors",
+            StopDrag::Clamp => "Stop at neighbors",
+        }
+    }
+}
+
+/// Orbit-trap coloring blended over the escape-time palette.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OrbitTrap {
+    pub enabled: bool,
+    pub kind: OrbitTrapKind,
+    pub radius: f32,
+    pub softness: f32,
+    pub color: [f32; 3],
+    pub point: Complex,
+}
+
+impl Default for OrbitTrap {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            kind: OrbitTrapKind::Point,
+            radius: 0.35,
+            softness: 5.0,
+            color: [1.0, 0.5, 0.3],
+            point: Complex { re: 0.0, im: 0.0 },
+        }
+    }
+}
+
+/// One color stop of a gradient palette.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PaletteStop {
+    /// Position along the gradient, 0..1.
+    pub pos: f32,
+    /// Linear RGB, 0..1 per channel.
+    pub color: [f32; 3],
+}
+
+impl PaletteStop {
+    pub fn new(pos: f32, color: [f32; 3]) -> Self {
+        Self { pos, color }
+    }
+}
+
+/// Everything that determines the image apart from framing.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FractalParams {
+    pub kind: FractalKind,
+    pub max_iter: u32,
+    /// Replace `max_iter` with the suggestion for the zoom depth whenever a frame renders.
+    #[serde(default)]
+    pub auto_iter: bool,
+    pub escape_radius: f32,
+    /// Exponent for [`FractalKind::Multibrot`]; root count for [`FractalKind::Newton`].
+    pub power: f32,
+    /// Constant for [`FractalKind::Julia`] and [`FractalKind::Phoenix`].
+    pub c: Complex,
+    /// Weight of the previous `z` for [`FractalKind::Phoenix`].
+    #[serde(default = "default_phoenix_p")]
+    pub phoenix_p: Complex,
+    /// Which rate drives each logistic-map step of [`FractalKind::Lyapunov`], repeating:
+    /// `A` the pixel's real part, `B` its imaginary part. Other characters are skipped.
+    #[serde(default = "default_lyapunov_sequence")]
+    pub lyapunov_sequence: String,
+    /// Logistic-map steps [`FractalKind::Lyapunov`] takes before it starts measuring,
+    /// so the exponent describes where the orbit settles.
+    #[serde(default = "default_lyapunov_warmup")]
+    pub lyapunov_warmup: u32,
+    /// Step of [`FractalKind::Custom`], in the language of [`crate::custom_formula`].
+    #[serde(default = "default_custom_formula")]
+    pub custom_formula: String,
+    /// Palette offset, 0..1.
+    pub palette_phase: f32,
+    /// Palette cycles per second added on top of the phase during playback and export.
+    #[serde(default)]
+    pub palette_cycle_speed: f32,
+    /// Smooth iterations one trip through the palette spans; smaller repeats the colors
+    /// more densely. Independent of `max_iter`, so raising it leaves colors in place.
+    #[serde(default = "default_palette_repeat")]
+    pub palette_repeat: f32,
+    /// Spreads each frame's iteration counts evenly over one trip through the palette,
+    /// in place of `palette_repeat`; see [`crate::equalize`].
+    #[serde(default)]
+    pub equalize: bool,
+    /// Burning Ship as it was first implemented, folding each iterate back into the first
+    /// quadrant. Only orbit traps tell the difference; kept for projects made before the
+    /// formula was corrected.
+    #[serde(default)]
+    pub legacy_burning_ship: bool,
+    pub exposure: f32,
+    pub gamma: f32,
+    pub palette: Vec<PaletteStop>,
+    pub orbit: OrbitTrap,
+    #[serde(default)]
+    pub coloring: ColoringMode,
+    /// Stripes per turn around the origin under [`ColoringMode::StripeAverage`], 1 to
+    /// [`MAX_STRIPE_DENSITY`]; whole, so the stripe function has no seam.
+    #[serde(default = "default_stripe_density")]
+    pub stripe_density: u32,
+    /// How much of the stripe average's color is mixed over the iteration count's, 0..1.
+    #[serde(default = "default_stripe_mix")]
+    pub stripe_mix: f32,
+    #[serde(default)]
+    pub exterior_mode: ExteriorMode,
+    /// Exterior color in [`ExteriorMode::Solid`], and what transparent exteriors are
+    /// composited over where the output has no alpha channel.
+    #[serde(default)]
+    pub exterior_color: [f32; 3],
+    #[serde(default)]
+    pub interior_coloring: InteriorColoring,
+    /// Interior color in [`InteriorColoring::Solid`], mixed in after exposure and gamma.
+    #[serde(default)]
+    pub interior_color: [f32; 3],
+    /// Flat bands each trip through the palette is split into ("dwell bands"); 0 keeps
+    /// the colors continuous.
+    #[serde(default)]
+    pub band_count: u32,
+    /// Draws one-pixel lines in `contour_color` where dwell bands meet.
+    #[serde(default)]
+    pub band_contours: bool,
+    /// Color of the band contours, mixed in after exposure and gamma.
+    #[serde(default)]
+    pub contour_color: [f32; 3],
+    /// Samples along each side of a pixel, up to [`crate::MAX_SUPERSAMPLE`]: 3 averages a
+    /// 3×3 grid of them. Exports honor it; the preview always takes one.
+    #[serde(default = "default_supersample")]
+    pub supersample: u32,
+    /// Sub-pixel pattern and seed of the frame being rendered; set per frame by exports.
+    #[serde(skip)]
+    pub sampling: SampleJitter,
+    /// Number format the CPU iterates in; set from [`Project::precision`] when a frame is
+    /// evaluated.
+    #[serde(skip)]
+    pub precision: Precision,
+    /// Reference orbit for [`Precision::Perturbation`], attached per frame by the
+    /// renderers unless the caller already attached one from an [`crate::OrbitCache`].
+    #[serde(skip)]
+    pub reference: Option<Arc<ReferenceOrbit>>,
+    /// The frame's [`PaletteEqualizer`] when `equalize` is on, attached per frame by the
+    /// renderers unless the caller already attached one, as exports do.
+    #[serde(skip)]
+    pub equalizer: Option<Arc<PaletteEqualizer>>,
+    /// `custom_formula` parsed, attached by the renderers unless the caller already
+    /// attached it from a [`FormulaCache`], as the app does.
+    #[serde(skip)]
+    pub compiled_formula: Option<Arc<CustomFormula>>,
+    /// `custom_formula` when it was found not to parse, so renderers do not parse it again
+    /// for every pixel.
+    #[serde(skip)]
+    pub unparsed_formula: Option<Arc<str>>,
+}
+
+fn default_palette_repeat() -> f32 {
+    800.0
+}
+
+fn default_supersample() -> u32 {
+    1
+}
+
+/// Largest [`FractalParams::stripe_density`].
+pub const MAX_STRIPE_DENSITY: u32 = 32;
+
+fn default_stripe_density() -> u32 {
+    5
+}
+
+fn default_stripe_mix() -> f32 {
+    1.0
+}
+
+fn default_phoenix_p() -> Complex {
+    Complex::new(-0.5, 0.0)
+}
+
+fn default_lyapunov_sequence() -> String {
+    "AB".into()
+}
+
+fn default_lyapunov_warmup() -> u32 {
+    50
+}
+
+fn default_custom_formula() -> String {
+    "sqr(z) + c".into()
+}
+
+impl Default for FractalParams {
+    fn default() -> Self {
+        Self {
+            kind: FractalKind::Mandelbrot,
+            max_iter: 800,
+            auto_iter: false,
+            escape_radius: 4.0,
+            power: 2.0,
+            c: Complex {
+                re: -0.8,
+                im: 0.156,
+            },
+            phoenix_p: default_phoenix_p(),
+            lyapunov_sequence: default_lyapunov_sequence(),
+            lyapunov_warmup: default_lyapunov_warmup(),
+            custom_formula: default_custom_formula(),
+            palette_phase: 0.0,
+            palette_cycle_speed: 0.0,
+            palette_repeat: default_palette_repeat(),
+            equalize: false,
+            legacy_burning_ship: false,
+            exposure: 1.0,
+            gamma: 2.2,
+            palette: default_palette(),
+            orbit: OrbitTrap::default(),
+            coloring: ColoringMode::default(),
+            stripe_density: default_stripe_density(),
+            stripe_mix: default_stripe_mix(),
+            exterior_mode: ExteriorMode::default(),
+            exterior_color: [0.0; 3],
+            interior_coloring: InteriorColoring::default(),
+            interior_color: [0.0; 3],
+            band_count: 0,
+            band_contours: false,
+            contour_color: [0.0; 3],
+            supersample: default_supersample(),
+            sampling: SampleJitter::default(),
+            precision: Precision::default(),
+            reference: None,
+            equalizer: None,
+            compiled_formula: None,
+            unparsed_formula: None,
+        }
+    }
+}
+
+impl FractalParams {
+    /// Default parameters for `kind`.
+    pub fn new(kind: FractalKind) -> Self {
+        let mut params = Self::default();
+        params.switch_kind(kind);
+        params
+    }
+
+    pub fn with_max_iter(mut self, max_iter: u32) -> Self {
+        self.max_iter = max_iter;
+        self
+    }
+
+    pub fn with_power(mut self, power: f32) -> Self {
+        self.power = power;
+        self
+    }
+
+    pub fn with_julia_c(mut self, c: Complex) -> Self {
+        self.c = c;
+        self
+    }
+
+    pub fn with_phoenix_p(mut self, p: Complex) -> Self {
+        self.phoenix_p = p;
+        self
+    }
+
+    pub fn with_lyapunov_sequence(mut self, sequence: impl Into<String>) -> Self {
+        self.lyapunov_sequence = sequence.into();
+        self
+    }
+
+    pub fn with_custom_formula(mut self, formula: impl Into<String>) -> Self {
+        self.custom_formula = formula.into();
+        self
+    }
+
+    pub fn with_palette(mut self, palette: Vec<PaletteStop>) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    pub fn with_palette_repeat(mut self, iterations: f32) -> Self {
+        self.palette_repeat = iterations;
+        self
+    }
+
+    pub fn with_equalized_palette(mut self) -> Self {
+        self.equalize = true;
+        self
+    }
+
+    pub fn with_palette_phase(mut self, phase: f32) -> Self {
+        self.palette_phase = phase;
+        self
+    }
+
+    pub fn with_palette_cycle_speed(mut self, cycles_per_second: f32) -> Self {
+        self.palette_cycle_speed = cycles_per_second;
+        self
+    }
+
+    pub fn with_exterior(mut self, mode: ExteriorMode, color: [f32; 3]) -> Self {
+        self.exterior_mode = mode;
+        self.exterior_color = color;
+        self
+    }
+
+    pub fn with_coloring(mut self, coloring: ColoringMode) -> Self {
+        self.coloring = coloring;
+        self
+    }
+
+    /// Stripe average coloring with `density` stripes per turn, mixed in by `mix`.
+    pub fn with_stripes(mut self, density: u32, mix: f32) -> Self {
+        self.coloring = ColoringMode::StripeAverage;
+        self.stripe_density = density;
+        self.stripe_mix = mix;
+        self
+    }
+
+    pub fn with_interior(mut self, coloring: InteriorColoring, color: [f32; 3]) -> Self {
+        self.interior_coloring = coloring;
+        self.interior_color = color;
+        self
+    }
+
+    pub fn with_dwell_bands(mut self, count: u32) -> Self {
+        self.band_count = count;
+        self
+    }
+
+    pub fn with_band_contours(mut self, color: [f32; 3]) -> Self {
+        self.band_contours = true;
+        self.contour_color = color;
+        self
+    }
+
+    pub fn with_supersample(mut self, side: u32) -> Self {
+        self.supersample = side;
+        self
+    }
+
+    /// Whether frames get contour lines between dwell bands.
+    pub fn has_band_contours(&self) -> bool {
+        self.band_count > 0 && self.band_contours
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Complex {
+    pub re: f32,
+    pub im: f32,
+}
+
+impl Complex {
+    pub const fn new(re: f32, im: f32) -> Self {
+        Self { re, im }
+    }
+}
+
+/// A complex number in double precision, for camera centers deeper than `f32` can place.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Complex64 {
+    #[serde(deserialize_with = "number_or_decimal")]
+    pub re: f64,
+    #[serde(deserialize_with = "number_or_decimal")]
+    pub im: f64,
+}
+
+impl Complex64 {
+    pub const fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    /// Rounded to single precision.
+    pub fn to_f32(self) -> Complex {
+        Complex::new(self.re as f32, self.im as f32)
+    }
+}
+
+impl From<Complex> for Complex64 {
+    fn from(c: Complex) -> Self {
+        Self::new(f64::from(c.re), f64::from(c.im))
+    }
+}
+
+/// Framing of the complex plane. Center and scale are `f64`; files written when they were
+/// `f32` read the same. Files hold the center as decimal strings with every digit of
+/// [`Camera::deep_center`], so deep zooms survive a save; see [`CameraFile`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "CameraFile", into = "CameraFile")]
+pub struct Camera {
+    /// Complex-plane point at the center of the view.
+    pub center: Complex64,
+    /// Pixels per unit (zoom).
+    pub scale: f64,
+    /// Radians.
+    pub rotation: f32,
+    /// The center to more digits than `f64` holds, once navigation or a file has placed
+    /// it finer. Ignored unless it rounds to `center`, so setting `center` alone moves the
+    /// view.
+    pub deep_center: Option<BigComplex>,
+}
+
+/// A [`Camera`] as files hold it.
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Camera", deny_unknown_fields)]
+struct CameraFile {
+    center: CenterFile,
+    #[serde(deserialize_with = "null_as_nan")]
+    scale: f64,
+    rotation: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CenterFile {
+    re: Coordinate,
+    im: Coordinate,
+}
+
+/// One coordinate of a center: a decimal string since version 6, a number before.
+#[derive(Debug, Clone, PartialEq)]
+enum Coordinate {
+    Number(f64),
+    Decimal(String),
+}
+
+impl Coordinate {
+    /// The value rounded to `f64`, and exactly at `frac_limbs` if the decimal holds more
+    /// than the `f64` does.
+    fn read(&self, frac_limbs: usize) -> Result<(f64, Option<BigFloat>), String> {
+        let text = match self {
+            Coordinate::Number(x) => return Ok((*x, None)),
+            Coordinate::Decimal(text) => text,
+        };
+        let rounded = text
+            .trim()
+            .parse::<f64>()
+            .map_err(|_| format!("center coordinate \"{text}\" is not a number"))?;
+        let exact = BigFloat::parse_decimal(text, frac_limbs)
+            .filter(|exact| *exact != BigFloat::from_f64(rounded, frac_limbs));
+        Ok(match exact {
+            Some(exact) => (exact.to_f64(), Some(exact)),
+            None => (rounded, None),
+        })
+    }
+
+    /// Digits after the point, exponent included, for picking a precision that holds
+    /// them all.
+    fn frac_digits(&self) -> i32 {
+        let Coordinate::Decimal(text) = self else {
+            return 0;
+        };
+        let (mantissa, exponent) = text.split_once(['e', 'E']).unwrap_or((text, "0"));
+        let frac = mantissa.split_once('.').map_or(0, |(_, frac)| frac.len());
+        (frac as i32).saturating_sub(exponent.trim().parse::<i32>().unwrap_or(0))
+    }
+}
+
+impl Serialize for Coordinate {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Coordinate::Number(x) => s.serialize_f64(*x),
+            Coordinate::Decimal(text) => s.serialize_str(text),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Coordinate {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        struct Visitor;
+        impl serde::de::Visitor<'_> for Visitor {
+            type Value = Coordinate;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a decimal string or a number")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Coordinate, E> {
+                Ok(Coordinate::Decimal(v.to_owned()))
+            }
+
+            fn visit_f64<E: serde::de::Error>(self, v: f64) -> Result<Coordinate, E> {
+                Ok(Coordinate::Number(v))
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, v: i64) -> Result<Coordinate, E> {
+                Ok(Coordinate::Number(v as f64))
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, v: u64) -> Result<Coordinate, E> {
+                Ok(Coordinate::Number(v as f64))
+            }
+
+            // JSON's `null` for NaN, for `repair_project` to reset.
+            fn visit_unit<E: serde::de::Error>(self) -> Result<Coordinate, E> {
+                Ok(Coordinate::Number(f64::NAN))
+            }
+        }
+        d.deserialize_any(Visitor)
+    }
+}
+
+impl TryFrom<CameraFile> for Camera {
+    type Error = String;
+
+    fn try_from(file: CameraFile) -> Result<Self, String> {
+        let mut camera = Self {
+            rotation: file.rotation,
+            ..Self::new(Complex64::default(), file.scale)
+        };
+        camera.set_center(&file.center.re, &file.center.im)?;
+        Ok(camera)
+    }
+}
+
+impl From<Camera> for CameraFile {
+    fn from(camera: Camera) -> Self {
+        let deep = camera
+            .deep_center
+            .as_ref()
+            .filter(|deep| deep.to_complex64() == camera.center);
+        // A component the `f64` holds exactly is written as short as the `f64` prints.
+        let coordinate = |rounded: f64, deep: Option<&BigFloat>| {
+            Coordinate::Decimal(match deep {
+                Some(deep) if *deep != BigFloat::from_f64(rounded, deep.frac_limbs()) => {
+                    deep.to_decimal()
+                }
+                _ => rounded.to_string(),
+            })
+        };
+        Self {
+            center: CenterFile {
+                re: coordinate(camera.center.re, deep.map(|deep| &deep.re)),
+                im: coordinate(camera.center.im, deep.map(|deep| &deep.im)),
+            },
+            scale: camera.scale,
+            rotation: camera.rotation,
+        }
+    }
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            center: Complex64 { re: -0.5, im: 0.0 },
+            scale: 300.0,
+            rotation: 0.0,
+            deep_center: None,
+        }
+    }
+}
+
+impl Camera {
+    pub fn new(center: impl Into<Complex64>, scale: f64) -> Self {
+        Self {
+            center: center.into(),
+            scale,
+            rotation: 0.0,
+            deep_center: None,
+        }
+    }
+
+    /// The center at the precision this scale needs: `deep_center` while it is current,
+    /// else `center`.
+    pub fn exact_center(&self) -> BigComplex {
+        let limbs = BigFloat::limbs_for_scale(self.scale);
+        match &self.deep_center {
+            Some(deep) if deep.to_complex64() == self.center => {
+                deep.with_frac_limbs(limbs.max(deep.re.frac_limbs()).max(deep.im.frac_limbs()))
+            }
+            _ => BigComplex::from_complex64(self.center, limbs),
+        }
+    }
+
+    /// Moves the center by `offset` without rounding it to `f64`, so pans a fraction of a
+    /// pixel wide still move the view at any depth.
+    pub fn translate(&mut self, offset: Complex64) {
+        let limbs = BigFloat::limbs_for_scale(self.scale);
+        let deep = &self.exact_center() + &BigComplex::from_complex64(offset, limbs);
+        self.center = deep.to_complex64();
+        self.deep_center = Some(deep);
+    }
+
+    /// How far `other`'s center is from this one's, at full precision before rounding.
+    pub fn offset_to(&self, other: &Camera) -> Complex64 {
+        (&other.exact_center() - &self.exact_center()).to_complex64()
+    }
+
+    /// Offset of pixel (`x`, `y`) of a `width`×`height` view from the center, in the
+    /// complex plane.
+    pub fn pixel_offset(&self, x: f32, y: f32, width: f32, height: f32) -> Complex64 {
+        let u = f64::from(x - width / 2.0);
+        let v = f64::from(y - height / 2.0);
+        let (sinr, cosr) = f64::from(self.rotation).sin_cos();
+        Complex64 {
+            re: (u * cosr - v * sinr) / self.scale,
+            im: (u * sinr + v * cosr) / self.scale,
+        }
+    }
+
+    /// A camera centered on the exact sum of `terms`, for points given to more digits than
+    /// one `f64` holds.
+    pub fn at_sum(terms: &[Complex64], scale: f64) -> Self {
+        let mut center = BigComplex::default();
+        for &term in terms {
+            let magnitude = term.re.abs().max(term.im.abs());
+            if magnitude > 0.0 {
+                let limbs = BigFloat::limbs_for_scale(scale.max(1.0 / magnitude));
+                center = &center + &BigComplex::from_complex64(term, limbs);
+            }
+        }
+        Self {
+            center: center.to_complex64(),
+            deep_center: Some(center),
+            ..Self::new(Complex64::default(), scale)
+        }
+    }
+
+    /// Centers on the decimals `re` and `im`, such as `-0.7436438870371587047521`, to
+    /// every digit they give or the scale needs, whichever is more.
+    pub fn set_decimal_center(&mut self, re: &str, im: &str) -> Result<(), String> {
+        self.set_center(
+            &Coordinate::Decimal(re.to_owned()),
+            &Coordinate::Decimal(im.to_owned()),
+        )
+    }
+
+    fn set_center(&mut self, re: &Coordinate, im: &Coordinate) -> Result<(), String> {
+        let digits = re.frac_digits().max(im.frac_digits()).clamp(0, 1000);
+        let limbs = BigFloat::limbs_for_scale(self.scale)
+            .max(BigFloat::limbs_for_scale(10f64.powi(digits)));
+        let ((re, exact_re), (im, exact_im)) = (re.read(limbs)?, im.read(limbs)?);
+        self.center = Complex64::new(re, im);
+        self.deep_center = (exact_re.is_some() || exact_im.is_some()).then(|| {
+            BigComplex::new(
+                exact_re.unwrap_or_else(|| BigFloat::from_f64(re, limbs)),
+                exact_im.unwrap_or_else(|| BigFloat::from_f64(im, limbs)),
+            )
+        });
+        Ok(())
+    }
+
+    pub fn with_rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Maps pixel (`x`, `y`) of a `width`×`height` view to its complex-plane coordinate.
+    pub fn pixel_to_plane(&self, x: f32, y: f32, width: f32, height: f32) -> Complex64 {
+        let offset = self.pixel_offset(x, y, width, height);
+        Complex64 {
+            re: offset.re + self.center.re,
+            im: offset.im + self.center.im,
+        }
+    }
+
+    /// Inverse of [`Camera::pixel_to_plane`].
+    pub fn plane_to_pixel(&self, p: impl Into<Complex64>, width: f32, height: f32) -> (f32, f32) {
+        let p = p.into();
+        let dx = (p.re - self.center.re) * self.scale;
+        let dy = (p.im - self.center.im) * self.scale;
+        let (sinr, cosr) = f64::from(self.rotation).sin_cos();
+        (
+            (dx * cosr + dy * sinr) as f32 + width / 2.0,
+            (-dx * sinr + dy * cosr) as f32 + height / 2.0,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RepeatingSpot {
+    pub center: Complex,
+    pub rotation: f32,
+    pub start_scale: f32,
+}
+
+/// Location inside Seahorse Valley that exhibits near-perfect self similarity.
+pub const SEAHORSE_REPEAT_SPOT: RepeatingSpot = RepeatingSpot {
+    center: Complex {
+        re: -0.743_643_9,
+        im: 0.131_825_91,
+    },
+    rotation: 0.0,
+    start_scale: 3_200.0,
+};
+
+/// A Misiurewicz point in Seahorse Valley, two pixels from [`SEAHORSE_REPEAT_SPOT`] at its
+/// start scale. It lies on the boundary, so there is detail around it at every depth; as
+/// the sum of these terms it is placed to 64 digits, enough for a scale of about 1e60.
+/// See [`Camera::at_sum`].
+pub const SEAHORSE_DEEP_POINT: [Complex64; 4] = [
+    Complex64::new(-0.743_291_890_852_430_2, 0.131_240_552_308_797_6),
+    Complex64::new(-1.029_391_230_065_607_8e-17, -4.144_978_732_971_26e-18),
+    Complex64::new(-2.231_571_060_133_014e-34, -2.682_047_460_654_852_6e-34),
+    Complex64::new(-1.189_520_394_946_397e-50, -1.691_135_575_270_044e-50),
+];
+
+/// Newest project file format this build reads and the one it writes. Version 2 corrected
+/// the Burning Ship formula; older Burning Ship projects with an orbit trap load with
+/// [`FractalParams::legacy_burning_ship`] set so they keep their look. Version 3 spread the
+/// palette over [`FractalParams::palette_repeat`] iterations instead of `max_iter`; older
+/// projects load with the repeat set to their `max_iter`. Version 4 moved the export's
+/// `tile_size` and `sample_pattern` into its [`QualityProfile`]. Version 5 made the
+/// built-in profiles tile automatically; older ones at their fixed 2048 px tiles load set
+/// to auto. Version 6 writes the camera center as decimal strings, to every digit a deep
+/// zoom places it to; older files hold numbers, which still read. Version 7 colors the
+/// interior solid black by default; older projects load with
+/// [`InteriorColoring::Palette`], the flat palette color they had.
+pub const PROJECT_VERSION: u32 = 7;
+
+/// Version of files written before the format was versioned.
+fn first_version() -> u32 {
+    1
+}
+
+/// A complete scene: what is rendered, how it animates, and how it is exported.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Project {
+    /// File format version; files without one predate versioning and read as version 1.
+    #[serde(default = "first_version")]
+    pub version: u32,
+    pub name: String,
+    pub fractal: FractalParams,
+    pub camera: Camera,
+    pub anim: Animation,
+    pub export: ExportSettings,
+    pub render_backend: RenderBackend,
+    /// Number format of the CPU renderer; the GPU always iterates in `f32`.
+    #[serde(default)]
+    pub precision: Precision,
+    /// Quality the preview renders at; exports use [`ExportSettings::quality`].
+    #[serde(default)]
+    pub quality: QualityProfile,
+    #[serde(default)]
+    pub locks: ParamLocks,
+    /// Runs after keyframe sampling in both preview and export.
+    #[serde(default)]
+    pub script: FrameScript,
+    #[serde(default)]
+    pub palette_editing: PaletteEditing,
+    /// What dragging a palette stop into its neighbor does.
+    #[serde(default)]
+    pub palette_stop_drag: StopDrag,
+    /// Base64 PNG of the poster frame, refreshed on every save from the app; see
+    /// [`project_thumbnail`](crate::project_thumbnail).
+    #[serde(default)]
+    pub thumbnail: Option<String>,
+    /// Every random choice in the project is drawn from this; see [`seeded_rng`](crate::seeded_rng).
+    #[serde(default)]
+    pub seed: u64,
+    /// Text drawn over the preview and into exports, in drawing order.
+    #[serde(default)]
+    pub annotations: Vec<Annotation>,
+    /// Second viewport showing the Julia set for a point of the main view.
+    #[serde(default)]
+    pub julia_view: JuliaView,
+}
+
+impl Default for Project {
+    fn default() -> Self {
+        Self {
+            version: PROJECT_VERSION,
+            name: "Untitled".into(),
+            fractal: Default::default(),
+            camera: Default::default(),
+            anim: Animation::default(),
+            export: ExportSettings::default(),
+            render_backend: RenderBackend::default(),
+            precision: Precision::default(),
+            quality: QualityProfile::default(),
+            locks: ParamLocks::default(),
+            script: FrameScript::default(),
+            palette_editing: PaletteEditing::default(),
+            palette_stop_drag: StopDrag::default(),
+            thumbnail: None,
+            seed: 0,
+            annotations: Vec::new(),
+            julia_view: JuliaView::default(),
+        }
+    }
+}
+
+impl Project {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_fractal(mut self, fractal: FractalParams) -> Self {
+        self.fractal = fractal;
+        self
+    }
+
+    pub fn with_camera(mut self, camera: Camera) -> Self {
+        self.camera = camera;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// The project without session state: playhead at 0, paused, no key selected. Two
+    /// projects that only differ in where the preview was left are equal here.
+    pub fn authored(&self) -> Project {
+        let mut proj = self.clone();
+        proj.anim.t = 0.0;
+        proj.anim.playing = false;
+        proj.anim.selection = None;
+        proj
+    }
+}
+
+// ------------------------- Project IO -------------------------
+
+/// 1-based line and column in a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextPosition {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl TextPosition {
+    /// Position of byte `offset` in `text`, counting columns in characters.
+    pub fn of_offset(text: &str, offset: usize) -> Self {
+        let mut offset = offset.min(text.len());
+        while !text.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let before = &text[..offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Self {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+impl std::fmt::Display for TextPosition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// Why a project or palette file could not be read or written.
+#[derive(thiserror::Error, Debug)]
+pub enum ProjectError {
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    #[error("{at}: {message}")]
+    Json { at: TextPosition, message: String },
+    #[error("{}{message}", at.map(|at| format!("{at}: ")).unwrap_or_default())]
+    Toml {
+        at: Option<TextPosition>,
+        /// Byte range of the offending key or value.
+        span: Option<Range<usize>>,
+        message: String,
+    },
+    #[error(
+        "written by a newer version (format {found}, this build reads up to {PROJECT_VERSION})"
+    )]
+    UnsupportedVersion { found: u32 },
+    #[error("unknown file type {0:?}; use .json, .mahproj or .toml")]
+    UnknownExtension(String),
+}
+
+impl ProjectError {
+    /// The error prefixed with the file it came from, as `path:line:column — message` when
+    /// the position is known and `path: message` otherwise.
+    pub fn at(&self, path: &Path) -> String {
+        let position = match self {
+            ProjectError::Json { at, message }
+            | ProjectError::Toml {
+                at: Some(at),
+                message,
+                ..
+            } => Some((at, message)),
+            _ => None,
+        };
+        match position {
+            Some((at, message)) => {
+                format!("{}:{}:{} — {message}", path.display(), at.line, at.column)
+            }
+            None => format!("{}: {self}", path.display()),
+        }
+    }
+
+    pub(crate) fn json(e: serde_json::Error) -> Self {
+        let at = TextPosition {
+            line: e.line(),
+            column: e.column(),
+        };
+        let message = e.to_string();
+        let message = message
+            .strip_suffix(&format!(" at line {} column {}", at.line, at.column))
+            .unwrap_or(&message);
+        ProjectError::Json {
+            at,
+            message: suggest_field(message),
+        }
+    }
+
+    pub(crate) fn toml(e: toml::de::Error, text: &str) -> Self {
+        ProjectError::Toml {
+            at: e
+                .span()
+                .map(|span| TextPosition::of_offset(text, span.start)),
+            span: e.span(),
+            message: suggest_field(e.message().trim_end()),
+        }
+    }
+}
+
+/// Rewrites serde's "unknown field `colour`, expected one of ..." as "unknown field
+/// `colour`, did you mean `color`?" when one of the expected names is a likely typo fix.
+fn suggest_field(message: &str) -> String {
+    let Some((unknown, expected)) = message.split_once(", expected ") else {
+        return message.to_string();
+    };
+    if !(unknown.starts_with("unknown field `") || unknown.starts_with("unknown variant `")) {
+        return message.to_string();
+    }
+    let Some(name) = unknown.split('`').nth(1) else {
+        return message.to_string();
+    };
+    let best = expected
+        .split('`')
+        .skip(1)
+        .step_by(2)
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .min();
+    match best {
+        Some((distance, candidate)) if distance <= (name.chars().count() / 3).max(1) => {
+            format!("{unknown}, did you mean `{candidate}`?")
+        }
+        _ => message.to_string(),
+    }
+}
+
+/// Levenshtein distance in characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitute = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitute.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Project file formats, chosen by extension.
+enum ProjectFormat {
+    Json,
+    Toml,
+}
+
+impl ProjectFormat {
+    fn of(path: &Path) -> Result<Self, ProjectError> {
+        match path.extension().and_then(|ext| ext.to_str()).unwrap_or("") {
+            "json" => Ok(ProjectFormat::Json),
+            "mahproj" | "toml" => Ok(ProjectFormat::Toml),
+            other => Err(ProjectError::UnknownExtension(other.to_string())),
+        }
+    }
+
+    fn parse<T: DeserializeOwned>(&self, data: &str) -> Result<T, ProjectError> {
+        match self {
+            ProjectFormat::Json => serde_json::from_str(data).map_err(ProjectError::json),
+            ProjectFormat::Toml => toml::from_str(data).map_err(|e| ProjectError::toml(e, data)),
+        }
+    }
+}
+
+/// Reads the `null` that NaN and infinity are written to JSON as, for [`repair_project`]
+/// to reset.
+fn null_as_nan<'de, D: Deserializer<'de>>(d: D) -> Result<f64, D::Error> {
+    Ok(Option::<f64>::deserialize(d)?.unwrap_or(f64::NAN))
+}
+
+/// Reads a number, `null` as [`null_as_nan`] does, or a decimal string like those of a
+/// [`Camera`] center, rounded to `f64`.
+fn number_or_decimal<'de, D: Deserializer<'de>>(d: D) -> Result<f64, D::Error> {
+    match Coordinate::deserialize(d)? {
+        Coordinate::Number(x) => Ok(x),
+        Coordinate::Decimal(text) => text
+            .trim()
+            .parse()
+            .map_err(|_| serde::de::Error::custom(format!("\"{text}\" is not a number"))),
+    }
+}
+
+/// JSON has no NaN or infinity, so serde writes them as `null`, which no `f32` field
+/// reads back. Reads such a file with each of those `null`s made infinite, for
+/// [`repair_project`] to reset.
+fn parse_json_nulls<T: DeserializeOwned>(data: &str) -> Result<T, ProjectError> {
+    let mut data = Cow::Borrowed(data);
+    // One null per pass; more than a project has floats means something else is wrong.
+    for _ in 0..10_000 {
+        let err = match serde_json::from_str(&data) {
+            Ok(parsed) => return Ok(parsed),
+            Err(err) => err,
+        };
+        if !err
+            .to_string()
+            .starts_with("invalid type: null, expected f32")
+        {
+            return Err(ProjectError::json(err));
+        }
+        let line_start: usize = data
+            .split_inclusive('\n')
+            .take(err.line() - 1)
+            .map(str::len)
+            .sum();
+        let line = &data[line_start..];
+        let end = line.len().min(err.column());
+        let Some(null) = line.get(..end).and_then(|before| before.rfind("null")) else {
+            return Err(ProjectError::json(err));
+        };
+        let at = line_start + null;
+        // Past the f32 range, so it reads as infinity.
+        data.to_mut().replace_range(at..at + 4, "1e300");
+    }
+    ProjectFormat::Json.parse(&data)
+}
+
+/// Writes TOML for `.mahproj`/`.toml` paths and JSON for `.json`. NaN and infinite
+/// values are reset first, so the file always loads again.
+pub fn save_project(p: &Project, path: &Path) -> Result<(), ProjectError> {
+    let mut p = p.clone();
+    repair_project(&mut p);
+    let data = match ProjectFormat::of(path)? {
+        ProjectFormat::Toml => toml::to_string_pretty(&p).map_err(std::io::Error::other)?,
+        ProjectFormat::Json => serde_json::to_string_pretty(&p).map_err(std::io::Error::from)?,
+    };
+    fs::write(path, data)?;
+    Ok(())
+}
+
+/// Reads a project file, picking JSON or TOML from the extension.
+pub fn load_project(path: &Path) -> Result<Project, ProjectError> {
+    load_project_repaired(path).map(|(proj, _)| proj)
+}
+
+/// [`load_project`], also returning the NaN and infinite values it had to repair.
+pub fn load_project_repaired(path: &Path) -> Result<(Project, Vec<NonFinite>), ProjectError> {
+    let format = ProjectFormat::of(path)?;
+    let data = fs::read_to_string(path)?;
+    parse_project(&data, format)
+}
+
+/// Parses project text; `path` only selects JSON or TOML by its extension.
+pub fn project_from_str(data: &str, path: &Path) -> Result<Project, ProjectError> {
+    project_from_str_repaired(data, path).map(|(proj, _)| proj)
+}
+
+/// [`project_from_str`], also returning the NaN and infinite values it had to repair.
+pub fn project_from_str_repaired(
+    data: &str,
+    path: &Path,
+) -> Result<(Project, Vec<NonFinite>), ProjectError> {
+    parse_project(data, ProjectFormat::of(path)?)
+}
+
+fn parse_project(
+    data: &str,
+    format: ProjectFormat,
+) -> Result<(Project, Vec<NonFinite>), ProjectError> {
+    /// Reads only the version, so newer files are reported as such rather than as
+    /// having unknown fields.
+    #[derive(Deserialize)]
+    struct VersionProbe {
+        version: Option<u32>,
+    }
+    if let Ok(VersionProbe {
+        version: Some(found),
+    }) = format.parse(data)
+    {
+        if found > PROJECT_VERSION {
+            return Err(ProjectError::UnsupportedVersion { found });
+        }
+    }
+    let mut proj: Project = match format {
+        ProjectFormat::Json => parse_json_nulls(data)?,
+        ProjectFormat::Toml => format.parse(data)?,
+    };
+    proj.upgrade();
+    let repairs = repair_project(&mut proj);
+    Ok((proj, repairs))
+}
+
+impl Project {
+    /// Brings a project read from an older file format up to [`PROJECT_VERSION`].
+    fn upgrade(&mut self) {
+        if self.version < 2
+            && self.fractal.kind == FractalKind::BurningShip
+            && self.fractal.orbit.enabled
+        {
+            self.fractal.legacy_burning_ship = true;
+        }
+        if self.version < 3 {
+            self.fractal.palette_repeat = self.fractal.max_iter as f32;
+        }
+        if self.version < 4 {
+            let export = &mut self.export;
+            let quality = QualityProfile::final_quality()
+                .with_tile_size(export.legacy_tile_size.unwrap_or(2048))
+                .with_sample_pattern(export.legacy_sample_pattern.unwrap_or_default());
+            export.quality = if quality == QualityProfile::final_quality().with_tile_size(2048) {
+                quality
+            } else {
+                quality.with_name("Custom")
+            };
+        }
+        if self.version < 5 {
+            for quality in [&mut self.quality, &mut self.export.quality] {
+                if quality.tile_size == 2048 && QualityProfile::named(&quality.name).is_some() {
+                    quality.tile_size = 0;
+                }
+            }
+        }
+        if self.version < 7 {
+            self.fractal.interior_coloring = InteriorColoring::Palette;
+        }
+        self.export.legacy_tile_size = None;
+        self.export.legacy_sample_pattern = None;
+        self.version = PROJECT_VERSION;
+    }
+}
+
+/// Writes a palette as `.ahpal` (JSON list of stops).
+pub fn save_palette(stops: &[PaletteStop], path: &Path) -> Result<(), ProjectError> {
+    let data = serde_json::to_string_pretty(stops).map_err(std::io::Error::from)?;
+    fs::write(path, data)?;
+    Ok(())
+}
+
+/// Reads an `.ahpal` palette.
+pub fn load_palette(path: &Path) -> Result<Vec<PaletteStop>, ProjectError> {
+    let data = fs::read_to_string(path)?;
+    serde_json::from_str(&data).map_err(ProjectError::json)
+}
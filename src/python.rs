@@ -0,0 +1,550 @@
+//! `matterhorn` Python module, built with the `python` feature (see `pyproject.toml`).
+//!
+//! `Project` owns its `fractal`, `camera` and `export_settings` objects, so
+//! `proj.fractal.max_iter = 5000` edits the project in place. Renders and exports run with
+//! the GIL released.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{atomic::Ordering as AtomicOrdering, Arc},
+    thread,
+    time::Duration,
+};
+
+use pyo3::{
+    exceptions::{PyIOError, PyKeyboardInterrupt, PyRuntimeError, PyValueError},
+    prelude::*,
+    types::PyByteArray,
+};
+
+use crate::{
+    check_frame_size, evaluate_frame, export_video_blocking, frame_at, load_project,
+    project_from_str, render_image, save_project, Camera, Complex, Complex64, CpuRenderer,
+    EvalTarget, ExportError, ExportProgress, ExportSettings, FractalKind, FractalParams, Project,
+    ProjectError, ScriptRunner, VideoCodec,
+};
+
+/// How often `Project.export` reports progress and checks for Ctrl+C.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+fn value_error(e: impl ToString) -> PyErr {
+    PyValueError::new_err(e.to_string())
+}
+
+/// File problems raise `OSError`; everything else about the contents `ValueError`.
+fn project_error(e: ProjectError, path: &Path) -> PyErr {
+    match e {
+        ProjectError::Io(_) => PyIOError::new_err(e.at(path)),
+        _ => value_error(e.at(path)),
+    }
+}
+
+/// Serde names, e.g. `burning_ship`, so Python and project files spell kinds alike.
+fn enum_name<T: serde::Serialize>(value: T) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_owned))
+        .unwrap_or_default()
+}
+
+fn parse_enum<T: serde::de::DeserializeOwned>(name: &str, what: &str) -> PyResult<T> {
+    serde_json::from_value(serde_json::Value::String(name.to_owned()))
+        .map_err(|_| value_error(format!("unknown {what} {name:?}")))
+}
+
+// ------------------------- Fractal -------------------------
+
+/// Fractal formula, iteration and color parameters.
+#[pyclass(name = "Fractal", module = "matterhorn")]
+#[derive(Clone)]
+pub struct PyFractal(FractalParams);
+
+#[pymethods]
+impl PyFractal {
+    /// Formula name: `mandelbrot`, `julia`, `burning_ship` or `multibrot`.
+    #[getter]
+    fn kind(&self) -> String {
+        enum_name(self.0.kind)
+    }
+
+    #[setter]
+    fn set_kind(&mut self, kind: &str) -> PyResult<()> {
+        self.0.kind = parse_enum::<FractalKind>(kind, "fractal kind")?;
+        Ok(())
+    }
+
+    #[getter]
+    fn max_iter(&self) -> u32 {
+        self.0.max_iter
+    }
+
+    #[setter]
+    fn set_max_iter(&mut self, max_iter: u32) -> PyResult<()> {
+        if max_iter == 0 {
+            return Err(value_error("max_iter must be at least 1"));
+        }
+        self.0.max_iter = max_iter;
+        Ok(())
+    }
+
+    #[getter]
+    fn escape_radius(&self) -> f32 {
+        self.0.escape_radius
+    }
+
+    #[setter]
+    fn set_escape_radius(&mut self, radius: f32) {
+        self.0.escape_radius = radius;
+    }
+
+    /// Exponent for `multibrot`.
+    #[getter]
+    fn power(&self) -> f32 {
+        self.0.power
+    }
+
+    #[setter]
+    fn set_power(&mut self, power: f32) {
+        self.0.power = power;
+    }
+
+    /// Julia constant as `(re, im)`.
+    #[getter]
+    fn c(&self) -> (f32, f32) {
+        (self.0.c.re, self.0.c.im)
+    }
+
+    #[setter]
+    fn set_c(&mut self, c: (f32, f32)) {
+        self.0.c = Complex::new(c.0, c.1);
+    }
+
+    #[getter]
+    fn palette_phase(&self) -> f32 {
+        self.0.palette_phase
+    }
+
+    #[setter]
+    fn set_palette_phase(&mut self, phase: f32) {
+        self.0.palette_phase = phase;
+    }
+
+    /// Palette cycles per second added to the phase over time.
+    #[getter]
+    fn palette_cycle_speed(&self) -> f32 {
+        self.0.palette_cycle_speed
+    }
+
+    #[setter]
+    fn set_palette_cycle_speed(&mut self, cycles_per_second: f32) {
+        self.0.palette_cycle_speed = cycles_per_second;
+    }
+
+    /// Smooth iterations one trip through the palette spans.
+    #[getter]
+    fn palette_repeat(&self) -> f32 {
+        self.0.palette_repeat
+    }
+
+    #[setter]
+    fn set_palette_repeat(&mut self, iterations: f32) {
+        self.0.palette_repeat = iterations;
+    }
+
+    #[getter]
+    fn exposure(&self) -> f32 {
+        self.0.exposure
+    }
+
+    #[setter]
+    fn set_exposure(&mut self, exposure: f32) {
+        self.0.exposure = exposure;
+    }
+
+    #[getter]
+    fn gamma(&self) -> f32 {
+        self.0.gamma
+    }
+
+    #[setter]
+    fn set_gamma(&mut self, gamma: f32) {
+        self.0.gamma = gamma;
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Fractal(kind={:?}, max_iter={})",
+            self.kind(),
+            self.0.max_iter
+        )
+    }
+}
+
+// ------------------------- Camera -------------------------
+
+/// Framing of the complex plane.
+#[pyclass(name = "Camera", module = "matterhorn")]
+#[derive(Clone)]
+pub struct PyCamera(Camera);
+
+#[pymethods]
+impl PyCamera {
+    /// View center as `(re, im)`.
+    #[getter]
+    fn center(&self) -> (f64, f64) {
+        (self.0.center.re, self.0.center.im)
+    }
+
+    #[setter]
+    fn set_center(&mut self, center: (f64, f64)) {
+        self.0.center = Complex64::new(center.0, center.1);
+    }
+
+    /// Pixels per unit.
+    #[getter]
+    fn scale(&self) -> f64 {
+        self.0.scale
+    }
+
+    #[setter]
+    fn set_scale(&mut self, scale: f64) -> PyResult<()> {
+        if !(scale > 0.0 && scale.is_finite()) {
+            return Err(value_error("scale must be positive"));
+        }
+        self.0.scale = scale;
+        Ok(())
+    }
+
+    /// Radians.
+    #[getter]
+    fn rotation(&self) -> f32 {
+        self.0.rotation
+    }
+
+    #[setter]
+    fn set_rotation(&mut self, rotation: f32) {
+        self.0.rotation = rotation;
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Camera(center=({}, {}), scale={}, rotation={})",
+            self.0.center.re, self.0.center.im, self.0.scale, self.0.rotation
+        )
+    }
+}
+
+// ------------------------- Export settings -------------------------
+
+/// Video size, timing and encoding used by `Project.export`.
+#[pyclass(name = "ExportSettings", module = "matterhorn")]
+#[derive(Clone)]
+pub struct PyExportSettings(ExportSettings);
+
+#[pymethods]
+impl PyExportSettings {
+    #[getter]
+    fn width(&self) -> u32 {
+        self.0.width
+    }
+
+    #[setter]
+    fn set_width(&mut self, width: u32) {
+        self.0.width = width;
+    }
+
+    #[getter]
+    fn height(&self) -> u32 {
+        self.0.height
+    }
+
+    #[setter]
+    fn set_height(&mut self, height: u32) {
+        self.0.height = height;
+    }
+
+    #[getter]
+    fn fps(&self) -> u32 {
+        self.0.fps
+    }
+
+    #[setter]
+    fn set_fps(&mut self, fps: u32) {
+        self.0.fps = fps;
+    }
+
+    /// Seconds.
+    #[getter]
+    fn duration(&self) -> f32 {
+        self.0.duration
+    }
+
+    #[setter]
+    fn set_duration(&mut self, duration: f32) {
+        self.0.duration = duration;
+    }
+
+    /// `h264`, `pro_res`, `vp9` or `av1`.
+    #[getter]
+    fn codec(&self) -> String {
+        enum_name(self.0.codec)
+    }
+
+    #[setter]
+    fn set_codec(&mut self, codec: &str) -> PyResult<()> {
+        self.0.codec = parse_enum::<VideoCodec>(codec, "codec")?;
+        Ok(())
+    }
+
+    #[getter]
+    fn crf(&self) -> u8 {
+        self.0.crf
+    }
+
+    #[setter]
+    fn set_crf(&mut self, crf: u8) {
+        self.0.crf = crf;
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "ExportSettings({}x{}, {} fps, {}s, {})",
+            self.0.width,
+            self.0.height,
+            self.0.fps,
+            self.0.duration,
+            self.codec()
+        )
+    }
+}
+
+// ------------------------- Project -------------------------
+
+/// A complete scene. Load one with `Project.load(path)` or start from `Project()`.
+#[pyclass(name = "Project", module = "matterhorn")]
+pub struct PyProject {
+    /// Everything except the parts below, which Python holds references to.
+    rest: Project,
+    fractal: Py<PyFractal>,
+    camera: Py<PyCamera>,
+    export_settings: Py<PyExportSettings>,
+}
+
+impl PyProject {
+    fn wrap(py: Python<'_>, proj: Project) -> PyResult<Self> {
+        Ok(Self {
+            fractal: Py::new(py, PyFractal(proj.fractal.clone()))?,
+            camera: Py::new(py, PyCamera(proj.camera.clone()))?,
+            export_settings: Py::new(py, PyExportSettings(proj.export.clone()))?,
+            rest: proj,
+        })
+    }
+
+    /// The project as the core library sees it, with the current Python-side edits.
+    fn snapshot(&self, py: Python<'_>) -> Project {
+        Project {
+            fractal: self.fractal.borrow(py).0.clone(),
+            camera: self.camera.borrow(py).0.clone(),
+            export: self.export_settings.borrow(py).0.clone(),
+            ..self.rest.clone()
+        }
+    }
+}
+
+#[pymethods]
+impl PyProject {
+    #[new]
+    fn new(py: Python<'_>) -> PyResult<Self> {
+        Self::wrap(py, Project::default())
+    }
+
+    /// Reads a `.json` or `.mahproj` project file.
+    #[staticmethod]
+    fn load(py: Python<'_>, path: PathBuf) -> PyResult<Self> {
+        let proj = load_project(&path).map_err(|e| project_error(e, &path))?;
+        Self::wrap(py, proj)
+    }
+
+    /// Parses project JSON text, as written by `to_json`.
+    #[staticmethod]
+    fn from_json(py: Python<'_>, text: &str) -> PyResult<Self> {
+        let proj = project_from_str(text, "project.json".as_ref()).map_err(value_error)?;
+        Self::wrap(py, proj)
+    }
+
+    fn to_json(&self, py: Python<'_>) -> PyResult<String> {
+        serde_json::to_string_pretty(&self.snapshot(py)).map_err(value_error)
+    }
+
+    /// Writes the project; the extension picks JSON or TOML (`.mahproj`).
+    fn save(&self, py: Python<'_>, path: PathBuf) -> PyResult<()> {
+        save_project(&self.snapshot(py), &path).map_err(|e| project_error(e, &path))
+    }
+
+    #[getter]
+    fn name(&self) -> String {
+        self.rest.name.clone()
+    }
+
+    #[setter]
+    fn set_name(&mut self, name: String) {
+        self.rest.name = name;
+    }
+
+    /// Animation length in seconds.
+    #[getter]
+    fn duration(&self) -> f32 {
+        self.rest.anim.duration
+    }
+
+    #[setter]
+    fn set_duration(&mut self, duration: f32) {
+        self.rest.anim.duration = duration;
+    }
+
+    #[getter]
+    fn fractal(&self, py: Python<'_>) -> Py<PyFractal> {
+        self.fractal.clone_ref(py)
+    }
+
+    #[setter]
+    fn set_fractal(&mut self, py: Python<'_>, fractal: PyFractal) -> PyResult<()> {
+        self.fractal = Py::new(py, fractal)?;
+        Ok(())
+    }
+
+    #[getter]
+    fn camera(&self, py: Python<'_>) -> Py<PyCamera> {
+        self.camera.clone_ref(py)
+    }
+
+    #[setter]
+    fn set_camera(&mut self, py: Python<'_>, camera: PyCamera) -> PyResult<()> {
+        self.camera = Py::new(py, camera)?;
+        Ok(())
+    }
+
+    #[getter]
+    fn export_settings(&self, py: Python<'_>) -> Py<PyExportSettings> {
+        self.export_settings.clone_ref(py)
+    }
+
+    #[setter]
+    fn set_export_settings(&mut self, py: Python<'_>, export: PyExportSettings) -> PyResult<()> {
+        self.export_settings = Py::new(py, export)?;
+        Ok(())
+    }
+
+    /// Renders the frame at `t` seconds on the CPU, as a `(height, width, 4)` uint8 numpy
+    /// array of RGBA pixels. Keyframes and the project's script apply as in an export.
+    #[pyo3(signature = (width, height, t = 0.0))]
+    fn render<'py>(
+        &self,
+        py: Python<'py>,
+        width: u32,
+        height: u32,
+        t: f32,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        check_frame_size((width, height)).map_err(value_error)?;
+        let proj = self.snapshot(py);
+        let pixels = py.allow_threads(|| -> Result<Vec<u8>, String> {
+            let mut frame = evaluate_frame(&proj, t, EvalTarget::Export);
+            frame.fractal.apply_auto_iter(&frame.camera, width as f32);
+            let index = frame_at(t, proj.export.fps);
+            proj.export
+                .quality
+                .apply(&mut frame.fractal, proj.seed, index);
+            if let Some(script) =
+                ScriptRunner::for_project(&proj).map_err(|e| format!("script {e}"))?
+            {
+                script
+                    .apply(t, &mut frame)
+                    .map_err(|e| format!("script {e}"))?;
+            }
+            render_image(
+                (width, height),
+                &frame.fractal,
+                &frame.camera,
+                &mut CpuRenderer,
+                proj.export.quality.tile_size,
+            )
+            .map_err(|e| e.to_string())
+        });
+        let pixels = pixels.map_err(PyRuntimeError::new_err)?;
+        let numpy = py.import_bound("numpy")?;
+        // A bytearray keeps the array writable.
+        numpy
+            .call_method1("frombuffer", (PyByteArray::new_bound(py, &pixels), "uint8"))?
+            .call_method1("reshape", ((height, width, 4),))
+    }
+
+    /// Renders the animation on the CPU and encodes it to `out` with FFmpeg, using
+    /// `export_settings`. Calls `progress_callback(done, total)` as frames finish; if it
+    /// raises, or on Ctrl+C, the export is cancelled. Returns the path of the written video.
+    #[pyo3(signature = (out, progress_callback = None, ffmpeg = PathBuf::from("ffmpeg")))]
+    fn export(
+        &self,
+        py: Python<'_>,
+        out: PathBuf,
+        progress_callback: Option<PyObject>,
+        ffmpeg: PathBuf,
+    ) -> PyResult<PathBuf> {
+        let mut proj = self.snapshot(py);
+        proj.export.out_path = out;
+        let progress = Arc::new(ExportProgress::default());
+        let worker_progress = Arc::clone(&progress);
+        let worker = thread::spawn(move || {
+            export_video_blocking(&proj, &ffmpeg, &worker_progress, &mut CpuRenderer)
+        });
+
+        let mut reported = None;
+        let mut interrupted = None;
+        while !worker.is_finished() {
+            py.allow_threads(|| thread::sleep(PROGRESS_INTERVAL));
+            let now = progress.fraction();
+            let step = py.check_signals().and_then(|()| match &progress_callback {
+                Some(callback) if reported != Some(now) => callback.call1(py, now).map(drop),
+                _ => Ok(()),
+            });
+            reported = Some(now);
+            if let Err(e) = step {
+                progress.cancel.store(true, AtomicOrdering::Relaxed);
+                interrupted = Some(e);
+                break;
+            }
+        }
+        let result = py
+            .allow_threads(|| worker.join())
+            .map_err(|_| PyRuntimeError::new_err("export worker panicked"))?;
+        if let Some(e) = interrupted {
+            return Err(e);
+        }
+        match result {
+            Ok(report) => {
+                if let Some(callback) = &progress_callback {
+                    callback.call1(py, progress.fraction())?;
+                }
+                Ok(report.path)
+            }
+            Err(ExportError::Cancelled) => Err(PyKeyboardInterrupt::new_err("export cancelled")),
+            Err(e) => Err(PyRuntimeError::new_err(e.to_string())),
+        }
+    }
+
+    fn __repr__(&self, py: Python<'_>) -> String {
+        format!(
+            "Project(name={:?}, kind={:?})",
+            self.rest.name,
+            self.fractal.borrow(py).kind()
+        )
+    }
+}
+
+#[pymodule]
+fn matterhorn(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyProject>()?;
+    m.add_class::<PyFractal>()?;
+    m.add_class::<PyCamera>()?;
+    m.add_class::<PyExportSettings>()?;
+    Ok(())
+}
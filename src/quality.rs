@@ -0,0 +1,182 @@
+//! Named render-quality profiles: the iteration, sampling and tiling settings a render is
+//! made with, shared by the preview and exports.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{frame_seed, FractalParams, SampleJitter, SamplePattern};
+
+/// Names of the built-in profiles, fastest first.
+pub const BUILT_IN_QUALITY: [&str; 4] = ["Draft", "Preview", "Final", "Insane"];
+
+/// How carefully a frame is rendered. Projects keep a whole copy for the preview and one
+/// for exports, so they render the same on machines without the named profile.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QualityProfile {
+    pub name: String,
+    /// Multiplies each frame's Max Iter, after Auto has picked it.
+    pub iter_scale: f32,
+    /// Sub-pixel sample pattern; jittered patterns are seeded from the frame index.
+    pub sample_pattern: SamplePattern,
+    /// Largest tile rendered at once; 0 picks automatically, tuned per renderer in exports.
+    pub tile_size: u32,
+}
+
+impl Default for QualityProfile {
+    fn default() -> Self {
+        Self::preview()
+    }
+}
+
+impl QualityProfile {
+    /// Quarter the iterations, for blocking out a scene.
+    pub fn draft() -> Self {
+        Self {
+            name: "Draft".into(),
+            iter_scale: 0.25,
+            sample_pattern: SamplePattern::Grid,
+            tile_size: 0,
+        }
+    }
+
+    /// The project's own settings, in tiles picked for responsiveness.
+    pub fn preview() -> Self {
+        Self {
+            name: "Preview".into(),
+            iter_scale: 1.0,
+            sample_pattern: SamplePattern::Grid,
+            tile_size: 0,
+        }
+    }
+
+    /// The project's own settings in tiles tuned for the renderer; what exports use by
+    /// default.
+    pub fn final_quality() -> Self {
+        Self {
+            name: "Final".into(),
+            iter_scale: 1.0,
+            sample_pattern: SamplePattern::Grid,
+            tile_size: 0,
+        }
+    }
+
+    /// Four times the iterations with jittered samples, for stills that must hold up.
+    pub fn insane() -> Self {
+        Self {
+            name: "Insane".into(),
+            iter_scale: 4.0,
+            sample_pattern: SamplePattern::R2,
+            tile_size: 0,
+        }
+    }
+
+    /// The built-in profiles, in the order of [`BUILT_IN_QUALITY`].
+    pub fn built_in() -> [QualityProfile; 4] {
+        [
+            Self::draft(),
+            Self::preview(),
+            Self::final_quality(),
+            Self::insane(),
+        ]
+    }
+
+    /// The built-in profile called `name`, ignoring case.
+    pub fn named(name: &str) -> Option<Self> {
+        Self::built_in()
+            .into_iter()
+            .find(|p| p.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn with_iter_scale(mut self, scale: f32) -> Self {
+        self.iter_scale = scale;
+        self
+    }
+
+    pub fn with_sample_pattern(mut self, pattern: SamplePattern) -> Self {
+        self.sample_pattern = pattern;
+        self
+    }
+
+    pub fn with_tile_size(mut self, tile_size: u32) -> Self {
+        self.tile_size = tile_size;
+        self
+    }
+
+    /// `max_iter` scaled by [`QualityProfile::iter_scale`], at least 1.
+    pub fn max_iter(&self, max_iter: u32) -> u32 {
+        let scale = if self.iter_scale.is_finite() {
+            self.iter_scale.max(0.0)
+        } else {
+            1.0
+        };
+        ((max_iter as f32 * scale).round() as u32).max(1)
+    }
+
+    /// Pattern and seed frame `frame` of a project seeded with `project_seed` is sampled
+    /// with.
+    pub fn sampling(&self, project_seed: u64, frame: u32) -> SampleJitter {
+        SampleJitter {
+            pattern: self.sample_pattern,
+            seed: frame_seed(project_seed, frame),
+        }
+    }
+
+    /// Applies the profile's iterations and sample pattern to `fractal`, evaluated for
+    /// frame `frame`. Call it once, after Auto Max Iter.
+    pub fn apply(&self, fractal: &mut FractalParams, project_seed: u64, frame: u32) {
+        fractal.max_iter = self.max_iter(fractal.max_iter);
+        fractal.sampling = self.sampling(project_seed, frame);
+    }
+
+    /// Whether this is a built-in profile or one in `user`, unchanged.
+    pub fn is_saved(&self, user: &[QualityProfile]) -> bool {
+        Self::built_in().iter().chain(user).any(|p| p == self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profiles_scale_iterations_and_set_sampling() {
+        let mut fractal = FractalParams::default().with_max_iter(800);
+        QualityProfile::draft().apply(&mut fractal, 7, 3);
+        assert_eq!(fractal.max_iter, 200);
+        assert_eq!(fractal.sampling.pattern, SamplePattern::Grid);
+
+        let mut fractal = FractalParams::default().with_max_iter(800);
+        QualityProfile::insane().apply(&mut fractal, 7, 3);
+        assert_eq!(fractal.max_iter, 3200);
+        assert_eq!(fractal.sampling, QualityProfile::insane().sampling(7, 3));
+        assert_ne!(fractal.sampling, QualityProfile::insane().sampling(7, 4));
+
+        let broken = QualityProfile::preview().with_iter_scale(f32::NAN);
+        assert_eq!(broken.max_iter(800), 800);
+        assert_eq!(QualityProfile::draft().max_iter(1), 1);
+    }
+
+    #[test]
+    fn built_in_profiles_are_found_by_name() {
+        for (profile, name) in QualityProfile::built_in().iter().zip(BUILT_IN_QUALITY) {
+            assert_eq!(profile.name, name);
+            assert_eq!(
+                QualityProfile::named(&name.to_lowercase()).as_ref(),
+                Some(profile)
+            );
+        }
+        assert_eq!(QualityProfile::named("Custom"), None);
+
+        let user = QualityProfile::final_quality()
+            .with_name("Night")
+            .with_tile_size(512);
+        assert!(user.is_saved(std::slice::from_ref(&user)));
+        assert!(!user.clone().with_tile_size(256).is_saved(&[user]));
+        assert!(QualityProfile::insane().is_saved(&[]));
+    }
+}
@@ -0,0 +1,362 @@
+//! Parameter ranges: for each number the side panel edits, the span its slider covers, a
+//! wider one for expert mode, and the hard limit typed, pasted and `--set` values keep to.
+
+use crate::{Project, SHARE_MAX_ITER};
+
+/// How a slider's travel maps onto values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SliderResponse {
+    Linear,
+    /// Equal travel multiplies the value by equal factors; for ranges spanning decades.
+    Logarithmic,
+}
+
+/// The ranges of one parameter, as `(min, max)` pairs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParamRange {
+    /// What the slider covers.
+    pub soft: (f64, f64),
+    /// What the slider covers in expert mode.
+    pub expert: (f64, f64),
+    /// Values past this are rejected: the renderer or encoder cannot use them.
+    pub hard: (f64, f64),
+    pub response: SliderResponse,
+    /// Only whole numbers are allowed.
+    pub integer: bool,
+}
+
+impl ParamRange {
+    const fn new(soft: (f64, f64), expert: (f64, f64), hard: (f64, f64)) -> Self {
+        Self {
+            soft,
+            expert,
+            hard,
+            response: SliderResponse::Linear,
+            integer: false,
+        }
+    }
+
+    const fn logarithmic(mut self) -> Self {
+        self.response = SliderResponse::Logarithmic;
+        self
+    }
+
+    const fn integer(mut self) -> Self {
+        self.integer = true;
+        self
+    }
+
+    /// What the slider covers, in expert mode or not.
+    pub fn slider(&self, expert: bool) -> (f64, f64) {
+        if expert {
+            self.expert
+        } else {
+            self.soft
+        }
+    }
+
+    /// `value` moved inside the hard limit; NaN becomes the low end.
+    pub fn clamp(&self, value: f64) -> f64 {
+        let clamped = value.clamp(self.hard.0, self.hard.1);
+        match (clamped.is_nan(), self.integer) {
+            (true, _) => self.hard.0,
+            (false, true) => clamped.round(),
+            (false, false) => clamped,
+        }
+    }
+}
+
+/// A parameter with an entry in the range table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangedParam {
+    MaxIter,
+    EscapeRadius,
+    CenterX,
+    CenterY,
+    Scale,
+    Rotation,
+    PalettePhase,
+    PaletteCycleSpeed,
+    PaletteRepeat,
+    Exposure,
+    Gamma,
+    TrapRadius,
+    TrapSoftness,
+    Duration,
+    Fps,
+    Crf,
+}
+
+/// Why a value was not accepted for a parameter.
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum RangeError {
+    #[error("unknown parameter {0:?}; expected one of {names}", names = param_names())]
+    Unknown(String),
+    #[error("expected NAME=VALUE, not {0:?}")]
+    Syntax(String),
+    #[error("{name} must be a number, not {value:?}")]
+    NotANumber { name: &'static str, value: String },
+    #[error("{name} must be a whole number, not {value}")]
+    NotWhole { name: &'static str, value: f64 },
+    #[error("{name} must be between {min} and {max}, not {value}")]
+    OutOfRange {
+        name: &'static str,
+        value: f64,
+        min: f64,
+        max: f64,
+    },
+}
+
+fn param_names() -> String {
+    let names: Vec<&str> = RangedParam::ALL.iter().map(|p| p.name()).collect();
+    names.join(", ")
+}
+
+impl RangedParam {
+    pub const ALL: [RangedParam; 16] = [
+        RangedParam::MaxIter,
+        RangedParam::EscapeRadius,
+        RangedParam::CenterX,
+        RangedParam::CenterY,
+        RangedParam::Scale,
+        RangedParam::Rotation,
+        RangedParam::PalettePhase,
+        RangedParam::PaletteCycleSpeed,
+        RangedParam::PaletteRepeat,
+        RangedParam::Exposure,
+        RangedParam::Gamma,
+        RangedParam::TrapRadius,
+        RangedParam::TrapSoftness,
+        RangedParam::Duration,
+        RangedParam::Fps,
+        RangedParam::Crf,
+    ];
+
+    /// Name in settings snippets and `--set`.
+    pub fn name(self) -> &'static str {
+        match self {
+            RangedParam::MaxIter => "max_iter",
+            RangedParam::EscapeRadius => "escape_radius",
+            RangedParam::CenterX => "center.re",
+            RangedParam::CenterY => "center.im",
+            RangedParam::Scale => "scale",
+            RangedParam::Rotation => "rotation",
+            RangedParam::PalettePhase => "palette_phase",
+            RangedParam::PaletteCycleSpeed => "palette_cycle_speed",
+            RangedParam::PaletteRepeat => "palette_repeat",
+            RangedParam::Exposure => "exposure",
+            RangedParam::Gamma => "gamma",
+            RangedParam::TrapRadius => "orbit.radius",
+            RangedParam::TrapSoftness => "orbit.softness",
+            RangedParam::Duration => "export.duration",
+            RangedParam::Fps => "export.fps",
+            RangedParam::Crf => "export.crf",
+        }
+    }
+
+    pub fn named(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|p| p.name() == name)
+    }
+
+    pub fn range(self) -> ParamRange {
+        use std::f64::consts::PI;
+        match self {
+            RangedParam::MaxIter => {
+                let max = f64::from(SHARE_MAX_ITER);
+                ParamRange::new((50.0, 20_000.0), (10.0, max), (1.0, max)).integer()
+            }
+            // The escape test squares R, and |z| may be well past R by the time it runs.
+            RangedParam::EscapeRadius => {
+                ParamRange::new((2.0, 128.0), (1.01, 1e6), (0.01, 1e9)).logarithmic()
+            }
+            RangedParam::CenterX => ParamRange::new((-2.5, 2.5), (-8.0, 8.0), (-1e6, 1e6)),
+            RangedParam::CenterY => ParamRange::new((-2.0, 2.0), (-8.0, 8.0), (-1e6, 1e6)),
+            RangedParam::Scale => {
+                ParamRange::new((50.0, 8000.0), (1.0, 1e8), (1e-3, 1e15)).logarithmic()
+            }
+            RangedParam::Rotation => ParamRange::new((-PI, PI), (-4.0 * PI, 4.0 * PI), (-1e4, 1e4)),
+            RangedParam::PalettePhase => ParamRange::new((0.0, 1.0), (-4.0, 4.0), (-1e4, 1e4)),
+            RangedParam::PaletteCycleSpeed => {
+                ParamRange::new((-2.0, 2.0), (-20.0, 20.0), (-1e3, 1e3))
+            }
+            RangedParam::PaletteRepeat => {
+                ParamRange::new((10.0, 20_000.0), (1.0, 1e6), (1e-3, 1e9)).logarithmic()
+            }
+            RangedParam::Exposure => {
+                ParamRange::new((0.1, 6.0), (0.01, 100.0), (1e-3, 1e4)).logarithmic()
+            }
+            RangedParam::Gamma => ParamRange::new((0.5, 4.0), (0.1, 10.0), (0.05, 20.0)),
+            RangedParam::TrapRadius => ParamRange::new((0.05, 2.0), (0.0, 10.0), (0.0, 1e4)),
+            RangedParam::TrapSoftness => {
+                ParamRange::new((0.5, 20.0), (0.1, 200.0), (0.01, 1e4)).logarithmic()
+            }
+            RangedParam::Duration => ParamRange::new((1.0, 120.0), (0.1, 3600.0), (0.01, 86_400.0)),
+            RangedParam::Fps => {
+                ParamRange::new((12.0, 120.0), (1.0, 240.0), (1.0, 1000.0)).integer()
+            }
+            // The widest CRF scale FFmpeg's encoders take (VP9 and AV1).
+            RangedParam::Crf => ParamRange::new((0.0, 40.0), (0.0, 63.0), (0.0, 63.0)).integer(),
+        }
+    }
+
+    /// `value` if the hard limit allows it.
+    pub fn check(self, value: f64) -> Result<f64, RangeError> {
+        let range = self.range();
+        let (min, max) = range.hard;
+        if !(min..=max).contains(&value) {
+            return Err(RangeError::OutOfRange {
+                name: self.name(),
+                value,
+                min,
+                max,
+            });
+        }
+        if range.integer && value.fract() != 0.0 {
+            return Err(RangeError::NotWhole {
+                name: self.name(),
+                value,
+            });
+        }
+        Ok(value)
+    }
+
+    pub fn get(self, proj: &Project) -> f64 {
+        let (fractal, camera, export) = (&proj.fractal, &proj.camera, &proj.export);
+        match self {
+            RangedParam::MaxIter => fractal.max_iter.into(),
+            RangedParam::EscapeRadius => fractal.escape_radius.into(),
+            RangedParam::CenterX => camera.center.re,
+            RangedParam::CenterY => camera.center.im,
+            RangedParam::Scale => camera.scale,
+            RangedParam::Rotation => camera.rotation.into(),
+            RangedParam::PalettePhase => fractal.palette_phase.into(),
+            RangedParam::PaletteCycleSpeed => fractal.palette_cycle_speed.into(),
+            RangedParam::PaletteRepeat => fractal.palette_repeat.into(),
+            RangedParam::Exposure => fractal.exposure.into(),
+            RangedParam::Gamma => fractal.gamma.into(),
+            RangedParam::TrapRadius => fractal.orbit.radius.into(),
+            RangedParam::TrapSoftness => fractal.orbit.softness.into(),
+            RangedParam::Duration => export.duration.into(),
+            RangedParam::Fps => export.fps.into(),
+            RangedParam::Crf => export.crf.into(),
+        }
+    }
+
+    /// Sets the parameter in `proj` after checking `value`. Setting Max Iter turns Auto
+    /// off, so the value sticks.
+    pub fn set(self, proj: &mut Project, value: f64) -> Result<(), RangeError> {
+        let value = self.check(value)?;
+        let (fractal, camera, export) = (&mut proj.fractal, &mut proj.camera, &mut proj.export);
+        let float = value as f32;
+        match self {
+            RangedParam::MaxIter => {
+                fractal.max_iter = value as u32;
+                fractal.auto_iter = false;
+            }
+            RangedParam::EscapeRadius => fractal.escape_radius = float,
+            RangedParam::CenterX => camera.center.re = value,
+            RangedParam::CenterY => camera.center.im = value,
+            RangedParam::Scale => camera.scale = value,
+            RangedParam::Rotation => camera.rotation = float,
+            RangedParam::PalettePhase => fractal.palette_phase = float,
+            RangedParam::PaletteCycleSpeed => fractal.palette_cycle_speed = float,
+            RangedParam::PaletteRepeat => fractal.palette_repeat = float,
+            RangedParam::Exposure => fractal.exposure = float,
+            RangedParam::Gamma => fractal.gamma = float,
+            RangedParam::TrapRadius => fractal.orbit.radius = float,
+            RangedParam::TrapSoftness => fractal.orbit.softness = float,
+            RangedParam::Duration => export.duration = float,
+            RangedParam::Fps => export.fps = value as u32,
+            RangedParam::Crf => export.crf = value as u8,
+        }
+        Ok(())
+    }
+}
+
+/// Reads a `NAME=VALUE` override, as `--set` takes, and checks the value.
+pub fn parse_override(text: &str) -> Result<(RangedParam, f64), RangeError> {
+    let (name, value) = text
+        .split_once('=')
+        .ok_or_else(|| RangeError::Syntax(text.to_owned()))?;
+    let (name, value) = (name.trim(), value.trim());
+    let param = RangedParam::named(name).ok_or_else(|| RangeError::Unknown(name.to_owned()))?;
+    let number = value.parse::<f64>().map_err(|_| RangeError::NotANumber {
+        name: param.name(),
+        value: value.to_owned(),
+    })?;
+    Ok((param, param.check(number)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_slider_range_lies_within_the_hard_limit() {
+        let inside = |(lo, hi): (f64, f64), (min, max): (f64, f64)| min <= lo && hi <= max;
+        for param in RangedParam::ALL {
+            let range = param.range();
+            assert_eq!(RangedParam::named(param.name()), Some(param));
+            assert!(range.soft.0 < range.soft.1, "{param:?}");
+            assert!(inside(range.soft, range.expert), "{param:?}");
+            assert!(inside(range.expert, range.hard), "{param:?}");
+            if range.response == SliderResponse::Logarithmic {
+                assert!(range.expert.0 > 0.0, "{param:?}");
+            }
+            // The defaults sit on the slider.
+            let default = param.get(&Project::default());
+            assert!(
+                inside((default, default), range.soft),
+                "{param:?}: {default}"
+            );
+        }
+    }
+
+    #[test]
+    fn values_are_checked_against_the_hard_limit() {
+        let mut proj = Project::default();
+        RangedParam::Scale.set(&mut proj, 1e9).unwrap();
+        assert_eq!(proj.camera.scale, 1e9);
+        RangedParam::MaxIter.set(&mut proj, 50_000.0).unwrap();
+        assert_eq!(proj.fractal.max_iter, 50_000);
+        assert!(!proj.fractal.auto_iter);
+
+        let err = RangedParam::Gamma.set(&mut proj, 0.0).unwrap_err();
+        assert_eq!(err.to_string(), "gamma must be between 0.05 and 20, not 0");
+        assert!(RangedParam::Exposure.check(f64::NAN).is_err());
+        assert!(matches!(
+            RangedParam::Fps.check(29.97),
+            Err(RangeError::NotWhole { .. })
+        ));
+        assert_eq!(RangedParam::Crf.range().clamp(99.4), 63.0);
+        assert_eq!(RangedParam::MaxIter.range().clamp(f64::NAN), 1.0);
+    }
+
+    #[test]
+    fn overrides_parse_with_a_reason_when_they_do_not() {
+        assert_eq!(
+            parse_override("exposure = 12.5"),
+            Ok((RangedParam::Exposure, 12.5))
+        );
+        assert_eq!(
+            parse_override("orbit.radius=3"),
+            Ok((RangedParam::TrapRadius, 3.0))
+        );
+        let cases = [
+            ("exposure", "expected NAME=VALUE"),
+            (
+                "zoom=3",
+                "unknown parameter \"zoom\"; expected one of max_iter, ",
+            ),
+            ("scale=deep", "scale must be a number, not \"deep\""),
+            (
+                "export.crf=70",
+                "export.crf must be between 0 and 63, not 70",
+            ),
+        ];
+        for (text, reason) in cases {
+            let err = parse_override(text).unwrap_err().to_string();
+            assert!(err.starts_with(reason), "{text}: {err}");
+        }
+    }
+}
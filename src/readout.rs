@@ -0,0 +1,407 @@
+//! Zoom depth readout: magnification against the formula's overview and the size of one
+//! pixel in the complex plane, shown in the viewport and optionally burned into exports.
+
+use std::{fmt, ops::Range};
+
+use serde::Serialize;
+
+use crate::{formula, Camera, FractalKind};
+
+/// How deep a camera is, measured against the overview framing of its formula.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ZoomReadout {
+    /// Times the overview is magnified; 1 at the formula's default view.
+    pub magnification: f64,
+    /// Width of one pixel in the complex plane.
+    pub pixel_size: f64,
+}
+
+impl ZoomReadout {
+    /// Readout for `cam` shown `view_width` pixels wide. Pass the evaluated camera so the
+    /// numbers match what is rendered, including an endless zoom.
+    pub fn new(kind: FractalKind, cam: &Camera, view_width: f32) -> Self {
+        let (_, overview_width) = formula(kind).overview();
+        let scale = cam.scale;
+        Self {
+            magnification: f64::from(overview_width) * scale / f64::from(view_width.max(1.0)),
+            pixel_size: 1.0 / scale,
+        }
+    }
+}
+
+/// `×3.4e9 · px 2.9e-12`.
+impl fmt::Display for ZoomReadout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} · px {}",
+            format_magnification(self.magnification),
+            format_scientific(self.pixel_size)
+        )
+    }
+}
+
+/// Magnification as `×250` or `×3.4e9`.
+pub fn format_magnification(magnification: f64) -> String {
+    format!("×{}", format_scientific(magnification))
+}
+
+/// Two to three significant digits: plain from 0.01 up to a thousand, `3.4e9` or
+/// `2.9e-12` outside that.
+pub fn format_scientific(value: f64) -> String {
+    if value.is_nan() {
+        return "NaN".into();
+    }
+    if value.is_infinite() {
+        return if value > 0.0 { "∞" } else { "-∞" }.into();
+    }
+    let abs = value.abs();
+    if abs == 0.0 {
+        return "0".into();
+    }
+    if !(0.01..999.5).contains(&abs) {
+        return format!("{value:.1e}");
+    }
+    let decimals = if abs >= 99.95 {
+        0
+    } else if abs >= 9.995 {
+        1
+    } else {
+        2
+    };
+    let plain = format!("{value:.decimals$}");
+    if plain.contains('.') {
+        plain
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_string()
+    } else {
+        plain
+    }
+}
+
+// ------------------------- Burn-in -------------------------
+
+/// 5×7 glyphs for printable ASCII and the readout's symbols, one byte per row with the
+/// leftmost pixel in bit 4. Anything else is blank.
+fn glyph(c: char) -> [u8; 7] {
+    match c {
+        '0' => [0x0e, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0e],
+        '1' => [0x04, 0x0c, 0x04, 0x04, 0x04, 0x04, 0x0e],
+        '2' => [0x0e, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1f],
+        '3' => [0x1f, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0e],
+        '4' => [0x02, 0x06, 0x0a, 0x12, 0x1f, 0x02, 0x02],
+        '5' => [0x1f, 0x10, 0x1e, 0x01, 0x01, 0x11, 0x0e],
+        '6' => [0x06, 0x08, 0x10, 0x1e, 0x11, 0x11, 0x0e],
+        '7' => [0x1f, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0e, 0x11, 0x11, 0x0e, 0x11, 0x11, 0x0e],
+        '9' => [0x0e, 0x11, 0x11, 0x0f, 0x01, 0x02, 0x0c],
+        '.' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x0c, 0x0c],
+        '-' => [0x00, 0x00, 0x00, 0x1f, 0x00, 0x00, 0x00],
+        '+' => [0x00, 0x04, 0x04, 0x1f, 0x04, 0x04, 0x00],
+        '·' => [0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00],
+        '×' => [0x00, 0x11, 0x0a, 0x04, 0x0a, 0x11, 0x00],
+        '∞' => [0x00, 0x00, 0x0a, 0x15, 0x0a, 0x00, 0x00],
+        'e' => [0x00, 0x00, 0x0e, 0x11, 0x1f, 0x10, 0x0e],
+        'p' => [0x00, 0x00, 0x1e, 0x11, 0x1e, 0x10, 0x10],
+        'x' => [0x00, 0x00, 0x11, 0x0a, 0x04, 0x0a, 0x11],
+        'A' => [0x0e, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11],
+        'B' => [0x1e, 0x11, 0x11, 0x1e, 0x11, 0x11, 0x1e],
+        'C' => [0x0e, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0e],
+        'D' => [0x1c, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1c],
+        'E' => [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x1f],
+        'F' => [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x10],
+        'G' => [0x0e, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0f],
+        'H' => [0x11, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11],
+        'I' => [0x0e, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0e],
+        'J' => [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0c],
+        'K' => [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+        'L' => [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1f],
+        'M' => [0x11, 0x1b, 0x15, 0x15, 0x11, 0x11, 0x11],
+        'N' => [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+        'O' => [0x0e, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e],
+        'P' => [0x1e, 0x11, 0x11, 0x1e, 0x10, 0x10, 0x10],
+        'Q' => [0x0e, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0d],
+        'R' => [0x1e, 0x11, 0x11, 0x1e, 0x14, 0x12, 0x11],
+        'S' => [0x0f, 0x10, 0x10, 0x0e, 0x01, 0x01, 0x1e],
+        'T' => [0x1f, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+        'U' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e],
+        'V' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x0a, 0x04],
+        'W' => [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0a],
+        'X' => [0x11, 0x11, 0x0a, 0x04, 0x0a, 0x11, 0x11],
+        'Y' => [0x11, 0x11, 0x0a, 0x04, 0x04, 0x04, 0x04],
+        'Z' => [0x1f, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1f],
+        'a' => [0x00, 0x00, 0x0e, 0x01, 0x0f, 0x11, 0x0f],
+        'b' => [0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x1e],
+        'c' => [0x00, 0x00, 0x0e, 0x10, 0x10, 0x11, 0x0e],
+        'd' => [0x01, 0x01, 0x0d, 0x13, 0x11, 0x11, 0x0f],
+        'f' => [0x06, 0x09, 0x08, 0x1c, 0x08, 0x08, 0x08],
+        'g' => [0x00, 0x0f, 0x11, 0x11, 0x0f, 0x01, 0x0e],
+        'h' => [0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x11],
+        'i' => [0x04, 0x00, 0x0c, 0x04, 0x04, 0x04, 0x0e],
+        'j' => [0x02, 0x00, 0x06, 0x02, 0x02, 0x12, 0x0c],
+        'k' => [0x10, 0x10, 0x12, 0x14, 0x18, 0x14, 0x12],
+        'l' => [0x0c, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0e],
+        'm' => [0x00, 0x00, 0x1a, 0x15, 0x15, 0x11, 0x11],
+        'n' => [0x00, 0x00, 0x16, 0x19, 0x11, 0x11, 0x11],
+        'o' => [0x00, 0x00, 0x0e, 0x11, 0x11, 0x11, 0x0e],
+        'q' => [0x00, 0x00, 0x0d, 0x13, 0x0f, 0x01, 0x01],
+        'r' => [0x00, 0x00, 0x16, 0x19, 0x10, 0x10, 0x10],
+        's' => [0x00, 0x00, 0x0e, 0x10, 0x0e, 0x01, 0x1e],
+        't' => [0x08, 0x08, 0x1c, 0x08, 0x08, 0x09, 0x06],
+        'u' => [0x00, 0x00, 0x11, 0x11, 0x11, 0x13, 0x0d],
+        'v' => [0x00, 0x00, 0x11, 0x11, 0x11, 0x0a, 0x04],
+        'w' => [0x00, 0x00, 0x11, 0x11, 0x15, 0x15, 0x0a],
+        'y' => [0x00, 0x00, 0x11, 0x11, 0x0f, 0x01, 0x0e],
+        'z' => [0x00, 0x00, 0x1f, 0x02, 0x04, 0x08, 0x1f],
+        '!' => [0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x04],
+        '"' => [0x0a, 0x0a, 0x00, 0x00, 0x00, 0x00, 0x00],
+        '#' => [0x0a, 0x0a, 0x1f, 0x0a, 0x1f, 0x0a, 0x0a],
+        '%' => [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03],
+        '&' => [0x0c, 0x12, 0x14, 0x08, 0x15, 0x12, 0x0d],
+        '\'' => [0x04, 0x04, 0x08, 0x00, 0x00, 0x00, 0x00],
+        '(' => [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02],
+        ')' => [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08],
+        '*' => [0x00, 0x04, 0x15, 0x0e, 0x15, 0x04, 0x00],
+        ',' => [0x00, 0x00, 0x00, 0x00, 0x0c, 0x04, 0x08],
+        '/' => [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00],
+        ':' => [0x00, 0x0c, 0x0c, 0x00, 0x0c, 0x0c, 0x00],
+        ';' => [0x00, 0x0c, 0x0c, 0x00, 0x0c, 0x04, 0x08],
+        '<' => [0x02, 0x04, 0x08, 0x10, 0x08, 0x04, 0x02],
+        '=' => [0x00, 0x00, 0x1f, 0x00, 0x1f, 0x00, 0x00],
+        '>' => [0x08, 0x04, 0x02, 0x01, 0x02, 0x04, 0x08],
+        '?' => [0x0e, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04],
+        '_' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1f],
+        '°' => [0x0c, 0x12, 0x12, 0x0c, 0x00, 0x00, 0x00],
+        _ => [0; 7],
+    }
+}
+
+/// Draws `text` in the bottom-left corner of a `size` frame over a darkened box, sized
+/// for the frame height. `pixels` holds the RGBA8 rows `rows` of the frame, so banded
+/// frames can be stamped one band at a time.
+pub fn burn_in_text(pixels: &mut [u8], size: (u32, u32), rows: Range<u32>, text: &str) {
+    let chars: Vec<[u8; 7]> = text.chars().map(glyph).collect();
+    let dot = (size.1 / 240).clamp(1, 8);
+    let (pad, advance) = (2 * dot, 6 * dot);
+    let box_w = 2 * pad + (chars.len() as u32 * advance).saturating_sub(dot);
+    let box_h = 2 * pad + 7 * dot;
+    let x0 = pad;
+    let y0 = size.1.saturating_sub(pad + box_h);
+    let width = size.0 as usize;
+    for y in rows.start.max(y0)..rows.end.min(y0 + box_h).min(size.1) {
+        let row = (y - rows.start) as usize * width;
+        let gy = (y - y0).checked_sub(pad).map(|gy| gy / dot);
+        for x in x0..(x0 + box_w).min(size.0) {
+            let Some(px) = pixels.get_mut((row + x as usize) * 4..(row + x as usize) * 4 + 4)
+            else {
+                return;
+            };
+            let lit = gy.filter(|&gy| gy < 7).is_some_and(|gy| {
+                let Some(gx) = (x - x0).checked_sub(pad) else {
+                    return false;
+                };
+                let col = (gx % advance) / dot;
+                chars
+                    .get((gx / advance) as usize)
+                    .is_some_and(|g| col < 5 && (g[gy as usize] >> (4 - col)) & 1 == 1)
+            });
+            // Opaque, so the readout shows over a transparent exterior too.
+            px[3] = 255;
+            for c in &mut px[..3] {
+                *c = if lit {
+                    255
+                } else {
+                    (u16::from(*c) * 2 / 5) as u8
+                };
+            }
+        }
+    }
+}
+
+/// Width and height in pixels of `text` stamped with `dot`-pixel glyph pixels.
+pub fn text_size(text: &str, dot: u32) -> (u32, u32) {
+    let chars = text.chars().count() as u32;
+    ((chars * 6 * dot).saturating_sub(dot), 7 * dot)
+}
+
+/// Text placed on a frame, ready to draw.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TextStamp {
+    pub text: String,
+    /// Top-left corner in frame pixels; may lie outside the frame.
+    pub origin: (i32, i32),
+    /// Side of one glyph pixel, in frame pixels.
+    pub dot: u32,
+    pub color: [u8; 3],
+    pub alpha: u8,
+}
+
+impl TextStamp {
+    /// Blends the text over the RGBA8 rows `rows` of a `size` frame in `pixels`, clipped to
+    /// the frame. Like [`burn_in_text`], banded frames can be stamped one band at a time.
+    pub fn draw(&self, pixels: &mut [u8], size: (u32, u32), rows: Range<u32>) {
+        stamp_text(pixels, size, rows, self);
+    }
+
+    /// Lit glyph pixels as (column, row) counted in dots from the origin, for drawing the
+    /// text some other way.
+    pub fn lit_cells(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        self.text.chars().enumerate().flat_map(|(i, c)| {
+            let g = glyph(c);
+            (0..7u32).flat_map(move |row| {
+                (0..5u32)
+                    .filter(move |col| (g[row as usize] >> (4 - col)) & 1 == 1)
+                    .map(move |col| (i as u32 * 6 + col, row))
+            })
+        })
+    }
+}
+
+fn stamp_text(pixels: &mut [u8], size: (u32, u32), rows: Range<u32>, stamp: &TextStamp) {
+    let dot = i64::from(stamp.dot.max(1));
+    let chars: Vec<[u8; 7]> = stamp.text.chars().map(glyph).collect();
+    let (w, h) = text_size(&stamp.text, dot as u32);
+    let (ox, oy) = (i64::from(stamp.origin.0), i64::from(stamp.origin.1));
+    let a = f32::from(stamp.alpha) / 255.0;
+    let width = i64::from(size.0);
+    let y_range = oy.max(i64::from(rows.start))..(oy + i64::from(h)).min(i64::from(rows.end));
+    let x_range = ox.max(0)..(ox + i64::from(w)).min(width);
+    for y in y_range {
+        let gy = ((y - oy) / dot) as usize;
+        let row = (y - i64::from(rows.start)) * width;
+        for x in x_range.clone() {
+            let gx = x - ox;
+            let col = (gx % (6 * dot)) / dot;
+            let lit = chars
+                .get((gx / (6 * dot)) as usize)
+                .is_some_and(|g| col < 5 && (g[gy] >> (4 - col)) & 1 == 1);
+            if !lit {
+                continue;
+            }
+            let i = ((row + x) * 4) as usize;
+            let Some(px) = pixels.get_mut(i..i + 4) else {
+                return;
+            };
+            // "Over" in straight alpha, so text keeps its color over a transparent exterior.
+            let under = f32::from(px[3]) / 255.0;
+            let out = a + under * (1.0 - a);
+            if out <= 0.0 {
+                continue;
+            }
+            for (c, &src) in px[..3].iter_mut().zip(&stamp.color) {
+                let v = (f32::from(src) * a + f32::from(*c) * under * (1.0 - a)) / out;
+                *c = v.round().clamp(0.0, 255.0) as u8;
+            }
+            px[3] = (out * 255.0).round() as u8;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Complex;
+
+    #[test]
+    fn formats_large_and_small_values() {
+        assert_eq!(format_magnification(1.0), "×1");
+        assert_eq!(format_magnification(2.5), "×2.5");
+        assert_eq!(format_magnification(250.0), "×250");
+        assert_eq!(format_magnification(999.7), "×1.0e3");
+        assert_eq!(format_magnification(3.4e9), "×3.4e9");
+        assert_eq!(format_magnification(9.96e9), "×1.0e10");
+        assert_eq!(format_magnification(1e300), "×1.0e300");
+        assert_eq!(format_magnification(f64::MAX), "×1.8e308");
+        assert_eq!(format_magnification(f64::INFINITY), "×∞");
+        assert_eq!(format_scientific(2.9e-12), "2.9e-12");
+        assert_eq!(format_scientific(f64::MIN_POSITIVE), "2.2e-308");
+        assert_eq!(format_scientific(0.25), "0.25");
+        assert_eq!(format_scientific(0.0), "0");
+        assert_eq!(format_scientific(-12.34), "-12.3");
+    }
+
+    #[test]
+    fn overview_is_unit_magnification() {
+        let kind = FractalKind::Mandelbrot;
+        let cam = formula(kind).default_camera(800.0);
+        let readout = ZoomReadout::new(kind, &cam, 800.0);
+        assert!((readout.magnification - 1.0).abs() < 1e-6);
+        let deep = Camera::new(Complex::new(-0.75, 0.1), cam.scale * 3.4e9);
+        let readout = ZoomReadout::new(kind, &deep, 800.0);
+        assert_eq!(format_magnification(readout.magnification), "×3.4e9");
+        assert!((readout.pixel_size * deep.scale - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn endless_zoom_reads_the_rendered_scale() {
+        let mut proj = crate::Project::new("deep");
+        proj.anim.apply_endless_zoom_preset(300.0);
+        if let Some(zoom) = &mut proj.anim.zoom_forever {
+            zoom.reverse = true;
+        }
+        let zoom = proj.anim.zoom_forever.unwrap();
+        let frame = crate::evaluate_frame(&proj, 4.0, crate::EvalTarget::Export);
+        let readout = ZoomReadout::new(proj.fractal.kind, &frame.camera, 640.0);
+        let expected = ZoomReadout::new(
+            proj.fractal.kind,
+            &Camera {
+                scale: zoom.value_at(4.0),
+                ..proj.camera.clone()
+            },
+            640.0,
+        );
+        assert_eq!(readout, expected);
+        assert!(
+            readout.magnification
+                > ZoomReadout::new(proj.fractal.kind, &proj.camera, 640.0).magnification
+        );
+    }
+
+    #[test]
+    fn burn_in_is_the_same_in_bands() {
+        let size = (200, 120);
+        let text = "×3.4e9 · px 2.9e-12";
+        let mut whole = vec![128; (size.0 * size.1 * 4) as usize];
+        burn_in_text(&mut whole, size, 0..size.1, text);
+        assert_ne!(whole, vec![128; whole.len()]);
+        assert!(whole.contains(&255));
+
+        let row = (size.0 * 4) as usize;
+        let mut banded = Vec::new();
+        for top in (0..size.1).step_by(7) {
+            let band = top..(top + 7).min(size.1);
+            let mut pixels = vec![128; band.len() * row];
+            burn_in_text(&mut pixels, size, band, text);
+            banded.extend(pixels);
+        }
+        assert_eq!(banded, whole);
+    }
+
+    #[test]
+    fn stamped_text_is_clipped_and_blended() {
+        let size = (40, 20);
+        let mut pixels = vec![0; (size.0 * size.1 * 4) as usize];
+        // Starts off the left edge and runs off the right one.
+        let stamp = TextStamp {
+            text: "Hi there".into(),
+            origin: (-3, 2),
+            dot: 2,
+            color: [255, 0, 0],
+            alpha: 128,
+        };
+        stamp.draw(&mut pixels, size, 0..size.1);
+        let lit: Vec<&[u8]> = pixels.chunks(4).filter(|px| px[3] > 0).collect();
+        assert!(!lit.is_empty());
+        assert!(lit.iter().all(|px| px == &[255, 0, 0, 128]));
+        assert_eq!(text_size("Hi", 2), (22, 14));
+
+        let mut hidden = vec![0; pixels.len()];
+        let offscreen = TextStamp {
+            origin: (-100, 50),
+            ..stamp
+        };
+        offscreen.draw(&mut hidden, size, 0..size.1);
+        assert_eq!(hidden, vec![0; pixels.len()]);
+    }
+}
@@ -0,0 +1,132 @@
+//! Reconciling an open project with its file after the file was edited elsewhere, e.g.
+//! by hand in a text editor while the app is running.
+
+use std::time::{Duration, Instant};
+
+use crate::Project;
+
+/// How long a file has to stay quiet before it is read again. Editors often save in
+/// several writes, or write a temporary file and rename it over the original.
+pub const RELOAD_QUIET: Duration = Duration::from_millis(300);
+
+/// What a change to the open project's file calls for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reconcile {
+    /// The file holds the open scene or the one last loaded, e.g. after the app's own
+    /// save or a `touch`; nothing to do.
+    Unchanged,
+    /// Nothing was edited in the app since the file was last loaded or saved, so the
+    /// file replaces the open project.
+    Reload,
+    /// Both the app and the file changed; the user picks a [`ReloadChoice`].
+    Conflict,
+}
+
+/// The user's answer to a [`Reconcile::Conflict`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReloadChoice {
+    /// Take the file and drop the edits made in the app.
+    Reload,
+    /// Keep the open project; the file is overwritten by the next save.
+    KeepMine,
+    /// Take the file but keep the camera the app is looking through.
+    MergeView,
+}
+
+impl ReloadChoice {
+    pub const ALL: [ReloadChoice; 3] = [
+        ReloadChoice::Reload,
+        ReloadChoice::KeepMine,
+        ReloadChoice::MergeView,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ReloadChoice::Reload => "Reload",
+            ReloadChoice::KeepMine => "Keep mine",
+            ReloadChoice::MergeView => "Merge view only",
+        }
+    }
+}
+
+/// Compares `open` and the freshly read `on_disk` against `base`, the project as it was
+/// last loaded from or saved to the file. Session state (playhead, playback, key
+/// selection) and the thumbnail do not count as edits.
+pub fn reconcile(open: &Project, base: &Project, on_disk: &Project) -> Reconcile {
+    let disk = scene(on_disk);
+    if disk == scene(open) || disk == scene(base) {
+        Reconcile::Unchanged
+    } else if scene(open) == scene(base) {
+        Reconcile::Reload
+    } else {
+        Reconcile::Conflict
+    }
+}
+
+/// The project to continue with after `choice`. Whatever is taken from the file keeps
+/// the open project's playhead and playback, so a reload does not jump the timeline.
+pub fn resolve(open: &Project, on_disk: Project, choice: ReloadChoice) -> Project {
+    let mut proj = match choice {
+        ReloadChoice::KeepMine => return open.clone(),
+        ReloadChoice::Reload => on_disk,
+        ReloadChoice::MergeView => Project {
+            camera: open.camera.clone(),
+            ..on_disk
+        },
+    };
+    proj.anim.t = open.anim.t.clamp(0.0, proj.anim.duration.max(0.0));
+    proj.anim.playing = open.anim.playing;
+    // The selected key may be gone from the file.
+    proj.anim.selection = None;
+    proj
+}
+
+fn scene(proj: &Project) -> serde_json::Value {
+    let mut proj = proj.authored();
+    proj.thumbnail = None;
+    serde_json::to_value(&proj).unwrap_or_default()
+}
+
+/// Collapses a burst of change notifications into one read, once the file has been
+/// quiet for `quiet`.
+#[derive(Debug, Clone)]
+pub struct Debounce {
+    pub quiet: Duration,
+    last_change: Option<Instant>,
+}
+
+impl Default for Debounce {
+    fn default() -> Self {
+        Self::new(RELOAD_QUIET)
+    }
+}
+
+impl Debounce {
+    pub fn new(quiet: Duration) -> Self {
+        Self {
+            quiet,
+            last_change: None,
+        }
+    }
+
+    /// Notes a change notification seen at `now`.
+    pub fn changed(&mut self, now: Instant) {
+        self.last_change = Some(now);
+    }
+
+    /// Whether a change is waiting for the quiet period to pass.
+    pub fn pending(&self) -> bool {
+        self.last_change.is_some()
+    }
+
+    /// True once per burst, when `quiet` has passed since its last change.
+    pub fn ready(&mut self, now: Instant) -> bool {
+        match self.last_change {
+            Some(at) if now.saturating_duration_since(at) >= self.quiet => {
+                self.last_change = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,444 @@
+//! Network tile rendering: [`serve`] turns a machine into a render worker, and
+//! [`RemoteRenderer`] farms tiles out to a list of workers.
+//!
+//! The protocol is plain TCP. Every message starts with a frame: the magic `MHTL`, a
+//! big-endian `u32` length, and that many bytes of JSON header. A request is a
+//! [`TileJob`] header; the answer is a [`TileReply`] header followed by the tile's RGBA8
+//! pixels, zlib-compressed. A connection carries any number of jobs, one at a time.
+
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    io::{self, Read, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    blit_tile, build_palette, create_renderer, tile_iterator, Camera, CpuRenderer, FractalParams,
+    PaletteEqualizer, RenderBackend, RenderError, Renderer, TileInfo,
+};
+
+/// Version of the job and reply headers. Workers refuse jobs of any other version.
+pub const JOB_VERSION: u32 = 2;
+
+/// Port `serve` listens on unless told otherwise.
+pub const DEFAULT_WORKER_PORT: u16 = 7878;
+
+/// Largest tile a worker renders, in pixels.
+pub const MAX_JOB_PIXELS: u64 = 4096 * 4096;
+
+const MAGIC: &[u8; 4] = b"MHTL";
+
+/// Largest header accepted, so a stray connection cannot make a worker allocate much.
+const MAX_HEADER_BYTES: u32 = 1 << 20;
+
+/// One tile to render, as sent to a worker. Workers build the palette from `params`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TileJob {
+    pub version: u32,
+    pub params: FractalParams,
+    pub camera: Camera,
+    pub tile: TileInfo,
+    /// Levels of the [`FractalParams::equalizer`] attached to `params`, which does not
+    /// serialize it. Workers use it rather than measuring their own, so every tile of a
+    /// frame, smoothed over time or not, is equalized alike.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub equalizer: Option<Vec<f32>>,
+}
+
+impl TileJob {
+    pub fn new(params: &FractalParams, camera: &Camera, tile: TileInfo) -> Self {
+        Self {
+            version: JOB_VERSION,
+            params: params.clone(),
+            camera: camera.clone(),
+            tile,
+            equalizer: params.equalizer.as_ref().map(|e| e.levels().to_vec()),
+        }
+    }
+}
+
+/// A worker's answer to a [`TileJob`]. Without an error, `compressed` bytes of zlib data
+/// follow the header.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TileReply {
+    pub version: u32,
+    /// Why the worker did not render the job.
+    pub error: Option<String>,
+    /// CRC-32 of the uncompressed pixels.
+    pub crc32: u32,
+    pub compressed: u32,
+}
+
+impl TileReply {
+    fn error(message: String) -> Self {
+        Self {
+            version: JOB_VERSION,
+            error: Some(message),
+            crc32: 0,
+            compressed: 0,
+        }
+    }
+}
+
+/// Why a remote tile could not be rendered.
+#[derive(thiserror::Error, Debug)]
+pub enum RemoteError {
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    /// The peer sent something that is not a well-formed message.
+    #[error("protocol error: {0}")]
+    Protocol(String),
+    #[error("peer speaks job version {found}, this build speaks {JOB_VERSION}")]
+    Version { found: u32 },
+    #[error("tile checksum mismatch: expected {expected:08x}, got {actual:08x}")]
+    Checksum { expected: u32, actual: u32 },
+    /// The worker answered but could not render the job. The connection stays usable.
+    #[error("worker error: {0}")]
+    Worker(String),
+}
+
+// ------------------------- Worker -------------------------
+
+/// Accepts connections forever, rendering each one's jobs on its own thread with a
+/// renderer for `backend`, or the CPU if that backend is unavailable.
+pub fn serve(listener: &TcpListener, backend: RenderBackend) {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("Could not accept a connection: {e}");
+                continue;
+            }
+        };
+        thread::spawn(move || {
+            let peer = stream
+                .peer_addr()
+                .map_or_else(|_| "unknown peer".into(), |a| a.to_string());
+            let mut renderer = create_renderer(backend).unwrap_or_else(|err| {
+                eprintln!("{} init failed: {err}. Using the CPU.", backend.label());
+                Box::new(CpuRenderer)
+            });
+            if let Err(e) = serve_connection(stream, renderer.as_mut()) {
+                eprintln!("{peer}: {e}");
+            }
+        });
+    }
+}
+
+/// Answers jobs on `stream` until the client hangs up. Jobs that cannot be rendered get
+/// an error reply; only a broken stream ends the connection early.
+pub fn serve_connection(
+    mut stream: TcpStream,
+    renderer: &mut dyn Renderer,
+) -> Result<(), RemoteError> {
+    stream.set_nodelay(true)?;
+    loop {
+        let header = match read_frame(&mut stream) {
+            Ok(header) => header,
+            Err(RemoteError::Io(e)) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        let pixels = parse_header::<TileJob>(&header).and_then(|job| render_job(&job, renderer));
+        match pixels {
+            Ok(pixels) => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), Compression::fast());
+                encoder.write_all(&pixels)?;
+                let compressed = encoder.finish()?;
+                let reply = TileReply {
+                    version: JOB_VERSION,
+                    error: None,
+                    crc32: crc32fast::hash(&pixels),
+                    compressed: compressed.len() as u32,
+                };
+                write_frame(&mut stream, &reply)?;
+                stream.write_all(&compressed)?;
+            }
+            Err(e) => write_frame(&mut stream, &TileReply::error(e.to_string()))?,
+        }
+        stream.flush()?;
+    }
+}
+
+fn render_job(job: &TileJob, renderer: &mut dyn Renderer) -> Result<Vec<u8>, RemoteError> {
+    let t = &job.tile;
+    let pixels = t.tile_w as u64 * t.tile_h as u64;
+    let inside = t.offset_x as u64 + t.tile_w as u64 <= t.full_w as u64
+        && t.offset_y as u64 + t.tile_h as u64 <= t.full_h as u64;
+    if pixels == 0 || pixels > MAX_JOB_PIXELS || !inside {
+        return Err(RemoteError::Worker(format!(
+            "tile {}×{} at ({}, {}) of {}×{} is empty, too large or outside the frame",
+            t.tile_w, t.tile_h, t.offset_x, t.offset_y, t.full_w, t.full_h
+        )));
+    }
+    let params = match &job.equalizer {
+        Some(levels) => {
+            let equalizer = PaletteEqualizer::from_levels(levels.clone()).ok_or_else(|| {
+                RemoteError::Worker(format!("invalid equalizer of {} levels", levels.len()))
+            })?;
+            Cow::Owned(FractalParams {
+                equalizer: Some(Arc::new(equalizer)),
+                ..job.params.clone()
+            })
+        }
+        None => Cow::Borrowed(&job.params),
+    };
+    let palette = build_palette(&params, 2048);
+    renderer
+        .render_tile(t, &params, &job.camera, &palette)
+        .map_err(|e| RemoteError::Worker(format!("{} render failed: {e}", renderer.name())))
+}
+
+// ------------------------- Client -------------------------
+
+/// A connection to one worker, reused across jobs.
+pub struct WorkerConnection {
+    stream: TcpStream,
+}
+
+impl WorkerConnection {
+    /// Connects to `addr` (`host:port`). `timeout` bounds the connect and every read and
+    /// write after it.
+    pub fn connect(addr: &str, timeout: Duration) -> Result<Self, RemoteError> {
+        let mut last_error = None;
+        for addr in addr.to_socket_addrs()? {
+            match TcpStream::connect_timeout(&addr, timeout) {
+                Ok(stream) => {
+                    stream.set_read_timeout(Some(timeout))?;
+                    stream.set_write_timeout(Some(timeout))?;
+                    stream.set_nodelay(true)?;
+                    return Ok(Self { stream });
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(last_error
+            .unwrap_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no address to connect to"))
+            .into())
+    }
+
+    /// Sends `job` and waits for its pixels, checking their size and checksum.
+    pub fn render(&mut self, job: &TileJob) -> Result<Vec<u8>, RemoteError> {
+        write_frame(&mut self.stream, job)?;
+        self.stream.flush()?;
+        let reply: TileReply = parse_header(&read_frame(&mut self.stream)?)?;
+        if let Some(error) = reply.error {
+            return Err(RemoteError::Worker(error));
+        }
+        let expected = job.tile.tile_w as usize * job.tile.tile_h as usize * 4;
+        // Incompressible pixels grow by a few bytes per 16 KiB block under zlib.
+        if reply.compressed as usize > expected + expected / 64 + 1024 {
+            return Err(RemoteError::Protocol(format!(
+                "{} compressed bytes for a {expected} byte tile",
+                reply.compressed
+            )));
+        }
+        let mut compressed = vec![0; reply.compressed as usize];
+        self.stream.read_exact(&mut compressed)?;
+
+        let mut pixels = Vec::with_capacity(expected);
+        ZlibDecoder::new(compressed.as_slice())
+            .take(expected as u64 + 1)
+            .read_to_end(&mut pixels)
+            .map_err(|e| RemoteError::Protocol(format!("bad tile data: {e}")))?;
+        if pixels.len() != expected {
+            return Err(RemoteError::Protocol(format!(
+                "tile has {} bytes, expected {expected}",
+                pixels.len()
+            )));
+        }
+        let actual = crc32fast::hash(&pixels);
+        if actual != reply.crc32 {
+            return Err(RemoteError::Checksum {
+                expected: reply.crc32,
+                actual,
+            });
+        }
+        Ok(pixels)
+    }
+}
+
+/// Renders on network workers started with [`serve`], splitting each tile into jobs from
+/// [`tile_iterator`] that the workers take from a shared queue. A failed job goes back on
+/// the queue for the next free worker, and after `retries` retries it is rendered by the
+/// local renderer instead, as is everything once no worker is left.
+pub struct RemoteRenderer {
+    workers: Vec<String>,
+    /// Open connections, one slot per worker, kept between tiles and frames.
+    connections: Vec<Option<WorkerConnection>>,
+    local: Box<dyn Renderer>,
+    job_size: u32,
+    retries: u32,
+    timeout: Duration,
+}
+
+impl RemoteRenderer {
+    /// Workers are `host:port` addresses; `local` renders the jobs none of them could.
+    pub fn new(workers: Vec<String>, local: Box<dyn Renderer>) -> Self {
+        Self {
+            connections: workers.iter().map(|_| None).collect(),
+            workers,
+            local,
+            job_size: 512,
+            retries: 2,
+            timeout: Duration::from_secs(30),
+        }
+    }
+
+    /// Largest job side sent to a worker, in pixels; at least 256.
+    pub fn with_job_size(mut self, job_size: u32) -> Self {
+        self.job_size = job_size.max(256);
+        self
+    }
+
+    /// Times a failed job goes back on the queue before the local renderer takes it.
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Connect, read and write timeout for each worker.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+impl Renderer for RemoteRenderer {
+    fn name(&self) -> &'static str {
+        "remote"
+    }
+
+    fn render_tile(
+        &mut self,
+        tile: &TileInfo,
+        params: &FractalParams,
+        cam: &Camera,
+        palette: &[[u8; 3]],
+    ) -> Result<Vec<u8>, RenderError> {
+        let jobs: Vec<TileInfo> = tile_iterator(tile.tile_w, tile.tile_h, self.job_size)
+            .into_iter()
+            .map(|part| TileInfo {
+                full_w: tile.full_w,
+                full_h: tile.full_h,
+                offset_x: tile.offset_x + part.offset_x,
+                offset_y: tile.offset_y + part.offset_y,
+                ..part
+            })
+            .collect();
+        let queue: Mutex<VecDeque<(usize, u32)>> =
+            Mutex::new((0..jobs.len()).map(|i| (i, 0)).collect());
+        let results: Mutex<Vec<Option<Vec<u8>>>> = Mutex::new(vec![None; jobs.len()]);
+        let (retries, timeout) = (self.retries, self.timeout);
+
+        thread::scope(|scope| {
+            for (addr, connection) in self.workers.iter().zip(&mut self.connections) {
+                let (jobs, queue, results) = (&jobs, &queue, &results);
+                scope.spawn(move || loop {
+                    let Some((i, attempts)) = queue.lock().expect("job queue").pop_front() else {
+                        break;
+                    };
+                    let result = match connection {
+                        Some(conn) => conn.render(&TileJob::new(params, cam, jobs[i])),
+                        None => WorkerConnection::connect(addr, timeout).and_then(|conn| {
+                            connection
+                                .insert(conn)
+                                .render(&TileJob::new(params, cam, jobs[i]))
+                        }),
+                    };
+                    match result {
+                        Ok(pixels) => results.lock().expect("job results")[i] = Some(pixels),
+                        Err(e) => {
+                            eprintln!("Worker {addr}: {e}");
+                            if attempts < retries {
+                                queue
+                                    .lock()
+                                    .expect("job queue")
+                                    .push_back((i, attempts + 1));
+                            }
+                            // A worker that answered can take the next job; a broken
+                            // connection takes this worker out until the next tile.
+                            if !matches!(e, RemoteError::Worker(_)) {
+                                *connection = None;
+                                break;
+                            }
+                        }
+                    }
+                });
+            }
+        });
+
+        let results = results.into_inner().expect("job results");
+        let missing = results.iter().filter(|r| r.is_none()).count();
+        if missing > 0 && !self.workers.is_empty() {
+            eprintln!(
+                "Rendering {missing} of {} jobs with the {} renderer",
+                jobs.len(),
+                self.local.name()
+            );
+        }
+        let mut pixels = vec![0u8; tile.tile_w as usize * tile.tile_h as usize * 4];
+        for (job, result) in jobs.iter().zip(results) {
+            let job_pixels = match result {
+                Some(job_pixels) => job_pixels,
+                None => self.local.render_tile(job, params, cam, palette)?,
+            };
+            let within = TileInfo {
+                offset_x: job.offset_x - tile.offset_x,
+                offset_y: job.offset_y - tile.offset_y,
+                ..*job
+            };
+            blit_tile(&mut pixels, tile.tile_w, &within, &job_pixels);
+        }
+        Ok(pixels)
+    }
+}
+
+// ------------------------- Framing -------------------------
+
+fn write_frame(stream: &mut impl Write, header: &impl Serialize) -> io::Result<()> {
+    let json = serde_json::to_vec(header)?;
+    stream.write_all(MAGIC)?;
+    stream.write_all(&(json.len() as u32).to_be_bytes())?;
+    stream.write_all(&json)
+}
+
+fn read_frame(stream: &mut impl Read) -> Result<Vec<u8>, RemoteError> {
+    let mut head = [0u8; 8];
+    stream.read_exact(&mut head)?;
+    if &head[..4] != MAGIC {
+        return Err(RemoteError::Protocol("not a Matterhorn tile stream".into()));
+    }
+    let len = u32::from_be_bytes([head[4], head[5], head[6], head[7]]);
+    if len > MAX_HEADER_BYTES {
+        return Err(RemoteError::Protocol(format!(
+            "{len} byte header is over the {MAX_HEADER_BYTES} byte limit"
+        )));
+    }
+    let mut header = vec![0; len as usize];
+    stream.read_exact(&mut header)?;
+    Ok(header)
+}
+
+/// Checks the version before the rest, so a newer peer is reported as such rather than
+/// as a field this build does not know.
+fn parse_header<T: DeserializeOwned>(header: &[u8]) -> Result<T, RemoteError> {
+    #[derive(Deserialize)]
+    struct VersionProbe {
+        version: u32,
+    }
+    let bad = |e: serde_json::Error| RemoteError::Protocol(format!("bad header: {e}"));
+    let probe: VersionProbe = serde_json::from_slice(header).map_err(bad)?;
+    if probe.version != JOB_VERSION {
+        return Err(RemoteError::Version {
+            found: probe.version,
+        });
+    }
+    serde_json::from_slice(header).map_err(bad)
+}
@@ -0,0 +1,2663 @@
+//! The [`Renderer`] backends, CPU rendering, and tiling.
+
+use std::{
+    borrow::Cow,
+    f32::consts::TAU,
+    ops::Range,
+    sync::{
+        atomic::{AtomicUsize, Ordering as AtomicOrdering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use web_time::Instant;
+
+#[cfg(feature = "gpu")]
+use crate::gpu_renderer::GpuRenderer;
+use crate::{
+    build_palette, central_sample, format_bytes, formula, frame_equalizer, iterate_perturbed,
+    needs_reference, newton_brightness, newton_roots, sample_palette, stats::millis,
+    with_compiled_formula, with_equalizer, with_reference, Camera, Complex, Complex64,
+    ExteriorMode, FractalKind, FractalParams, InteriorColoring, Interp, ReferenceOrbit,
+    RenderBackend, RenderStats,
+};
+
+/// A rectangle of a `full_w`×`full_h` frame rendered as one unit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TileInfo {
+    pub full_w: u32,
+    pub full_h: u32,
+    pub offset_x: u32,
+    pub offset_y: u32,
+    pub tile_w: u32,
+    pub tile_h: u32,
+}
+
+impl TileInfo {
+    /// A single tile covering the whole frame.
+    pub fn full(width: u32, height: u32) -> Self {
+        Self {
+            full_w: width,
+            full_h: height,
+            offset_x: 0,
+            offset_y: 0,
+            tile_w: width,
+            tile_h: height,
+        }
+    }
+}
+
+/// Tiles are never cut smaller than this many pixels per side.
+const MIN_TILE: u32 = 256;
+
+/// Tiles are never larger than this many pixels per side, whatever is requested.
+pub const MAX_TILE: u32 = 16_384;
+
+/// Order [`TileLayout::tiles`] returns tiles in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TileOrder {
+    /// Rows top to bottom, each left to right.
+    RowMajor,
+    /// Square rings outward from the middle of the frame, each clockwise from the top, so
+    /// a progressive display shows the subject first.
+    #[default]
+    SpiralFromCenter,
+}
+
+/// How a frame is cut into tiles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TileLayout {
+    /// Largest tile side, overlap included, capped at [`MAX_TILE`]; 0 picks
+    /// automatically: one tile up to 8192 px, 4096 px tiles beyond.
+    pub max_tile: u32,
+    pub order: TileOrder,
+    /// Pixels each tile reaches into its neighbours, for post-filters that need a
+    /// neighbourhood. Overlapping tiles are combined by a [`TileBlender`]. At most a
+    /// quarter of the tile size is used.
+    pub overlap: u32,
+}
+
+impl TileLayout {
+    pub fn new(max_tile: u32) -> Self {
+        Self {
+            max_tile,
+            order: TileOrder::default(),
+            overlap: 0,
+        }
+    }
+
+    pub fn with_order(mut self, order: TileOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    pub fn with_overlap(mut self, overlap: u32) -> Self {
+        self.overlap = overlap;
+        self
+    }
+
+    /// Caps the tile size at a renderer's [`Renderer::max_tile_size`] (0 means no limit).
+    pub fn limited_to(mut self, limit: u32) -> Self {
+        self.max_tile = match (self.max_tile, limit) {
+            (0, limit) | (limit, 0) => limit,
+            (requested, limit) => requested.min(limit),
+        };
+        self
+    }
+
+    /// Overlap actually used with tiles of at most `tile` pixels.
+    fn overlap_for(&self, tile: u32) -> u32 {
+        self.overlap.min(tile / 4)
+    }
+
+    /// Tiles of a `width`×`height` frame. Without overlap they cover every pixel exactly
+    /// once. Each row and column of tiles is split evenly, so edge tiles are at most one
+    /// pixel narrower than the rest.
+    pub fn tiles(&self, width: u32, height: u32) -> Vec<TileInfo> {
+        let mut tile = self.max_tile.min(MAX_TILE);
+        if tile == 0 {
+            if width <= 8192 && height <= 8192 {
+                return vec![TileInfo::full(width, height)];
+            }
+            tile = 4096;
+        }
+        if width <= tile && height <= tile {
+            return vec![TileInfo::full(width, height)];
+        }
+        let tile = tile.max(MIN_TILE);
+        let overlap = self.overlap_for(tile);
+        let columns = spans(width, tile - 2 * overlap);
+        let rows = spans(height, tile - 2 * overlap);
+
+        let mut grid = Vec::with_capacity(columns.len() * rows.len());
+        for (row, &(y, h)) in rows.iter().enumerate() {
+            for (column, &(x, w)) in columns.iter().enumerate() {
+                let (x, w) = grow(x, w, width, overlap);
+                let (y, h) = grow(y, h, height, overlap);
+                let info = TileInfo {
+                    full_w: width,
+                    full_h: height,
+                    offset_x: x,
+                    offset_y: y,
+                    tile_w: w,
+                    tile_h: h,
+                };
+                grid.push((spiral_key(column, row, columns.len(), rows.len()), info));
+            }
+        }
+        if self.order == TileOrder::SpiralFromCenter {
+            grid.sort_by(|(a, _), (b, _)| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)));
+        }
+        grid.into_iter().map(|(_, tile)| tile).collect()
+    }
+}
+
+/// Splits `len` into the fewest spans of at most `max` pixels, sized within one pixel of
+/// each other. Returns `(start, size)` pairs.
+fn spans(len: u32, max: u32) -> Vec<(u32, u32)> {
+    let count = len.div_ceil(max).max(1);
+    let (base, extra) = (len / count, len % count);
+    let mut start = 0;
+    (0..count)
+        .map(|i| {
+            let size = base + u32::from(i < extra);
+            start += size;
+            (start - size, size)
+        })
+        .collect()
+}
+
+/// Widens the span `start..start + size` by `overlap` on each side, within `0..len`.
+fn grow(start: u32, size: u32, len: u32, overlap: u32) -> (u32, u32) {
+    let lo = start.saturating_sub(overlap);
+    let hi = (start + size + overlap).min(len);
+    (lo, hi - lo)
+}
+
+/// Ring around the middle of a `columns`×`rows` grid (in half tiles), then the clockwise
+/// angle from straight up.
+fn spiral_key(column: usize, row: usize, columns: usize, rows: usize) -> (u32, f32) {
+    let dx = column as f32 - (columns - 1) as f32 / 2.0;
+    let dy = row as f32 - (rows - 1) as f32 / 2.0;
+    let ring = (dx.abs().max(dy.abs()) * 2.0) as u32;
+    (ring, dx.atan2(-dy).rem_euclid(TAU))
+}
+
+// ------------------------- Frame size limits -------------------------
+
+/// Largest frame side accepted, in pixels.
+pub const MAX_FRAME_SIDE: u32 = 65_536;
+
+/// Most memory one in-memory render may use across all its per-pixel buffers. Larger
+/// frames are rendered with [`render_band`].
+pub const MAX_IN_MEMORY_FRAME: u64 = 4 << 30;
+
+/// Bytes of a `size` frame at `bytes_per_pixel`; `None` if that overflows a `u64`.
+pub fn frame_bytes(size: (u32, u32), bytes_per_pixel: u64) -> Option<u64> {
+    u64::from(size.0)
+        .checked_mul(u64::from(size.1))?
+        .checked_mul(bytes_per_pixel)
+}
+
+/// Refuses empty frames and frames wider or taller than [`MAX_FRAME_SIDE`].
+pub fn check_frame_size(size: (u32, u32)) -> Result<(), RenderError> {
+    if size.0 == 0 || size.1 == 0 {
+        return Err(RenderError::FrameSize(format!(
+            "a {}×{} frame has no pixels",
+            size.0, size.1
+        )));
+    }
+    if size.0 > MAX_FRAME_SIDE || size.1 > MAX_FRAME_SIDE {
+        return Err(RenderError::FrameSize(format!(
+            "a {}×{} frame is larger than {MAX_FRAME_SIDE} px per side",
+            size.0, size.1
+        )));
+    }
+    Ok(())
+}
+
+/// [`MAX_IN_MEMORY_FRAME`], or less where the address space is smaller.
+fn in_memory_limit() -> u64 {
+    MAX_IN_MEMORY_FRAME.min(isize::MAX as u64)
+}
+
+/// Pixel count of a `size` frame held in memory at `bytes_per_pixel`, or why it cannot be.
+fn in_memory_pixels(size: (u32, u32), bytes_per_pixel: u64) -> Result<usize, RenderError> {
+    check_frame_size(size)?;
+    let bytes = frame_bytes(size, bytes_per_pixel).unwrap_or(u64::MAX);
+    if bytes > in_memory_limit() {
+        return Err(RenderError::FrameSize(format!(
+            "a {}×{} frame needs {} in memory, more than the {} limit",
+            size.0,
+            size.1,
+            format_bytes(bytes),
+            format_bytes(in_memory_limit())
+        )));
+    }
+    Ok(size.0 as usize * size.1 as usize)
+}
+
+/// How a frame is rendered when it is written to disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameMode {
+    /// The whole frame at once.
+    InMemory,
+    /// Horizontal bands of this many rows, each written out before the next is rendered.
+    Banded(u32),
+}
+
+impl FrameMode {
+    /// In memory while the RGBA8 frame fits in `budget` bytes, otherwise in bands of at
+    /// most `budget` bytes (but at least one row).
+    pub fn for_size(size: (u32, u32), budget: u64) -> Result<Self, RenderError> {
+        check_frame_size(size)?;
+        let budget = budget.min(in_memory_limit());
+        let row = u64::from(size.0) * 4;
+        if row * u64::from(size.1) <= budget {
+            return Ok(FrameMode::InMemory);
+        }
+        Ok(FrameMode::Banded(
+            (budget / row).clamp(1, u64::from(size.1)) as u32,
+        ))
+    }
+}
+
+/// Splits a frame into tiles of at most `tile` pixels per side (0 picks automatically),
+/// spiralling out from the middle. See [`TileLayout`] for other orders and overlap.
+pub fn tile_iterator(width: u32, height: u32, tile: u32) -> Vec<TileInfo> {
+    TileLayout::new(tile).tiles(width, height)
+}
+
+/// Renders a full frame on the CPU with automatic tiling.
+pub fn render_cpu(size: (u32, u32), params: &FractalParams, cam: &Camera) -> Vec<u8> {
+    render_image(size, params, cam, &mut CpuRenderer, 0).expect("the CPU renderer cannot fail")
+}
+
+/// Renders a full RGBA8 frame (row-major, `width * height * 4` bytes), tiling as needed.
+/// Tiles are at most `tile_override` pixels per side (0 picks automatically) and never
+/// larger than the renderer's [`Renderer::max_tile_size`].
+pub fn render_image(
+    size: (u32, u32),
+    params: &FractalParams,
+    cam: &Camera,
+    renderer: &mut dyn Renderer,
+    tile_override: u32,
+) -> Result<Vec<u8>, RenderError> {
+    render_image_stats(size, params, cam, renderer, tile_override).map(|(frame, _)| frame)
+}
+
+/// [`render_image`], also timing the frame and each of its tiles.
+pub fn render_image_stats(
+    size: (u32, u32),
+    params: &FractalParams,
+    cam: &Camera,
+    renderer: &mut dyn Renderer,
+    tile_override: u32,
+) -> Result<(Vec<u8>, RenderStats), RenderError> {
+    render_image_layout(size, params, cam, renderer, TileLayout::new(tile_override))
+}
+
+/// [`render_image_stats`] with explicit tiling. Tiles never exceed the renderer's
+/// [`Renderer::max_tile_size`]; overlapping tiles are blended.
+pub fn render_image_layout(
+    size: (u32, u32),
+    params: &FractalParams,
+    cam: &Camera,
+    renderer: &mut dyn Renderer,
+    layout: TileLayout,
+) -> Result<(Vec<u8>, RenderStats), RenderError> {
+    // Contours need every pixel's neighbors, so they are painted once the frame is done,
+    // from a sample per pixel kept alongside it.
+    let contours = params.has_band_contours() && !renderer.draws_band_contours(params);
+    let mut pixel_bytes = if layout.overlap > 0 { 8 } else { 4 };
+    if contours {
+        pixel_bytes += std::mem::size_of::<PixelSample>() as u64;
+    }
+    let pixels = in_memory_pixels(size, pixel_bytes)?;
+    let attached = with_reference(params, cam, renderer.precision());
+    let equalized = with_equalizer(&attached, cam, size);
+    let params = &*equalized;
+    let started = Instant::now();
+    let palette = build_palette(params, 2048);
+    let tiles = layout
+        .limited_to(renderer.max_tile_size())
+        .tiles(size.0, size.1);
+    let mut blender = TileBlender::new(size.0, size.1, layout.overlap);
+    let mut stats = RenderStats::new(renderer, size, params);
+    let mut samples = contours.then(|| vec![PixelSample::default(); pixels]);
+
+    for tile in tiles {
+        let tile_started = Instant::now();
+        let tile_pixels = match samples.as_mut() {
+            Some(samples) => {
+                let (tile_pixels, tile_samples) =
+                    renderer.render_tile_samples(&tile, params, cam, &palette)?;
+                blit(samples, size.0, &tile, &tile_samples, 1);
+                tile_pixels
+            }
+            None => renderer.render_tile(&tile, params, cam, &palette)?,
+        };
+        stats.tile_ms.push(millis(tile_started.elapsed()));
+        blender.add(&tile, &tile_pixels);
+    }
+
+    let mut frame = blender.finish();
+    if let Some(samples) = samples {
+        paint_band_contours(size.0, &samples, 0, params, &mut frame);
+    }
+    stats.total_ms = millis(started.elapsed());
+    Ok((frame, stats))
+}
+
+/// [`render_image_stats`], also returning every pixel's [`PixelSample`], row-major.
+pub fn render_image_samples(
+    size: (u32, u32),
+    params: &FractalParams,
+    cam: &Camera,
+    renderer: &mut dyn Renderer,
+    tile_override: u32,
+) -> Result<(Vec<u8>, RenderStats, Vec<PixelSample>), RenderError> {
+    let pixels = in_memory_pixels(size, 4 + std::mem::size_of::<PixelSample>() as u64)?;
+    let attached = with_reference(params, cam, renderer.precision());
+    let equalized = with_equalizer(&attached, cam, size);
+    let params = &*equalized;
+    let started = Instant::now();
+    let palette = build_palette(params, 2048);
+    let tiles = TileLayout::new(tile_override)
+        .limited_to(renderer.max_tile_size())
+        .tiles(size.0, size.1);
+    let mut frame = vec![0u8; pixels * 4];
+    let mut samples = vec![PixelSample::default(); pixels];
+    let mut stats = RenderStats::new(renderer, size, params);
+
+    for tile in tiles {
+        let tile_started = Instant::now();
+        let (tile_pixels, tile_samples) =
+            renderer.render_tile_samples(&tile, params, cam, &palette)?;
+        stats.tile_ms.push(millis(tile_started.elapsed()));
+        blit_tile(&mut frame, size.0, &tile, &tile_pixels);
+        blit(&mut samples, size.0, &tile, &tile_samples, 1);
+    }
+
+    if params.has_band_contours() && !renderer.draws_band_contours(params) {
+        paint_band_contours(size.0, &samples, 0, params, &mut frame);
+    }
+    stats.total_ms = millis(started.elapsed());
+    Ok((frame, stats, samples))
+}
+
+/// Colors the row-major `samples` of a whole frame, as from [`render_image_samples`] with
+/// the same tiling, without iterating again. The result matches a full render of
+/// `params` as long as the samples were taken with [`FractalParams::same_samples`] params.
+/// An equalized palette is measured from the samples themselves.
+pub fn shade_image(
+    size: (u32, u32),
+    samples: &[PixelSample],
+    params: &FractalParams,
+    renderer: &mut dyn Renderer,
+    tile_override: u32,
+) -> Result<(Vec<u8>, RenderStats), RenderError> {
+    let pixels = in_memory_pixels(size, 4)?;
+    assert_eq!(samples.len(), pixels, "one sample per pixel");
+    let mut equalized = Cow::Borrowed(params);
+    if params.equalizes() && params.equalizer.is_none() {
+        equalized.to_mut().equalizer = frame_equalizer(size, samples).map(Arc::new);
+    }
+    let params = &*equalized;
+    let started = Instant::now();
+    let palette = build_palette(params, 2048);
+    let tiles = TileLayout::new(tile_override)
+        .limited_to(renderer.max_tile_size())
+        .tiles(size.0, size.1);
+    let mut frame = vec![0u8; pixels * 4];
+    let mut stats = RenderStats::new(renderer, size, params);
+
+    for tile in tiles {
+        let tile_started = Instant::now();
+        let tile_samples = crop(samples, size.0, &tile, 1);
+        let tile_pixels = renderer.shade_tile(&tile, &tile_samples, params, &palette)?;
+        stats.tile_ms.push(millis(tile_started.elapsed()));
+        blit_tile(&mut frame, size.0, &tile, &tile_pixels);
+    }
+
+    if params.has_band_contours() && !renderer.draws_band_contours(params) {
+        paint_band_contours(size.0, samples, 0, params, &mut frame);
+    }
+    stats.total_ms = millis(started.elapsed());
+    Ok((frame, stats))
+}
+
+/// Rows `rows` of a `size` frame as RGBA8, tiled like [`render_image_stats`] and
+/// identical to the same rows of the whole frame. For frames too large to hold at once;
+/// see [`FrameMode`].
+pub fn render_band(
+    size: (u32, u32),
+    rows: Range<u32>,
+    params: &FractalParams,
+    cam: &Camera,
+    renderer: &mut dyn Renderer,
+    tile_override: u32,
+) -> Result<(Vec<u8>, RenderStats), RenderError> {
+    check_frame_size(size)?;
+    assert!(
+        rows.start < rows.end && rows.end <= size.1,
+        "rows {rows:?} outside a {}-row frame",
+        size.1
+    );
+    let band = (size.0, rows.end - rows.start);
+    let pixels = in_memory_pixels(band, 4)?;
+    // With contours, the band's samples plus the rows just outside it, whose pixels the
+    // band's edge rows are compared with.
+    let contours = params.has_band_contours() && !renderer.draws_band_contours(params);
+    let sample_bytes = 4 + std::mem::size_of::<PixelSample>() as u64;
+    let sampled_rows = in_memory_limit() / (u64::from(size.0) * sample_bytes);
+    if contours && u64::from(band.1) + 2 > sampled_rows && sampled_rows > 3 {
+        // Too tall to hold its samples: render it in parts, which join up exactly since
+        // each compares its edge rows with the rows beyond it.
+        let part = (sampled_rows - 2) as u32;
+        let started = Instant::now();
+        let mut frame = Vec::with_capacity(pixels * 4);
+        let mut stats = RenderStats::new(renderer, size, params);
+        for start in rows.clone().step_by(part as usize) {
+            let end = (start + part).min(rows.end);
+            let (part_pixels, part_stats) =
+                render_band(size, start..end, params, cam, renderer, tile_override)?;
+            frame.extend_from_slice(&part_pixels);
+            stats.tile_ms.extend(part_stats.tile_ms);
+        }
+        stats.total_ms = millis(started.elapsed());
+        return Ok((frame, stats));
+    }
+    let above = u32::from(contours && rows.start > 0);
+    let below = u32::from(contours && rows.end < size.1);
+    let sample_count = if contours {
+        in_memory_pixels((size.0, band.1 + above + below), sample_bytes)?
+    } else {
+        0
+    };
+    let attached = with_reference(params, cam, renderer.precision());
+    // Equalized over the whole frame, not the band, so bands join up.
+    let equalized = with_equalizer(&attached, cam, size);
+    let params = &*equalized;
+    let started = Instant::now();
+    let palette = build_palette(params, 2048);
+    let tiles = TileLayout::new(tile_override)
+        .limited_to(renderer.max_tile_size())
+        .tiles(band.0, band.1);
+    let mut frame = vec![0u8; pixels * 4];
+    let mut stats = RenderStats::new(renderer, size, params);
+    let mut samples = contours.then(|| vec![PixelSample::default(); sample_count]);
+
+    for tile in tiles {
+        let in_frame = TileInfo {
+            full_h: size.1,
+            offset_y: tile.offset_y + rows.start,
+            ..tile
+        };
+        let tile_started = Instant::now();
+        let tile_pixels = match samples.as_mut() {
+            Some(samples) => {
+                let (tile_pixels, tile_samples) =
+                    renderer.render_tile_samples(&in_frame, params, cam, &palette)?;
+                let within = TileInfo {
+                    offset_y: tile.offset_y + above,
+                    ..tile
+                };
+                blit(samples, band.0, &within, &tile_samples, 1);
+                tile_pixels
+            }
+            None => renderer.render_tile(&in_frame, params, cam, &palette)?,
+        };
+        stats.tile_ms.push(millis(tile_started.elapsed()));
+        blit_tile(&mut frame, band.0, &tile, &tile_pixels);
+    }
+
+    if let Some(mut samples) = samples {
+        let edge_row = |y: u32| {
+            let row = TileInfo {
+                offset_y: y,
+                tile_h: 1,
+                ..TileInfo::full(size.0, size.1)
+            };
+            sample_tile_cpu(&row, params, cam)
+        };
+        let width = size.0 as usize;
+        if above == 1 {
+            samples[..width].copy_from_slice(&edge_row(rows.start - 1));
+        }
+        if below == 1 {
+            let last = samples.len() - width;
+            samples[last..].copy_from_slice(&edge_row(rows.end));
+        }
+        paint_band_contours(size.0, &samples, above, params, &mut frame);
+    }
+    stats.total_ms = millis(started.elapsed());
+    Ok((frame, stats))
+}
+
+/// Copies a rendered tile into its place in a `full_width`-wide RGBA8 frame.
+pub fn blit_tile(target: &mut [u8], full_width: u32, tile: &TileInfo, tile_pixels: &[u8]) {
+    blit(target, full_width, tile, tile_pixels, 4);
+}
+
+/// Copies a tile of `channels` values per pixel into its place in a frame.
+fn blit<T: Copy>(target: &mut [T], full_width: u32, tile: &TileInfo, src: &[T], channels: u32) {
+    let channels = channels as usize;
+    for ty in 0..tile.tile_h as usize {
+        let dst_y = tile.offset_y as usize + ty;
+        let dst_offset = (dst_y * full_width as usize + tile.offset_x as usize) * channels;
+        let src_offset = ty * tile.tile_w as usize * channels;
+        let len = tile.tile_w as usize * channels;
+        target[dst_offset..dst_offset + len].copy_from_slice(&src[src_offset..src_offset + len]);
+    }
+}
+
+/// The `channels` values per pixel of `tile`, rows top to bottom, cut out of a
+/// `full_width`-wide frame.
+fn crop<T: Copy>(frame: &[T], full_width: u32, tile: &TileInfo, channels: u32) -> Vec<T> {
+    let channels = channels as usize;
+    let len = tile.tile_w as usize * channels;
+    let mut out = Vec::with_capacity(len * tile.tile_h as usize);
+    for y in tile.offset_y..tile.offset_y + tile.tile_h {
+        let start = (y as usize * full_width as usize + tile.offset_x as usize) * channels;
+        out.extend_from_slice(&frame[start..start + len]);
+    }
+    out
+}
+
+/// Assembles an RGBA8 frame from tiles that may overlap. Each tile fades out over the
+/// `2 * overlap` pixels it shares with a neighbour, so seams from per-tile post-filters
+/// are feathered away; without overlap tiles are simply copied.
+pub struct TileBlender {
+    width: u32,
+    overlap: u32,
+    frame: Vec<u8>,
+    /// Weight already blended into each pixel; empty without overlap.
+    weight: Vec<f32>,
+}
+
+impl TileBlender {
+    pub fn new(width: u32, height: u32, overlap: u32) -> Self {
+        let pixels = width as usize * height as usize;
+        Self {
+            width,
+            overlap,
+            frame: vec![0; pixels * 4],
+            weight: if overlap > 0 {
+                vec![0.0; pixels]
+            } else {
+                Vec::new()
+            },
+        }
+    }
+
+    pub fn add(&mut self, tile: &TileInfo, pixels: &[u8]) {
+        if self.overlap == 0 {
+            blit_tile(&mut self.frame, self.width, tile, pixels);
+            return;
+        }
+        let ramp = 2.0 * self.overlap as f32;
+        // Fades only towards neighbours; a tile edge on the frame edge keeps full weight.
+        let feather = |pos: u32, size: u32, start: u32, len: u32| {
+            let mut w = 1.0f32;
+            if start > 0 {
+                w = w.min((pos as f32 + 0.5) / ramp);
+            }
+            if start + size < len {
+                w = w.min(((size - pos) as f32 - 0.5) / ramp);
+            }
+            w
+        };
+        for ty in 0..tile.tile_h {
+            let wy = feather(ty, tile.tile_h, tile.offset_y, tile.full_h);
+            for tx in 0..tile.tile_w {
+                let w = wy * feather(tx, tile.tile_w, tile.offset_x, tile.full_w);
+                let dst = (tile.offset_y + ty) as usize * self.width as usize
+                    + (tile.offset_x + tx) as usize;
+                let src = ((ty * tile.tile_w + tx) * 4) as usize;
+                let total = self.weight[dst] + w;
+                let t = w / total;
+                for c in 0..4 {
+                    let old = self.frame[dst * 4 + c] as f32;
+                    let new = pixels[src + c] as f32;
+                    self.frame[dst * 4 + c] = (old + (new - old) * t).round() as u8;
+                }
+                self.weight[dst] = total;
+            }
+        }
+    }
+
+    /// The assembled RGBA8 frame.
+    pub fn finish(self) -> Vec<u8> {
+        self.frame
+    }
+}
+
+// ------------------------- Progressive rendering -------------------------
+
+/// Tiles of a frame still to render, handed out nearest a focus point first.
+#[derive(Debug, Clone, Default)]
+pub struct TileQueue {
+    /// Remaining tiles, the next one last.
+    tiles: Vec<TileInfo>,
+    focus: Option<(f32, f32)>,
+}
+
+impl TileQueue {
+    /// Hands out `tiles` in the given order until [`TileQueue::prioritize`] is called.
+    pub fn new(mut tiles: Vec<TileInfo>) -> Self {
+        tiles.reverse();
+        Self { tiles, focus: None }
+    }
+
+    /// Reorders the remaining tiles by the distance of their centers from `focus`, in
+    /// frame pixels. Ties keep their current order.
+    pub fn prioritize(&mut self, focus: (f32, f32)) {
+        let distance = |tile: &TileInfo| {
+            let dx = tile.offset_x as f32 + tile.tile_w as f32 / 2.0 - focus.0;
+            let dy = tile.offset_y as f32 + tile.tile_h as f32 / 2.0 - focus.1;
+            dx * dx + dy * dy
+        };
+        self.tiles.reverse();
+        self.tiles
+            .sort_by(|a, b| distance(a).total_cmp(&distance(b)));
+        self.tiles.reverse();
+        self.focus = Some(focus);
+    }
+
+    /// The point the queue was last prioritized around.
+    pub fn focus(&self) -> Option<(f32, f32)> {
+        self.focus
+    }
+
+    pub fn pop(&mut self) -> Option<TileInfo> {
+        self.tiles.pop()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+}
+
+/// A frame rendered a few tiles at a time, so a preview can show it as it fills in and
+/// drop it when the view changes. Matches [`render_image_samples`] once done.
+pub struct ProgressiveRender {
+    size: (u32, u32),
+    /// Part of the frame rendered; pixels, samples and tiles are relative to it.
+    region: TileInfo,
+    params: FractalParams,
+    cam: Camera,
+    palette: Vec<[u8; 3]>,
+    queue: TileQueue,
+    frame: Vec<u8>,
+    samples: Vec<PixelSample>,
+    stats: RenderStats,
+    /// Band contours are painted over the finished frame.
+    contours: bool,
+}
+
+impl ProgressiveRender {
+    /// Starts a `size` frame in tiles of at most `tile` pixels, in spiral order until
+    /// [`ProgressiveRender::prioritize`] is called.
+    pub fn new(
+        size: (u32, u32),
+        params: &FractalParams,
+        cam: &Camera,
+        renderer: &dyn Renderer,
+        tile: u32,
+    ) -> Result<Self, RenderError> {
+        Self::region(&TileInfo::full(size.0, size.1), params, cam, renderer, tile)
+    }
+
+    /// Starts `region` of its frame alone. Once done its pixels match the same pixels of
+    /// the whole frame, band contours along its edges included.
+    pub fn region(
+        region: &TileInfo,
+        params: &FractalParams,
+        cam: &Camera,
+        renderer: &dyn Renderer,
+        tile: u32,
+    ) -> Result<Self, RenderError> {
+        let size = (region.full_w, region.full_h);
+        check_frame_size(size)?;
+        assert!(
+            region.offset_x + region.tile_w <= size.0 && region.offset_y + region.tile_h <= size.1,
+            "region {region:?} outside its frame"
+        );
+        let area = (region.tile_w, region.tile_h);
+        let pixels = in_memory_pixels(area, 4 + std::mem::size_of::<PixelSample>() as u64)?;
+        let tiles = TileLayout::new(tile)
+            .limited_to(renderer.max_tile_size())
+            .tiles(area.0, area.1);
+        Ok(Self {
+            size,
+            region: *region,
+            // A region's palette is equalized over its whole frame.
+            params: with_equalizer(params, cam, size).into_owned(),
+            cam: cam.clone(),
+            palette: build_palette(params, 2048),
+            queue: TileQueue::new(tiles),
+            frame: vec![0; pixels * 4],
+            samples: vec![PixelSample::default(); pixels],
+            stats: RenderStats::new(renderer, area, params),
+            contours: params.has_band_contours() && !renderer.draws_band_contours(params),
+        })
+    }
+
+    /// Size of the whole frame.
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    pub fn region_info(&self) -> &TileInfo {
+        &self.region
+    }
+
+    /// The params it renders, with the frame's equalizer attached when they equalize.
+    pub fn params(&self) -> &FractalParams {
+        &self.params
+    }
+
+    pub fn camera(&self) -> &Camera {
+        &self.cam
+    }
+
+    /// Reorders the remaining tiles around `focus`, in region pixels.
+    pub fn prioritize(&mut self, focus: (f32, f32)) {
+        self.queue.prioritize(focus);
+    }
+
+    pub fn focus(&self) -> Option<(f32, f32)> {
+        self.queue.focus()
+    }
+
+    /// Renders tiles until `budget` has passed, at least one. Returns the finished tiles,
+    /// placed in the region; see [`ProgressiveRender::tile_pixels`].
+    pub fn step(
+        &mut self,
+        renderer: &mut dyn Renderer,
+        budget: Duration,
+    ) -> Result<Vec<TileInfo>, RenderError> {
+        let started = Instant::now();
+        let mut done = Vec::new();
+        while let Some(tile) = self.queue.pop() {
+            let tile_started = Instant::now();
+            let in_frame = TileInfo {
+                full_w: self.size.0,
+                full_h: self.size.1,
+                offset_x: self.region.offset_x + tile.offset_x,
+                offset_y: self.region.offset_y + tile.offset_y,
+                ..tile
+            };
+            let (pixels, samples) =
+                renderer.render_tile_samples(&in_frame, &self.params, &self.cam, &self.palette)?;
+            let ms = millis(tile_started.elapsed());
+            self.stats.tile_ms.push(ms);
+            self.stats.total_ms += ms;
+            blit_tile(&mut self.frame, self.region.tile_w, &tile, &pixels);
+            blit(&mut self.samples, self.region.tile_w, &tile, &samples, 1);
+            done.push(tile);
+            if started.elapsed() >= budget {
+                break;
+            }
+        }
+        Ok(done)
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Tiles still to render.
+    pub fn remaining(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// The RGBA8 pixels of a finished `tile`.
+    pub fn tile_pixels(&self, tile: &TileInfo) -> Vec<u8> {
+        crop(&self.frame, self.region.tile_w, tile, 4)
+    }
+
+    /// The [`PixelSample`]s of a finished `tile`.
+    pub fn tile_samples(&self, tile: &TileInfo) -> Vec<PixelSample> {
+        crop(&self.samples, self.region.tile_w, tile, 1)
+    }
+
+    /// The region's pixels, its stats (with the time spent rendering as the total) and its
+    /// samples.
+    pub fn finish(mut self) -> (Vec<u8>, RenderStats, Vec<PixelSample>) {
+        if self.contours {
+            self.paint_contours();
+        }
+        (self.frame, self.stats, self.samples)
+    }
+
+    /// Paints band contours over the finished region. Pixels on its edges are compared
+    /// with the frame pixels just outside it too, sampled on the CPU like
+    /// [`render_band`]'s.
+    fn paint_contours(&mut self) {
+        let r = self.region;
+        if (r.tile_w, r.tile_h) == self.size {
+            paint_band_contours(r.tile_w, &self.samples, 0, &self.params, &mut self.frame);
+            return;
+        }
+        // The region with a one-pixel ring; the ring's pixels outside the frame stay
+        // interior, which never draws a contour.
+        let ring = TileInfo {
+            full_w: r.tile_w + 2,
+            full_h: r.tile_h + 2,
+            offset_x: 1,
+            offset_y: 1,
+            ..r
+        };
+        let mut grown = vec![
+            PixelSample {
+                interior: true,
+                ..PixelSample::default()
+            };
+            (ring.full_w * ring.full_h) as usize
+        ];
+        blit(&mut grown, ring.full_w, &ring, &self.samples, 1);
+        let strips = [
+            (r.offset_y > 0).then_some((0, 0, ring.full_w, 1)),
+            (r.offset_y + r.tile_h < self.size.1).then_some((0, ring.full_h - 1, ring.full_w, 1)),
+            (r.offset_x > 0).then_some((0, 1, 1, r.tile_h)),
+            (r.offset_x + r.tile_w < self.size.0).then_some((ring.full_w - 1, 1, 1, r.tile_h)),
+        ];
+        for (x, y, w, h) in strips.into_iter().flatten() {
+            // Ring coordinates, clipped to the frame at the corners.
+            let x0 = (r.offset_x + x).saturating_sub(1);
+            let x1 = (r.offset_x + x + w - 1).min(self.size.0);
+            let strip = TileInfo {
+                full_w: self.size.0,
+                full_h: self.size.1,
+                offset_x: x0,
+                offset_y: r.offset_y + y - 1,
+                tile_w: x1 - x0,
+                tile_h: h,
+            };
+            let samples = sample_tile_cpu(&strip, &self.params, &self.cam);
+            let within = TileInfo {
+                offset_x: x0 + 1 - r.offset_x,
+                offset_y: y,
+                ..strip
+            };
+            blit(&mut grown, ring.full_w, &within, &samples, 1);
+        }
+        let mut frame = vec![0; grown.len() * 4];
+        blit_tile(&mut frame, ring.full_w, &ring, &self.frame);
+        paint_band_contours(ring.full_w, &grown, 0, &self.params, &mut frame);
+        self.frame = crop(&frame, ring.full_w, &ring, 4);
+    }
+}
+
+// ------------------------- Renderer backends -------------------------
+
+/// Number format a renderer iterates in; deeper zooms need more.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Precision {
+    /// Fast, and enough to a scale of about 1e5.
+    #[default]
+    F32,
+    /// Crisp to a scale of about 1e13.
+    F64,
+    /// Crisp to a scale of about 1e250: one reference orbit at full precision and every
+    /// pixel as an `f64` delta from it; see [`crate::perturbation`]. Formulas without a
+    /// delta iteration render at `F64`.
+    Perturbation,
+}
+
+impl Precision {
+    pub const ALL: [Precision; 3] = [Precision::F32, Precision::F64, Precision::Perturbation];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Precision::F32 => "f32",
+            Precision::F64 => "f64",
+            Precision::Perturbation => "perturbation",
+        }
+    }
+
+    /// Deepest magnification of a view 4 units tall that still resolves pixel by pixel.
+    pub fn zoom_limit(&self) -> f64 {
+        match self {
+            Precision::F32 => 1e5,
+            Precision::F64 => 1e13,
+            Precision::Perturbation => 1e250,
+        }
+    }
+}
+
+/// Why a renderer could not produce a tile.
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum RenderError {
+    /// The backend cannot draw these parameters, e.g. a formula without a GPU shader.
+    #[error("{0}")]
+    Unsupported(String),
+    /// The backend broke, e.g. a driver error.
+    #[error("{0}")]
+    Failed(String),
+    /// The GPU device was lost; a new renderer may work where this one never will again.
+    #[error("GPU device lost: {0}")]
+    DeviceLost(String),
+    /// The frame is empty, or too large to render or to hold in memory.
+    #[error("{0}")]
+    FrameSize(String),
+}
+
+/// A backend that turns fractal parameters into RGBA8 tiles.
+pub trait Renderer {
+    /// Short name for messages, e.g. "CPU".
+    fn name(&self) -> &'static str;
+
+    /// Renders `tile` as `tile_w * tile_h` RGBA8 pixels, rows top to bottom. `palette` is
+    /// the parameters' palette from [`build_palette`]; backends that keep their own baked
+    /// copy may ignore it.
+    fn render_tile(
+        &mut self,
+        tile: &TileInfo,
+        params: &FractalParams,
+        cam: &Camera,
+        palette: &[[u8; 3]],
+    ) -> Result<Vec<u8>, RenderError>;
+
+    /// Largest tile side rendered at once; 0 means no limit.
+    fn max_tile_size(&self) -> u32 {
+        0
+    }
+
+    /// Most precise number format the backend iterates in; [`FractalParams::precision`]
+    /// above it is rendered at this one.
+    fn precision(&self) -> Precision {
+        Precision::F32
+    }
+
+    /// Whether [`Renderer::render_tile`] can draw `params` at all.
+    fn supports(&self, _params: &FractalParams) -> bool {
+        true
+    }
+
+    /// [`Renderer::render_tile`] plus the tile's [`PixelSample`]s for compositing passes.
+    /// By default the samples come from a second pass on the CPU.
+    fn render_tile_samples(
+        &mut self,
+        tile: &TileInfo,
+        params: &FractalParams,
+        cam: &Camera,
+        palette: &[[u8; 3]],
+    ) -> Result<(Vec<u8>, Vec<PixelSample>), RenderError> {
+        let pixels = self.render_tile(tile, params, cam, palette)?;
+        Ok((pixels, sample_tile_cpu(tile, params, cam)))
+    }
+
+    /// Colors `samples` of `tile` taken earlier, without iterating. Must match what
+    /// [`Renderer::render_tile`] draws for the same pixels. Shades on the CPU by default.
+    fn shade_tile(
+        &mut self,
+        tile: &TileInfo,
+        samples: &[PixelSample],
+        params: &FractalParams,
+        palette: &[[u8; 3]],
+    ) -> Result<Vec<u8>, RenderError> {
+        Ok(shade_tile_cpu(tile, samples, params, palette))
+    }
+
+    /// Whether the tiles it draws for `params` already have their dwell-band contours.
+    /// Otherwise the frame functions paint them over the whole frame with
+    /// [`paint_band_contours`], since they depend on neighboring pixels.
+    fn draws_band_contours(&self, _params: &FractalParams) -> bool {
+        false
+    }
+
+    /// Why this renderer stopped using its preferred backend, if it did; see
+    /// [`FallbackRenderer`].
+    fn demotion(&self) -> Option<&Demotion> {
+        None
+    }
+
+    /// Gives a demoted backend another chance.
+    fn retry_primary(&mut self) -> Result<(), RenderError> {
+        Ok(())
+    }
+}
+
+/// Multithreaded CPU rendering; handles every formula and never fails.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CpuRenderer;
+
+impl Renderer for CpuRenderer {
+    fn name(&self) -> &'static str {
+        "CPU"
+    }
+
+    fn render_tile(
+        &mut self,
+        tile: &TileInfo,
+        params: &FractalParams,
+        cam: &Camera,
+        palette: &[[u8; 3]],
+    ) -> Result<Vec<u8>, RenderError> {
+        Ok(render_tile_cpu(tile, params, cam, palette))
+    }
+
+    fn precision(&self) -> Precision {
+        Precision::Perturbation
+    }
+
+    /// Colors the samples it already has instead of iterating twice.
+    fn render_tile_samples(
+        &mut self,
+        tile: &TileInfo,
+        params: &FractalParams,
+        cam: &Camera,
+        palette: &[[u8; 3]],
+    ) -> Result<(Vec<u8>, Vec<PixelSample>), RenderError> {
+        let attached = with_reference(params, cam, self.precision());
+        let equalized = with_equalizer(&attached, cam, (tile.full_w, tile.full_h));
+        let params = &*equalized;
+        let bands = in_bands(tile, |part| render_band_cpu(part, params, cam, palette));
+        let (pixels, samples): (Vec<_>, Vec<_>) = bands.into_iter().unzip();
+        Ok((pixels.concat(), samples.concat()))
+    }
+}
+
+/// Consecutive failures after which [`FallbackRenderer`] stops trying its primary renderer.
+pub const PRIMARY_FAILURE_LIMIT: u32 = 3;
+
+/// Builds a fresh primary renderer after its device was lost.
+pub type RecreateRenderer = Box<dyn FnMut() -> Result<Box<dyn Renderer>, RenderError> + Send>;
+
+/// Why a [`FallbackRenderer`] switched to its fallback for good.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Demotion {
+    /// Name of the renderer that was given up on.
+    pub renderer: &'static str,
+    /// Failures in a row that led to it.
+    pub failures: u32,
+    /// The last of them.
+    pub error: RenderError,
+}
+
+/// Renders with `primary`, switching to `fallback` for tiles it does not support or fails.
+/// After [`PRIMARY_FAILURE_LIMIT`] failures in a row, or a lost device that could not be
+/// recreated, every tile goes to `fallback` until [`Renderer::retry_primary`].
+pub struct FallbackRenderer {
+    primary: Box<dyn Renderer>,
+    fallback: Box<dyn Renderer>,
+    recreate: Option<RecreateRenderer>,
+    /// Primary failures since its last success.
+    failures: u32,
+    /// Whether the primary was already recreated since its last success.
+    recreated: bool,
+    demoted: Option<Demotion>,
+    /// Kind the primary last could not draw, reported once until it draws again.
+    unsupported: Option<FractalKind>,
+}
+
+impl FallbackRenderer {
+    pub fn new(primary: Box<dyn Renderer>, fallback: Box<dyn Renderer>) -> Self {
+        Self {
+            primary,
+            fallback,
+            recreate: None,
+            failures: 0,
+            recreated: false,
+            demoted: None,
+            unsupported: None,
+        }
+    }
+
+    /// Recreates the primary renderer once when it reports [`RenderError::DeviceLost`],
+    /// before giving up on it.
+    pub fn with_recreate(mut self, recreate: RecreateRenderer) -> Self {
+        self.recreate = Some(recreate);
+        self
+    }
+
+    /// Draws with the primary renderer unless it is demoted or cannot draw `params`, and
+    /// with the fallback otherwise or when it fails.
+    fn dispatch<T>(
+        &mut self,
+        params: &FractalParams,
+        mut draw: impl FnMut(&mut dyn Renderer) -> Result<T, RenderError>,
+    ) -> Result<T, RenderError> {
+        if self.demoted.is_some() {
+            return draw(self.fallback.as_mut());
+        }
+        if !self.primary.supports(params) {
+            if self.unsupported != Some(params.kind) {
+                eprintln!(
+                    "{} cannot draw {} with these settings, using {}",
+                    self.primary.name(),
+                    params.kind.label(),
+                    self.fallback.name()
+                );
+                self.unsupported = Some(params.kind);
+            }
+            return draw(self.fallback.as_mut());
+        }
+        self.unsupported = None;
+        match draw(self.primary.as_mut()) {
+            Ok(out) => {
+                self.failures = 0;
+                self.recreated = false;
+                Ok(out)
+            }
+            Err(err) => {
+                self.primary_failed(err);
+                draw(self.fallback.as_mut())
+            }
+        }
+    }
+
+    fn primary_failed(&mut self, err: RenderError) {
+        let name = self.primary.name();
+        self.failures += 1;
+        eprintln!(
+            "{name} render failed ({} in a row), falling back to {}: {err}",
+            self.failures,
+            self.fallback.name()
+        );
+        if matches!(err, RenderError::DeviceLost(_)) {
+            if let Some(recreate) = self.recreate.as_mut().filter(|_| !self.recreated) {
+                self.recreated = true;
+                match recreate() {
+                    Ok(primary) => {
+                        eprintln!("{name} renderer recreated after the device was lost");
+                        self.primary = primary;
+                        self.failures = 0;
+                        return;
+                    }
+                    Err(e) => eprintln!("Could not recreate the {name} renderer: {e}"),
+                }
+            }
+            self.demote(err);
+        } else if self.failures >= PRIMARY_FAILURE_LIMIT {
+            self.demote(err);
+        }
+    }
+
+    fn demote(&mut self, error: RenderError) {
+        let demotion = Demotion {
+            renderer: self.primary.name(),
+            failures: self.failures,
+            error,
+        };
+        eprintln!(
+            "{} disabled after {} failures in a row, using {} from now on: {}",
+            demotion.renderer,
+            demotion.failures,
+            self.fallback.name(),
+            demotion.error
+        );
+        self.demoted = Some(demotion);
+    }
+}
+
+impl Renderer for FallbackRenderer {
+    /// The primary's name, or the fallback's once demoted.
+    fn name(&self) -> &'static str {
+        match self.demoted {
+            Some(_) => self.fallback.name(),
+            None => self.primary.name(),
+        }
+    }
+
+    fn render_tile(
+        &mut self,
+        tile: &TileInfo,
+        params: &FractalParams,
+        cam: &Camera,
+        palette: &[[u8; 3]],
+    ) -> Result<Vec<u8>, RenderError> {
+        self.dispatch(params, |r| r.render_tile(tile, params, cam, palette))
+    }
+
+    fn render_tile_samples(
+        &mut self,
+        tile: &TileInfo,
+        params: &FractalParams,
+        cam: &Camera,
+        palette: &[[u8; 3]],
+    ) -> Result<(Vec<u8>, Vec<PixelSample>), RenderError> {
+        self.dispatch(params, |r| {
+            r.render_tile_samples(tile, params, cam, palette)
+        })
+    }
+
+    /// Samples may have come from either renderer; the CPU colors them like both do.
+    fn shade_tile(
+        &mut self,
+        tile: &TileInfo,
+        samples: &[PixelSample],
+        params: &FractalParams,
+        palette: &[[u8; 3]],
+    ) -> Result<Vec<u8>, RenderError> {
+        self.dispatch(params, |r| r.shade_tile(tile, samples, params, palette))
+    }
+
+    /// Asks whichever renderer tiles of `params` go to while the primary keeps working.
+    fn draws_band_contours(&self, params: &FractalParams) -> bool {
+        if self.demoted.is_some() || !self.primary.supports(params) {
+            self.fallback.draws_band_contours(params)
+        } else {
+            self.primary.draws_band_contours(params)
+        }
+    }
+
+    fn demotion(&self) -> Option<&Demotion> {
+        self.demoted.as_ref()
+    }
+
+    /// Clears the demotion, recreating the primary first if its device was lost.
+    fn retry_primary(&mut self) -> Result<(), RenderError> {
+        let Some(demotion) = self.demoted.take() else {
+            return Ok(());
+        };
+        if matches!(demotion.error, RenderError::DeviceLost(_)) {
+            if let Some(recreate) = self.recreate.as_mut() {
+                match recreate() {
+                    Ok(primary) => self.primary = primary,
+                    Err(e) => {
+                        self.demoted = Some(Demotion {
+                            error: e.clone(),
+                            ..demotion
+                        });
+                        return Err(e);
+                    }
+                }
+            }
+        }
+        self.failures = 0;
+        self.recreated = false;
+        Ok(())
+    }
+
+    /// Tiles may land on either renderer, so both limits apply.
+    fn max_tile_size(&self) -> u32 {
+        match (self.primary.max_tile_size(), self.fallback.max_tile_size()) {
+            (0, limit) | (limit, 0) => limit,
+            (a, b) => a.min(b),
+        }
+    }
+
+    fn precision(&self) -> Precision {
+        self.primary.precision().min(self.fallback.precision())
+    }
+
+    fn supports(&self, params: &FractalParams) -> bool {
+        self.primary.supports(params) || self.fallback.supports(params)
+    }
+}
+
+/// The renderer for `backend`. The GPU renderer falls back to the CPU per tile, and for the
+/// rest of the session once it keeps failing; creating it fails when no GPU device is
+/// available.
+pub fn create_renderer(backend: RenderBackend) -> Result<Box<dyn Renderer>, RenderError> {
+    match backend {
+        RenderBackend::Cpu => Ok(Box::new(CpuRenderer)),
+        #[cfg(feature = "gpu")]
+        RenderBackend::Gpu => {
+            fn gpu() -> Result<Box<dyn Renderer>, RenderError> {
+                Ok(Box::new(GpuRenderer::new().map_err(RenderError::Failed)?))
+            }
+            Ok(Box::new(
+                FallbackRenderer::new(gpu()?, Box::new(CpuRenderer)).with_recreate(Box::new(gpu)),
+            ))
+        }
+    }
+}
+
+/// Worker threads used by the CPU renderer; 0 means one per available core.
+static RENDER_THREADS: AtomicUsize = AtomicUsize::new(0);
+
+/// Sets the CPU worker thread count for all subsequent renders; 0 means automatic.
+pub fn set_render_threads(threads: usize) {
+    RENDER_THREADS.store(threads, AtomicOrdering::Relaxed);
+}
+
+/// Effective CPU worker thread count. Always 1 in the browser, which has no threads.
+pub fn render_threads() -> usize {
+    if cfg!(target_arch = "wasm32") {
+        return 1;
+    }
+    match RENDER_THREADS.load(AtomicOrdering::Relaxed) {
+        0 => thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    }
+}
+
+/// Renders a tile on the CPU, splitting its rows into bands across worker threads.
+pub fn render_tile_cpu(
+    tile: &TileInfo,
+    params: &FractalParams,
+    cam: &Camera,
+    palette: &[[u8; 3]],
+) -> Vec<u8> {
+    let attached = with_reference(params, cam, Precision::Perturbation);
+    let equalized = with_equalizer(&attached, cam, (tile.full_w, tile.full_h));
+    let params = &*equalized;
+    in_bands(tile, |part| render_band_cpu(part, params, cam, palette).0).concat()
+}
+
+/// The [`PixelSample`]s of a tile, computed on the CPU like [`render_tile_cpu`].
+pub fn sample_tile_cpu(tile: &TileInfo, params: &FractalParams, cam: &Camera) -> Vec<PixelSample> {
+    let attached = with_reference(params, cam, Precision::Perturbation);
+    let offsets = attached.sample_offsets();
+    let kept = offsets[central_sample(&offsets)];
+    in_bands(tile, |part| sample_fractal_cpu(part, &attached, cam, kept)).concat()
+}
+
+/// Both passes of a CPU render over `tile`. A supersampled pixel averages the colors of
+/// all its samples and keeps the one nearest its middle as its [`PixelSample`].
+fn render_band_cpu(
+    tile: &TileInfo,
+    p: &FractalParams,
+    cam: &Camera,
+    palette: &[[u8; 3]],
+) -> (Vec<u8>, Vec<PixelSample>) {
+    let offsets = p.sample_offsets();
+    if let [offset] = offsets[..] {
+        let samples = sample_fractal_cpu(tile, p, cam, offset);
+        return (shade_samples(&samples, p, palette), samples);
+    }
+    let central = central_sample(&offsets);
+    let mut sums = vec![0u32; (tile.tile_w * tile.tile_h * 4) as usize];
+    let mut kept = Vec::new();
+    for (i, &offset) in offsets.iter().enumerate() {
+        let samples = sample_fractal_cpu(tile, p, cam, offset);
+        for (sum, &channel) in sums.iter_mut().zip(&shade_samples(&samples, p, palette)) {
+            *sum += u32::from(channel);
+        }
+        if i == central {
+            kept = samples;
+        }
+    }
+    let count = offsets.len() as u32;
+    let pixels = sums
+        .into_iter()
+        .map(|sum| ((sum + count / 2) / count) as u8);
+    (pixels.collect(), kept)
+}
+
+/// Colors the samples of `tile` on worker threads, as the CPU renderer's second pass.
+pub fn shade_tile_cpu(
+    tile: &TileInfo,
+    samples: &[PixelSample],
+    params: &FractalParams,
+    palette: &[[u8; 3]],
+) -> Vec<u8> {
+    in_bands(tile, |part| {
+        let start = ((part.offset_y - tile.offset_y) * tile.tile_w) as usize;
+        let len = (part.tile_w * part.tile_h) as usize;
+        shade_samples(&samples[start..start + len], params, palette)
+    })
+    .concat()
+}
+
+/// Runs `band` on horizontal slices of `tile`, one per worker thread, in top to bottom
+/// order.
+fn in_bands<R: Send>(tile: &TileInfo, band: impl Fn(&TileInfo) -> R + Sync) -> Vec<R> {
+    let bands = (render_threads() as u32).clamp(1, tile.tile_h.max(1));
+    if bands == 1 {
+        return vec![band(tile)];
+    }
+    let rows = tile.tile_h.div_ceil(bands);
+    let parts: Vec<TileInfo> = (0..tile.tile_h)
+        .step_by(rows as usize)
+        .map(|y| TileInfo {
+            offset_y: tile.offset_y + y,
+            tile_h: rows.min(tile.tile_h - y),
+            ..*tile
+        })
+        .collect();
+    let band = &band;
+    thread::scope(|scope| {
+        let handles: Vec<_> = parts
+            .iter()
+            .map(|part| scope.spawn(move || band(part)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("CPU render thread panicked"))
+            .collect()
+    })
+}
+
+/// Final state of a single point's orbit after escaping or hitting `max_iter`.
+#[derive(Debug, Clone, Copy)]
+pub struct OrbitResult {
+    pub iter: u32,
+    pub zx: f32,
+    pub zy: f32,
+    pub trap_min: f32,
+    /// Distance estimate in plane units when [`FractalParams::estimates_distance`]; in
+    /// double precision, as deep zooms place it far below `f32`'s range.
+    pub distance: Option<f64>,
+    /// Averages of the stripe function over the orbit with its final iterate and without,
+    /// under [`ColoringMode::StripeAverage`](crate::ColoringMode); see [`stripe_average`].
+    pub stripe: Option<[f32; 2]>,
+    /// For formulas that converge rather than escape, like [`FractalKind::Newton`], the
+    /// index of the root an orbit that counts as escaped reached, and its final distance
+    /// from it.
+    pub root: Option<(u32, f64)>,
+    /// Lyapunov exponent under [`FractalKind::Lyapunov`], which has no orbit in `z`.
+    pub lyapunov: Option<f32>,
+}
+
+impl OrbitResult {
+    pub fn escaped(&self, p: &FractalParams) -> bool {
+        self.iter < p.max_iter
+    }
+}
+
+/// Iterates the selected formula for the plane point (`rx`, `ry`).
+pub fn iterate_point(p: &FractalParams, rx: f32, ry: f32) -> OrbitResult {
+    formula(p.kind).iterate(p, rx, ry)
+}
+
+/// [`iterate_point`] in double precision.
+pub fn iterate_point_f64(p: &FractalParams, rx: f64, ry: f64) -> OrbitResult {
+    formula(p.kind).iterate_f64(p, rx, ry)
+}
+
+/// What the CPU renderer knows about a pixel before coloring it; the source of the
+/// compositing passes in [`crate::aov`].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PixelSample {
+    /// Smooth (fractional) iteration count at escape; 0 for interior points.
+    pub smooth_iter: f32,
+    /// The orbit never escaped.
+    pub interior: bool,
+    /// Closest approach of the orbit to the orbit trap.
+    pub trap_min: f32,
+    /// Palette position, 0..1, of an interior point under
+    /// [`FractalParams::interior_coloring`]; 0 for escaped points.
+    pub interior_shade: f32,
+    /// Estimated distance to the set in pixels, when
+    /// [`FractalParams::estimates_distance`].
+    pub distance: Option<f32>,
+    /// Palette position, 0..1, of an escaped point's [`stripe_average`] under
+    /// [`ColoringMode::StripeAverage`](crate::ColoringMode).
+    pub stripe: Option<f32>,
+    /// Index of the root a converging formula's orbit reached, which picks its palette
+    /// segment; see [`OrbitResult::root`].
+    pub root: Option<u32>,
+    /// Lyapunov exponent; see [`OrbitResult::lyapunov`].
+    pub lyapunov: Option<f32>,
+}
+
+impl PixelSample {
+    /// How much of the orbit-trap color is mixed over the palette color; 0 with the trap off.
+    pub fn trap_weight(&self, p: &FractalParams) -> f32 {
+        if !p.orbit.enabled {
+            return 0.0;
+        }
+        (-self.trap_min * p.orbit.softness).exp().clamp(0.0, 1.0)
+    }
+}
+
+impl FractalParams {
+    /// Whether `other` gives the same [`PixelSample`]s as these params, differing at most
+    /// in coloring: palette, phase, repeat, exposure, gamma and the trap's color and
+    /// softness. Never for supersampled params, whose pixels blend samples not kept.
+    pub fn same_samples(&self, other: &FractalParams) -> bool {
+        let (a, b) = (&self.orbit, &other.orbit);
+        self.supersample_side() == 1
+            && other.supersample_side() == 1
+            && self.kind == other.kind
+            && self.max_iter == other.max_iter
+            && self.escape_radius == other.escape_radius
+            && self.power == other.power
+            && self.c == other.c
+            && self.phoenix_p == other.phoenix_p
+            && self.lyapunov_sequence == other.lyapunov_sequence
+            && self.lyapunov_warmup == other.lyapunov_warmup
+            && self.custom_formula == other.custom_formula
+            && self.interior_coloring == other.interior_coloring
+            && self.coloring == other.coloring
+            && self.stripe_density == other.stripe_density
+            && self.legacy_burning_ship == other.legacy_burning_ship
+            && self.sampling == other.sampling
+            && self.precision == other.precision
+            && a.enabled == b.enabled
+            && (!a.enabled || (a.kind == b.kind && a.point == b.point && a.radius == b.radius))
+    }
+}
+
+/// First pass of a CPU render: iterates every pixel of `tile`, rows top to bottom, at
+/// `offset` within the pixel. The middle, (0.5, 0.5), is the pixel's own point.
+fn sample_fractal_cpu(
+    tile: &TileInfo,
+    p: &FractalParams,
+    cam: &Camera,
+    offset: [f32; 2],
+) -> Vec<PixelSample> {
+    let compiled = with_compiled_formula(p);
+    let sampler = PointSampler::new(&compiled, cam, (tile.full_w, tile.full_h));
+    let mut samples = Vec::with_capacity((tile.tile_w * tile.tile_h) as usize);
+    for y in 0..tile.tile_h {
+        let global_y = (tile.offset_y + y) as f32 + (offset[1] - 0.5);
+        for x in 0..tile.tile_w {
+            let global_x = (tile.offset_x + x) as f32 + (offset[0] - 0.5);
+            samples.push(sampler.sample(global_x, global_y));
+        }
+    }
+    samples
+}
+
+/// Iterates arbitrary `points` of a `size` frame on worker threads, in order. Points are
+/// in pixels from the frame's top-left corner, with pixel (x, y) itself at (x, y).
+pub(crate) fn sample_points_cpu(
+    points: &[[f32; 2]],
+    size: (u32, u32),
+    p: &FractalParams,
+    cam: &Camera,
+) -> Vec<PixelSample> {
+    let attached = with_reference(p, cam, Precision::Perturbation);
+    let compiled = with_compiled_formula(&attached);
+    let sampler = PointSampler::new(&compiled, cam, size);
+    let chunk = points.len().div_ceil(render_threads()).max(1);
+    let sampler = &sampler;
+    thread::scope(|scope| {
+        let handles: Vec<_> = points
+            .chunks(chunk)
+            .map(|part| {
+                scope.spawn(move || part.iter().map(|&[x, y]| sampler.sample(x, y)).collect())
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| -> Vec<_> { handle.join().expect("CPU render thread panicked") })
+            .collect()
+    })
+}
+
+/// Maps frame positions to the plane and iterates them, at the precision `p` asks for.
+struct PointSampler<'a> {
+    p: &'a FractalParams,
+    cam: &'a Camera,
+    half: (f32, f32),
+    cosr: f32,
+    sinr: f32,
+    scale: f32,
+    center: Complex,
+    /// The reference orbit and its offset from the center, with perturbation.
+    reference: Option<(&'a ReferenceOrbit, Complex64)>,
+}
+
+impl<'a> PointSampler<'a> {
+    fn new(p: &'a FractalParams, cam: &'a Camera, size: (u32, u32)) -> Self {
+        Self {
+            p,
+            cam,
+            half: ((size.0 as f32) / 2.0, (size.1 as f32) / 2.0),
+            cosr: cam.rotation.cos(),
+            sinr: cam.rotation.sin(),
+            scale: cam.scale as f32,
+            center: cam.center.to_f32(),
+            // The pixel offsets below are relative to the center, the reference's to its
+            // point.
+            reference: p
+                .reference
+                .as_deref()
+                .filter(|_| needs_reference(p, Precision::Perturbation))
+                .map(|reference| (reference, reference.center_offset(cam))),
+        }
+    }
+
+    /// The sample at `x`, `y` pixels from the frame's top-left corner.
+    fn sample(&self, x: f32, y: f32) -> PixelSample {
+        let Self {
+            p,
+            cam,
+            cosr,
+            sinr,
+            scale,
+            center,
+            ..
+        } = *self;
+        let (u, v) = (x - self.half.0, y - self.half.1);
+        let orbit = match p.precision {
+            Precision::F32 => {
+                let rx = (u * cosr - v * sinr) / scale + center.re;
+                let ry = (u * sinr + v * cosr) / scale + center.im;
+                iterate_point(p, rx, ry)
+            }
+            Precision::F64 | Precision::Perturbation => {
+                let (u, v) = (f64::from(u), f64::from(v));
+                let (cosr, sinr) = (f64::from(cosr), f64::from(sinr));
+                let dx = (u * cosr - v * sinr) / cam.scale;
+                let dy = (u * sinr + v * cosr) / cam.scale;
+                match self.reference {
+                    Some((reference, offset)) => iterate_perturbed(
+                        p,
+                        reference,
+                        Complex64::new(offset.re + dx, offset.im + dy),
+                    ),
+                    None => iterate_point_f64(p, dx + cam.center.re, dy + cam.center.im),
+                }
+            }
+        };
+        let (smooth_iter, interior_shade) = if orbit.escaped(p) {
+            (smooth_iteration(p, &orbit), 0.0)
+        } else {
+            (0.0, interior_shade(p, &orbit))
+        };
+        PixelSample {
+            smooth_iter,
+            interior: !orbit.escaped(p),
+            trap_min: orbit.trap_min,
+            interior_shade,
+            distance: orbit.distance.map(|d| (d * cam.scale) as f32),
+            stripe: stripe_average(p, &orbit),
+            root: orbit.root.map(|(k, _)| k),
+            lyapunov: orbit.lyapunov,
+        }
+    }
+}
+
+/// Continuous iteration count of an escaped orbit, as its formula
+/// [counts it](crate::FractalFormula::smooth_iteration).
+pub fn smooth_iteration(p: &FractalParams, orbit: &OrbitResult) -> f32 {
+    formula(p.kind).smooth_iteration(p, orbit)
+}
+
+/// Continuous iteration count of an escaped orbit, renormalized against the actual escape
+/// radius and the formula's `degree`: between `iter` and `iter + 1`, and continuous where
+/// `iter` steps up.
+pub fn escape_smooth_iteration(p: &FractalParams, orbit: &OrbitResult, degree: f32) -> f32 {
+    // |z| itself, not its square, which leaves f32 at high powers and escape radii; an
+    // orbit that overflowed anyway counts as the largest finite |z|.
+    let log_r = orbit.zx.hypot(orbit.zy).max(1e-10).ln().min(f32::MAX.ln());
+    let log_bailout = p.escape_radius.max(1.01).ln();
+    let log_degree = degree.max(1.01).ln();
+    // ln|z| / ln R, which is just past 1 at escape; its log is clamped at 0 so |z| at or
+    // below the bailout can't give NaN.
+    (orbit.iter as f32) + 1.0 - (log_r / log_bailout).max(1.0).ln() / log_degree
+}
+
+/// Stripe average of an escaped orbit under [`ColoringMode::StripeAverage`](crate::ColoringMode), 0..1: the
+/// averages with and without the final iterate, interpolated by the fractional part of
+/// the smooth iteration count so that it stays continuous where `iter` steps up.
+pub fn stripe_average(p: &FractalParams, orbit: &OrbitResult) -> Option<f32> {
+    let [with, without] = orbit.stripe.filter(|_| orbit.escaped(p))?;
+    let fraction = (smooth_iteration(p, orbit) - orbit.iter as f32).clamp(0.0, 1.0);
+    Some(Interp::lerp(without, with, fraction))
+}
+
+/// Position in the palette, 0..1, of an orbit that never escaped, as
+/// [`FractalParams::interior_coloring`] asks; 0 for the flat modes.
+pub fn interior_shade(p: &FractalParams, orbit: &OrbitResult) -> f32 {
+    let radius = p.escape_radius.max(1e-3);
+    match p.interior_coloring {
+        InteriorColoring::Palette | InteriorColoring::Solid => 0.0,
+        InteriorColoring::Magnitude => (orbit.zx.hypot(orbit.zy) / radius).min(1.0),
+        InteriorColoring::Argument => orbit.zy.atan2(orbit.zx) / std::f32::consts::TAU + 0.5,
+        InteriorColoring::TrapDistance => (orbit.trap_min / radius).min(1.0),
+    }
+}
+
+/// Position of `smooth_iter` in the palette, 0..1, before the phase is applied. With
+/// dwell bands, the start of its band.
+pub fn palette_position(p: &FractalParams, smooth_iter: f32) -> f32 {
+    if p.band_count > 0 {
+        return (dwell_band(p, smooth_iter) / p.band_count as f32).fract();
+    }
+    palette_trips(p, smooth_iter).fract()
+}
+
+/// Trips through the palette `smooth_iter` is from iteration 0: one every
+/// [`FractalParams::palette_repeat`] iterations, or less than one, its rank in the frame,
+/// with an attached [`FractalParams::equalizer`].
+fn palette_trips(p: &FractalParams, smooth_iter: f32) -> f32 {
+    match p.equalizer.as_deref().filter(|_| p.equalizes()) {
+        Some(equalizer) => equalizer.position(smooth_iter),
+        None => smooth_iter / p.palette_repeat.max(1e-3),
+    }
+}
+
+/// Doublings of the distance to the set one trip through the palette spans under
+/// [`ColoringMode::DistanceEstimate`](crate::ColoringMode).
+pub const DISTANCE_OCTAVES: f32 = 8.0;
+
+/// Position in the palette, 0..1, of a point `pixels` from the set: the palette's start
+/// at the set's edge, and one trip every [`DISTANCE_OCTAVES`] doublings out from it.
+pub fn distance_position(pixels: f32) -> f32 {
+    ((1.0 + pixels.max(0.0)).log2() / DISTANCE_OCTAVES).fract()
+}
+
+/// Position in the palette, 0..1, of a Lyapunov exponent: stable (negative) exponents in
+/// the first half and chaotic (positive) ones in the second, meeting at 0.
+pub fn lyapunov_position(exponent: f32) -> f32 {
+    if exponent.is_nan() {
+        return 0.5;
+    }
+    0.5 + 0.5 * exponent.tanh()
+}
+
+/// Which of the [`FractalParams::band_count`] bands per palette trip `smooth_iter` falls
+/// in, counted from iteration 0 so bands stay apart across trips.
+pub fn dwell_band(p: &FractalParams, smooth_iter: f32) -> f32 {
+    (palette_trips(p, smooth_iter) * p.band_count as f32).floor()
+}
+
+/// Paints [`FractalParams::contour_color`] over the edges between dwell bands. `samples`
+/// are whole rows of a `width`-wide frame and `frame` holds the RGBA8 pixels of those rows
+/// from `first_row` on. An escaped pixel is on an edge when an escaped 4-neighbor is in a
+/// higher band, so lines are one pixel wide and the same however the frame was tiled.
+pub fn paint_band_contours(
+    width: u32,
+    samples: &[PixelSample],
+    first_row: u32,
+    p: &FractalParams,
+    frame: &mut [u8],
+) {
+    let width = width as usize;
+    let rows = samples.len() / width.max(1);
+    let band = |i: usize| (!samples[i].interior).then(|| dwell_band(p, samples[i].smooth_iter));
+    let contour = p.contour_color.map(|c| (c * 255.0) as u8);
+    for (i, pixel) in frame.chunks_exact_mut(4).enumerate() {
+        let at = i + first_row as usize * width;
+        let Some(own) = band(at) else {
+            continue;
+        };
+        let (x, y) = (at % width, at / width);
+        let neighbors = [
+            (x > 0).then(|| at - 1),
+            (x + 1 < width).then(|| at + 1),
+            (y > 0).then(|| at - width),
+            (y + 1 < rows).then(|| at + width),
+        ];
+        if neighbors
+            .into_iter()
+            .flatten()
+            .any(|n| band(n).is_some_and(|other| other > own))
+        {
+            pixel.copy_from_slice(&[contour[0], contour[1], contour[2], 255]);
+        }
+    }
+}
+
+/// The tone curve palette colors go through on their way to the screen: exposure, then
+/// gamma. Takes and returns a 0..1 channel value.
+pub fn tone_map(value: f32, exposure: f32, gamma: f32) -> f32 {
+    (-(-value * exposure).exp_m1()).powf(1.0 / gamma)
+}
+
+/// The stored 0..1 channel value that [`tone_map`] takes closest to `output`. Outputs
+/// brighter than a stored 1 can reach come back as 1.
+pub fn inverse_tone_map(output: f32, exposure: f32, gamma: f32) -> f32 {
+    if exposure <= 0.0 {
+        return 0.0;
+    }
+    let linear = output.clamp(0.0, 1.0).powf(gamma);
+    (-(-linear).ln_1p() / exposure).clamp(0.0, 1.0)
+}
+
+impl FractalParams {
+    /// How palette color `rgb` shows on screen under this exposure and gamma.
+    pub fn tone_map_rgb(&self, rgb: [f32; 3]) -> [f32; 3] {
+        rgb.map(|c| tone_map(c, self.exposure, self.gamma))
+    }
+
+    /// The palette color that shows as `rgb` on screen; see [`inverse_tone_map`].
+    pub fn inverse_tone_map_rgb(&self, rgb: [f32; 3]) -> [f32; 3] {
+        rgb.map(|c| inverse_tone_map(c, self.exposure, self.gamma))
+    }
+}
+
+/// Palette position, 0..1, of the middle of a root's segment: the palette splits evenly
+/// among the [roots](newton_roots).
+fn root_position(p: &FractalParams, root: u32) -> f32 {
+    (root as f32 + 0.5) / newton_roots(p) as f32
+}
+
+/// Second pass of a CPU render: colors samples as RGBA8 pixels.
+pub(crate) fn shade_samples(
+    samples: &[PixelSample],
+    p: &FractalParams,
+    palette: &[[u8; 3]],
+) -> Vec<u8> {
+    let mut pixels = Vec::with_capacity(samples.len() * 4);
+    for sample in samples {
+        let position = match (sample.lyapunov, sample.root, sample.distance) {
+            _ if sample.interior => sample.interior_shade,
+            (Some(exponent), _, _) => lyapunov_position(exponent),
+            (None, Some(root), _) => root_position(p, root),
+            (None, None, Some(pixels)) => distance_position(pixels),
+            (None, None, None) => palette_position(p, sample.smooth_iter),
+        };
+        let mut col = sample_palette(palette, position).map(|c| c as f32 / 255.0);
+        if !sample.interior && sample.root.is_some() {
+            let brightness = newton_brightness(sample.smooth_iter);
+            col = col.map(|c| c * brightness);
+        }
+        if let Some(stripe) = sample.stripe {
+            let stripes = sample_palette(palette, stripe);
+            for (c, s) in col.iter_mut().zip(stripes) {
+                *c = Interp::lerp(*c, s as f32 / 255.0, p.stripe_mix);
+            }
+        }
+        let [mut r, mut g, mut b] = p.tone_map_rgb(col);
+
+        let mut alpha = 1.0;
+        if sample.interior && p.interior_coloring == InteriorColoring::Solid {
+            [r, g, b] = p.interior_color;
+        } else if !sample.interior {
+            match p.exterior_mode {
+                ExteriorMode::Palette => {}
+                ExteriorMode::Solid => [r, g, b] = p.exterior_color,
+                ExteriorMode::Transparent => {
+                    [r, g, b] = p.exterior_color;
+                    alpha = 0.0;
+                }
+            }
+        }
+
+        if p.orbit.enabled {
+            let mut trap = sample.trap_weight(p);
+            if alpha < 1.0 {
+                // Over a transparent exterior the tint keeps its color and its weight
+                // becomes coverage.
+                let over = trap + alpha * (1.0 - trap);
+                trap = if over > 0.0 { trap / over } else { 0.0 };
+                alpha = over;
+            }
+            r = Interp::lerp(r, p.orbit.color[0], trap);
+            g = Interp::lerp(g, p.orbit.color[1], trap);
+            b = Interp::lerp(b, p.orbit.color[2], trap);
+        }
+
+        pixels.extend([
+            (r * 255.0) as u8,
+            (g * 255.0) as u8,
+            (b * 255.0) as u8,
+            (alpha * 255.0) as u8,
+        ]);
+    }
+    pixels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bake_palette, palette_key, ColoringMode, Complex, Complex64, FractalKind,
+        SEAHORSE_DEEP_POINT,
+    };
+
+    /// How many tiles cover each pixel.
+    fn coverage(w: u32, h: u32, tiles: &[TileInfo]) -> Vec<u8> {
+        let mut hits = vec![0u8; (w * h) as usize];
+        for t in tiles {
+            assert!(
+                t.offset_x + t.tile_w <= w && t.offset_y + t.tile_h <= h,
+                "{t:?}"
+            );
+            for y in t.offset_y..t.offset_y + t.tile_h {
+                let row = (y * w) as usize;
+                for hit in
+                    &mut hits[row + t.offset_x as usize..row + (t.offset_x + t.tile_w) as usize]
+                {
+                    *hit += 1;
+                }
+            }
+        }
+        hits
+    }
+
+    const AWKWARD: [(u32, u32, u32); 6] = [
+        (8191, 4097, 512),
+        (700, 530, 256),
+        (1000, 257, 300),
+        (513, 513, 512),
+        (4097, 1, 256),
+        (9000, 300, 0),
+    ];
+
+    #[test]
+    fn tiles_cover_every_pixel_once() {
+        for (w, h, tile) in AWKWARD {
+            for order in [TileOrder::RowMajor, TileOrder::SpiralFromCenter] {
+                let tiles = TileLayout::new(tile).with_order(order).tiles(w, h);
+                let limit = if tile == 0 { 4096 } else { tile };
+                assert!(tiles.iter().all(|t| t.tile_w <= limit && t.tile_h <= limit));
+                assert!(
+                    coverage(w, h, &tiles).iter().all(|&n| n == 1),
+                    "{w}x{h} in {tile} px tiles, {order:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn edge_tiles_are_balanced() {
+        let tiles = tile_iterator(8191, 4097, 512);
+        assert_eq!(tiles.len(), 16 * 9);
+        let (min_w, max_w) = tiles.iter().fold((u32::MAX, 0), |(lo, hi), t| {
+            (lo.min(t.tile_w), hi.max(t.tile_w))
+        });
+        let (min_h, max_h) = tiles.iter().fold((u32::MAX, 0), |(lo, hi), t| {
+            (lo.min(t.tile_h), hi.max(t.tile_h))
+        });
+        assert_eq!((min_w, max_w), (511, 512));
+        assert_eq!((min_h, max_h), (455, 456));
+        assert_eq!(spans(10, 4), vec![(0, 4), (4, 3), (7, 3)]);
+    }
+
+    #[test]
+    fn spiral_starts_in_the_middle() {
+        let tiles = tile_iterator(2560, 1536, 512);
+        let first = tiles[0];
+        assert!(first.offset_x <= 1280 && 1280 < first.offset_x + first.tile_w);
+        assert!(first.offset_y <= 768 && 768 < first.offset_y + first.tile_h);
+
+        // Each tile is at least as far from the middle as the one before it.
+        let ring = |t: &TileInfo| {
+            let dx = (t.offset_x + t.tile_w / 2).abs_diff(1280) / 512;
+            let dy = (t.offset_y + t.tile_h / 2).abs_diff(768) / 512;
+            dx.max(dy)
+        };
+        assert!(tiles.windows(2).all(|p| ring(&p[0]) <= ring(&p[1])));
+
+        let row_major = TileLayout::new(512)
+            .with_order(TileOrder::RowMajor)
+            .tiles(2560, 1536);
+        assert_eq!(row_major[0].offset_x, 0);
+        assert_eq!(row_major[0].offset_y, 0);
+        let mut sorted = tiles.clone();
+        sorted.sort_by_key(|t| (t.offset_y, t.offset_x));
+        assert_eq!(sorted, row_major);
+    }
+
+    #[test]
+    fn overlapping_tiles_stay_within_the_limit() {
+        for (w, h, tile) in AWKWARD {
+            if tile == 0 {
+                continue;
+            }
+            let tiles = TileLayout::new(tile).with_overlap(16).tiles(w, h);
+            assert!(tiles.iter().all(|t| t.tile_w <= tile && t.tile_h <= tile));
+            let hits = coverage(w, h, &tiles);
+            assert!(hits.iter().all(|&n| n >= 1), "{w}x{h} in {tile} px tiles");
+            if tiles.len() > 1 {
+                assert!(hits.iter().any(|&n| n > 1));
+            }
+        }
+        // The overlap never takes more than half of a tile.
+        let tiles = TileLayout::new(256).with_overlap(500).tiles(1024, 256);
+        assert!(tiles.iter().all(|t| t.tile_w <= 256));
+    }
+
+    #[test]
+    fn overlapping_renders_match_plain_ones() {
+        let params = FractalParams::new(FractalKind::Mandelbrot).with_max_iter(40);
+        let cam = Camera::new(Complex::new(-0.5, 0.0), 150.0).with_rotation(0.4);
+        let plain = render_cpu((600, 420), &params, &cam);
+        for overlap in [1, 7, 32] {
+            let layout = TileLayout::new(256).with_overlap(overlap);
+            let (frame, stats) =
+                render_image_layout((600, 420), &params, &cam, &mut CpuRenderer, layout).unwrap();
+            assert!(frame == plain, "overlap {overlap}");
+            assert!(stats.tiles() >= 6);
+        }
+    }
+
+    #[test]
+    fn blending_feathers_across_the_overlap() {
+        // Two 6 px tiles sharing 4 columns of a 8×1 frame, one white and one black.
+        let left = TileInfo {
+            full_w: 8,
+            full_h: 1,
+            offset_x: 0,
+            offset_y: 0,
+            tile_w: 6,
+            tile_h: 1,
+        };
+        let right = TileInfo {
+            offset_x: 2,
+            ..left
+        };
+        let mut blender = TileBlender::new(8, 1, 2);
+        blender.add(&left, &[255; 24]);
+        blender.add(&right, &[0, 0, 0, 255].repeat(6));
+        let red: Vec<u8> = blender.finish().chunks(4).map(|px| px[0]).collect();
+        assert_eq!(red, vec![255, 255, 223, 159, 96, 32, 0, 0]);
+    }
+
+    #[test]
+    fn frame_sizes_are_checked_at_the_overflow_boundary() {
+        // 65536² RGBA8 pixels overflow u32 arithmetic but not u64.
+        assert_eq!(
+            frame_bytes((MAX_FRAME_SIDE, MAX_FRAME_SIDE), 4),
+            Some(1 << 34)
+        );
+        assert_eq!(frame_bytes((u32::MAX, u32::MAX), 2), None);
+        assert!(check_frame_size((MAX_FRAME_SIDE, MAX_FRAME_SIDE)).is_ok());
+        assert!(check_frame_size((MAX_FRAME_SIDE + 1, 1)).is_err());
+        assert!(check_frame_size((200_000, 200_000)).is_err());
+        assert!(check_frame_size((0, 1080)).is_err());
+
+        // Exactly at the in-memory limit is fine; one more row is not.
+        let side = 32_768;
+        assert_eq!(frame_bytes((side, side), 4), Some(MAX_IN_MEMORY_FRAME));
+        if usize::BITS == 64 {
+            assert_eq!(in_memory_pixels((side, side), 4).unwrap(), 1 << 30);
+        }
+        let err = in_memory_pixels((side, side + 1), 4).unwrap_err();
+        assert!(err.to_string().contains("needs 4.0 GB"), "{err}");
+        // Refused before anything is allocated.
+        let err = render_image(
+            (MAX_FRAME_SIDE, MAX_FRAME_SIDE),
+            &FractalParams::default(),
+            &Camera::default(),
+            &mut CpuRenderer,
+            0,
+        )
+        .unwrap_err();
+        assert!(matches!(err, RenderError::FrameSize(_)));
+        // Contours keep a sample per pixel, which the limit counts too.
+        let contours = FractalParams::default()
+            .with_dwell_bands(8)
+            .with_band_contours([0.0; 3]);
+        let err = render_image(
+            (side, side),
+            &contours,
+            &Camera::default(),
+            &mut CpuRenderer,
+            0,
+        )
+        .unwrap_err();
+        assert!(matches!(err, RenderError::FrameSize(_)));
+    }
+
+    #[test]
+    fn frames_over_budget_switch_to_bands() {
+        let mib = 1 << 20;
+        assert_eq!(
+            FrameMode::for_size((1920, 1080), 512 * mib).unwrap(),
+            FrameMode::InMemory
+        );
+        // 512 rows of 65536 RGBA8 pixels are 128 MiB.
+        assert_eq!(
+            FrameMode::for_size((65_536, 65_536), 128 * mib).unwrap(),
+            FrameMode::Banded(512)
+        );
+        // A budget smaller than one row still makes progress.
+        assert_eq!(
+            FrameMode::for_size((4000, 10), 100).unwrap(),
+            FrameMode::Banded(1)
+        );
+        // The budget never exceeds the in-memory limit.
+        assert_eq!(
+            FrameMode::for_size((65_536, 65_536), u64::MAX).unwrap(),
+            FrameMode::Banded((in_memory_limit() / (65_536 * 4)) as u32)
+        );
+        assert!(FrameMode::for_size((200_000, 100), u64::MAX).is_err());
+    }
+
+    #[test]
+    fn tiles_are_capped_whatever_is_requested() {
+        let tiles = TileLayout::new(u32::MAX).tiles(40_000, 300);
+        assert_eq!(tiles.len(), 3);
+        assert!(tiles.iter().all(|t| t.tile_w <= MAX_TILE));
+    }
+
+    #[test]
+    fn exterior_colors_do_not_depend_on_max_iter() {
+        let cam = Camera::new(Complex::new(-0.75, 0.1), 20.0);
+        let low = FractalParams::new(FractalKind::Mandelbrot).with_max_iter(150);
+        let high = low.clone().with_max_iter(3000);
+        let (a, b) = (
+            render_cpu((48, 32), &low, &cam),
+            render_cpu((48, 32), &high, &cam),
+        );
+        let mut exterior = 0;
+        for y in 0..32 {
+            for x in 0..48 {
+                let (rx, ry) = (
+                    (x as f32 - 24.0) / 20.0 - 0.75,
+                    (y as f32 - 16.0) / 20.0 + 0.1,
+                );
+                if iterate_point(&low, rx, ry).escaped(&low) {
+                    let i = (y * 48 + x) * 4;
+                    assert_eq!(a[i..i + 4], b[i..i + 4], "pixel ({x}, {y})");
+                    exterior += 1;
+                }
+            }
+        }
+        assert!(exterior > 500);
+    }
+
+    #[test]
+    fn smooth_iterations_are_continuous_across_bands() {
+        // Walking outward along the real axis, the smooth count falls without jumps where
+        // the integer count steps, for any escape radius and Multibrot power.
+        // Starting outside each set.
+        let kinds = [
+            (FractalKind::Mandelbrot, 2.0, 0.6),
+            (FractalKind::Multibrot, 3.5, 1.4),
+            (FractalKind::Multibrot, 5.0, 1.4),
+            (FractalKind::Celtic, 2.0, 0.6),
+            (FractalKind::Buffalo, 2.0, 1.2),
+        ];
+        for (kind, power, start) in kinds {
+            for radius in [2.0, 4.0, 64.0] {
+                let p = FractalParams {
+                    escape_radius: radius,
+                    power,
+                    ..FractalParams::new(kind).with_max_iter(500)
+                };
+                let smooth = |re: f32| {
+                    let orbit = iterate_point(&p, re, 0.4);
+                    smooth_iteration(&p, &orbit)
+                };
+                let mut last = smooth(start);
+                for step in 1..=400 {
+                    let next = smooth(start + step as f32 * 0.0025);
+                    assert!(
+                        (next - last).abs() < 0.5,
+                        "power {power}, R = {radius}: {last} -> {next}"
+                    );
+                    last = next;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn stripe_averages_are_continuous_across_bands() {
+        // The same walk as above: interpolating between the averages with and without the
+        // final iterate hides the steps of the integer count.
+        let p = FractalParams {
+            escape_radius: 1000.0,
+            ..FractalParams::new(FractalKind::Mandelbrot)
+                .with_max_iter(500)
+                .with_stripes(5, 1.0)
+        };
+        let stripe = |re: f32| stripe_average(&p, &iterate_point(&p, re, 0.4)).unwrap();
+        let mut last = stripe(0.6);
+        for step in 1..=400 {
+            let next = stripe(0.6 + step as f32 * 0.0025);
+            assert!((0.0..=1.0).contains(&next));
+            assert!((next - last).abs() < 0.05, "{last} -> {next}");
+            last = next;
+        }
+
+        // Interior points and other coloring modes have none.
+        assert_eq!(stripe_average(&p, &iterate_point(&p, -0.1, 0.0)), None);
+        let plain = p.clone().with_coloring(ColoringMode::SmoothIteration);
+        assert_eq!(
+            stripe_average(&plain, &iterate_point(&plain, 0.6, 0.4)),
+            None
+        );
+    }
+
+    #[test]
+    fn stripe_mix_blends_over_the_iteration_colors() {
+        let cam = Camera::new(Complex64::new(-0.5, 0.0), 8.0);
+        let base = FractalParams::new(FractalKind::Mandelbrot).with_max_iter(200);
+        let render = |p: &FractalParams| render_cpu((32, 24), p, &cam);
+        // No mix leaves the iteration count's colors; a full one replaces them.
+        assert_eq!(render(&base.clone().with_stripes(5, 0.0)), render(&base));
+        assert_ne!(render(&base.clone().with_stripes(5, 1.0)), render(&base));
+    }
+
+    #[test]
+    fn lyapunov_exponents_take_a_palette_half_each() {
+        assert_eq!(lyapunov_position(0.0), 0.5);
+        assert_eq!(lyapunov_position(f32::NAN), 0.5);
+        assert_eq!(lyapunov_position(f32::INFINITY), 1.0);
+        assert_eq!(lyapunov_position(f32::NEG_INFINITY), 0.0);
+        for exponent in [0.01, 0.5, 2.0] {
+            assert!(lyapunov_position(exponent) > 0.5);
+            assert!(lyapunov_position(-exponent) < 0.5);
+            assert!(lyapunov_position(exponent) > lyapunov_position(exponent / 2.0));
+        }
+    }
+
+    #[test]
+    fn smooth_iterations_stay_finite_at_high_powers() {
+        // At power 12, |z| at escape is up to R^12, whose square overflows f32 at R = 128.
+        for radius in [2.0, 128.0, 1e4, 1e9] {
+            let p = FractalParams {
+                escape_radius: radius,
+                power: 12.0,
+                ..FractalParams::new(FractalKind::Multibrot).with_max_iter(200)
+            };
+            for re in [1.05, 1.1, 1.5, 3.0] {
+                let orbit = iterate_point(&p, re, 0.3);
+                assert!(orbit.escaped(&p), "R = {radius}, re = {re}");
+                let smooth = smooth_iteration(&p, &orbit);
+                assert!(
+                    smooth.is_finite() && smooth <= orbit.iter as f32 + 1.0,
+                    "R = {radius}, re = {re}: {smooth} after {} iterations",
+                    orbit.iter
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn palette_phase_shifts_the_table_its_key_ignores() {
+        let mut params = FractalParams::new(FractalKind::Mandelbrot);
+        params.palette_phase = 0.25;
+        let shifted = build_palette(&params, 2048);
+        let unshifted = bake_palette(&params.palette, 0.0, 2048);
+        assert_eq!(shifted, bake_palette(&params.palette, 0.25, 2048));
+        // Sampling the phase-free table at t + phase, as the GPU shader does.
+        for i in [0, 100, 1000, 1800] {
+            let t = i as f32 / 2047.0;
+            let a = shifted[i];
+            let b = sample_palette(&unshifted, (t + 0.25).fract());
+            assert!(
+                a.iter().zip(b).all(|(&a, b)| a.abs_diff(b) <= 2),
+                "{a:?} vs {b:?}"
+            );
+        }
+
+        let key = palette_key(&params.palette);
+        params.palette_phase = 0.6;
+        assert_eq!(palette_key(&params.palette), key);
+        params.palette[1].color[2] += 0.01;
+        assert_ne!(palette_key(&params.palette), key);
+    }
+
+    #[test]
+    fn exterior_modes_replace_only_escaped_pixels() {
+        let palette_params = FractalParams::new(FractalKind::Mandelbrot);
+        let palette = build_palette(&palette_params, 2048);
+        let escaped = PixelSample {
+            smooth_iter: 12.5,
+            interior: false,
+            trap_min: 1.0,
+            interior_shade: 0.0,
+            distance: None,
+            stripe: None,
+            root: None,
+            lyapunov: None,
+        };
+        let interior = PixelSample {
+            interior: true,
+            ..escaped
+        };
+        let shade = |p: &FractalParams| shade_samples(&[escaped, interior], p, &palette);
+        let plain = shade(&palette_params);
+
+        let solid = shade(
+            &palette_params
+                .clone()
+                .with_exterior(ExteriorMode::Solid, [0.0, 0.0, 1.0]),
+        );
+        assert_eq!(solid[..4], [0, 0, 255, 255]);
+        assert_eq!(solid[4..], plain[4..]);
+
+        let mut p = palette_params.with_exterior(ExteriorMode::Transparent, [0.0, 0.0, 1.0]);
+        let transparent = shade(&p);
+        assert_eq!(transparent[3], 0);
+        assert_eq!(transparent[4..], plain[4..]);
+
+        // The trap tint brings its own color and its weight as coverage.
+        p.orbit.enabled = true;
+        p.orbit.color = [1.0, 0.5, 0.0];
+        let weight = escaped.trap_weight(&p);
+        let tinted = shade(&p);
+        assert_eq!(tinted[..3], [255, 127, 0]);
+        assert_eq!(tinted[3], (weight * 255.0) as u8);
+        // Composited over the exterior color, it matches the solid mode.
+        p.exterior_mode = ExteriorMode::Solid;
+        let over = shade(&p);
+        for c in 0..3 {
+            let a = f32::from(tinted[3]) / 255.0;
+            let composited = f32::from(tinted[c]) * a + p.exterior_color[c] * 255.0 * (1.0 - a);
+            assert!((composited - f32::from(over[c])).abs() <= 2.0);
+        }
+    }
+
+    #[test]
+    fn interior_colorings_shade_only_interior_points() {
+        let p = FractalParams::new(FractalKind::Mandelbrot).with_max_iter(100);
+        let orbit = OrbitResult {
+            iter: 100,
+            zx: 0.0,
+            zy: -1.0,
+            trap_min: 0.5,
+            distance: None,
+            stripe: None,
+            root: None,
+            lyapunov: None,
+        };
+        let shade = |coloring| interior_shade(&p.clone().with_interior(coloring, [0.0; 3]), &orbit);
+        assert_eq!(shade(InteriorColoring::Solid), 0.0);
+        assert_eq!(shade(InteriorColoring::Magnitude), 0.25);
+        assert_eq!(shade(InteriorColoring::Argument), 0.25);
+        assert_eq!(shade(InteriorColoring::TrapDistance), 0.125);
+
+        let palette = build_palette(&p, 2048);
+        let escaped = PixelSample {
+            smooth_iter: 12.5,
+            ..PixelSample::default()
+        };
+        // Sampling leaves the shade at 0 for the flat colorings.
+        let shade = |coloring, interior_shade| {
+            let p = p.clone().with_interior(coloring, [0.0, 1.0, 0.0]);
+            let interior = PixelSample {
+                interior: true,
+                interior_shade,
+                ..PixelSample::default()
+            };
+            shade_samples(&[escaped, interior], &p, &palette)
+        };
+        let solid = shade(InteriorColoring::Solid, 0.0);
+        let flat = shade(InteriorColoring::Palette, 0.0);
+        let shaded = shade(InteriorColoring::Magnitude, 0.6);
+        assert_eq!(solid[4..], [0, 255, 0, 255]);
+        assert_eq!(solid[..4], shaded[..4]);
+        assert_eq!(flat[..4], shaded[..4]);
+        let at = |t: f32| p.tone_map_rgb(sample_palette(&palette, t).map(|c| c as f32 / 255.0));
+        assert_eq!(flat[4..7], at(0.0).map(|c| (c * 255.0) as u8));
+        assert_eq!(shaded[4..7], at(0.6).map(|c| (c * 255.0) as u8));
+    }
+
+    #[test]
+    fn distance_estimates_are_in_pixels_at_any_zoom() {
+        // The middle pixel of a 3×3 frame is the camera's center, here above the neck
+        // of the seahorse valley.
+        let p = FractalParams::new(FractalKind::Mandelbrot)
+            .with_max_iter(2000)
+            .with_coloring(ColoringMode::DistanceEstimate);
+        let tile = TileInfo::full(3, 3);
+        let at = |scale| {
+            let cam = Camera::new(Complex64::new(-0.75, 0.3), scale);
+            sample_tile_cpu(&tile, &p, &cam)[4].distance
+        };
+        let (near, far) = (at(1e5).unwrap(), at(2e5).unwrap());
+        assert!((far / near - 2.0).abs() < 1e-3, "{near} -> {far}");
+
+        // Filaments of the seahorse valley at a scale of 1e5 show as pixels a hairline from
+        // the set, at the palette's start, between pixels several away.
+        let center = Complex64::new(-0.743_643_9, 0.131_825_9);
+        let tile = TileInfo::full(96, 96);
+        let samples = sample_tile_cpu(&tile, &p, &Camera::new(center, 1e5));
+        let distances: Vec<f32> = samples
+            .iter()
+            .filter(|s| !s.interior)
+            .map(|s| s.distance.unwrap())
+            .collect();
+        let hairline = distances.iter().filter(|&&d| d < 1.0).count();
+        let open = distances.iter().filter(|&&d| d > 4.0).count();
+        assert!(hairline > 1000 && open > 1000, "{hairline} {open}");
+        assert!(distance_position(0.5) < 0.1 && distance_position(16.0) >= 0.5);
+    }
+
+    #[test]
+    fn supersampling_blends_edges_and_leaves_flat_regions_alone() {
+        let params = FractalParams::new(FractalKind::Mandelbrot).with_max_iter(200);
+        let size = (64, 48);
+        // Deep in the main cardioid every sample is interior, so the pixels can't change.
+        let inside = Camera::new(Complex::new(-0.2, 0.0), 2000.0);
+        assert_eq!(
+            render_cpu(size, &params, &inside),
+            render_cpu(size, &params.clone().with_supersample(3), &inside)
+        );
+
+        // With a flat exterior, pixels on the edge of the set come out in between.
+        let params = params.with_exterior(ExteriorMode::Solid, [1.0; 3]);
+        let edge = Camera::new(Complex::new(-0.75, 0.0), 200.0);
+        let colors = |pixels: Vec<u8>| {
+            let mut colors: Vec<&[u8]> = pixels.chunks_exact(4).collect();
+            colors.sort_unstable();
+            colors.dedup();
+            colors.len()
+        };
+        assert_eq!(colors(render_cpu(size, &params, &edge)), 2);
+        assert!(colors(render_cpu(size, &params.clone().with_supersample(3), &edge)) > 2);
+
+        // A 3×3 grid's middle sample is the pixel's own point.
+        let samples = |p: &FractalParams| {
+            render_image_samples(size, p, &edge, &mut CpuRenderer, 0)
+                .unwrap()
+                .2
+        };
+        assert_eq!(
+            samples(&params),
+            samples(&params.clone().with_supersample(3))
+        );
+    }
+
+    #[test]
+    fn contours_edge_the_dwell_bands_whatever_the_tiling() {
+        let params = FractalParams::new(FractalKind::Mandelbrot)
+            .with_max_iter(60)
+            .with_palette_repeat(40.0)
+            .with_dwell_bands(8)
+            .with_band_contours([1.0, 0.0, 1.0]);
+        let cam = Camera::new(Complex::new(-0.5, 0.0), 120.0);
+        let size = (300, 200);
+        let (whole, _, samples) =
+            render_image_samples(size, &params, &cam, &mut CpuRenderer, 0).unwrap();
+
+        // Bands are flat: the palette is only sampled at band starts.
+        let palette = build_palette(&params, 2048);
+        let band_colors: Vec<[u8; 3]> = (0..8)
+            .map(|band| sample_palette(&palette, band as f32 / 8.0))
+            .collect();
+        let mut lines = 0;
+        for (pixel, sample) in whole.chunks_exact(4).zip(&samples) {
+            if pixel == [255, 0, 255, 255] {
+                lines += 1;
+            } else if !sample.interior {
+                let band = dwell_band(&params, sample.smooth_iter) as usize % 8;
+                let toned = params.tone_map_rgb(band_colors[band].map(|c| c as f32 / 255.0));
+                assert_eq!(pixel[..3], toned.map(|c| (c * 255.0) as u8));
+            }
+        }
+        assert!(lines > 100, "{lines} contour pixels");
+
+        let layout = TileLayout::new(64).with_overlap(5);
+        let (tiled, _) =
+            render_image_layout(size, &params, &cam, &mut CpuRenderer, layout).unwrap();
+        assert!(tiled == whole);
+        let (band, _) = render_band(size, 70..130, &params, &cam, &mut CpuRenderer, 64).unwrap();
+        assert!(band[..] == whole[70 * 300 * 4..130 * 300 * 4]);
+        let (shaded, _) = shade_image(size, &samples, &params, &mut CpuRenderer, 96).unwrap();
+        assert!(shaded == whole);
+    }
+
+    #[test]
+    fn tone_curve_round_trips() {
+        for (exposure, gamma) in [(1.0, 2.2), (0.3, 1.0), (4.0, 0.5), (2.5, 4.0)] {
+            for i in 0..=100 {
+                let stored = i as f32 / 100.0;
+                let shown = tone_map(stored, exposure, gamma);
+                let back = inverse_tone_map(shown, exposure, gamma);
+                assert!(
+                    (back - stored).abs() < 1e-3,
+                    "{stored} -> {shown} -> {back}"
+                );
+            }
+            // Outputs past what a stored 1 reaches clamp to it.
+            let brightest = tone_map(1.0, exposure, gamma);
+            for i in 0..=100 {
+                let output = i as f32 / 100.0;
+                let stored = inverse_tone_map(output, exposure, gamma);
+                assert!((0.0..=1.0).contains(&stored));
+                let shown = tone_map(stored, exposure, gamma);
+                assert!(
+                    (shown - output.min(brightest)).abs() < 1e-3,
+                    "{output} -> {shown}"
+                );
+            }
+        }
+        assert_eq!(inverse_tone_map(0.5, 0.0, 2.2), 0.0);
+
+        // The CPU shader uses the same curve.
+        let p = FractalParams {
+            exposure: 1.7,
+            gamma: 1.8,
+            ..FractalParams::new(FractalKind::Mandelbrot)
+        };
+        let palette = vec![[200, 100, 30]; 4];
+        let sample = PixelSample {
+            smooth_iter: 3.0,
+            interior: false,
+            trap_min: 1.0,
+            interior_shade: 0.0,
+            distance: None,
+            stripe: None,
+            root: None,
+            lyapunov: None,
+        };
+        let shaded = shade_samples(&[sample], &p, &palette);
+        let expected = p.tone_map_rgb([200.0 / 255.0, 100.0 / 255.0, 30.0 / 255.0]);
+        for c in 0..3 {
+            assert_eq!(shaded[c], (expected[c] * 255.0) as u8);
+        }
+    }
+
+    #[test]
+    fn queued_tiles_start_under_the_focus() {
+        let tiles = TileLayout::new(256)
+            .with_order(TileOrder::RowMajor)
+            .tiles(1024, 512);
+        let mut queue = TileQueue::new(tiles.clone());
+        assert_eq!(queue.pop(), Some(tiles[0]));
+        queue.prioritize((1000.0, 500.0));
+        let first = queue.pop().unwrap();
+        assert_eq!((first.offset_x, first.offset_y), (768, 256));
+        // Refocusing reorders only what is left.
+        queue.prioritize((0.0, 0.0));
+        let next = queue.pop().unwrap();
+        assert_eq!((next.offset_x, next.offset_y), (256, 0));
+        let mut rest = vec![tiles[0], first, next];
+        while let Some(tile) = queue.pop() {
+            rest.push(tile);
+        }
+        assert_eq!(rest.len(), tiles.len());
+        rest.sort_by_key(|t| (t.offset_y, t.offset_x));
+        assert_eq!(rest, tiles);
+    }
+
+    #[test]
+    fn progressive_frames_match_whole_renders() {
+        let params = FractalParams::new(FractalKind::Mandelbrot).with_max_iter(60);
+        let cam = Camera::new(Complex::new(-0.6, 0.1), 90.0).with_rotation(0.2);
+        let size = (600, 340);
+        let (whole, _, whole_samples) =
+            render_image_samples(size, &params, &cam, &mut CpuRenderer, 0).unwrap();
+
+        let mut progressive =
+            ProgressiveRender::new(size, &params, &cam, &CpuRenderer, 256).unwrap();
+        progressive.prioritize((550.0, 20.0));
+        let mut steps = 0;
+        while !progressive.is_done() {
+            let remaining = progressive.remaining();
+            let done = progressive.step(&mut CpuRenderer, Duration::ZERO).unwrap();
+            assert_eq!(done.len(), 1, "a zero budget still renders a tile");
+            assert_eq!(progressive.remaining(), remaining - 1);
+            let tile = done[0];
+            let i = ((tile.offset_y * size.0 + tile.offset_x) * 4) as usize;
+            assert_eq!(progressive.tile_pixels(&tile)[..4], whole[i..i + 4]);
+            steps += 1;
+            if steps == 2 {
+                progressive.prioritize((0.0, 320.0));
+            }
+        }
+        let (frame, stats, samples) = progressive.finish();
+        assert_eq!(stats.tiles(), steps);
+        assert!(frame == whole);
+        assert!(samples == whole_samples);
+    }
+
+    #[test]
+    fn progressive_regions_match_the_whole_frame() {
+        let params = FractalParams::new(FractalKind::Mandelbrot)
+            .with_max_iter(60)
+            .with_palette_repeat(40.0)
+            .with_dwell_bands(8)
+            .with_band_contours([1.0, 0.0, 1.0]);
+        let cam = Camera::new(Complex::new(-0.5, 0.0), 120.0);
+        let size = (300, 200);
+        let (whole, _, whole_samples) =
+            render_image_samples(size, &params, &cam, &mut CpuRenderer, 0).unwrap();
+        // Inside the frame, and against its top-left and bottom-right corners.
+        for (x, y, w, h) in [(90, 40, 130, 70), (0, 0, 61, 45), (200, 150, 100, 50)] {
+            let region = TileInfo {
+                offset_x: x,
+                offset_y: y,
+                tile_w: w,
+                tile_h: h,
+                ..TileInfo::full(size.0, size.1)
+            };
+            let mut progressive =
+                ProgressiveRender::region(&region, &params, &cam, &CpuRenderer, 256).unwrap();
+            while !progressive.is_done() {
+                progressive.step(&mut CpuRenderer, Duration::ZERO).unwrap();
+            }
+            let (pixels, stats, samples) = progressive.finish();
+            assert_eq!((stats.width, stats.height), (w, h));
+            assert!(pixels == crop(&whole, size.0, &region, 4), "{region:?}");
+            assert!(samples == crop(&whole_samples, size.0, &region, 1));
+        }
+    }
+
+    #[test]
+    fn f64_precision_keeps_detail_at_a_scale_of_1e12() {
+        // Near the seahorse valley spiral of the classic deep zoom.
+        let cam = Camera::new(
+            Complex64::new(-0.743_643_887_037_151, 0.131_825_904_205_33),
+            1e12,
+        );
+        let single = FractalParams::new(FractalKind::Mandelbrot)
+            .with_max_iter(5000)
+            .with_palette_repeat(64.0);
+        let double = FractalParams {
+            precision: Precision::F64,
+            ..single.clone()
+        };
+        let colors = |params: &FractalParams| {
+            let rgba = render_cpu((64, 64), params, &cam);
+            let mut colors: Vec<&[u8]> = rgba.chunks(4).collect();
+            colors.sort_unstable();
+            colors.dedup();
+            colors.len()
+        };
+        let (blocky, crisp) = (colors(&single), colors(&double));
+        assert!(blocky <= 4, "f32 shows {blocky} colors");
+        assert!(crisp >= 500, "f64 shows only {crisp} colors");
+    }
+
+    #[test]
+    fn perturbation_keeps_detail_at_a_scale_of_1e50() {
+        let cam = Camera::at_sum(&SEAHORSE_DEEP_POINT, 1e50);
+        let double = FractalParams {
+            precision: Precision::F64,
+            ..FractalParams::new(FractalKind::Mandelbrot)
+                .with_max_iter(4000)
+                .with_palette_repeat(64.0)
+        };
+        let perturbed = FractalParams {
+            precision: Precision::Perturbation,
+            ..double.clone()
+        };
+        let colors = |params: &FractalParams| {
+            let rgba = render_cpu((64, 64), params, &cam);
+            let mut colors: Vec<&[u8]> = rgba.chunks(4).collect();
+            colors.sort_unstable();
+            colors.dedup();
+            colors.len()
+        };
+        let (flat, crisp) = (colors(&double), colors(&perturbed));
+        assert_eq!(flat, 1, "f64 shows {flat} colors");
+        assert!(crisp >= 500, "perturbation shows only {crisp} colors");
+    }
+}
@@ -0,0 +1,455 @@
+//! Project report: what a project's export will cost to render and what may go wrong
+//! with it, from its keys, every evaluated frame and the checks the app runs on them.
+
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::{
+    diagnose, for_each_export_frame, format_magnification, format_scientific, format_timestamp,
+    Diagnosis, EvaluatedFrame, Precision, Project, RenderBackend, RenderStats, ScriptError,
+    TrackKind,
+};
+
+/// Most points of the Max Iter curve a report lists.
+pub const REPORT_CURVE_POINTS: u32 = 12;
+
+/// The smallest and largest value a parameter takes over the export, and when it first
+/// does.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Extent<T> {
+    pub min: T,
+    /// Seconds into the animation.
+    pub min_time: f32,
+    pub max: T,
+    pub max_time: f32,
+}
+
+impl<T: PartialOrd + Copy> Extent<T> {
+    fn new(value: T, time: f32) -> Self {
+        Self {
+            min: value,
+            min_time: time,
+            max: value,
+            max_time: time,
+        }
+    }
+
+    fn include(extent: &mut Option<Self>, value: T, time: f32) {
+        let Some(extent) = extent else {
+            *extent = Some(Self::new(value, time));
+            return;
+        };
+        if value < extent.min {
+            extent.min = value;
+            extent.min_time = time;
+        }
+        if value > extent.max {
+            extent.max = value;
+            extent.max_time = time;
+        }
+    }
+}
+
+/// What the frames of an export span, gathered frame by frame in time order.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AnimationExtent {
+    pub frames: u32,
+    /// Camera scale in pixels per unit; `None` without frames.
+    pub scale: Option<Extent<f64>>,
+    pub max_iter: Option<Extent<u32>>,
+    /// Max Iter summed over the frames: the most iterations one pixel can take across
+    /// the export.
+    pub total_iterations: u64,
+    /// Max Iter at up to [`REPORT_CURVE_POINTS`] frames spread over the export, as
+    /// `(seconds, max_iter)`.
+    pub max_iter_curve: Vec<(f32, u32)>,
+}
+
+impl AnimationExtent {
+    /// Ready for the frames of an export `frames` long.
+    pub fn new(frames: u32) -> Self {
+        Self {
+            frames,
+            scale: None,
+            max_iter: None,
+            total_iterations: 0,
+            max_iter_curve: Vec::new(),
+        }
+    }
+
+    /// Takes in frame `frame`, `time` seconds in.
+    pub fn include(&mut self, frame: u32, time: f32, eval: &EvaluatedFrame) {
+        let max_iter = eval.fractal.max_iter;
+        Extent::include(&mut self.scale, eval.camera.scale, time);
+        Extent::include(&mut self.max_iter, max_iter, time);
+        self.total_iterations += u64::from(max_iter);
+        let stride = self.frames.div_ceil(REPORT_CURVE_POINTS).max(1);
+        if frame.is_multiple_of(stride) {
+            self.max_iter_curve.push((time, max_iter));
+        }
+    }
+}
+
+/// Evaluates every frame of `proj`'s export, script included, and gathers what they span.
+pub fn animation_extent(proj: &Project) -> Result<AnimationExtent, ScriptError> {
+    let mut extent = AnimationExtent::new(proj.export.frame_count());
+    for_each_export_frame(proj, |frame, time, eval| extent.include(frame, time, eval))?;
+    Ok(extent)
+}
+
+/// Keys on one animated track.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TrackSummary {
+    pub track: &'static str,
+    pub keys: usize,
+    /// Seconds of the first and last key.
+    pub start: f32,
+    pub end: f32,
+}
+
+/// Everything that drives a project's render cost and output, and the problems found
+/// with it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProjectReport {
+    pub name: String,
+    pub formula: &'static str,
+    pub width: u32,
+    pub height: u32,
+    pub fps: u32,
+    pub duration: f32,
+    /// Tracks with keys, in timeline order.
+    pub tracks: Vec<TrackSummary>,
+    pub extent: AnimationExtent,
+    /// Magnification of the deepest frame, measured as [`Precision::zoom_limit`] does.
+    pub deepest_zoom: f64,
+    pub precision: Precision,
+    /// The least precision that renders the deepest frame crisply.
+    pub precision_needed: Precision,
+    /// Whether the deepest frame is past what `f32` resolves, as the GPU iterates.
+    pub beyond_f32: bool,
+    /// Seconds the export should take, scaled from a measured frame.
+    pub estimated_seconds: Option<f64>,
+    pub palette_stops: usize,
+    pub warnings: Vec<String>,
+}
+
+/// Reports on `proj`'s export. `benchmark` is a frame rendered on this machine, such as
+/// the last preview or a calibration render; the render time is estimated from it.
+pub fn project_report(proj: &Project, benchmark: Option<&RenderStats>) -> ProjectReport {
+    let mut warnings = Vec::new();
+    let width = proj.export.width as f32;
+    // Each rule once, at the first frame it applies to.
+    let mut found: Vec<(f32, Diagnosis)> = Vec::new();
+    let mut gather = |proj: &Project| {
+        found.clear();
+        let mut extent = AnimationExtent::new(proj.export.frame_count());
+        for_each_export_frame(proj, |frame, time, eval| {
+            extent.include(frame, time, eval);
+            for diagnosis in diagnose(&eval.fractal, &eval.camera, width, None) {
+                if !found.iter().any(|(_, d)| d.same_rule(&diagnosis)) {
+                    found.push((time, diagnosis));
+                }
+            }
+        })
+        .map(|()| extent)
+    };
+    let extent = match gather(proj) {
+        Ok(extent) => extent,
+        Err(e) => {
+            warnings.push(format!("Script: {e}; the figures below leave it out"));
+            let mut unscripted = proj.clone();
+            unscripted.script.enabled = false;
+            gather(&unscripted).expect("frames without a script always evaluate")
+        }
+    };
+    for (time, diagnosis) in found {
+        warnings.push(format!("{}: {diagnosis}", format_timestamp(time)));
+    }
+
+    let deepest_scale = extent.scale.map_or(proj.camera.scale, |s| s.max);
+    let deepest_zoom = 4.0 * deepest_scale / f64::from(proj.export.height.max(1));
+    let precision_needed = Precision::ALL
+        .into_iter()
+        .find(|precision| deepest_zoom <= precision.zoom_limit())
+        .unwrap_or(Precision::Perturbation);
+    let beyond_f32 = deepest_zoom > Precision::F32.zoom_limit();
+    if precision_needed > proj.precision {
+        warnings.push(format!(
+            "The deepest frame, {}, needs {} precision; the project renders in {}",
+            format_magnification(deepest_zoom),
+            precision_needed.label(),
+            proj.precision.label()
+        ));
+    }
+    if beyond_f32 && proj.render_backend != RenderBackend::Cpu {
+        warnings.push(format!(
+            "The GPU iterates in f32 and breaks up past {}; render on the CPU",
+            format_magnification(Precision::F32.zoom_limit())
+        ));
+    }
+    if extent.frames == 0 {
+        warnings.push("The export has no frames".into());
+    }
+    if let Err(e) = proj.export.frame_mode() {
+        warnings.push(format!("Export: {e}"));
+    }
+
+    let mut tracks: Vec<TrackSummary> = TrackKind::ALL
+        .iter()
+        .filter_map(|&kind| summarize(kind.label(), proj.anim.track(kind).keys.iter().map(|k| k.t)))
+        .collect();
+    tracks.extend(summarize(
+        "Trap color",
+        proj.anim.kf_trap_color.keys.iter().map(|k| k.t),
+    ));
+
+    let mut report = ProjectReport {
+        name: proj.name.clone(),
+        formula: proj.fractal.kind.label(),
+        width: proj.export.width,
+        height: proj.export.height,
+        fps: proj.export.fps,
+        duration: proj.export.duration,
+        tracks,
+        extent,
+        deepest_zoom,
+        precision: proj.precision,
+        precision_needed,
+        beyond_f32,
+        estimated_seconds: None,
+        palette_stops: proj.fractal.palette.len(),
+        warnings,
+    };
+    report.estimated_seconds = benchmark.and_then(|b| report.estimate_seconds(b));
+    report
+}
+
+fn summarize(track: &'static str, times: impl Iterator<Item = f32>) -> Option<TrackSummary> {
+    let times: Vec<f32> = times.collect();
+    Some(TrackSummary {
+        track,
+        keys: times.len(),
+        start: times.iter().copied().reduce(f32::min)?,
+        end: times.iter().copied().reduce(f32::max)?,
+    })
+}
+
+impl ProjectReport {
+    /// Seconds the export should take if a frame costs in proportion to its pixels times
+    /// its Max Iter, as `benchmark` did. `None` for an empty benchmark.
+    pub fn estimate_seconds(&self, benchmark: &RenderStats) -> Option<f64> {
+        let work = |pixels: f64, iterations: f64| pixels * iterations;
+        let measured = work(
+            f64::from(benchmark.width) * f64::from(benchmark.height),
+            f64::from(benchmark.max_iter),
+        );
+        if measured <= 0.0 || !benchmark.total_ms.is_finite() {
+            return None;
+        }
+        let export = work(
+            f64::from(self.width) * f64::from(self.height),
+            self.extent.total_iterations as f64,
+        );
+        Some(benchmark.total_ms / 1000.0 * export / measured)
+    }
+}
+
+/// `38 s`, `4 min 12 s` or `2 h 05 min`.
+fn format_render_time(seconds: f64) -> String {
+    let seconds = seconds.max(0.0).round() as u64;
+    match seconds {
+        0..=59 => format!("{seconds} s"),
+        60..=3599 => format!("{} min {:02} s", seconds / 60, seconds % 60),
+        _ => format!("{} h {:02} min", seconds / 3600, seconds / 60 % 60),
+    }
+}
+
+impl fmt::Display for ProjectReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "\"{}\", {}", self.name, self.formula)?;
+        writeln!(
+            f,
+            "Export: {}×{} at {} fps for {:.1} s, {} frames",
+            self.width, self.height, self.fps, self.duration, self.extent.frames
+        )?;
+        match self.estimated_seconds {
+            Some(seconds) => writeln!(f, "Estimated render time: {}", format_render_time(seconds))?,
+            None => writeln!(f, "Estimated render time: unknown, nothing was measured")?,
+        }
+        writeln!(f, "Palette: {} stops", self.palette_stops)?;
+        if self.tracks.is_empty() {
+            writeln!(f, "Tracks: no keys")?;
+        } else {
+            writeln!(f, "Tracks:")?;
+            for track in &self.tracks {
+                writeln!(
+                    f,
+                    "  {}: {} key{}, {} to {}",
+                    track.track,
+                    track.keys,
+                    if track.keys == 1 { "" } else { "s" },
+                    format_timestamp(track.start),
+                    format_timestamp(track.end)
+                )?;
+            }
+        }
+        if let Some(scale) = &self.extent.scale {
+            writeln!(
+                f,
+                "Scale: {} at {} to {} at {}; deepest {}, needs {} precision{}",
+                format_scientific(scale.min),
+                format_timestamp(scale.min_time),
+                format_scientific(scale.max),
+                format_timestamp(scale.max_time),
+                format_magnification(self.deepest_zoom),
+                self.precision_needed.label(),
+                if self.beyond_f32 { ", past f32" } else { "" }
+            )?;
+        }
+        if let Some(max_iter) = &self.extent.max_iter {
+            writeln!(
+                f,
+                "Max Iter: {} at {} to {} at {}",
+                max_iter.min,
+                format_timestamp(max_iter.min_time),
+                max_iter.max,
+                format_timestamp(max_iter.max_time)
+            )?;
+            let curve: Vec<String> = self
+                .extent
+                .max_iter_curve
+                .iter()
+                .map(|(time, max_iter)| format!("{} {max_iter}", format_timestamp(*time)))
+                .collect();
+            writeln!(f, "  {}", curve.join(" · "))?;
+        }
+        if self.warnings.is_empty() {
+            write!(f, "No warnings")
+        } else {
+            write!(f, "Warnings:")?;
+            for warning in &self.warnings {
+                write!(f, "\n  {warning}")?;
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Easing, Keyframe};
+
+    /// Two seconds at 10 fps zooming from a scale of 100 to 1e6.
+    fn zooming() -> Project {
+        let mut proj = Project::default();
+        proj.export.fps = 10;
+        proj.export.duration = 2.0;
+        proj.export.width = 400;
+        proj.export.height = 400;
+        proj.fractal.auto_iter = false;
+        proj.fractal.max_iter = 500;
+        proj.anim.duration = 2.0;
+        let zoom = proj.anim.track_mut(TrackKind::Zoom);
+        for (t, v) in [(0.0, 100.0), (2.0, 1e6)] {
+            zoom.keys.push(Keyframe {
+                t,
+                v,
+                easing: Easing::Linear,
+            });
+        }
+        proj
+    }
+
+    #[test]
+    fn extent_spans_every_frame() {
+        let extent = animation_extent(&zooming()).unwrap();
+        assert_eq!(extent.frames, 20);
+        let scale = extent.scale.unwrap();
+        assert!((scale.min - 100.0).abs() < 1e-3);
+        assert_eq!(scale.min_time, 0.0);
+        // The last frame is at 1.9 s, short of the final key.
+        assert!(scale.max > 1e5 && scale.max < 1e6);
+        assert_eq!(scale.max_time, 1.9);
+        let max_iter = extent.max_iter.unwrap();
+        assert_eq!((max_iter.min, max_iter.max), (500, 500));
+        assert_eq!(extent.total_iterations, 20 * 500);
+        // Every other frame, to stay within the limit.
+        assert_eq!(extent.max_iter_curve.len(), 10);
+        assert_eq!(extent.max_iter_curve[1], (0.2, 500));
+    }
+
+    #[test]
+    fn extent_follows_auto_iterations_and_the_script() {
+        let mut proj = zooming();
+        proj.fractal.auto_iter = true;
+        let auto = animation_extent(&proj).unwrap().max_iter.unwrap();
+        assert!(auto.max > auto.min, "{auto:?}");
+        assert!(auto.max_time > auto.min_time, "{auto:?}");
+
+        // The script runs last, so it has the final say over the keyed zoom.
+        proj.script.enabled = true;
+        proj.script.source = "fn frame(t, p) { p.scale = 50.0 + 10.0 * t; p }".into();
+        let scripted = animation_extent(&proj).unwrap().scale.unwrap();
+        assert_eq!((scripted.min, scripted.min_time), (50.0, 0.0));
+        assert!((scripted.max - 69.0).abs() < 1e-4, "{scripted:?}");
+    }
+
+    #[test]
+    fn empty_export_has_no_extent() {
+        let mut proj = zooming();
+        proj.export.duration = 0.0;
+        let extent = animation_extent(&proj).unwrap();
+        assert_eq!(extent.frames, 0);
+        assert!(extent.scale.is_none() && extent.max_iter_curve.is_empty());
+    }
+
+    #[test]
+    fn report_flags_precision_and_estimates_from_a_benchmark() {
+        let mut proj = zooming();
+        proj.anim.track_mut(TrackKind::Zoom).keys[1].v = 1e9;
+        let mut benchmark = RenderStats::new(&crate::CpuRenderer, (100, 100), &proj.fractal);
+        benchmark.total_ms = 50.0;
+        let report = project_report(&proj, Some(&benchmark));
+        assert_eq!(report.tracks.len(), 1);
+        assert_eq!(report.tracks[0].keys, 2);
+        assert_eq!((report.tracks[0].start, report.tracks[0].end), (0.0, 2.0));
+        assert!(report.beyond_f32);
+        assert_eq!(report.precision_needed, Precision::F64);
+        assert!(report
+            .warnings
+            .iter()
+            .any(|w| w.contains("needs f64 precision")));
+        // 16 times the pixels, at the same Max Iter, for 20 frames.
+        let estimated = report.estimated_seconds.unwrap();
+        assert!((estimated - 0.05 * 16.0 * 20.0).abs() < 1e-9, "{estimated}");
+        let text = report.to_string();
+        assert!(text.contains("Estimated render time: 16 s"), "{text}");
+        assert!(text.contains("Zoom: 2 keys, 0:00.00 to 0:02.00"), "{text}");
+    }
+
+    #[test]
+    fn report_reads_frames_as_the_checks_do() {
+        let mut proj = zooming();
+        proj.fractal.exposure = 0.0;
+        let report = project_report(&proj, None);
+        assert_eq!(report.estimated_seconds, None);
+        assert!(
+            report.warnings[0].starts_with("0:00.00: Exposure 0"),
+            "{:?}",
+            report.warnings
+        );
+
+        proj.script.enabled = true;
+        proj.script.source = "fn frame(t, p) { p.nope() }".into();
+        let report = project_report(&proj, None);
+        assert!(
+            report.warnings[0].starts_with("Script: "),
+            "{:?}",
+            report.warnings
+        );
+        assert_eq!(report.extent.frames, 20);
+        assert!(report.extent.scale.is_some());
+    }
+}
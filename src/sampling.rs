@@ -0,0 +1,245 @@
+//! Sub-pixel sample patterns for supersampling, and the per-frame seeds that jitter them.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{seeded_u32, FractalParams, RandomStream};
+
+/// Largest [`FractalParams::supersample`]: a 4×4 grid of samples per pixel.
+pub const MAX_SUPERSAMPLE: u32 = 4;
+
+/// Where the samples of one pixel go.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SamplePattern {
+    /// Regular grid; the same every frame, so edges can shimmer in motion.
+    #[default]
+    Grid,
+    /// Grid rotated by atan(1/2), which resolves near-horizontal and near-vertical edges
+    /// better than a plain grid.
+    RotatedGrid,
+    /// Halton (2, 3) points, continued from a different index each frame.
+    Halton,
+    /// Roberts' R2 sequence, continued from a different index each frame.
+    R2,
+}
+
+impl SamplePattern {
+    pub const ALL: [SamplePattern; 4] = [
+        SamplePattern::Grid,
+        SamplePattern::RotatedGrid,
+        SamplePattern::Halton,
+        SamplePattern::R2,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SamplePattern::Grid => "Grid",
+            SamplePattern::RotatedGrid => "Rotated grid",
+            SamplePattern::Halton => "Halton jitter",
+            SamplePattern::R2 => "R2 jitter",
+        }
+    }
+
+    /// Whether the pattern changes with the seed.
+    pub fn is_jittered(&self) -> bool {
+        matches!(self, SamplePattern::Halton | SamplePattern::R2)
+    }
+}
+
+/// Pattern and seed a frame is sampled with. Set per frame by the exporter, never saved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub struct SampleJitter {
+    pub pattern: SamplePattern,
+    pub seed: u32,
+}
+
+/// Jitter seed for frame `frame` of a project seeded with `project_seed`, drawn from its
+/// [`RandomStream::SampleJitter`] stream: an export always jitters the same way while
+/// neighbouring frames get unrelated patterns.
+pub fn frame_seed(project_seed: u64, frame: u32) -> u32 {
+    seeded_u32(project_seed, RandomStream::SampleJitter, u64::from(frame))
+}
+
+/// `count` sample positions inside a pixel, each in [0, 1)². Jittered patterns take a
+/// different stretch of their sequence for every `seed`; the grids ignore it.
+pub fn sample_offsets(pattern: SamplePattern, count: u32, seed: u32) -> Vec<[f32; 2]> {
+    let count = count.max(1);
+    match pattern {
+        SamplePattern::Grid => grid(count).collect(),
+        SamplePattern::RotatedGrid => {
+            // atan(1/2): every row and column of the rotated grid lands on its own offset.
+            let (sin, cos) = 0.5f32.atan().sin_cos();
+            grid(count)
+                .map(|[x, y]| {
+                    let (u, v) = (x - 0.5, y - 0.5);
+                    [wrap(u * cos - v * sin + 0.5), wrap(u * sin + v * cos + 0.5)]
+                })
+                .collect()
+        }
+        SamplePattern::Halton => {
+            let start = u64::from(seed) * u64::from(count);
+            (0..u64::from(count))
+                .map(|i| {
+                    [
+                        radical_inverse(start + i + 1, 2),
+                        radical_inverse(start + i + 1, 3),
+                    ]
+                })
+                .collect()
+        }
+        SamplePattern::R2 => {
+            // Powers of the inverse plastic number.
+            const G: f64 = 1.324_717_957_244_746;
+            let (a1, a2) = (1.0 / G, 1.0 / (G * G));
+            let start = u64::from(seed) * u64::from(count);
+            (0..u64::from(count))
+                .map(|i| {
+                    let n = (start + i) as f64;
+                    [
+                        wrap((0.5 + a1 * n).fract() as f32),
+                        wrap((0.5 + a2 * n).fract() as f32),
+                    ]
+                })
+                .collect()
+        }
+    }
+}
+
+/// Index of the offset nearest the middle of the pixel, the sample a supersampled pixel
+/// keeps as its [`crate::PixelSample`].
+pub fn central_sample(offsets: &[[f32; 2]]) -> usize {
+    let distance = |[x, y]: [f32; 2]| (x - 0.5).hypot(y - 0.5);
+    (0..offsets.len())
+        .min_by(|&a, &b| distance(offsets[a]).total_cmp(&distance(offsets[b])))
+        .unwrap_or(0)
+}
+
+impl FractalParams {
+    /// [`FractalParams::supersample`] within 1..=[`MAX_SUPERSAMPLE`].
+    pub fn supersample_side(&self) -> u32 {
+        self.supersample.clamp(1, MAX_SUPERSAMPLE)
+    }
+
+    /// Where each pixel of the frame is sampled, following its pattern and seed. A lone
+    /// sample always sits in the middle of the pixel.
+    pub fn sample_offsets(&self) -> Vec<[f32; 2]> {
+        let side = self.supersample_side();
+        if side == 1 {
+            return vec![[0.5, 0.5]];
+        }
+        sample_offsets(self.sampling.pattern, side * side, self.sampling.seed)
+    }
+}
+
+/// Cell centers of the smallest square grid holding `count` samples, row by row.
+fn grid(count: u32) -> impl Iterator<Item = [f32; 2]> {
+    let side = (count as f32).sqrt().ceil() as u32;
+    (0..count).map(move |i| {
+        [
+            ((i % side) as f32 + 0.5) / side as f32,
+            ((i / side) as f32 + 0.5) / side as f32,
+        ]
+    })
+}
+
+/// `n` mirrored around the radix point in `base`, in [0, 1).
+fn radical_inverse(mut n: u64, base: u64) -> f32 {
+    let mut inv = 1.0 / base as f64;
+    let mut result = 0.0;
+    while n > 0 {
+        result += (n % base) as f64 * inv;
+        n /= base;
+        inv /= base as f64;
+    }
+    wrap(result as f32)
+}
+
+/// Into [0, 1), guarding against values that round up to 1.0 in `f32`.
+fn wrap(x: f32) -> f32 {
+    let x = x.rem_euclid(1.0);
+    if x >= 1.0 {
+        0.0
+    } else {
+        x
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offsets_stay_inside_the_pixel() {
+        for pattern in SamplePattern::ALL {
+            for count in [1, 2, 4, 9, 16, 64] {
+                for seed in [0, 1, 7, u32::MAX] {
+                    let offsets = sample_offsets(pattern, count, seed);
+                    assert_eq!(offsets.len(), count as usize);
+                    assert!(
+                        offsets.iter().flatten().all(|v| (0.0..1.0).contains(v)),
+                        "{pattern:?} ×{count} seed {seed}: {offsets:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn offsets_cover_the_pixel() {
+        // Every quadrant gets a share of 16 samples, and no two samples coincide.
+        for pattern in SamplePattern::ALL {
+            let offsets = sample_offsets(pattern, 16, 3);
+            for quadrant in 0..4 {
+                let (qx, qy) = ((quadrant % 2) as f32 * 0.5, (quadrant / 2) as f32 * 0.5);
+                let inside = offsets
+                    .iter()
+                    .filter(|[x, y]| (qx..qx + 0.5).contains(x) && (qy..qy + 0.5).contains(y))
+                    .count();
+                assert!(inside >= 2, "{pattern:?} quadrant {quadrant}: {offsets:?}");
+            }
+            for (i, a) in offsets.iter().enumerate() {
+                assert!(offsets[i + 1..].iter().all(|b| a != b), "{pattern:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn patterns_are_deterministic() {
+        for pattern in SamplePattern::ALL {
+            assert_eq!(
+                sample_offsets(pattern, 9, 42),
+                sample_offsets(pattern, 9, 42)
+            );
+            let other_frame = sample_offsets(pattern, 9, 43);
+            assert_eq!(
+                pattern.is_jittered(),
+                other_frame != sample_offsets(pattern, 9, 42),
+                "{pattern:?}"
+            );
+        }
+        assert_eq!(
+            sample_offsets(SamplePattern::Grid, 4, 0),
+            [[0.25, 0.25], [0.75, 0.25], [0.25, 0.75], [0.75, 0.75]]
+        );
+        assert_eq!(frame_seed(5, 12), frame_seed(5, 12));
+        assert_ne!(frame_seed(5, 12), frame_seed(5, 13));
+        assert_ne!(frame_seed(5, 0), frame_seed(5, 1));
+        assert_ne!(frame_seed(5, 12), frame_seed(6, 12));
+    }
+
+    #[test]
+    fn supersampling_keeps_the_sample_nearest_the_middle() {
+        let mut params = FractalParams::default().with_supersample(3);
+        let offsets = params.sample_offsets();
+        assert_eq!(offsets.len(), 9);
+        assert_eq!(offsets[central_sample(&offsets)], [0.5, 0.5]);
+
+        params.sampling.pattern = SamplePattern::R2;
+        assert_eq!(
+            params.clone().with_supersample(1).sample_offsets(),
+            [[0.5, 0.5]]
+        );
+        assert_eq!(params.clone().with_supersample(0).sample_offsets().len(), 1);
+        assert_eq!(params.with_supersample(9).sample_offsets().len(), 16);
+    }
+}
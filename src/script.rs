@@ -0,0 +1,288 @@
+//! Per-frame parameter modulation with sandboxed Rhai scripts.
+
+use rhai::{Dynamic, Engine, EvalAltResult, Map, Position, Scope, AST};
+use serde::{Deserialize, Serialize};
+
+use crate::{EvaluatedFrame, Project};
+
+/// Upper bound on Rhai operations per `frame` call; stops runaway loops.
+pub const SCRIPT_MAX_OPERATIONS: u64 = 200_000;
+
+/// Script source stored with a project.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct FrameScript {
+    pub enabled: bool,
+    pub source: String,
+}
+
+/// A compile or runtime failure, with the script line where Rhai reports one.
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+#[error("{}{message}", line.map(|l| format!("line {l}: ")).unwrap_or_default())]
+pub struct ScriptError {
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl ScriptError {
+    fn new(message: impl Into<String>) -> Self {
+        Self {
+            line: None,
+            message: message.into(),
+        }
+    }
+
+    fn at(pos: Position, message: impl Into<String>) -> Self {
+        Self {
+            line: pos.line(),
+            message: message.into(),
+        }
+    }
+
+    fn from_eval(mut err: EvalAltResult) -> Self {
+        // Errors inside `frame` arrive wrapped in the call; report the innermost position.
+        while let EvalAltResult::ErrorInFunctionCall(_, _, inner, _) = err {
+            err = *inner;
+        }
+        let pos = err.position();
+        err.clear_position();
+        Self::at(pos, err.to_string())
+    }
+}
+
+/// Parameters a script may read and write, as named in the `params` map.
+pub const SCRIPT_PARAMS: [&str; 9] = [
+    "center_x",
+    "center_y",
+    "scale",
+    "rotation",
+    "palette_phase",
+    "julia_re",
+    "julia_im",
+    "power",
+    "exposure",
+];
+
+/// Built-in examples offered by the script editor: (title, source).
+pub const EXAMPLE_SCRIPTS: [(&str, &str); 3] = [
+    (
+        "Breathing palette",
+        "// Palette phase swings with a 7 second period.
+fn frame(t, params) {
+    params.palette_phase = 0.5 + 0.5 * sin(t * 2.0 * PI() / 7.0);
+    params
+}
+",
+    ),
+    (
+        "Julia orbit",
+        "// Julia c traces a circle of radius 0.7885 once every 12 seconds.
+fn frame(t, params) {
+    let a = t * 2.0 * PI() / 12.0;
+    params.julia_re = 0.7885 * cos(a);
+    params.julia_im = 0.7885 * sin(a);
+    params
+}
+",
+    ),
+    (
+        "Slow spin",
+        "// Adds a constant rotation on top of the keyframed camera.
+fn frame(t, params) {
+    params.rotation += t * 0.1;
+    params
+}
+",
+    ),
+];
+
+/// A compiled script ready to run once per frame.
+pub struct ScriptRunner {
+    engine: Engine,
+    ast: AST,
+}
+
+impl ScriptRunner {
+    /// Compiles `source`, which must define `fn frame(t, params)`.
+    pub fn compile(source: &str) -> Result<Self, ScriptError> {
+        let engine = sandboxed_engine();
+        let ast = engine
+            .compile(source)
+            .map_err(|e| ScriptError::at(e.position(), e.err_type().to_string()))?;
+        if !ast
+            .iter_functions()
+            .any(|f| f.name == "frame" && f.params.len() == 2)
+        {
+            return Err(ScriptError::new("script must define fn frame(t, params)"));
+        }
+        Ok(Self { engine, ast })
+    }
+
+    /// The project's script if it is enabled and non-empty.
+    pub fn for_project(proj: &Project) -> Result<Option<Self>, ScriptError> {
+        if !proj.script.enabled || proj.script.source.trim().is_empty() {
+            return Ok(None);
+        }
+        Self::compile(&proj.script.source).map(Some)
+    }
+
+    /// Calls `frame(time, params)` and writes the returned values back into `frame`.
+    pub fn apply(&self, time: f32, frame: &mut EvaluatedFrame) -> Result<(), ScriptError> {
+        let params = to_map(frame);
+        let result: Dynamic = self
+            .engine
+            .call_fn(
+                &mut Scope::new(),
+                &self.ast,
+                "frame",
+                (time as rhai::FLOAT, params),
+            )
+            .map_err(|e| ScriptError::from_eval(*e))?;
+        let Some(map) = result.try_cast::<Map>() else {
+            return Err(ScriptError::new("frame() must return the params map"));
+        };
+        from_map(&map, frame)
+    }
+}
+
+fn sandboxed_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_max_operations(SCRIPT_MAX_OPERATIONS)
+        .set_max_call_levels(32)
+        .set_max_expr_depths(64, 32)
+        .set_max_string_size(4096)
+        .set_max_array_size(4096)
+        .set_max_map_size(256)
+        .set_module_resolver(rhai::module_resolvers::DummyModuleResolver::new())
+        .on_print(|_| {})
+        .on_debug(|_, _, _| {});
+    engine.disable_symbol("eval");
+    engine
+}
+
+fn to_map(frame: &EvaluatedFrame) -> Map {
+    let values = [
+        frame.camera.center.re,
+        frame.camera.center.im,
+        frame.camera.scale,
+        frame.camera.rotation.into(),
+        frame.fractal.palette_phase.into(),
+        frame.fractal.c.re.into(),
+        frame.fractal.c.im.into(),
+        frame.fractal.power.into(),
+        frame.fractal.exposure.into(),
+    ];
+    SCRIPT_PARAMS
+        .iter()
+        .zip(values)
+        .map(|(name, v)| ((*name).into(), Dynamic::from_float(v as rhai::FLOAT)))
+        .collect()
+}
+
+/// Writes `map` into `frame`; on error `frame` is left untouched.
+fn from_map(map: &Map, frame: &mut EvaluatedFrame) -> Result<(), ScriptError> {
+    let mut out = frame.clone();
+    for (name, value) in map {
+        let v = value
+            .as_float()
+            .or_else(|_| value.as_int().map(|i| i as rhai::FLOAT))
+            .map_err(|ty| ScriptError::new(format!("params.{name} must be a number, not {ty}")))?;
+        let not_finite = || ScriptError::new(format!("params.{name} is not finite"));
+        if !v.is_finite() {
+            return Err(not_finite());
+        }
+        let slot = match name.as_str() {
+            "center_x" => {
+                out.camera.center.re = v;
+                continue;
+            }
+            "center_y" => {
+                out.camera.center.im = v;
+                continue;
+            }
+            "scale" => {
+                out.camera.scale = v;
+                continue;
+            }
+            "rotation" => &mut out.camera.rotation,
+            "palette_phase" => &mut out.fractal.palette_phase,
+            "julia_re" => &mut out.fractal.c.re,
+            "julia_im" => &mut out.fractal.c.im,
+            "power" => &mut out.fractal.power,
+            "exposure" => &mut out.fractal.exposure,
+            other => {
+                return Err(ScriptError::new(format!(
+                    "params.{other} is not a scriptable parameter"
+                )))
+            }
+        };
+        *slot = v as f32;
+        if !slot.is_finite() {
+            return Err(not_finite());
+        }
+    }
+    *frame = out;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{evaluate_frame, EvalTarget};
+
+    fn frame() -> EvaluatedFrame {
+        evaluate_frame(&Project::default(), 0.0, EvalTarget::Preview)
+    }
+
+    #[test]
+    fn examples_compile_and_run() {
+        for (name, source) in EXAMPLE_SCRIPTS {
+            let runner = ScriptRunner::compile(source).unwrap_or_else(|e| panic!("{name}: {e}"));
+            let mut f = frame();
+            runner
+                .apply(3.5, &mut f)
+                .unwrap_or_else(|e| panic!("{name}: {e}"));
+        }
+    }
+
+    #[test]
+    fn writes_whitelisted_params() {
+        let runner = ScriptRunner::compile(
+            "fn frame(t, params) { params.palette_phase = t / 10.0; params.scale = 2; params }",
+        )
+        .unwrap();
+        let mut f = frame();
+        runner.apply(5.0, &mut f).unwrap();
+        assert_eq!(f.fractal.palette_phase, 0.5);
+        assert_eq!(f.camera.scale, 2.0);
+    }
+
+    #[test]
+    fn rejects_unknown_params_and_missing_frame() {
+        let runner =
+            ScriptRunner::compile("fn frame(t, params) { params.max_iter = 5; params }").unwrap();
+        assert!(runner.apply(0.0, &mut frame()).is_err());
+        assert!(ScriptRunner::compile("fn other(t) { t }").is_err());
+    }
+
+    #[test]
+    fn reports_line_numbers() {
+        let err = ScriptRunner::compile("fn frame(t, params) {\n  params.scale = ;\n}")
+            .err()
+            .unwrap();
+        assert_eq!(err.line, Some(2));
+        let runner =
+            ScriptRunner::compile("fn frame(t, params) {\n\n  params.scale = nope;\n  params\n}")
+                .unwrap();
+        let err = runner.apply(0.0, &mut frame()).unwrap_err();
+        assert_eq!(err.line, Some(3));
+    }
+
+    #[test]
+    fn operation_limit_stops_runaway_scripts() {
+        let runner = ScriptRunner::compile("fn frame(t, params) { loop {} }").unwrap();
+        assert!(runner.apply(0.0, &mut frame()).is_err());
+    }
+}
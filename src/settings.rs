@@ -0,0 +1,164 @@
+//! Per-user preferences shared by the GUI and the CLI.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use matterhorn_core::{
+    InputMapping, QualityProfile, RenderBackend, TileTuning, TrackGroup, DEFAULT_FRAME_BUDGET_MS,
+};
+use serde::{Deserialize, Serialize};
+
+/// Per-user preferences persisted to the platform config directory.
+/// Deliberately separate from `Project`: nothing here travels with a scene file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct AppSettings {
+    pub(crate) side_panel_width: f32,
+    pub(crate) timeline_height: f32,
+    /// Share of the central panel's width given to the main view beside the Julia view.
+    pub(crate) julia_split: f32,
+    pub(crate) preview_scale: f32, // fraction of the viewport resolution rendered live
+    /// Side-panel sliders span their wider expert ranges.
+    pub(crate) expert_ranges: bool,
+    /// Hold the preview to `frame_budget_ms` per frame while the view changes.
+    pub(crate) budget_preview: bool,
+    pub(crate) frame_budget_ms: f32,
+    pub(crate) last_project_dir: Option<PathBuf>,
+    pub(crate) last_palette_dir: Option<PathBuf>,
+    pub(crate) last_export_dir: Option<PathBuf>,
+    pub(crate) ffmpeg_path: PathBuf,
+    pub(crate) threads: usize, // 0 = one per core
+    pub(crate) default_backend: RenderBackend,
+    pub(crate) snapshot_use_view_size: bool,
+    pub(crate) snapshot_width: u32,
+    pub(crate) snapshot_height: u32,
+    pub(crate) snapshot_dir: Option<PathBuf>,
+    pub(crate) live_input: LiveInputSettings,
+    pub(crate) show_render_stats: bool,
+    /// Magnification and pixel size in the corner of the viewport.
+    pub(crate) show_zoom_readout: bool,
+    /// CSV or JSON Lines file that receives one record per preview and export frame.
+    pub(crate) stats_log: Option<PathBuf>,
+    /// Dope-sheet track groups folded shut.
+    pub(crate) collapsed_track_groups: Vec<TrackGroup>,
+    /// Quality profiles saved alongside the built-in ones.
+    pub(crate) quality_profiles: Vec<QualityProfile>,
+    /// Auto tile sizes calibrated on this machine.
+    pub(crate) tile_tuning: TileTuning,
+    /// Ids of the guided tours already finished or skipped.
+    pub(crate) seen_tours: Vec<String>,
+    /// Reload the open project when its file is changed by another program.
+    pub(crate) watch_project: bool,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            side_panel_width: 320.0,
+            timeline_height: 200.0,
+            julia_split: 0.5,
+            preview_scale: 1.0,
+            expert_ranges: false,
+            budget_preview: false,
+            frame_budget_ms: DEFAULT_FRAME_BUDGET_MS,
+            last_project_dir: None,
+            last_palette_dir: None,
+            last_export_dir: None,
+            ffmpeg_path: PathBuf::from("ffmpeg"),
+            threads: 0,
+            default_backend: RenderBackend::default(),
+            snapshot_use_view_size: true,
+            snapshot_width: 1920,
+            snapshot_height: 1080,
+            snapshot_dir: None,
+            live_input: LiveInputSettings::default(),
+            show_render_stats: false,
+            show_zoom_readout: true,
+            stats_log: None,
+            collapsed_track_groups: Vec::new(),
+            quality_profiles: Vec::new(),
+            tile_tuning: TileTuning::default(),
+            seen_tours: Vec::new(),
+            watch_project: true,
+        }
+    }
+}
+
+/// MIDI/OSC listeners and the controls mapped onto parameters.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct LiveInputSettings {
+    pub(crate) osc_enabled: bool,
+    pub(crate) osc_port: u16,
+    /// Name of the MIDI input port to open, as listed by the system.
+    pub(crate) midi_port: Option<String>,
+    pub(crate) mappings: Vec<InputMapping>,
+}
+
+impl Default for LiveInputSettings {
+    fn default() -> Self {
+        Self {
+            osc_enabled: false,
+            osc_port: 9000,
+            midi_port: None,
+            mappings: Vec::new(),
+        }
+    }
+}
+
+impl AppSettings {
+    /// `None` in the browser build, where preferences last for the session only.
+    pub(crate) fn path() -> Option<PathBuf> {
+        #[cfg(target_arch = "wasm32")]
+        return None;
+        #[cfg(not(target_arch = "wasm32"))]
+        directories::ProjectDirs::from("", "AH", "Matterhorn AH")
+            .map(|dirs| dirs.config_dir().join("settings.toml"))
+    }
+
+    /// Missing files yield defaults; unreadable or corrupt files are reported as errors.
+    pub(crate) fn load() -> Result<Self, String> {
+        let Some(path) = Self::path() else {
+            return Ok(Self::default());
+        };
+        match fs::read_to_string(&path) {
+            Ok(data) => toml::from_str(&data)
+                .map_err(|e| format!("Settings file {} is invalid: {e}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("Could not read {}: {e}", path.display())),
+        }
+    }
+
+    /// Folder for dialog-free snapshots: the configured one, else Pictures/Matterhorn AH.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn snapshot_dir(&self) -> PathBuf {
+        self.snapshot_dir
+            .clone()
+            .or_else(|| {
+                directories::UserDirs::new()
+                    .and_then(|dirs| dirs.picture_dir().map(|p| p.join("Matterhorn AH")))
+            })
+            .unwrap_or_else(|| PathBuf::from("snapshots"))
+    }
+
+    pub(crate) fn save(&self) -> Result<(), String> {
+        if cfg!(target_arch = "wasm32") {
+            return Ok(());
+        }
+        let path = Self::path().ok_or("No config directory available")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let data = toml::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(&path, data).map_err(|e| e.to_string())
+    }
+}
+
+/// Records the directory of a file picked in a dialog so the next dialog opens there.
+pub(crate) fn remember_dir(slot: &mut Option<PathBuf>, picked: &Path) {
+    if let Some(dir) = picked.parent() {
+        *slot = Some(dir.to_path_buf());
+    }
+}
@@ -0,0 +1,466 @@
+//! Compact, versioned view descriptors for pasting into a chat message.
+//!
+//! `matterhorn:v1;kind=mandelbrot;re=-0.7436439;im=0.13182591;scale=3.2e3;iter=2000`
+//! with optional `rot`, `power`, `jre`/`jim`, `pre`/`pim`, `seq`/`warmup`, `formula`,
+//! `phase` and `pal` fields. Fields may come in any order and unknown ones are skipped, so
+//! later versions can add fields freely.
+
+use std::{fmt::Display, ops::RangeInclusive, str::FromStr};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+use crate::{
+    formulas, Camera, Complex, Complex64, CustomFormula, FractalKind, FractalParams, PaletteStop,
+    ParamField, Project,
+};
+
+/// Prefix and version tag every share string starts with.
+pub const SHARE_PREFIX: &str = "matterhorn:v1";
+
+/// Largest accepted `iter` value.
+pub const SHARE_MAX_ITER: u32 = 1_000_000;
+
+/// Longest accepted Lyapunov sequence, in letters.
+pub const SHARE_MAX_SEQUENCE: usize = 64;
+
+/// Longest accepted custom formula, in characters.
+pub const SHARE_MAX_FORMULA: usize = 256;
+
+/// Largest accepted palette, in stops.
+pub const SHARE_MAX_STOPS: usize = 256;
+
+/// A view decoded from a share string. Fields the string left out stay `None`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SharedView {
+    pub kind: FractalKind,
+    pub center: Complex64,
+    pub scale: f64,
+    pub rotation: f32,
+    pub max_iter: u32,
+    pub power: Option<f32>,
+    pub julia_c: Option<Complex>,
+    pub phoenix_p: Option<Complex>,
+    pub lyapunov_sequence: Option<String>,
+    pub lyapunov_warmup: Option<u32>,
+    pub custom_formula: Option<String>,
+    pub palette_phase: f32,
+    pub palette: Option<Vec<PaletteStop>>,
+}
+
+/// Encodes the view; `with_palette` adds the gradient stops.
+pub fn to_share_string(fractal: &FractalParams, camera: &Camera, with_palette: bool) -> String {
+    let mut out = format!(
+        "{SHARE_PREFIX};kind={};re={};im={};scale={:e};iter={}",
+        kind_name(fractal.kind),
+        camera.center.re,
+        camera.center.im,
+        camera.scale,
+        fractal.max_iter
+    );
+    if camera.rotation != 0.0 {
+        out += &format!(";rot={}", camera.rotation);
+    }
+    if fractal.uses(ParamField::Power) {
+        out += &format!(";power={}", fractal.power);
+    }
+    if fractal.uses(ParamField::JuliaRe) {
+        out += &format!(";jre={};jim={}", fractal.c.re, fractal.c.im);
+    }
+    if fractal.uses(ParamField::PhoenixRe) {
+        let p = fractal.phoenix_p;
+        out += &format!(";pre={};pim={}", p.re, p.im);
+    }
+    if fractal.kind == FractalKind::Lyapunov {
+        // Only the letters that count, which keeps the field free of separators.
+        let sequence: String = fractal
+            .lyapunov_sequence
+            .chars()
+            .filter(|ch| matches!(ch, 'A' | 'B' | 'a' | 'b'))
+            .take(SHARE_MAX_SEQUENCE)
+            .collect();
+        if !sequence.is_empty() {
+            out += &format!(";seq={sequence}");
+        }
+        out += &format!(";warmup={}", fractal.lyapunov_warmup);
+    }
+    if fractal.kind == FractalKind::Custom {
+        // The language has no `;`, so the formula goes in as typed. One the decoder would
+        // refuse is left out, and the view opens with the default formula instead.
+        let formula = fractal.custom_formula.trim();
+        if formula.chars().count() <= SHARE_MAX_FORMULA && CustomFormula::parse(formula).is_ok() {
+            out += &format!(";formula={formula}");
+        }
+    }
+    if fractal.palette_phase != 0.0 {
+        out += &format!(";phase={}", fractal.palette_phase);
+    }
+    if with_palette && !fractal.palette.is_empty() {
+        out += &format!(";pal={}", encode_palette(&fractal.palette));
+    }
+    out
+}
+
+/// Decodes a share string. Surrounding whitespace and unknown fields are ignored.
+pub fn parse_share_string(text: &str) -> Result<SharedView, String> {
+    let text = text.trim();
+    let mut fields = text.split(';');
+    let head = fields.next().unwrap_or_default().trim();
+    if head != SHARE_PREFIX {
+        return Err(match head.strip_prefix("matterhorn:") {
+            Some(version) => format!("unsupported share string version {version:?}"),
+            None => format!("not a Matterhorn view: expected it to start with {SHARE_PREFIX}"),
+        });
+    }
+
+    let mut kind = None;
+    let (mut re, mut im, mut scale, mut iter) = (None, None, None, None);
+    let mut view = SharedView {
+        kind: FractalKind::Mandelbrot,
+        center: Complex64::default(),
+        scale: 1.0,
+        rotation: 0.0,
+        max_iter: 1,
+        power: None,
+        julia_c: None,
+        phoenix_p: None,
+        lyapunov_sequence: None,
+        lyapunov_warmup: None,
+        custom_formula: None,
+        palette_phase: 0.0,
+        palette: None,
+    };
+    let (mut jre, mut jim) = (None, None);
+    let (mut pre, mut pim) = (None, None);
+    for field in fields {
+        let field = field.trim();
+        if field.is_empty() {
+            continue;
+        }
+        let Some((key, value)) = field.split_once('=') else {
+            return Err(format!("field {field:?} is not key=value"));
+        };
+        let (key, value) = (key.trim(), value.trim());
+        match key {
+            "kind" => {
+                kind = Some(
+                    formulas()
+                        .iter()
+                        .map(|f| f.kind())
+                        .find(|&k| kind_name(k) == value)
+                        .ok_or_else(|| format!("unknown fractal kind {value:?}"))?,
+                )
+            }
+            "re" => re = Some(number(key, value, -4.0..=4.0)?),
+            "im" => im = Some(number(key, value, -4.0..=4.0)?),
+            "scale" => scale = Some(number(key, value, f64::MIN_POSITIVE..=f64::MAX)?),
+            "iter" => {
+                iter = Some(
+                    value
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|i| (1..=SHARE_MAX_ITER).contains(i))
+                        .ok_or_else(|| {
+                            format!("iter must be between 1 and {SHARE_MAX_ITER}, not {value:?}")
+                        })?,
+                )
+            }
+            "rot" => view.rotation = number(key, value, -1e4..=1e4)?,
+            "power" => view.power = Some(number(key, value, 1.0..=32.0)?),
+            "jre" => jre = Some(number(key, value, -4.0..=4.0)?),
+            "jim" => jim = Some(number(key, value, -4.0..=4.0)?),
+            "pre" => pre = Some(number(key, value, -4.0..=4.0)?),
+            "pim" => pim = Some(number(key, value, -4.0..=4.0)?),
+            "seq" => {
+                if value.is_empty()
+                    || value.len() > SHARE_MAX_SEQUENCE
+                    || !value.chars().all(|ch| matches!(ch, 'A' | 'B' | 'a' | 'b'))
+                {
+                    return Err(format!(
+                        "seq must be 1 to {SHARE_MAX_SEQUENCE} letters A and B, not {value:?}"
+                    ));
+                }
+                view.lyapunov_sequence = Some(value.to_string());
+            }
+            "warmup" => view.lyapunov_warmup = Some(number(key, value, 0..=100_000)?),
+            "formula" => {
+                if value.chars().count() > SHARE_MAX_FORMULA {
+                    return Err(format!(
+                        "formula must be at most {SHARE_MAX_FORMULA} characters"
+                    ));
+                }
+                CustomFormula::parse(value).map_err(|e| format!("formula {value:?}: {e}"))?;
+                view.custom_formula = Some(value.to_string());
+            }
+            "phase" => view.palette_phase = number(key, value, -1e4..=1e4)?,
+            "pal" => view.palette = Some(decode_palette(value)?),
+            // Fields from newer versions.
+            _ => {}
+        }
+    }
+    let missing = |name: &str| format!("missing {name}");
+    view.kind = kind.ok_or_else(|| missing("kind"))?;
+    view.center = Complex64::new(
+        re.ok_or_else(|| missing("re"))?,
+        im.ok_or_else(|| missing("im"))?,
+    );
+    view.scale = scale.ok_or_else(|| missing("scale"))?;
+    view.max_iter = iter.ok_or_else(|| missing("iter"))?;
+    view.julia_c = match (jre, jim) {
+        (Some(re), Some(im)) => Some(Complex::new(re, im)),
+        (None, None) => None,
+        _ => return Err("jre and jim must be given together".into()),
+    };
+    view.phoenix_p = match (pre, pim) {
+        (Some(re), Some(im)) => Some(Complex::new(re, im)),
+        (None, None) => None,
+        _ => return Err("pre and pim must be given together".into()),
+    };
+    Ok(view)
+}
+
+impl SharedView {
+    /// Moves `proj` to this view. Fields the string did not carry keep their values.
+    pub fn apply(&self, proj: &mut Project) {
+        proj.fractal.kind = self.kind;
+        proj.fractal.max_iter = self.max_iter;
+        proj.fractal.palette_phase = self.palette_phase;
+        if let Some(power) = self.power {
+            proj.fractal.power = power;
+        }
+        if let Some(c) = self.julia_c {
+            proj.fractal.c = c;
+        }
+        if let Some(p) = self.phoenix_p {
+            proj.fractal.phoenix_p = p;
+        }
+        if let Some(sequence) = &self.lyapunov_sequence {
+            proj.fractal.lyapunov_sequence = sequence.clone();
+        }
+        if let Some(warmup) = self.lyapunov_warmup {
+            proj.fractal.lyapunov_warmup = warmup;
+        }
+        if let Some(formula) = &self.custom_formula {
+            proj.fractal.custom_formula = formula.clone();
+        }
+        if let Some(palette) = &self.palette {
+            proj.fractal.palette = palette.clone();
+        }
+        proj.camera = Camera {
+            center: self.center,
+            scale: self.scale,
+            rotation: self.rotation,
+            deep_center: None,
+        };
+    }
+}
+
+fn kind_name(kind: FractalKind) -> String {
+    serde_json::to_value(kind)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_owned))
+        .unwrap_or_default()
+}
+
+fn number<T: FromStr + PartialOrd + Display>(
+    key: &str,
+    value: &str,
+    range: RangeInclusive<T>,
+) -> Result<T, String> {
+    value
+        .parse::<T>()
+        .ok()
+        .filter(|v| range.contains(v))
+        .ok_or_else(|| {
+            format!(
+                "{key} must be a number between {} and {}, not {value:?}",
+                range.start(),
+                range.end()
+            )
+        })
+}
+
+/// Four bytes per stop: position, then red, green and blue, each scaled to 0..=255.
+fn encode_palette(stops: &[PaletteStop]) -> String {
+    let to_byte = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+    let bytes: Vec<u8> = stops
+        .iter()
+        .take(SHARE_MAX_STOPS)
+        .flat_map(|s| {
+            [
+                to_byte(s.pos),
+                to_byte(s.color[0]),
+                to_byte(s.color[1]),
+                to_byte(s.color[2]),
+            ]
+        })
+        .collect();
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+fn decode_palette(value: &str) -> Result<Vec<PaletteStop>, String> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(value)
+        .map_err(|e| format!("pal is not valid base64: {e}"))?;
+    if bytes.is_empty() || !bytes.len().is_multiple_of(4) || bytes.len() / 4 > SHARE_MAX_STOPS {
+        return Err(format!(
+            "pal must hold between 1 and {SHARE_MAX_STOPS} stops of 4 bytes"
+        ));
+    }
+    let f = |b: u8| b as f32 / 255.0;
+    Ok(bytes
+        .chunks(4)
+        .map(|c| PaletteStop::new(f(c[0]), [f(c[1]), f(c[2]), f(c[3])]))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn project(kind: FractalKind) -> Project {
+        let mut proj = Project::default();
+        proj.fractal.kind = kind;
+        proj.fractal.max_iter = 2000;
+        proj.fractal.power = 4.0;
+        proj.fractal.c = Complex::new(-0.4, 0.6);
+        proj.fractal.phoenix_p = Complex::new(-0.45, 0.1);
+        proj.fractal.lyapunov_sequence = "AABAB".into();
+        proj.fractal.lyapunov_warmup = 120;
+        proj.fractal.custom_formula = "z^3 + c*(1 - 0.5i)".into();
+        proj.fractal.palette_phase = 0.25;
+        proj.camera = Camera {
+            center: Complex64::new(-0.743_643_9, 0.131_825_91),
+            scale: 3.2e3,
+            rotation: 0.3,
+            deep_center: None,
+        };
+        proj
+    }
+
+    #[test]
+    fn round_trips_every_kind() {
+        for f in formulas() {
+            let proj = project(f.kind());
+            let text = to_share_string(&proj.fractal, &proj.camera, true);
+            let mut back = Project::default();
+            parse_share_string(&text).unwrap().apply(&mut back);
+            assert_eq!(back.fractal.kind, proj.fractal.kind, "{text}");
+            assert_eq!(back.camera.center, proj.camera.center);
+            assert_eq!(back.camera.scale, proj.camera.scale);
+            assert_eq!(back.camera.rotation, proj.camera.rotation);
+            assert_eq!(back.fractal.max_iter, 2000);
+            assert_eq!(back.fractal.palette_phase, 0.25);
+            if proj.fractal.uses(ParamField::PhoenixRe) {
+                assert_eq!(back.fractal.phoenix_p, proj.fractal.phoenix_p);
+            }
+            if proj.fractal.kind == FractalKind::Lyapunov {
+                assert_eq!(back.fractal.lyapunov_sequence, "AABAB");
+                assert_eq!(back.fractal.lyapunov_warmup, 120);
+            }
+            if proj.fractal.kind == FractalKind::Custom {
+                assert_eq!(back.fractal.custom_formula, "z^3 + c*(1 - 0.5i)");
+            }
+            assert_eq!(back.fractal.palette.len(), proj.fractal.palette.len());
+            for (a, b) in back.fractal.palette.iter().zip(&proj.fractal.palette) {
+                assert!((a.pos - b.pos).abs() <= 0.5 / 255.0);
+            }
+        }
+        // Formulas the decoder would refuse are left out rather than breaking the string.
+        let mut custom = project(FractalKind::Custom);
+        for formula in [
+            "z^2 +".to_string(),
+            format!("z^2 + {}", "1".repeat(SHARE_MAX_FORMULA)),
+        ] {
+            custom.fractal.custom_formula = formula;
+            let text = to_share_string(&custom.fractal, &custom.camera, false);
+            assert!(!text.contains("formula="), "{text}");
+            let view = parse_share_string(&text).unwrap();
+            assert_eq!(view.custom_formula, None);
+        }
+        let text = to_share_string(
+            &project(FractalKind::Julia).fractal,
+            &Camera::default(),
+            false,
+        );
+        assert_eq!(
+            parse_share_string(&text).unwrap().julia_c,
+            Some(Complex::new(-0.4, 0.6))
+        );
+    }
+
+    #[test]
+    fn accepts_any_field_order_whitespace_and_unknown_fields() {
+        let view = parse_share_string(
+            "  matterhorn:v1; iter=500 ;future=1;scale=2e2;im=0.1;re=-0.75;kind=burning_ship;\n",
+        )
+        .unwrap();
+        assert_eq!(view.kind, FractalKind::BurningShip);
+        assert_eq!(view.center, Complex64::new(-0.75, 0.1));
+        assert_eq!(view.scale, 200.0);
+        assert_eq!(view.max_iter, 500);
+        assert_eq!(view.palette, None);
+    }
+
+    #[test]
+    fn rejects_bad_strings_with_a_reason() {
+        let base = "matterhorn:v1;kind=mandelbrot;re=0;im=0;scale=100;iter=100";
+        assert!(parse_share_string(base).is_ok());
+        let cases: [(&str, &str); 13] = [
+            ("hello", "not a Matterhorn view"),
+            ("matterhorn:v9;kind=mandelbrot", "version"),
+            ("matterhorn:v1;re=0;im=0;scale=1;iter=1", "missing kind"),
+            (&format!("{base};kind=lambda"), "unknown fractal kind"),
+            (&format!("{base};scale=-5"), "scale"),
+            (&format!("{base};iter=0"), "iter"),
+            (&format!("{base};re=NaN"), "re"),
+            (&format!("{base};jre=0.1"), "together"),
+            (&format!("{base};seq=ABC"), "letters A and B"),
+            (&format!("{base};formula=z^2 +"), "ends early at column 6"),
+            (&format!("{base};pal=!!"), "base64"),
+            (&format!("{base};pal=AAAA"), "stops"),
+            (&format!("{base};oops"), "key=value"),
+        ];
+        for (text, reason) in cases {
+            let err = parse_share_string(text).unwrap_err();
+            assert!(err.contains(reason), "{text}: {err}");
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn never_panics_on_arbitrary_input(text in "\\PC{0,200}") {
+            let _ = parse_share_string(&text);
+        }
+
+        #[test]
+        fn never_panics_on_mangled_valid_strings(
+            cut in 0usize..120,
+            junk in "[;=a-z0-9.e+-]{0,20}",
+        ) {
+            let proj = project(FractalKind::Julia);
+            let mut text = to_share_string(&proj.fractal, &proj.camera, true);
+            text.truncate(cut.min(text.len()));
+            text.push_str(&junk);
+            let _ = parse_share_string(&text);
+        }
+
+        #[test]
+        fn valid_views_round_trip(
+            re in -2.0f32..2.0,
+            im in -2.0f32..2.0,
+            scale in 1.0f32..1e7,
+            iter in 1u32..100_000,
+        ) {
+            let mut proj = project(FractalKind::Mandelbrot);
+            proj.camera.center = Complex::new(re, im).into();
+            proj.camera.scale = scale.into();
+            proj.fractal.max_iter = iter;
+            let view = parse_share_string(&to_share_string(&proj.fractal, &proj.camera, false))
+                .unwrap();
+            prop_assert_eq!(view.center, proj.camera.center);
+            prop_assert_eq!(view.scale, f64::from(scale));
+            prop_assert_eq!(view.max_iter, iter);
+        }
+    }
+}
@@ -0,0 +1,527 @@
+//! Settings snippets: the fractal and camera, optionally with the palette and export
+//! settings, as a short TOML block for bug reports and forum posts.
+//!
+//! Snippets use the project file's layout, so a whole project file pastes as well. Every
+//! field is optional and unknown fields are skipped: pasting merges what the snippet
+//! carries and leaves the rest of the project alone. Paths never travel in a snippet.
+
+use std::fmt::{Debug, Display};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Camera, Complex, Complex64, ExportSettings, FractalKind, FractalParams, OrbitTrapKind,
+    PaletteStop, ParamField, Project, ProjectError, RangedParam, VideoCodec, SHARE_MAX_STOPS,
+};
+
+/// A parsed snippet. Fields it left out are `None`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SettingsSnippet {
+    pub fractal: Option<FractalSnippet>,
+    pub camera: Option<CameraSnippet>,
+    pub export: Option<ExportSnippet>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct FractalSnippet {
+    pub kind: Option<FractalKind>,
+    pub max_iter: Option<u32>,
+    pub escape_radius: Option<f32>,
+    pub power: Option<f32>,
+    pub c: Option<Complex>,
+    pub phoenix_p: Option<Complex>,
+    pub lyapunov_sequence: Option<String>,
+    pub lyapunov_warmup: Option<u32>,
+    pub custom_formula: Option<String>,
+    pub palette_phase: Option<f32>,
+    pub palette_cycle_speed: Option<f32>,
+    pub palette_repeat: Option<f32>,
+    pub exposure: Option<f32>,
+    pub gamma: Option<f32>,
+    pub palette: Option<Vec<PaletteStop>>,
+    pub orbit: OrbitSnippet,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct OrbitSnippet {
+    pub enabled: Option<bool>,
+    pub kind: Option<OrbitTrapKind>,
+    pub radius: Option<f32>,
+    pub softness: Option<f32>,
+    pub color: Option<[f32; 3]>,
+    pub point: Option<Complex>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct CameraSnippet {
+    pub center: Option<Complex64>,
+    pub scale: Option<f64>,
+    pub rotation: Option<f32>,
+}
+
+/// Output format only; `out_path`, `audio` and `quality` stay local.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ExportSnippet {
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub fps: Option<u32>,
+    pub duration: Option<f32>,
+    pub crf: Option<u8>,
+    pub codec: Option<VideoCodec>,
+}
+
+/// One setting a paste would change, shown as `max_iter 800→5000`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SettingChange {
+    pub field: &'static str,
+    pub from: String,
+    pub to: String,
+}
+
+impl std::fmt::Display for SettingChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}→{}", self.field, self.from, self.to)
+    }
+}
+
+/// Writes the snippet. Parameters the fractal kind ignores are left out, as is the orbit
+/// trap beyond `enabled = false` when it is off.
+pub fn to_settings_snippet(
+    fractal: &FractalParams,
+    camera: &Camera,
+    with_palette: bool,
+    export: Option<&ExportSettings>,
+) -> String {
+    let mut out = String::from("[fractal]\n");
+    out += &format!("kind = {}\n", string(&fractal.kind));
+    out += &format!("max_iter = {}\n", fractal.max_iter);
+    out += &format!("escape_radius = {}\n", num(fractal.escape_radius));
+    if fractal.uses(ParamField::Power) {
+        out += &format!("power = {}\n", num(fractal.power));
+    }
+    if fractal.uses(ParamField::JuliaRe) {
+        out += &format!("c = {}\n", complex(fractal.c.re, fractal.c.im));
+    }
+    if fractal.uses(ParamField::PhoenixRe) {
+        let p = fractal.phoenix_p;
+        out += &format!("phoenix_p = {}\n", complex(p.re, p.im));
+    }
+    if fractal.kind == FractalKind::Lyapunov {
+        out += &format!(
+            "lyapunov_sequence = {}\n",
+            string(&fractal.lyapunov_sequence)
+        );
+        out += &format!("lyapunov_warmup = {}\n", fractal.lyapunov_warmup);
+    }
+    if fractal.kind == FractalKind::Custom {
+        out += &format!("custom_formula = {}\n", string(&fractal.custom_formula));
+    }
+    out += &format!("palette_phase = {}\n", num(fractal.palette_phase));
+    if fractal.palette_cycle_speed != 0.0 {
+        out += &format!(
+            "palette_cycle_speed = {}\n",
+            num(fractal.palette_cycle_speed)
+        );
+    }
+    out += &format!("palette_repeat = {}\n", num(fractal.palette_repeat));
+    out += &format!("exposure = {}\n", num(fractal.exposure));
+    out += &format!("gamma = {}\n", num(fractal.gamma));
+    if with_palette {
+        out += "palette = [\n";
+        for stop in &fractal.palette {
+            out += &format!(
+                "    {{ pos = {}, color = {} }},\n",
+                num(stop.pos),
+                color(stop.color)
+            );
+        }
+        out += "]\n";
+    }
+    let orbit = &fractal.orbit;
+    if orbit.enabled {
+        out += &format!(
+            "orbit = {{ enabled = true, kind = {}, radius = {}, softness = {}, color = {}, point = {} }}\n",
+            string(&orbit.kind),
+            num(orbit.radius),
+            num(orbit.softness),
+            color(orbit.color),
+            complex(orbit.point.re, orbit.point.im)
+        );
+    } else {
+        out += "orbit = { enabled = false }\n";
+    }
+
+    out += "\n[camera]\n";
+    out += &format!("center = {}\n", complex(camera.center.re, camera.center.im));
+    out += &format!("scale = {}\n", num(camera.scale));
+    out += &format!("rotation = {}\n", num(camera.rotation));
+
+    if let Some(export) = export {
+        out += "\n[export]\n";
+        out += &format!("width = {}\n", export.width);
+        out += &format!("height = {}\n", export.height);
+        out += &format!("fps = {}\n", export.fps);
+        out += &format!("duration = {}\n", num(export.duration));
+        out += &format!("crf = {}\n", export.crf);
+        out += &format!("codec = {}\n", string(&export.codec));
+    }
+    out
+}
+
+/// Reads a TOML or JSON snippet, or a whole project file in either format.
+pub fn parse_settings_snippet(text: &str) -> Result<SettingsSnippet, String> {
+    let text = text.trim();
+    let snippet: SettingsSnippet = if text.starts_with('{') {
+        serde_json::from_str(text).map_err(|e| ProjectError::json(e).to_string())?
+    } else {
+        toml::from_str(text).map_err(|e| ProjectError::toml(e, text).to_string())?
+    };
+    if snippet.fractal.is_none() && snippet.camera.is_none() && snippet.export.is_none() {
+        return Err("no settings found: expected a [fractal], [camera] or [export] table".into());
+    }
+    snippet.validate()?;
+    Ok(snippet)
+}
+
+impl SettingsSnippet {
+    /// What [`SettingsSnippet::apply`] would change in `proj`, in file order.
+    pub fn changes(&self, proj: &Project) -> Vec<SettingChange> {
+        let mut after = proj.clone();
+        self.apply(&mut after);
+        diff(proj, &after)
+    }
+
+    /// Merges the snippet into `proj`. Fields it left out keep their values.
+    pub fn apply(&self, proj: &mut Project) {
+        if let Some(f) = &self.fractal {
+            let fractal = &mut proj.fractal;
+            set(&mut fractal.kind, f.kind);
+            set(&mut fractal.max_iter, f.max_iter);
+            set(&mut fractal.escape_radius, f.escape_radius);
+            set(&mut fractal.power, f.power);
+            set(&mut fractal.c, f.c);
+            set(&mut fractal.phoenix_p, f.phoenix_p);
+            set(&mut fractal.lyapunov_sequence, f.lyapunov_sequence.clone());
+            set(&mut fractal.lyapunov_warmup, f.lyapunov_warmup);
+            set(&mut fractal.custom_formula, f.custom_formula.clone());
+            set(&mut fractal.palette_phase, f.palette_phase);
+            set(&mut fractal.palette_cycle_speed, f.palette_cycle_speed);
+            set(&mut fractal.palette_repeat, f.palette_repeat);
+            set(&mut fractal.exposure, f.exposure);
+            set(&mut fractal.gamma, f.gamma);
+            set(&mut fractal.palette, f.palette.clone());
+            let (o, orbit) = (&f.orbit, &mut fractal.orbit);
+            set(&mut orbit.enabled, o.enabled);
+            set(&mut orbit.kind, o.kind);
+            set(&mut orbit.radius, o.radius);
+            set(&mut orbit.softness, o.softness);
+            set(&mut orbit.color, o.color);
+            set(&mut orbit.point, o.point);
+        }
+        if let Some(c) = &self.camera {
+            let camera = &mut proj.camera;
+            set(&mut camera.center, c.center);
+            set(&mut camera.scale, c.scale);
+            set(&mut camera.rotation, c.rotation);
+        }
+        if let Some(e) = &self.export {
+            let export = &mut proj.export;
+            set(&mut export.width, e.width);
+            set(&mut export.height, e.height);
+            set(&mut export.fps, e.fps);
+            set(&mut export.duration, e.duration);
+            set(&mut export.crf, e.crf);
+            set(&mut export.codec, e.codec);
+        }
+    }
+
+    /// Rejects values the renderer or exporter cannot use.
+    fn validate(&self) -> Result<(), String> {
+        let (f, c, e) = (
+            self.fractal.clone().unwrap_or_default(),
+            self.camera.clone().unwrap_or_default(),
+            self.export.clone().unwrap_or_default(),
+        );
+        let o = &f.orbit;
+        let ranged = [
+            (RangedParam::MaxIter, f.max_iter.map(f64::from)),
+            (RangedParam::EscapeRadius, f.escape_radius.map(f64::from)),
+            (RangedParam::PalettePhase, f.palette_phase.map(f64::from)),
+            (
+                RangedParam::PaletteCycleSpeed,
+                f.palette_cycle_speed.map(f64::from),
+            ),
+            (RangedParam::PaletteRepeat, f.palette_repeat.map(f64::from)),
+            (RangedParam::Exposure, f.exposure.map(f64::from)),
+            (RangedParam::Gamma, f.gamma.map(f64::from)),
+            (RangedParam::TrapRadius, o.radius.map(f64::from)),
+            (RangedParam::TrapSoftness, o.softness.map(f64::from)),
+            (RangedParam::CenterX, c.center.map(|c| c.re)),
+            (RangedParam::CenterY, c.center.map(|c| c.im)),
+            (RangedParam::Scale, c.scale),
+            (RangedParam::Rotation, c.rotation.map(f64::from)),
+            (RangedParam::Duration, e.duration.map(f64::from)),
+            (RangedParam::Fps, e.fps.map(f64::from)),
+            (RangedParam::Crf, e.crf.map(f64::from)),
+        ];
+        for (param, value) in ranged {
+            if let Some(value) = value {
+                param.check(value).map_err(|e| e.to_string())?;
+            }
+        }
+        if let Some(v) = f.power.filter(|v| !(v.is_finite() && *v > 0.0)) {
+            return Err(format!("power must be a positive number, not {v}"));
+        }
+        if let Some(palette) = &f.palette {
+            if palette.is_empty() || palette.len() > SHARE_MAX_STOPS {
+                return Err(format!(
+                    "palette must hold between 1 and {SHARE_MAX_STOPS} stops"
+                ));
+            }
+        }
+        for (name, value) in [("width", e.width), ("height", e.height)] {
+            if value == Some(0) {
+                return Err(format!("{name} must be at least 1"));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn set<T>(field: &mut T, value: Option<T>) {
+    if let Some(value) = value {
+        *field = value;
+    }
+}
+
+/// Fields that differ between `before` and `after`, compared as displayed.
+fn diff(before: &Project, after: &Project) -> Vec<SettingChange> {
+    let mut changes = Vec::new();
+    let mut field = |field: &'static str, show: fn(&Project) -> String| {
+        let (from, to) = (show(before), show(after));
+        if from != to {
+            changes.push(SettingChange { field, from, to });
+        }
+    };
+    field("kind", |p| p.fractal.kind.label().into());
+    field("max_iter", |p| p.fractal.max_iter.to_string());
+    field("escape_radius", |p| p.fractal.escape_radius.to_string());
+    field("power", |p| p.fractal.power.to_string());
+    field("c", |p| point(p.fractal.c.re, p.fractal.c.im));
+    field("phoenix_p", |p| {
+        point(p.fractal.phoenix_p.re, p.fractal.phoenix_p.im)
+    });
+    field("lyapunov_sequence", |p| p.fractal.lyapunov_sequence.clone());
+    field("lyapunov_warmup", |p| p.fractal.lyapunov_warmup.to_string());
+    field("custom_formula", |p| p.fractal.custom_formula.clone());
+    field("palette_phase", |p| p.fractal.palette_phase.to_string());
+    field("palette_cycle_speed", |p| {
+        p.fractal.palette_cycle_speed.to_string()
+    });
+    field("palette_repeat", |p| p.fractal.palette_repeat.to_string());
+    field("exposure", |p| p.fractal.exposure.to_string());
+    field("gamma", |p| p.fractal.gamma.to_string());
+    field("palette", |p| stops(&p.fractal.palette));
+    field("orbit", |p| on_off(p.fractal.orbit.enabled));
+    field("orbit.kind", |p| {
+        string(&p.fractal.orbit.kind).replace('"', "")
+    });
+    field("orbit.radius", |p| p.fractal.orbit.radius.to_string());
+    field("orbit.softness", |p| p.fractal.orbit.softness.to_string());
+    field("orbit.color", |p| hex(p.fractal.orbit.color));
+    field("orbit.point", |p| {
+        point(p.fractal.orbit.point.re, p.fractal.orbit.point.im)
+    });
+    field("center", |p| point(p.camera.center.re, p.camera.center.im));
+    field("scale", |p| p.camera.scale.to_string());
+    field("rotation", |p| p.camera.rotation.to_string());
+    field("export.size", |p| {
+        format!("{}×{}", p.export.width, p.export.height)
+    });
+    field("export.fps", |p| p.export.fps.to_string());
+    field("export.duration", |p| format!("{}s", p.export.duration));
+    field("export.crf", |p| p.export.crf.to_string());
+    field("export.codec", |p| p.export.codec.label().into());
+    changes
+}
+
+/// Stop count plus the colors in order, so a recolored palette of the same length still
+/// shows up as a change.
+fn stops(palette: &[PaletteStop]) -> String {
+    let colors: Vec<String> = palette.iter().map(|s| hex(s.color)).collect();
+    format!("{} stops ({})", palette.len(), colors.join(" "))
+}
+
+fn on_off(enabled: bool) -> String {
+    if enabled { "on" } else { "off" }.into()
+}
+
+fn point(re: impl Display, im: impl Display) -> String {
+    format!("({re}, {im})")
+}
+
+fn hex(color: [f32; 3]) -> String {
+    let byte = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+    format!(
+        "#{:02x}{:02x}{:02x}",
+        byte(color[0]),
+        byte(color[1]),
+        byte(color[2])
+    )
+}
+
+/// Serde's name for an enum value, quoted.
+fn string<T: Serialize>(value: &T) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+/// Shortest form that reads back as the same float and is a valid TOML float.
+fn num(v: impl Debug) -> String {
+    format!("{v:?}")
+}
+
+fn complex(re: impl Debug, im: impl Debug) -> String {
+    format!("{{ re = {}, im = {} }}", num(re), num(im))
+}
+
+fn color(c: [f32; 3]) -> String {
+    format!("[{}, {}, {}]", num(c[0]), num(c[1]), num(c[2]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn julia() -> Project {
+        let mut proj = Project::default();
+        proj.fractal.kind = FractalKind::Julia;
+        proj.fractal.max_iter = 5000;
+        proj.fractal.c = Complex::new(-0.4, 0.6);
+        proj.fractal.gamma = 1.8;
+        proj.fractal.palette.truncate(3);
+        proj.fractal.orbit.enabled = true;
+        proj.fractal.orbit.kind = OrbitTrapKind::Circle;
+        proj.camera = Camera {
+            center: Complex64::new(-0.743_643_9, 0.131_825_91),
+            scale: 3.2e3,
+            rotation: 0.3,
+            deep_center: None,
+        };
+        proj.export.codec = VideoCodec::Vp9;
+        proj
+    }
+
+    #[test]
+    fn round_trips_with_palette_and_export() {
+        let proj = julia();
+        let text = to_settings_snippet(&proj.fractal, &proj.camera, true, Some(&proj.export));
+        let snippet = parse_settings_snippet(&text).unwrap();
+        let mut back = Project::default();
+        snippet.apply(&mut back);
+        assert!(snippet.changes(&back).is_empty(), "{text}");
+        assert_eq!(
+            to_settings_snippet(&back.fractal, &back.camera, true, Some(&back.export)),
+            text
+        );
+        assert_eq!(back.export.out_path, Project::default().export.out_path);
+    }
+
+    #[test]
+    fn snippets_are_trimmed_to_the_kind() {
+        let proj = Project::default();
+        let text = to_settings_snippet(&proj.fractal, &proj.camera, false, None);
+        assert_eq!(
+            text,
+            "[fractal]\n\
+             kind = \"mandelbrot\"\n\
+             max_iter = 800\n\
+             escape_radius = 4.0\n\
+             palette_phase = 0.0\n\
+             palette_repeat = 800.0\n\
+             exposure = 1.0\n\
+             gamma = 2.2\n\
+             orbit = { enabled = false }\n\
+             \n\
+             [camera]\n\
+             center = { re = -0.5, im = 0.0 }\n\
+             scale = 300.0\n\
+             rotation = 0.0\n"
+        );
+        let proj = julia();
+        let text = to_settings_snippet(&proj.fractal, &proj.camera, false, None);
+        assert!(text.contains("c = { re = -0.4, im = 0.6 }"), "{text}");
+        assert!(
+            !text.contains("power") && !text.contains("[export]"),
+            "{text}"
+        );
+    }
+
+    #[test]
+    fn partial_snippets_merge_and_list_their_changes() {
+        let snippet = parse_settings_snippet(
+            "[fractal]\nmax_iter = 5000\nkind = \"julia\"\nsmoothing = \"new\"\n\n[future]\nx = 1\n",
+        )
+        .unwrap();
+        let mut proj = Project::default();
+        let camera = proj.camera.clone();
+        let changes: Vec<String> = snippet
+            .changes(&proj)
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        assert_eq!(changes, ["kind Mandelbrot→Julia", "max_iter 800→5000"]);
+
+        snippet.apply(&mut proj);
+        assert_eq!(proj.fractal.kind, FractalKind::Julia);
+        assert_eq!(proj.camera.center, camera.center);
+        assert!(snippet.changes(&proj).is_empty());
+    }
+
+    #[test]
+    fn recolored_palettes_count_as_changes() {
+        let proj = julia();
+        let mut other = proj.clone();
+        other.fractal.palette[1].color = [0.0, 0.0, 1.0];
+        let text = to_settings_snippet(&other.fractal, &other.camera, true, None);
+        let changes = parse_settings_snippet(&text).unwrap().changes(&proj);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].field, "palette");
+        assert!(changes[0].to.contains("#0000ff"), "{}", changes[0]);
+    }
+
+    #[test]
+    fn whole_project_files_paste_in_both_formats() {
+        let proj = julia();
+        for text in [
+            serde_json::to_string_pretty(&proj).unwrap(),
+            toml::to_string(&proj).unwrap(),
+        ] {
+            let snippet = parse_settings_snippet(&text).unwrap();
+            assert!(snippet.changes(&proj).is_empty());
+            assert!(!snippet.changes(&Project::default()).is_empty());
+        }
+    }
+
+    #[test]
+    fn rejects_bad_snippets_with_a_reason() {
+        let cases = [
+            ("", "no settings"),
+            ("name = \"x\"", "no settings"),
+            ("[fractal]\nmax_iter = 0", "max_iter"),
+            ("[camera]\nscale = -1.0", "scale"),
+            ("[fractal]\npalette = []", "palette"),
+            ("[export]\nwidth = 0", "width"),
+            ("[fractal]\nkind = \"sierpinski\"", "line 2"),
+            ("{\n  \"camera\": {\"scale\": }\n}", "line 2"),
+        ];
+        for (text, reason) in cases {
+            let err = parse_settings_snippet(text).unwrap_err();
+            assert!(err.contains(reason), "{text}: {err}");
+        }
+    }
+}
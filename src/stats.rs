@@ -0,0 +1,360 @@
+//! Per-render timing: [`RenderStats`] for one frame, shown by the UI's stats overlay and
+//! appended to a CSV or JSON Lines file by [`StatsLog`] for profiling sessions.
+
+use std::{
+    borrow::Cow,
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::Path,
+    time::Duration,
+};
+
+use serde::Serialize;
+use web_time::{SystemTime, UNIX_EPOCH};
+
+use crate::{FractalParams, Precision, Renderer};
+
+/// Columns of a CSV stats log, in order. Only ever append to this list: scripts read the
+/// columns by position.
+pub const STATS_COLUMNS: [&str; 16] = [
+    "timestamp",
+    "backend",
+    "width",
+    "height",
+    "tiles",
+    "max_iter",
+    "tile_ms",
+    "total_ms",
+    "mpix_per_s",
+    "precision",
+    "frame",
+    "encode_wait_ms",
+    "tile_size",
+    "cache_hits",
+    "cache_misses",
+    "refined_pixels",
+];
+
+/// Timing of one rendered frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderStats {
+    /// Seconds since the Unix epoch when the render started.
+    pub timestamp: f64,
+    pub backend: String,
+    pub width: u32,
+    pub height: u32,
+    pub max_iter: u32,
+    pub precision: Precision,
+    /// Milliseconds per tile, in render order.
+    pub tile_ms: Vec<f64>,
+    pub total_ms: f64,
+    /// Export frame index; `None` for stills and the preview.
+    pub frame: Option<u32>,
+    /// Milliseconds an export spent writing the frame out after rendering it.
+    pub encode_wait_ms: Option<f64>,
+    /// Tile size an export asked for, after calibration has picked one for auto tiles.
+    pub tile_size: Option<u32>,
+    /// Frames of a perturbation export that reused the previous reference orbit: 1 if
+    /// this one did, else 0. `None` for other renders.
+    pub cache_hits: Option<u32>,
+    /// Reference orbits a perturbation export computed for this frame.
+    pub cache_misses: Option<u32>,
+    /// Pixels adaptive anti-aliasing rendered again; `None` when it was off.
+    pub refined_pixels: Option<u64>,
+}
+
+impl RenderStats {
+    /// Empty stats for a render starting now.
+    pub fn new(renderer: &dyn Renderer, size: (u32, u32), params: &FractalParams) -> Self {
+        Self {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0.0, |d| d.as_secs_f64()),
+            backend: renderer.name().into(),
+            width: size.0,
+            height: size.1,
+            max_iter: params.max_iter,
+            precision: renderer.precision().min(params.precision),
+            tile_ms: Vec::new(),
+            total_ms: 0.0,
+            frame: None,
+            encode_wait_ms: None,
+            tile_size: None,
+            cache_hits: None,
+            cache_misses: None,
+            refined_pixels: None,
+        }
+    }
+
+    pub fn tiles(&self) -> usize {
+        self.tile_ms.len()
+    }
+
+    /// Throughput in megapixels per second of total time.
+    pub fn mpix_per_s(&self) -> f64 {
+        if self.total_ms <= 0.0 {
+            return 0.0;
+        }
+        self.width as f64 * self.height as f64 / 1e6 / (self.total_ms / 1e3)
+    }
+
+    /// One CSV row matching [`STATS_COLUMNS`]. Tile times are joined with `;`.
+    pub fn csv_row(&self) -> String {
+        let tile_ms: Vec<String> = self.tile_ms.iter().map(|&ms| ms3(ms)).collect();
+        let fields = [
+            format!("{:.3}", self.timestamp),
+            self.backend.clone(),
+            self.width.to_string(),
+            self.height.to_string(),
+            self.tiles().to_string(),
+            self.max_iter.to_string(),
+            tile_ms.join(";"),
+            ms3(self.total_ms),
+            format!("{:.2}", self.mpix_per_s()),
+            self.precision.label().into(),
+            self.frame.map(|f| f.to_string()).unwrap_or_default(),
+            self.encode_wait_ms.map(ms3).unwrap_or_default(),
+            self.tile_size.map(|t| t.to_string()).unwrap_or_default(),
+            self.cache_hits.map(|n| n.to_string()).unwrap_or_default(),
+            self.cache_misses.map(|n| n.to_string()).unwrap_or_default(),
+            self.refined_pixels
+                .map(|n| n.to_string())
+                .unwrap_or_default(),
+        ];
+        fields
+            .iter()
+            .map(|field| csv_field(field))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// One JSON object with the [`STATS_COLUMNS`] as keys, in the same order.
+    pub fn json_line(&self) -> String {
+        #[derive(Serialize)]
+        struct Record<'a> {
+            timestamp: f64,
+            backend: &'a str,
+            width: u32,
+            height: u32,
+            tiles: usize,
+            max_iter: u32,
+            tile_ms: Vec<f64>,
+            total_ms: f64,
+            mpix_per_s: f64,
+            precision: Precision,
+            frame: Option<u32>,
+            encode_wait_ms: Option<f64>,
+            tile_size: Option<u32>,
+            cache_hits: Option<u32>,
+            cache_misses: Option<u32>,
+            refined_pixels: Option<u64>,
+        }
+        let round = |v: f64, places: i32| {
+            let scale = 10f64.powi(places);
+            (v * scale).round() / scale
+        };
+        let record = Record {
+            timestamp: round(self.timestamp, 3),
+            backend: &self.backend,
+            width: self.width,
+            height: self.height,
+            tiles: self.tiles(),
+            max_iter: self.max_iter,
+            tile_ms: self.tile_ms.iter().map(|&ms| round(ms, 3)).collect(),
+            total_ms: round(self.total_ms, 3),
+            mpix_per_s: round(self.mpix_per_s(), 2),
+            precision: self.precision,
+            frame: self.frame,
+            encode_wait_ms: self.encode_wait_ms.map(|ms| round(ms, 3)),
+            tile_size: self.tile_size,
+            cache_hits: self.cache_hits,
+            cache_misses: self.cache_misses,
+            refined_pixels: self.refined_pixels,
+        };
+        serde_json::to_string(&record).unwrap_or_default()
+    }
+}
+
+/// Record layout of a [`StatsLog`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsFormat {
+    /// A header row, then one row per render.
+    Csv,
+    /// One JSON object per line.
+    JsonLines,
+}
+
+impl StatsFormat {
+    /// JSON Lines for `.json` and `.jsonl`, CSV for anything else.
+    pub fn of(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") || ext.eq_ignore_ascii_case("jsonl") => {
+                StatsFormat::JsonLines
+            }
+            _ => StatsFormat::Csv,
+        }
+    }
+}
+
+/// Appends one record per render to a file or any other writer.
+pub struct StatsLog<W: Write = File> {
+    out: W,
+    format: StatsFormat,
+}
+
+impl StatsLog {
+    /// Appends to `path`, creating it if needed. A new or empty CSV file gets the header
+    /// row; records of an existing file are kept.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let empty = file.metadata()?.len() == 0;
+        StatsLog::new(file, StatsFormat::of(path), empty)
+    }
+}
+
+impl<W: Write> StatsLog<W> {
+    /// Writes the CSV header first when `header` is set.
+    pub fn new(mut out: W, format: StatsFormat, header: bool) -> io::Result<Self> {
+        if header && format == StatsFormat::Csv {
+            writeln!(out, "{}", STATS_COLUMNS.join(","))?;
+        }
+        Ok(Self { out, format })
+    }
+
+    /// Writes `stats` and flushes, so the file is complete even if the process dies.
+    pub fn record(&mut self, stats: &RenderStats) -> io::Result<()> {
+        let line = match self.format {
+            StatsFormat::Csv => stats.csv_row(),
+            StatsFormat::JsonLines => stats.json_line(),
+        };
+        writeln!(self.out, "{line}")?;
+        self.out.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+/// Quotes a CSV field when it holds a comma, quote or line break, doubling any quotes.
+pub fn csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+pub(crate) fn millis(d: Duration) -> f64 {
+    d.as_secs_f64() * 1e3
+}
+
+fn ms3(ms: f64) -> String {
+    format!("{ms:.3}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats() -> RenderStats {
+        RenderStats {
+            timestamp: 1_760_000_000.25,
+            backend: "CPU".into(),
+            width: 2000,
+            height: 1000,
+            max_iter: 800,
+            precision: Precision::F32,
+            tile_ms: vec![12.5, 7.25],
+            total_ms: 20.0,
+            frame: Some(3),
+            encode_wait_ms: Some(4.5),
+            tile_size: Some(1024),
+            cache_hits: Some(1),
+            cache_misses: Some(0),
+            refined_pixels: Some(250),
+        }
+    }
+
+    #[test]
+    fn csv_fields_are_escaped() {
+        assert_eq!(csv_field("CPU"), "CPU");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+        assert_eq!(csv_field(""), "");
+
+        let mut odd = stats();
+        odd.backend = "GPU, \"fast\"".into();
+        assert!(odd.csv_row().contains(",\"GPU, \"\"fast\"\"\","));
+    }
+
+    #[test]
+    fn csv_schema_is_stable() {
+        let mut log = StatsLog::new(Vec::new(), StatsFormat::Csv, true).unwrap();
+        log.record(&stats()).unwrap();
+        let mut still = stats();
+        still.frame = None;
+        still.encode_wait_ms = None;
+        still.tile_size = None;
+        still.cache_hits = None;
+        still.cache_misses = None;
+        still.refined_pixels = None;
+        log.record(&still).unwrap();
+        assert_eq!(
+            String::from_utf8(log.into_inner()).unwrap(),
+            "timestamp,backend,width,height,tiles,max_iter,tile_ms,total_ms,mpix_per_s,precision,frame,encode_wait_ms,tile_size,cache_hits,cache_misses,refined_pixels\n\
+             1760000000.250,CPU,2000,1000,2,800,12.500;7.250,20.000,100.00,f32,3,4.500,1024,1,0,250\n\
+             1760000000.250,CPU,2000,1000,2,800,12.500;7.250,20.000,100.00,f32,,,,,,\n"
+        );
+    }
+
+    #[test]
+    fn json_schema_is_stable() {
+        let mut log = StatsLog::new(Vec::new(), StatsFormat::JsonLines, true).unwrap();
+        log.record(&stats()).unwrap();
+        assert_eq!(
+            String::from_utf8(log.into_inner()).unwrap(),
+            "{\"timestamp\":1760000000.25,\"backend\":\"CPU\",\"width\":2000,\"height\":1000,\
+             \"tiles\":2,\"max_iter\":800,\"tile_ms\":[12.5,7.25],\"total_ms\":20.0,\
+             \"mpix_per_s\":100.0,\"precision\":\"f32\",\"frame\":3,\"encode_wait_ms\":4.5,\
+             \"tile_size\":1024,\"cache_hits\":1,\"cache_misses\":0,\"refined_pixels\":250}\n"
+        );
+        let keys: Vec<String> = serde_json::from_str::<serde_json::Value>(&stats().json_line())
+            .unwrap()
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect();
+        let mut columns: Vec<String> = STATS_COLUMNS.iter().map(|c| c.to_string()).collect();
+        columns.sort();
+        assert_eq!(keys, columns);
+    }
+
+    #[test]
+    fn format_follows_the_extension() {
+        assert_eq!(StatsFormat::of(Path::new("run.csv")), StatsFormat::Csv);
+        assert_eq!(
+            StatsFormat::of(Path::new("run.JSONL")),
+            StatsFormat::JsonLines
+        );
+        assert_eq!(
+            StatsFormat::of(Path::new("run.json")),
+            StatsFormat::JsonLines
+        );
+        assert_eq!(StatsFormat::of(Path::new("run")), StatsFormat::Csv);
+    }
+
+    #[test]
+    fn appending_keeps_a_single_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stats.csv");
+        for _ in 0..2 {
+            StatsLog::open(&path).unwrap().record(&stats()).unwrap();
+        }
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(text.lines().count(), 3);
+        assert_eq!(text.matches("timestamp,").count(), 1);
+    }
+}
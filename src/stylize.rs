@@ -0,0 +1,442 @@
+//! Low-color stylized frames: each frame is reduced to a few colors, optionally with
+//! ordered dithering, and drawn in chunky square pixels for a retro look.
+
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{build_palette, Camera, FractalParams};
+
+/// Most colors a stylized frame can keep.
+pub const MAX_STYLE_COLORS: u32 = 256;
+
+/// Largest block of output pixels one rendered pixel can fill.
+pub const MAX_PIXEL_SIZE: u32 = 64;
+
+/// Where a stylized frame's colors come from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StylePalette {
+    /// Picked for each frame by median cut, so every frame makes the most of its colors.
+    #[default]
+    MedianCut,
+    /// Spread evenly along the project's palette and the same in every frame, so colors
+    /// do not shift from frame to frame.
+    Project,
+}
+
+impl StylePalette {
+    pub const ALL: [StylePalette; 2] = [StylePalette::MedianCut, StylePalette::Project];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            StylePalette::MedianCut => "Per frame",
+            StylePalette::Project => "Project palette",
+        }
+    }
+}
+
+/// Ordered dithering, by a Bayer threshold matrix, applied before each pixel takes its
+/// nearest color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Dither {
+    None,
+    Bayer2,
+    #[default]
+    Bayer4,
+    Bayer8,
+}
+
+impl Dither {
+    pub const ALL: [Dither; 4] = [Dither::None, Dither::Bayer2, Dither::Bayer4, Dither::Bayer8];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Dither::None => "None",
+            Dither::Bayer2 => "Bayer 2×2",
+            Dither::Bayer4 => "Bayer 4×4",
+            Dither::Bayer8 => "Bayer 8×8",
+        }
+    }
+
+    /// Side of the threshold matrix; 0 without dithering.
+    pub fn side(&self) -> usize {
+        match self {
+            Dither::None => 0,
+            Dither::Bayer2 => 2,
+            Dither::Bayer4 => 4,
+            Dither::Bayer8 => 8,
+        }
+    }
+
+    /// The threshold matrix, row by row: each of `0..side²` once, spread so that any
+    /// run of thresholds covers the square evenly.
+    pub fn matrix(&self) -> Vec<u32> {
+        let mut matrix = vec![0];
+        let mut side = 1;
+        while side < self.side() {
+            let grown = side * 2;
+            let mut next = vec![0; grown * grown];
+            for y in 0..grown {
+                for x in 0..grown {
+                    let inner = 4 * matrix[(y % side) * side + x % side];
+                    let quadrant = [0, 2, 3, 1][(y / side) * 2 + x / side];
+                    next[y * grown + x] = inner + quadrant;
+                }
+            }
+            matrix = next;
+            side = grown;
+        }
+        matrix
+    }
+}
+
+/// The stylized look of an export: how many colors, from where, dithered how, and in
+/// how large pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Stylize {
+    pub enabled: bool,
+    /// Colors each frame is reduced to, 2 to [`MAX_STYLE_COLORS`].
+    pub colors: u32,
+    pub palette: StylePalette,
+    pub dither: Dither,
+    /// Side of the square of output pixels each rendered pixel fills; 1 renders at full
+    /// resolution.
+    pub pixel_size: u32,
+}
+
+impl Default for Stylize {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            colors: 16,
+            palette: StylePalette::default(),
+            dither: Dither::default(),
+            pixel_size: 4,
+        }
+    }
+}
+
+impl Stylize {
+    fn pixel(&self) -> u32 {
+        self.pixel_size.clamp(1, MAX_PIXEL_SIZE)
+    }
+
+    /// Size a `size` frame is rendered at: one pixel per block, rounded up so the blocks
+    /// cover the frame.
+    pub fn render_size(&self, size: (u32, u32)) -> (u32, u32) {
+        let pixel = self.pixel();
+        (size.0.div_ceil(pixel), size.1.div_ceil(pixel))
+    }
+
+    /// `cam` for the frame at [`Stylize::render_size`], showing what `cam` shows.
+    pub fn render_camera(&self, cam: &Camera) -> Camera {
+        Camera {
+            scale: cam.scale / f64::from(self.pixel()),
+            ..cam.clone()
+        }
+    }
+
+    /// The colors a frame of `pixels` (RGBA8) rendered with `params` is reduced to.
+    pub fn colors_for(&self, pixels: &[u8], params: &FractalParams) -> Vec<[u8; 3]> {
+        let count = self.colors.clamp(2, MAX_STYLE_COLORS) as usize;
+        match self.palette {
+            StylePalette::MedianCut => median_cut(pixels, count),
+            StylePalette::Project => build_palette(params, count)
+                .into_iter()
+                .map(|c| {
+                    params
+                        .tone_map_rgb(c.map(|c| c as f32 / 255.0))
+                        .map(|c| (c.clamp(0.0, 1.0) * 255.0) as u8)
+                })
+                .collect(),
+        }
+    }
+
+    /// Reduces the RGBA8 `pixels` of a frame `width` wide to the style's colors, in place.
+    pub fn quantize(&self, pixels: &mut [u8], width: u32, params: &FractalParams) {
+        let colors = self.colors_for(pixels, params);
+        quantize(pixels, width, &colors, self.dither);
+    }
+
+    /// `rows` of the stylized `small` frame scaled up to `size` in blocks of
+    /// `pixel_size`; see [`upscale_nearest`].
+    pub fn upscale_rows(
+        &self,
+        small: &[u8],
+        small_size: (u32, u32),
+        size: (u32, u32),
+        rows: Range<u32>,
+    ) -> Vec<u8> {
+        upscale_nearest(small, small_size, self.pixel(), size, rows)
+    }
+}
+
+/// Up to `count` colors standing for the RGBA8 `pixels`, by median cut: the box of colors
+/// with the widest spread along any channel is split at its median until there are
+/// `count` boxes, and each box gives its mean. Alpha is ignored. Fewer colors come back
+/// when the pixels have fewer distinct ones.
+pub fn median_cut(pixels: &[u8], count: usize) -> Vec<[u8; 3]> {
+    let mut colors: Vec<[u8; 3]> = pixels.chunks_exact(4).map(|p| [p[0], p[1], p[2]]).collect();
+    if colors.is_empty() || count == 0 {
+        return Vec::new();
+    }
+    /// Widest channel of a box and its range.
+    fn spread(colors: &[[u8; 3]]) -> (usize, u8) {
+        (0..3)
+            .map(|c| {
+                let (lo, hi) = colors.iter().fold((u8::MAX, 0), |(lo, hi), col| {
+                    (lo.min(col[c]), hi.max(col[c]))
+                });
+                (c, hi.saturating_sub(lo))
+            })
+            .max_by_key(|&(_, range)| range)
+            .expect("three channels")
+    }
+    // Each box with its widest channel and that channel's range.
+    let mut boxes = vec![(0..colors.len(), spread(&colors))];
+    while boxes.len() < count {
+        let widest = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, (_, range)))| *range > 0)
+            .max_by_key(|&(i, (_, (_, range)))| (range, std::cmp::Reverse(i)));
+        let Some((index, (range, (channel, _)))) = widest else {
+            break;
+        };
+        let (range, channel) = (range.clone(), *channel);
+        let part = &mut colors[range.clone()];
+        part.sort_unstable_by_key(|c| (c[channel], *c));
+        // Split at the median, moved off any run of equal values so both halves differ.
+        let median = part[part.len() / 2][channel];
+        let split = part.partition_point(|c| c[channel] < median);
+        let split = if split == 0 {
+            part.partition_point(|c| c[channel] <= median)
+        } else {
+            split
+        };
+        let (low, high) = (
+            range.start..range.start + split,
+            range.start + split..range.end,
+        );
+        boxes[index] = (low.clone(), spread(&colors[low]));
+        boxes.push((high.clone(), spread(&colors[high])));
+    }
+    boxes
+        .into_iter()
+        .map(|(b, _)| {
+            let n = b.len() as u64;
+            let sum = colors[b].iter().fold([0u64; 3], |mut sum, c| {
+                for (s, &v) in sum.iter_mut().zip(c) {
+                    *s += u64::from(v);
+                }
+                sum
+            });
+            sum.map(|s| ((s + n / 2) / n) as u8)
+        })
+        .collect()
+}
+
+/// Replaces each RGBA8 pixel of a frame `width` wide with the nearest of `colors`, after
+/// nudging it by `dither`'s threshold for its position. Alpha is kept.
+pub fn quantize(pixels: &mut [u8], width: u32, colors: &[[u8; 3]], dither: Dither) {
+    if colors.is_empty() {
+        return;
+    }
+    let side = dither.side();
+    let matrix = dither.matrix();
+    let strength = dither_strength(colors);
+    let width = width.max(1) as usize;
+    for (i, px) in pixels.chunks_exact_mut(4).enumerate() {
+        let offset = if side == 0 {
+            0.0
+        } else {
+            let (x, y) = (i % width, i / width);
+            let threshold = matrix[(y % side) * side + x % side] as f32;
+            ((threshold + 0.5) / (side * side) as f32 - 0.5) * strength
+        };
+        let target = [0, 1, 2].map(|c| (f32::from(px[c]) + offset).clamp(0.0, 255.0));
+        let nearest = colors
+            .iter()
+            .min_by(|a, b| {
+                distance(&target, a)
+                    .partial_cmp(&distance(&target, b))
+                    .expect("distances are finite")
+            })
+            .expect("colors is not empty");
+        px[..3].copy_from_slice(nearest);
+    }
+}
+
+/// How far dithering nudges a channel: the average gap from each color to its nearest
+/// neighbor, per channel, so pixels between two colors mix them in proportion.
+fn dither_strength(colors: &[[u8; 3]]) -> f32 {
+    if colors.len() < 2 {
+        return 0.0;
+    }
+    let gaps: f32 = colors
+        .iter()
+        .enumerate()
+        .map(|(i, a)| {
+            let target = a.map(f32::from);
+            colors
+                .iter()
+                .enumerate()
+                .filter(|&(j, _)| j != i)
+                .map(|(_, b)| distance(&target, b))
+                .fold(f32::INFINITY, f32::min)
+                .sqrt()
+        })
+        .sum();
+    gaps / colors.len() as f32 / 3f32.sqrt()
+}
+
+fn distance(target: &[f32; 3], color: &[u8; 3]) -> f32 {
+    target
+        .iter()
+        .zip(color)
+        .map(|(t, &c)| (t - f32::from(c)).powi(2))
+        .sum()
+}
+
+/// `rows` of the RGBA8 `small` frame scaled up by `factor` in both directions and cut to
+/// `size`, centered. Each source pixel becomes a `factor`×`factor` block; blocks at the
+/// edges lose what sticks out.
+pub fn upscale_nearest(
+    small: &[u8],
+    small_size: (u32, u32),
+    factor: u32,
+    size: (u32, u32),
+    rows: Range<u32>,
+) -> Vec<u8> {
+    let factor = factor.max(1);
+    let cut_x = (small_size.0 * factor).saturating_sub(size.0) / 2;
+    let cut_y = (small_size.1 * factor).saturating_sub(size.1) / 2;
+    let source = |v: u32, cut: u32, len: u32| ((v + cut) / factor).min(len.saturating_sub(1));
+    let mut out = Vec::with_capacity(rows.len() * size.0 as usize * 4);
+    for y in rows {
+        let sy = source(y, cut_y, small_size.1) as usize;
+        let row = &small[sy * small_size.0 as usize * 4..][..small_size.0 as usize * 4];
+        for x in 0..size.0 {
+            let sx = source(x, cut_x, small_size.0) as usize;
+            out.extend_from_slice(&row[sx * 4..sx * 4 + 4]);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Complex64, FractalKind};
+
+    fn rgba(colors: &[[u8; 3]]) -> Vec<u8> {
+        colors
+            .iter()
+            .flat_map(|&[r, g, b]| [r, g, b, 255])
+            .collect()
+    }
+
+    #[test]
+    fn bayer_matrices_hold_every_threshold_once() {
+        assert_eq!(Dither::None.matrix(), vec![0]);
+        assert_eq!(Dither::Bayer2.matrix(), vec![0, 2, 3, 1]);
+        assert_eq!(
+            Dither::Bayer4.matrix(),
+            vec![0, 8, 2, 10, 12, 4, 14, 6, 3, 11, 1, 9, 15, 7, 13, 5]
+        );
+        let mut bayer8 = Dither::Bayer8.matrix();
+        bayer8.sort_unstable();
+        assert_eq!(bayer8, (0..64).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn median_cut_separates_clusters() {
+        let mut pixels = Vec::new();
+        for i in 0..50u8 {
+            pixels.push([200 + i % 5, 10, 10]);
+            pixels.push([10, 10, 200 + i % 3]);
+            pixels.push([10, 190 + i % 7, 10]);
+        }
+        let mut colors = median_cut(&rgba(&pixels), 3);
+        colors.sort_unstable();
+        assert_eq!(colors, vec![[10, 10, 201], [10, 193, 10], [202, 10, 10]]);
+    }
+
+    #[test]
+    fn median_cut_stops_at_the_distinct_colors() {
+        let pixels = rgba(&[[1, 2, 3], [1, 2, 3], [9, 9, 9]]);
+        assert_eq!(median_cut(&pixels, 16).len(), 2);
+        assert!(median_cut(&[], 16).is_empty());
+    }
+
+    #[test]
+    fn quantize_without_dither_picks_nearest() {
+        let mut pixels = rgba(&[[20, 20, 20], [240, 230, 250], [100, 100, 100]]);
+        pixels[7] = 0;
+        quantize(&mut pixels, 3, &[[0, 0, 0], [255, 255, 255]], Dither::None);
+        assert_eq!(pixels, vec![0, 0, 0, 255, 255, 255, 255, 0, 0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn dithered_gray_mixes_in_proportion() {
+        // A quarter of the way from black to white: a quarter of the pixels go white.
+        let mut pixels = rgba(&[[64, 64, 64]; 16]);
+        quantize(
+            &mut pixels,
+            4,
+            &[[0, 0, 0], [255, 255, 255]],
+            Dither::Bayer4,
+        );
+        let white = pixels.chunks_exact(4).filter(|p| p[0] == 255).count();
+        assert_eq!(white, 4);
+    }
+
+    #[test]
+    fn upscaling_centers_the_blocks() {
+        let small = rgba(&[[1, 0, 0], [2, 0, 0], [3, 0, 0], [4, 0, 0]]);
+        // Blocks of 3×3 pixels make 6×6; cut to 5×5 the last row and column go.
+        let out = upscale_nearest(&small, (2, 2), 3, (5, 5), 0..5);
+        let reds: Vec<u8> = out.chunks_exact(4).map(|p| p[0]).collect();
+        assert_eq!(
+            reds,
+            vec![
+                1, 1, 1, 2, 2, //
+                1, 1, 1, 2, 2, //
+                1, 1, 1, 2, 2, //
+                3, 3, 3, 4, 4, //
+                3, 3, 3, 4, 4,
+            ]
+        );
+        assert_eq!(
+            upscale_nearest(&small, (2, 2), 3, (5, 5), 2..3),
+            out[40..60]
+        );
+    }
+
+    #[test]
+    fn project_palette_colors_do_not_depend_on_the_frame() {
+        let style = Stylize {
+            palette: StylePalette::Project,
+            colors: 8,
+            ..Stylize::default()
+        };
+        let params = FractalParams::new(FractalKind::Mandelbrot);
+        let a = style.colors_for(&rgba(&[[0, 0, 0]]), &params);
+        assert_eq!(a.len(), 8);
+        assert_eq!(a, style.colors_for(&rgba(&[[255, 9, 9]; 4]), &params));
+    }
+
+    #[test]
+    fn render_size_covers_the_frame() {
+        let style = Stylize {
+            pixel_size: 4,
+            ..Stylize::default()
+        };
+        assert_eq!(style.render_size((1921, 1080)), (481, 270));
+        let cam = Camera::new(Complex64::new(-0.5, 0.0), 400.0);
+        assert_eq!(style.render_camera(&cam).scale, 100.0);
+    }
+}
@@ -0,0 +1,444 @@
+//! Small previews for preset menus and view lists, rendered off the UI thread and
+//! cached by content.
+
+use std::collections::{hash_map::DefaultHasher, HashMap, HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::mpsc;
+use std::sync::{Arc, Condvar, Mutex};
+
+use crate::{build_palette, render_cpu, Camera, FractalParams};
+
+/// Iteration cap for thumbnails; detail past this is invisible at thumbnail size.
+pub const THUMBNAIL_MAX_ITER: u32 = 300;
+/// Thumbnails are rendered this many times larger per side and averaged down.
+pub const THUMBNAIL_SUPERSAMPLE: u32 = 2;
+/// Rows of palette gradient under a [`ThumbnailRequest::with_gradient`] thumbnail.
+pub const THUMBNAIL_GRADIENT_ROWS: u32 = 6;
+
+/// Content hash of a [`ThumbnailRequest`]; equal requests share one thumbnail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ThumbnailKey(u64);
+
+/// What to render: a view at thumbnail size, optionally with its palette underneath.
+#[derive(Debug, Clone)]
+pub struct ThumbnailRequest {
+    pub params: FractalParams,
+    /// Framing at thumbnail size (pixels per unit of the thumbnail, not the view).
+    pub camera: Camera,
+    pub size: (u32, u32),
+    pub gradient: bool,
+}
+
+impl ThumbnailRequest {
+    /// A thumbnail of `camera`; `max_iter` is capped at [`THUMBNAIL_MAX_ITER`].
+    pub fn new(params: &FractalParams, camera: &Camera, size: (u32, u32)) -> Self {
+        let mut params = params.clone();
+        params.max_iter = params.max_iter.min(THUMBNAIL_MAX_ITER);
+        Self {
+            params,
+            camera: camera.clone(),
+            size: (size.0.max(1), size.1.max(1)),
+            gradient: false,
+        }
+    }
+
+    /// A thumbnail of a view framed by `camera` across `view_width` pixels.
+    pub fn framed(
+        params: &FractalParams,
+        camera: &Camera,
+        view_width: f32,
+        size: (u32, u32),
+    ) -> Self {
+        let camera = Camera {
+            scale: camera.scale * f64::from(size.0) / f64::from(view_width.max(1.0)),
+            ..camera.clone()
+        };
+        Self::new(params, &camera, size)
+    }
+
+    /// Replaces the bottom rows with the palette as a gradient.
+    pub fn with_gradient(mut self) -> Self {
+        self.gradient = true;
+        self
+    }
+
+    pub fn key(&self) -> ThumbnailKey {
+        let mut hasher = DefaultHasher::new();
+        // Serialized so every field, including floats, takes part in the hash.
+        serde_json::to_vec(&(&self.params, &self.camera))
+            .unwrap_or_default()
+            .hash(&mut hasher);
+        (self.size, self.gradient).hash(&mut hasher);
+        ThumbnailKey(hasher.finish())
+    }
+
+    /// Renders the thumbnail as RGBA8, supersampled by [`THUMBNAIL_SUPERSAMPLE`].
+    pub fn render(&self) -> Vec<u8> {
+        let ss = THUMBNAIL_SUPERSAMPLE;
+        let (w, h) = self.size;
+        let camera = Camera {
+            scale: self.camera.scale * f64::from(ss),
+            ..self.camera.clone()
+        };
+        let big = render_cpu((w * ss, h * ss), &self.params, &camera);
+        let mut pixels = vec![0u8; w as usize * h as usize * 4];
+        let count = ss * ss;
+        for y in 0..h {
+            for x in 0..w {
+                let mut sum = [0u32; 4];
+                for sy in 0..ss {
+                    for sx in 0..ss {
+                        let i = ((y * ss + sy) as usize * (w * ss) as usize
+                            + (x * ss + sx) as usize)
+                            * 4;
+                        for (acc, &v) in sum.iter_mut().zip(&big[i..i + 4]) {
+                            *acc += u32::from(v);
+                        }
+                    }
+                }
+                let o = (y as usize * w as usize + x as usize) * 4;
+                for (px, acc) in pixels[o..o + 4].iter_mut().zip(sum) {
+                    *px = ((acc + count / 2) / count) as u8;
+                }
+            }
+        }
+        if self.gradient {
+            let lut = build_palette(&self.params, w as usize);
+            for y in h.saturating_sub(THUMBNAIL_GRADIENT_ROWS)..h {
+                for (x, color) in lut.iter().enumerate() {
+                    let o = (y as usize * w as usize + x) * 4;
+                    pixels[o..o + 3].copy_from_slice(color);
+                    pixels[o + 3] = 255;
+                }
+            }
+        }
+        pixels
+    }
+}
+
+// ------------------------- Cache -------------------------
+
+/// Least-recently-used map from [`ThumbnailKey`] to whatever the caller keeps per
+/// thumbnail (pixels, a GPU texture, ...).
+pub struct ThumbnailCache<T> {
+    capacity: usize,
+    entries: HashMap<ThumbnailKey, (T, u64)>,
+    clock: u64,
+}
+
+impl<T> ThumbnailCache<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entries: HashMap::new(),
+            clock: 0,
+        }
+    }
+
+    /// Looks up `key`, marking it as recently used.
+    pub fn get(&mut self, key: ThumbnailKey) -> Option<&T> {
+        self.clock += 1;
+        let clock = self.clock;
+        self.entries.get_mut(&key).map(|(value, used)| {
+            *used = clock;
+            &*value
+        })
+    }
+
+    pub fn contains(&self, key: ThumbnailKey) -> bool {
+        self.entries.contains_key(&key)
+    }
+
+    /// Stores `value`, evicting the least recently used entries beyond the capacity.
+    pub fn insert(&mut self, key: ThumbnailKey, value: T) {
+        self.clock += 1;
+        self.entries.insert(key, (value, self.clock));
+        while self.entries.len() > self.capacity {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(&key, _)| key);
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+// ------------------------- Service -------------------------
+
+/// A finished thumbnail.
+#[derive(Debug, Clone)]
+pub struct Thumbnail {
+    pub key: ThumbnailKey,
+    pub size: (u32, u32),
+    /// RGBA8, row-major.
+    pub pixels: Vec<u8>,
+}
+
+#[derive(Default)]
+struct Queue {
+    jobs: VecDeque<(ThumbnailKey, ThumbnailRequest)>,
+    shutdown: bool,
+}
+
+/// Renders thumbnail requests in order on a background thread. Requests nobody asked
+/// for again since the last [`ThumbnailService::cancel_unrequested`] are dropped, so a
+/// closed menu does not keep the worker busy. In the browser build, where there are no
+/// threads, [`ThumbnailService::poll`] renders one job per call instead.
+pub struct ThumbnailService {
+    queue: Arc<(Mutex<Queue>, Condvar)>,
+    /// Queued or rendering; results for anything else are discarded.
+    pending: HashSet<ThumbnailKey>,
+    requested: HashSet<ThumbnailKey>,
+    #[cfg(not(target_arch = "wasm32"))]
+    results: mpsc::Receiver<Thumbnail>,
+}
+
+impl Default for ThumbnailService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ThumbnailService {
+    pub fn new() -> Self {
+        let queue = Arc::new((Mutex::new(Queue::default()), Condvar::new()));
+        #[cfg(not(target_arch = "wasm32"))]
+        let results = {
+            let (tx, rx) = mpsc::channel();
+            let queue = Arc::clone(&queue);
+            std::thread::Builder::new()
+                .name("thumbnails".into())
+                .spawn(move || worker(&queue, &tx))
+                .expect("could not start the thumbnail thread");
+            rx
+        };
+        Self {
+            queue,
+            pending: HashSet::new(),
+            requested: HashSet::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            results,
+        }
+    }
+
+    /// Queues `request` unless it is already queued or rendering.
+    pub fn request(&mut self, request: ThumbnailRequest) -> ThumbnailKey {
+        let key = request.key();
+        self.requested.insert(key);
+        if self.pending.insert(key) {
+            let (queue, ready) = &*self.queue;
+            queue.lock().unwrap().jobs.push_back((key, request));
+            ready.notify_one();
+        }
+        key
+    }
+
+    pub fn is_pending(&self, key: ThumbnailKey) -> bool {
+        self.pending.contains(&key)
+    }
+
+    /// Queued or rendering requests.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Drops `key` from the queue; if it is already rendering, its result is discarded.
+    pub fn cancel(&mut self, key: ThumbnailKey) {
+        if self.pending.remove(&key) {
+            let mut queue = self.queue.0.lock().unwrap();
+            queue.jobs.retain(|(queued, _)| *queued != key);
+        }
+    }
+
+    /// Cancels pending requests that were not requested again since the last call.
+    /// Call once per frame after drawing everything that shows thumbnails.
+    pub fn cancel_unrequested(&mut self) {
+        let stale: Vec<ThumbnailKey> = self.pending.difference(&self.requested).copied().collect();
+        for key in stale {
+            self.cancel(key);
+        }
+        self.requested.clear();
+    }
+
+    /// Thumbnails finished since the last call.
+    pub fn poll(&mut self) -> Vec<Thumbnail> {
+        #[cfg(not(target_arch = "wasm32"))]
+        let done: Vec<Thumbnail> = self.results.try_iter().collect();
+        #[cfg(target_arch = "wasm32")]
+        let done: Vec<Thumbnail> = {
+            let job = self.queue.0.lock().unwrap().jobs.pop_front();
+            job.map(|(key, request)| render_job(key, &request))
+                .into_iter()
+                .collect()
+        };
+        done.into_iter()
+            .filter(|thumb| self.pending.remove(&thumb.key))
+            .collect()
+    }
+
+    /// Waits until nothing is pending, returning everything that finished. For tests and
+    /// batch tools; the UI polls instead.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn wait(&mut self) -> Vec<Thumbnail> {
+        let mut done = self.poll();
+        while !self.pending.is_empty() {
+            match self.results.recv() {
+                Ok(thumb) if self.pending.remove(&thumb.key) => done.push(thumb),
+                Ok(_) => {}
+                Err(_) => break,
+            }
+        }
+        done
+    }
+}
+
+impl Drop for ThumbnailService {
+    fn drop(&mut self) {
+        let (queue, ready) = &*self.queue;
+        if let Ok(mut queue) = queue.lock() {
+            queue.shutdown = true;
+            queue.jobs.clear();
+        }
+        ready.notify_all();
+    }
+}
+
+fn render_job(key: ThumbnailKey, request: &ThumbnailRequest) -> Thumbnail {
+    Thumbnail {
+        key,
+        size: request.size,
+        pixels: request.render(),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn worker(queue: &(Mutex<Queue>, Condvar), results: &mpsc::Sender<Thumbnail>) {
+    let (queue, ready) = queue;
+    loop {
+        let job = {
+            let mut queue = queue.lock().unwrap();
+            loop {
+                if queue.shutdown {
+                    return;
+                }
+                if let Some(job) = queue.jobs.pop_front() {
+                    break job;
+                }
+                queue = ready.wait(queue).unwrap();
+            }
+        };
+        if results.send(render_job(job.0, &job.1)).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Complex, FractalKind};
+
+    fn request(re: f32) -> ThumbnailRequest {
+        let params = FractalParams::new(FractalKind::Mandelbrot).with_max_iter(5000);
+        ThumbnailRequest::new(&params, &Camera::new(Complex::new(re, 0.0), 12.0), (24, 16))
+    }
+
+    #[test]
+    fn keys_follow_content() {
+        assert_eq!(request(-0.5).key(), request(-0.5).key());
+        assert_ne!(request(-0.5).key(), request(-0.4).key());
+        assert_ne!(request(-0.5).key(), request(-0.5).with_gradient().key());
+        let mut recolored = request(-0.5);
+        recolored.params.palette_phase = 0.5;
+        assert_ne!(recolored.key(), request(-0.5).key());
+        assert_eq!(request(-0.5).params.max_iter, THUMBNAIL_MAX_ITER);
+    }
+
+    #[test]
+    fn supersampling_averages_the_larger_render() {
+        let req = request(-0.5);
+        let pixels = req.render();
+        assert_eq!(pixels.len(), 24 * 16 * 4);
+        let big = render_cpu(
+            (48, 32),
+            &req.params,
+            &Camera {
+                scale: 24.0,
+                ..req.camera.clone()
+            },
+        );
+        let block: u32 = [0, 4, 48 * 4, 48 * 4 + 4]
+            .iter()
+            .map(|&i| u32::from(big[i]))
+            .sum();
+        assert_eq!(u32::from(pixels[0]), (block + 2) / 4);
+
+        let lut = build_palette(&req.params, 24);
+        let striped = req.with_gradient().render();
+        let last_row = 15 * 24 * 4;
+        assert_eq!(striped[last_row..last_row + 3], lut[0]);
+        assert_eq!(striped[last_row + 23 * 4..last_row + 23 * 4 + 3], lut[23]);
+    }
+
+    #[test]
+    fn cache_evicts_the_least_recently_used() {
+        let keys: Vec<ThumbnailKey> = (0..3).map(|i| request(i as f32).key()).collect();
+        let mut cache = ThumbnailCache::new(2);
+        cache.insert(keys[0], 0);
+        cache.insert(keys[1], 1);
+        assert_eq!(cache.get(keys[0]), Some(&0));
+        cache.insert(keys[2], 2);
+        assert_eq!(cache.len(), 2);
+        assert!(!cache.contains(keys[1]));
+        assert_eq!(cache.get(keys[0]), Some(&0));
+        assert_eq!(cache.get(keys[2]), Some(&2));
+    }
+
+    #[test]
+    fn service_renders_each_request_once() {
+        let mut service = ThumbnailService::new();
+        let a = service.request(request(-0.5));
+        assert_eq!(service.request(request(-0.5)), a);
+        let b = service.request(request(-1.0));
+        assert_eq!(service.pending(), 2);
+        let done = service.wait();
+        assert_eq!(done.len(), 2);
+        assert_eq!(done[0].key, a);
+        assert_eq!(done[0].pixels, request(-0.5).render());
+        assert_eq!(done[1].key, b);
+        assert_eq!(service.pending(), 0);
+        assert!(service.poll().is_empty());
+    }
+
+    #[test]
+    fn stale_requests_are_cancelled() {
+        let mut service = ThumbnailService::new();
+        let kept = service.request(request(-0.5));
+        let dropped = service.request(request(-1.0));
+        service.cancel_unrequested();
+        assert_eq!(service.pending(), 2, "both were requested this frame");
+
+        service.request(request(-0.5));
+        service.cancel_unrequested();
+        assert!(service.is_pending(kept));
+        assert!(!service.is_pending(dropped));
+        let done = service.wait();
+        assert!(done.iter().all(|thumb| thumb.key == kept), "{done:?}");
+
+        let cancelled = service.request(request(0.25));
+        service.cancel(cancelled);
+        assert!(service.wait().is_empty());
+    }
+}
@@ -0,0 +1,153 @@
+//! Automatic tile sizes: timing a few tile sizes on a frame to find the fastest for a
+//! renderer at a resolution, and the sizes learned that way.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{render_image, render_image_stats, Camera, FractalParams, RenderError, Renderer};
+
+/// Tile sizes calibration tries, smallest first.
+pub const TILE_CANDIDATES: [u32; 4] = [256, 512, 1024, 2048];
+
+/// Calibration renders the middle of the frame, at most this many pixels a side.
+pub const CALIBRATION_PROBE: u32 = 2048;
+
+/// The fastest tile size found for one renderer at one resolution.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LearnedTileSize {
+    /// [`Renderer::name`] of the renderer timed.
+    pub backend: String,
+    pub width: u32,
+    pub height: u32,
+    pub tile_size: u32,
+}
+
+/// Tile sizes learned by calibration, looked up by renderer and resolution.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct TileTuning {
+    pub learned: Vec<LearnedTileSize>,
+}
+
+impl TileTuning {
+    /// The tile size learned for `backend` at `size`, if it has been calibrated.
+    pub fn get(&self, backend: &str, size: (u32, u32)) -> Option<u32> {
+        self.learned
+            .iter()
+            .find(|l| l.backend == backend && (l.width, l.height) == size)
+            .map(|l| l.tile_size)
+    }
+
+    /// Remembers a calibration, replacing an older one of the same renderer and size.
+    pub fn learn(&mut self, calibration: &TileCalibration) {
+        let learned = LearnedTileSize {
+            backend: calibration.backend.clone(),
+            width: calibration.size.0,
+            height: calibration.size.1,
+            tile_size: calibration.tile_size,
+        };
+        self.learned
+            .retain(|l| l.backend != learned.backend || (l.width, l.height) != calibration.size);
+        self.learned.push(learned);
+    }
+}
+
+/// What a calibration measured.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TileCalibration {
+    pub backend: String,
+    /// Frame size calibrated for.
+    pub size: (u32, u32),
+    /// The fastest of the candidates.
+    pub tile_size: u32,
+    /// Milliseconds to render the probe with each candidate tile size.
+    pub timings: Vec<(u32, f64)>,
+}
+
+impl fmt::Display for TileCalibration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let timings: Vec<String> = self
+            .timings
+            .iter()
+            .map(|(tile, ms)| format!("{tile} px {ms:.0} ms"))
+            .collect();
+        write!(
+            f,
+            "{} px tiles for {} at {}×{} ({})",
+            self.tile_size,
+            self.backend,
+            self.size.0,
+            self.size.1,
+            timings.join(", ")
+        )
+    }
+}
+
+/// Times [`TILE_CANDIDATES`] on the middle of a `size` frame of `params` through `cam` and
+/// returns the fastest. Candidates past the first that covers the probe in one tile
+/// render it the same way, so they are skipped.
+pub fn calibrate_tile_size(
+    size: (u32, u32),
+    params: &FractalParams,
+    cam: &Camera,
+    renderer: &mut dyn Renderer,
+) -> Result<TileCalibration, RenderError> {
+    let probe = (
+        size.0.clamp(1, CALIBRATION_PROBE),
+        size.1.clamp(1, CALIBRATION_PROBE),
+    );
+    let longest = probe.0.max(probe.1);
+    // The first render sets the renderer up (threads, GPU pipelines); keep it out of the
+    // timings.
+    render_image((probe.0.min(64), probe.1.min(64)), params, cam, renderer, 0)?;
+    let mut timings = Vec::new();
+    for tile in TILE_CANDIDATES {
+        if tile / 2 >= longest && !timings.is_empty() {
+            break;
+        }
+        let (_, stats) = render_image_stats(probe, params, cam, renderer, tile)?;
+        timings.push((tile, stats.total_ms));
+    }
+    let (tile_size, _) = timings
+        .iter()
+        .copied()
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .expect("at least one tile size is timed");
+    Ok(TileCalibration {
+        backend: renderer.name().into(),
+        size,
+        tile_size,
+        timings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Complex, CpuRenderer, FractalKind};
+
+    #[test]
+    fn calibration_times_the_candidates_that_tile_the_probe() {
+        let params = FractalParams::new(FractalKind::Mandelbrot).with_max_iter(50);
+        let cam = Camera::new(Complex::new(-0.5, 0.0), 200.0);
+        let found = calibrate_tile_size((600, 300), &params, &cam, &mut CpuRenderer).unwrap();
+        let tried: Vec<u32> = found.timings.iter().map(|&(tile, _)| tile).collect();
+        assert_eq!(tried, [256, 512, 1024]);
+        assert!(tried.contains(&found.tile_size));
+        assert_eq!((found.backend.as_str(), found.size), ("CPU", (600, 300)));
+
+        let mut tuning = TileTuning::default();
+        tuning.learn(&found);
+        tuning.learn(&TileCalibration {
+            tile_size: 256,
+            ..found.clone()
+        });
+        assert_eq!(tuning.learned.len(), 1);
+        assert_eq!(tuning.get("CPU", (600, 300)), Some(256));
+        assert_eq!(tuning.get("CPU", (300, 600)), None);
+        assert_eq!(tuning.get("GPU", (600, 300)), None);
+    }
+}
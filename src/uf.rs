@@ -0,0 +1,374 @@
+//! Ultra Fractal parameter blocks in the plain (uncompressed) form.
+
+use crate::{newton_roots, Complex, Complex64, FractalKind, Project};
+
+/// Plane height (units) that Ultra Fractal frames at `magn=1`.
+pub const UF_MAGN1_HEIGHT: f64 = 3.0;
+
+/// Converts UF magnification to pixels per unit for a view `view_height` pixels tall.
+pub fn uf_magn_to_scale(magn: f64, view_height: f32) -> f64 {
+    view_height as f64 * magn / UF_MAGN1_HEIGHT
+}
+
+/// Converts pixels per unit for a view `view_height` pixels tall to UF magnification.
+pub fn uf_scale_to_magn(scale: f64, view_height: f32) -> f64 {
+    scale * UF_MAGN1_HEIGHT / view_height.max(1.0) as f64
+}
+
+/// Writes the project's view as a UF parameter block, framed for its export size.
+pub fn to_uf_params(proj: &Project) -> String {
+    let name: String = proj
+        .name
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+    let name = if name.is_empty() { "Matterhorn" } else { &name };
+    let f = &proj.fractal;
+    let cam = &proj.camera;
+    let magn = uf_scale_to_magn(cam.scale, proj.export.height as f32);
+    let formula = match f.kind {
+        FractalKind::Mandelbrot | FractalKind::Multibrot => format!(
+            "filename=\"Standard.ufm\" entry=\"Mandelbrot\"\n  p_start=0/0 p_power={}/0 p_bailout={}",
+            power_of(proj),
+            f.escape_radius * f.escape_radius
+        ),
+        FractalKind::Julia => format!(
+            "filename=\"Standard.ufm\" entry=\"Julia\"\n  p_seed={}/{} p_power=2/0 p_bailout={}",
+            f.c.re,
+            f.c.im,
+            f.escape_radius * f.escape_radius
+        ),
+        FractalKind::BurningShip => format!(
+            "entry=\"BurningShip\"\n  p_bailout={}",
+            f.escape_radius * f.escape_radius
+        ),
+        FractalKind::Tricorn => format!(
+            "entry=\"Tricorn\"\n  p_bailout={}",
+            f.escape_radius * f.escape_radius
+        ),
+        FractalKind::Celtic => format!(
+            "entry=\"Celtic\"\n  p_bailout={}",
+            f.escape_radius * f.escape_radius
+        ),
+        FractalKind::Buffalo => format!(
+            "entry=\"Buffalo\"\n  p_bailout={}",
+            f.escape_radius * f.escape_radius
+        ),
+        FractalKind::Lyapunov => "entry=\"Lyapunov\"".to_string(),
+        // Not a UF formula, but this crate reads it back.
+        FractalKind::Custom => format!(
+            "entry=\"Custom\"\n  p_formula=\"{}\" p_bailout={}",
+            f.custom_formula.replace('"', "'"),
+            f.escape_radius * f.escape_radius
+        ),
+        FractalKind::Newton => format!(
+            "filename=\"Standard.ufm\" entry=\"Newton\"\n  p_power={}/0",
+            newton_roots(f)
+        ),
+        FractalKind::Phoenix => format!(
+            "entry=\"Phoenix\"\n  p_seed={}/{} p_induct={}/{} p_bailout={}",
+            f.c.re,
+            f.c.im,
+            f.phoenix_p.re,
+            f.phoenix_p.im,
+            f.escape_radius * f.escape_radius
+        ),
+    };
+    format!(
+        "{name} {{\nfractal:\n  title=\"{title}\" width={w} height={h} layers=1\nlayer:\n  caption=\"Background\" opacity=100\nmapping:\n  center={re}/{im} magn={magn:E}\n  angle={angle}\nformula:\n  maxiter={iter} {formula}\n}}\n",
+        title = proj.name.replace('"', "'"),
+        w = proj.export.width,
+        h = proj.export.height,
+        re = cam.center.re,
+        im = cam.center.im,
+        angle = cam.rotation.to_degrees(),
+        iter = f.max_iter,
+    )
+}
+
+fn power_of(proj: &Project) -> f32 {
+    match proj.fractal.kind {
+        FractalKind::Multibrot => proj.fractal.power,
+        _ => 2.0,
+    }
+}
+
+/// The parts of a UF parameter block this crate understands.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UfLocation {
+    pub center: Complex64,
+    pub magn: f64,
+    /// Degrees.
+    pub angle: f32,
+    pub maxiter: Option<u32>,
+    /// Formula entry name, e.g. `Mandelbrot`.
+    pub entry: Option<String>,
+    pub power: Option<f32>,
+    pub seed: Option<Complex>,
+    /// Phoenix weight of the previous `z`.
+    pub induct: Option<Complex>,
+    /// Step of a `Custom` entry, as this crate writes it.
+    pub formula: Option<String>,
+}
+
+/// Parses a pasted UF parameter block. Compressed blocks (`::` lines) are rejected.
+pub fn parse_uf_params(text: &str) -> Result<UfLocation, String> {
+    if text.lines().any(|l| l.trim_start().starts_with("::")) {
+        return Err(
+            "compressed Ultra Fractal parameters are not supported; copy them from UF with compression turned off"
+                .into(),
+        );
+    }
+    let mut loc = UfLocation::default();
+    let mut center = None;
+    let mut magn = None;
+    for token in tokens(text) {
+        let Some((key, value)) = token.split_once('=') else {
+            continue;
+        };
+        let value = value.trim_matches('"');
+        let bad = || format!("{key} is not valid: {value:?}");
+        match key {
+            "center" => center = Some(parse_pair(value).ok_or_else(bad)?),
+            "magn" => {
+                magn = Some(
+                    value
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|m| *m > 0.0 && m.is_finite())
+                        .ok_or_else(bad)?,
+                )
+            }
+            "angle" => loc.angle = value.parse().map_err(|_| bad())?,
+            "maxiter" => loc.maxiter = Some(value.parse().map_err(|_| bad())?),
+            "entry" => loc.entry = Some(value.to_string()),
+            "p_power" => loc.power = parse_pair(value).map(|p| p.re as f32),
+            "p_seed" => loc.seed = parse_pair(value).map(Complex64::to_f32),
+            "p_induct" => loc.induct = parse_pair(value).map(Complex64::to_f32),
+            "p_formula" => loc.formula = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    loc.center = center.ok_or("no center= in the parameters")?;
+    loc.magn = magn.ok_or("no magn= in the parameters")?;
+    Ok(loc)
+}
+
+/// `key=value` tokens, keeping quoted values with spaces together.
+fn tokens(text: &str) -> Vec<&str> {
+    let mut out = Vec::new();
+    for line in text.lines() {
+        let mut start = None;
+        let mut quoted = false;
+        for (i, ch) in line.char_indices() {
+            match ch {
+                '"' => quoted = !quoted,
+                c if c.is_whitespace() && !quoted => {
+                    if let Some(s) = start.take() {
+                        out.push(&line[s..i]);
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+            start.get_or_insert(i);
+        }
+        if let Some(s) = start {
+            out.push(&line[s..]);
+        }
+    }
+    out
+}
+
+/// UF writes complex values as `re/im`; a bare number is real.
+fn parse_pair(value: &str) -> Option<Complex64> {
+    let (re, im) = value.split_once('/').unwrap_or((value, "0"));
+    let (re, im) = (re.parse::<f64>().ok()?, im.parse::<f64>().ok()?);
+    (re.is_finite() && im.is_finite()).then(|| Complex64::new(re, im))
+}
+
+impl UfLocation {
+    /// Moves `proj` to this location, framed for its export height. Returns warnings for
+    /// anything that could not be carried over.
+    pub fn apply(&self, proj: &mut Project) -> Vec<String> {
+        let mut warnings = Vec::new();
+        proj.camera.center = self.center;
+        proj.camera.scale = uf_magn_to_scale(self.magn, proj.export.height as f32);
+        proj.camera.rotation = self.angle.to_radians();
+        if let Some(maxiter) = self.maxiter {
+            proj.fractal.max_iter = maxiter.max(1);
+        }
+        match self.entry.as_deref() {
+            Some("Mandelbrot") => match self.power {
+                Some(p) if p != 2.0 => {
+                    proj.fractal.kind = FractalKind::Multibrot;
+                    proj.fractal.power = p;
+                }
+                _ => proj.fractal.kind = FractalKind::Mandelbrot,
+            },
+            Some("Julia") => {
+                proj.fractal.kind = FractalKind::Julia;
+                if let Some(seed) = self.seed {
+                    proj.fractal.c = seed;
+                }
+            }
+            Some("BurningShip") => proj.fractal.kind = FractalKind::BurningShip,
+            Some("Tricorn") => proj.fractal.kind = FractalKind::Tricorn,
+            Some("Celtic") => proj.fractal.kind = FractalKind::Celtic,
+            Some("Buffalo") => proj.fractal.kind = FractalKind::Buffalo,
+            Some("Lyapunov") => proj.fractal.kind = FractalKind::Lyapunov,
+            Some("Custom") => {
+                proj.fractal.kind = FractalKind::Custom;
+                if let Some(formula) = &self.formula {
+                    proj.fractal.custom_formula = formula.clone();
+                }
+            }
+            Some("Phoenix") => {
+                proj.fractal.kind = FractalKind::Phoenix;
+                if let Some(seed) = self.seed {
+                    proj.fractal.c = seed;
+                }
+                if let Some(induct) = self.induct {
+                    proj.fractal.phoenix_p = induct;
+                }
+            }
+            Some("Newton") => {
+                proj.fractal.kind = FractalKind::Newton;
+                proj.fractal.power = self.power.map_or(3.0, f32::round).clamp(2.0, 12.0);
+            }
+            Some(other) => warnings.push(format!(
+                "formula {other:?} has no equivalent; kept {}",
+                proj.fractal.kind.label()
+            )),
+            None => {}
+        }
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ParamField;
+
+    const PLAIN: &str = r#"Seahorse {
+fractal:
+  title="Seahorse valley" width=800 height=600 layers=1
+  credits="someone;1/1/2024"
+layer:
+  caption="Background" opacity=100
+mapping:
+  center=-0.7436447860/0.1318252536 magn=2.5E4
+  angle=15
+formula:
+  maxiter=1500 percheck=off filename="Standard.ufm" entry="Mandelbrot"
+  p_start=0/0 p_power=2/0 p_bailout=128
+inside:
+  transfer=none
+}"#;
+
+    #[test]
+    fn magnification_and_scale_convert_both_ways() {
+        // magn 1 frames UF_MAGN1_HEIGHT units across the view height.
+        assert_eq!(uf_magn_to_scale(1.0, 300.0), 100.0);
+        assert_eq!(uf_scale_to_magn(100.0, 300.0), 1.0);
+        assert_eq!(uf_magn_to_scale(2.5e4, 600.0), 5e6);
+        for magn in [1e-3, 0.5, 1.0, 7.25, 3.3e3, 1.2e7] {
+            let back = uf_scale_to_magn(uf_magn_to_scale(magn, 1080.0), 1080.0);
+            assert!(
+                (back / magn - 1.0).abs() < 1e-6,
+                "{magn} came back as {back}"
+            );
+        }
+    }
+
+    #[test]
+    fn parses_a_plain_block() {
+        let loc = parse_uf_params(PLAIN).unwrap();
+        assert_eq!(loc.center, Complex64::new(-0.7436447860, 0.1318252536));
+        assert_eq!(loc.magn, 2.5e4);
+        assert_eq!(loc.angle, 15.0);
+        assert_eq!(loc.maxiter, Some(1500));
+        assert_eq!(loc.entry.as_deref(), Some("Mandelbrot"));
+        assert_eq!(loc.power, Some(2.0));
+
+        let mut proj = Project::default();
+        proj.export.height = 600;
+        assert!(loc.apply(&mut proj).is_empty());
+        assert_eq!(proj.camera.scale, 5e6);
+        assert!((proj.camera.rotation - 15f32.to_radians()).abs() < 1e-6);
+        assert_eq!(proj.fractal.max_iter, 1500);
+        assert_eq!(proj.fractal.kind, FractalKind::Mandelbrot);
+    }
+
+    #[test]
+    fn round_trips_every_kind() {
+        for kind in [
+            FractalKind::Mandelbrot,
+            FractalKind::Julia,
+            FractalKind::BurningShip,
+            FractalKind::Multibrot,
+            FractalKind::Tricorn,
+            FractalKind::Newton,
+            FractalKind::Phoenix,
+            FractalKind::Celtic,
+            FractalKind::Buffalo,
+            FractalKind::Lyapunov,
+            FractalKind::Custom,
+        ] {
+            let mut proj = Project::default();
+            proj.fractal.kind = kind;
+            proj.fractal.custom_formula = "z^3 - 0.5i*z + c".into();
+            proj.fractal.power = 3.0;
+            proj.fractal.c = Complex::new(-0.8, 0.156);
+            proj.fractal.phoenix_p = Complex::new(-0.45, 0.1);
+            proj.fractal.max_iter = 777;
+            proj.camera.center = Complex64::new(-1.25066, 0.02012);
+            proj.camera.scale = 12_345.0;
+            proj.camera.rotation = 0.5;
+
+            let mut back = Project::default();
+            back.export.height = proj.export.height;
+            assert!(parse_uf_params(&to_uf_params(&proj))
+                .unwrap()
+                .apply(&mut back)
+                .is_empty());
+            assert_eq!(back.fractal.kind, kind);
+            assert_eq!(back.camera.center, proj.camera.center);
+            assert!((back.camera.scale / proj.camera.scale - 1.0).abs() < 1e-6);
+            assert!((back.camera.rotation - 0.5).abs() < 1e-6);
+            assert_eq!(back.fractal.max_iter, 777);
+            if back.fractal.uses(ParamField::JuliaRe) {
+                assert_eq!(back.fractal.c, proj.fractal.c);
+            }
+            if kind == FractalKind::Phoenix {
+                assert_eq!(back.fractal.phoenix_p, proj.fractal.phoenix_p);
+            }
+            if kind == FractalKind::Custom {
+                assert_eq!(back.fractal.custom_formula, proj.fractal.custom_formula);
+            }
+            if back.fractal.uses(ParamField::Power) {
+                assert_eq!(back.fractal.power, 3.0);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_compressed_and_incomplete_blocks() {
+        let compressed = "Seahorse {\n::Bv7ehgn2t1VXPuNMQ47Gw/BH8ZOkd0lfSS8zqJwlvxtIwb+WeNPBZ\n}";
+        assert!(parse_uf_params(compressed)
+            .unwrap_err()
+            .contains("compressed"));
+        assert!(parse_uf_params("mapping:\n  magn=2").is_err());
+        assert!(parse_uf_params("mapping:\n  center=0/0 magn=-1").is_err());
+        assert!(parse_uf_params("mapping:\n  center=a/b magn=1").is_err());
+    }
+
+    #[test]
+    fn unknown_formulas_only_warn() {
+        let loc = parse_uf_params("center=0/0 magn=1 entry=\"Lambda\"").unwrap();
+        let mut proj = Project::default();
+        assert_eq!(loc.apply(&mut proj).len(), 1);
+        assert_eq!(proj.fractal.kind, FractalKind::Mandelbrot);
+    }
+}
@@ -0,0 +1,226 @@
+//! Annotations window and the preview overlay that draws them with the export's glyphs.
+
+use super::*;
+
+#[derive(Default)]
+pub(crate) struct AnnotationsPanel {
+    pub(crate) open: bool,
+    selected: Option<usize>,
+}
+
+/// Draws the `annotations` showing at `t` over the preview image in `rect`, glyph pixel
+/// for glyph pixel as the export stamps them.
+pub(crate) fn annotations_overlay(
+    ui: &egui::Ui,
+    rect: Rect,
+    annotations: &[Annotation],
+    t: f32,
+    camera: &Camera,
+) {
+    let size = (rect.width().round() as u32, rect.height().round() as u32);
+    let painter = ui.painter_at(rect);
+    for stamp in annotation_stamps(annotations, t, camera, size) {
+        let [r, g, b] = stamp.color;
+        let color = Color32::from_rgba_unmultiplied(r, g, b, stamp.alpha);
+        let dot = stamp.dot as f32;
+        let origin = rect.min + vec2(stamp.origin.0 as f32, stamp.origin.1 as f32);
+        for (col, row) in stamp.lit_cells() {
+            let min = origin + vec2(col as f32, row as f32) * dot;
+            if rect.intersects(Rect::from_min_size(min, Vec2::splat(dot))) {
+                painter.rect_filled(Rect::from_min_size(min, Vec2::splat(dot)), 0.0, color);
+            }
+        }
+    }
+}
+
+/// Moves `annotation` to `anchor` without moving it on a `view`-sized preview of `camera`.
+fn change_anchor(
+    annotation: &mut Annotation,
+    anchor: AnnotationAnchor,
+    camera: &Camera,
+    view: Vec2,
+) {
+    let [x, y] = annotation.position;
+    match (annotation.anchor, anchor) {
+        (AnnotationAnchor::Screen, AnnotationAnchor::Plane) => {
+            let point = camera.pixel_to_plane(x * view.x, y * view.y, view.x, view.y);
+            annotation.position = [point.re as f32, point.im as f32];
+            annotation.size *= (f64::from(view.y) / camera.scale) as f32;
+        }
+        (AnnotationAnchor::Plane, AnnotationAnchor::Screen) => {
+            let (px, py) = camera.plane_to_pixel(Complex::new(x, y), view.x, view.y);
+            annotation.position = [px / view.x, py / view.y];
+            annotation.size *= (camera.scale / f64::from(view.y)) as f32;
+        }
+        _ => {}
+    }
+    annotation.anchor = anchor;
+}
+
+/// Lists the project's annotations and edits the selected one. `camera` and `view` are
+/// the preview's, for placing new annotations and converting anchors.
+pub(crate) fn annotations_window(
+    ctx: &egui::Context,
+    panel: &mut AnnotationsPanel,
+    proj: &mut Project,
+    camera: &Camera,
+    view: Vec2,
+) {
+    let mut open = panel.open;
+    egui::Window::new("Annotations")
+        .open(&mut open)
+        .default_width(320.0)
+        .show(ctx, |ui| {
+            let t = proj.anim.t;
+            let annotations = &mut proj.annotations;
+            ui.horizontal(|ui| {
+                if ui
+                    .button("➕ Add")
+                    .on_hover_text("New label starting at the playhead")
+                    .clicked()
+                {
+                    annotations.push(Annotation::new("Label").with_times(t, None));
+                    panel.selected = Some(annotations.len() - 1);
+                }
+                let Some(index) = panel.selected.filter(|&i| i < annotations.len()) else {
+                    return;
+                };
+                if ui.add_enabled(index > 0, egui::Button::new("⬆")).clicked() {
+                    annotations.swap(index, index - 1);
+                    panel.selected = Some(index - 1);
+                }
+                let last = index + 1 == annotations.len();
+                if ui.add_enabled(!last, egui::Button::new("⬇")).clicked() {
+                    annotations.swap(index, index + 1);
+                    panel.selected = Some(index + 1);
+                }
+                if ui.button("🗑").on_hover_text("Delete").clicked() {
+                    annotations.remove(index);
+                    panel.selected = None;
+                }
+            });
+            if annotations.is_empty() {
+                ui.weak("No annotations. Later ones draw over earlier ones.");
+            }
+            for (i, annotation) in annotations.iter().enumerate() {
+                let label = format!("{} · {}", annotation.text, annotation.anchor.label());
+                if ui
+                    .selectable_label(panel.selected == Some(i), label)
+                    .clicked()
+                {
+                    panel.selected = Some(i);
+                }
+            }
+            let Some(annotation) = panel.selected.and_then(|i| annotations.get_mut(i)) else {
+                return;
+            };
+            ui.separator();
+            ui.text_edit_singleline(&mut annotation.text);
+            ui.horizontal(|ui| {
+                ui.label("Anchor");
+                for anchor in [AnnotationAnchor::Screen, AnnotationAnchor::Plane] {
+                    if ui
+                        .selectable_label(annotation.anchor == anchor, anchor.label())
+                        .clicked()
+                    {
+                        change_anchor(annotation, anchor, camera, view);
+                    }
+                }
+            });
+            match annotation.anchor {
+                AnnotationAnchor::Screen => {
+                    let [x, y] = &mut annotation.position;
+                    ui.add(egui::Slider::new(x, 0.0..=1.0).text("X"));
+                    ui.add(egui::Slider::new(y, 0.0..=1.0).text("Y"));
+                    ui.add(
+                        egui::Slider::new(&mut annotation.size, 0.01..=0.5)
+                            .text("Height (of frame)"),
+                    );
+                }
+                AnnotationAnchor::Plane => {
+                    let speed = 2.0 / camera.scale;
+                    ui.horizontal(|ui| {
+                        let [re, im] = &mut annotation.position;
+                        ui.add(egui::DragValue::new(re).speed(speed).prefix("re "));
+                        ui.add(egui::DragValue::new(im).speed(speed).prefix("im "));
+                        if ui
+                            .button("Center")
+                            .on_hover_text("Move to the view's center")
+                            .clicked()
+                        {
+                            let center = camera.center.to_f32();
+                            annotation.position = [center.re, center.im];
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        let speed = f64::from(annotation.size).max(1.0 / camera.scale) * 0.01;
+                        ui.add(
+                            egui::DragValue::new(&mut annotation.size)
+                                .speed(speed)
+                                .clamp_range(0.0..=f32::MAX)
+                                .prefix("height "),
+                        );
+                        ui.weak(format!(
+                            "{:.0} px here",
+                            f64::from(annotation.size) * camera.scale
+                        ))
+                        .on_hover_text(format!(
+                            "Fades out below {ANNOTATION_FADE_PX:.0} px and hides below \
+                                 {ANNOTATION_HIDE_PX:.0} px"
+                        ));
+                    });
+                }
+            }
+            ui.horizontal(|ui| {
+                ui.label("Color");
+                rgb_edit_button(ui, &mut annotation.color);
+            });
+            ui.horizontal(|ui| {
+                ui.label("Shown from");
+                ui.add(
+                    egui::DragValue::new(&mut annotation.start)
+                        .speed(0.05)
+                        .suffix(" s"),
+                );
+                let mut ends = annotation.end.is_some();
+                if ui.checkbox(&mut ends, "until").changed() {
+                    annotation.end = ends.then_some(annotation.start.max(t) + 2.0);
+                }
+                if let Some(end) = &mut annotation.end {
+                    ui.add(egui::DragValue::new(end).speed(0.05).suffix(" s"));
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Fade");
+                ui.add(
+                    egui::DragValue::new(&mut annotation.fade_in)
+                        .speed(0.05)
+                        .clamp_range(0.0..=60.0)
+                        .prefix("in ")
+                        .suffix(" s"),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut annotation.fade_out)
+                        .speed(0.05)
+                        .clamp_range(0.0..=60.0)
+                        .prefix("out ")
+                        .suffix(" s"),
+                );
+            });
+            ui.horizontal(|ui| {
+                let mut opacity = annotation.opacity.sample(t, 1.0);
+                if ui
+                    .add(egui::Slider::new(&mut opacity, 0.0..=1.0).text("Opacity"))
+                    .on_hover_text("Keys the opacity at the playhead")
+                    .changed()
+                {
+                    annotation.opacity.upsert(t, opacity);
+                }
+                let keys = annotation.opacity.keys.len();
+                if keys > 0 && ui.small_button(format!("Clear {keys} keys")).clicked() {
+                    annotation.opacity.keys.clear();
+                }
+            });
+        });
+    panel.open = open;
+}
@@ -0,0 +1,187 @@
+//! Audio window: analyze a soundtrack and bake one of its envelopes into a keyframe track.
+
+use super::*;
+
+#[derive(Default)]
+pub(crate) struct AudioPanel {
+    pub(crate) open: bool,
+    path: Option<PathBuf>,
+    analysis: Option<AudioAnalysis>,
+    binding: AudioBinding,
+    pending: Option<mpsc::Receiver<Result<AudioAnalysis, String>>>,
+}
+
+impl AudioPanel {
+    /// Decodes and analyzes `path` on a worker thread, one value per export frame.
+    pub(crate) fn load(&mut self, path: PathBuf, fps: u32) {
+        let (tx, rx) = mpsc::channel();
+        let worker_path = path.clone();
+        thread::spawn(move || {
+            let _ = tx.send(analyze_audio(&worker_path, fps));
+        });
+        self.path = Some(path);
+        self.analysis = None;
+        self.pending = Some(rx);
+    }
+
+    fn poll(&mut self, toasts: &mut Toasts) {
+        let Some(rx) = &self.pending else {
+            return;
+        };
+        match rx.try_recv() {
+            Ok(Ok(analysis)) => self.analysis = Some(analysis),
+            Ok(Err(e)) => toasts.error(e),
+            Err(mpsc::TryRecvError::Empty) => return,
+            Err(mpsc::TryRecvError::Disconnected) => {
+                toasts.error("Audio analysis stopped unexpectedly")
+            }
+        }
+        self.pending = None;
+    }
+}
+
+pub(crate) fn audio_window(
+    ctx: &egui::Context,
+    panel: &mut AudioPanel,
+    proj: &mut Project,
+    dialogs: &mut Dialogs,
+    last_dir: &Option<PathBuf>,
+    toasts: &mut Toasts,
+) {
+    panel.poll(toasts);
+    // Keys are baked per export frame, so a changed frame rate needs a fresh analysis.
+    if let (Some(path), Some(analysis)) = (&panel.path, &panel.analysis) {
+        if analysis.fps != proj.export.fps {
+            panel.load(path.clone(), proj.export.fps);
+        }
+    }
+    let mut open = panel.open;
+    egui::Window::new("Audio")
+        .open(&mut open)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("Load audio…").clicked() {
+                    dialogs.pick(
+                        ctx,
+                        DialogPurpose::LoadAudio,
+                        dialog_in(last_dir).add_filter("Audio", &["wav", "mp3"]),
+                    );
+                }
+                match (&panel.path, &panel.analysis) {
+                    (Some(path), Some(analysis)) => {
+                        ui.label(format!(
+                            "{} ({:.1}s)",
+                            file_label(path),
+                            analysis.duration
+                        ));
+                    }
+                    (Some(path), None) if panel.pending.is_some() => {
+                        ui.spinner();
+                        ui.label(format!("Analyzing {}…", file_label(path)));
+                    }
+                    _ => {
+                        ui.weak("WAV or MP3");
+                    }
+                }
+            });
+            let Some(analysis) = &panel.analysis else {
+                return;
+            };
+            let binding = &mut panel.binding;
+            egui::ComboBox::from_label("Source")
+                .selected_text(binding.band.label())
+                .show_ui(ui, |ui| {
+                    for band in AudioBand::ALL {
+                        ui.selectable_value(&mut binding.band, band, band.label());
+                    }
+                });
+            let target = binding.target;
+            egui::ComboBox::from_label("Target track")
+                .selected_text(target.label())
+                .show_ui(ui, |ui| {
+                    for track in TrackKind::ALL {
+                        ui.selectable_value(&mut binding.target, track, track.label());
+                    }
+                });
+            if binding.target != target {
+                // Start from the current value so the bake moves around what is on screen.
+                binding.offset = binding.target.value(&proj.camera, &proj.fractal);
+            }
+            ui.add(egui::Slider::new(&mut binding.smoothing, 0.0..=2.0).text("Release (s)"));
+            let gain_hint = if binding.target == TrackKind::Zoom {
+                "Doublings of the scale at full level"
+            } else {
+                "Track change at full level"
+            };
+            ui.horizontal(|ui| {
+                ui.label("Gain");
+                ui.add(egui::DragValue::new(&mut binding.gain).speed(0.01))
+                    .on_hover_text(gain_hint);
+                ui.label("Base");
+                let speed = (binding.offset.abs() * 0.01).max(0.001);
+                ui.add(egui::DragValue::new(&mut binding.offset).speed(speed))
+                    .on_hover_text("Track value in silence");
+            });
+            ui.checkbox(&mut binding.accumulate, "Accumulate")
+                .on_hover_text("Loudness sets how fast the value changes, e.g. for a zoom that surges with the music");
+
+            envelope_plot(ui, analysis.envelope(binding.band));
+            ui.horizontal(|ui| {
+                if ui
+                    .button(format!("Bake into {}", binding.target.label()))
+                    .on_hover_text("Replaces the track's keys with one key per export frame")
+                    .clicked()
+                {
+                    match bake_audio(&mut proj.anim, analysis, binding) {
+                        Ok(keys) => toasts.info(format!(
+                            "Baked {keys} keys into the {} track",
+                            binding.target.label()
+                        )),
+                        Err(e) => toasts.error(format!("Could not bake: {e}")),
+                    }
+                }
+                let mut soundtrack = proj.export.audio == panel.path;
+                if ui
+                    .checkbox(&mut soundtrack, "Use as export soundtrack")
+                    .changed()
+                {
+                    proj.export.audio = soundtrack.then(|| panel.path.clone()).flatten();
+                }
+            });
+        });
+    panel.open = open;
+}
+
+fn file_label(path: &Path) -> String {
+    path.file_name().map_or_else(
+        || path.display().to_string(),
+        |n| n.to_string_lossy().into(),
+    )
+}
+
+/// The envelope over the whole file, 0 at the bottom and 1 at the top.
+fn envelope_plot(ui: &mut egui::Ui, envelope: &[f32]) {
+    let (rect, _) =
+        ui.allocate_exact_size(vec2(ui.available_width().max(240.0), 48.0), Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+    if envelope.len() < 2 {
+        return;
+    }
+    // One point per pixel column is plenty; long files have many more frames.
+    let columns = (rect.width() as usize).max(2);
+    let points: Vec<_> = (0..columns)
+        .map(|x| {
+            let i = x * (envelope.len() - 1) / (columns - 1);
+            pos2(
+                rect.left() + x as f32,
+                rect.bottom() - envelope[i] * rect.height(),
+            )
+        })
+        .collect();
+    painter.add(egui::Shape::line(
+        points,
+        Stroke::new(1.0, ui.visuals().selection.bg_fill),
+    ));
+}
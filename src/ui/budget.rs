@@ -0,0 +1,49 @@
+//! Frame-time budget mode: while the view changes, the preview renders whole frames with
+//! the iterations and resolution the budget allows, then one full-quality frame once the
+//! view has been still for a moment.
+
+use super::*;
+
+/// The budget controller and the frame it last shaped.
+#[derive(Default)]
+pub(crate) struct BudgetPreview {
+    pub(crate) controller: FrameBudget,
+    /// Iterations and resolution scale of the last preview frame, `None` when it was
+    /// rendered at full quality.
+    pub(crate) shown: Option<(u32, f32)>,
+}
+
+impl BudgetPreview {
+    /// `fractal` with its iterations held to the cap.
+    pub(crate) fn capped(&self, fractal: &FractalParams) -> FractalParams {
+        FractalParams {
+            max_iter: self.controller.limit(fractal.max_iter),
+            ..fractal.clone()
+        }
+    }
+
+    /// Feeds the controller a finished preview frame, if it was rendered fresh for a
+    /// view that just changed (`moved`).
+    pub(crate) fn observe(&mut self, stats: &RenderStats, moved: bool) {
+        if moved {
+            self.controller
+                .observe(stats.total_ms as f32, stats.max_iter);
+        }
+    }
+}
+
+/// The toolbar toggle for budget mode and, while it is on, the cap in effect.
+pub(crate) fn budget_toolbar(ui: &mut egui::Ui, enabled: &mut bool, budget: &BudgetPreview) {
+    ui.toggle_value(enabled, "⏱ Budget").on_hover_text(
+        "Hold the preview to the frame time set in Preferences while the view changes, \
+         by capping iterations and then resolution. A full-quality frame renders once \
+         the view is still.",
+    );
+    if !*enabled {
+        return;
+    }
+    match budget.shown {
+        Some((iter, scale)) => ui.weak(format!("≤{iter} iter · {:.0}%", scale * 100.0)),
+        None => ui.weak("full quality"),
+    };
+}
@@ -0,0 +1,186 @@
+//! A/B comparison of two captured views.
+
+use super::*;
+
+/// Session-only capture of the parameters and rendered frame (never serialized).
+pub(crate) struct Snapshot {
+    pub(crate) fractal: FractalParams,
+    pub(crate) camera: Camera,
+    pub(crate) tex: TextureHandle,
+    pub(crate) size: [usize; 2],
+}
+
+impl Snapshot {
+    pub(crate) fn capture(
+        ctx: &egui::Context,
+        name: &str,
+        eval: &EvaluatedFrame,
+        frame: &ColorImage,
+    ) -> Self {
+        Self {
+            fractal: eval.fractal.clone(),
+            camera: eval.camera.clone(),
+            tex: ctx.load_texture(name, frame.clone(), egui::TextureOptions::LINEAR),
+            size: frame.size,
+        }
+    }
+
+    pub(crate) fn apply(&self, proj: &mut Project) {
+        proj.fractal = self.fractal.clone();
+        proj.camera = self.camera.clone();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CompareMode {
+    Off,
+    SideBySide,
+    Wipe,
+}
+
+impl CompareMode {
+    pub(crate) fn label(&self) -> &'static str {
+        match self {
+            CompareMode::Off => "Live",
+            CompareMode::SideBySide => "Side by side",
+            CompareMode::Wipe => "Wipe",
+        }
+    }
+}
+
+pub(crate) struct AbCompare {
+    pub(crate) a: Option<Snapshot>,
+    pub(crate) b: Option<Snapshot>,
+    pub(crate) mode: CompareMode,
+    pub(crate) split: f32, // wipe position, 0..1 across the viewport
+}
+
+impl Default for AbCompare {
+    fn default() -> Self {
+        Self {
+            a: None,
+            b: None,
+            mode: CompareMode::Off,
+            split: 0.5,
+        }
+    }
+}
+
+impl AbCompare {
+    pub(crate) fn swap(&mut self) {
+        std::mem::swap(&mut self.a, &mut self.b);
+    }
+
+    pub(crate) fn is_comparing(&self) -> bool {
+        self.mode != CompareMode::Off && self.a.is_some() && self.b.is_some()
+    }
+}
+
+pub(crate) fn ab_compare_ui(
+    ui: &mut egui::Ui,
+    ab: &mut AbCompare,
+    proj: &mut Project,
+    eval: &EvaluatedFrame,
+    last_frame: Option<&ColorImage>,
+) {
+    ui.label("A/B:");
+    ui.add_enabled_ui(last_frame.is_some(), |ui| {
+        if let Some(frame) = last_frame {
+            if ui.button("Store A").clicked() {
+                ab.a = Some(Snapshot::capture(ui.ctx(), "snapshot_a", eval, frame));
+            }
+            if ui.button("Store B").clicked() {
+                ab.b = Some(Snapshot::capture(ui.ctx(), "snapshot_b", eval, frame));
+            }
+        }
+    });
+    if let Some(snap) = &ab.a {
+        if ui.button("Apply A").clicked() {
+            snap.apply(proj);
+        }
+    }
+    if let Some(snap) = &ab.b {
+        if ui.button("Apply B").clicked() {
+            snap.apply(proj);
+        }
+    }
+    if (ab.a.is_some() || ab.b.is_some()) && ui.button("Swap").clicked() {
+        ab.swap();
+    }
+    if ab.a.is_some() && ab.b.is_some() {
+        for mode in [CompareMode::Off, CompareMode::SideBySide, CompareMode::Wipe] {
+            ui.selectable_value(&mut ab.mode, mode, mode.label());
+        }
+    }
+}
+
+/// Largest rect with the image's aspect ratio that fits centered inside `area`.
+pub(crate) fn fit_rect(area: Rect, size: [usize; 2]) -> Rect {
+    let (w, h) = (size[0].max(1) as f32, size[1].max(1) as f32);
+    let scale = (area.width() / w).min(area.height() / h);
+    Rect::from_center_size(area.center(), vec2(w * scale, h * scale))
+}
+
+/// Draws the A/B comparison over the viewport. Returns false when there is nothing to compare.
+pub(crate) fn ab_compare_view(ui: &mut egui::Ui, ab: &mut AbCompare) -> bool {
+    if !ab.is_comparing() {
+        return false;
+    }
+    let (Some(a), Some(b)) = (&ab.a, &ab.b) else {
+        return false;
+    };
+    let rect = ui.available_rect_before_wrap();
+    let response = ui.allocate_rect(rect, Sense::click_and_drag());
+    let painter = ui.painter_at(rect);
+    let full_uv = Rect::from_min_max(pos2(0.0, 0.0), pos2(1.0, 1.0));
+    let label = |pos, text: &str| {
+        painter.text(
+            pos,
+            egui::Align2::LEFT_TOP,
+            text,
+            egui::FontId::proportional(16.0),
+            Color32::WHITE,
+        );
+    };
+
+    match ab.mode {
+        CompareMode::Off => return false,
+        CompareMode::SideBySide => {
+            let half = Rect::from_min_size(rect.min, vec2(rect.width() / 2.0, rect.height()));
+            let left = fit_rect(half, a.size);
+            let right = fit_rect(half.translate(vec2(half.width(), 0.0)), b.size);
+            painter.image(a.tex.id(), left, full_uv, Color32::WHITE);
+            painter.image(b.tex.id(), right, full_uv, Color32::WHITE);
+            label(left.min + vec2(8.0, 8.0), "A");
+            label(right.min + vec2(8.0, 8.0), "B");
+        }
+        CompareMode::Wipe => {
+            let target = fit_rect(rect, a.size);
+            if response.dragged() || response.clicked() {
+                if let Some(pos) = response.interact_pointer_pos() {
+                    ab.split = ((pos.x - target.left()) / target.width()).clamp(0.0, 1.0);
+                }
+            }
+            let split_x = target.left() + target.width() * ab.split;
+            painter.image(
+                a.tex.id(),
+                Rect::from_min_max(target.min, pos2(split_x, target.bottom())),
+                Rect::from_min_max(pos2(0.0, 0.0), pos2(ab.split, 1.0)),
+                Color32::WHITE,
+            );
+            painter.image(
+                b.tex.id(),
+                Rect::from_min_max(pos2(split_x, target.top()), target.max),
+                Rect::from_min_max(pos2(ab.split, 0.0), pos2(1.0, 1.0)),
+                Color32::WHITE,
+            );
+            painter.line_segment(
+                [pos2(split_x, target.top()), pos2(split_x, target.bottom())],
+                Stroke::new(2.0, Color32::WHITE),
+            );
+            label(target.min + vec2(8.0, 8.0), "A");
+            label(pos2(target.right() - 20.0, target.top() + 8.0), "B");
+        }
+    }
+    true
+}
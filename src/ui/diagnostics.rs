@@ -0,0 +1,55 @@
+//! Warnings for black or flat previews, with one-click fixes, in the viewport corner.
+
+use super::*;
+
+#[derive(Default)]
+pub(crate) struct DiagnosticsPanel {
+    /// Luminance of the last full preview frame; `None` while a new one renders.
+    pub(crate) frame: Option<FrameStats>,
+    /// Rules hidden with Dismiss; each comes back once it has cleared and recurs.
+    dismissed: Vec<Diagnosis>,
+}
+
+/// Lists the diagnoses in `found` not yet dismissed in the top-right corner of `rect`.
+/// Returns the one whose fix was clicked.
+pub(crate) fn diagnostics_overlay(
+    ui: &egui::Ui,
+    rect: Rect,
+    panel: &mut DiagnosticsPanel,
+    found: &[Diagnosis],
+) -> Option<Diagnosis> {
+    panel
+        .dismissed
+        .retain(|d| found.iter().any(|f| f.same_rule(d)));
+    let shown: Vec<Diagnosis> = found
+        .iter()
+        .filter(|f| !panel.dismissed.iter().any(|d| d.same_rule(f)))
+        .copied()
+        .collect();
+    if shown.is_empty() {
+        return None;
+    }
+    let mut chosen = None;
+    egui::Area::new(Id::new("diagnostics"))
+        .fixed_pos(rect.right_top() + vec2(-8.0, 8.0))
+        .pivot(egui::Align2::RIGHT_TOP)
+        .show(ui.ctx(), |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.set_max_width(320.0);
+                ui.strong("⚠ Nothing to see?");
+                for diagnosis in &shown {
+                    ui.separator();
+                    ui.label(diagnosis.to_string());
+                    ui.horizontal(|ui| {
+                        if ui.button(diagnosis.fix_label()).clicked() {
+                            chosen = Some(*diagnosis);
+                        }
+                        if ui.small_button("Dismiss").clicked() {
+                            panel.dismissed.push(*diagnosis);
+                        }
+                    });
+                }
+            });
+        });
+    chosen
+}
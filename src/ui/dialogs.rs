@@ -0,0 +1,107 @@
+//! Native file dialogs driven off the UI thread.
+
+use super::*;
+
+pub(crate) fn dialog_in(dir: &Option<PathBuf>) -> rfd::AsyncFileDialog {
+    match dir {
+        Some(dir) => rfd::AsyncFileDialog::new().set_directory(dir),
+        None => rfd::AsyncFileDialog::new(),
+    }
+}
+
+// ------------------------- File Dialogs -------------------------
+
+/// What a pending file dialog's result should be applied to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DialogPurpose {
+    SaveProject,
+    OpenProject,
+    SavePalette,
+    LoadPalette,
+    ExportPath,
+    SnapshotFolder,
+    /// A Kalles Fraktaler `.kfr` file; `palette` also takes over its color table.
+    ImportLocation {
+        palette: bool,
+    },
+    LoadAudio,
+    /// Every export frame's animated parameters, as CSV or JSON.
+    BakeAnimation,
+    /// A baked animation to replace the keyframes with.
+    ImportAnimation,
+    /// Where to write the export's contact sheet PNG.
+    ContactSheet,
+}
+
+pub(crate) struct PendingDialog {
+    pub(crate) purpose: DialogPurpose,
+    pub(crate) result: mpsc::Receiver<Option<PathBuf>>,
+}
+
+/// Native dialogs run as `rfd` async tasks on a helper thread so the UI keeps
+/// rendering behind them. Only one dialog is open at a time.
+#[derive(Default)]
+pub(crate) struct Dialogs {
+    pub(crate) pending: Option<PendingDialog>,
+}
+
+impl Dialogs {
+    pub(crate) fn save(
+        &mut self,
+        ctx: &egui::Context,
+        purpose: DialogPurpose,
+        dialog: rfd::AsyncFileDialog,
+    ) {
+        self.spawn(ctx, purpose, dialog.save_file());
+    }
+
+    pub(crate) fn pick(
+        &mut self,
+        ctx: &egui::Context,
+        purpose: DialogPurpose,
+        dialog: rfd::AsyncFileDialog,
+    ) {
+        self.spawn(ctx, purpose, dialog.pick_file());
+    }
+
+    pub(crate) fn pick_folder(
+        &mut self,
+        ctx: &egui::Context,
+        purpose: DialogPurpose,
+        dialog: rfd::AsyncFileDialog,
+    ) {
+        self.spawn(ctx, purpose, dialog.pick_folder());
+    }
+
+    pub(crate) fn spawn(
+        &mut self,
+        ctx: &egui::Context,
+        purpose: DialogPurpose,
+        task: impl std::future::Future<Output = Option<rfd::FileHandle>> + Send + 'static,
+    ) {
+        if self.pending.is_some() {
+            return;
+        }
+        let (tx, result) = mpsc::channel();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let picked = pollster::block_on(task).map(|handle| handle.path().to_path_buf());
+            let _ = tx.send(picked);
+            ctx.request_repaint();
+        });
+        self.pending = Some(PendingDialog { purpose, result });
+    }
+
+    /// Returns the chosen path once the dialog closes; cancelled dialogs yield nothing.
+    pub(crate) fn poll(&mut self) -> Option<(DialogPurpose, PathBuf)> {
+        let pending = self.pending.as_ref()?;
+        let picked = match pending.result.try_recv() {
+            Ok(picked) => picked,
+            Err(mpsc::TryRecvError::Empty) => return None,
+            Err(mpsc::TryRecvError::Disconnected) => None,
+        };
+        let purpose = pending.purpose;
+        self.pending = None;
+        picked.map(|path| (purpose, path))
+    }
+}
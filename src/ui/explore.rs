@@ -0,0 +1,235 @@
+//! Random exploration of interesting regions, drawn from the project seed.
+
+use super::*;
+use rand::{
+    distributions::{Distribution, WeightedIndex},
+    Rng,
+};
+
+pub(crate) const EXPLORE_HISTORY_LEN: usize = 12;
+pub(crate) const EXPLORE_SCAN_SIZE: u32 = 64;
+/// Viewport width (px) that explored camera scales are expressed against.
+pub(crate) const EXPLORE_VIEW_PX: f32 = 1000.0;
+pub(crate) const EXPLORE_THUMB: (u32, u32) = (96, 64);
+
+/// A previously explored view, kept so a good find can be revisited.
+pub(crate) struct ExploreEntry {
+    /// Draw of the project's explore stream it came from.
+    pub(crate) index: u64,
+    pub(crate) fractal: FractalParams,
+    pub(crate) camera: Camera,
+}
+
+impl ExploreEntry {
+    pub(crate) fn thumbnail(&self) -> ThumbnailRequest {
+        ThumbnailRequest::framed(&self.fractal, &self.camera, EXPLORE_VIEW_PX, EXPLORE_THUMB)
+    }
+}
+
+pub(crate) struct Explorer {
+    /// Draw of the project's explore stream the next jump uses.
+    pub(crate) next: u64,
+    pub(crate) random_palette: bool,
+    pub(crate) history: Vec<ExploreEntry>, // newest first
+}
+
+impl Default for Explorer {
+    fn default() -> Self {
+        Self {
+            next: 0,
+            random_palette: true,
+            history: Vec::new(),
+        }
+    }
+}
+
+impl Explorer {
+    pub(crate) fn remember(&mut self, index: u64, fractal: &FractalParams, camera: &Camera) {
+        self.history.insert(
+            0,
+            ExploreEntry {
+                index,
+                fractal: fractal.clone(),
+                camera: camera.clone(),
+            },
+        );
+        self.history.truncate(EXPLORE_HISTORY_LEN);
+    }
+}
+
+/// Coarse escape scan returning pixels whose 4-neighbors straddle interior and exterior.
+pub(crate) fn boundary_pixels(p: &FractalParams, cam: &Camera, size: u32) -> Vec<(u32, u32)> {
+    let s = size as f32;
+    let inside: Vec<bool> = (0..size * size)
+        .map(|idx| {
+            let pt = cam.pixel_to_plane((idx % size) as f32, (idx / size) as f32, s, s);
+            !iterate_point(p, pt.re as f32, pt.im as f32).escaped(p)
+        })
+        .collect();
+    let mut out = Vec::new();
+    let stride = size as usize;
+    for y in 1..size - 1 {
+        for x in 1..size - 1 {
+            let i = y as usize * stride + x as usize;
+            let here = inside[i];
+            if [i - 1, i + 1, i - stride, i + stride]
+                .iter()
+                .any(|&j| inside[j] != here)
+            {
+                out.push((x, y));
+            }
+        }
+    }
+    out
+}
+
+/// Picks a random plausible view near the set boundary from draw `index` of the explore
+/// stream of project seed `seed`. The same seed and index always yield the same view.
+pub(crate) fn explore_view(
+    seed: u64,
+    index: u64,
+    base: &FractalParams,
+    random_palette: bool,
+) -> (FractalParams, Camera) {
+    let mut rng = seeded_rng(seed, RandomStream::Explore, index);
+    let mut fractal = base.clone();
+    let kinds = [
+        (FractalKind::Mandelbrot, 5),
+        (FractalKind::Julia, 3),
+        (FractalKind::BurningShip, 2),
+        (FractalKind::Multibrot, 2),
+        (FractalKind::Tricorn, 1),
+        (FractalKind::Newton, 1),
+        (FractalKind::Phoenix, 1),
+        (FractalKind::Celtic, 1),
+        (FractalKind::Buffalo, 1),
+        (FractalKind::Lyapunov, 1),
+    ];
+    let weights = WeightedIndex::new(kinds.iter().map(|(_, w)| *w)).unwrap();
+    fractal.kind = kinds[weights.sample(&mut rng)].0;
+    match fractal.kind {
+        FractalKind::Julia => {
+            // Seeds just around the main cardioid give connected, detailed Julia sets.
+            let theta: f32 = rng.gen_range(0.0..2.0 * PI);
+            let r: f32 = rng.gen_range(0.95..1.02);
+            fractal.c = Complex {
+                re: r * (0.5 * theta.cos() - 0.25 * (2.0 * theta).cos()),
+                im: r * (0.5 * theta.sin() - 0.25 * (2.0 * theta).sin()),
+            };
+        }
+        FractalKind::Phoenix => {
+            // The classic seed, with the previous `z` weighted around its -0.5.
+            fractal.c = Complex::new(0.5667, 0.0);
+            fractal.phoenix_p = Complex::new(rng.gen_range(-0.6..-0.4), rng.gen_range(-0.1..0.1));
+        }
+        FractalKind::Multibrot | FractalKind::Newton => {
+            fractal.power = rng.gen_range(3..=6) as f32;
+        }
+        _ => {}
+    }
+
+    let (center, width) = formula(fractal.kind).overview();
+    let mut camera = Camera {
+        center: center.into(),
+        scale: f64::from(EXPLORE_SCAN_SIZE as f32 / width),
+        rotation: 0.0,
+        deep_center: None,
+    };
+    let mut scan = fractal.clone();
+    scan.max_iter = 200;
+    scan.orbit.enabled = false;
+    let steps = rng.gen_range(1..=3);
+    for _ in 0..steps {
+        let candidates = boundary_pixels(&scan, &camera, EXPLORE_SCAN_SIZE);
+        if candidates.is_empty() {
+            break;
+        }
+        let (x, y) = candidates[rng.gen_range(0..candidates.len())];
+        let size = EXPLORE_SCAN_SIZE as f32;
+        camera.center = camera.pixel_to_plane(x as f32, y as f32, size, size);
+        camera.scale *= f64::from(10f32.powf(rng.gen_range(0.6..1.4)));
+        scan.max_iter += 150;
+    }
+
+    let depth = (camera.scale * f64::from(width / EXPLORE_SCAN_SIZE as f32))
+        .log10()
+        .max(0.0);
+    fractal.max_iter = ((300.0 + 300.0 * depth) as u32).min(20_000);
+    camera.scale *= f64::from(EXPLORE_VIEW_PX / EXPLORE_SCAN_SIZE as f32);
+
+    if random_palette {
+        let presets = palette_presets();
+        apply_palette_preset(
+            &mut fractal.palette,
+            &presets[rng.gen_range(0..presets.len())],
+        );
+        fractal.palette_phase = rng.gen_range(0.0..1.0);
+    }
+    (fractal, camera)
+}
+
+/// The Explore button and the draw it uses next.
+pub(crate) fn explore_jump_ui(ui: &mut egui::Ui, explorer: &mut Explorer, proj: &mut Project) {
+    ui.horizontal(|ui| {
+        if ui
+            .button("Explore")
+            .on_hover_text("Jump to a random spot near the set boundary")
+            .clicked()
+        {
+            let index = explorer.next;
+            let (fractal, camera) =
+                explore_view(proj.seed, index, &proj.fractal, explorer.random_palette);
+            explorer.remember(index, &fractal, &camera);
+            proj.fractal = fractal;
+            proj.camera = camera;
+            explorer.next = index + 1;
+        }
+        ui.label("Jump");
+        ui.add(egui::DragValue::new(&mut explorer.next).clamp_range(0..=u32::MAX));
+    });
+}
+
+/// The project seed and a button to draw a new one.
+pub(crate) fn project_seed_ui(ui: &mut egui::Ui, explorer: &mut Explorer, proj: &mut Project) {
+    ui.horizontal(|ui| {
+        ui.label("Project seed");
+        ui.add(egui::DragValue::new(&mut proj.seed))
+            .on_hover_text("Every random choice in the project: explore jumps and sample jitter");
+        if ui
+            .small_button("Regenerate")
+            .on_hover_text("Draw a new seed; the same seed always explores and renders the same")
+            .clicked()
+        {
+            proj.seed = fresh_seed();
+            explorer.next = 0;
+        }
+    });
+}
+
+/// Thumbnails of recent finds; clicking one goes back to it.
+pub(crate) fn explore_history_ui(
+    ui: &mut egui::Ui,
+    explorer: &Explorer,
+    thumbnails: &mut Thumbnails,
+    proj: &mut Project,
+) {
+    let mut restore = None;
+    ui.horizontal_wrapped(|ui| {
+        for (idx, entry) in explorer.history.iter().enumerate() {
+            if thumbnail_button(ui, thumbnails, entry.thumbnail())
+                .on_hover_text(format!(
+                    "{} — jump {}",
+                    entry.fractal.kind.label(),
+                    entry.index
+                ))
+                .clicked()
+            {
+                restore = Some(idx);
+            }
+        }
+    });
+    if let Some(entry) = restore.and_then(|idx| explorer.history.get(idx)) {
+        proj.fractal = entry.fractal.clone();
+        proj.camera = entry.camera.clone();
+    }
+}
@@ -0,0 +1,81 @@
+//! Background export worker.
+
+use super::*;
+
+/// An export running on its own thread so the UI keeps rendering.
+pub(crate) struct ExportJob {
+    /// What the job writes, for its progress bar and messages, e.g. "Export".
+    pub(crate) name: &'static str,
+    pub(crate) progress: Arc<ExportProgress>,
+    pub(crate) result: mpsc::Receiver<Result<ExportReport, ExportError>>,
+}
+
+impl ExportJob {
+    pub(crate) fn start(
+        proj: Project,
+        ffmpeg: PathBuf,
+        stats_log: Option<StatsLog>,
+        tile_sizes: TileTuning,
+    ) -> Self {
+        let progress = ExportProgress {
+            stats_log: Mutex::new(stats_log),
+            tile_sizes: Mutex::new(tile_sizes),
+            ..ExportProgress::default()
+        };
+        Self::spawn("Export", proj, progress, move |proj, progress, renderer| {
+            export_video_blocking(proj, &ffmpeg, progress, renderer)
+        })
+    }
+
+    /// Writes the project's [`ContactSheet`] to `path`. `tile_sizes` pass through
+    /// unchanged, since the sheet never calibrates.
+    pub(crate) fn contact_sheet(proj: Project, path: PathBuf, tile_sizes: TileTuning) -> Self {
+        let sheet = proj.export.contact_sheet;
+        let progress = ExportProgress {
+            tile_sizes: Mutex::new(tile_sizes),
+            ..ExportProgress::default()
+        };
+        Self::spawn(
+            "Contact sheet",
+            proj,
+            progress,
+            move |proj, progress, renderer| {
+                write_contact_sheet(proj, &sheet, &path, progress, renderer)
+            },
+        )
+    }
+
+    fn spawn(
+        name: &'static str,
+        proj: Project,
+        progress: ExportProgress,
+        run: impl FnOnce(
+                &Project,
+                &ExportProgress,
+                &mut dyn Renderer,
+            ) -> Result<ExportReport, ExportError>
+            + Send
+            + 'static,
+    ) -> Self {
+        let progress = Arc::new(progress);
+        let (tx, result) = mpsc::channel();
+        let worker_progress = Arc::clone(&progress);
+        thread::spawn(move || {
+            // The UI's renderer stays on the UI thread; GPU exports get their own device.
+            let mut renderer = create_renderer(proj.render_backend).unwrap_or_else(|err| {
+                eprintln!(
+                    "{} init failed: {err}. Falling back to CPU.",
+                    proj.render_backend.label()
+                );
+                Box::new(CpuRenderer)
+            });
+            let res = run(&proj, &worker_progress, renderer.as_mut());
+            let _ = tx.send(res);
+        });
+        Self {
+            name,
+            progress,
+            result,
+        }
+    }
+}
@@ -0,0 +1,65 @@
+//! Undo/redo for project edits.
+
+use super::*;
+
+pub(crate) const HISTORY_LIMIT: usize = 100;
+
+/// Snapshot-based undo: each committed project state is kept serialized.
+/// Playback state (time, play/pause, key selection) is not part of history.
+pub(crate) struct History {
+    pub(crate) undo: Vec<String>,
+    pub(crate) redo: Vec<String>,
+    pub(crate) committed: String,
+}
+
+impl History {
+    pub(crate) fn new(proj: &Project) -> Self {
+        Self {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            committed: Self::state(proj),
+        }
+    }
+
+    pub(crate) fn state(proj: &Project) -> String {
+        serde_json::to_string(&proj.authored()).unwrap_or_default()
+    }
+
+    /// Pushes an undo step if the project changed since the last commit.
+    pub(crate) fn record(&mut self, proj: &Project) {
+        let state = Self::state(proj);
+        if state == self.committed {
+            return;
+        }
+        self.undo
+            .push(std::mem::replace(&mut self.committed, state));
+        if self.undo.len() > HISTORY_LIMIT {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    pub(crate) fn undo(&mut self, proj: &mut Project) {
+        // Commit pending edits first so they can be redone.
+        self.record(proj);
+        if let Some(prev) = self.undo.pop() {
+            self.redo.push(std::mem::replace(&mut self.committed, prev));
+            Self::restore(&self.committed, proj);
+        }
+    }
+
+    pub(crate) fn redo(&mut self, proj: &mut Project) {
+        if let Some(next) = self.redo.pop() {
+            self.undo.push(std::mem::replace(&mut self.committed, next));
+            Self::restore(&self.committed, proj);
+        }
+    }
+
+    pub(crate) fn restore(state: &str, proj: &mut Project) {
+        if let Ok(mut p) = serde_json::from_str::<Project>(state) {
+            p.anim.t = proj.anim.t;
+            p.anim.playing = proj.anim.playing;
+            *proj = p;
+        }
+    }
+}
@@ -0,0 +1,154 @@
+//! Julia viewport beside the main one: the splitter between them, the c marker on the
+//! main view, and the Julia preview, which renders only while the main one is idle.
+
+use super::*;
+
+/// Width of the splitter's grab area between the viewports, in points.
+const SPLITTER_WIDTH: f32 = 6.0;
+
+/// Preview state of the Julia viewport.
+#[derive(Default)]
+pub(crate) struct JuliaViewport {
+    pub(crate) preview: PreviewState,
+    tex: Option<TextureHandle>,
+    /// Whether the c marker is being dragged, so the Julia preview drops to draft resolution.
+    pub(crate) marker_dragging: bool,
+}
+
+/// Splits the space left in `ui` into the main and Julia viewports at `split` (the main
+/// view's share of the width), which the splitter between them drags.
+pub(crate) fn viewport_split(ui: &mut egui::Ui, split: &mut f32) -> (Rect, Rect) {
+    let rect = ui.available_rect_before_wrap();
+    let x = rect.left() + rect.width() * split.clamp(0.2, 0.8);
+    let handle = Rect::from_x_y_ranges(
+        x - SPLITTER_WIDTH / 2.0..=x + SPLITTER_WIDTH / 2.0,
+        rect.y_range(),
+    );
+    let response = ui
+        .interact(handle, Id::new("julia_splitter"), Sense::drag())
+        .on_hover_cursor(egui::CursorIcon::ResizeHorizontal);
+    if let Some(pos) = response
+        .interact_pointer_pos()
+        .filter(|_| response.dragged())
+    {
+        *split = ((pos.x - rect.left()) / rect.width()).clamp(0.2, 0.8);
+    }
+    let color = if response.hovered() || response.dragged() {
+        ui.visuals().widgets.hovered.bg_stroke.color
+    } else {
+        ui.visuals().widgets.noninteractive.bg_stroke.color
+    };
+    ui.painter()
+        .vline(x, rect.y_range(), Stroke::new(SPLITTER_WIDTH / 2.0, color));
+    let main = Rect::from_x_y_ranges(rect.left()..=handle.left(), rect.y_range());
+    let julia = Rect::from_x_y_ranges(handle.right()..=rect.right(), rect.y_range());
+    (main, julia)
+}
+
+/// Marks the Julia constant `c` on the main view and lets it be dragged. Returns true
+/// while dragging.
+pub(crate) fn julia_marker_overlay(
+    ui: &mut egui::Ui,
+    rect: Rect,
+    camera: &Camera,
+    c: &mut Complex,
+) -> bool {
+    let (x, y) = camera.plane_to_pixel(*c, rect.width(), rect.height());
+    let at = rect.min + vec2(x, y);
+    let handle = Rect::from_center_size(at, vec2(16.0, 16.0));
+    let response = ui
+        .interact(handle, Id::new("julia_marker"), Sense::drag())
+        .on_hover_cursor(egui::CursorIcon::Grab);
+    let dragging = response.dragged();
+    if let Some(pos) = response.interact_pointer_pos().filter(|_| dragging) {
+        let local = pos - rect.min;
+        *c = camera
+            .pixel_to_plane(local.x, local.y, rect.width(), rect.height())
+            .to_f32();
+    }
+    if rect.expand(8.0).contains(at) {
+        let painter = ui.painter_at(rect);
+        let stroke = Stroke::new(1.5, Color32::from_rgb(120, 200, 255));
+        painter.circle_stroke(at, 7.0, stroke);
+        painter.line_segment([at - vec2(11.0, 0.0), at + vec2(11.0, 0.0)], stroke);
+        painter.line_segment([at - vec2(0.0, 11.0), at + vec2(0.0, 11.0)], stroke);
+        response.on_hover_text(format!("Julia c = {:.5} {:+.5}i", c.re, c.im));
+    }
+    dragging
+}
+
+/// Draws the Julia viewport for `main` in the space left in `ui` and navigates its camera.
+/// Nothing new is rendered while `waiting` (the main preview has tiles to go); the last
+/// image stays up until it is done.
+pub(crate) fn julia_viewport_ui(
+    ui: &mut egui::Ui,
+    state: &mut JuliaViewport,
+    view: &mut JuliaView,
+    main: &EvaluatedFrame,
+    renderer: &mut dyn Renderer,
+    preview_scale: f32,
+    waiting: bool,
+) -> Result<(), RenderError> {
+    let avail = ui.available_size().max(Vec2::splat(64.0));
+    let scale = if state.marker_dragging {
+        preview_scale * 0.5
+    } else {
+        preview_scale
+    };
+    let size = ((avail.x * scale) as u32, (avail.y * scale) as u32);
+    let shown = view.frame(main);
+    let image_size = [size.0 as usize, size.1 as usize];
+    let tex = state.tex.get_or_insert_with(|| {
+        ui.ctx().load_texture(
+            "julia_preview",
+            ColorImage::new(image_size, Color32::TRANSPARENT),
+            egui::TextureOptions::LINEAR,
+        )
+    });
+    let mut result = Ok(());
+    // The main preview repaints the UI when it is done.
+    if !waiting {
+        let camera = Camera {
+            scale: shown.camera.scale * f64::from(scale),
+            ..shown.camera.clone()
+        };
+        let center = pos2(size.0 as f32 / 2.0, size.1 as f32 / 2.0);
+        let rendered = state.preview.render(
+            ui.ctx(),
+            size,
+            &shown.fractal,
+            &camera,
+            renderer,
+            Steer {
+                pass: None,
+                focus: center,
+                whole: false,
+            },
+        );
+        match rendered {
+            Ok(PreviewUpdate::Frame(pixels, _)) => {
+                let image = ColorImage::from_rgba_unmultiplied(image_size, &pixels);
+                tex.set(image, egui::TextureOptions::LINEAR);
+            }
+            Ok(PreviewUpdate::Tiles(tiles)) => upload_tiles(tex, image_size, tiles),
+            Err(err) => result = Err(err),
+        }
+    }
+    let image = ui.image((tex.id(), avail));
+    let before = view.camera.clone();
+    viewport_navigation(
+        ui,
+        Id::new("julia_viewport_nav"),
+        image.rect,
+        &before,
+        &mut view.camera,
+    );
+    ui.painter_at(image.rect).text(
+        image.rect.left_top() + vec2(8.0, 8.0),
+        egui::Align2::LEFT_TOP,
+        format!("Julia  c = {:.5} {:+.5}i", view.c.re, view.c.im),
+        egui::FontId::monospace(12.0),
+        Color32::WHITE,
+    );
+    result
+}
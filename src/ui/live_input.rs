@@ -0,0 +1,300 @@
+//! MIDI and OSC listeners driving mapped parameters, and the window that edits the mappings.
+
+use std::{net::UdpSocket, sync::atomic::AtomicBool};
+
+use super::*;
+use crate::settings::LiveInputSettings;
+
+/// Edits closer together than this count as one gesture for undo.
+const GESTURE_GAP: Duration = Duration::from_millis(500);
+
+/// Listener threads feed one channel that the UI drains at the start of each frame.
+pub(crate) struct LiveInput {
+    pub(crate) open: bool,
+    tx: mpsc::Sender<LiveEvent>,
+    rx: mpsc::Receiver<LiveEvent>,
+    osc: Option<OscListener>,
+    #[cfg(feature = "midi")]
+    midi: Option<midir::MidiInputConnection<()>>,
+    #[cfg(feature = "midi")]
+    midi_ports: Vec<String>,
+    /// Listener settings the running listeners were started with.
+    started: Option<(bool, u16, Option<String>)>,
+    /// Mapping waiting for the next control that moves.
+    learning: Option<usize>,
+    last_event: Option<(LiveEvent, Instant)>,
+    error: Option<String>,
+}
+
+impl Default for LiveInput {
+    fn default() -> Self {
+        let (tx, rx) = mpsc::channel();
+        Self {
+            open: false,
+            tx,
+            rx,
+            osc: None,
+            #[cfg(feature = "midi")]
+            midi: None,
+            #[cfg(feature = "midi")]
+            midi_ports: midi_port_names(),
+            started: None,
+            learning: None,
+            last_event: None,
+            error: None,
+        }
+    }
+}
+
+impl LiveInput {
+    /// (Re)starts the listeners when their settings changed since the last frame.
+    pub(crate) fn sync(&mut self, ctx: &egui::Context, settings: &LiveInputSettings) {
+        let wanted = (
+            settings.osc_enabled,
+            settings.osc_port,
+            settings.midi_port.clone(),
+        );
+        // Wait for the port field to be released rather than binding every value on the way.
+        if self.started.as_ref() == Some(&wanted) || ctx.input(|i| i.pointer.any_down()) {
+            return;
+        }
+        self.started = Some(wanted);
+        self.error = None;
+        self.osc = None;
+        if settings.osc_enabled {
+            match OscListener::start(settings.osc_port, self.tx.clone(), ctx.clone()) {
+                Ok(listener) => self.osc = Some(listener),
+                Err(e) => self.error = Some(format!("OSC port {}: {e}", settings.osc_port)),
+            }
+        }
+        #[cfg(feature = "midi")]
+        {
+            self.midi = None;
+            if let Some(name) = &settings.midi_port {
+                match connect_midi(name, self.tx.clone(), ctx.clone()) {
+                    Ok(conn) => self.midi = Some(conn),
+                    Err(e) => self.error = Some(format!("MIDI {name}: {e}")),
+                }
+            }
+        }
+    }
+
+    /// Writes everything received since the last frame to the authored parameters, or binds
+    /// the first control that moved while learning.
+    pub(crate) fn poll(&mut self, proj: &mut Project, settings: &mut LiveInputSettings) {
+        while let Ok(event) = self.rx.try_recv() {
+            if let Some(index) = self.learning.take() {
+                if let Some(mapping) = settings.mappings.get_mut(index) {
+                    mapping.source = Some(event.source.clone());
+                }
+            } else {
+                apply_live_event(proj, &settings.mappings, &event);
+            }
+            self.last_event = Some((event, Instant::now()));
+        }
+    }
+
+    /// True while controls are moving, so a knob turn becomes a single undo step.
+    pub(crate) fn gesture_active(&self) -> bool {
+        self.last_event
+            .as_ref()
+            .is_some_and(|(_, at)| at.elapsed() < GESTURE_GAP)
+    }
+}
+
+/// Receives OSC packets on a UDP port until dropped.
+struct OscListener {
+    stop: Arc<AtomicBool>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl OscListener {
+    fn start(port: u16, tx: mpsc::Sender<LiveEvent>, ctx: egui::Context) -> std::io::Result<Self> {
+        let socket = UdpSocket::bind(("0.0.0.0", port))?;
+        // Wake up regularly to notice `stop`.
+        socket.set_read_timeout(Some(Duration::from_millis(100)))?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let worker_stop = Arc::clone(&stop);
+        let thread = thread::spawn(move || {
+            let mut buf = [0u8; 2048];
+            while !worker_stop.load(AtomicOrdering::Relaxed) {
+                let Ok(len) = socket.recv(&mut buf) else {
+                    continue;
+                };
+                // Malformed packets from other senders on the port are ignored.
+                for event in decode_osc(&buf[..len]).unwrap_or_default() {
+                    if tx.send(event).is_err() {
+                        return;
+                    }
+                }
+                ctx.request_repaint();
+            }
+        });
+        Ok(Self {
+            stop,
+            thread: Some(thread),
+        })
+    }
+}
+
+impl Drop for OscListener {
+    fn drop(&mut self) {
+        self.stop.store(true, AtomicOrdering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(feature = "midi")]
+fn midi_port_names() -> Vec<String> {
+    let Ok(midi) = midir::MidiInput::new("Matterhorn AH") else {
+        return Vec::new();
+    };
+    midi.ports()
+        .iter()
+        .filter_map(|port| midi.port_name(port).ok())
+        .collect()
+}
+
+#[cfg(feature = "midi")]
+fn connect_midi(
+    name: &str,
+    tx: mpsc::Sender<LiveEvent>,
+    ctx: egui::Context,
+) -> Result<midir::MidiInputConnection<()>, String> {
+    let midi = midir::MidiInput::new("Matterhorn AH").map_err(|e| e.to_string())?;
+    let port = midi
+        .ports()
+        .into_iter()
+        .find(|port| midi.port_name(port).is_ok_and(|n| n == name))
+        .ok_or("port not found")?;
+    midi.connect(
+        &port,
+        "matterhorn-live-input",
+        move |_stamp, message, _| {
+            if let Some(event) = decode_midi_cc(message) {
+                let _ = tx.send(event);
+                ctx.request_repaint();
+            }
+        },
+        (),
+    )
+    .map_err(|e| e.to_string())
+}
+
+pub(crate) fn live_input_window(
+    ctx: &egui::Context,
+    live: &mut LiveInput,
+    settings: &mut LiveInputSettings,
+) {
+    let mut open = live.open;
+    egui::Window::new("Live Input")
+        .open(&mut open)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut settings.osc_enabled, "OSC on UDP port");
+                ui.add(egui::DragValue::new(&mut settings.osc_port).clamp_range(1024..=65535));
+            });
+            midi_port_ui(ui, live, settings);
+            if let Some(e) = &live.error {
+                ui.colored_label(ui.visuals().error_fg_color, e);
+            }
+            match &live.last_event {
+                Some((event, _)) => ui.weak(format!("Last: {} = {:.3}", event.source, event.value)),
+                None => ui.weak("Nothing received yet"),
+            };
+            ui.separator();
+
+            let mut remove = None;
+            egui::Grid::new("live_mappings")
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong("Control");
+                    ui.strong("Parameter");
+                    ui.strong("Min");
+                    ui.strong("Max");
+                    ui.strong("Curve");
+                    ui.end_row();
+                    for (i, mapping) in settings.mappings.iter_mut().enumerate() {
+                        let learning = live.learning == Some(i);
+                        let label = match (&mapping.source, learning) {
+                            (_, true) => "Move a control…".to_string(),
+                            (Some(source), false) => source.to_string(),
+                            (None, false) => "Learn".to_string(),
+                        };
+                        if ui
+                            .selectable_label(learning, label)
+                            .on_hover_text("Click, then move a knob or fader to bind it")
+                            .clicked()
+                        {
+                            live.learning = (!learning).then_some(i);
+                        }
+                        let target = mapping.target;
+                        egui::ComboBox::from_id_source(("live_target", i))
+                            .selected_text(target.label())
+                            .show_ui(ui, |ui| {
+                                for t in LiveTarget::ALL {
+                                    ui.selectable_value(&mut mapping.target, t, t.label());
+                                }
+                            });
+                        if mapping.target != target {
+                            (mapping.min, mapping.max) = mapping.target.default_range();
+                        }
+                        ui.add(egui::DragValue::new(&mut mapping.min).speed(0.01));
+                        ui.add(egui::DragValue::new(&mut mapping.max).speed(0.01));
+                        egui::ComboBox::from_id_source(("live_curve", i))
+                            .selected_text(mapping.curve.label())
+                            .show_ui(ui, |ui| {
+                                for curve in InputCurve::ALL {
+                                    ui.selectable_value(&mut mapping.curve, curve, curve.label());
+                                }
+                            });
+                        if ui.small_button("✕").clicked() {
+                            remove = Some(i);
+                        }
+                        ui.end_row();
+                    }
+                });
+            if let Some(i) = remove {
+                settings.mappings.remove(i);
+                live.learning = None;
+            }
+            if ui.button("Add mapping").clicked() {
+                settings
+                    .mappings
+                    .push(InputMapping::new(LiveTarget::PalettePhase));
+                live.learning = Some(settings.mappings.len() - 1);
+            }
+        });
+    live.open = open;
+    if !live.open {
+        live.learning = None;
+    }
+}
+
+#[cfg(feature = "midi")]
+fn midi_port_ui(ui: &mut egui::Ui, live: &mut LiveInput, settings: &mut LiveInputSettings) {
+    ui.horizontal(|ui| {
+        ui.label("MIDI input");
+        egui::ComboBox::from_id_source("midi_port")
+            .selected_text(settings.midi_port.as_deref().unwrap_or("Off"))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut settings.midi_port, None, "Off");
+                for name in &live.midi_ports {
+                    ui.selectable_value(&mut settings.midi_port, Some(name.clone()), name);
+                }
+            });
+        if ui.small_button("Refresh").clicked() {
+            live.midi_ports = midi_port_names();
+            // Reconnect too, in case the selected device was plugged back in.
+            live.started = None;
+        }
+    });
+}
+
+#[cfg(not(feature = "midi"))]
+fn midi_port_ui(ui: &mut egui::Ui, _live: &mut LiveInput, _settings: &mut LiveInputSettings) {
+    ui.weak("MIDI needs a build with the `midi` feature.");
+}
@@ -0,0 +1,1415 @@
+//! egui/eframe front end.
+
+mod annotations;
+#[cfg(not(target_arch = "wasm32"))]
+mod audio;
+mod budget;
+mod compare;
+mod diagnostics;
+#[cfg(not(target_arch = "wasm32"))]
+mod dialogs;
+mod explore;
+#[cfg(not(target_arch = "wasm32"))]
+mod export_job;
+mod history;
+mod julia_view;
+#[cfg(not(target_arch = "wasm32"))]
+mod live_input;
+mod panels;
+#[cfg(not(target_arch = "wasm32"))]
+mod preview_worker;
+mod quality;
+mod ranges;
+mod region;
+mod report;
+mod script;
+mod search;
+mod share;
+mod side_panel;
+mod snapshots;
+mod thumbnails;
+mod timeline;
+mod toasts;
+mod tour;
+mod viewport;
+#[cfg(not(target_arch = "wasm32"))]
+mod watch;
+#[cfg(target_arch = "wasm32")]
+mod web;
+mod zoom_wizard;
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+use std::{
+    borrow::Cow,
+    convert::Infallible,
+    f32::consts::PI,
+    fs,
+    path::{Path, PathBuf},
+    sync::{atomic::Ordering as AtomicOrdering, mpsc, Arc, Mutex},
+};
+
+use eframe::{egui, egui::Vec2, App};
+use egui::{
+    pos2, vec2,
+    widgets::color_picker::{color_edit_button_srgba, Alpha},
+    Color32, ColorImage, Id, Rect, Sense, Stroke, TextureHandle,
+};
+use image::{ImageBuffer, Rgba};
+use matterhorn_core::*;
+use web_time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+#[cfg(target_arch = "wasm32")]
+use self::web::*;
+use self::{
+    annotations::*, budget::*, compare::*, diagnostics::*, explore::*, history::*, julia_view::*,
+    panels::*, quality::*, ranges::*, region::*, report::*, script::*, search::*, share::*,
+    side_panel::*, snapshots::*, thumbnails::*, timeline::*, toasts::*, tour::*, viewport::*,
+    zoom_wizard::*,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use self::{audio::*, dialogs::*, export_job::*, live_input::*, preview_worker::*, watch::*};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::settings::remember_dir;
+use crate::settings::AppSettings;
+
+struct MatterhornApp {
+    proj: Project,
+    tex: Option<TextureHandle>,
+    last_frame: Option<ColorImage>,
+    ab: AbCompare,
+    explorer: Explorer,
+    thumbnails: Thumbnails,
+    settings: AppSettings,
+    settings_saved: AppSettings,
+    show_settings: bool,
+    toasts: Toasts,
+    viewport: Vec2,
+    /// Whether a drag in the viewport changed the view last UI frame.
+    draft_preview: bool,
+    /// Whether the preview shows the export's stylized look while it is on.
+    stylize_preview: bool,
+    interaction: Interaction,
+    budget: BudgetPreview,
+    eval: EvaluatedFrame,
+    timeline: TimelineEditor,
+    script: ScriptEditor,
+    report: ReportWindow,
+    zoom_wizard: ZoomWizard,
+    history: History,
+    share: ShareState,
+    search: PanelSearch,
+    #[cfg(not(target_arch = "wasm32"))]
+    export_job: Option<ExportJob>,
+    #[cfg(not(target_arch = "wasm32"))]
+    dialogs: Dialogs,
+    #[cfg(not(target_arch = "wasm32"))]
+    live: LiveInput,
+    #[cfg(not(target_arch = "wasm32"))]
+    audio: AudioPanel,
+    #[cfg(target_arch = "wasm32")]
+    web_files: WebFiles,
+    last_update: Instant,
+    /// Draws the preview and stills; see [`MatterhornApp::sync_renderer`].
+    renderer: Box<dyn Renderer>,
+    /// Backend `renderer` was created for.
+    renderer_backend: RenderBackend,
+    /// Whether the toast for the renderer's current demotion is up.
+    demotion_shown: bool,
+    /// Timing of the last preview frame, for the stats overlay.
+    render_stats: Option<RenderStats>,
+    /// Reference orbits of perturbation previews, so scrubbing near one center and depth
+    /// does not compute a new one every frame.
+    reference_orbits: OrbitCache<Arc<ReferenceOrbit>>,
+    /// The custom formula compiled, so it is parsed when edited rather than every frame.
+    custom_formula: FormulaCache,
+    /// Compositing pass shown instead of the beauty image.
+    preview_pass: Option<AovPass>,
+    /// Samples of the last preview and the frame being rendered.
+    preview: PreviewState,
+    diagnostics: DiagnosticsPanel,
+    /// Box of the viewport rendered at export quality.
+    region: RegionPreview,
+    quality: QualityEditor,
+    annotations: AnnotationsPanel,
+    julia: JuliaViewport,
+    /// Glide of the preview onto a newly picked zoom target, and when it started.
+    recenter: Option<(CameraTransition, Instant)>,
+    /// Preview parameters last found NaN or infinite, so a bad track warns once rather than
+    /// every frame.
+    non_finite: Vec<String>,
+    tour: TourState,
+    /// Open stats log and the path it was opened for.
+    #[cfg(not(target_arch = "wasm32"))]
+    stats_log: Option<(PathBuf, StatsLog)>,
+    /// File the project was opened from or last saved to, watched for outside edits.
+    #[cfg(not(target_arch = "wasm32"))]
+    project_file: Option<ProjectFile>,
+}
+
+impl MatterhornApp {
+    fn new(proj: Project, settings: AppSettings, toasts: Toasts) -> Self {
+        Self {
+            eval: evaluate_frame(&proj, proj.anim.t, EvalTarget::Preview),
+            timeline: TimelineEditor::default(),
+            script: ScriptEditor::default(),
+            report: ReportWindow::default(),
+            zoom_wizard: ZoomWizard::default(),
+            history: History::new(&proj),
+            share: ShareState::default(),
+            search: PanelSearch::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            export_job: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            dialogs: Dialogs::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            live: LiveInput::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            audio: AudioPanel::default(),
+            #[cfg(target_arch = "wasm32")]
+            web_files: WebFiles::default(),
+            proj,
+            tex: None,
+            last_frame: None,
+            ab: AbCompare::default(),
+            explorer: Explorer::default(),
+            thumbnails: Thumbnails::default(),
+            settings_saved: settings.clone(),
+            settings,
+            show_settings: false,
+            toasts,
+            viewport: Vec2::new(1280.0, 720.0),
+            draft_preview: false,
+            stylize_preview: false,
+            interaction: Interaction::default(),
+            budget: BudgetPreview::default(),
+            last_update: Instant::now(),
+            renderer: Box::new(CpuRenderer),
+            renderer_backend: RenderBackend::Cpu,
+            demotion_shown: false,
+            render_stats: None,
+            reference_orbits: OrbitCache::default(),
+            custom_formula: FormulaCache::default(),
+            preview_pass: None,
+            preview: PreviewState::default(),
+            diagnostics: DiagnosticsPanel::default(),
+            region: RegionPreview::default(),
+            quality: QualityEditor::default(),
+            annotations: AnnotationsPanel::default(),
+            julia: JuliaViewport::default(),
+            recenter: None,
+            non_finite: Vec::new(),
+            tour: TourState::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            stats_log: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            project_file: None,
+        }
+    }
+
+    /// Recreates the renderer once the project's backend has changed, switching back to
+    /// the CPU if the new backend is unavailable.
+    fn sync_renderer(&mut self) {
+        if self.renderer_backend == self.proj.render_backend {
+            return;
+        }
+        match create_renderer(self.proj.render_backend) {
+            Ok(renderer) => self.renderer = renderer,
+            Err(err) => {
+                self.toasts.error(format!(
+                    "{} init failed: {err}. Using the CPU.",
+                    self.proj.render_backend.label()
+                ));
+                self.proj.render_backend = RenderBackend::Cpu;
+                self.renderer = Box::new(CpuRenderer);
+            }
+        }
+        self.renderer_backend = self.proj.render_backend;
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.preview.backend = self.renderer_backend;
+            self.julia.preview.backend = self.renderer_backend;
+        }
+    }
+
+    /// Puts up a sticky toast once the renderer has fallen back to the CPU for the rest of
+    /// the session. The project's backend is left alone so saving keeps the choice.
+    fn watch_demotion(&mut self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        let demotion = self.renderer.demotion().or(self.preview.demotion());
+        #[cfg(target_arch = "wasm32")]
+        let demotion = self.renderer.demotion();
+        match demotion {
+            Some(demotion) if !self.demotion_shown => {
+                self.toasts.push_sticky(
+                    ToastKind::Error,
+                    format!(
+                        "{} rendering failed {} times in a row ({}). Using the CPU for the \
+                         rest of this session.",
+                        demotion.renderer, demotion.failures, demotion.error
+                    ),
+                    vec![ToastAction::RetryGpu],
+                );
+                self.demotion_shown = true;
+            }
+            Some(_) => {}
+            None => self.demotion_shown = false,
+        }
+    }
+
+    /// Gives the demoted GPU renderer another chance.
+    fn retry_gpu(&mut self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        self.preview.retry_primary();
+        match self.renderer.retry_primary() {
+            Ok(()) => self.toasts.info("Retrying the GPU."),
+            // Still demoted: the next frame shows the new error.
+            Err(_) => self.demotion_shown = false,
+        }
+    }
+
+    /// Appends `stats` to the stats log chosen in Preferences, (re)opening it when the path
+    /// changed. A log that cannot be opened or written is switched off with a warning.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn log_render_stats(&mut self, stats: &RenderStats) {
+        let Some(path) = &self.settings.stats_log else {
+            self.stats_log = None;
+            return;
+        };
+        if self.stats_log.as_ref().map(|(open, _)| open) != Some(path) {
+            self.stats_log = match StatsLog::open(path) {
+                Ok(log) => Some((path.clone(), log)),
+                Err(e) => {
+                    self.toasts
+                        .warning(format!("Could not open {}: {e}", path.display()));
+                    self.settings.stats_log = None;
+                    return;
+                }
+            };
+        }
+        if let Some((path, log)) = &mut self.stats_log {
+            if let Err(e) = log.record(stats) {
+                self.toasts.warning(format!(
+                    "Stopped logging render stats to {}: {e}",
+                    path.display()
+                ));
+                self.settings.stats_log = None;
+                self.stats_log = None;
+            }
+        }
+    }
+
+    /// The evaluated frame at the preview's quality. Frame-locked previews show export
+    /// frames, which already have the export's. Either way one sample per pixel; only
+    /// exports supersample.
+    fn preview_frame(&self) -> EvaluatedFrame {
+        let mut frame = self.eval.clone();
+        if !self.proj.anim.frame_locked {
+            let index = frame_at(self.proj.anim.t, self.proj.export.fps);
+            self.proj
+                .quality
+                .apply(&mut frame.fractal, self.proj.seed, index);
+        }
+        frame.fractal.supersample = 1;
+        frame
+    }
+
+    /// `params` with a reference orbit for `cam` from the cache, if the preview renders
+    /// them with perturbation.
+    fn with_reference_orbit<'a>(
+        &mut self,
+        params: &'a FractalParams,
+        cam: &Camera,
+    ) -> Cow<'a, FractalParams> {
+        if !needs_reference(params, self.renderer.precision()) {
+            return Cow::Borrowed(params);
+        }
+        let reference = self
+            .reference_orbits
+            .get_or_compute(OrbitKey::new(params, cam), |_| {
+                Ok::<_, Infallible>(Arc::new(ReferenceOrbit::compute(params, cam)))
+            })
+            .expect("computing a reference orbit cannot fail");
+        let mut params = params.clone();
+        params.reference = Some(reference.clone());
+        Cow::Owned(params)
+    }
+
+    /// The main preview in the space left in `ui`, with its overlays and navigation.
+    /// Returns the fraction of its resolution it rendered at.
+    fn main_viewport(&mut self, ui: &mut egui::Ui) -> f32 {
+        let avail = ui.available_size();
+        self.viewport = avail.max(Vec2::splat(128.0));
+        // In budget mode a changing view renders whole frames as fast as the budget
+        // allows; otherwise it drops to a quarter of the resolution until it settles.
+        self.budget.controller.target_ms = self.settings.frame_budget_ms;
+        let playing = self.proj.anim.playing;
+        let busy = self.draft_preview || playing;
+        let interacting = self.interaction.update(ui.ctx(), &self.eval, busy);
+        let budgeted = self.settings.budget_preview && interacting;
+        let draft = if budgeted {
+            self.budget.controller.scale()
+        } else if interacting && !playing {
+            DRAFT_SCALE
+        } else {
+            1.0
+        };
+        let mut preview_scale = self.settings.preview_scale.clamp(0.1, 1.0) * draft;
+        // A stylized preview renders one pixel per block of the export's pixels, scaled
+        // to the view, and is reduced to its colors as a whole frame.
+        let stylized = (self.stylize_preview
+            && self.proj.export.stylize.enabled
+            && self.preview_pass.is_none())
+        .then_some(self.proj.export.stylize);
+        if let Some(stylize) = &stylized {
+            let (width, _) = stylize.render_size((self.proj.export.width, 1));
+            preview_scale = preview_scale.min(width as f32 / avail.x.max(128.0));
+        }
+        let size = (
+            (avail.x.max(128.0) * preview_scale) as u32,
+            (avail.y.max(128.0) * preview_scale) as u32,
+        );
+        // Lower preview resolutions keep the same framing by scaling pixels-per-unit too.
+        let preview_cam = Camera {
+            scale: self.eval.camera.scale * f64::from(preview_scale),
+            ..self.eval.camera.clone()
+        };
+        self.sync_renderer();
+        // Tiles under the cursor render first; during playback, the middle of the view.
+        let origin = ui.available_rect_before_wrap().min;
+        let focus = ui
+            .ctx()
+            .pointer_hover_pos()
+            .filter(|_| !self.proj.anim.playing)
+            .map(|pos| pos2(0.0, 0.0) + (pos - origin) * preview_scale)
+            .unwrap_or(pos2(size.0 as f32 / 2.0, size.1 as f32 / 2.0));
+        let shown = self.preview_frame();
+        let capped = budgeted.then(|| self.budget.capped(&shown.fractal));
+        let fractal = capped.as_ref().unwrap_or(&shown.fractal);
+        let fractal = &*self.with_reference_orbit(fractal, &preview_cam);
+        let rendered = self.preview.render(
+            ui.ctx(),
+            size,
+            fractal,
+            &preview_cam,
+            self.renderer.as_mut(),
+            Steer {
+                pass: self.preview_pass,
+                focus,
+                whole: budgeted || stylized.is_some(),
+            },
+        );
+        let update = match rendered {
+            Ok(update) => update,
+            Err(err) => {
+                self.toasts.error(format!(
+                    "{} preview failed: {err}. Using the CPU.",
+                    self.renderer.name()
+                ));
+                // The last image stays up until the CPU has rendered the frame again.
+                self.proj.render_backend = RenderBackend::Cpu;
+                ui.ctx().request_repaint();
+                PreviewUpdate::Tiles(Vec::new())
+            }
+        };
+        if let PreviewUpdate::Frame(_, stats) = &update {
+            if budgeted {
+                self.budget.observe(stats, self.interaction.moved);
+            }
+            self.budget.shown = budgeted.then_some((fractal.max_iter, draft));
+            #[cfg(not(target_arch = "wasm32"))]
+            self.log_render_stats(stats);
+            self.render_stats = Some(stats.clone());
+        }
+        let image_size = [size.0 as usize, size.1 as usize];
+        let tex = self.tex.get_or_insert_with(|| {
+            ui.ctx().load_texture(
+                "preview",
+                ColorImage::new(image_size, Color32::TRANSPARENT),
+                egui::TextureOptions::LINEAR,
+            )
+        });
+        match update {
+            PreviewUpdate::Frame(mut pixels, _) => {
+                self.diagnostics.frame =
+                    FrameStats::of_rgba(&pixels).filter(|_| self.preview_pass.is_none());
+                let filter = match &stylized {
+                    Some(stylize) => {
+                        stylize.quantize(&mut pixels, size.0, fractal);
+                        egui::TextureOptions::NEAREST
+                    }
+                    None => egui::TextureOptions::LINEAR,
+                };
+                let color_image = ColorImage::from_rgba_unmultiplied(image_size, &pixels);
+                tex.set(color_image.clone(), filter);
+                self.last_frame = Some(color_image);
+            }
+            PreviewUpdate::Tiles(tiles) => {
+                self.diagnostics.frame = None;
+                // A frame at another resolution than the one shown, such as the sharp
+                // frame after a draft, replaces it once done rather than filling in over
+                // a blank texture.
+                if tex.size() == image_size {
+                    upload_tiles(tex, image_size, tiles);
+                }
+            }
+        }
+        let image = ui.image((
+            tex.id(),
+            Vec2::new(size.0 as f32, size.1 as f32) / preview_scale,
+        ));
+        annotations_overlay(
+            ui,
+            image.rect,
+            &self.proj.annotations,
+            self.proj.anim.t,
+            &self.eval.camera,
+        );
+        if let Some(stats) = self
+            .render_stats
+            .as_ref()
+            .filter(|_| self.settings.show_render_stats)
+        {
+            render_stats_overlay(ui, image.rect, stats);
+        }
+        if self.settings.show_zoom_readout {
+            // The evaluated camera, so an endless zoom reads what is on screen.
+            let readout = ZoomReadout::new(
+                self.eval.fractal.kind,
+                &self.eval.camera,
+                image.rect.width(),
+            );
+            zoom_readout_overlay(ui, image.rect, &readout);
+        }
+        let found = diagnose(
+            &self.eval.fractal,
+            &self.eval.camera,
+            image.rect.width(),
+            self.diagnostics.frame.as_ref(),
+        );
+        if let Some(diagnosis) = diagnostics_overlay(ui, image.rect, &mut self.diagnostics, &found)
+        {
+            diagnosis.fix(&mut self.proj, image.rect.width());
+        }
+        // Registered before the overlay so trap handles win where they overlap. The
+        // wizard's starting view is what moves while it is open.
+        let camera = match &mut self.zoom_wizard.start {
+            Some(start) => start,
+            None => &mut self.proj.camera,
+        };
+        let navigating = viewport_navigation(
+            ui,
+            Id::new("viewport_nav"),
+            image.rect,
+            &self.eval.camera,
+            camera,
+        );
+        zoom_destination_overlay(ui, image.rect, &self.eval.camera, &mut self.zoom_wizard);
+        let target = self.proj.anim.zoom_forever.and_then(|zoom| zoom.target);
+        if let Some(target) = zoom_target_overlay(
+            ui,
+            image.rect,
+            &self.eval.camera,
+            &mut self.timeline.picking_zoom_target,
+            target,
+        ) {
+            self.retarget_zoom(target);
+        }
+        let dragging = orbit_trap_overlay(
+            ui,
+            image.rect,
+            &self.eval.camera,
+            &mut self.proj.fractal.orbit,
+        ) || navigating;
+        if self.proj.julia_view.enabled {
+            self.julia.marker_dragging = julia_marker_overlay(
+                ui,
+                image.rect,
+                &self.eval.camera,
+                &mut self.proj.julia_view.c,
+            );
+        }
+        let idle = !dragging && !self.proj.anim.playing && !self.preview.in_progress();
+        let (proj, eval) = (&self.proj, &self.eval);
+        if let Err(err) = region_preview_overlay(
+            ui,
+            image.rect,
+            &mut self.region,
+            || region_frame(proj, eval),
+            self.renderer.as_mut(),
+            idle,
+        ) {
+            self.toasts.error(format!(
+                "{} region render failed: {err}",
+                self.renderer.name()
+            ));
+            self.region.clear();
+        }
+        if self.draft_preview && !dragging {
+            ui.ctx().request_repaint();
+        }
+        self.draft_preview = dragging;
+        preview_scale
+    }
+
+    /// Samples the animation at the current time and runs the project's script on top.
+    /// While the deep zoom wizard is open, its planned movie is shown instead.
+    fn evaluate(&mut self) {
+        let fixed = repair_project(&mut self.proj);
+        if !fixed.is_empty() {
+            self.toasts.warning(non_finite_warning(&fixed));
+        }
+        let planned = self.zoom_wizard.preview(&self.proj);
+        let proj = planned.as_ref().unwrap_or(&self.proj);
+        let time = if proj.anim.frame_locked {
+            let frame = frame_at(proj.anim.t, proj.export.fps);
+            self.eval = evaluate_export_frame(proj, frame);
+            frame_time(frame, proj.export.fps)
+        } else {
+            self.eval = evaluate_frame(proj, proj.anim.t, EvalTarget::Preview);
+            self.eval
+                .fractal
+                .apply_auto_iter(&self.eval.camera, self.viewport.x);
+            proj.anim.t
+        };
+        self.script
+            .apply(proj, time, &mut self.eval, &mut self.toasts);
+        if let Some((transition, started)) = &self.recenter {
+            let elapsed = started.elapsed().as_secs_f32();
+            if transition.is_done(elapsed) {
+                self.recenter = None;
+            } else {
+                self.eval.camera.center = transition.camera_at(elapsed).center;
+            }
+        }
+        self.custom_formula.attach(&mut self.eval.fractal);
+        let repairs = repair_frame(&mut self.eval, &self.proj);
+        let params: Vec<String> = repairs.iter().map(|r| r.param.clone()).collect();
+        if !repairs.is_empty() && params != self.non_finite {
+            self.toasts.warning(non_finite_warning(&repairs));
+        }
+        self.non_finite = params;
+    }
+
+    /// Restarts the endless zoom centered on `target` from the view on screen, gliding
+    /// the preview over rather than cutting.
+    fn retarget_zoom(&mut self, target: Complex64) {
+        let from = self.eval.camera.clone();
+        let Some(zoom) = &mut self.proj.anim.zoom_forever else {
+            return;
+        };
+        zoom.retarget(target, from.scale);
+        self.proj.camera.center = target;
+        self.proj.anim.t = 0.0;
+        let to = Camera {
+            center: target,
+            ..from.clone()
+        };
+        self.recenter = Some((
+            CameraTransition::new(from, to, RECENTER_SECONDS),
+            Instant::now(),
+        ));
+    }
+
+    /// Applies the result of a finished file dialog to whatever it was opened for.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn poll_dialogs(&mut self) {
+        let Some((purpose, path)) = self.dialogs.poll() else {
+            return;
+        };
+        let settings = &mut self.settings;
+        let result = match purpose {
+            DialogPurpose::SaveProject => {
+                remember_dir(&mut settings.last_project_dir, &path);
+                let mut saved = self.proj.clone();
+                saved.thumbnail = project_thumbnail(&saved, &mut CpuRenderer).ok();
+                save_project(&saved, &path)
+                    .map(|()| self.set_project_file(path.clone()))
+                    .map_err(|e| e.at(&path))
+            }
+            DialogPurpose::OpenProject => {
+                remember_dir(&mut settings.last_project_dir, &path);
+                load_project_repaired(&path)
+                    .map(|(p, repairs)| {
+                        if !repairs.is_empty() {
+                            self.toasts.warning(format!(
+                                "{}: {}",
+                                path.display(),
+                                non_finite_warning(&repairs)
+                            ));
+                        }
+                        self.proj = p;
+                        self.set_project_file(path.clone());
+                    })
+                    .map_err(|e| e.at(&path))
+            }
+            DialogPurpose::SavePalette => {
+                remember_dir(&mut settings.last_palette_dir, &path);
+                save_palette(&self.proj.fractal.palette, &path).map_err(|e| e.at(&path))
+            }
+            DialogPurpose::LoadPalette => {
+                remember_dir(&mut settings.last_palette_dir, &path);
+                load_palette(&path)
+                    .map(|mut pal| {
+                        tidy_palette(&mut pal);
+                        self.proj.fractal.palette = pal;
+                    })
+                    .map_err(|e| e.at(&path))
+            }
+            DialogPurpose::ExportPath => {
+                remember_dir(&mut settings.last_export_dir, &path);
+                self.proj.export.out_path = path.clone();
+                Ok(())
+            }
+            DialogPurpose::SnapshotFolder => {
+                settings.snapshot_dir = Some(path.clone());
+                Ok(())
+            }
+            DialogPurpose::ImportLocation { palette } => {
+                remember_dir(&mut settings.last_project_dir, &path);
+                load_kfr(&path)
+                    .map(|loc| {
+                        for warning in loc.apply(&mut self.proj, palette) {
+                            self.toasts
+                                .warning(format!("{}: {warning}", path.display()));
+                        }
+                    })
+                    .map_err(|e| format!("{}: {e}", path.display()))
+            }
+            DialogPurpose::LoadAudio => {
+                remember_dir(&mut settings.last_project_dir, &path);
+                self.audio.load(path.clone(), self.proj.export.fps);
+                Ok(())
+            }
+            DialogPurpose::BakeAnimation => {
+                remember_dir(&mut settings.last_export_dir, &path);
+                bake_animation(&self.proj, self.proj.export.fps)
+                    .and_then(|baked| baked.save(&path))
+                    .map_err(|e| format!("{}: {e}", path.display()))
+            }
+            DialogPurpose::ContactSheet => {
+                remember_dir(&mut settings.last_export_dir, &path);
+                if self.export_job.is_some() {
+                    Err("Wait for the running export to finish".to_owned())
+                } else {
+                    self.export_job = Some(ExportJob::contact_sheet(
+                        self.proj.clone(),
+                        path,
+                        settings.tile_tuning.clone(),
+                    ));
+                    Ok(())
+                }
+            }
+            DialogPurpose::ImportAnimation => {
+                remember_dir(&mut settings.last_export_dir, &path);
+                import_baked_animation(&mut self.proj, &path)
+                    .map(|warnings| {
+                        for warning in warnings {
+                            self.toasts
+                                .warning(format!("{}: {warning}", path.display()));
+                        }
+                    })
+                    .map_err(|e| format!("{}: {e}", path.display()))
+            }
+        };
+        if let Err(e) = result {
+            self.toasts.error(e);
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn poll_export(&mut self, ctx: &egui::Context) {
+        let Some(job) = &self.export_job else {
+            return;
+        };
+        let result = match job.result.try_recv() {
+            Ok(result) => result,
+            Err(mpsc::TryRecvError::Empty) => {
+                ctx.request_repaint_after(Duration::from_millis(100));
+                return;
+            }
+            Err(mpsc::TryRecvError::Disconnected) => Err(ExportError::Worker),
+        };
+        // Calibrations stick even when the export itself failed.
+        self.settings.tile_tuning = job
+            .progress
+            .tile_sizes
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        let name = job.name;
+        self.export_job = None;
+        match result {
+            Ok(report) => {
+                let text = report.to_string();
+                desktop_notify(&format!("{name} finished"), &text);
+                self.toasts.push_with_actions(
+                    ToastKind::Info,
+                    format!("{name} finished: {text}"),
+                    vec![
+                        ToastAction::Open(report.path.clone()),
+                        ToastAction::Reveal(report.path),
+                    ],
+                );
+            }
+            Err(ExportError::Cancelled) => self.toasts.info(format!("{name} cancelled")),
+            Err(e) => {
+                desktop_notify(&format!("{name} failed"), &e.to_string());
+                self.toasts.error(format!("{name} failed: {e}"));
+            }
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn take_snapshot(&mut self, action: SnapshotAction) {
+        let size = if self.settings.snapshot_use_view_size {
+            (self.viewport.x as u32, self.viewport.y as u32)
+        } else {
+            (
+                self.settings.snapshot_width.max(1),
+                self.settings.snapshot_height.max(1),
+            )
+        };
+        self.sync_renderer();
+        let pixels = match render_still(
+            &self.preview_frame(),
+            self.renderer.as_mut(),
+            &self.proj.quality,
+            size,
+            self.viewport.x,
+        ) {
+            Ok(pixels) => pixels,
+            Err(e) => return self.toasts.error(format!("Snapshot failed: {e}")),
+        };
+        match action {
+            SnapshotAction::Copy => match copy_image_to_clipboard(size, pixels) {
+                Ok(()) => self.toasts.info(format!(
+                    "Copied {}×{} image to the clipboard",
+                    size.0, size.1
+                )),
+                Err(e) => self.toasts.error(format!("Clipboard copy failed: {e}")),
+            },
+            SnapshotAction::Save => {
+                match save_snapshot_png(&self.settings.snapshot_dir(), size, pixels) {
+                    Ok(path) => self.toasts.info(format!("Saved {}", path.display())),
+                    Err(e) => self.toasts.error(format!("Snapshot failed: {e}")),
+                }
+            }
+        }
+    }
+
+    /// Times tile sizes on the current export frame at the export size and remembers the
+    /// fastest for auto-tiled exports with this renderer.
+    fn calibrate_tiles(&mut self) {
+        self.sync_renderer();
+        let proj = self.proj.authored();
+        let size = (proj.export.width, proj.export.height);
+        let frame = evaluate_export_frame(&proj, frame_at(proj.anim.t, proj.export.fps));
+        match calibrate_tile_size(size, &frame.fractal, &frame.camera, self.renderer.as_mut()) {
+            Ok(found) => {
+                self.settings.tile_tuning.learn(&found);
+                self.toasts.info(format!("Calibrated {found}"));
+            }
+            Err(e) => self.toasts.error(format!("Calibration failed: {e}")),
+        }
+    }
+
+    /// Save/load/import buttons backed by native file dialogs.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn file_buttons(&mut self, ui: &mut egui::Ui) {
+        let dir = &self.settings.last_project_dir;
+        if ui.button("Save JSON").clicked() {
+            self.dialogs.save(
+                ui.ctx(),
+                DialogPurpose::SaveProject,
+                dialog_in(dir)
+                    .add_filter("Project", &["json"])
+                    .set_file_name("project.json"),
+            );
+        }
+        if ui.button("Save .mahproj").clicked() {
+            self.dialogs.save(
+                ui.ctx(),
+                DialogPurpose::SaveProject,
+                dialog_in(dir)
+                    .add_filter("Matterhorn", &["mahproj", "toml"])
+                    .set_file_name("project.mahproj"),
+            );
+        }
+        if ui.button("Load Project").clicked() {
+            self.dialogs.pick(
+                ui.ctx(),
+                DialogPurpose::OpenProject,
+                dialog_in(dir).add_filter("Project", &["json", "mahproj", "toml"]),
+            );
+        }
+        ui.menu_button("Import", |ui| {
+            for (label, palette) in [
+                ("Kalles Fraktaler location (.kfr)…", true),
+                ("Kalles Fraktaler location, keep palette…", false),
+            ] {
+                if ui.button(label).clicked() {
+                    self.dialogs.pick(
+                        ui.ctx(),
+                        DialogPurpose::ImportLocation { palette },
+                        dialog_in(dir).add_filter("Kalles Fraktaler", &["kfr"]),
+                    );
+                    ui.close_menu();
+                }
+            }
+            if ui.button("Baked animation (.csv, .json)…").clicked() {
+                self.dialogs.pick(
+                    ui.ctx(),
+                    DialogPurpose::ImportAnimation,
+                    dialog_in(&self.settings.last_export_dir)
+                        .add_filter("Baked animation", &["csv", "json"]),
+                );
+                ui.close_menu();
+            }
+        });
+    }
+
+    /// In the browser, projects are downloaded and uploaded as JSON.
+    #[cfg(target_arch = "wasm32")]
+    fn file_buttons(&mut self, ui: &mut egui::Ui) {
+        if ui.button("Download project").clicked() {
+            match serde_json::to_string_pretty(&self.proj) {
+                Ok(json) => self
+                    .web_files
+                    .download(ui.ctx(), "project.json", json.into_bytes()),
+                Err(e) => self
+                    .toasts
+                    .error(format!("Could not save the project: {e}")),
+            }
+        }
+        if ui.button("Upload project").clicked() {
+            self.web_files.upload(
+                ui.ctx(),
+                UploadPurpose::Project,
+                rfd::AsyncFileDialog::new().add_filter("Project", &["json", "mahproj", "toml"]),
+            );
+        }
+        ui.menu_button("Import", |ui| {
+            for (label, palette) in [
+                ("Kalles Fraktaler location (.kfr)…", true),
+                ("Kalles Fraktaler location, keep palette…", false),
+            ] {
+                if ui.button(label).clicked() {
+                    self.web_files.upload(
+                        ui.ctx(),
+                        UploadPurpose::Location { palette },
+                        rfd::AsyncFileDialog::new().add_filter("Kalles Fraktaler", &["kfr"]),
+                    );
+                    ui.close_menu();
+                }
+            }
+        });
+    }
+
+    /// Applies a finished upload to whatever it was picked for.
+    #[cfg(target_arch = "wasm32")]
+    fn poll_uploads(&mut self) {
+        for failure in self.web_files.take_failed_downloads() {
+            self.toasts.error(failure);
+        }
+        let Some((purpose, name, data)) = self.web_files.poll() else {
+            return;
+        };
+        let text = String::from_utf8_lossy(&data);
+        let result = match purpose {
+            UploadPurpose::Project => project_from_str_repaired(&text, Path::new(&name))
+                .map(|(p, repairs)| {
+                    if !repairs.is_empty() {
+                        self.toasts
+                            .warning(format!("{name}: {}", non_finite_warning(&repairs)));
+                    }
+                    self.proj = p;
+                })
+                .map_err(|e| e.at(Path::new(&name))),
+            UploadPurpose::Location { palette } => parse_kfr(&text)
+                .map(|loc| {
+                    for warning in loc.apply(&mut self.proj, palette) {
+                        self.toasts.warning(format!("{name}: {warning}"));
+                    }
+                })
+                .map_err(|e| format!("{name}: {e}")),
+        };
+        if let Err(e) = result {
+            self.toasts.error(e);
+        }
+    }
+
+    /// Playback time, or with Frame lock on the export frame, which can be typed to jump
+    /// straight to it.
+    fn time_readout(&mut self, ui: &mut egui::Ui) {
+        let anim = &mut self.proj.anim;
+        ui.checkbox(&mut anim.frame_locked, "Frame lock").on_hover_text(
+            "Step through export frames one at a time, showing each exactly as the export renders it",
+        );
+        if !anim.frame_locked {
+            ui.label(format!("t = {:.2}s", anim.t));
+            return;
+        }
+        let fps = self.proj.export.fps;
+        let last = frame_at(anim.duration, fps);
+        let mut frame = frame_at(anim.t, fps);
+        let response = ui.add(
+            egui::DragValue::new(&mut frame)
+                .clamp_range(0..=last)
+                .prefix("frame ")
+                .suffix(format!(" / {last}")),
+        );
+        if response.changed() {
+            anim.go_to_frame(frame, fps);
+        }
+        response.on_hover_text(format!("{:.3}s at {fps} fps", frame_time(frame, fps)));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn export_button(&mut self, ui: &mut egui::Ui) {
+        if let Some(job) = &self.export_job {
+            let (done, total) = job.progress.fraction();
+            ui.add(
+                egui::ProgressBar::new(done as f32 / total.max(1) as f32)
+                    .desired_width(140.0)
+                    .text(format!("{} {done}/{total}", job.name)),
+            );
+            if ui.button("Cancel").clicked() {
+                job.progress.cancel.store(true, AtomicOrdering::Relaxed);
+            }
+        } else if ui.button("Export Video").clicked() {
+            // The export appends to the file through its own handle.
+            let stats_log = self.settings.stats_log.as_deref().and_then(|path| {
+                StatsLog::open(path)
+                    .map_err(|e| {
+                        self.toasts.warning(format!(
+                            "Not logging render stats: could not open {}: {e}",
+                            path.display()
+                        ))
+                    })
+                    .ok()
+            });
+            self.export_job = Some(ExportJob::start(
+                self.proj.clone(),
+                self.settings.ffmpeg_path.clone(),
+                stats_log,
+                self.settings.tile_tuning.clone(),
+            ));
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn export_button(&mut self, ui: &mut egui::Ui) {
+        ui.add_enabled(false, egui::Button::new("Export Video"))
+            .on_disabled_hover_text(WEB_EXPORT_NOTE);
+    }
+
+    /// Renders the current view at the viewport size and downloads it as a PNG.
+    #[cfg(target_arch = "wasm32")]
+    fn download_png(&mut self, ctx: &egui::Context) {
+        let size = (self.viewport.x as u32, self.viewport.y as u32);
+        self.sync_renderer();
+        let png = render_still(
+            &self.preview_frame(),
+            self.renderer.as_mut(),
+            &self.proj.quality,
+            size,
+            self.viewport.x,
+        )
+        .map_err(|e| e.to_string())
+        .and_then(|pixels| encode_png(size, pixels));
+        match png {
+            Ok(png) => {
+                self.web_files
+                    .download(ctx, &format!("snapshot_{}.png", utc_timestamp()), png)
+            }
+            Err(e) => self.toasts.error(format!("Snapshot failed: {e}")),
+        }
+    }
+}
+
+impl App for MatterhornApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        let dt = self.last_update.elapsed().as_secs_f32();
+        self.last_update = Instant::now();
+        if !self.proj.anim.frame_locked {
+            self.proj.anim.advance(dt);
+        } else if !self.preview.in_progress() {
+            // One export frame per finished preview frame, however long it took.
+            self.proj.anim.step_frame(self.proj.export.fps);
+        }
+        if self.proj.anim.playing || self.recenter.is_some() {
+            ctx.request_repaint();
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.live.sync(ctx, &self.settings.live_input);
+            self.live
+                .poll(&mut self.proj, &mut self.settings.live_input);
+        }
+        self.evaluate();
+        #[cfg(not(target_arch = "wasm32"))]
+        let mut snapshot_action = None;
+        #[cfg(not(target_arch = "wasm32"))]
+        let copy_shortcut = egui::KeyboardShortcut::new(
+            egui::Modifiers::COMMAND | egui::Modifiers::SHIFT,
+            egui::Key::C,
+        );
+        #[cfg(not(target_arch = "wasm32"))]
+        if ctx.input_mut(|i| i.consume_shortcut(&copy_shortcut)) {
+            snapshot_action = Some(SnapshotAction::Copy);
+        }
+        let undo_shortcut = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Z);
+        let redo_shortcut = egui::KeyboardShortcut::new(
+            egui::Modifiers::COMMAND | egui::Modifiers::SHIFT,
+            egui::Key::Z,
+        );
+        // Redo first: consuming Ctrl+Z would also match Ctrl+Shift+Z. Text fields keep their own undo.
+        if !ctx.wants_keyboard_input() {
+            if ctx.input_mut(|i| i.consume_shortcut(&redo_shortcut)) {
+                self.history.redo(&mut self.proj);
+            } else if ctx.input_mut(|i| i.consume_shortcut(&undo_shortcut)) {
+                self.history.undo(&mut self.proj);
+            }
+        }
+
+        let top = egui::TopBottomPanel::top("top").show(ctx, |ui| {
+            ui.horizontal_wrapped(|ui| {
+                ui.heading("Matterhorn AH");
+                ui.separator();
+                if ui
+                    .button(if self.proj.anim.playing {
+                        "Pause"
+                    } else {
+                        "Play"
+                    })
+                    .clicked()
+                {
+                    self.proj.anim.playing = !self.proj.anim.playing;
+                }
+                if ui.button("Restart").clicked() {
+                    self.proj.anim.t = 0.0;
+                }
+                self.time_readout(ui);
+                ui.separator();
+                self.file_buttons(ui);
+                share_menu(
+                    ui,
+                    &mut self.share,
+                    &mut self.proj,
+                    &self.eval,
+                    &mut self.toasts,
+                );
+                self.export_button(ui);
+                if ui
+                    .add_enabled(!self.zoom_wizard.is_open(), egui::Button::new("Deep zoom"))
+                    .on_hover_text("Plan a zoom movie down to a destination")
+                    .clicked()
+                {
+                    self.zoom_wizard.open(&self.eval.camera);
+                }
+                if ui.button("Script").clicked() {
+                    self.script.open = !self.script.open;
+                }
+                if ui.button("Annotations").clicked() {
+                    self.annotations.open = !self.annotations.open;
+                }
+                if ui
+                    .button("Report")
+                    .on_hover_text("What the export will cost to render, and what may go wrong")
+                    .clicked()
+                {
+                    self.report
+                        .toggle(&self.proj, self.preview.last_iterated.as_ref());
+                }
+                ui.toggle_value(&mut self.proj.julia_view.enabled, "Julia view")
+                    .on_hover_text("Show the Julia set for a point marked on the main view");
+                if ui.button("Preferences").clicked() {
+                    self.show_settings = !self.show_settings;
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                if ui.button("Audio").clicked() {
+                    self.audio.open = !self.audio.open;
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                if ui.button("Live input").clicked() {
+                    self.live.open = !self.live.open;
+                }
+                ui.separator();
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(action) =
+                    snapshot_ui(ui, &mut self.settings, &mut self.dialogs, &copy_shortcut)
+                {
+                    snapshot_action = Some(action);
+                }
+                #[cfg(target_arch = "wasm32")]
+                if ui.button("Download PNG").clicked() {
+                    self.download_png(ui.ctx());
+                }
+                ui.separator();
+                ab_compare_ui(
+                    ui,
+                    &mut self.ab,
+                    &mut self.proj,
+                    &self.eval,
+                    self.last_frame.as_ref(),
+                );
+                ui.separator();
+                ui.label("Backend:");
+                let backend_before = self.proj.render_backend;
+                ui.selectable_value(
+                    &mut self.proj.render_backend,
+                    RenderBackend::Cpu,
+                    RenderBackend::Cpu.label(),
+                );
+                #[cfg(feature = "gpu")]
+                ui.selectable_value(
+                    &mut self.proj.render_backend,
+                    RenderBackend::Gpu,
+                    RenderBackend::Gpu.label(),
+                );
+                if self.proj.render_backend != backend_before {
+                    self.settings.default_backend = self.proj.render_backend;
+                }
+                ui.label("Precision:");
+                for precision in Precision::ALL {
+                    let how = match precision {
+                        Precision::Perturbation => "Iterate one reference orbit at full precision \
+                            and each pixel as an f64 delta from it (Mandelbrot; other formulas \
+                            use f64)"
+                            .to_string(),
+                        _ => format!("Iterate in {}", precision.label()),
+                    };
+                    ui.selectable_value(&mut self.proj.precision, precision, precision.label())
+                        .on_hover_text(format!(
+                            "{how}, crisp to a scale of about {:.0e}",
+                            precision.zoom_limit()
+                        ));
+                }
+                #[cfg(feature = "gpu")]
+                if self.proj.render_backend == RenderBackend::Gpu
+                    && self.proj.precision > Precision::F32
+                {
+                    ui.colored_label(ui.visuals().warn_fg_color, "⚠")
+                        .on_hover_text(format!(
+                            "The GPU renders in f32; use the CPU for {} detail",
+                            self.proj.precision.label()
+                        ));
+                }
+                ui.separator();
+                egui::ComboBox::from_id_source("preview_pass")
+                    .selected_text(self.preview_pass.map_or("Beauty", |p| p.label()))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.preview_pass, None, "Beauty");
+                        for pass in AovPass::ALL {
+                            ui.selectable_value(&mut self.preview_pass, Some(pass), pass.label());
+                        }
+                    })
+                    .response
+                    .on_hover_text("Preview a compositing pass");
+                region_preview_toolbar(ui, &mut self.region);
+                ui.separator();
+                quality_combo(
+                    ui,
+                    "preview_quality",
+                    &mut self.proj.quality,
+                    &self.settings.quality_profiles,
+                )
+                .on_hover_text("Quality profile of the preview");
+                if ui
+                    .small_button("✎")
+                    .on_hover_text("Edit quality profiles")
+                    .clicked()
+                {
+                    self.quality.open(QualityTarget::Preview);
+                }
+                ui.separator();
+                budget_toolbar(ui, &mut self.settings.budget_preview, &self.budget);
+            });
+        });
+        tour_anchor(ctx, "toolbar", top.response.rect);
+
+        let side = egui::SidePanel::left("left")
+            .default_width(self.settings.side_panel_width)
+            .show(ctx, |ui| {
+                let sections = side_panel_sections(self);
+                side_panel_ui(self, ui, sections);
+            });
+        self.settings.side_panel_width = side.response.rect.width();
+        tour_anchor(ctx, "side_panel", side.response.rect);
+
+        let central = egui::CentralPanel::default().show(ctx, |ui| {
+            if ab_compare_view(ui, &mut self.ab) {
+                return;
+            }
+            // Re-evaluate so edits made in the side panel this frame are visible immediately.
+            self.evaluate();
+
+            if !self.proj.julia_view.enabled {
+                self.main_viewport(ui);
+                return;
+            }
+            let (main, julia) = viewport_split(ui, &mut self.settings.julia_split);
+            let preview_scale = ui
+                .allocate_ui_at_rect(main, |ui| self.main_viewport(ui))
+                .inner;
+            // The main view renders first; the Julia view waits for it to finish.
+            let waiting = self.preview.in_progress();
+            let shown = self.preview_frame();
+            let rendered = ui.allocate_ui_at_rect(julia, |ui| {
+                julia_viewport_ui(
+                    ui,
+                    &mut self.julia,
+                    &mut self.proj.julia_view,
+                    &shown,
+                    self.renderer.as_mut(),
+                    preview_scale,
+                    waiting,
+                )
+            });
+            if let Err(err) = rendered.inner {
+                self.toasts.error(format!(
+                    "{} Julia preview failed: {err}",
+                    self.renderer.name()
+                ));
+            }
+        });
+        tour_anchor(ctx, "viewport", central.response.rect);
+
+        let timeline = egui::TopBottomPanel::bottom("timeline")
+            .default_height(self.settings.timeline_height)
+            .show(ctx, |ui| {
+                timeline_ui(
+                    ui,
+                    &mut self.timeline,
+                    &mut self.proj,
+                    &mut self.settings.collapsed_track_groups,
+                );
+            });
+        self.settings.timeline_height = timeline.response.rect.height();
+        tour_anchor(ctx, "timeline", timeline.response.rect);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(action) = snapshot_action {
+            self.take_snapshot(action);
+        }
+        settings_window(ctx, &mut self.show_settings, &mut self.settings);
+        quality_window(
+            ctx,
+            &mut self.quality,
+            &mut self.proj,
+            &mut self.settings.quality_profiles,
+        );
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            live_input_window(ctx, &mut self.live, &mut self.settings.live_input);
+            audio_window(
+                ctx,
+                &mut self.audio,
+                &mut self.proj,
+                &mut self.dialogs,
+                &self.settings.last_project_dir,
+                &mut self.toasts,
+            );
+        }
+        script_window(ctx, &mut self.script, &mut self.proj.script);
+        report_window(
+            ctx,
+            &mut self.report,
+            &self.proj,
+            self.preview.last_iterated.as_ref(),
+            &mut self.toasts,
+        );
+        annotations_window(
+            ctx,
+            &mut self.annotations,
+            &mut self.proj,
+            &self.eval.camera,
+            self.viewport,
+        );
+        zoom_wizard_window(ctx, &mut self.zoom_wizard, &mut self.proj, self.viewport.x);
+        paste_settings_window(ctx, &mut self.share, &mut self.proj);
+        tour_ui(ctx, &mut self.tour, &mut self.settings.seen_tours);
+        self.thumbnails.end_frame(ctx);
+        set_render_threads(self.settings.threads);
+        self.watch_demotion();
+        match self.toasts.show(ctx) {
+            Some(ToastAction::RetryGpu) => self.retry_gpu(),
+            #[cfg(not(target_arch = "wasm32"))]
+            Some(ToastAction::ReloadProject(choice)) => self.resolve_reload(choice),
+            _ => {}
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.poll_export(ctx);
+            self.poll_dialogs();
+            self.poll_project_file(ctx);
+        }
+        #[cfg(target_arch = "wasm32")]
+        self.poll_uploads();
+
+        // A drag is one undo step: only record once every button is released.
+        // Knob turns on a controller likewise wait until the controls settle.
+        #[cfg(not(target_arch = "wasm32"))]
+        let live_gesture = self.live.gesture_active();
+        #[cfg(target_arch = "wasm32")]
+        let live_gesture = false;
+        if !ctx.input(|i| i.pointer.any_down()) && !live_gesture {
+            self.history.record(&self.proj);
+        }
+
+        // Persist preference changes once any drag that caused them has finished.
+        if self.settings != self.settings_saved && !ctx.input(|i| i.pointer.any_down()) {
+            if let Err(e) = self.settings.save() {
+                self.toasts
+                    .error(format!("Could not save preferences: {e}"));
+            }
+            self.settings_saved = self.settings.clone();
+        }
+    }
+
+    fn on_exit(&mut self) {
+        if self.settings != self.settings_saved {
+            if let Err(e) = self.settings.save() {
+                eprintln!("Could not save preferences: {e}");
+            }
+        }
+    }
+}
+
+/// Opens the main window; `warnings` are shown as toasts once it is up. `opened` is the
+/// file `proj` was loaded from, with the project as read from it.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn run(
+    proj: Project,
+    opened: Option<(PathBuf, Project)>,
+    settings: AppSettings,
+    warnings: Vec<String>,
+) -> eframe::Result<()> {
+    let mut toasts = Toasts::default();
+    for warning in warnings {
+        toasts.warning(warning);
+    }
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default()
+            .with_inner_size([1280.0, 840.0])
+            .with_min_inner_size([900.0, 600.0]),
+        ..Default::default()
+    };
+    eframe::run_native(
+        "Matterhorn AH",
+        options,
+        Box::new(|_cc| {
+            let mut app = MatterhornApp::new(proj, settings, toasts);
+            app.project_file = opened.map(|(path, base)| ProjectFile::new(path, base));
+            Box::new(app)
+        }),
+    )
+}
+
+/// Starts the browser build on the hosting page's canvas.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn run_web(proj: Project, settings: AppSettings) {
+    web::start(proj, settings);
+}
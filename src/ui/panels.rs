@@ -0,0 +1,553 @@
+//! Side panel widgets and the Preferences window.
+
+use super::*;
+
+fn to_color32(rgb: [f32; 3]) -> Color32 {
+    Color32::from_rgb(
+        (rgb[0] * 255.0) as u8,
+        (rgb[1] * 255.0) as u8,
+        (rgb[2] * 255.0) as u8,
+    )
+}
+
+/// Color picker for 0..1 RGB channels.
+pub(crate) fn rgb_edit_button(ui: &mut egui::Ui, rgb: &mut [f32; 3]) -> egui::Response {
+    let mut color = to_color32(*rgb);
+    let response = color_edit_button_srgba(ui, &mut color, Alpha::Opaque);
+    if response.changed() {
+        *rgb = [
+            color.r() as f32 / 255.0,
+            color.g() as f32 / 255.0,
+            color.b() as f32 / 255.0,
+        ];
+    }
+    response
+}
+
+/// Exterior mode and color, for flat or transparent backgrounds.
+pub(crate) fn exterior_ui(ui: &mut egui::Ui, fractal: &mut FractalParams) {
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_label("Exterior")
+            .selected_text(fractal.exterior_mode.label())
+            .show_ui(ui, |ui| {
+                for mode in ExteriorMode::ALL {
+                    ui.selectable_value(&mut fractal.exterior_mode, mode, mode.label());
+                }
+            })
+            .response
+            .on_hover_text(
+                "Color escaped points with the palette, a flat color, or transparency for \
+                 compositing. The orbit trap still tints them.",
+            );
+        if fractal.exterior_mode != ExteriorMode::Palette {
+            rgb_edit_button(ui, &mut fractal.exterior_color).on_hover_text(
+                if fractal.exterior_mode == ExteriorMode::Transparent {
+                    "Background for outputs without alpha"
+                } else {
+                    "Exterior color"
+                },
+            );
+        }
+    });
+}
+
+/// What escaped points are placed in the palette by.
+pub(crate) fn coloring_ui(ui: &mut egui::Ui, fractal: &mut FractalParams) {
+    egui::ComboBox::from_label("Coloring")
+        .selected_text(fractal.coloring.label())
+        .show_ui(ui, |ui| {
+            for coloring in ColoringMode::ALL {
+                ui.selectable_value(&mut fractal.coloring, coloring, coloring.label());
+            }
+        })
+        .response
+        .on_hover_text(
+            "Distance estimate colors by how many pixels away the set is, so filaments stay \
+             a crisp hairline at any zoom. It renders on the CPU. Stripe average traces \
+             stripes along the filaments from the angles the orbit passes through.",
+        );
+    if fractal.coloring == ColoringMode::DistanceEstimate && !fractal.estimates_distance() {
+        let name = formula(fractal.kind).name();
+        ui.weak(format!(
+            "{name} has no distance estimate; colored by iteration count"
+        ));
+    }
+    if fractal.coloring == ColoringMode::StripeAverage {
+        ui.add(
+            egui::Slider::new(&mut fractal.stripe_density, 1..=MAX_STRIPE_DENSITY)
+                .text("Stripe density"),
+        )
+        .on_hover_text("Stripes per turn around the origin");
+        ui.add(egui::Slider::new(&mut fractal.stripe_mix, 0.0..=1.0).text("Stripe mix"))
+            .on_hover_text("0 shows the iteration count's colors, 1 only the stripes");
+    }
+}
+
+/// Interior coloring and color, for the points that never escape.
+pub(crate) fn interior_ui(ui: &mut egui::Ui, fractal: &mut FractalParams) {
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_label("Interior")
+            .selected_text(fractal.interior_coloring.label())
+            .show_ui(ui, |ui| {
+                for coloring in InteriorColoring::ALL {
+                    ui.selectable_value(&mut fractal.interior_coloring, coloring, coloring.label());
+                }
+            })
+            .response
+            .on_hover_text(
+                "Color points inside the set with a flat color, or run the palette over where \
+                 their orbits end up",
+            );
+        if fractal.interior_coloring == InteriorColoring::Solid {
+            rgb_edit_button(ui, &mut fractal.interior_color).on_hover_text("Interior color");
+        }
+    });
+    if fractal.interior_coloring == InteriorColoring::TrapDistance && !fractal.orbit.enabled {
+        ui.weak("Enable the orbit trap to measure distances to it");
+    }
+}
+
+/// Samples per pixel in exports.
+pub(crate) fn supersample_ui(ui: &mut egui::Ui, fractal: &mut FractalParams) {
+    let label = |side: u32| match side {
+        1 => "1×".to_string(),
+        side => format!("{side}×{side}"),
+    };
+    egui::ComboBox::from_label("Supersampling")
+        .selected_text(label(fractal.supersample_side()))
+        .show_ui(ui, |ui| {
+            for side in 1..=MAX_SUPERSAMPLE {
+                ui.selectable_value(&mut fractal.supersample, side, label(side));
+            }
+        })
+        .response
+        .on_hover_text(
+            "Averages a grid of samples in every exported pixel, smoothing the edges between \
+             iteration bands. Renders take that many times longer; the preview stays at 1×.",
+        );
+}
+
+/// Dwell bands and the contour lines between them.
+pub(crate) fn dwell_bands_ui(ui: &mut egui::Ui, fractal: &mut FractalParams) {
+    ui.horizontal(|ui| {
+        ui.add(egui::DragValue::new(&mut fractal.band_count).clamp_range(0..=256))
+            .on_hover_text(
+                "Flat color bands per trip through the palette, for print-style renders. \
+                 0 keeps colors smooth.",
+            );
+        ui.label("Dwell bands");
+        ui.add_enabled_ui(fractal.band_count > 0, |ui| {
+            ui.checkbox(&mut fractal.band_contours, "Contours")
+                .on_hover_text("One-pixel lines where bands meet");
+            if fractal.band_contours {
+                rgb_edit_button(ui, &mut fractal.contour_color).on_hover_text("Contour color");
+            }
+        });
+    });
+}
+
+/// A swatch the size of a color button, showing `rgb`.
+fn color_swatch(ui: &mut egui::Ui, rgb: [f32; 3]) -> egui::Response {
+    let (rect, response) = ui.allocate_exact_size(
+        vec2(
+            ui.spacing().interact_size.y * 1.5,
+            ui.spacing().interact_size.y,
+        ),
+        Sense::hover(),
+    );
+    ui.painter().rect_filled(rect, 2.0, to_color32(rgb));
+    response
+}
+
+/// The color button of a palette stop in `editing` mode, with `tone` holding the
+/// exposure and gamma it is shown under.
+fn palette_stop_color_ui(
+    ui: &mut egui::Ui,
+    stop: &mut PaletteStop,
+    editing: PaletteEditing,
+    tone: &FractalParams,
+) {
+    match editing {
+        PaletteEditing::Stored => {
+            rgb_edit_button(ui, &mut stop.color);
+        }
+        PaletteEditing::Preview => {
+            rgb_edit_button(ui, &mut stop.color).on_hover_text("Stored color");
+            color_swatch(ui, tone.tone_map_rgb(stop.color))
+                .on_hover_text("On screen, after exposure and gamma");
+        }
+        PaletteEditing::Output => {
+            let mut shown = tone.tone_map_rgb(stop.color);
+            if rgb_edit_button(ui, &mut shown)
+                .on_hover_text(
+                    "On screen, after exposure and gamma. Colors brighter than the \
+                     exposure allows are stored as the brightest it reaches.",
+                )
+                .changed()
+            {
+                stop.color = tone.inverse_tone_map_rgb(shown);
+            }
+        }
+    }
+}
+
+/// Preset menu and whole-palette edits.
+pub(crate) fn palette_presets_ui(
+    ui: &mut egui::Ui,
+    palette: &mut Vec<PaletteStop>,
+    preset_base: &FractalParams,
+    thumbnails: &mut Thumbnails,
+) {
+    ui.horizontal(|ui| {
+        ui.menu_button("Flashy presets", |menu| {
+            for preset in palette_presets() {
+                let clicked = menu
+                    .horizontal(|ui| {
+                        let thumb = preset_thumbnail(preset_base, preset);
+                        thumbnail_button(ui, thumbnails, thumb).clicked()
+                            | ui.button(preset.name).clicked()
+                    })
+                    .inner;
+                if clicked {
+                    apply_palette_preset(palette, preset);
+                    menu.close_menu();
+                }
+            }
+        });
+        if ui.button("Flip colors").clicked() {
+            flip_palette(palette);
+        }
+        if ui.button("Cycle colors").clicked() {
+            cycle_palette_colors(palette);
+        }
+    });
+}
+
+/// Whether stop colors are picked as stored or as shown.
+pub(crate) fn palette_pickers_ui(ui: &mut egui::Ui, editing: &mut PaletteEditing) {
+    egui::ComboBox::from_label("Pickers")
+        .selected_text(editing.label())
+        .show_ui(ui, |ui| {
+            for mode in PaletteEditing::ALL {
+                ui.selectable_value(editing, mode, mode.label());
+            }
+        })
+        .response
+        .on_hover_text(
+            "Exposure and gamma apply after the palette. Show each stop as it appears \
+             on screen, or pick the on-screen color directly.",
+        );
+}
+
+/// The gradient with a handle under each stop; dragging a handle moves its stop as `drag`
+/// says.
+fn palette_gradient_bar(ui: &mut egui::Ui, palette: &mut Vec<PaletteStop>, drag: StopDrag) {
+    const BAR_HEIGHT: f32 = 18.0;
+    const HANDLE: f32 = 6.0;
+    let width = ui.available_width().max(64.0);
+    let (rect, response) =
+        ui.allocate_exact_size(vec2(width, BAR_HEIGHT + 2.0 * HANDLE), Sense::drag());
+    let bar = Rect::from_min_size(rect.min, vec2(width, BAR_HEIGHT));
+    let painter = ui.painter_at(rect.expand(1.0));
+    let columns = bar.width().ceil() as usize;
+    for (i, [r, g, b]) in bake_palette(palette, 0.0, columns.max(2))
+        .into_iter()
+        .enumerate()
+    {
+        let x = bar.left() + i as f32;
+        painter.rect_filled(
+            Rect::from_x_y_ranges(x..=x + 1.0, bar.y_range()),
+            0.0,
+            Color32::from_rgb(r, g, b),
+        );
+    }
+    let x_of = |pos: f32| bar.left() + pos * bar.width();
+
+    // The stop being dragged, tracked by index as it moves through the order.
+    let dragged_id = ui.id().with("palette_bar_drag");
+    let mut dragged = ui
+        .data(|d| d.get_temp::<Option<usize>>(dragged_id))
+        .flatten();
+    if response.drag_started() {
+        dragged = response.interact_pointer_pos().and_then(|at| {
+            (0..palette.len())
+                .map(|i| (i, (x_of(palette[i].pos) - at.x).abs()))
+                .filter(|&(_, dist)| dist <= HANDLE * 1.5)
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(i, _)| i)
+        });
+    }
+    if let (Some(index), Some(at)) = (dragged, response.interact_pointer_pos()) {
+        if response.dragged() && index < palette.len() {
+            let pos = (at.x - bar.left()) / bar.width();
+            dragged = Some(move_palette_stop(palette, index, pos, drag));
+        }
+    }
+    if !response.dragged() {
+        dragged = None;
+    }
+    ui.data_mut(|d| d.insert_temp(dragged_id, dragged));
+
+    let warn = ui.visuals().warn_fg_color;
+    for (i, stop) in palette.iter().enumerate() {
+        let x = x_of(stop.pos);
+        let tip = pos2(x, bar.bottom());
+        let outline = if dragged == Some(i) {
+            Stroke::new(2.0, ui.visuals().selection.stroke.color)
+        } else if stop_is_crowded(palette, i) {
+            Stroke::new(1.5, warn)
+        } else {
+            Stroke::new(1.0, ui.visuals().widgets.inactive.fg_stroke.color)
+        };
+        painter.add(egui::Shape::convex_polygon(
+            vec![
+                tip,
+                tip + vec2(HANDLE, 2.0 * HANDLE),
+                tip + vec2(-HANDLE, 2.0 * HANDLE),
+            ],
+            to_color32(stop.color),
+            outline,
+        ));
+    }
+    response.on_hover_text("Drag a handle to move its stop");
+}
+
+/// The gradient bar and one row per palette stop, with `tone` holding the exposure and
+/// gamma they are shown under. Stops stay sorted and apart as they are edited.
+pub(crate) fn palette_stops_ui(
+    ui: &mut egui::Ui,
+    palette: &mut Vec<PaletteStop>,
+    editing: PaletteEditing,
+    drag: &mut StopDrag,
+    tone: &FractalParams,
+) {
+    if palette.is_empty() {
+        *palette = default_palette();
+    }
+    tidy_palette(palette);
+    palette_gradient_bar(ui, palette, *drag);
+    egui::ComboBox::from_label("Dragging into a neighbor")
+        .selected_text(drag.label())
+        .show_ui(ui, |ui| {
+            for mode in StopDrag::ALL {
+                ui.selectable_value(drag, mode, mode.label());
+            }
+        });
+    // A slider dragged past a neighbor keeps driving its stop from the row it started in.
+    let row_drag_id = ui.id().with("palette_row_drag");
+    let row_drag = ui
+        .data(|d| d.get_temp::<Option<(usize, usize)>>(row_drag_id))
+        .flatten();
+    let mut moved: Option<(usize, usize, f32)> = None;
+    let mut sliding = false;
+    let mut remove_idx: Option<usize> = None;
+    for idx in 0..palette.len() {
+        let crowded = stop_is_crowded(palette, idx);
+        let stop = &mut palette[idx];
+        ui.horizontal(|ui| {
+            ui.label(format!("Stop {}", idx + 1));
+            let mut pos = stop.pos;
+            let slider = ui.add(egui::Slider::new(&mut pos, 0.0..=1.0).text("Pos"));
+            let target = match row_drag {
+                Some((row, target)) if row == idx && slider.dragged() => target,
+                _ => idx,
+            };
+            sliding |= slider.dragged();
+            if slider.changed() {
+                moved = Some((idx, target, pos));
+            }
+            if crowded {
+                ui.colored_label(ui.visuals().warn_fg_color, "↔")
+                    .on_hover_text(format!(
+                        "Moved off a neighbor's position; stops keep at least {MIN_STOP_GAP} \
+                         apart"
+                    ));
+            }
+            palette_stop_color_ui(ui, stop, editing, tone);
+            if ui.button("✕").clicked() {
+                remove_idx = Some(idx);
+            }
+        });
+    }
+    let row_drag = match moved {
+        Some((row, target, pos)) if target < palette.len() => {
+            Some((row, move_palette_stop(palette, target, pos, *drag)))
+        }
+        _ => row_drag.filter(|_| sliding),
+    };
+    ui.data_mut(|d| d.insert_temp(row_drag_id, row_drag));
+    if let Some(idx) = remove_idx {
+        if palette.len() > 2 {
+            palette.remove(idx);
+        }
+    }
+    if ui.button("Add stop").clicked() {
+        palette.push(PaletteStop {
+            pos: 0.5,
+            color: [1.0, 1.0, 1.0],
+        });
+        tidy_palette(palette);
+    }
+}
+
+/// Saving and loading `.ahpal` files.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn palette_files_ui(
+    ui: &mut egui::Ui,
+    dialogs: &mut Dialogs,
+    last_dir: &Option<PathBuf>,
+) {
+    ui.horizontal(|ui| {
+        if ui.button("Export .ahpal").clicked() {
+            dialogs.save(
+                ui.ctx(),
+                DialogPurpose::SavePalette,
+                dialog_in(last_dir)
+                    .add_filter("Palette", &["ahpal"])
+                    .set_file_name("palette.ahpal"),
+            );
+        }
+        if ui.button("Import .ahpal").clicked() {
+            dialogs.pick(
+                ui.ctx(),
+                DialogPurpose::LoadPalette,
+                dialog_in(last_dir).add_filter("Palette", &["ahpal"]),
+            );
+        }
+    });
+}
+
+/// 🔒 toggle shown beside an animatable slider; a locked track is skipped during evaluation.
+pub(crate) fn lock_toggle(ui: &mut egui::Ui, locks: &mut ParamLocks, track: TrackKind) {
+    let flag = locks.flag_mut(track);
+    let (icon, tip) = if *flag {
+        (
+            "🔒",
+            format!("{} track bypassed: the slider value is used", track.label()),
+        )
+    } else {
+        (
+            "🔓",
+            format!("Lock to bypass the {} track while tweaking", track.label()),
+        )
+    };
+    if ui
+        .selectable_label(*flag, icon)
+        .on_hover_text(tip)
+        .clicked()
+    {
+        *flag = !*flag;
+    }
+}
+
+pub(crate) fn lock_export_note(ui: &mut egui::Ui, locks: &mut ParamLocks) {
+    if !locks.any() {
+        return;
+    }
+    if !locks.apply_to_export {
+        ui.colored_label(
+            egui::Color32::from_rgb(230, 180, 80),
+            "⚠ Locked tracks still animate in exports",
+        );
+    }
+    ui.checkbox(
+        &mut locks.apply_to_export,
+        "Also bypass locked tracks in export",
+    );
+}
+
+pub(crate) fn settings_window(ctx: &egui::Context, open: &mut bool, settings: &mut AppSettings) {
+    egui::Window::new("Preferences")
+        .open(open)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.add(
+                egui::Slider::new(&mut settings.preview_scale, 0.25..=1.0).text("Preview scale"),
+            );
+            ui.checkbox(&mut settings.expert_ranges, "Expert slider ranges")
+                .on_hover_text(
+                    "Widens the side panel's sliders. Ctrl-click a slider to type any value \
+                     the renderer accepts, expert or not.",
+                );
+            ui.horizontal(|ui| {
+                ui.label("Frame budget");
+                ui.add(
+                    egui::DragValue::new(&mut settings.frame_budget_ms)
+                        .clamp_range(5.0..=200.0)
+                        .suffix(" ms"),
+                )
+                .on_hover_text(
+                    "Frame time the ⏱ Budget preview mode aims for while the view changes",
+                );
+            });
+            ui.horizontal(|ui| {
+                ui.label("Render threads");
+                ui.add(egui::DragValue::new(&mut settings.threads).clamp_range(0..=256));
+                ui.weak("0 = auto");
+            });
+            ui.horizontal(|ui| {
+                ui.label("FFmpeg");
+                let mut text = settings.ffmpeg_path.display().to_string();
+                if ui.text_edit_singleline(&mut text).changed() {
+                    settings.ffmpeg_path = PathBuf::from(text);
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Default backend");
+                ui.selectable_value(
+                    &mut settings.default_backend,
+                    RenderBackend::Cpu,
+                    RenderBackend::Cpu.label(),
+                );
+                #[cfg(feature = "gpu")]
+                ui.selectable_value(
+                    &mut settings.default_backend,
+                    RenderBackend::Gpu,
+                    RenderBackend::Gpu.label(),
+                );
+            });
+            ui.checkbox(&mut settings.show_render_stats, "Show render stats");
+            ui.checkbox(&mut settings.show_zoom_readout, "Show zoom depth");
+            #[cfg(not(target_arch = "wasm32"))]
+            ui.checkbox(&mut settings.watch_project, "Reload edited project files")
+                .on_hover_text(
+                    "Reloads the open project when another program saves its file. With unsaved \
+                     edits in the app, asks first.",
+                );
+            #[cfg(not(target_arch = "wasm32"))]
+            ui.horizontal(|ui| {
+                let mut logging = settings.stats_log.is_some();
+                if ui
+                    .checkbox(&mut logging, "Log render stats to")
+                    .on_hover_text(
+                        "One record per preview and export frame; .json/.jsonl for JSON Lines",
+                    )
+                    .changed()
+                {
+                    settings.stats_log = logging.then(|| PathBuf::from("matterhorn-stats.csv"));
+                }
+                if let Some(path) = &mut settings.stats_log {
+                    let mut text = path.display().to_string();
+                    if ui.text_edit_singleline(&mut text).changed() {
+                        *path = PathBuf::from(text);
+                    }
+                }
+            });
+            if ui.button("Reset layout").clicked() {
+                let defaults = AppSettings::default();
+                settings.side_panel_width = defaults.side_panel_width;
+                settings.timeline_height = defaults.timeline_height;
+                settings.preview_scale = defaults.preview_scale;
+            }
+            if ui
+                .add_enabled(
+                    !settings.seen_tours.is_empty(),
+                    egui::Button::new("Replay tours"),
+                )
+                .on_hover_text("Show the guided tour of the window again")
+                .clicked()
+            {
+                settings.seen_tours.clear();
+            }
+        });
+}
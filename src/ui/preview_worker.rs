@@ -0,0 +1,210 @@
+//! Preview rendering on a thread of its own, so a slow frame never holds up the UI.
+
+use super::*;
+
+/// A frame for the worker to start rendering, replacing any it is still on.
+pub(crate) struct PreviewRequest {
+    /// Tags everything rendered for this request.
+    pub(crate) generation: u64,
+    pub(crate) size: (u32, u32),
+    pub(crate) fractal: FractalParams,
+    pub(crate) camera: Camera,
+    pub(crate) backend: RenderBackend,
+    pub(crate) steer: Steer,
+}
+
+enum Command {
+    Start(Box<PreviewRequest>),
+    Steer(Steer),
+    /// Drop the frame in progress.
+    Cancel,
+    RetryPrimary,
+}
+
+/// What the worker rendered for one request.
+pub(crate) enum Rendered {
+    /// Newly finished tiles, with their RGBA8 pixels.
+    Tiles(Vec<(TileInfo, Vec<u8>)>),
+    /// The finished frame, its stats and the samples behind it.
+    Frame(Vec<u8>, RenderStats, Vec<PixelSample>),
+}
+
+pub(crate) struct WorkerResult {
+    pub(crate) generation: u64,
+    pub(crate) rendered: Result<Rendered, RenderError>,
+    /// Whether the worker's renderer has given up on its backend, as of this result.
+    pub(crate) demotion: Option<Demotion>,
+}
+
+/// Handle to the preview's render thread. The thread exits once the handle is dropped and
+/// the frame it is on, if any, is done.
+pub(crate) struct PreviewWorker {
+    commands: mpsc::Sender<Command>,
+    pub(crate) results: mpsc::Receiver<WorkerResult>,
+}
+
+impl PreviewWorker {
+    /// Starts the thread; `ctx` is repainted whenever it has something to show.
+    pub(crate) fn spawn(ctx: &egui::Context) -> Self {
+        let (commands, inbox) = mpsc::channel();
+        let (outbox, results) = mpsc::channel();
+        let ctx = ctx.clone();
+        thread::spawn(move || run(&inbox, &outbox, &ctx));
+        Self { commands, results }
+    }
+
+    pub(crate) fn start(&self, request: PreviewRequest) {
+        let _ = self.commands.send(Command::Start(Box::new(request)));
+    }
+
+    pub(crate) fn steer(&self, steer: Steer) {
+        let _ = self.commands.send(Command::Steer(steer));
+    }
+
+    pub(crate) fn cancel(&self) {
+        let _ = self.commands.send(Command::Cancel);
+    }
+
+    pub(crate) fn retry_primary(&self) {
+        let _ = self.commands.send(Command::RetryPrimary);
+    }
+}
+
+/// The frame the worker is on.
+struct Job {
+    generation: u64,
+    render: ProgressiveRender,
+    steer: Steer,
+}
+
+fn run(inbox: &mpsc::Receiver<Command>, outbox: &mpsc::Sender<WorkerResult>, ctx: &egui::Context) {
+    // The UI's renderer stays on the UI thread; the worker makes its own, as exports do.
+    let mut renderer: Option<(RenderBackend, Box<dyn Renderer>)> = None;
+    let mut job: Option<Job> = None;
+    loop {
+        // Idle, wait for work; busy, take whatever came in since the last step.
+        let waited = match job {
+            Some(_) => None,
+            None => match inbox.recv() {
+                Ok(command) => Some(command),
+                Err(_) => return,
+            },
+        };
+        for command in waited.into_iter().chain(inbox.try_iter()) {
+            match command {
+                Command::Start(request) => {
+                    job = None;
+                    let started = renderer_for(&mut renderer, request.backend).and_then(|r| {
+                        ProgressiveRender::new(
+                            request.size,
+                            &request.fractal,
+                            &request.camera,
+                            r,
+                            PREVIEW_TILE,
+                        )
+                    });
+                    match started {
+                        Ok(render) => {
+                            job = Some(Job {
+                                generation: request.generation,
+                                render,
+                                steer: request.steer,
+                            })
+                        }
+                        Err(err) => {
+                            send(outbox, ctx, request.generation, Err(err), &renderer);
+                        }
+                    }
+                }
+                Command::Steer(steer) => {
+                    if let Some(job) = &mut job {
+                        job.steer = steer;
+                    }
+                }
+                Command::Cancel => job = None,
+                Command::RetryPrimary => {
+                    if let Some((_, r)) = &mut renderer {
+                        let _ = r.retry_primary();
+                    }
+                }
+            }
+        }
+        let (Some(current), Some((_, r))) = (&mut job, &mut renderer) else {
+            continue;
+        };
+        let Steer { pass, focus, whole } = current.steer;
+        let settled = current
+            .render
+            .focus()
+            .is_some_and(|(x, y)| focus.distance(pos2(x, y)) <= REFOCUS_DISTANCE);
+        if !settled {
+            current.render.prioritize((focus.x, focus.y));
+        }
+        let budget = if whole { Duration::MAX } else { PREVIEW_BUDGET };
+        let generation = current.generation;
+        let tiles = match current.render.step(r.as_mut(), budget) {
+            Ok(tiles) => tiles,
+            Err(err) => {
+                job = None;
+                send(outbox, ctx, generation, Err(err), &renderer);
+                continue;
+            }
+        };
+        if !current.render.is_done() {
+            if !whole {
+                let fractal = current.render.params();
+                let tile_pixels = |tile: &TileInfo| match pass {
+                    None => current.render.tile_pixels(tile),
+                    Some(pass) => pass_preview(pass, &current.render.tile_samples(tile), fractal),
+                };
+                let tiles = tiles.into_iter().map(|t| (t, tile_pixels(&t))).collect();
+                send(
+                    outbox,
+                    ctx,
+                    generation,
+                    Ok(Rendered::Tiles(tiles)),
+                    &renderer,
+                );
+            }
+            continue;
+        }
+        let done = job.take().expect("the finished job is still there");
+        let fractal = done.render.params().clone();
+        let (pixels, stats, samples) = done.render.finish();
+        let pixels = match pass {
+            None => pixels,
+            Some(pass) => pass_preview(pass, &samples, &fractal),
+        };
+        let frame = Rendered::Frame(pixels, stats, samples);
+        send(outbox, ctx, generation, Ok(frame), &renderer);
+    }
+}
+
+/// The worker's renderer for `backend`, made anew when the backend changed.
+fn renderer_for(
+    renderer: &mut Option<(RenderBackend, Box<dyn Renderer>)>,
+    backend: RenderBackend,
+) -> Result<&mut dyn Renderer, RenderError> {
+    if renderer.as_ref().map(|(b, _)| *b) != Some(backend) {
+        *renderer = None;
+        *renderer = Some((backend, create_renderer(backend)?));
+    }
+    let (_, r) = renderer.as_mut().expect("the renderer was just made");
+    Ok(r.as_mut())
+}
+
+fn send(
+    outbox: &mpsc::Sender<WorkerResult>,
+    ctx: &egui::Context,
+    generation: u64,
+    rendered: Result<Rendered, RenderError>,
+    renderer: &Option<(RenderBackend, Box<dyn Renderer>)>,
+) {
+    let demotion = renderer.as_ref().and_then(|(_, r)| r.demotion().cloned());
+    let _ = outbox.send(WorkerResult {
+        generation,
+        rendered,
+        demotion,
+    });
+    ctx.request_repaint();
+}
@@ -0,0 +1,142 @@
+//! Render-quality profiles: pickers for the preview's and the export's, and a window that
+//! edits either and saves it under a name.
+
+use super::*;
+
+/// Which of the project's two profiles the editor changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum QualityTarget {
+    #[default]
+    Preview,
+    Export,
+}
+
+impl QualityTarget {
+    fn label(self) -> &'static str {
+        match self {
+            QualityTarget::Preview => "Preview",
+            QualityTarget::Export => "Export",
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct QualityEditor {
+    pub(crate) open: bool,
+    pub(crate) target: QualityTarget,
+    /// Name typed for "Save as".
+    save_name: String,
+}
+
+impl QualityEditor {
+    pub(crate) fn open(&mut self, target: QualityTarget) {
+        self.open = true;
+        self.target = target;
+    }
+}
+
+/// Picks one of the built-in and `user` profiles into `profile`. A profile changed since
+/// it was picked is marked as edited.
+pub(crate) fn quality_combo(
+    ui: &mut egui::Ui,
+    id: &str,
+    profile: &mut QualityProfile,
+    user: &[QualityProfile],
+) -> egui::Response {
+    let text = if profile.is_saved(user) {
+        profile.name.clone()
+    } else {
+        format!("{} (edited)", profile.name)
+    };
+    egui::ComboBox::from_id_source(id)
+        .selected_text(text)
+        .show_ui(ui, |ui| {
+            for choice in QualityProfile::built_in().iter().chain(user) {
+                if ui
+                    .selectable_label(profile == choice, &choice.name)
+                    .clicked()
+                {
+                    *profile = choice.clone();
+                }
+            }
+        })
+        .response
+}
+
+/// The profile editor: iterations, sample pattern and tiles of the preview's or the
+/// export's profile, and saving it among `user`.
+pub(crate) fn quality_window(
+    ctx: &egui::Context,
+    editor: &mut QualityEditor,
+    proj: &mut Project,
+    user: &mut Vec<QualityProfile>,
+) {
+    let mut open = editor.open;
+    egui::Window::new("Quality profiles")
+        .open(&mut open)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                for target in [QualityTarget::Preview, QualityTarget::Export] {
+                    ui.selectable_value(&mut editor.target, target, target.label());
+                }
+            });
+            let profile = match editor.target {
+                QualityTarget::Preview => &mut proj.quality,
+                QualityTarget::Export => &mut proj.export.quality,
+            };
+            quality_combo(ui, "quality_editor_profile", profile, user);
+            ui.separator();
+            ui.add(
+                egui::Slider::new(&mut profile.iter_scale, 0.1..=8.0)
+                    .logarithmic(true)
+                    .text("Iteration scale"),
+            )
+            .on_hover_text("Multiplies Max Iter, after Auto has picked it");
+            egui::ComboBox::from_label("Sample pattern")
+                .selected_text(profile.sample_pattern.label())
+                .show_ui(ui, |ui| {
+                    for pattern in SamplePattern::ALL {
+                        ui.selectable_value(&mut profile.sample_pattern, pattern, pattern.label());
+                    }
+                })
+                .response
+                .on_hover_text(
+                    "Where the samples of a supersampled pixel go. Jittered patterns change \
+                     every frame, drawn from the project seed and the frame index, so exports \
+                     stay reproducible.",
+                );
+            ui.horizontal(|ui| {
+                ui.label("Tile size");
+                ui.add(egui::DragValue::new(&mut profile.tile_size).clamp_range(0..=8192));
+                ui.weak("0 = auto");
+            });
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut editor.save_name);
+                let name = editor.save_name.trim();
+                let built_in = QualityProfile::named(name).is_some();
+                let save = ui
+                    .add_enabled(!name.is_empty() && !built_in, egui::Button::new("Save as"))
+                    .on_disabled_hover_text("Built-in profiles cannot be replaced");
+                if save.clicked() {
+                    profile.name = name.to_owned();
+                    match user.iter_mut().find(|p| p.name == profile.name) {
+                        Some(saved) => *saved = profile.clone(),
+                        None => user.push(profile.clone()),
+                    }
+                }
+            });
+            let saved = user.iter().position(|p| p.name == profile.name);
+            if let Some(index) = saved {
+                if ui
+                    .button(format!("Delete \"{}\"", profile.name))
+                    .on_hover_text("Projects using it keep their copy")
+                    .clicked()
+                {
+                    user.remove(index);
+                }
+            }
+        });
+    editor.open = open;
+}
@@ -0,0 +1,85 @@
+//! Sliders over the parameter range table, with Ctrl-click to type a value past the end of
+//! the slider.
+
+use super::*;
+
+/// A slider for `param` across its soft range, or its expert range in expert mode. Values
+/// typed into its box, or after Ctrl-clicking it, may go anywhere within the hard limit.
+pub(crate) fn ranged_slider<N: egui::emath::Numeric>(
+    ui: &mut egui::Ui,
+    param: RangedParam,
+    value: &mut N,
+    text: &str,
+    expert: bool,
+) -> egui::Response {
+    let id = Id::new(("ranged_slider", param.name()));
+    let range = param.range();
+    if let Some(typed) = ui.data(|d| d.get_temp::<String>(id)) {
+        return typed_value(ui, id, param, value, typed, text);
+    }
+    let (lo, hi) = range.slider(expert);
+    let before = *value;
+    let response = ui.add(
+        egui::Slider::new(value, N::from_f64(lo)..=N::from_f64(hi))
+            .logarithmic(range.response == SliderResponse::Logarithmic)
+            .clamp_to_range(false)
+            .text(text),
+    );
+    if response.clicked() && ui.input(|i| i.modifiers.command) {
+        *value = before;
+        ui.data_mut(|d| d.insert_temp(id, before.to_f64().to_string()));
+        ui.memory_mut(|m| m.request_focus(id.with("edit")));
+    }
+    let clamped = range.clamp(value.to_f64());
+    if clamped != value.to_f64() {
+        *value = N::from_f64(clamped);
+    }
+    response.on_hover_text("Ctrl-click to type a value")
+}
+
+/// The text box a Ctrl-clicked slider turns into. Enter sets the value if the hard limit
+/// allows it; clicking away or Escape leaves it alone.
+fn typed_value<N: egui::emath::Numeric>(
+    ui: &mut egui::Ui,
+    id: Id,
+    param: RangedParam,
+    value: &mut N,
+    mut typed: String,
+    text: &str,
+) -> egui::Response {
+    let checked = typed
+        .trim()
+        .parse::<f64>()
+        .map_err(|_| format!("{} must be a number", param.name()))
+        .and_then(|v| param.check(v).map_err(|e| e.to_string()));
+    let inner = ui.horizontal(|ui| {
+        let edit = ui.add(
+            egui::TextEdit::singleline(&mut typed)
+                .id(id.with("edit"))
+                .desired_width(96.0),
+        );
+        ui.label(text);
+        edit
+    });
+    let edit = inner.inner;
+    if let Err(err) = &checked {
+        ui.colored_label(ui.visuals().error_fg_color, err);
+    }
+    let entered = ui.input(|i| i.key_pressed(egui::Key::Enter));
+    if edit.lost_focus() {
+        match checked {
+            Ok(v) if entered => *value = N::from_f64(v),
+            // Stay open to fix the value.
+            Err(_) if entered => {
+                edit.request_focus();
+                ui.data_mut(|d| d.insert_temp(id, typed));
+                return edit;
+            }
+            _ => {}
+        }
+        ui.data_mut(|d| d.remove::<String>(id));
+    } else {
+        ui.data_mut(|d| d.insert_temp(id, typed));
+    }
+    edit
+}
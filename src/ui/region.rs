@@ -0,0 +1,225 @@
+//! Preview region: a box dragged over the viewport, rendered at export quality on top of
+//! the preview.
+
+use super::*;
+
+/// Outline of the preview region.
+const REGION_STROKE: Color32 = Color32::from_rgb(250, 210, 90);
+
+/// Boxes smaller than this, in points per side, are taken for a stray click.
+const MIN_REGION: f32 = 8.0;
+
+/// A region rendered, or being rendered, with the parameters it was started with.
+struct RegionRender {
+    /// `None` once every tile is in `texture`.
+    job: Option<ProgressiveRender>,
+    region: TileInfo,
+    fractal: FractalParams,
+    camera: Camera,
+    renderer: &'static str,
+    texture: TextureHandle,
+}
+
+impl RegionRender {
+    fn matches(&self, region: &TileInfo, frame: &EvaluatedFrame, renderer: &dyn Renderer) -> bool {
+        self.region == *region
+            && self.renderer == renderer.name()
+            && same_framing(&self.camera, &frame.camera)
+            && self.fractal == frame.fractal
+    }
+}
+
+/// The preview region tool's state.
+#[derive(Default)]
+pub(crate) struct RegionPreview {
+    /// Whether the next drag over the viewport draws the box.
+    pub(crate) picking: bool,
+    /// The box, in points from the top-left corner of the viewport image.
+    pub(crate) rect: Option<Rect>,
+    /// Corners of the box being dragged.
+    drag: Option<(egui::Pos2, egui::Pos2)>,
+    render: Option<RegionRender>,
+}
+
+impl RegionPreview {
+    pub(crate) fn clear(&mut self) {
+        self.rect = None;
+        self.render = None;
+    }
+}
+
+/// `preview` as the exporter would render it: iterations for the export width and the
+/// export's sample pattern. The locks and framing stay the preview's, so the region lines
+/// up with the image under it.
+pub(crate) fn region_frame(proj: &Project, preview: &EvaluatedFrame) -> EvaluatedFrame {
+    let mut frame = preview.clone();
+    frame
+        .fractal
+        .apply_auto_iter(&frame.camera, proj.export.width as f32);
+    let index = frame_at(proj.anim.t, proj.export.fps);
+    proj.export
+        .quality
+        .apply(&mut frame.fractal, proj.seed, index);
+    frame
+}
+
+/// The toolbar toggle for drawing a preview region, and a button to clear it.
+pub(crate) fn region_preview_toolbar(ui: &mut egui::Ui, region: &mut RegionPreview) {
+    ui.toggle_value(&mut region.picking, "▭ Preview region")
+        .on_hover_text("Drag a box on the viewport to render it at export quality");
+    if region.rect.is_some() && ui.small_button("✕").on_hover_text("Clear region").clicked() {
+        region.clear();
+    }
+}
+
+/// Lets a box be dragged over the viewport image at `rect` while picking, then shows the
+/// box rendered at export quality with `frame`. Tiles render only while `idle`, so the
+/// region waits for the preview under it, and keep until the parameters change.
+pub(crate) fn region_preview_overlay(
+    ui: &mut egui::Ui,
+    rect: Rect,
+    region: &mut RegionPreview,
+    frame: impl FnOnce() -> EvaluatedFrame,
+    renderer: &mut dyn Renderer,
+    idle: bool,
+) -> Result<(), RenderError> {
+    let painter = ui.painter_at(rect);
+    if region.picking {
+        let response = ui
+            .interact(rect, Id::new("preview_region_pick"), Sense::drag())
+            .on_hover_cursor(egui::CursorIcon::Crosshair);
+        if let Some(pos) = response.interact_pointer_pos() {
+            let pos = pos2(0.0, 0.0) + (pos - rect.min);
+            match &mut region.drag {
+                Some((_, to)) if !response.drag_started() => *to = pos,
+                drag => *drag = Some((pos, pos)),
+            }
+        }
+        if let Some((from, to)) = region.drag {
+            let drawn = Rect::from_two_pos(from, to);
+            painter.rect_stroke(
+                drawn.translate(rect.min.to_vec2()),
+                0.0,
+                Stroke::new(1.0, REGION_STROKE),
+            );
+            if !response.dragged() {
+                region.drag = None;
+                if drawn.width() >= MIN_REGION && drawn.height() >= MIN_REGION {
+                    region.rect = Some(drawn);
+                    region.render = None;
+                    region.picking = false;
+                }
+            }
+        }
+        if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+            region.picking = false;
+            region.drag = None;
+        }
+    }
+
+    let Some(boxed) = region.rect else {
+        return Ok(());
+    };
+    // One pixel per point, the density the camera's scale is given in for exports.
+    let full = (rect.width().round() as u32, rect.height().round() as u32);
+    let clipped = boxed.intersect(Rect::from_min_size(pos2(0.0, 0.0), rect.size()));
+    let x0 = (clipped.min.x.max(0.0).round() as u32).min(full.0);
+    let y0 = (clipped.min.y.max(0.0).round() as u32).min(full.1);
+    let area = TileInfo {
+        full_w: full.0,
+        full_h: full.1,
+        offset_x: x0,
+        offset_y: y0,
+        tile_w: (clipped.max.x.round() as u32)
+            .min(full.0)
+            .saturating_sub(x0),
+        tile_h: (clipped.max.y.round() as u32)
+            .min(full.1)
+            .saturating_sub(y0),
+    };
+    let screen = Rect::from_min_size(
+        rect.min + vec2(area.offset_x as f32, area.offset_y as f32),
+        vec2(area.tile_w as f32, area.tile_h as f32),
+    );
+    let outline = |painter: &egui::Painter| {
+        painter.rect_stroke(screen.expand(1.0), 0.0, Stroke::new(1.5, REGION_STROKE));
+    };
+    if area.tile_w == 0 || area.tile_h == 0 {
+        outline(&painter);
+        return Ok(());
+    }
+
+    let frame = frame();
+    if !region
+        .render
+        .as_ref()
+        .is_some_and(|r| r.matches(&area, &frame, renderer))
+    {
+        // A stale region is hidden until the view settles, rather than restarted every
+        // frame of a drag.
+        if !idle {
+            region.render = None;
+            outline(&painter);
+            return Ok(());
+        }
+        let size = [area.tile_w as usize, area.tile_h as usize];
+        region.render = Some(RegionRender {
+            job: Some(ProgressiveRender::region(
+                &area,
+                &frame.fractal,
+                &frame.camera,
+                renderer,
+                PREVIEW_TILE,
+            )?),
+            region: area,
+            fractal: frame.fractal,
+            camera: frame.camera,
+            renderer: renderer.name(),
+            texture: ui.ctx().load_texture(
+                "preview_region",
+                ColorImage::new(size, Color32::TRANSPARENT),
+                egui::TextureOptions::LINEAR,
+            ),
+        });
+    }
+    let render = region
+        .render
+        .as_mut()
+        .expect("a region render was just started");
+    if let Some(job) = render.job.as_mut().filter(|_| idle) {
+        let tiles = match job.step(renderer, PREVIEW_BUDGET) {
+            Ok(tiles) => tiles,
+            Err(err) => {
+                region.render = None;
+                return Err(err);
+            }
+        };
+        if job.is_done() {
+            let (pixels, _, _) = render.job.take().expect("the job is running").finish();
+            let size = [area.tile_w as usize, area.tile_h as usize];
+            render.texture.set(
+                ColorImage::from_rgba_unmultiplied(size, &pixels),
+                egui::TextureOptions::LINEAR,
+            );
+        } else {
+            for tile in tiles {
+                let image = ColorImage::from_rgba_unmultiplied(
+                    [tile.tile_w as usize, tile.tile_h as usize],
+                    &job.tile_pixels(&tile),
+                );
+                render.texture.set_partial(
+                    [tile.offset_x as usize, tile.offset_y as usize],
+                    image,
+                    egui::TextureOptions::LINEAR,
+                );
+            }
+        }
+    }
+    if render.job.is_some() {
+        ui.ctx().request_repaint();
+    }
+    let uv = Rect::from_min_max(pos2(0.0, 0.0), pos2(1.0, 1.0));
+    painter.image(render.texture.id(), screen, uv, Color32::WHITE);
+    outline(&painter);
+    Ok(())
+}
@@ -0,0 +1,62 @@
+//! Project report window.
+
+use super::*;
+
+#[derive(Default)]
+pub(crate) struct ReportWindow {
+    pub(crate) open: bool,
+    /// Made when the window opens and on Refresh, since it evaluates every export frame.
+    report: Option<ProjectReport>,
+}
+
+impl ReportWindow {
+    /// Opens the window on a fresh report of `proj`, or closes it.
+    pub(crate) fn toggle(&mut self, proj: &Project, benchmark: Option<&RenderStats>) {
+        self.open = !self.open;
+        if self.open {
+            self.report = Some(project_report(proj, benchmark));
+        }
+    }
+}
+
+/// The report as text. `benchmark` is the last frame the preview iterated, which the
+/// render time is estimated from.
+pub(crate) fn report_window(
+    ctx: &egui::Context,
+    window: &mut ReportWindow,
+    proj: &Project,
+    benchmark: Option<&RenderStats>,
+    toasts: &mut Toasts,
+) {
+    let mut open = window.open;
+    egui::Window::new("Project report")
+        .open(&mut open)
+        .default_width(480.0)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui
+                    .button("Refresh")
+                    .on_hover_text("Evaluate every export frame again")
+                    .clicked()
+                {
+                    window.report = Some(project_report(proj, benchmark));
+                }
+                if let Some(report) = &window.report {
+                    if ui.button("Copy").clicked() {
+                        if let Err(e) = copy_text(ui, report.to_string()) {
+                            toasts.error(format!("Could not copy the report: {e}"));
+                        }
+                    }
+                }
+            });
+            if benchmark.is_none() {
+                ui.weak("The render time is estimated once the preview has rendered a frame.");
+            }
+            if let Some(report) = &window.report {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    ui.monospace(report.to_string());
+                });
+            }
+        });
+    window.open = open;
+}
@@ -0,0 +1,102 @@
+//! Script editor window and script evaluation for the live preview.
+
+use super::*;
+
+/// Editor state plus the compiled form of the project's script.
+#[derive(Default)]
+pub(crate) struct ScriptEditor {
+    pub(crate) open: bool,
+    draft: String,
+    runner: Option<ScriptRunner>,
+    /// What `runner` was built from; a mismatch means the project changed (load, undo).
+    compiled: Option<FrameScript>,
+    error: Option<ScriptError>,
+}
+
+impl ScriptEditor {
+    /// Runs the project's script on `frame`, recompiling first if the project's script changed.
+    /// A failing script is switched off for the preview until it is applied again.
+    pub(crate) fn apply(
+        &mut self,
+        proj: &Project,
+        time: f32,
+        frame: &mut EvaluatedFrame,
+        toasts: &mut Toasts,
+    ) {
+        if self.compiled.as_ref() != Some(&proj.script) {
+            self.compiled = Some(proj.script.clone());
+            self.draft = proj.script.source.clone();
+            self.error = None;
+            self.runner = ScriptRunner::for_project(proj).unwrap_or_else(|e| {
+                toasts.error(format!("Script {e}"));
+                self.error = Some(e);
+                None
+            });
+        }
+        if let Some(runner) = &self.runner {
+            if let Err(e) = runner.apply(time, frame) {
+                toasts.error(format!("Script stopped: {e}"));
+                self.error = Some(e);
+                self.runner = None;
+            }
+        }
+    }
+
+    /// Compiles the draft and, if it is valid, stores it in `script` and enables it.
+    fn commit(&mut self, script: &mut FrameScript) {
+        match ScriptRunner::compile(&self.draft) {
+            Ok(runner) => {
+                script.source = self.draft.clone();
+                script.enabled = true;
+                self.runner = Some(runner);
+                self.compiled = Some(script.clone());
+                self.error = None;
+            }
+            Err(e) => self.error = Some(e),
+        }
+    }
+}
+
+pub(crate) fn script_window(
+    ctx: &egui::Context,
+    editor: &mut ScriptEditor,
+    script: &mut FrameScript,
+) {
+    let mut open = editor.open;
+    egui::Window::new("Script")
+        .open(&mut open)
+        .default_width(440.0)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut script.enabled, "Enabled");
+                ui.menu_button("Examples", |ui| {
+                    for (name, source) in EXAMPLE_SCRIPTS {
+                        if ui.button(name).clicked() {
+                            editor.draft = source.into();
+                            ui.close_menu();
+                        }
+                    }
+                });
+                if ui.button("Apply").clicked() {
+                    editor.commit(script);
+                }
+                if editor.draft != script.source {
+                    ui.weak("unapplied changes");
+                }
+            });
+            ui.add(
+                egui::TextEdit::multiline(&mut editor.draft)
+                    .code_editor()
+                    .desired_rows(12)
+                    .desired_width(f32::INFINITY),
+            );
+            if let Some(e) = &editor.error {
+                ui.colored_label(ToastKind::Error.color(), e.to_string());
+            }
+            ui.weak(format!(
+                "fn frame(t, params) runs after keyframes and returns params. Writable: {}.",
+                SCRIPT_PARAMS.join(", ")
+            ));
+        });
+    editor.open = open;
+}
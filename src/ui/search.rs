@@ -0,0 +1,177 @@
+//! Search over the side panel. The panel is declared as sections of labeled controls
+//! (see `side_panel.rs`), so a query can filter them, highlight the matches and open the
+//! collapsed sections they are in.
+
+use super::*;
+
+/// Draws one control from the app state.
+pub(crate) type DrawControl = Box<dyn Fn(&mut MatterhornApp, &mut egui::Ui)>;
+
+/// A side panel control and the label searches match against.
+pub(crate) struct PanelControl {
+    pub(crate) label: &'static str,
+    pub(crate) draw: DrawControl,
+}
+
+/// How a section's title is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SectionStyle {
+    /// A heading over controls that are always shown.
+    Heading,
+    /// A collapsing header, closed until opened or searched.
+    Collapsing,
+}
+
+/// A titled group of side panel controls.
+pub(crate) struct PanelSection {
+    pub(crate) title: &'static str,
+    pub(crate) style: SectionStyle,
+    pub(crate) controls: Vec<PanelControl>,
+}
+
+impl PanelSection {
+    pub(crate) fn new(title: &'static str, style: SectionStyle) -> Self {
+        Self {
+            title,
+            style,
+            controls: Vec::new(),
+        }
+    }
+
+    /// Adds a control found by searching for `label`.
+    pub(crate) fn control(
+        mut self,
+        label: &'static str,
+        draw: impl Fn(&mut MatterhornApp, &mut egui::Ui) + 'static,
+    ) -> Self {
+        self.controls.push(PanelControl {
+            label,
+            draw: Box::new(draw),
+        });
+        self
+    }
+
+    /// [`PanelSection::control`] when `shown`, so hidden controls are not found either.
+    pub(crate) fn control_if(
+        self,
+        shown: bool,
+        label: &'static str,
+        draw: impl Fn(&mut MatterhornApp, &mut egui::Ui) + 'static,
+    ) -> Self {
+        if shown {
+            self.control(label, draw)
+        } else {
+            self
+        }
+    }
+}
+
+/// Query typed into the side panel's search box.
+#[derive(Default)]
+pub(crate) struct PanelSearch {
+    pub(crate) query: String,
+}
+
+/// Whether `query` fuzzily matches `label`: case-insensitively, every word of the query
+/// is a subsequence of the label starting at the start of one of its words. "max it"
+/// finds "Max Iter" and "pcyc" finds "Palette cycle (/s)", but "ter" does not.
+pub(crate) fn fuzzy_match(query: &str, label: &str) -> bool {
+    let label: Vec<char> = label.to_lowercase().chars().collect();
+    let word_start =
+        |i: usize| label[i].is_alphanumeric() && (i == 0 || !label[i - 1].is_alphanumeric());
+    query.to_lowercase().split_whitespace().all(|term| {
+        let mut chars = term.chars();
+        let first = chars.next();
+        let rest: Vec<char> = chars.collect();
+        (0..label.len())
+            .filter(|&i| word_start(i) && Some(label[i]) == first)
+            .any(|i| {
+                let mut tail = label[i + 1..].iter();
+                rest.iter().all(|c| tail.any(|l| l == c))
+            })
+    })
+}
+
+/// The search box, with Ctrl/Cmd+F to focus it and Esc to clear it.
+fn search_box_ui(ui: &mut egui::Ui, query: &mut String) {
+    ui.horizontal(|ui| {
+        let response = ui.add(
+            egui::TextEdit::singleline(query)
+                .hint_text("🔍 Search controls")
+                .desired_width(ui.available_width() - 24.0),
+        );
+        let find = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::F);
+        if ui.input_mut(|i| i.consume_shortcut(&find)) {
+            response.request_focus();
+        }
+        // A text field gives up focus on Esc.
+        if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+            query.clear();
+        }
+        if !query.is_empty() && ui.small_button("✕").on_hover_text("Clear (Esc)").clicked() {
+            query.clear();
+        }
+    });
+}
+
+/// The search box and `sections`. While searching, only controls whose label or section
+/// title matches are shown, label matches highlighted, with collapsed sections opened.
+pub(crate) fn side_panel_ui(
+    app: &mut MatterhornApp,
+    ui: &mut egui::Ui,
+    sections: Vec<PanelSection>,
+) {
+    search_box_ui(ui, &mut app.search.query);
+    let query = app.search.query.trim().to_owned();
+    let searching = !query.is_empty();
+    let mut first = true;
+    for section in sections {
+        let whole = fuzzy_match(&query, section.title);
+        let controls: Vec<(bool, PanelControl)> = section
+            .controls
+            .into_iter()
+            .map(|c| (searching && fuzzy_match(&query, c.label), c))
+            .filter(|(hit, _)| !searching || whole || *hit)
+            .collect();
+        if controls.is_empty() {
+            continue;
+        }
+        let draw = |app: &mut MatterhornApp, ui: &mut egui::Ui| {
+            for (hit, control) in &controls {
+                if *hit {
+                    egui::Frame::none()
+                        .fill(ui.visuals().selection.bg_fill.gamma_multiply(0.3))
+                        .rounding(3.0)
+                        .inner_margin(2.0)
+                        .show(ui, |ui| (control.draw)(app, ui));
+                } else {
+                    (control.draw)(app, ui);
+                }
+            }
+        };
+        match section.style {
+            SectionStyle::Heading => {
+                if !first {
+                    ui.separator();
+                }
+                ui.heading(section.title);
+                draw(app, ui);
+            }
+            // Searching opens sections under their own id, leaving the user's choice of
+            // open sections as it was once the search is cleared.
+            SectionStyle::Collapsing if searching => {
+                egui::CollapsingHeader::new(section.title)
+                    .id_source(("panel_search", section.title))
+                    .default_open(true)
+                    .show(ui, |ui| draw(app, ui));
+            }
+            SectionStyle::Collapsing => {
+                ui.collapsing(section.title, |ui| draw(app, ui));
+            }
+        }
+        first = false;
+    }
+    if first {
+        ui.weak(format!("No controls match “{query}”"));
+    }
+}
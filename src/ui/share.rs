@@ -0,0 +1,185 @@
+//! Copying and pasting views as text through the clipboard.
+
+use super::*;
+
+/// Options for copied settings, and a pasted snippet waiting for confirmation.
+#[derive(Default)]
+pub(crate) struct ShareState {
+    with_palette: bool,
+    with_export: bool,
+    pending: Option<SettingsSnippet>,
+}
+
+/// The Share menu: view descriptors for other programs, via the clipboard.
+pub(crate) fn share_menu(
+    ui: &mut egui::Ui,
+    state: &mut ShareState,
+    proj: &mut Project,
+    eval: &EvaluatedFrame,
+    toasts: &mut Toasts,
+) {
+    ui.menu_button("Share", |ui| {
+        if ui
+            .button("Copy view as text")
+            .on_hover_text("A matterhorn:v1 string with the view and palette, for chat messages")
+            .clicked()
+        {
+            match copy_text(ui, to_share_string(&eval.fractal, &eval.camera, true)) {
+                Ok(()) => toasts.info("Copied the view as text"),
+                Err(e) => toasts.error(format!("Clipboard copy failed: {e}")),
+            }
+            ui.close_menu();
+        }
+        if ui.button("Paste view").clicked() {
+            match paste_text().and_then(|text| parse_share_string(&text)) {
+                Ok(view) => view.apply(proj),
+                Err(e) => toasts.error(format!("Paste failed: {e}")),
+            }
+            ui.close_menu();
+        }
+        ui.separator();
+        if ui
+            .button("Copy settings")
+            .on_hover_text("A TOML snippet of the fractal and camera, for bug reports and forums")
+            .clicked()
+        {
+            let export = state.with_export.then_some(&proj.export);
+            let text = to_settings_snippet(&eval.fractal, &eval.camera, state.with_palette, export);
+            match copy_text(ui, text) {
+                Ok(()) => toasts.info("Copied the settings"),
+                Err(e) => toasts.error(format!("Clipboard copy failed: {e}")),
+            }
+            ui.close_menu();
+        }
+        ui.indent("snippet_options", |ui| {
+            ui.checkbox(&mut state.with_palette, "With palette");
+            ui.checkbox(&mut state.with_export, "With export settings");
+        });
+        if ui
+            .button("Paste settings…")
+            .on_hover_text("Merge a TOML or JSON snippet into the project, after a preview")
+            .clicked()
+        {
+            match paste_text().and_then(|text| parse_settings_snippet(&text)) {
+                Ok(snippet) if snippet.changes(proj).is_empty() => {
+                    toasts.info("The pasted settings match the project")
+                }
+                Ok(snippet) => state.pending = Some(snippet),
+                Err(e) => toasts.error(format!("Paste failed: {e}")),
+            }
+            ui.close_menu();
+        }
+        ui.separator();
+        if ui
+            .button("Copy UF parameters")
+            .on_hover_text("Ultra Fractal parameter block of the current view")
+            .clicked()
+        {
+            let mut view = proj.clone();
+            view.camera = eval.camera.clone();
+            view.fractal = eval.fractal.clone();
+            match copy_text(ui, to_uf_params(&view)) {
+                Ok(()) => toasts.info("Copied Ultra Fractal parameters"),
+                Err(e) => toasts.error(format!("Clipboard copy failed: {e}")),
+            }
+            ui.close_menu();
+        }
+        if ui.button("Paste UF parameters").clicked() {
+            match paste_text().and_then(|text| parse_uf_params(&text)) {
+                Ok(loc) => {
+                    for warning in loc.apply(proj) {
+                        toasts.warning(warning);
+                    }
+                }
+                Err(e) => toasts.error(format!("Paste failed: {e}")),
+            }
+            ui.close_menu();
+        }
+    });
+}
+
+/// Lists what a pasted snippet will change, old values struck through, until it is
+/// applied or dismissed.
+pub(crate) fn paste_settings_window(
+    ctx: &egui::Context,
+    state: &mut ShareState,
+    proj: &mut Project,
+) {
+    let Some(snippet) = &state.pending else {
+        return;
+    };
+    let changes = snippet.changes(proj);
+    let mut open = true;
+    let mut done = false;
+    egui::Window::new("Paste settings")
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            if changes.is_empty() {
+                ui.label("The project already matches these settings.");
+            } else {
+                ui.label(format!(
+                    "Will change {} setting{}:",
+                    changes.len(),
+                    if changes.len() == 1 { "" } else { "s" }
+                ));
+                egui::Grid::new("paste_settings_changes")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for change in &changes {
+                            ui.monospace(change.field);
+                            ui.label(egui::RichText::new(&change.from).strikethrough().weak());
+                            ui.label("→");
+                            ui.strong(&change.to);
+                            ui.end_row();
+                        }
+                    });
+            }
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(!changes.is_empty(), egui::Button::new("Apply"))
+                    .clicked()
+                {
+                    snippet.apply(proj);
+                    done = true;
+                }
+                if ui.button("Cancel").clicked() {
+                    done = true;
+                }
+            });
+        });
+    if done || !open {
+        state.pending = None;
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn copy_text(_ui: &egui::Ui, text: String) -> Result<(), String> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text))
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn paste_text() -> Result<String, String> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.get_text())
+        .map_err(|e| e.to_string())
+}
+
+/// Browsers only let egui write the clipboard.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn copy_text(ui: &egui::Ui, text: String) -> Result<(), String> {
+    ui.ctx().output_mut(|o| o.copied_text = text);
+    Ok(())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn paste_text() -> Result<String, String> {
+    Err(
+        "the browser build cannot read the clipboard; use `open --from-string` in the desktop app"
+            .into(),
+    )
+}
@@ -0,0 +1,763 @@
+//! The left side panel, declared as sections of labeled controls for [`side_panel_ui`].
+
+use super::*;
+
+/// Every section of the side panel, in order, with the controls that apply to the
+/// current project.
+pub(crate) fn side_panel_sections(app: &MatterhornApp) -> Vec<PanelSection> {
+    vec![
+        explore_section(),
+        fractal_section(app),
+        camera_section(),
+        color_section(app),
+        orbit_trap_section(),
+        palette_section(),
+        export_section(app),
+    ]
+}
+
+fn explore_section() -> PanelSection {
+    PanelSection::new("Explore", SectionStyle::Collapsing)
+        .control("Explore jump", |app, ui| {
+            explore_jump_ui(ui, &mut app.explorer, &mut app.proj);
+        })
+        .control("Project seed", |app, ui| {
+            project_seed_ui(ui, &mut app.explorer, &mut app.proj);
+        })
+        .control("Random palette", |app, ui| {
+            ui.checkbox(&mut app.explorer.random_palette, "Random palette");
+        })
+        .control("Explore history", |app, ui| {
+            explore_history_ui(ui, &app.explorer, &mut app.thumbnails, &mut app.proj);
+        })
+}
+
+fn fractal_section(app: &MatterhornApp) -> PanelSection {
+    let fractal = &app.proj.fractal;
+    let mut section = PanelSection::new("Fractal", SectionStyle::Heading)
+        .control("Kind", |app, ui| {
+            ui.horizontal(|ui| {
+                ui.label("Kind");
+                if ui
+                    .small_button("Reset view")
+                    .on_hover_text("Frame the whole set for this formula")
+                    .clicked()
+                {
+                    app.proj.camera = formula(app.proj.fractal.kind).default_camera(app.viewport.x);
+                }
+            });
+            for f in formulas() {
+                let selected = app.proj.fractal.kind == f.kind();
+                if ui.selectable_label(selected, f.name()).clicked() {
+                    app.proj.fractal.switch_kind(f.kind());
+                }
+            }
+            #[cfg(feature = "gpu")]
+            if app.proj.render_backend == RenderBackend::Gpu
+                && formula(app.proj.fractal.kind).wgsl_step().is_none()
+            {
+                ui.weak("Renders on the CPU");
+            }
+        })
+        .control("Max Iter", |app, ui| {
+            let expert = app.settings.expert_ranges;
+            let suggested = app
+                .eval
+                .fractal
+                .suggested_max_iter(&app.eval.camera, app.viewport.x);
+            ui.horizontal(|ui| {
+                if app.proj.fractal.auto_iter {
+                    ui.add_enabled_ui(false, |ui| {
+                        let max_iter = &mut app.eval.fractal.max_iter;
+                        ranged_slider(ui, RangedParam::MaxIter, max_iter, "Max Iter", expert);
+                    });
+                } else {
+                    let max_iter = &mut app.proj.fractal.max_iter;
+                    ranged_slider(ui, RangedParam::MaxIter, max_iter, "Max Iter", expert);
+                }
+                ui.checkbox(&mut app.proj.fractal.auto_iter, "Auto")
+                    .on_hover_text(
+                    "Follow the suggested Max Iter as the view zooms, in the preview and exports",
+                );
+            });
+            if !app.proj.fractal.auto_iter {
+                ui.weak(format!("Suggested: ~{suggested} for this depth"));
+            }
+        })
+        .control("Escape R", |app, ui| {
+            ranged_slider(
+                ui,
+                RangedParam::EscapeRadius,
+                &mut app.proj.fractal.escape_radius,
+                "Escape R",
+                app.settings.expert_ranges,
+            );
+        })
+        .control_if(
+            fractal.kind == FractalKind::BurningShip,
+            "Legacy fold",
+            |app, ui| {
+                ui.checkbox(&mut app.proj.fractal.legacy_burning_ship, "Legacy fold")
+                    .on_hover_text(
+                        "The original Burning Ship step, which folded every iterate into \
+                         the first quadrant. Only orbit traps look different; kept for old \
+                         projects and renders on the CPU.",
+                    );
+            },
+        )
+        .control_if(fractal.kind == FractalKind::Custom, "Formula", |app, ui| {
+            ui.horizontal(|ui| {
+                ui.label("Formula");
+                ui.text_edit_singleline(&mut app.proj.fractal.custom_formula)
+                    .on_hover_text(
+                        "The next z from z and the pixel c, e.g. sqr(z) + c. Operators \
+                             + - * / ^; functions sqr, abs, conj, sin, cos, exp, log, re, im; \
+                             constants i, 2.5i, pi, e",
+                    );
+            });
+            if let Err(err) = app.custom_formula.compile(&app.proj.fractal.custom_formula) {
+                ui.colored_label(ui.visuals().error_fg_color, err.to_string());
+            }
+        })
+        .control_if(
+            fractal.kind == FractalKind::Lyapunov,
+            "Sequence",
+            |app, ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Sequence");
+                    let sequence = &mut app.proj.fractal.lyapunov_sequence;
+                    ui.text_edit_singleline(sequence).on_hover_text(
+                        "Which rate drives each logistic-map step, repeating: A the \
+                         horizontal axis, B the vertical one",
+                    );
+                    if !sequence
+                        .chars()
+                        .any(|ch| matches!(ch, 'A' | 'B' | 'a' | 'b'))
+                    {
+                        ui.weak("no A or B; drawing AB");
+                    }
+                });
+            },
+        )
+        .control_if(
+            fractal.kind == FractalKind::Lyapunov,
+            "Warm-up",
+            |app, ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Warm-up");
+                    ui.add(
+                        egui::DragValue::new(&mut app.proj.fractal.lyapunov_warmup)
+                            .clamp_range(0..=10_000),
+                    )
+                    .on_hover_text("Steps taken before the exponent is measured");
+                });
+            },
+        );
+    for &param in formula(fractal.kind).params() {
+        section = section.control(param.label, move |app, ui| {
+            ui.horizontal(|ui| {
+                if let Some(track) = param.field.track() {
+                    lock_toggle(ui, &mut app.proj.locks, track);
+                }
+                let value = param.field.get_mut(&mut app.proj.fractal);
+                match param.control {
+                    ParamControl::Slider => {
+                        ui.add(egui::Slider::new(value, param.min..=param.max).text(param.label));
+                    }
+                    ParamControl::Steps => {
+                        ui.add(
+                            egui::Slider::new(value, param.min..=param.max)
+                                .step_by(1.0)
+                                .text(param.label),
+                        );
+                    }
+                    ParamControl::Drag(speed) => {
+                        ui.label(param.label);
+                        ui.add(
+                            egui::DragValue::new(value)
+                                .speed(speed)
+                                .clamp_range(param.min..=param.max),
+                        );
+                    }
+                }
+            });
+        });
+    }
+    section
+}
+
+fn camera_section() -> PanelSection {
+    PanelSection::new("Camera", SectionStyle::Heading)
+        .control("Center X", |app, ui| {
+            ui.horizontal(|ui| {
+                lock_toggle(ui, &mut app.proj.locks, TrackKind::CenterX);
+                ranged_slider(
+                    ui,
+                    RangedParam::CenterX,
+                    &mut app.proj.camera.center.re,
+                    "Center X",
+                    app.settings.expert_ranges,
+                );
+            });
+        })
+        .control("Center Y", |app, ui| {
+            ui.horizontal(|ui| {
+                lock_toggle(ui, &mut app.proj.locks, TrackKind::CenterY);
+                ranged_slider(
+                    ui,
+                    RangedParam::CenterY,
+                    &mut app.proj.camera.center.im,
+                    "Center Y",
+                    app.settings.expert_ranges,
+                );
+            });
+        })
+        .control("Scale (zoom)", |app, ui| {
+            ui.horizontal(|ui| {
+                lock_toggle(ui, &mut app.proj.locks, TrackKind::Zoom);
+                ranged_slider(
+                    ui,
+                    RangedParam::Scale,
+                    &mut app.proj.camera.scale,
+                    "Scale (zoom)",
+                    app.settings.expert_ranges,
+                );
+            });
+        })
+        .control("Rotation", |app, ui| {
+            ui.horizontal(|ui| {
+                lock_toggle(ui, &mut app.proj.locks, TrackKind::Rotation);
+                ranged_slider(
+                    ui,
+                    RangedParam::Rotation,
+                    &mut app.proj.camera.rotation,
+                    "Rotation",
+                    app.settings.expert_ranges,
+                );
+            });
+        })
+}
+
+fn color_section(app: &MatterhornApp) -> PanelSection {
+    PanelSection::new("Color & FX", SectionStyle::Heading)
+        .control("Palette phase", |app, ui| {
+            ui.horizontal(|ui| {
+                lock_toggle(ui, &mut app.proj.locks, TrackKind::Palette);
+                ranged_slider(
+                    ui,
+                    RangedParam::PalettePhase,
+                    &mut app.proj.fractal.palette_phase,
+                    "Palette phase",
+                    app.settings.expert_ranges,
+                );
+            });
+        })
+        .control("Palette cycle (/s)", |app, ui| {
+            ranged_slider(
+                ui,
+                RangedParam::PaletteCycleSpeed,
+                &mut app.proj.fractal.palette_cycle_speed,
+                "Palette cycle (/s)",
+                app.settings.expert_ranges,
+            )
+            .on_hover_text(
+                "Cycles the palette continuously on top of the palette track. Looping \
+                 animations round it to whole cycles per loop.",
+            );
+            let speed = app.proj.fractal.palette_cycle_speed;
+            let rate = app.proj.anim.palette_cycle_rate(speed);
+            if rate != speed {
+                ui.weak(format!(
+                    "Looping: {} cycles per loop ({rate:.3}/s)",
+                    (rate * app.proj.anim.duration).round()
+                ));
+            }
+        })
+        .control_if(app.proj.locks.any(), "Locked tracks", |app, ui| {
+            lock_export_note(ui, &mut app.proj.locks);
+        })
+        .control("Palette repeat", |app, ui| {
+            ranged_slider(
+                ui,
+                RangedParam::PaletteRepeat,
+                &mut app.proj.fractal.palette_repeat,
+                "Palette repeat",
+                app.settings.expert_ranges,
+            )
+            .on_hover_text(
+                "Iterations per trip through the palette. Changing Max Iter keeps colors \
+                 in place.",
+            );
+        })
+        .control("Equalize palette histogram", |app, ui| {
+            let fractal = &mut app.proj.fractal;
+            ui.checkbox(&mut fractal.equalize, "Equalize palette")
+                .on_hover_text(
+                    "Spreads each frame's iteration counts evenly over one trip through the \
+                     palette, so deep zooms use all of its colors. Replaces Palette repeat.",
+                );
+            if fractal.equalize && fractal.estimates_distance() {
+                ui.weak("No effect with distance-estimate coloring");
+            }
+        })
+        .control("Exposure", |app, ui| {
+            ranged_slider(
+                ui,
+                RangedParam::Exposure,
+                &mut app.proj.fractal.exposure,
+                "Exposure",
+                app.settings.expert_ranges,
+            );
+        })
+        .control("Gamma", |app, ui| {
+            ranged_slider(
+                ui,
+                RangedParam::Gamma,
+                &mut app.proj.fractal.gamma,
+                "Gamma",
+                app.settings.expert_ranges,
+            );
+        })
+        .control("Coloring mode distance estimate stripes", |app, ui| {
+            coloring_ui(ui, &mut app.proj.fractal);
+        })
+        .control("Dwell bands contours", |app, ui| {
+            dwell_bands_ui(ui, &mut app.proj.fractal);
+        })
+        .control("Exterior", |app, ui| {
+            exterior_ui(ui, &mut app.proj.fractal);
+        })
+        .control("Interior coloring", |app, ui| {
+            interior_ui(ui, &mut app.proj.fractal);
+        })
+        .control("Supersampling anti-aliasing", |app, ui| {
+            supersample_ui(ui, &mut app.proj.fractal);
+        })
+}
+
+fn orbit_trap_section() -> PanelSection {
+    PanelSection::new("Orbit Trap", SectionStyle::Collapsing)
+        .control("Orbit trap enabled", |app, ui| {
+            ui.checkbox(&mut app.proj.fractal.orbit.enabled, "Enabled");
+        })
+        .control("Trap kind", |app, ui| {
+            let orbit = &mut app.proj.fractal.orbit;
+            ui.horizontal(|ui| {
+                ui.label("Kind");
+                ui.selectable_value(&mut orbit.kind, OrbitTrapKind::Point, "Point");
+                ui.selectable_value(&mut orbit.kind, OrbitTrapKind::Circle, "Circle");
+                ui.selectable_value(&mut orbit.kind, OrbitTrapKind::Cross, "Cross");
+            });
+        })
+        .control("Trap radius", |app, ui| {
+            ranged_slider(
+                ui,
+                RangedParam::TrapRadius,
+                &mut app.proj.fractal.orbit.radius,
+                "Radius",
+                app.settings.expert_ranges,
+            );
+        })
+        .control("Trap softness", |app, ui| {
+            ranged_slider(
+                ui,
+                RangedParam::TrapSoftness,
+                &mut app.proj.fractal.orbit.softness,
+                "Softness",
+                app.settings.expert_ranges,
+            );
+        })
+        .control("Trap point", |app, ui| {
+            let point = &mut app.proj.fractal.orbit.point;
+            ui.horizontal(|ui| {
+                ui.label("Point Re");
+                ui.add(egui::DragValue::new(&mut point.re).speed(0.01));
+            });
+            ui.horizontal(|ui| {
+                ui.label("Point Im");
+                ui.add(egui::DragValue::new(&mut point.im).speed(0.01));
+            });
+        })
+        .control("Trap color", |app, ui| {
+            rgb_edit_button(ui, &mut app.proj.fractal.orbit.color)
+                .on_hover_text("Mixed in after exposure and gamma, so it shows as picked");
+        })
+}
+
+fn palette_section() -> PanelSection {
+    let section = PanelSection::new("Palette", SectionStyle::Collapsing)
+        .control("Palette presets flip cycle", |app, ui| {
+            let preset_base = app.proj.fractal.clone();
+            palette_presets_ui(
+                ui,
+                &mut app.proj.fractal.palette,
+                &preset_base,
+                &mut app.thumbnails,
+            );
+        })
+        .control("Pickers", |app, ui| {
+            palette_pickers_ui(ui, &mut app.proj.palette_editing);
+            ui.separator();
+        })
+        .control("Palette stops", |app, ui| {
+            let tone = app.proj.fractal.clone();
+            palette_stops_ui(
+                ui,
+                &mut app.proj.fractal.palette,
+                app.proj.palette_editing,
+                &mut app.proj.palette_stop_drag,
+                &tone,
+            );
+        });
+    #[cfg(not(target_arch = "wasm32"))]
+    let section = section.control("Palette files .ahpal", |app, ui| {
+        palette_files_ui(ui, &mut app.dialogs, &app.settings.last_palette_dir);
+    });
+    section
+}
+
+fn export_section(app: &MatterhornApp) -> PanelSection {
+    let section = PanelSection::new("Export", SectionStyle::Collapsing)
+        .control("Size width height", |app, ui| {
+            let export = &mut app.proj.export;
+            ui.add(
+                egui::DragValue::new(&mut export.width)
+                    .speed(16)
+                    .clamp_range(1..=MAX_FRAME_SIDE)
+                    .suffix(" px"),
+            );
+            ui.add(
+                egui::DragValue::new(&mut export.height)
+                    .speed(16)
+                    .clamp_range(1..=MAX_FRAME_SIDE)
+                    .suffix(" px"),
+            );
+        })
+        .control("Duration (s)", |app, ui| {
+            ranged_slider(
+                ui,
+                RangedParam::Duration,
+                &mut app.proj.export.duration,
+                "Duration (s)",
+                app.settings.expert_ranges,
+            );
+        })
+        .control("FPS", |app, ui| {
+            ranged_slider(
+                ui,
+                RangedParam::Fps,
+                &mut app.proj.export.fps,
+                "FPS",
+                app.settings.expert_ranges,
+            );
+        })
+        .control("Quality/CRF", |app, ui| {
+            ranged_slider(
+                ui,
+                RangedParam::Crf,
+                &mut app.proj.export.crf,
+                "Quality/CRF",
+                app.settings.expert_ranges,
+            );
+        })
+        .control("Export quality profile", |app, ui| {
+            ui.horizontal(|ui| {
+                ui.label("Quality profile");
+                quality_combo(
+                    ui,
+                    "export_quality",
+                    &mut app.proj.export.quality,
+                    &app.settings.quality_profiles,
+                )
+                .on_hover_text("Iterations, sample pattern and tiles exports render with");
+                if ui.small_button("✎").clicked() {
+                    app.quality.open(QualityTarget::Export);
+                }
+            });
+            if app.proj.export.quality.tile_size == 0 {
+                let size = (app.proj.export.width, app.proj.export.height);
+                ui.horizontal(|ui| {
+                    match app.settings.tile_tuning.get(app.renderer.name(), size) {
+                        Some(tile) => ui.weak(format!("Auto tiles: {tile} px")),
+                        None => ui.weak("Auto tiles: calibrated on export"),
+                    };
+                    if ui
+                        .small_button("Calibrate")
+                        .on_hover_text("Time tile sizes on this frame at the export size now")
+                        .clicked()
+                    {
+                        app.calibrate_tiles();
+                    }
+                });
+            }
+        })
+        .control("Export viewport", |app, ui| {
+            let export = &mut app.proj.export;
+            egui::ComboBox::from_label("Viewport")
+                .selected_text(export.viewport.label())
+                .show_ui(ui, |ui| {
+                    for viewport in ExportViewport::ALL {
+                        ui.selectable_value(&mut export.viewport, viewport, viewport.label());
+                    }
+                })
+                .response
+                .on_hover_text("Which view exports render; side by side puts Julia on the right");
+            let side_by_side = export.viewport == ExportViewport::SideBySide;
+            if side_by_side && !export.passes.selected().is_empty() {
+                let error = ExportError::SideBySidePasses.to_string();
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+        })
+        .control("Frame memory", |app, ui| {
+            let export = &mut app.proj.export;
+            ui.add(
+                egui::DragValue::new(&mut export.frame_memory_mb)
+                    .clamp_range(64..=4096)
+                    .suffix(" MB frame memory"),
+            )
+            .on_hover_text("Larger frames are rendered in bands and streamed to disk");
+            if let Err(e) = export.frame_mode() {
+                ui.colored_label(ui.visuals().error_fg_color, e.to_string());
+            }
+        })
+        .control("Codec", |app, ui| {
+            let export = &mut app.proj.export;
+            ui.horizontal(|ui| {
+                ui.label("Codec");
+                for codec in [
+                    VideoCodec::H264,
+                    VideoCodec::ProRes,
+                    VideoCodec::ProRes4444,
+                    VideoCodec::Vp9,
+                    VideoCodec::Av1,
+                ] {
+                    ui.selectable_value(&mut export.codec, codec, codec.label());
+                }
+            });
+            if let Some(warning) = export.codec.alpha_warning(&app.proj.fractal) {
+                ui.colored_label(ui.visuals().warn_fg_color, warning);
+            }
+        })
+        .control_if(
+            app.proj.fractal.equalize,
+            "Equalize smoothing frames",
+            |app, ui| {
+                ui.add(
+                    egui::DragValue::new(&mut app.proj.export.equalize_smoothing)
+                        .clamp_range(1..=120)
+                        .prefix("equalize over ")
+                        .suffix(" frames"),
+                )
+                .on_hover_text(
+                    "Averages each frame's palette equalization with the frames before it, \
+                     so colors don't pump as the zoom moves",
+                );
+            },
+        )
+        .control("Burn in zoom depth", |app, ui| {
+            ui.checkbox(&mut app.proj.export.burn_in_zoom, "Burn in zoom depth")
+                .on_hover_text("Stamps the magnification and pixel size into every frame");
+        })
+        .control("Adaptive anti-aliasing", |app, ui| {
+            let aa = &mut app.proj.export.adaptive_aa;
+            ui.checkbox(&mut aa.enabled, "Adaptive anti-aliasing")
+                .on_hover_text(
+                    "Renders extra samples only where neighboring pixels differ; \
+                 the preview isn't affected",
+                );
+            if !aa.enabled {
+                return;
+            }
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::DragValue::new(&mut aa.threshold)
+                        .clamp_range(0.0..=1.0)
+                        .speed(0.005)
+                        .prefix("threshold "),
+                )
+                .on_hover_text("How far a pixel can differ from a neighbor before it's refined");
+                ui.add(
+                    egui::DragValue::new(&mut aa.max_samples)
+                        .clamp_range(MIN_REFINE_SAMPLES..=MAX_REFINE_SAMPLES)
+                        .suffix(" samples"),
+                )
+                .on_hover_text("Most samples a refined pixel takes");
+            });
+            if !aa.applies_to(&app.proj.fractal) {
+                ui.weak("No effect with band contours or as much supersampling");
+            }
+        })
+        .control("Stylize", |app, ui| {
+            let stylize = &mut app.proj.export.stylize;
+            ui.checkbox(&mut stylize.enabled, "Stylize")
+                .on_hover_text("Reduces every frame to a few colors, drawn in chunky pixels");
+            if !stylize.enabled {
+                return;
+            }
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::DragValue::new(&mut stylize.colors)
+                        .clamp_range(2..=MAX_STYLE_COLORS)
+                        .suffix(" colors"),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut stylize.pixel_size)
+                        .clamp_range(1..=MAX_PIXEL_SIZE)
+                        .suffix(" px pixels"),
+                )
+                .on_hover_text("Side of the square of output pixels each rendered pixel fills");
+            });
+            egui::ComboBox::from_label("Colors from")
+                .selected_text(stylize.palette.label())
+                .show_ui(ui, |ui| {
+                    for palette in StylePalette::ALL {
+                        ui.selectable_value(&mut stylize.palette, palette, palette.label());
+                    }
+                })
+                .response
+                .on_hover_text(
+                    "Per frame picks the colors that fit each frame best; the project palette \
+                     keeps them the same in every frame",
+                );
+            egui::ComboBox::from_label("Dither")
+                .selected_text(stylize.dither.label())
+                .show_ui(ui, |ui| {
+                    for dither in Dither::ALL {
+                        ui.selectable_value(&mut stylize.dither, dither, dither.label());
+                    }
+                });
+            ui.checkbox(&mut app.stylize_preview, "Show in preview");
+            if !app.proj.export.passes.selected().is_empty() {
+                let error = ExportError::StylizedPasses.to_string();
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+        })
+        .control("Poster at", |app, ui| {
+            let export = &mut app.proj.export;
+            ui.horizontal(|ui| {
+                let mut chosen = export.poster_time.is_some();
+                if ui
+                    .checkbox(&mut chosen, "Poster at")
+                    .on_hover_text(
+                        "Frame saved as a PNG next to the video and embedded in saved projects. \
+                         Unset, the most detailed of a few evenly spread frames is used.",
+                    )
+                    .changed()
+                {
+                    export.poster_time = chosen.then_some(0.0);
+                }
+                match &mut export.poster_time {
+                    Some(time) => {
+                        ui.add(
+                            egui::DragValue::new(time)
+                                .speed(0.05)
+                                .clamp_range(0.0..=export.duration)
+                                .suffix(" s"),
+                        );
+                    }
+                    None => {
+                        ui.weak("most detailed frame");
+                    }
+                }
+            });
+        });
+    #[cfg(not(target_arch = "wasm32"))]
+    let section = section
+        .control("Pick output", |app, ui| {
+            if ui.button("Pick output").clicked() {
+                app.dialogs.save(
+                    ui.ctx(),
+                    DialogPurpose::ExportPath,
+                    dialog_in(&app.settings.last_export_dir)
+                        .add_filter("Video", &["mp4", "mov", "webm", "mkv"]),
+                );
+            }
+            ui.label(format!("Output: {}", app.proj.export.out_path.display()));
+        })
+        .control("Bake animation", |app, ui| {
+            if ui
+                .button("Bake animation…")
+                .on_hover_text(
+                    "Writes every animated parameter, sampled once per export frame, to CSV \
+                     or JSON for other tools. Import in the top bar reads it back as keyframes.",
+                )
+                .clicked()
+            {
+                app.dialogs.save(
+                    ui.ctx(),
+                    DialogPurpose::BakeAnimation,
+                    dialog_in(&app.settings.last_export_dir)
+                        .add_filter("CSV", &["csv"])
+                        .add_filter("JSON", &["json"])
+                        .set_file_name("anim.csv"),
+                );
+            }
+        })
+        .control("Contact sheet", |app, ui| {
+            let sheet = &mut app.proj.export.contact_sheet;
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut sheet.cols).clamp_range(1..=32));
+                ui.label("×");
+                ui.add(egui::DragValue::new(&mut sheet.rows).clamp_range(1..=32));
+                ui.label("cells of");
+                ui.add(egui::DragValue::new(&mut sheet.cell.0).clamp_range(16..=3840));
+                ui.label("×");
+                ui.add(egui::DragValue::new(&mut sheet.cell.1).clamp_range(16..=2160));
+            });
+            let running = app.export_job.is_some();
+            if ui
+                .add_enabled(!running, egui::Button::new("Contact sheet…"))
+                .on_hover_text(
+                    "Renders frames spread over the animation into one PNG grid, each labeled \
+                     with its time, to look over before a full export",
+                )
+                .clicked()
+            {
+                app.dialogs.save(
+                    ui.ctx(),
+                    DialogPurpose::ContactSheet,
+                    dialog_in(&app.settings.last_export_dir)
+                        .add_filter("PNG", &["png"])
+                        .set_file_name("contact_sheet.png"),
+                );
+            }
+        })
+        .control("Passes", |app, ui| {
+            let export = &mut app.proj.export;
+            ui.horizontal_wrapped(|ui| {
+                ui.label("Passes");
+                for pass in AovPass::ALL {
+                    ui.checkbox(export.passes.flag_mut(pass), pass.label());
+                }
+            });
+            if !export.passes.selected().is_empty() {
+                ui.horizontal(|ui| {
+                    ui.label("Pass format");
+                    for format in [AovFormat::Exr, AovFormat::Png] {
+                        ui.selectable_value(&mut export.passes.format, format, format.label());
+                    }
+                });
+                ui.label(format!("Passes: {}", export.passes_dir().display()));
+            }
+        })
+        .control_if(app.proj.export.audio.is_some(), "Audio", |app, ui| {
+            let export = &mut app.proj.export;
+            if let Some(audio) = export.audio.as_ref().map(|p| p.display().to_string()) {
+                ui.horizontal(|ui| {
+                    ui.label(format!("Audio: {audio}"));
+                    if ui
+                        .small_button("✕")
+                        .on_hover_text("Export without sound")
+                        .clicked()
+                    {
+                        export.audio = None;
+                    }
+                });
+            }
+        });
+    #[cfg(target_arch = "wasm32")]
+    let section = section.control("Web export", |_, ui| {
+        ui.label(WEB_EXPORT_NOTE);
+    });
+    section
+}
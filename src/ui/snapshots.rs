@@ -0,0 +1,139 @@
+//! Clipboard copies and saved PNG snapshots of the current view.
+
+use super::*;
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SnapshotAction {
+    Copy,
+    Save,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn snapshot_ui(
+    ui: &mut egui::Ui,
+    settings: &mut AppSettings,
+    dialogs: &mut Dialogs,
+    copy_shortcut: &egui::KeyboardShortcut,
+) -> Option<SnapshotAction> {
+    let mut action = None;
+    if ui
+        .button("Copy image")
+        .on_hover_text(ui.ctx().format_shortcut(copy_shortcut))
+        .clicked()
+    {
+        action = Some(SnapshotAction::Copy);
+    }
+    if ui
+        .button("Save snapshot")
+        .on_hover_text(format!(
+            "Write a PNG to {}",
+            settings.snapshot_dir().display()
+        ))
+        .clicked()
+    {
+        action = Some(SnapshotAction::Save);
+    }
+    ui.menu_button("⚙", |ui| {
+        ui.checkbox(&mut settings.snapshot_use_view_size, "Use viewport size");
+        ui.add_enabled_ui(!settings.snapshot_use_view_size, |ui| {
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::DragValue::new(&mut settings.snapshot_width)
+                        .clamp_range(16..=16_384)
+                        .suffix(" px"),
+                );
+                ui.label("×");
+                ui.add(
+                    egui::DragValue::new(&mut settings.snapshot_height)
+                        .clamp_range(16..=16_384)
+                        .suffix(" px"),
+                );
+            });
+        });
+        ui.label(format!("Folder: {}", settings.snapshot_dir().display()));
+        if ui.button("Choose folder…").clicked() {
+            dialogs.pick_folder(
+                ui.ctx(),
+                DialogPurpose::SnapshotFolder,
+                dialog_in(&settings.snapshot_dir),
+            );
+            ui.close_menu();
+        }
+    });
+    action
+}
+
+/// Renders the current frame at `size` in `quality`'s tiles, keeping the framing of a view
+/// `view_width` points wide.
+pub(crate) fn render_still(
+    frame: &EvaluatedFrame,
+    renderer: &mut dyn Renderer,
+    quality: &QualityProfile,
+    size: (u32, u32),
+    view_width: f32,
+) -> Result<Vec<u8>, RenderError> {
+    let cam = Camera {
+        scale: frame.camera.scale * f64::from(size.0) / f64::from(view_width.max(1.0)),
+        ..frame.camera.clone()
+    };
+    render_image(size, &frame.fractal, &cam, renderer, quality.tile_size)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn copy_image_to_clipboard(
+    size: (u32, u32),
+    pixels: Vec<u8>,
+) -> Result<(), arboard::Error> {
+    arboard::Clipboard::new()?.set_image(arboard::ImageData {
+        width: size.0 as usize,
+        height: size.1 as usize,
+        bytes: pixels.into(),
+    })
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn save_snapshot_png(
+    dir: &Path,
+    size: (u32, u32),
+    pixels: Vec<u8>,
+) -> Result<PathBuf, ExportError> {
+    fs::create_dir_all(dir)?;
+    // Snapshots taken within the same second get `_2`, `_3`, … rather than overwriting.
+    let stamp = utc_timestamp();
+    let path = (1..)
+        .map(|n| match n {
+            1 => dir.join(format!("snapshot_{stamp}.png")),
+            n => dir.join(format!("snapshot_{stamp}_{n}.png")),
+        })
+        .find(|path| !path.exists())
+        .expect("some numbered snapshot name is free");
+    ImageBuffer::<Rgba<u8>, _>::from_raw(size.0, size.1, pixels)
+        .expect("pixel buffer matches snapshot size")
+        .save(&path)?;
+    Ok(path)
+}
+
+/// `YYYYMMDD-HHMMSS` in UTC, so file names sort chronologically.
+pub(crate) fn utc_timestamp() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let (days, rem) = ((secs / 86_400) as i64, secs % 86_400);
+    // Civil-from-days conversion (proleptic Gregorian calendar).
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}{month:02}{day:02}-{:02}{:02}{:02}",
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
@@ -0,0 +1,75 @@
+//! Thumbnail textures shared by the preset menu and the explore history.
+
+use super::*;
+
+/// Thumbnail textures kept on the GPU before the least recently shown are dropped.
+pub(crate) const THUMBNAIL_TEXTURES: usize = 128;
+pub(crate) const PRESET_THUMB: (u32, u32) = (72, 48);
+
+/// Background thumbnail renders and the textures they were uploaded to.
+pub(crate) struct Thumbnails {
+    service: ThumbnailService,
+    textures: ThumbnailCache<TextureHandle>,
+}
+
+impl Default for Thumbnails {
+    fn default() -> Self {
+        Self {
+            service: ThumbnailService::new(),
+            textures: ThumbnailCache::new(THUMBNAIL_TEXTURES),
+        }
+    }
+}
+
+impl Thumbnails {
+    /// The texture for `request`, or `None` while it is rendering.
+    pub(crate) fn get(&mut self, request: ThumbnailRequest) -> Option<TextureHandle> {
+        if let Some(tex) = self.textures.get(request.key()) {
+            return Some(tex.clone());
+        }
+        self.service.request(request);
+        None
+    }
+
+    /// Uploads finished thumbnails and drops requests nothing showed this frame. Call
+    /// after all panels are drawn.
+    pub(crate) fn end_frame(&mut self, ctx: &egui::Context) {
+        self.service.cancel_unrequested();
+        for thumb in self.service.poll() {
+            let image = ColorImage::from_rgba_unmultiplied(
+                [thumb.size.0 as usize, thumb.size.1 as usize],
+                &thumb.pixels,
+            );
+            let tex = ctx.load_texture("thumbnail", image, Default::default());
+            self.textures.insert(thumb.key, tex);
+        }
+        if self.service.pending() > 0 {
+            ctx.request_repaint_after(Duration::from_millis(30));
+        }
+    }
+}
+
+/// Clickable thumbnail, or a placeholder of the same size while it renders.
+pub(crate) fn thumbnail_button(
+    ui: &mut egui::Ui,
+    thumbnails: &mut Thumbnails,
+    request: ThumbnailRequest,
+) -> egui::Response {
+    let size = vec2(request.size.0 as f32, request.size.1 as f32);
+    match thumbnails.get(request) {
+        Some(tex) => ui.add(egui::ImageButton::new((tex.id(), size))),
+        None => ui.add_sized(size, egui::Button::new("…")),
+    }
+}
+
+/// The current formula's overview in `preset`'s colors, with the gradient underneath.
+pub(crate) fn preset_thumbnail(
+    fractal: &FractalParams,
+    preset: &PalettePreset,
+) -> ThumbnailRequest {
+    let mut params = fractal.clone();
+    apply_palette_preset(&mut params.palette, preset);
+    params.palette_phase = 0.0;
+    let camera = formula(params.kind).default_camera(PRESET_THUMB.0 as f32);
+    ThumbnailRequest::new(&params, &camera, PRESET_THUMB).with_gradient()
+}
@@ -0,0 +1,1031 @@
+//! Timeline panel: dope sheet, curve editor, and key inspector.
+
+use super::*;
+
+pub(crate) fn track_color(track: TrackKind) -> Color32 {
+    match track {
+        TrackKind::Zoom => Color32::from_rgb(120, 200, 255),
+        TrackKind::Palette => Color32::from_rgb(230, 120, 230),
+        TrackKind::CenterX => Color32::from_rgb(130, 220, 120),
+        TrackKind::CenterY => Color32::from_rgb(240, 200, 90),
+        TrackKind::Rotation => Color32::from_rgb(255, 140, 100),
+        TrackKind::JuliaRe => Color32::from_rgb(110, 230, 210),
+        TrackKind::JuliaIm => Color32::from_rgb(170, 150, 255),
+    }
+}
+
+/// The timeline panel. `collapsed` lists the dope-sheet groups folded shut, a view
+/// preference kept in the app settings.
+pub(crate) fn timeline_ui(
+    ui: &mut egui::Ui,
+    editor: &mut TimelineEditor,
+    proj: &mut Project,
+    collapsed: &mut Vec<TrackGroup>,
+) {
+    let Project {
+        anim,
+        camera,
+        fractal,
+        locks,
+        ..
+    } = proj;
+    let initial_cursor = anim.timeline_time();
+    let mut timeline_cursor = initial_cursor;
+    ui.horizontal(|ui| {
+        for view in [TimelineView::DopeSheet, TimelineView::Curves] {
+            ui.selectable_value(&mut editor.view, view, view.label());
+        }
+        ui.separator();
+        ui.add(egui::Slider::new(&mut anim.duration, 0.5..=120.0).text("Duration"));
+        anim.kf_zoom.clamp_all(anim.duration);
+        anim.kf_palette.clamp_all(anim.duration);
+        anim.kf_center_x.clamp_all(anim.duration);
+        anim.kf_center_y.clamp_all(anim.duration);
+        anim.kf_rotation.clamp_all(anim.duration);
+        anim.kf_julia_re.clamp_all(anim.duration);
+        anim.kf_julia_im.clamp_all(anim.duration);
+        anim.kf_trap_color.clamp_all(anim.duration);
+        ui.add(egui::Slider::new(&mut anim.fps, 12..=240).text("Preview FPS"));
+        ui.checkbox(&mut anim.looping, "Loop playback");
+        if ui.button("Add key @t").clicked() {
+            for group in TrackGroup::ALL {
+                // Only the parameters the frame shows: the trap color with the trap on,
+                // `c` for formulas that read it.
+                let shown = match group {
+                    TrackGroup::Traps => fractal.orbit.enabled,
+                    TrackGroup::Fractal => fractal.uses(ParamField::JuliaRe),
+                    TrackGroup::Camera | TrackGroup::Color => true,
+                };
+                if shown {
+                    anim.key_group(group, timeline_cursor, camera, fractal);
+                }
+            }
+        }
+    });
+
+    let current_scale = anim.sample_zoom(anim.current_times().1, camera.scale);
+    ui.horizontal(|ui| {
+        if ui.button("Preset: Endless Zoom").clicked() {
+            anim.apply_endless_zoom_preset(camera.scale);
+            timeline_cursor = 0.0;
+        }
+        if let Some(zoom) = anim.zoom_forever.as_mut() {
+            ui.label("Speed");
+            ui.add(egui::Slider::new(&mut zoom.speed, 0.5..=0.995).text("scale/sec"));
+            ui.checkbox(&mut zoom.reverse, "Reverse direction");
+            ui.add(
+                egui::Slider::new(&mut zoom.rotation_speed, -0.5..=0.5)
+                    .text("Drift (rad/s)"),
+            )
+            .on_hover_text("Slow continuous rotation while zooming; 0 keeps the view upright.");
+            let lock_resp = ui
+                .checkbox(&mut zoom.lock_repeating_spot, "Auto-place repeating spot")
+                .on_hover_text("Snap to a self-similar Seahorse Valley minibrot so the zoom keeps repeating.");
+            if lock_resp.changed() && zoom.lock_repeating_spot {
+                snap_camera_to_repeating_spot(camera, zoom);
+                editor.picking_zoom_target = false;
+                timeline_cursor = 0.0;
+            }
+            ui.toggle_value(&mut editor.picking_zoom_target, "Pick zoom target")
+                .on_hover_text("Click the viewport to zoom into that point instead of the center.");
+            if let Some(target) = zoom.target {
+                ui.weak(format!("→ {:.6}, {:.6}", target.re, target.im));
+                if ui.small_button("Clear target").clicked() {
+                    zoom.target = None;
+                }
+            }
+            if zoom.lock_repeating_spot
+                && ui
+                    .button("Re-center to repeating spot")
+                    .on_hover_text("Move the camera back to the repeating minibrot and reset the zoom start scale.")
+                    .clicked()
+            {
+                snap_camera_to_repeating_spot(camera, zoom);
+                timeline_cursor = 0.0;
+            }
+            if ui.button("Re-base").on_hover_text("Use current zoom as the new starting scale").clicked() {
+                zoom.start_scale = current_scale.max(0.0001);
+                timeline_cursor = 0.0;
+            }
+            if ui.button("Disable").clicked() {
+                anim.zoom_forever = None;
+            }
+        }
+    });
+    if anim.zoom_forever.is_none() {
+        editor.picking_zoom_target = false;
+    }
+    if anim.zoom_forever.is_some() {
+        ui.small("Endless zoom keeps shrinking scale beyond the timeline duration.");
+    }
+
+    let julia = formula(fractal.kind).julia_mode();
+    ui.horizontal(|ui| {
+        if ui
+            .add_enabled(julia, egui::Button::new("Preset: Cardioid Orbit"))
+            .on_hover_text(
+                "Move the Julia constant around the main cardioid of the Mandelbrot set.",
+            )
+            .on_disabled_hover_text("Only for Julia-mode fractals.")
+            .clicked()
+        {
+            anim.apply_julia_orbit_preset();
+            timeline_cursor = 0.0;
+        }
+        let duration = anim.duration;
+        if let Some(orbit) = anim.julia_orbit.as_mut() {
+            ui.add(egui::Slider::new(&mut orbit.speed, -1.0..=1.0).text("loops/sec"));
+            let mut loops = orbit.speed * duration;
+            if ui
+                .add(
+                    egui::DragValue::new(&mut loops)
+                        .speed(0.05)
+                        .prefix("Loops: "),
+                )
+                .on_hover_text("Loops around the cardioid over the timeline duration.")
+                .changed()
+            {
+                orbit.speed = loops / duration;
+            }
+            ui.add(egui::Slider::new(&mut orbit.phase, 0.0..=1.0).text("Phase (turns)"));
+            if ui.button("Disable").clicked() {
+                anim.julia_orbit = None;
+            }
+        }
+    });
+    if anim.julia_orbit.is_some() {
+        ui.small(if julia {
+            "The cardioid orbit overrides the Julia constant in the preview and export."
+        } else {
+            "The cardioid orbit only applies to Julia-mode fractals."
+        });
+    }
+
+    let current_values = TrackKind::ALL.map(|track| track.value(camera, fractal));
+    let selected_before = anim.selection.as_ref().map(|sel| (sel.track, sel.index));
+    match editor.view {
+        TimelineView::DopeSheet => {
+            for group in TrackGroup::ALL.into_iter().filter(|g| !g.is_empty()) {
+                if track_group_header(ui, group, collapsed, locks) {
+                    anim.key_group(group, timeline_cursor, camera, fractal);
+                }
+                if collapsed.contains(&group) {
+                    continue;
+                }
+                for track in group.tracks() {
+                    let keys = match track {
+                        TrackKind::Zoom => &mut anim.kf_zoom,
+                        TrackKind::Palette => &mut anim.kf_palette,
+                        TrackKind::CenterX => &mut anim.kf_center_x,
+                        TrackKind::CenterY => &mut anim.kf_center_y,
+                        TrackKind::Rotation => &mut anim.kf_rotation,
+                        TrackKind::JuliaRe => &mut anim.kf_julia_re,
+                        TrackKind::JuliaIm => &mut anim.kf_julia_im,
+                    };
+                    let action = track_timeline_row(
+                        ui,
+                        track,
+                        track.label(),
+                        track.value(camera, fractal),
+                        anim.duration,
+                        &mut timeline_cursor,
+                        &mut anim.selection,
+                        keys,
+                    );
+                    match action {
+                        Some(TrackAction::ClearKeys) => {
+                            editor.confirm_clear = Some(ClearTarget::Track(track));
+                        }
+                        Some(TrackAction::ResetParameter) => {
+                            track.reset_value(camera, fractal);
+                        }
+                        None => {}
+                    }
+                }
+                if group.has_trap_color() {
+                    let action =
+                        trap_color_row(ui, editor, anim, fractal.orbit.color, &mut timeline_cursor);
+                    match action {
+                        Some(TrackAction::ClearKeys) => {
+                            editor.confirm_clear = Some(ClearTarget::TrapColor);
+                        }
+                        Some(TrackAction::ResetParameter) => {
+                            fractal.orbit.color = OrbitTrap::default().color;
+                        }
+                        None => {}
+                    }
+                }
+            }
+        }
+        TimelineView::Curves => {
+            curve_editor_ui(ui, editor, anim, current_values, &mut timeline_cursor);
+        }
+    }
+    // One key is selected at a time across the value and color rows.
+    let selected_after = anim.selection.as_ref().map(|sel| (sel.track, sel.index));
+    if selected_after.is_some() && selected_after != selected_before {
+        editor.color_key = None;
+    }
+    if (timeline_cursor - initial_cursor).abs() > f32::EPSILON {
+        anim.set_timeline_time(timeline_cursor);
+    }
+
+    if let Some(sel) = anim.selection.clone() {
+        let duration = anim.duration;
+        let segment = anim
+            .track(sel.track)
+            .segment(sel.index)
+            .map(|(a, b)| (a.t, b.t));
+        // Playhead position within the segment, shown as a dot while playing.
+        let progress = segment.filter(|_| anim.playing).and_then(|(t0, t1)| {
+            let t = anim.timeline_time();
+            (t >= t0 && t <= t1 && t1 > t0).then(|| (t - t0) / (t1 - t0))
+        });
+        let keys = anim.track_mut(sel.track);
+        if let Some(key) = keys.keys.get_mut(sel.index) {
+            ui.separator();
+            let mut time_edited = false;
+            ui.horizontal(|ui| {
+                ui.label(format!("Editing {} key", sel.track.label()));
+                time_edited = ui
+                    .add(
+                        egui::DragValue::new(&mut key.t)
+                            .clamp_range(0.0..=duration)
+                            .speed(0.01)
+                            .suffix(" s"),
+                    )
+                    .changed();
+                ui.label("Value");
+                let speed = key.v.abs().max(0.01) * 0.005;
+                ui.add(egui::DragValue::new(&mut key.v).speed(speed));
+            });
+            let mut delete = false;
+            ui.horizontal(|ui| {
+                ui.vertical(|ui| {
+                    egui::ComboBox::from_label("Easing")
+                        .selected_text(key.easing.label())
+                        .show_ui(ui, |ui| {
+                            for easing in Easing::ALL {
+                                ui.horizontal(|ui| {
+                                    easing_plot(ui, easing, None, vec2(16.0, 16.0));
+                                    ui.selectable_value(&mut key.easing, easing, easing.label());
+                                });
+                            }
+                        });
+                    delete = ui.button("Delete key").clicked();
+                });
+                if segment.is_some() {
+                    easing_plot(ui, key.easing, progress, vec2(80.0, 80.0));
+                } else {
+                    ui.small("Last key: holds its value.");
+                }
+            });
+            if delete {
+                keys.keys.remove(sel.index);
+                anim.selection = None;
+            } else if time_edited {
+                let index = keys.resort(sel.index);
+                anim.selection = Some(SelectedKey { index, ..sel });
+            }
+        } else {
+            anim.selection = None;
+        }
+    }
+
+    if let Some(index) = editor.color_key {
+        trap_color_key_inspector(ui, editor, anim, index);
+    }
+    confirm_clear_window(ui.ctx(), editor, anim);
+}
+
+/// What a track row's context menu asked for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TrackAction {
+    ClearKeys,
+    ResetParameter,
+}
+
+/// Track whose keys are waiting for "Clear all keys" to be confirmed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ClearTarget {
+    Track(TrackKind),
+    TrapColor,
+}
+
+/// The context menu shared by the dope-sheet rows.
+fn track_context_menu(response: egui::Response, has_keys: bool) -> Option<TrackAction> {
+    let mut action = None;
+    response.context_menu(|ui| {
+        if ui
+            .add_enabled(has_keys, egui::Button::new("Clear all keys…"))
+            .clicked()
+        {
+            action = Some(TrackAction::ClearKeys);
+            ui.close_menu();
+        }
+        if ui.button("Reset parameter to default").clicked() {
+            action = Some(TrackAction::ResetParameter);
+            ui.close_menu();
+        }
+    });
+    action
+}
+
+/// Header of a dope-sheet group: fold arrow, name, mute toggle, and a button keying every
+/// track in it. Returns whether that button was clicked.
+fn track_group_header(
+    ui: &mut egui::Ui,
+    group: TrackGroup,
+    collapsed: &mut Vec<TrackGroup>,
+    locks: &mut ParamLocks,
+) -> bool {
+    ui.horizontal(|ui| {
+        let folded = collapsed.contains(&group);
+        if ui
+            .selectable_label(false, if folded { "▶" } else { "▼" })
+            .clicked()
+        {
+            if folded {
+                collapsed.retain(|g| *g != group);
+            } else {
+                collapsed.push(group);
+            }
+        }
+        ui.strong(group.label());
+        let mut muted = locks.group_locked(group);
+        if ui
+            .toggle_value(&mut muted, "Mute")
+            .on_hover_text("Bypass the group's keys in the preview, like its lock toggles.")
+            .changed()
+        {
+            locks.set_group(group, muted);
+        }
+        ui.small_button("Key all")
+            .on_hover_text("Key every track in the group at the playhead.")
+            .clicked()
+    })
+    .inner
+}
+
+/// Asks before "Clear all keys" deletes a track's keys; clearing is one undo step.
+fn confirm_clear_window(ctx: &egui::Context, editor: &mut TimelineEditor, anim: &mut Animation) {
+    let Some(target) = editor.confirm_clear else {
+        return;
+    };
+    let (name, count) = match target {
+        ClearTarget::Track(track) => (track.label(), anim.track(track).keys.len()),
+        ClearTarget::TrapColor => ("Trap color", anim.kf_trap_color.keys.len()),
+    };
+    let mut done = false;
+    egui::Window::new("Clear keys")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, Vec2::ZERO)
+        .show(ctx, |ui| {
+            ui.label(format!(
+                "Delete all {count} {name} key{}?",
+                if count == 1 { "" } else { "s" }
+            ));
+            ui.horizontal(|ui| {
+                if ui.button("Clear").clicked() {
+                    match target {
+                        ClearTarget::Track(track) => anim.clear_track(track),
+                        ClearTarget::TrapColor => {
+                            anim.kf_trap_color.keys.clear();
+                            editor.color_key = None;
+                        }
+                    }
+                    done = true;
+                }
+                if ui.button("Cancel").clicked() {
+                    done = true;
+                }
+            });
+        });
+    if done {
+        editor.confirm_clear = None;
+    }
+}
+
+/// Linear 0..1 channels as 8-bit, the way the orbit-trap picker shows them.
+fn rgb_to_color32(c: [f32; 3]) -> Color32 {
+    Color32::from_rgb(
+        (c[0] * 255.0) as u8,
+        (c[1] * 255.0) as u8,
+        (c[2] * 255.0) as u8,
+    )
+}
+
+/// Dope-sheet row of the trap-color track: handles are filled with their key color and
+/// a strip along the bottom previews the blend between keys.
+fn trap_color_row(
+    ui: &mut egui::Ui,
+    editor: &mut TimelineEditor,
+    anim: &mut Animation,
+    current: [f32; 3],
+    time: &mut f32,
+) -> Option<TrackAction> {
+    let height = 36.0;
+    let duration = anim.duration.max(0.001);
+    ui.horizontal(|ui| {
+        ui.label("Trap color");
+        egui::ComboBox::from_id_source("trap_color_space")
+            .selected_text(anim.color_space.label())
+            .show_ui(ui, |ui| {
+                for space in ColorSpace::ALL {
+                    ui.selectable_value(&mut anim.color_space, space, space.label());
+                }
+            })
+            .response
+            .on_hover_text("Space the colors blend in between keys.");
+    });
+    let (rect, response) =
+        ui.allocate_exact_size(vec2(ui.available_width(), height), Sense::click_and_drag());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 4.0, Color32::from_gray(28));
+
+    if (response.dragged() || response.clicked()) && anim.duration > 0.0 {
+        if let Some(pos) = response.interact_pointer_pos() {
+            let rel = ((pos.x - rect.left()) / rect.width()).clamp(0.0, 1.0);
+            *time = rel * anim.duration;
+        }
+    }
+
+    if !anim.kf_trap_color.keys.is_empty() {
+        let strip = Rect::from_min_max(pos2(rect.left(), rect.bottom() - 5.0), rect.max);
+        let steps = (rect.width() / 4.0).max(1.0) as usize;
+        for s in 0..steps {
+            let x0 = rect.left() + rect.width() * s as f32 / steps as f32;
+            let x1 = rect.left() + rect.width() * (s + 1) as f32 / steps as f32;
+            let t = duration * (s as f32 + 0.5) / steps as f32;
+            let c = anim
+                .kf_trap_color
+                .sample_color(t, current, anim.color_space);
+            painter.rect_filled(
+                Rect::from_x_y_ranges(x0..=x1, strip.y_range()),
+                0.0,
+                rgb_to_color32(c),
+            );
+        }
+    }
+
+    let scrub_x = rect.left() + rect.width() * (*time / duration);
+    painter.line_segment(
+        [pos2(scrub_x, rect.top()), pos2(scrub_x, rect.bottom())],
+        Stroke::new(1.5, Color32::LIGHT_BLUE),
+    );
+
+    hold_bars(&painter, rect, duration, &anim.kf_trap_color.keys);
+
+    let mut remove_idx = None;
+    let mut released = None;
+    let selected = editor.color_key;
+    for (idx, key) in anim.kf_trap_color.keys.iter_mut().enumerate() {
+        let x = rect.left() + rect.width() * (key.t / duration);
+        let key_rect = Rect::from_center_size(pos2(x, rect.center().y), vec2(10.0, height - 8.0));
+        let resp = ui
+            .interact(
+                key_rect,
+                Id::new(("trap_color_key", idx as u32)),
+                Sense::click_and_drag(),
+            )
+            .on_hover_text(format!(
+                "Trap color ({:.2}, {:.2}, {:.2}) @ {:.2}s",
+                key.v[0], key.v[1], key.v[2], key.t
+            ));
+        let outline = if selected == Some(idx) {
+            Stroke::new(2.0, Color32::from_rgb(255, 170, 70))
+        } else {
+            Stroke::new(1.0, Color32::BLACK)
+        };
+        painter.rect(key_rect, 2.0, rgb_to_color32(key.v), outline);
+        painter.text(
+            key_rect.center_top() + vec2(0.0, -10.0),
+            egui::Align2::CENTER_TOP,
+            key.easing.label(),
+            egui::FontId::proportional(10.0),
+            Color32::GRAY,
+        );
+
+        if resp.dragged() {
+            if let Some(pos) = resp.interact_pointer_pos() {
+                let rel = ((pos.x - rect.left()) / rect.width()).clamp(0.0, 1.0);
+                key.t = rel * anim.duration;
+            }
+        }
+        if resp.drag_stopped() {
+            released = Some(idx);
+        }
+        if resp.clicked() {
+            editor.color_key = Some(idx);
+            anim.selection = None;
+        }
+        if resp.secondary_clicked() {
+            remove_idx = Some(idx);
+        }
+    }
+    if let Some(idx) = released {
+        editor.color_key = Some(anim.kf_trap_color.resort(idx));
+        anim.selection = None;
+    }
+    if let Some(idx) = remove_idx {
+        anim.kf_trap_color.keys.remove(idx);
+        if editor.color_key == Some(idx) {
+            editor.color_key = None;
+        }
+    }
+
+    if response.double_clicked() {
+        if let Some(pos) = response.interact_pointer_pos() {
+            let rel = ((pos.x - rect.left()) / rect.width()).clamp(0.0, 1.0);
+            anim.kf_trap_color.upsert(rel * anim.duration, current);
+        }
+    }
+    track_context_menu(response, !anim.kf_trap_color.keys.is_empty())
+}
+
+fn trap_color_key_inspector(
+    ui: &mut egui::Ui,
+    editor: &mut TimelineEditor,
+    anim: &mut Animation,
+    index: usize,
+) {
+    let duration = anim.duration;
+    let Some(key) = anim.kf_trap_color.keys.get_mut(index) else {
+        editor.color_key = None;
+        return;
+    };
+    ui.separator();
+    let mut time_edited = false;
+    let mut delete = false;
+    ui.horizontal(|ui| {
+        ui.label("Editing Trap color key");
+        time_edited = ui
+            .add(
+                egui::DragValue::new(&mut key.t)
+                    .clamp_range(0.0..=duration)
+                    .speed(0.01)
+                    .suffix(" s"),
+            )
+            .changed();
+        ui.label("Color");
+        let mut color = rgb_to_color32(key.v);
+        if color_edit_button_srgba(ui, &mut color, Alpha::Opaque).changed() {
+            key.v = [
+                color.r() as f32 / 255.0,
+                color.g() as f32 / 255.0,
+                color.b() as f32 / 255.0,
+            ];
+        }
+        egui::ComboBox::from_label("Easing")
+            .selected_text(key.easing.label())
+            .show_ui(ui, |ui| {
+                for easing in Easing::ALL {
+                    ui.selectable_value(&mut key.easing, easing, easing.label());
+                }
+            });
+        delete = ui.button("Delete key").clicked();
+    });
+    if delete {
+        anim.kf_trap_color.keys.remove(index);
+        editor.color_key = None;
+    } else if time_edited {
+        editor.color_key = Some(anim.kf_trap_color.resort(index));
+    }
+}
+
+/// Bars from each Hold key to the next key across a dope-sheet row, where the value
+/// stays put before it jumps.
+fn hold_bars<T>(painter: &egui::Painter, rect: Rect, duration: f32, keys: &[Keyframe<T>]) {
+    let to_x = |t: f32| rect.left() + rect.width() * (t / duration);
+    for pair in keys.windows(2) {
+        if pair[0].easing == Easing::Hold {
+            let y = rect.center().y;
+            painter.line_segment(
+                [pos2(to_x(pair[0].t), y), pos2(to_x(pair[1].t), y)],
+                Stroke::new(3.0, Color32::from_gray(90)),
+            );
+        }
+    }
+}
+
+/// Plots `easing` over 0..1; `progress` marks the playhead on the curve.
+pub(crate) fn easing_plot(ui: &mut egui::Ui, easing: Easing, progress: Option<f32>, size: Vec2) {
+    let (rect, _) = ui.allocate_exact_size(size, Sense::hover());
+    let painter = ui.painter_at(rect);
+    let small = size.x < 32.0;
+    if !small {
+        painter.rect_filled(rect, 3.0, Color32::from_gray(28));
+    }
+    let area = rect.shrink(if small { 1.0 } else { 6.0 });
+    let to_screen = |u: f32, v: f32| {
+        pos2(
+            area.left() + u * area.width(),
+            area.bottom() - v * area.height(),
+        )
+    };
+    let steps = if small { 12 } else { 40 };
+    let points = (0..=steps)
+        .map(|i| {
+            let u = i as f32 / steps as f32;
+            to_screen(u, easing.apply(u))
+        })
+        .collect();
+    let color = ui.visuals().text_color();
+    painter.add(egui::Shape::line(
+        points,
+        Stroke::new(if small { 1.0 } else { 1.5 }, color),
+    ));
+    if let Some(u) = progress {
+        painter.circle_filled(
+            to_screen(u, easing.apply(u)),
+            3.5,
+            Color32::from_rgb(255, 170, 70),
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TimelineView {
+    DopeSheet,
+    Curves,
+}
+
+impl TimelineView {
+    pub(crate) fn label(&self) -> &'static str {
+        match self {
+            TimelineView::DopeSheet => "Dope sheet",
+            TimelineView::Curves => "Curves",
+        }
+    }
+}
+
+/// View state of the timeline panel; not saved with the project.
+pub(crate) struct TimelineEditor {
+    pub(crate) view: TimelineView,
+    pub(crate) visible: [bool; TrackKind::ALL.len()],
+    /// Visible time window in seconds; `None` follows the animation duration.
+    pub(crate) time_window: Option<(f32, f32)>,
+    /// Visible window of the normalized value axis (0..1 spans each track's key range).
+    pub(crate) value_window: (f32, f32),
+    /// Per-track value ranges, frozen while a key is dragged so the graph doesn't rescale under it.
+    pub(crate) frozen_ranges: Option<[(f32, f32); TrackKind::ALL.len()]>,
+    /// Selected key of the trap-color track; exclusive with `Animation::selection`.
+    pub(crate) color_key: Option<usize>,
+    pub(crate) confirm_clear: Option<ClearTarget>,
+    /// The next viewport click sets the endless zoom's target.
+    pub(crate) picking_zoom_target: bool,
+}
+
+impl Default for TimelineEditor {
+    fn default() -> Self {
+        Self {
+            view: TimelineView::DopeSheet,
+            visible: [true; TrackKind::ALL.len()],
+            time_window: None,
+            value_window: (-0.1, 1.1),
+            frozen_ranges: None,
+            color_key: None,
+            confirm_clear: None,
+            picking_zoom_target: false,
+        }
+    }
+}
+
+pub(crate) fn track_value_range(keys: &Keyframes<f32>, current: f32) -> (f32, f32) {
+    let (lo, hi) = keys
+        .keys
+        .iter()
+        .fold((current, current), |(lo, hi), k| (lo.min(k.v), hi.max(k.v)));
+    let span = (hi - lo).max(lo.abs().max(hi.abs()) * 0.1).max(1e-3);
+    let mid = (lo + hi) * 0.5;
+    (mid - span * 0.5, mid + span * 0.5)
+}
+
+/// Combined value-over-time graph of the visible tracks with draggable keys.
+pub(crate) fn curve_editor_ui(
+    ui: &mut egui::Ui,
+    editor: &mut TimelineEditor,
+    anim: &mut Animation,
+    current_values: [f32; TrackKind::ALL.len()],
+    time: &mut f32,
+) {
+    ui.horizontal(|ui| {
+        for (i, track) in TrackKind::ALL.into_iter().enumerate() {
+            let text = egui::RichText::new(track.label()).color(track_color(track));
+            ui.checkbox(&mut editor.visible[i], text);
+        }
+        if ui.button("Frame all").clicked() {
+            editor.time_window = None;
+            editor.value_window = (-0.1, 1.1);
+        }
+        ui.small("Scroll: zoom time · Shift+scroll: zoom values · Right-drag: pan");
+    });
+
+    let duration = anim.duration.max(0.001);
+    let height = (ui.available_height() - 48.0).max(140.0);
+    let (rect, response) =
+        ui.allocate_exact_size(vec2(ui.available_width(), height), Sense::click_and_drag());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 4.0, Color32::from_gray(24));
+
+    let (mut t0, mut t1) = editor.time_window.unwrap_or((0.0, duration));
+    let (mut v0, mut v1) = editor.value_window;
+
+    // Zoom around the pointer, pan with the secondary button.
+    if let Some(hover) = response.hover_pos() {
+        let (scroll, shift) = ui.input(|i| (i.smooth_scroll_delta, i.modifiers.shift));
+        let amount = if scroll.y != 0.0 { scroll.y } else { scroll.x };
+        if amount != 0.0 {
+            let factor = (-amount * 0.002).exp();
+            if shift {
+                let pivot = v0 + (rect.bottom() - hover.y) / rect.height() * (v1 - v0);
+                v0 = pivot + (v0 - pivot) * factor;
+                v1 = pivot + (v1 - pivot) * factor;
+            } else {
+                let pivot = t0 + (hover.x - rect.left()) / rect.width() * (t1 - t0);
+                t0 = pivot + (t0 - pivot) * factor;
+                t1 = pivot + (t1 - pivot) * factor;
+            }
+        }
+    }
+    if response.dragged_by(egui::PointerButton::Secondary)
+        || response.dragged_by(egui::PointerButton::Middle)
+    {
+        let delta = response.drag_delta();
+        let dt = delta.x / rect.width() * (t1 - t0);
+        let dv = delta.y / rect.height() * (v1 - v0);
+        t0 -= dt;
+        t1 -= dt;
+        v0 += dv;
+        v1 += dv;
+    }
+    if t1 - t0 > 1e-3 {
+        editor.time_window = Some((t0, t1));
+    }
+    if v1 - v0 > 1e-4 {
+        editor.value_window = (v0, v1);
+    }
+    let (t0, t1) = editor.time_window.unwrap_or((0.0, duration));
+    let (v0, v1) = editor.value_window;
+
+    let to_x = |t: f32| rect.left() + (t - t0) / (t1 - t0) * rect.width();
+    let to_t = |x: f32| t0 + (x - rect.left()) / rect.width() * (t1 - t0);
+    let to_y = |n: f32| rect.bottom() - (n - v0) / (v1 - v0) * rect.height();
+    let to_n = |y: f32| v0 + (rect.bottom() - y) / rect.height() * (v1 - v0);
+
+    // Grid: duration bounds and the normalized 0/1 lines.
+    let grid = Stroke::new(1.0, Color32::from_gray(50));
+    for n in [0.0, 1.0] {
+        painter.hline(rect.x_range(), to_y(n), grid);
+    }
+    for t in [0.0, duration] {
+        painter.vline(to_x(t), rect.y_range(), grid);
+    }
+
+    let ranges = editor.frozen_ranges.unwrap_or_else(|| {
+        let mut r = [(0.0, 1.0); TrackKind::ALL.len()];
+        for (i, track) in TrackKind::ALL.into_iter().enumerate() {
+            r[i] = track_value_range(anim.track(track), current_values[i]);
+        }
+        r
+    });
+
+    let mut selection = anim.selection.clone();
+    let mut any_key_dragged = false;
+    let mut released = None;
+    let mut remove = None;
+    for (i, track) in TrackKind::ALL.into_iter().enumerate() {
+        if !editor.visible[i] {
+            continue;
+        }
+        let (lo, hi) = ranges[i];
+        let norm = |v: f32| (v - lo) / (hi - lo);
+        let keys = anim.track_mut(track);
+
+        let samples = (rect.width() / 3.0).max(2.0) as usize;
+        let points: Vec<egui::Pos2> = (0..=samples)
+            .map(|s| {
+                let x = rect.left() + rect.width() * s as f32 / samples as f32;
+                pos2(x, to_y(norm(keys.sample(to_t(x), current_values[i]))))
+            })
+            .collect();
+        painter.add(egui::Shape::line(
+            points,
+            Stroke::new(1.5, track_color(track)),
+        ));
+
+        for (idx, key) in keys.keys.iter_mut().enumerate() {
+            let center = pos2(to_x(key.t), to_y(norm(key.v)));
+            let key_rect = Rect::from_center_size(center, vec2(10.0, 10.0));
+            let resp = ui
+                .interact(
+                    key_rect,
+                    Id::new(("curve_key", track as u8, idx as u32)),
+                    Sense::click_and_drag(),
+                )
+                .on_hover_text(format!("{} = {:.5} @ {:.2}s", track.label(), key.v, key.t));
+            let selected = selection
+                .as_ref()
+                .is_some_and(|sel| sel.track == track && sel.index == idx);
+            if resp.dragged_by(egui::PointerButton::Primary) {
+                any_key_dragged = true;
+                if let Some(pos) = resp.interact_pointer_pos() {
+                    key.t = to_t(pos.x).clamp(0.0, duration);
+                    if !ui.input(|i| i.modifiers.shift) {
+                        key.v = lo + to_n(pos.y) * (hi - lo);
+                    }
+                }
+            }
+            if resp.drag_started() || resp.clicked() {
+                selection = Some(SelectedKey { track, index: idx });
+            }
+            if resp.drag_stopped() {
+                released = Some((track, idx));
+            }
+            if resp.secondary_clicked() {
+                remove = Some((track, idx));
+            }
+            let fill = if selected {
+                Color32::from_rgb(255, 170, 70)
+            } else {
+                track_color(track)
+            };
+            let outline = Stroke::new(1.0, Color32::BLACK);
+            if key.easing == Easing::Hold {
+                painter.rect(
+                    Rect::from_center_size(center, vec2(8.0, 8.0)),
+                    0.0,
+                    fill,
+                    outline,
+                );
+            } else {
+                painter.add(egui::Shape::convex_polygon(
+                    vec![
+                        center + vec2(0.0, -5.0),
+                        center + vec2(5.0, 0.0),
+                        center + vec2(0.0, 5.0),
+                        center + vec2(-5.0, 0.0),
+                    ],
+                    fill,
+                    outline,
+                ));
+            }
+        }
+    }
+    editor.frozen_ranges = any_key_dragged.then_some(ranges);
+    anim.selection = selection;
+
+    // Dragging in time can reorder keys; keep the selection on the dragged key.
+    if let Some((track, idx)) = released {
+        let new_idx = anim.track_mut(track).resort(idx);
+        anim.selection = Some(SelectedKey {
+            track,
+            index: new_idx,
+        });
+    }
+    if let Some((track, idx)) = remove {
+        anim.track_mut(track).keys.remove(idx);
+        anim.selection = None;
+    }
+
+    // Background: click/drag scrubs, double-click keys the selected (or first visible) track.
+    if response.dragged_by(egui::PointerButton::Primary) || response.clicked() {
+        if let Some(pos) = response.interact_pointer_pos() {
+            *time = to_t(pos.x).clamp(0.0, duration);
+        }
+    }
+    if response.double_clicked() {
+        let target = anim
+            .selection
+            .as_ref()
+            .map(|sel| sel.track)
+            .filter(|t| editor.visible[*t as usize])
+            .or_else(|| {
+                TrackKind::ALL
+                    .into_iter()
+                    .find(|t| editor.visible[*t as usize])
+            });
+        if let (Some(track), Some(pos)) = (target, response.interact_pointer_pos()) {
+            let (lo, hi) = ranges[track as usize];
+            let t = to_t(pos.x).clamp(0.0, duration);
+            anim.track_mut(track)
+                .upsert(t, lo + to_n(pos.y) * (hi - lo));
+        }
+    }
+
+    let scrub_x = to_x(*time);
+    painter.vline(
+        scrub_x,
+        rect.y_range(),
+        Stroke::new(1.5, Color32::LIGHT_BLUE),
+    );
+}
+
+pub(crate) fn track_timeline_row(
+    ui: &mut egui::Ui,
+    track: TrackKind,
+    label: &str,
+    current_value: f32,
+    duration: f32,
+    time: &mut f32,
+    selection: &mut Option<SelectedKey>,
+    keys: &mut Keyframes<f32>,
+) -> Option<TrackAction> {
+    let height = 36.0;
+    ui.label(label);
+    let (rect, response) =
+        ui.allocate_exact_size(vec2(ui.available_width(), height), Sense::click_and_drag());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 4.0, Color32::from_gray(28));
+
+    // Scrub timeline if dragged/clicked
+    if (response.dragged() || response.clicked()) && duration > 0.0 {
+        if let Some(pos) = response.interact_pointer_pos() {
+            let rel = ((pos.x - rect.left()) / rect.width()).clamp(0.0, 1.0);
+            *time = rel * duration;
+        }
+    }
+
+    // Draw scrubber
+    let scrub_x = rect.left() + rect.width() * (*time / duration.max(0.001));
+    painter.line_segment(
+        [pos2(scrub_x, rect.top()), pos2(scrub_x, rect.bottom())],
+        Stroke::new(1.5, Color32::LIGHT_BLUE),
+    );
+
+    hold_bars(&painter, rect, duration.max(0.001), &keys.keys);
+
+    let mut remove_idx = None;
+    let mut released = None;
+    let current_selection = selection.clone();
+    for (idx, key) in keys.keys.iter_mut().enumerate() {
+        let x = rect.left() + rect.width() * (key.t / duration.max(0.001));
+        let key_rect = Rect::from_center_size(pos2(x, rect.center().y), vec2(10.0, height - 8.0));
+        let id = Id::new((track as u8, idx as u32));
+        let resp = ui.interact(key_rect, id, Sense::click_and_drag());
+        let color = if current_selection
+            .as_ref()
+            .map_or(false, |sel| sel.track == track && sel.index == idx)
+        {
+            Color32::from_rgb(255, 170, 70)
+        } else {
+            Color32::from_rgb(120, 200, 255)
+        };
+        painter.rect_filled(key_rect, 2.0, color);
+        painter.text(
+            key_rect.center_top() + vec2(0.0, -10.0),
+            egui::Align2::CENTER_TOP,
+            key.easing.label(),
+            egui::FontId::proportional(10.0),
+            Color32::GRAY,
+        );
+
+        if resp.dragged() {
+            if let Some(pos) = resp.interact_pointer_pos() {
+                let rel = ((pos.x - rect.left()) / rect.width()).clamp(0.0, 1.0);
+                key.t = rel * duration;
+            }
+        }
+        if resp.drag_stopped() {
+            released = Some(idx);
+        }
+        if resp.clicked() {
+            *selection = Some(SelectedKey { track, index: idx });
+        }
+        if resp.double_clicked() {
+            key.easing = match key.easing {
+                Easing::Linear => Easing::EaseIn,
+                Easing::EaseIn => Easing::EaseOut,
+                Easing::EaseOut => Easing::EaseInOut,
+                Easing::EaseInOut => Easing::SmoothStep,
+                Easing::SmoothStep => Easing::Hold,
+                Easing::Hold => Easing::Linear,
+            };
+        }
+        if resp.secondary_clicked() {
+            remove_idx = Some(idx);
+        }
+    }
+    if let Some(idx) = released {
+        let index = keys.resort(idx);
+        *selection = Some(SelectedKey { track, index });
+    }
+    if let Some(idx) = remove_idx {
+        keys.keys.remove(idx);
+        if selection
+            .as_ref()
+            .map_or(false, |sel| sel.track == track && sel.index == idx)
+        {
+            *selection = None;
+        }
+    }
+
+    if response.double_clicked() {
+        if let Some(pos) = response.interact_pointer_pos() {
+            let rel = ((pos.x - rect.left()) / rect.width()).clamp(0.0, 1.0);
+            keys.upsert(rel * duration, current_value);
+        }
+    }
+    track_context_menu(response, !keys.keys.is_empty())
+}
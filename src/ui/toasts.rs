@@ -0,0 +1,224 @@
+//! In-app toast notifications and desktop integration helpers.
+
+use super::*;
+
+pub(crate) const TOAST_SECONDS: f32 = 6.0;
+/// Toasts with buttons stay up longer so there is time to use them.
+pub(crate) const TOAST_ACTION_SECONDS: f32 = 20.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ToastKind {
+    Info,
+    Warning,
+    Error,
+}
+
+impl ToastKind {
+    pub(crate) fn color(&self) -> Color32 {
+        match self {
+            ToastKind::Info => Color32::LIGHT_BLUE,
+            ToastKind::Warning => Color32::from_rgb(255, 190, 70),
+            ToastKind::Error => Color32::from_rgb(255, 100, 100),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum ToastAction {
+    Open(PathBuf),
+    Reveal(PathBuf),
+    /// Handed back to the app by [`Toasts::show`].
+    RetryGpu,
+    /// Answers a project file that changed on disk under unsaved edits; handed back too.
+    ReloadProject(ReloadChoice),
+}
+
+impl ToastAction {
+    pub(crate) fn label(&self) -> &'static str {
+        match self {
+            ToastAction::Open(_) => "Open",
+            ToastAction::Reveal(_) => "Show in folder",
+            ToastAction::RetryGpu => "Retry GPU",
+            ToastAction::ReloadProject(choice) => choice.label(),
+        }
+    }
+
+    pub(crate) fn run(&self) -> std::io::Result<()> {
+        match self {
+            ToastAction::Open(path) => open_path(path),
+            ToastAction::Reveal(path) => reveal_path(path),
+            ToastAction::RetryGpu | ToastAction::ReloadProject(_) => Ok(()),
+        }
+    }
+}
+
+pub(crate) struct Toast {
+    pub(crate) kind: ToastKind,
+    pub(crate) text: String,
+    pub(crate) actions: Vec<ToastAction>,
+    pub(crate) shown_at: Instant,
+    /// Stays until dismissed or acted on.
+    pub(crate) sticky: bool,
+}
+
+impl Toast {
+    pub(crate) fn lifetime(&self) -> f32 {
+        if self.sticky {
+            f32::INFINITY
+        } else if self.actions.is_empty() {
+            TOAST_SECONDS
+        } else {
+            TOAST_ACTION_SECONDS
+        }
+    }
+}
+
+/// Short-lived notifications stacked in the bottom-right corner.
+#[derive(Default)]
+pub(crate) struct Toasts {
+    pub(crate) items: Vec<Toast>,
+}
+
+impl Toasts {
+    pub(crate) fn push(&mut self, kind: ToastKind, text: impl Into<String>) {
+        self.push_with_actions(kind, text, Vec::new());
+    }
+
+    pub(crate) fn push_with_actions(
+        &mut self,
+        kind: ToastKind,
+        text: impl Into<String>,
+        actions: Vec<ToastAction>,
+    ) {
+        self.items.push(Toast {
+            kind,
+            text: text.into(),
+            actions,
+            shown_at: Instant::now(),
+            sticky: false,
+        });
+    }
+
+    pub(crate) fn push_sticky(
+        &mut self,
+        kind: ToastKind,
+        text: impl Into<String>,
+        actions: Vec<ToastAction>,
+    ) {
+        self.push_with_actions(kind, text, actions);
+        if let Some(toast) = self.items.last_mut() {
+            toast.sticky = true;
+        }
+    }
+
+    pub(crate) fn info(&mut self, text: impl Into<String>) {
+        self.push(ToastKind::Info, text);
+    }
+
+    pub(crate) fn warning(&mut self, text: impl Into<String>) {
+        self.push(ToastKind::Warning, text);
+    }
+
+    pub(crate) fn error(&mut self, text: impl Into<String>) {
+        self.push(ToastKind::Error, text);
+    }
+
+    /// Draws the toasts and runs file actions; actions only the app can carry out, such as
+    /// [`ToastAction::RetryGpu`], are returned and close their toast.
+    pub(crate) fn show(&mut self, ctx: &egui::Context) -> Option<ToastAction> {
+        self.items
+            .retain(|toast| toast.shown_at.elapsed().as_secs_f32() < toast.lifetime());
+        if self.items.is_empty() {
+            return None;
+        }
+        let mut dismiss = None;
+        let mut action = None;
+        egui::Area::new(Id::new("toasts"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, vec2(-12.0, -12.0))
+            .show(ctx, |ui| {
+                for (idx, toast) in self.items.iter().enumerate() {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.colored_label(toast.kind.color(), &toast.text);
+                            if ui.small_button("✕").clicked() {
+                                dismiss = Some(idx);
+                            }
+                        });
+                        if !toast.actions.is_empty() {
+                            ui.horizontal(|ui| {
+                                for a in &toast.actions {
+                                    if ui.button(a.label()).clicked() {
+                                        action = Some((idx, a.clone()));
+                                    }
+                                }
+                            });
+                        }
+                    });
+                }
+            });
+        ctx.request_repaint_after(Duration::from_millis(250));
+        if let Some(idx) = dismiss {
+            self.items.remove(idx);
+            return None;
+        }
+        match action {
+            Some((idx, action @ (ToastAction::RetryGpu | ToastAction::ReloadProject(_)))) => {
+                self.items.remove(idx);
+                Some(action)
+            }
+            Some((_, action)) => {
+                if let Err(e) = action.run() {
+                    self.error(format!("Could not open file manager: {e}"));
+                }
+                None
+            }
+            None => None,
+        }
+    }
+}
+
+/// Opens `path` with the platform's default application.
+pub(crate) fn open_path(path: &Path) -> std::io::Result<()> {
+    let mut cmd = if cfg!(target_os = "macos") {
+        std::process::Command::new("open")
+    } else if cfg!(target_os = "windows") {
+        let mut c = std::process::Command::new("cmd");
+        c.args(["/C", "start", ""]);
+        c
+    } else {
+        std::process::Command::new("xdg-open")
+    };
+    cmd.arg(path).spawn().map(drop)
+}
+
+/// Shows `path` in the platform file manager, selecting it where supported.
+pub(crate) fn reveal_path(path: &Path) -> std::io::Result<()> {
+    if cfg!(target_os = "macos") {
+        std::process::Command::new("open")
+            .arg("-R")
+            .arg(path)
+            .spawn()
+            .map(drop)
+    } else if cfg!(target_os = "windows") {
+        std::process::Command::new("explorer")
+            .arg(format!("/select,{}", path.display()))
+            .spawn()
+            .map(drop)
+    } else {
+        let dir = path.parent().filter(|d| !d.as_os_str().is_empty());
+        open_path(dir.unwrap_or(Path::new(".")))
+    }
+}
+
+/// Best-effort desktop notification; failures only go to stderr.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn desktop_notify(summary: &str, body: &str) {
+    if let Err(e) = notify_rust::Notification::new()
+        .appname("Matterhorn AH")
+        .summary(summary)
+        .body(body)
+        .show()
+    {
+        eprintln!("Desktop notification failed: {e}");
+    }
+}
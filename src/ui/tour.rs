@@ -0,0 +1,188 @@
+//! Guided tours: short notes stepped through one at a time, each pointing at a region of
+//! the window. Panels register their rects under an anchor name with [`tour_anchor`] as
+//! they are drawn; a tour step names the anchor it highlights. Every tour runs once and is
+//! then remembered in the preferences, so announcing a new feature is a matter of adding
+//! a [`Tour`] to [`TOURS`].
+
+use super::*;
+
+/// A note shown while a tour is on this step.
+pub(crate) struct TourStep {
+    /// Region to highlight, as registered with [`tour_anchor`]; `None` centers the note.
+    pub(crate) anchor: Option<&'static str>,
+    pub(crate) title: &'static str,
+    pub(crate) text: &'static str,
+}
+
+/// A sequence of steps, remembered in `AppSettings::seen_tours` under `id` once finished
+/// or skipped.
+pub(crate) struct Tour {
+    pub(crate) id: &'static str,
+    pub(crate) steps: &'static [TourStep],
+}
+
+/// Tour of the main window on first start, over the demo project.
+pub(crate) const WELCOME_TOUR: Tour = Tour {
+    id: "welcome",
+    steps: &[
+        TourStep {
+            anchor: None,
+            title: "Welcome to Matterhorn AH",
+            text: "This demo zooms into the seahorse valley of the Mandelbrot set. \
+                   Take a quick tour of the main controls?",
+        },
+        TourStep {
+            anchor: Some("viewport"),
+            title: "The view",
+            text: "Drag to pan, scroll to zoom and Alt-drag to rotate.",
+        },
+        TourStep {
+            anchor: Some("side_panel"),
+            title: "Parameters",
+            text: "Formula, iterations, palette and camera. Type in the search box to find \
+                   a setting.",
+        },
+        TourStep {
+            anchor: Some("timeline"),
+            title: "The timeline",
+            text: "This is the timeline — double-click a track to add a key, drag a key to \
+                   move it in time.",
+        },
+        TourStep {
+            anchor: Some("toolbar"),
+            title: "Play and export",
+            text: "Press Play to run the demo's zoom, save your project, and Export Video \
+                   once you like what you see.",
+        },
+    ],
+};
+
+/// Every tour, in the order they are offered.
+pub(crate) const TOURS: &[&Tour] = &[&WELCOME_TOUR];
+
+/// Records `rect` as where `anchor` is this frame, for tour steps pointing at it.
+pub(crate) fn tour_anchor(ctx: &egui::Context, anchor: &'static str, rect: Rect) {
+    ctx.data_mut(|d| d.insert_temp(anchor_id(anchor), rect));
+}
+
+/// Where `anchor` was last drawn.
+fn anchor_rect(ctx: &egui::Context, anchor: &str) -> Option<Rect> {
+    ctx.data(|d| d.get_temp(anchor_id(anchor)))
+}
+
+fn anchor_id(anchor: &str) -> Id {
+    Id::new(("tour_anchor", anchor))
+}
+
+/// What the buttons of a tour note do.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum TourEvent {
+    Back,
+    Next,
+    Skip,
+}
+
+/// The running tour, if any, and its step.
+#[derive(Default)]
+pub(crate) struct TourState {
+    running: Option<(&'static Tour, usize)>,
+}
+
+impl TourState {
+    /// Starts the first tour not in `seen`, unless one is running already.
+    fn start_unseen(&mut self, seen: &[String]) {
+        if self.running.is_none() {
+            self.running = TOURS
+                .iter()
+                .find(|tour| !seen.iter().any(|id| id == tour.id))
+                .map(|tour| (*tour, 0));
+        }
+    }
+
+    /// Moves through the running tour; returns its id once it has ended.
+    fn handle(&mut self, event: TourEvent) -> Option<&'static str> {
+        let (tour, step) = self.running.as_mut()?;
+        let id = tour.id;
+        match event {
+            TourEvent::Back => *step = step.saturating_sub(1),
+            TourEvent::Next if *step + 1 < tour.steps.len() => *step += 1,
+            TourEvent::Next | TourEvent::Skip => {
+                self.running = None;
+                return Some(id);
+            }
+        }
+        None
+    }
+}
+
+/// Draws the running tour's highlight and note, starting the next unseen tour first.
+/// Finished and skipped tours are added to `seen`.
+pub(crate) fn tour_ui(ctx: &egui::Context, state: &mut TourState, seen: &mut Vec<String>) {
+    state.start_unseen(seen);
+    let Some((tour, index)) = state.running else {
+        return;
+    };
+    let step = &tour.steps[index];
+    let screen = ctx.screen_rect();
+    let anchor = step.anchor.and_then(|a| anchor_rect(ctx, a));
+    if let Some(rect) = anchor {
+        let stroke = Stroke::new(3.0, ctx.style().visuals.selection.bg_fill);
+        ctx.layer_painter(egui::LayerId::new(
+            egui::Order::Foreground,
+            Id::new("tour_highlight"),
+        ))
+        .rect_stroke(rect.shrink(2.0), 6.0, stroke);
+    }
+    // Inside large regions, beside thin ones such as the toolbar.
+    let (pivot, pos) = match anchor {
+        None => (egui::Align2::CENTER_CENTER, screen.center()),
+        Some(rect) if rect.height() >= 160.0 => (egui::Align2::CENTER_CENTER, rect.center()),
+        Some(rect) if rect.bottom() + 160.0 < screen.bottom() => (
+            egui::Align2::CENTER_TOP,
+            rect.center_bottom() + vec2(0.0, 12.0),
+        ),
+        Some(rect) => (
+            egui::Align2::CENTER_BOTTOM,
+            rect.center_top() - vec2(0.0, 12.0),
+        ),
+    };
+    let mut event = None;
+    egui::Area::new(Id::new("tour_note"))
+        .order(egui::Order::Foreground)
+        .pivot(pivot)
+        .fixed_pos(pos)
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.set_max_width(320.0);
+                ui.strong(step.title);
+                ui.label(step.text);
+                ui.add_space(4.0);
+                ui.horizontal(|ui| {
+                    let last = index + 1 == tour.steps.len();
+                    let (next, skip) = match (index, last) {
+                        (0, _) => ("Take the tour", "Not now"),
+                        (_, true) => ("Done", "Skip"),
+                        _ => ("Next", "Skip"),
+                    };
+                    if ui.button(next).clicked() {
+                        event = Some(TourEvent::Next);
+                    }
+                    if index > 0 && ui.button("Back").clicked() {
+                        event = Some(TourEvent::Back);
+                    }
+                    if !last && ui.button(skip).clicked() {
+                        event = Some(TourEvent::Skip);
+                    }
+                    if index > 0 {
+                        ui.weak(format!("{}/{}", index, tour.steps.len() - 1));
+                    }
+                });
+            });
+        });
+    if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+        event = Some(TourEvent::Skip);
+    }
+    if let Some(id) = event.and_then(|event| state.handle(event)) {
+        seen.push(id.to_owned());
+    }
+}
@@ -0,0 +1,759 @@
+//! Camera navigation and on-canvas handles over the preview.
+
+use super::*;
+
+/// Wheel steps are multiplicative: one line zooms by 10%.
+pub(crate) const WHEEL_ZOOM_PER_LINE: f32 = 1.1;
+
+/// Shift+Alt-drag snaps the rotation to multiples of this.
+const ROTATION_SNAP: f32 = 15.0 * PI / 180.0;
+
+/// How long the preview glides onto a newly picked zoom target.
+pub(crate) const RECENTER_SECONDS: f32 = 0.4;
+
+/// One frame of viewport navigation, independent of whether it came from a mouse,
+/// a touchpad gesture or a touch screen. All input paths share [`NavInput::apply`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct NavInput {
+    /// Screen-space translation in points; content follows the pointer.
+    pub(crate) pan: Vec2,
+    /// Multiplicative zoom; > 1 zooms in.
+    pub(crate) zoom: f32,
+    /// Rotation of the content on screen in radians (clockwise, since y points down).
+    pub(crate) rotate: f32,
+    /// Fixed point for zoom and rotation, relative to the view's top-left corner.
+    pub(crate) anchor: Vec2,
+}
+
+impl NavInput {
+    /// Collects mouse drag/wheel, touchpad pinch/two-finger scroll, and touch gestures
+    /// over `response`. Uses raw events so there is no momentum or smoothing.
+    pub(crate) fn read(ui: &egui::Ui, response: &egui::Response) -> Self {
+        let rect = response.rect;
+        let mut nav = NavInput {
+            pan: Vec2::ZERO,
+            zoom: 1.0,
+            rotate: 0.0,
+            anchor: rect.size() / 2.0,
+        };
+        if response.dragged_by(egui::PointerButton::Primary) {
+            nav.pan += response.drag_delta();
+        }
+        if !response.hovered() {
+            return nav;
+        }
+        ui.input(|i| {
+            if let Some(pos) = i.pointer.hover_pos() {
+                nav.anchor = pos - rect.min;
+            }
+            // Pinch and Ctrl+wheel both arrive here.
+            nav.zoom *= i.zoom_delta();
+            if let Some(touch) = i.multi_touch() {
+                nav.rotate += touch.rotation_delta;
+                nav.pan += touch.translation_delta;
+            }
+            for event in &i.events {
+                if let egui::Event::MouseWheel {
+                    unit,
+                    delta,
+                    modifiers,
+                } = event
+                {
+                    if modifiers.ctrl || modifiers.command {
+                        continue;
+                    }
+                    match unit {
+                        // Touchpads report smooth point deltas: two-finger scroll pans.
+                        egui::MouseWheelUnit::Point => nav.pan += *delta,
+                        // Notched mouse wheels report lines: zoom like a map.
+                        egui::MouseWheelUnit::Line | egui::MouseWheelUnit::Page => {
+                            nav.zoom *= WHEEL_ZOOM_PER_LINE.powf(delta.y)
+                        }
+                    }
+                }
+            }
+        });
+        nav
+    }
+
+    pub(crate) fn is_idle(&self) -> bool {
+        self.pan == Vec2::ZERO && self.zoom == 1.0 && self.rotate == 0.0
+    }
+
+    /// Applies the gesture to a camera rendering into a `size` view.
+    pub(crate) fn apply(&self, cam: &mut Camera, size: Vec2) {
+        let (w, h) = (size.x, size.y);
+        let (ax, ay) = (self.anchor.x, self.anchor.y);
+        // Keep the plane point under the anchor fixed while zooming/rotating.
+        let fixed = cam.pixel_offset(ax, ay, w, h);
+        cam.scale = (cam.scale * f64::from(self.zoom)).max(1e-3);
+        // Turning the content clockwise turns the camera the other way.
+        cam.rotation -= self.rotate;
+        let moved = cam.pixel_offset(ax, ay, w, h);
+        // Then drag the content along with the pan. Offsets rather than plane points, so
+        // the move keeps its precision however deep the view is.
+        let pan = cam.pixel_offset(w / 2.0 - self.pan.x, h / 2.0 - self.pan.y, w, h);
+        cam.translate(Complex64::new(
+            fixed.re - moved.re + pan.re,
+            fixed.im - moved.im + pan.im,
+        ));
+    }
+}
+
+/// Navigates the authored camera. The gesture is solved against the evaluated
+/// (on-screen) camera and the resulting change is carried over, so it stays
+/// under the pointer even while tracks animate the view.
+pub(crate) fn viewport_navigation(
+    ui: &mut egui::Ui,
+    id: Id,
+    rect: Rect,
+    shown: &Camera,
+    camera: &mut Camera,
+) -> bool {
+    let response = ui.interact(rect, id, Sense::drag());
+    if rotate_gesture(ui, &response, camera) {
+        return true;
+    }
+    let nav = NavInput::read(ui, &response);
+    if nav.is_idle() {
+        return false;
+    }
+    let mut moved = shown.clone();
+    nav.apply(&mut moved, rect.size());
+    camera.translate(shown.offset_to(&moved));
+    camera.scale *= moved.scale / shown.scale;
+    camera.rotation += moved.rotation - shown.rotation;
+    true
+}
+
+/// Authored rotation and pointer angle when an Alt-drag started.
+#[derive(Debug, Clone, Copy)]
+struct RotateDrag {
+    rotation: f32,
+    angle: f32,
+}
+
+/// Alt-drag turns the camera around the view center with an angle readout; Shift snaps
+/// to 15°. Returns true while rotating.
+fn rotate_gesture(ui: &egui::Ui, response: &egui::Response, camera: &mut Camera) -> bool {
+    let id = response.id.with("rotate");
+    let (alt, shift) = ui.input(|i| (i.modifiers.alt, i.modifiers.shift));
+    let pos = response
+        .interact_pointer_pos()
+        .filter(|_| alt && response.dragged_by(egui::PointerButton::Primary));
+    let Some(pos) = pos else {
+        ui.data_mut(|d| d.remove::<RotateDrag>(id));
+        return false;
+    };
+    let center = response.rect.center();
+    let angle = (pos.y - center.y).atan2(pos.x - center.x);
+    let start = ui.data_mut(|d| {
+        *d.get_temp_mut_or_insert_with(id, || RotateDrag {
+            rotation: camera.rotation,
+            angle,
+        })
+    });
+    // The content follows the pointer, so the camera turns the other way.
+    let mut rotation = start.rotation - (angle - start.angle);
+    if shift {
+        rotation = (rotation / ROTATION_SNAP).round() * ROTATION_SNAP;
+    }
+    camera.rotation = rotation;
+
+    let painter = ui.painter_at(response.rect);
+    painter.line_segment(
+        [center, pos],
+        Stroke::new(1.0, Color32::from_white_alpha(140)),
+    );
+    painter.circle_stroke(center, 4.0, Stroke::new(1.5, Color32::WHITE));
+    let degrees = (rotation.to_degrees() + 180.0).rem_euclid(360.0) - 180.0;
+    let galley = painter.layout_no_wrap(
+        format!("{degrees:.1}°"),
+        egui::FontId::monospace(12.0),
+        Color32::WHITE,
+    );
+    let text_pos = pos + vec2(14.0, -14.0 - galley.size().y);
+    painter.rect_filled(
+        Rect::from_min_size(text_pos, galley.size()).expand(4.0),
+        4.0,
+        Color32::from_black_alpha(160),
+    );
+    painter.galley(text_pos, galley, Color32::WHITE);
+    true
+}
+
+/// Preview tile size, the smallest the tiling allows, so the part of the image under the
+/// cursor refines first.
+pub(crate) const PREVIEW_TILE: u32 = 256;
+
+/// Time spent on preview tiles before the finished ones are shown.
+pub(crate) const PREVIEW_BUDGET: Duration = Duration::from_millis(24);
+
+/// Fraction of the preview resolution rendered while the view is changing.
+pub(crate) const DRAFT_SCALE: f32 = 0.25;
+
+/// How long the view stays still before the full-resolution frame renders.
+pub(crate) const SETTLE: Duration = Duration::from_millis(300);
+
+/// How far, in preview pixels, the focus moves before the remaining tiles are reordered.
+pub(crate) const REFOCUS_DISTANCE: f32 = 48.0;
+
+/// Samples behind the last preview frame, so edits that only change colors recolor them
+/// instead of iterating again.
+struct PreviewSamples {
+    size: (u32, u32),
+    camera: Camera,
+    fractal: FractalParams,
+    /// Name of the renderer that took the samples.
+    renderer: &'static str,
+    samples: Vec<PixelSample>,
+}
+
+impl PreviewSamples {
+    fn matches(
+        &self,
+        size: (u32, u32),
+        camera: &Camera,
+        fractal: &FractalParams,
+        renderer: &dyn Renderer,
+    ) -> bool {
+        self.size == size
+            && same_framing(&self.camera, camera)
+            && self.renderer == renderer.name()
+            && self.fractal.same_samples(fractal)
+    }
+}
+
+pub(crate) fn same_framing(a: &Camera, b: &Camera) -> bool {
+    a.center == b.center && a.scale == b.scale && a.rotation == b.rotation
+}
+
+/// Tells a view being changed, by a drag, a slider or the keyboard, from a still one.
+#[derive(Default)]
+pub(crate) struct Interaction {
+    /// Framing and sampling parameters of the last UI frame.
+    last_view: Option<(Camera, FractalParams)>,
+    last_change: Option<Instant>,
+    /// Whether the view changed this UI frame, so the frame rendered for it is a fresh
+    /// one.
+    pub(crate) moved: bool,
+}
+
+impl Interaction {
+    /// Whether the view changed within [`SETTLE`], or `busy` (a drag or playback) keeps
+    /// it changing. Schedules a repaint for when the view settles.
+    pub(crate) fn update(
+        &mut self,
+        ctx: &egui::Context,
+        eval: &EvaluatedFrame,
+        busy: bool,
+    ) -> bool {
+        self.moved = !self.last_view.as_ref().is_some_and(|(camera, fractal)| {
+            same_framing(camera, &eval.camera) && fractal.same_samples(&eval.fractal)
+        });
+        if self.moved {
+            self.last_view = Some((eval.camera.clone(), eval.fractal.clone()));
+            self.last_change = Some(Instant::now());
+        }
+        if busy {
+            self.last_change = Some(Instant::now());
+        }
+        let Some(changed) = self.last_change else {
+            return false;
+        };
+        let still = changed.elapsed();
+        if still >= SETTLE {
+            self.last_change = None;
+            return false;
+        }
+        ctx.request_repaint_after(SETTLE - still);
+        true
+    }
+}
+
+/// Preview frame being rendered a few tiles per UI frame, in the browser, which has no
+/// threads to hand it to.
+#[cfg(target_arch = "wasm32")]
+struct PreviewJob {
+    render: ProgressiveRender,
+    renderer: &'static str,
+}
+
+/// The frame the preview worker was last asked for.
+#[cfg(not(target_arch = "wasm32"))]
+struct PreviewJob {
+    size: (u32, u32),
+    camera: Camera,
+    fractal: FractalParams,
+    renderer: &'static str,
+    steer: Steer,
+}
+
+impl PreviewJob {
+    #[cfg(target_arch = "wasm32")]
+    fn matches(
+        &self,
+        size: (u32, u32),
+        camera: &Camera,
+        fractal: &FractalParams,
+        renderer: &dyn Renderer,
+    ) -> bool {
+        self.render.size() == size
+            && same_framing(self.render.camera(), camera)
+            && self.renderer == renderer.name()
+            && FractalParams {
+                // The render attaches its own equalizer to what it was asked for.
+                equalizer: fractal.equalizer.clone(),
+                ..self.render.params().clone()
+            } == *fractal
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn matches(
+        &self,
+        size: (u32, u32),
+        camera: &Camera,
+        fractal: &FractalParams,
+        renderer: &dyn Renderer,
+    ) -> bool {
+        self.size == size
+            && same_framing(&self.camera, camera)
+            && self.renderer == renderer.name()
+            && self.fractal == *fractal
+    }
+}
+
+/// How the frame in progress is rendered; changing it does not start the frame over.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Steer {
+    pub(crate) pass: Option<AovPass>,
+    /// Preview pixel the remaining tiles are ordered around.
+    pub(crate) focus: egui::Pos2,
+    /// Show only the finished frame, no tiles along the way.
+    pub(crate) whole: bool,
+}
+
+/// What changed in the preview image this UI frame.
+pub(crate) enum PreviewUpdate {
+    /// A whole frame, with its stats.
+    Frame(Vec<u8>, RenderStats),
+    /// Newly finished tiles of a frame still in progress, with their RGBA8 pixels.
+    Tiles(Vec<(TileInfo, Vec<u8>)>),
+}
+
+/// The preview renderer's state between UI frames: the samples of the last full frame and
+/// the frame in progress.
+#[derive(Default)]
+pub(crate) struct PreviewState {
+    samples: Option<PreviewSamples>,
+    job: Option<PreviewJob>,
+    /// Stats of the last frame iterated rather than recolored: how fast this machine
+    /// renders, for estimates.
+    pub(crate) last_iterated: Option<RenderStats>,
+    /// Renders frames off the UI thread; started with the first one.
+    #[cfg(not(target_arch = "wasm32"))]
+    worker: Option<PreviewWorker>,
+    /// Counts the frames asked of the worker, so tiles of one it has since been told to
+    /// drop are not shown over a newer one.
+    #[cfg(not(target_arch = "wasm32"))]
+    generation: u64,
+    /// Backend the worker renders with; the app keeps it in step with its own renderer.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) backend: RenderBackend,
+    #[cfg(not(target_arch = "wasm32"))]
+    demotion: Option<Demotion>,
+}
+
+impl PreviewState {
+    /// Renders the preview, or its pass as `steer` picks. Edits that only change colors
+    /// recolor the last frame at once; anything else is rendered in tiles, nearest the
+    /// focus first, and shown as they finish unless `steer` wants the whole frame. A
+    /// change mid-frame drops the tiles still queued for the old view.
+    ///
+    /// Natively the tiles are rendered on a worker thread and this returns at once with
+    /// whatever it has finished since; in the browser they are rendered here, a few per UI
+    /// frame, or all at once when `whole`.
+    pub(crate) fn render(
+        &mut self,
+        ctx: &egui::Context,
+        size: (u32, u32),
+        fractal: &FractalParams,
+        camera: &Camera,
+        renderer: &mut dyn Renderer,
+        steer: Steer,
+    ) -> Result<PreviewUpdate, RenderError> {
+        let recolor = self
+            .samples
+            .as_ref()
+            .is_some_and(|c| c.matches(size, camera, fractal, renderer));
+        if recolor {
+            self.cancel();
+            let cached = self
+                .samples
+                .as_ref()
+                .expect("the samples were just matched");
+            return match steer.pass {
+                None => shade_image(size, &cached.samples, fractal, renderer, 0)
+                    .map(|(pixels, stats)| PreviewUpdate::Frame(pixels, stats)),
+                Some(pass) => {
+                    let started = Instant::now();
+                    let pixels = pass_preview(pass, &cached.samples, fractal);
+                    let mut stats = RenderStats::new(renderer, size, fractal);
+                    stats.total_ms = started.elapsed().as_secs_f64() * 1000.0;
+                    Ok(PreviewUpdate::Frame(pixels, stats))
+                }
+            };
+        }
+
+        self.samples = None;
+        #[cfg(not(target_arch = "wasm32"))]
+        return self.render_on_worker(ctx, (size, fractal, camera), renderer, steer);
+        #[cfg(target_arch = "wasm32")]
+        self.render_here(ctx, (size, fractal, camera), renderer, steer)
+    }
+
+    /// Asks the worker for the frame if it was not asked already, and collects what it
+    /// has rendered of it.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn render_on_worker(
+        &mut self,
+        ctx: &egui::Context,
+        (size, fractal, camera): ((u32, u32), &FractalParams, &Camera),
+        renderer: &dyn Renderer,
+        steer: Steer,
+    ) -> Result<PreviewUpdate, RenderError> {
+        let worker = self.worker.get_or_insert_with(|| PreviewWorker::spawn(ctx));
+        match &mut self.job {
+            Some(job) if job.matches(size, camera, fractal, renderer) => {
+                if job.steer != steer {
+                    job.steer = steer;
+                    worker.steer(steer);
+                }
+            }
+            _ => {
+                self.generation += 1;
+                worker.start(PreviewRequest {
+                    generation: self.generation,
+                    size,
+                    fractal: fractal.clone(),
+                    camera: camera.clone(),
+                    backend: self.backend,
+                    steer,
+                });
+                self.job = Some(PreviewJob {
+                    size,
+                    camera: camera.clone(),
+                    fractal: fractal.clone(),
+                    renderer: renderer.name(),
+                    steer,
+                });
+            }
+        }
+
+        let mut tiles = Vec::new();
+        for result in worker.results.try_iter() {
+            self.demotion = result.demotion;
+            if result.generation != self.generation {
+                continue;
+            }
+            match result.rendered {
+                Ok(Rendered::Tiles(more)) => tiles.extend(more),
+                Ok(Rendered::Frame(pixels, stats, samples)) => {
+                    let job = self
+                        .job
+                        .take()
+                        .expect("the worker only renders asked frames");
+                    self.last_iterated = Some(stats.clone());
+                    self.samples = Some(PreviewSamples {
+                        size,
+                        camera: job.camera,
+                        fractal: job.fractal,
+                        renderer: job.renderer,
+                        samples,
+                    });
+                    return Ok(PreviewUpdate::Frame(pixels, stats));
+                }
+                Err(err) => {
+                    self.job = None;
+                    return Err(err);
+                }
+            }
+        }
+        Ok(PreviewUpdate::Tiles(tiles))
+    }
+
+    /// Renders tiles of the frame for up to [`PREVIEW_BUDGET`], or all of them when
+    /// `whole`.
+    #[cfg(target_arch = "wasm32")]
+    fn render_here(
+        &mut self,
+        ctx: &egui::Context,
+        (size, fractal, camera): ((u32, u32), &FractalParams, &Camera),
+        renderer: &mut dyn Renderer,
+        Steer { pass, focus, whole }: Steer,
+    ) -> Result<PreviewUpdate, RenderError> {
+        if !self
+            .job
+            .as_ref()
+            .is_some_and(|job| job.matches(size, camera, fractal, renderer))
+        {
+            self.job = Some(PreviewJob {
+                render: ProgressiveRender::new(size, fractal, camera, renderer, PREVIEW_TILE)?,
+                renderer: renderer.name(),
+            });
+        }
+        let job = self.job.as_mut().expect("a preview job was just started");
+        let settled = job
+            .render
+            .focus()
+            .is_some_and(|(x, y)| focus.distance(pos2(x, y)) <= REFOCUS_DISTANCE);
+        if !settled {
+            job.render.prioritize((focus.x, focus.y));
+        }
+        let budget = if whole { Duration::MAX } else { PREVIEW_BUDGET };
+        let tiles = match job.render.step(renderer, budget) {
+            Ok(tiles) => tiles,
+            Err(err) => {
+                self.job = None;
+                return Err(err);
+            }
+        };
+        if !job.render.is_done() {
+            ctx.request_repaint();
+            let tile_pixels = |tile: &TileInfo| match pass {
+                None => job.render.tile_pixels(tile),
+                Some(pass) => pass_preview(pass, &job.render.tile_samples(tile), fractal),
+            };
+            return Ok(PreviewUpdate::Tiles(
+                tiles.into_iter().map(|t| (t, tile_pixels(&t))).collect(),
+            ));
+        }
+
+        let job = self.job.take().expect("the finished job is still there");
+        let (pixels, stats, samples) = job.render.finish();
+        let pixels = match pass {
+            None => pixels,
+            Some(pass) => pass_preview(pass, &samples, fractal),
+        };
+        self.last_iterated = Some(stats.clone());
+        self.samples = Some(PreviewSamples {
+            size,
+            camera: camera.clone(),
+            fractal: fractal.clone(),
+            renderer: job.renderer,
+            samples,
+        });
+        Ok(PreviewUpdate::Frame(pixels, stats))
+    }
+
+    /// Drops the frame in progress.
+    fn cancel(&mut self) {
+        if self.job.take().is_none() {
+            return;
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(worker) = &self.worker {
+            // Whatever it still sends for the frame is stale now.
+            self.generation += 1;
+            worker.cancel();
+        }
+    }
+
+    /// Whether a frame is still being rendered.
+    pub(crate) fn in_progress(&self) -> bool {
+        self.job.is_some()
+    }
+
+    /// Why the worker's renderer gave up on its backend, if it did.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn demotion(&self) -> Option<&Demotion> {
+        self.demotion.as_ref()
+    }
+
+    /// Gives the worker's demoted backend another chance.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn retry_primary(&mut self) {
+        if let Some(worker) = &self.worker {
+            worker.retry_primary();
+        }
+        self.demotion = None;
+    }
+}
+
+/// Copies newly finished preview tiles into `tex`, starting from a blank texture when the
+/// preview's size changed.
+pub(crate) fn upload_tiles(
+    tex: &mut TextureHandle,
+    image_size: [usize; 2],
+    tiles: Vec<(TileInfo, Vec<u8>)>,
+) {
+    if tex.size() != image_size {
+        tex.set(
+            ColorImage::new(image_size, Color32::TRANSPARENT),
+            egui::TextureOptions::LINEAR,
+        );
+    }
+    for (tile, pixels) in tiles {
+        let tile_image = ColorImage::from_rgba_unmultiplied(
+            [tile.tile_w as usize, tile.tile_h as usize],
+            &pixels,
+        );
+        tex.set_partial(
+            [tile.offset_x as usize, tile.offset_y as usize],
+            tile_image,
+            egui::TextureOptions::LINEAR,
+        );
+    }
+}
+
+/// Timing of the last preview frame in the top-left corner of the viewport image.
+pub(crate) fn render_stats_overlay(ui: &egui::Ui, rect: Rect, stats: &RenderStats) {
+    let text = format!(
+        "{} {}×{} · {} tile{} · {:.1} ms · {:.1} Mpix/s",
+        stats.backend,
+        stats.width,
+        stats.height,
+        stats.tiles(),
+        if stats.tiles() == 1 { "" } else { "s" },
+        stats.total_ms,
+        stats.mpix_per_s()
+    );
+    let painter = ui.painter_at(rect);
+    let galley = painter.layout_no_wrap(text, egui::FontId::monospace(12.0), Color32::WHITE);
+    let pos = rect.min + vec2(8.0, 8.0);
+    painter.rect_filled(
+        Rect::from_min_size(pos, galley.size()).expand(4.0),
+        4.0,
+        Color32::from_black_alpha(160),
+    );
+    painter.galley(pos, galley, Color32::WHITE);
+}
+
+/// Magnification and pixel size in the bottom-left corner of the viewport.
+pub(crate) fn zoom_readout_overlay(ui: &egui::Ui, rect: Rect, readout: &ZoomReadout) {
+    let painter = ui.painter_at(rect);
+    let galley = painter.layout_no_wrap(
+        readout.to_string(),
+        egui::FontId::monospace(12.0),
+        Color32::WHITE,
+    );
+    let pos = pos2(rect.min.x + 8.0, rect.max.y - 8.0 - galley.size().y);
+    painter.rect_filled(
+        Rect::from_min_size(pos, galley.size()).expand(4.0),
+        4.0,
+        Color32::from_black_alpha(160),
+    );
+    painter.galley(pos, galley, Color32::WHITE);
+}
+
+/// Marks the endless zoom's `target` and, while `picking`, returns the point clicked.
+pub(crate) fn zoom_target_overlay(
+    ui: &mut egui::Ui,
+    rect: Rect,
+    shown: &Camera,
+    picking: &mut bool,
+    target: Option<Complex64>,
+) -> Option<Complex64> {
+    let mut picked = None;
+    if *picking {
+        let response = ui
+            .interact(rect, Id::new("zoom_target_pick"), Sense::click())
+            .on_hover_cursor(egui::CursorIcon::Crosshair);
+        if let Some(pos) = response
+            .interact_pointer_pos()
+            .filter(|_| response.clicked())
+        {
+            let local = pos - rect.min;
+            picked = Some(shown.pixel_to_plane(local.x, local.y, rect.width(), rect.height()));
+            *picking = false;
+        }
+    }
+    if let Some(target) = picked.or(target) {
+        let (x, y) = shown.plane_to_pixel(target, rect.width(), rect.height());
+        let at = rect.min + vec2(x, y);
+        if rect.expand(12.0).contains(at) {
+            let painter = ui.painter_at(rect);
+            let stroke = Stroke::new(1.5, Color32::from_rgb(120, 230, 160));
+            painter.circle_stroke(at, 6.0, stroke);
+            for dir in [vec2(1.0, 0.0), vec2(0.0, 1.0)] {
+                painter.line_segment([at + dir * 9.0, at + dir * 16.0], stroke);
+                painter.line_segment([at - dir * 9.0, at - dir * 16.0], stroke);
+            }
+        }
+    }
+    picked
+}
+
+/// Draws draggable orbit-trap handles over the viewport image occupying `rect`.
+/// Returns true while a handle is being dragged so the preview can drop to draft quality.
+pub(crate) fn orbit_trap_overlay(
+    ui: &mut egui::Ui,
+    rect: Rect,
+    cam: &Camera,
+    orbit: &mut OrbitTrap,
+) -> bool {
+    if !orbit.enabled {
+        return false;
+    }
+    let painter = ui.painter_at(rect);
+    let to_screen = |p: Complex| {
+        let (x, y) = cam.plane_to_pixel(p, rect.width(), rect.height());
+        rect.min + vec2(x, y)
+    };
+    let to_plane = |pos: egui::Pos2| {
+        let rel = pos - rect.min;
+        cam.pixel_to_plane(rel.x, rel.y, rect.width(), rect.height())
+            .to_f32()
+    };
+    let stroke = Stroke::new(1.5, Color32::from_rgb(255, 170, 70));
+    let mut dragging = false;
+
+    match orbit.kind {
+        OrbitTrapKind::Point | OrbitTrapKind::Cross => {
+            let center = to_screen(orbit.point);
+            let handle = Rect::from_center_size(center, vec2(14.0, 14.0));
+            let resp = ui.interact(handle, Id::new("orbit_trap_point"), Sense::drag());
+            if resp.dragged() {
+                if let Some(pos) = resp.interact_pointer_pos() {
+                    orbit.point = to_plane(pos);
+                }
+                dragging = true;
+            }
+            if orbit.kind == OrbitTrapKind::Cross {
+                let faint = Stroke::new(1.0, stroke.color.gamma_multiply(0.5));
+                painter.hline(rect.x_range(), center.y, faint);
+                painter.vline(center.x, rect.y_range(), faint);
+            }
+            painter.circle_stroke(center, 6.0, stroke);
+            painter.circle_filled(center, 2.0, stroke.color);
+            resp.on_hover_text(format!(
+                "Trap point {:.5} {:+.5}i",
+                orbit.point.re, orbit.point.im
+            ));
+        }
+        OrbitTrapKind::Circle => {
+            // The circle trap measures |z| - radius, so it is centered on the plane origin.
+            let center = to_screen(Complex { re: 0.0, im: 0.0 });
+            let radius_px = (f64::from(orbit.radius) * cam.scale) as f32;
+            let ring_id = Id::new("orbit_trap_ring");
+            let near_ring = ui
+                .input(|i| i.pointer.hover_pos())
+                .is_some_and(|pos| ((pos - center).length() - radius_px).abs() < 6.0);
+            let mut hot = near_ring;
+            if near_ring || ui.ctx().is_being_dragged(ring_id) {
+                let resp = ui.interact(rect, ring_id, Sense::drag());
+                if resp.dragged() {
+                    if let Some(pos) = resp.interact_pointer_pos() {
+                        orbit.radius =
+                            ((f64::from((pos - center).length()) / cam.scale) as f32).max(1e-6);
+                    }
+                    dragging = true;
+                    hot = true;
+                }
+            }
+            let width = if hot { 3.0 } else { 1.5 };
+            painter.circle_stroke(center, radius_px, Stroke::new(width, stroke.color));
+        }
+    }
+    dragging
+}
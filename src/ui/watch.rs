@@ -0,0 +1,170 @@
+//! Watching the open project file for edits made outside the app.
+
+use notify::Watcher;
+
+use super::*;
+
+/// The project file last opened or saved, and what it held then.
+pub(crate) struct ProjectFile {
+    pub(crate) path: PathBuf,
+    /// The project as last loaded from or saved to `path`; edits are measured against it.
+    pub(crate) base: Project,
+    /// Started on the first poll with watching on, and dropped when it is turned off.
+    watcher: Option<notify::RecommendedWatcher>,
+    changes: Option<mpsc::Receiver<()>>,
+    debounce: Debounce,
+    /// The file's contents while the user decides what to do with them.
+    pub(crate) conflict: Option<Project>,
+}
+
+impl ProjectFile {
+    pub(crate) fn new(path: PathBuf, base: Project) -> Self {
+        Self {
+            path,
+            base,
+            watcher: None,
+            changes: None,
+            debounce: Debounce::default(),
+            conflict: None,
+        }
+    }
+
+    /// Watches the file's folder rather than the file, so editors that save by renaming
+    /// a temporary file over it are still seen.
+    fn start(&mut self, ctx: &egui::Context) -> notify::Result<()> {
+        let (tx, rx) = mpsc::channel();
+        let name = self.path.file_name().map(ToOwned::to_owned);
+        let ctx = ctx.clone();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                let Ok(event) = event else {
+                    return;
+                };
+                let ours = event
+                    .paths
+                    .iter()
+                    .any(|p| p.file_name().map(ToOwned::to_owned) == name);
+                if ours && (event.kind.is_modify() || event.kind.is_create()) {
+                    let _ = tx.send(());
+                    ctx.request_repaint();
+                }
+            })?;
+        let dir = self.path.parent().filter(|d| !d.as_os_str().is_empty());
+        watcher.watch(
+            dir.unwrap_or(Path::new(".")),
+            notify::RecursiveMode::NonRecursive,
+        )?;
+        self.watcher = Some(watcher);
+        self.changes = Some(rx);
+        Ok(())
+    }
+
+    /// The file's new contents once a burst of writes has settled, or why it could not
+    /// be read.
+    fn poll(
+        &mut self,
+        ctx: &egui::Context,
+    ) -> Option<Result<(Project, Vec<NonFinite>), ProjectError>> {
+        let now = Instant::now();
+        if let Some(changes) = &self.changes {
+            for () in changes.try_iter() {
+                self.debounce.changed(now);
+            }
+        }
+        if !self.debounce.pending() {
+            return None;
+        }
+        if !self.debounce.ready(now) {
+            ctx.request_repaint_after(self.debounce.quiet);
+            return None;
+        }
+        Some(load_project_repaired(&self.path))
+    }
+}
+
+impl MatterhornApp {
+    /// Starts over on `path` after the project was opened from or saved to it.
+    pub(crate) fn set_project_file(&mut self, path: PathBuf) {
+        self.project_file = Some(ProjectFile::new(path, self.proj.clone()));
+    }
+
+    /// Reloads the project file after it changed on disk, or asks first when the open
+    /// project has unsaved edits too.
+    pub(crate) fn poll_project_file(&mut self, ctx: &egui::Context) {
+        let Some(file) = &mut self.project_file else {
+            return;
+        };
+        if !self.settings.watch_project {
+            file.watcher = None;
+            file.changes = None;
+            return;
+        }
+        if file.watcher.is_none() {
+            if let Err(e) = file.start(ctx) {
+                self.toasts.error(format!(
+                    "Could not watch {} for changes: {e}",
+                    file.path.display()
+                ));
+                self.settings.watch_project = false;
+                return;
+            }
+        }
+        let on_disk = match file.poll(ctx) {
+            None => return,
+            Some(Ok((on_disk, repairs))) => {
+                if !repairs.is_empty() {
+                    self.toasts.warning(format!(
+                        "{}: {}",
+                        file.path.display(),
+                        non_finite_warning(&repairs)
+                    ));
+                }
+                on_disk
+            }
+            Some(Err(e)) => {
+                self.toasts
+                    .error(format!("Could not reload {}", e.at(&file.path)));
+                return;
+            }
+        };
+        let name = file.path.display().to_string();
+        match reconcile(&self.proj, &file.base, &on_disk) {
+            Reconcile::Unchanged => file.base = on_disk,
+            Reconcile::Reload => {
+                self.proj = resolve(&self.proj, on_disk.clone(), ReloadChoice::Reload);
+                file.base = on_disk;
+                self.toasts.info(format!("Reloaded {name}"));
+            }
+            Reconcile::Conflict => {
+                file.conflict = Some(on_disk);
+                // A newer change replaces the question about the older one.
+                self.toasts.items.retain(|toast| {
+                    !toast
+                        .actions
+                        .iter()
+                        .any(|a| matches!(a, ToastAction::ReloadProject(_)))
+                });
+                self.toasts.push_sticky(
+                    ToastKind::Warning,
+                    format!("{name} changed on disk, and the open project has unsaved edits"),
+                    ReloadChoice::ALL
+                        .into_iter()
+                        .map(ToastAction::ReloadProject)
+                        .collect(),
+                );
+            }
+        }
+    }
+
+    /// Applies the user's answer to a file that changed under unsaved edits.
+    pub(crate) fn resolve_reload(&mut self, choice: ReloadChoice) {
+        let Some(file) = &mut self.project_file else {
+            return;
+        };
+        let Some(on_disk) = file.conflict.take() else {
+            return;
+        };
+        self.proj = resolve(&self.proj, on_disk.clone(), choice);
+        file.base = on_disk;
+    }
+}
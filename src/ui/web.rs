@@ -0,0 +1,117 @@
+//! Browser build: projects and images move through uploads and downloads, not paths.
+
+use std::{cell::RefCell, rc::Rc};
+
+use super::*;
+
+/// What an uploaded file should be applied to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum UploadPurpose {
+    Project,
+    /// A Kalles Fraktaler `.kfr` file; `palette` also takes over its color table.
+    Location {
+        palette: bool,
+    },
+}
+
+/// Picked file name and contents.
+type Upload = (String, Vec<u8>);
+
+/// One upload at a time, read asynchronously on the browser's event loop.
+#[derive(Default)]
+pub(crate) struct WebFiles {
+    pending: Option<(UploadPurpose, mpsc::Receiver<Option<Upload>>)>,
+    /// Downloads that could not be written, for the UI to report.
+    failed_downloads: Rc<RefCell<Vec<String>>>,
+}
+
+impl WebFiles {
+    pub(crate) fn upload(
+        &mut self,
+        ctx: &egui::Context,
+        purpose: UploadPurpose,
+        dialog: rfd::AsyncFileDialog,
+    ) {
+        if self.pending.is_some() {
+            return;
+        }
+        let (tx, rx) = mpsc::channel();
+        let ctx = ctx.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let picked = match dialog.pick_file().await {
+                Some(handle) => Some((handle.file_name(), handle.read().await)),
+                None => None,
+            };
+            let _ = tx.send(picked);
+            ctx.request_repaint();
+        });
+        self.pending = Some((purpose, rx));
+    }
+
+    /// Returns the uploaded file once it has been read; cancelled pickers yield nothing.
+    pub(crate) fn poll(&mut self) -> Option<(UploadPurpose, String, Vec<u8>)> {
+        let (purpose, rx) = self.pending.as_ref()?;
+        let picked = match rx.try_recv() {
+            Ok(picked) => picked,
+            Err(mpsc::TryRecvError::Empty) => return None,
+            Err(mpsc::TryRecvError::Disconnected) => None,
+        };
+        let purpose = *purpose;
+        self.pending = None;
+        picked.map(|(name, data)| (purpose, name, data))
+    }
+}
+
+impl WebFiles {
+    /// Offers `data` to the browser as a download named `file_name`. Failures show up in
+    /// [`WebFiles::take_failed_downloads`].
+    pub(crate) fn download(&self, ctx: &egui::Context, file_name: &str, data: Vec<u8>) {
+        let dialog = rfd::AsyncFileDialog::new().set_file_name(file_name);
+        let failed = Rc::clone(&self.failed_downloads);
+        let ctx = ctx.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Some(handle) = dialog.save_file().await {
+                if let Err(e) = handle.write(&data).await {
+                    failed
+                        .borrow_mut()
+                        .push(format!("Could not save {}: {e}", handle.file_name()));
+                    ctx.request_repaint();
+                }
+            }
+        });
+    }
+
+    pub(crate) fn take_failed_downloads(&self) -> Vec<String> {
+        std::mem::take(&mut self.failed_downloads.borrow_mut())
+    }
+}
+
+/// Encodes RGBA pixels as PNG bytes.
+pub(crate) fn encode_png(size: (u32, u32), pixels: Vec<u8>) -> Result<Vec<u8>, String> {
+    let img = ImageBuffer::<Rgba<u8>, _>::from_raw(size.0, size.1, pixels)
+        .ok_or("pixel buffer does not match the image size")?;
+    let mut out = std::io::Cursor::new(Vec::new());
+    img.write_to(&mut out, image::ImageOutputFormat::Png)
+        .map_err(|e| e.to_string())?;
+    Ok(out.into_inner())
+}
+
+/// Starts the app on the `<canvas id="matterhorn_canvas">` of the hosting page.
+pub(crate) fn start(proj: Project, settings: AppSettings) {
+    wasm_bindgen_futures::spawn_local(async move {
+        eframe::WebRunner::new()
+            .start(
+                "matterhorn_canvas",
+                eframe::WebOptions::default(),
+                Box::new(move |_cc| {
+                    Box::new(MatterhornApp::new(proj, settings, Toasts::default()))
+                }),
+            )
+            .await
+            .expect("failed to start eframe on the canvas");
+    });
+}
+
+/// Shown wherever the desktop build would offer video export.
+pub(crate) const WEB_EXPORT_NOTE: &str =
+    "Video export runs FFmpeg and needs the desktop app; save the project and export it there.";
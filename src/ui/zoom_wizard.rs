@@ -0,0 +1,246 @@
+//! Deep zoom movie wizard: pick a destination and a speed, preview the flight, then write
+//! the keys into the timeline.
+
+use std::f32::consts::TAU;
+
+use super::*;
+
+/// Wizard state; `start` is set while the window is open.
+pub(crate) struct ZoomWizard {
+    /// View the movie starts from, taken from the viewport when the wizard opened.
+    pub(crate) start: Option<Camera>,
+    destination: Complex64,
+    /// Decades of magnification below the starting view.
+    depth: f32,
+    duration: f32,
+    profile: ZoomProfile,
+    rotate: bool,
+    /// Full turns over the movie, negative for clockwise.
+    turns: f32,
+    pasted: String,
+    /// The next click in the viewport sets the destination.
+    pub(crate) picking: bool,
+    /// Seconds into the movie shown in the viewport.
+    scrub: f32,
+    error: Option<String>,
+}
+
+impl Default for ZoomWizard {
+    fn default() -> Self {
+        Self {
+            start: None,
+            destination: Complex64::new(0.0, 0.0),
+            depth: 5.0,
+            duration: 20.0,
+            profile: ZoomProfile::default(),
+            rotate: false,
+            turns: 1.0,
+            pasted: String::new(),
+            picking: false,
+            scrub: 0.0,
+            error: None,
+        }
+    }
+}
+
+impl ZoomWizard {
+    /// Opens the wizard on the view `start`, aiming at its center until a destination is
+    /// picked.
+    pub(crate) fn open(&mut self, start: &Camera) {
+        self.start = Some(start.clone());
+        self.destination = start.center;
+        self.picking = true;
+        self.scrub = 0.0;
+        self.error = None;
+    }
+
+    pub(crate) fn is_open(&self) -> bool {
+        self.start.is_some()
+    }
+
+    fn plan(&self, start: &Camera) -> DeepZoomPlan {
+        DeepZoomPlan::new(
+            self.destination,
+            start.scale * 10f64.powf(f64::from(self.depth)),
+            self.duration,
+        )
+        .with_profile(self.profile)
+        .with_rotation(if self.rotate { self.turns * TAU } else { 0.0 })
+    }
+
+    /// `proj` with the planned movie applied, at the scrubbed time, for the viewport to
+    /// show while the wizard is open.
+    pub(crate) fn preview(&self, proj: &Project) -> Option<Project> {
+        let start = self.start.as_ref()?;
+        let mut preview = proj.clone();
+        self.plan(start)
+            .apply(&mut preview.anim, &mut preview.fractal, start);
+        preview.camera = start.clone();
+        preview.anim.t = self.scrub;
+        preview.anim.playing = false;
+        Some(preview)
+    }
+
+    fn use_pasted(&mut self) {
+        match parse_destination(&self.pasted) {
+            Ok(dest) => {
+                self.destination = dest.center;
+                if let (Some(scale), Some(start)) = (dest.scale, &self.start) {
+                    self.depth = (scale / start.scale).log10() as f32;
+                }
+                self.picking = false;
+                self.error = None;
+            }
+            Err(e) => self.error = Some(e),
+        }
+    }
+}
+
+/// Marks the destination in the viewport and, while picking, sets it from a click.
+pub(crate) fn zoom_destination_overlay(
+    ui: &mut egui::Ui,
+    rect: Rect,
+    shown: &Camera,
+    wizard: &mut ZoomWizard,
+) {
+    if !wizard.is_open() {
+        return;
+    }
+    if wizard.picking {
+        let response = ui
+            .interact(rect, Id::new("zoom_wizard_pick"), Sense::click())
+            .on_hover_cursor(egui::CursorIcon::Crosshair);
+        if let Some(pos) = response
+            .interact_pointer_pos()
+            .filter(|_| response.clicked())
+        {
+            let local = pos - rect.min;
+            wizard.destination =
+                shown.pixel_to_plane(local.x, local.y, rect.width(), rect.height());
+            wizard.picking = false;
+        }
+    }
+    let (x, y) = shown.plane_to_pixel(wizard.destination, rect.width(), rect.height());
+    let at = rect.min + vec2(x, y);
+    if rect.expand(12.0).contains(at) {
+        let painter = ui.painter_at(rect);
+        let stroke = Stroke::new(1.5, Color32::from_rgb(255, 140, 100));
+        painter.circle_stroke(at, 8.0, stroke);
+        painter.line_segment([at - vec2(14.0, 0.0), at + vec2(14.0, 0.0)], stroke);
+        painter.line_segment([at - vec2(0.0, 14.0), at + vec2(0.0, 14.0)], stroke);
+    }
+}
+
+/// The wizard window. Accepting replaces the zoom, center and rotation keys of `proj` in
+/// one undoable step; cancelling leaves the project untouched.
+pub(crate) fn zoom_wizard_window(
+    ctx: &egui::Context,
+    wizard: &mut ZoomWizard,
+    proj: &mut Project,
+    view_width: f32,
+) {
+    let Some(start) = wizard.start.clone() else {
+        return;
+    };
+    let mut open = true;
+    let mut accepted = false;
+    egui::Window::new("Deep zoom movie")
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(false)
+        .default_width(320.0)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Destination");
+                ui.add(egui::DragValue::new(&mut wizard.destination.re).speed(1.0 / start.scale));
+                ui.add(egui::DragValue::new(&mut wizard.destination.im).speed(1.0 / start.scale));
+                ui.toggle_value(&mut wizard.picking, "Pick")
+                    .on_hover_text("Click the viewport to set the destination");
+            });
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut wizard.pasted)
+                        .hint_text("-0.7436, 0.1318 or matterhorn:v1:…")
+                        .desired_width(220.0),
+                );
+                if ui.button("Use").clicked() {
+                    wizard.use_pasted();
+                }
+            });
+            if let Some(error) = &wizard.error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+            ui.separator();
+
+            let end = Camera {
+                scale: start.scale * 10f64.powf(f64::from(wizard.depth)),
+                ..start.clone()
+            };
+            let readout = ZoomReadout::new(proj.fractal.kind, &end, view_width);
+            ui.add(
+                egui::Slider::new(&mut wizard.depth, -3.0..=12.0)
+                    .text("Depth (decades)")
+                    .custom_formatter(|v, _| format!("{v:.1}")),
+            );
+            ui.label(format!("Ends at {readout}"));
+            ui.add(
+                egui::Slider::new(&mut wizard.duration, 1.0..=600.0)
+                    .logarithmic(true)
+                    .suffix(" s")
+                    .text("Duration"),
+            );
+            let doublings = wizard.depth * 10f32.log2();
+            ui.weak(format!(
+                "{:.2} doublings per second on average",
+                doublings / wizard.duration
+            ));
+            egui::ComboBox::from_label("Speed")
+                .selected_text(wizard.profile.label())
+                .show_ui(ui, |ui| {
+                    for profile in ZoomProfile::ALL {
+                        ui.selectable_value(&mut wizard.profile, profile, profile.label());
+                    }
+                });
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut wizard.rotate, "Rotate");
+                ui.add_enabled(
+                    wizard.rotate,
+                    egui::DragValue::new(&mut wizard.turns)
+                        .speed(0.05)
+                        .clamp_range(-10.0..=10.0)
+                        .suffix(" turns"),
+                );
+            });
+            ui.separator();
+
+            wizard.scrub = wizard.scrub.min(wizard.duration);
+            ui.add(
+                egui::Slider::new(&mut wizard.scrub, 0.0..=wizard.duration)
+                    .suffix(" s")
+                    .text("Preview"),
+            );
+            ui.weak("Replaces the zoom, center and rotation keys and turns on Auto Max Iter.");
+            ui.horizontal(|ui| {
+                if ui.button("Create keys").clicked() {
+                    accepted = true;
+                }
+                if ui.button("Cancel").clicked() {
+                    wizard.start = None;
+                }
+            });
+        });
+    if accepted {
+        wizard
+            .plan(&start)
+            .apply(&mut proj.anim, &mut proj.fractal, &start);
+        proj.camera = start;
+        proj.anim.t = 0.0;
+        wizard.start = None;
+    }
+    if !open {
+        wizard.start = None;
+    }
+    if !wizard.is_open() {
+        wizard.picking = false;
+    }
+}
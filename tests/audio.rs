@@ -0,0 +1,146 @@
+//! Analyzing generated WAV files and baking their envelopes into tracks.
+
+use std::{f32::consts::TAU, path::Path};
+
+use matterhorn_core::*;
+
+const RATE: u32 = 22_050;
+
+/// Two seconds of a 4 kHz tone with a 60 Hz kick on every half second.
+fn kick_and_hat() -> Vec<f32> {
+    (0..RATE * 2)
+        .map(|i| {
+            let t = i as f32 / RATE as f32;
+            let since_beat = t % 0.5;
+            let kick = if since_beat < 0.1 {
+                (TAU * 60.0 * t).sin() * (1.0 - since_beat / 0.1)
+            } else {
+                0.0
+            };
+            0.8 * kick + 0.1 * (TAU * 4000.0 * t).sin()
+        })
+        .collect()
+}
+
+/// 16-bit mono PCM WAV.
+fn write_wav(path: &Path, samples: &[f32]) {
+    let data_len = samples.len() as u32 * 2;
+    let mut out = Vec::new();
+    out.extend(b"RIFF");
+    out.extend((36 + data_len).to_le_bytes());
+    out.extend(b"WAVEfmt ");
+    out.extend(16u32.to_le_bytes());
+    out.extend(1u16.to_le_bytes()); // PCM
+    out.extend(1u16.to_le_bytes()); // mono
+    out.extend(RATE.to_le_bytes());
+    out.extend((RATE * 2).to_le_bytes());
+    out.extend(2u16.to_le_bytes());
+    out.extend(16u16.to_le_bytes());
+    out.extend(b"data");
+    out.extend(data_len.to_le_bytes());
+    for s in samples {
+        out.extend(((s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16).to_le_bytes());
+    }
+    std::fs::write(path, out).unwrap();
+}
+
+#[test]
+fn decodes_a_wav_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("beat.wav");
+    let samples = kick_and_hat();
+    write_wav(&path, &samples);
+
+    let (decoded, rate) = decode_audio(&path).unwrap();
+    assert_eq!(rate, RATE);
+    assert_eq!(decoded.len(), samples.len());
+    assert!((decoded[100] - samples[100]).abs() < 1e-3);
+
+    let analysis = analyze_audio(&path, 30).unwrap();
+    assert_eq!(analysis.frames(), 60);
+    assert_eq!(analysis.duration, 2.0);
+}
+
+#[test]
+fn bass_follows_the_kick_and_highs_stay_level() {
+    let analysis = analyze_samples(&kick_and_hat(), RATE, 20);
+    let bass = analysis.envelope(AudioBand::Bass);
+    assert_eq!(bass.len(), 40);
+    // Beats land on every tenth frame; between them the bass is nearly silent.
+    for beat in (0..40).step_by(10) {
+        assert!(bass[beat] > 0.5, "frame {beat}: {}", bass[beat]);
+        assert!(
+            bass[beat + 5] < 0.1,
+            "frame {}: {}",
+            beat + 5,
+            bass[beat + 5]
+        );
+    }
+    let high = analysis.envelope(AudioBand::High);
+    assert!(high[5..35].iter().all(|&v| v > 0.5));
+    for band in AudioBand::ALL {
+        let peak = analysis.envelope(band).iter().copied().fold(0.0, f32::max);
+        assert!((peak - 1.0).abs() < 1e-6, "{band:?}");
+    }
+}
+
+#[test]
+fn bakes_one_key_per_frame_up_to_the_duration() {
+    let analysis = analyze_samples(&kick_and_hat(), RATE, 20);
+    let mut anim = Animation {
+        duration: 1.0,
+        ..Animation::default()
+    };
+    let binding = AudioBinding {
+        smoothing: 0.0,
+        gain: 0.5,
+        offset: 0.25,
+        ..AudioBinding::default()
+    };
+    assert_eq!(bake_audio(&mut anim, &analysis, &binding), Ok(21));
+    let keys = &anim.kf_palette.keys;
+    assert_eq!(keys[20].t, 1.0);
+    assert!((keys[10].v - (0.25 + 0.5 * analysis.envelope(AudioBand::Bass)[10])).abs() < 1e-6);
+    // Baking is a pure function of the analysis.
+    let mut again = Animation {
+        duration: 1.0,
+        ..Animation::default()
+    };
+    bake_audio(&mut again, &analysis, &binding).unwrap();
+    assert!(again
+        .kf_palette
+        .keys
+        .iter()
+        .zip(keys)
+        .all(|(a, b)| a.v == b.v));
+}
+
+#[test]
+fn accumulated_zoom_only_ever_deepens() {
+    let analysis = analyze_samples(&kick_and_hat(), RATE, 20);
+    let mut anim = Animation::default();
+    let binding = AudioBinding {
+        band: AudioBand::Level,
+        gain: 3.0,
+        offset: 100.0,
+        accumulate: true,
+        target: TrackKind::Zoom,
+        ..AudioBinding::default()
+    };
+    bake_audio(&mut anim, &analysis, &binding).unwrap();
+    let keys = &anim.kf_zoom.keys;
+    assert!(keys.windows(2).all(|w| w[1].v >= w[0].v));
+    assert!(keys.last().unwrap().v > 100.0);
+
+    anim.zoom_forever = Some(EndlessZoom::with_defaults(1.0));
+    assert!(bake_audio(&mut anim, &analysis, &binding).is_err());
+}
+
+#[test]
+fn rejects_files_that_are_not_audio() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("notes.wav");
+    std::fs::write(&path, "not audio").unwrap();
+    assert!(analyze_audio(&path, 30).is_err());
+    assert!(analyze_audio(&dir.path().join("missing.wav"), 30).is_err());
+}
@@ -0,0 +1,306 @@
+//! Exported frames depend only on the authored project and the frame index. A shell
+//! script stands in for ffmpeg and copies the rendered frames to the output path.
+#![cfg(unix)]
+
+use std::collections::hash_map::{DefaultHasher, RandomState};
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use matterhorn_core::*;
+
+/// Fake ffmpeg: the frames directory is the `-i` argument's parent, the output is last.
+fn fake_ffmpeg(dir: &Path) -> PathBuf {
+    let path = dir.join("ffmpeg");
+    std::fs::write(
+        &path,
+        "#!/bin/sh\nfor out; do :; done\nrm -rf \"$out\"\ncp -r \"$(dirname \"$5\")\" \"$out\"\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    path
+}
+
+fn project() -> Project {
+    let mut proj = Project::new("deterministic")
+        .with_fractal(
+            FractalParams::new(FractalKind::Mandelbrot)
+                .with_max_iter(60)
+                .with_palette_cycle_speed(0.7),
+        )
+        .with_camera(Camera::new(Complex::new(-0.5, 0.0), 40.0));
+    proj.anim.duration = 2.0;
+    proj.anim.kf_zoom.upsert(0.0, 40.0);
+    proj.anim.kf_zoom.upsert(2.0, 160.0);
+    proj.anim.kf_center_x.upsert(0.0, -0.5);
+    proj.anim.kf_center_x.upsert(2.0, -0.74);
+    proj.anim.kf_palette.upsert(1.0, 0.3);
+    proj.export = ExportSettings::new(64, 48).with_fps(7).with_duration(2.0);
+    proj
+}
+
+/// Exports `proj` into `dir` and hashes every frame, in order.
+fn frame_hashes(proj: &Project, dir: &Path) -> Vec<u64> {
+    std::fs::create_dir_all(dir).unwrap();
+    let mut proj = proj.clone();
+    proj.export.out_path = dir.join("frames");
+    export_video_blocking(
+        &proj,
+        &fake_ffmpeg(dir),
+        &ExportProgress::default(),
+        &mut CpuRenderer,
+    )
+    .unwrap();
+    let mut frames: Vec<PathBuf> = std::fs::read_dir(&proj.export.out_path)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .collect();
+    frames.sort();
+    frames
+        .iter()
+        .map(|path| {
+            let mut hasher = DefaultHasher::new();
+            std::fs::read(path).unwrap().hash(&mut hasher);
+            hasher.finish()
+        })
+        .collect()
+}
+
+#[test]
+fn exporting_twice_gives_identical_frames() {
+    let dir = tempfile::tempdir().unwrap();
+    let proj = project();
+    let first = frame_hashes(&proj, &dir.path().join("first"));
+    assert_eq!(first.len(), 14);
+    assert_eq!(first, frame_hashes(&proj, &dir.path().join("second")));
+}
+
+#[test]
+fn preview_state_does_not_leak_into_the_export() {
+    let dir = tempfile::tempdir().unwrap();
+    let saved = dir.path().join("scene.json");
+    save_project(&project(), &saved).unwrap();
+
+    // Leave the preview somewhere arbitrary, playing, with a key selected.
+    let mut scrubbed = project();
+    let random = RandomState::new().hash_one(0u8);
+    scrubbed.anim.t = (random % 10_000) as f32 / 10_000.0 * scrubbed.anim.duration;
+    scrubbed.anim.playing = true;
+    scrubbed.anim.selection = Some(SelectedKey {
+        track: TrackKind::Zoom,
+        index: 1,
+    });
+    scrubbed.anim.advance(0.37);
+
+    let fresh = load_project(&saved).unwrap();
+    assert_eq!(
+        frame_hashes(&scrubbed, &dir.path().join("scrubbed")),
+        frame_hashes(&fresh, &dir.path().join("fresh")),
+        "preview left at t = {}",
+        scrubbed.anim.t
+    );
+}
+
+#[test]
+fn work_directories_resume_only_stale_frames() {
+    let dir = tempfile::tempdir().unwrap();
+    let work = dir.path().join("work");
+    let ffmpeg = fake_ffmpeg(dir.path());
+    let mut proj = project();
+    proj.export = proj.export.with_work_dir(&work);
+    proj.export.out_path = dir.path().join("frames");
+    let export = |proj: &Project| {
+        export_video_blocking(proj, &ffmpeg, &ExportProgress::default(), &mut CpuRenderer)
+            .unwrap()
+            .reused_frames
+    };
+    let manifest = || FrameManifest::load(&work).unwrap().unwrap();
+
+    assert_eq!(export(&proj), 0);
+    assert_eq!(manifest().frames.len(), 14);
+    assert!(manifest().verify(&work).is_ok());
+
+    // A longer export renders only the new tail.
+    proj.export.duration = 3.0;
+    assert_eq!(export(&proj), 14);
+    assert_eq!(manifest().frames.len(), 21);
+
+    // Editing the scene invalidates the frames it changes.
+    proj.anim.kf_palette.upsert(1.0, 0.6);
+    assert_eq!(export(&proj), 0);
+
+    // A shorter one drops the old tail from the folder and the manifest.
+    proj.export.duration = 1.0;
+    assert_eq!(export(&proj), 7);
+    assert_eq!(manifest().frames.len(), 7);
+    let frames = std::fs::read_dir(&work)
+        .unwrap()
+        .filter(|e| e.as_ref().unwrap().path().extension() == Some("png".as_ref()))
+        .count();
+    assert_eq!(frames, 7);
+
+    std::fs::write(work.join("frame_000002.png"), b"not a png").unwrap();
+    assert_eq!(manifest().verify(&work).changed, vec![2]);
+}
+
+#[test]
+fn frame_locked_preview_shows_the_exported_frames() {
+    let dir = tempfile::tempdir().unwrap();
+    let work = dir.path().join("work");
+    let mut proj = project();
+    proj.fractal.auto_iter = true;
+    // Preview-only locks must not leak into a frame-locked preview either.
+    proj.locks.zoom = true;
+    proj.export = proj
+        .export
+        .with_sample_pattern(SamplePattern::Halton)
+        .with_work_dir(&work);
+    proj.export.out_path = dir.path().join("frames");
+    export_video_blocking(
+        &proj,
+        &fake_ffmpeg(dir.path()),
+        &ExportProgress::default(),
+        &mut CpuRenderer,
+    )
+    .unwrap();
+    let manifest = FrameManifest::load(&work).unwrap().unwrap();
+    let fps = proj.export.fps;
+    let preview_hash = |proj: &Project, frame: u32| {
+        let eval = evaluate_export_frame(proj, frame);
+        FrameSnapshot {
+            size: (proj.export.width, proj.export.height),
+            fractal: &eval.fractal,
+            sampling: eval.fractal.sampling,
+            precision: eval.fractal.precision,
+            camera: &eval.camera,
+            zoom_readout: proj.export.burn_in_zoom,
+            annotations: &[],
+            side_by_side: None,
+            stylize: None,
+            adaptive_aa: None,
+            equalizer: None,
+        }
+        .hash()
+    };
+
+    proj.anim.frame_locked = true;
+    proj.anim.playing = true;
+    let mut shown = 0;
+    while shown < proj.export.frame_count() {
+        let frame = frame_at(proj.anim.t, fps);
+        assert_eq!(frame, shown);
+        assert_eq!(preview_hash(&proj, frame), manifest.frames[&frame].params);
+        proj.anim.step_frame(fps);
+        shown += 1;
+    }
+
+    proj.anim.go_to_frame(9, fps);
+    assert_eq!(frame_at(proj.anim.t, fps), 9);
+    assert_eq!(preview_hash(&proj, 9), manifest.frames[&9].params);
+}
+
+#[test]
+fn the_poster_is_a_copy_of_an_exported_frame() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut proj = project();
+    proj.export = proj.export.with_poster_time(1.0);
+    proj.export.out_path = dir.path().join("frames");
+    let report = export_video_blocking(
+        &proj,
+        &fake_ffmpeg(dir.path()),
+        &ExportProgress::default(),
+        &mut CpuRenderer,
+    )
+    .unwrap();
+    let poster = report.poster.unwrap();
+    assert_eq!(poster, dir.path().join("frames_poster.png"));
+    assert_eq!(
+        std::fs::read(&poster).unwrap(),
+        std::fs::read(proj.export.out_path.join(frame_file_name(7))).unwrap()
+    );
+}
+
+#[test]
+fn the_project_seed_decides_the_jitter() {
+    let dir = tempfile::tempdir().unwrap();
+    let export = |proj: &Project, name: &str| {
+        let work = dir.path().join(name).join("work");
+        let mut proj = proj.clone();
+        proj.export = proj.export.with_work_dir(&work);
+        let frames = frame_hashes(&proj, &dir.path().join(name));
+        let manifest = FrameManifest::load(&work).unwrap().unwrap();
+        let params: Vec<String> = manifest.frames.into_values().map(|h| h.params).collect();
+        (frames, params)
+    };
+    let mut proj = project().with_seed(0x5eed);
+    proj.export = proj.export.with_sample_pattern(SamplePattern::R2);
+
+    let (frames, params) = export(&proj, "first");
+    let reloaded = dir.path().join("seeded.json");
+    save_project(&proj, &reloaded).unwrap();
+    assert_eq!(
+        (frames, params.clone()),
+        export(&load_project(&reloaded).unwrap(), "second")
+    );
+
+    // Another seed jitters every frame differently.
+    let (_, reseeded) = export(&proj.clone().with_seed(0x5eee), "reseeded");
+    assert!(params.iter().zip(&reseeded).all(|(a, b)| a != b));
+}
+
+#[test]
+fn a_perturbation_zoom_to_1e50_exports_with_detail() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut proj = Project::new("deep")
+        .with_fractal(
+            FractalParams::new(FractalKind::Mandelbrot)
+                .with_max_iter(4000)
+                .with_palette_repeat(64.0),
+        )
+        .with_camera(Camera::at_sum(&SEAHORSE_DEEP_POINT, 1e49));
+    proj.precision = Precision::Perturbation;
+    // Doubling every second, from an eighth of 1e50 to 1e50.
+    proj.anim.zoom_forever = Some(EndlessZoom {
+        speed: 0.5,
+        reverse: true,
+        ..EndlessZoom::with_defaults(1.25e49)
+    });
+    proj.export = ExportSettings::new(64, 48).with_fps(1).with_duration(4.0);
+    proj.export.out_path = dir.path().join("frames");
+    let stats_path = dir.path().join("stats.csv");
+    let progress = ExportProgress {
+        stats_log: std::sync::Mutex::new(Some(StatsLog::open(&stats_path).unwrap())),
+        ..ExportProgress::default()
+    };
+    export_video_blocking(&proj, &fake_ffmpeg(dir.path()), &progress, &mut CpuRenderer).unwrap();
+
+    let mut frames: Vec<PathBuf> = std::fs::read_dir(&proj.export.out_path)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .collect();
+    frames.sort();
+    assert_eq!(frames.len(), 4);
+    for path in &frames {
+        let rgba = image::open(path).unwrap().to_rgba8().into_raw();
+        let mut colors: Vec<&[u8]> = rgba.chunks(4).collect();
+        colors.sort_unstable();
+        colors.dedup();
+        assert!(
+            colors.len() >= 300,
+            "{} shows only {} colors",
+            path.display(),
+            colors.len()
+        );
+    }
+
+    // All four frames share the first one's reference orbit.
+    let stats = std::fs::read_to_string(&stats_path).unwrap();
+    let mut rows = stats
+        .lines()
+        .map(|line| line.split(',').collect::<Vec<_>>());
+    let header = rows.next().unwrap();
+    let column = |name: &str| header.iter().position(|&c| c == name).unwrap();
+    let (hits, misses) = (column("cache_hits"), column("cache_misses"));
+    let counts: Vec<(&str, &str)> = rows.map(|row| (row[hits], row[misses])).collect();
+    assert_eq!(counts, [("0", "1"), ("1", "0"), ("1", "0"), ("1", "0")]);
+}
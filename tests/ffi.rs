@@ -0,0 +1,108 @@
+//! Drives the C API through its exported symbols, the way `include/matterhorn.h` declares them.
+#![cfg(feature = "ffi")]
+
+use std::ffi::{c_char, CStr, CString};
+
+use matterhorn_core::{ffi::MhStatus, FractalKind, Project};
+
+#[repr(C)]
+struct MhRenderer {
+    _private: [u8; 0],
+}
+
+extern "C" {
+    fn mh_renderer_new() -> *mut MhRenderer;
+    fn mh_renderer_free(renderer: *mut MhRenderer);
+    fn mh_renderer_load_project_json(renderer: *mut MhRenderer, json: *const c_char) -> MhStatus;
+    fn mh_renderer_set_time(renderer: *mut MhRenderer, seconds: f32) -> MhStatus;
+    fn mh_renderer_render(
+        renderer: *mut MhRenderer,
+        width: u32,
+        height: u32,
+        rgba: *mut u8,
+        len: usize,
+    ) -> MhStatus;
+    fn mh_last_error() -> *const c_char;
+}
+
+fn last_error() -> String {
+    let message = unsafe { mh_last_error() };
+    assert!(!message.is_null());
+    unsafe { CStr::from_ptr(message) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn project_json(proj: &Project) -> CString {
+    CString::new(serde_json::to_string(proj).unwrap()).unwrap()
+}
+
+#[test]
+fn renders_a_loaded_project_into_a_caller_buffer() {
+    let mut proj = Project::default();
+    proj.fractal.kind = FractalKind::Julia;
+    proj.fractal.max_iter = 100;
+    let expected = {
+        let frame =
+            matterhorn_core::evaluate_frame(&proj, 0.5, matterhorn_core::EvalTarget::Export);
+        matterhorn_core::render_cpu((48, 32), &frame.fractal, &frame.camera)
+    };
+
+    unsafe {
+        let renderer = mh_renderer_new();
+        assert_eq!(
+            mh_renderer_load_project_json(renderer, project_json(&proj).as_ptr()),
+            MhStatus::Ok
+        );
+        assert_eq!(mh_renderer_set_time(renderer, 0.5), MhStatus::Ok);
+        let mut rgba = vec![0u8; 48 * 32 * 4];
+        assert_eq!(
+            mh_renderer_render(renderer, 48, 32, rgba.as_mut_ptr(), rgba.len()),
+            MhStatus::Ok
+        );
+        assert_eq!(rgba, expected);
+        mh_renderer_free(renderer);
+    }
+}
+
+#[test]
+fn reports_errors_with_a_message() {
+    unsafe {
+        let renderer = mh_renderer_new();
+        let bad = CString::new("{ not json").unwrap();
+        assert_eq!(
+            mh_renderer_load_project_json(renderer, bad.as_ptr()),
+            MhStatus::InvalidProject
+        );
+        assert!(!last_error().is_empty());
+
+        let mut small = vec![0u8; 16];
+        assert_eq!(
+            mh_renderer_render(renderer, 4, 4, small.as_mut_ptr(), small.len()),
+            MhStatus::BufferTooSmall
+        );
+        assert!(last_error().contains("needs 64 bytes"));
+        assert_eq!(
+            mh_renderer_render(renderer, 0, 4, small.as_mut_ptr(), small.len()),
+            MhStatus::InvalidArgument
+        );
+        assert_eq!(
+            mh_renderer_render(renderer, 2, 2, std::ptr::null_mut(), 16),
+            MhStatus::NullArgument
+        );
+        assert_eq!(
+            mh_renderer_set_time(renderer, f32::NAN),
+            MhStatus::InvalidArgument
+        );
+
+        let mut proj = Project::default();
+        proj.script.enabled = true;
+        proj.script.source = "fn frame(t, params) {".into();
+        assert_eq!(
+            mh_renderer_load_project_json(renderer, project_json(&proj).as_ptr()),
+            MhStatus::ScriptError
+        );
+        mh_renderer_free(renderer);
+        mh_renderer_free(std::ptr::null_mut());
+    }
+}
@@ -0,0 +1,157 @@
+//! Golden-image regression tests: small fixed scenes compared against committed PNGs.
+//!
+//! Regenerate the references with `UPDATE_GOLDEN=1 cargo test --test golden`.
+//! Mismatches write `<scene>.actual.png` and `<scene>.diff.png` next to the
+//! test binary's temp dir and print their paths.
+
+mod scenes;
+
+use std::path::{Path, PathBuf};
+
+use image::RgbaImage;
+use matterhorn_core::{render_cpu, FractalKind, FractalParams};
+#[cfg(feature = "gpu")]
+use matterhorn_core::{create_renderer, render_image, RenderBackend};
+use scenes::{golden_scenes, Scene, GOLDEN_SIZE};
+
+/// Largest per-channel difference tolerated on the CPU backend.
+const CPU_TOLERANCE: u8 = 3;
+/// The GPU evaluates in different precision and order, so it gets more slack.
+#[cfg(feature = "gpu")]
+const GPU_TOLERANCE: u8 = 24;
+
+fn reference_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{name}.png"))
+}
+
+fn blessing() -> bool {
+    std::env::var_os("UPDATE_GOLDEN").is_some_and(|v| v != "0")
+}
+
+fn to_image(pixels: Vec<u8>) -> RgbaImage {
+    RgbaImage::from_raw(GOLDEN_SIZE, GOLDEN_SIZE, pixels).expect("render size mismatch")
+}
+
+/// Compares `actual` with the stored reference; returns a failure message on mismatch.
+fn compare(scene: &Scene, label: &str, actual: &RgbaImage, tolerance: u8) -> Option<String> {
+    let path = reference_path(scene.name);
+    let expected = match image::open(&path) {
+        Ok(img) => img.to_rgba8(),
+        Err(e) => {
+            return Some(format!(
+                "{}: no reference at {} ({e}); run with UPDATE_GOLDEN=1",
+                scene.name,
+                path.display()
+            ))
+        }
+    };
+    if expected.dimensions() != actual.dimensions() {
+        return Some(format!("{}: reference has a different size", scene.name));
+    }
+
+    let mut diff = RgbaImage::new(GOLDEN_SIZE, GOLDEN_SIZE);
+    let mut worst = 0u8;
+    let mut bad_pixels = 0usize;
+    for ((a, e), d) in actual
+        .pixels()
+        .zip(expected.pixels())
+        .zip(diff.pixels_mut())
+    {
+        let delta = (0..4).map(|c| a[c].abs_diff(e[c])).max().unwrap_or(0);
+        worst = worst.max(delta);
+        if delta > tolerance {
+            bad_pixels += 1;
+            *d = image::Rgba([255, 0, 255, 255]);
+        } else {
+            let g = e[0] / 4 + e[1] / 4 + e[2] / 4;
+            *d = image::Rgba([g, g, g, 255]);
+        }
+    }
+    if bad_pixels == 0 {
+        return None;
+    }
+
+    let out_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden");
+    let _ = std::fs::create_dir_all(&out_dir);
+    let actual_path = out_dir.join(format!("{}.{label}.actual.png", scene.name));
+    let diff_path = out_dir.join(format!("{}.{label}.diff.png", scene.name));
+    let _ = actual.save(&actual_path);
+    let _ = diff.save(&diff_path);
+    Some(format!(
+        "{} ({label}): {bad_pixels} pixels differ by more than {tolerance} (worst {worst}); see {} and {}",
+        scene.name,
+        actual_path.display(),
+        diff_path.display()
+    ))
+}
+
+fn render_scene_cpu(scene: &Scene) -> RgbaImage {
+    to_image(render_cpu(
+        (GOLDEN_SIZE, GOLDEN_SIZE),
+        &scene.params,
+        &scene.camera,
+    ))
+}
+
+#[test]
+fn cpu_matches_golden_images() {
+    let mut failures = Vec::new();
+    for scene in golden_scenes() {
+        let actual = render_scene_cpu(&scene);
+        if blessing() {
+            actual
+                .save(reference_path(scene.name))
+                .expect("could not write reference image");
+            continue;
+        }
+        failures.extend(compare(&scene, "cpu", &actual, CPU_TOLERANCE));
+    }
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+#[test]
+fn multibrot_at_power_two_matches_the_mandelbrot_reference() {
+    // Power 2 takes the quadratic step and normalizes by log 2, so nothing may differ.
+    let mut scene = golden_scenes()
+        .into_iter()
+        .find(|scene| scene.name == "mandelbrot")
+        .expect("the mandelbrot scene");
+    scene.params = FractalParams {
+        kind: FractalKind::Multibrot,
+        ..scene.params
+    }
+    .with_power(2.0);
+    let actual = render_scene_cpu(&scene);
+    let failure = compare(&scene, "multibrot_2", &actual, 0);
+    assert!(failure.is_none(), "\n{}", failure.unwrap_or_default());
+}
+
+#[cfg(feature = "gpu")]
+#[test]
+fn gpu_matches_golden_images() {
+    // Formulas without a shader fall back to the CPU, as in the app.
+    let mut gpu = match create_renderer(RenderBackend::Gpu) {
+        Ok(gpu) => gpu,
+        Err(e) => {
+            eprintln!("Skipping GPU golden images: {e}");
+            return;
+        }
+    };
+    let mut failures = Vec::new();
+    for scene in golden_scenes() {
+        let actual = to_image(
+            render_image(
+                (GOLDEN_SIZE, GOLDEN_SIZE),
+                &scene.params,
+                &scene.camera,
+                gpu.as_mut(),
+                0,
+            )
+            .expect("GPU render failed"),
+        );
+        failures.extend(compare(&scene, "gpu", &actual, GPU_TOLERANCE));
+    }
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
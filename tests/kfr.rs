@@ -0,0 +1,92 @@
+//! Parsing and applying the `.kfr` fixtures in `tests/fixtures/`.
+
+use std::path::Path;
+
+use matterhorn_core::*;
+
+fn fixture(name: &str) -> Result<KfrLocation, String> {
+    load_kfr(
+        &Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name),
+    )
+}
+
+#[test]
+fn parses_a_typical_location() {
+    let loc = fixture("seahorse.kfr").unwrap();
+    assert_eq!(loc.re, "-0.743643887037158704752191506114774");
+    assert_eq!(loc.im, "0.131825904205311970493132056385139");
+    assert_eq!(loc.zoom, 2500.0);
+    assert_eq!(loc.iterations, Some(3000));
+    assert_eq!(loc.rotate, Some(30.0));
+    assert_eq!(loc.colors.len(), 8);
+    assert_eq!(loc.colors[1], [128, 0, 64]);
+    // IterDiv, ColorOffset, Ratio, Smooth and Slopes have no equivalent.
+    assert_eq!(loc.warnings.len(), 5);
+}
+
+#[test]
+fn applies_camera_iterations_and_palette() {
+    let loc = fixture("seahorse.kfr").unwrap();
+    let mut proj = Project::default();
+    proj.export.height = 1080;
+    let warnings = loc.apply(&mut proj, true);
+    assert_eq!(warnings.len(), loc.warnings.len());
+    assert!((proj.camera.center.re + 0.743_643_9).abs() < 1e-6);
+    assert!((proj.camera.center.im - 0.131_825_9).abs() < 1e-6);
+    // Every digit of the file survives in the exact center.
+    let exact = proj.camera.exact_center();
+    assert_eq!(exact.re.to_decimal(), loc.re);
+    assert_eq!(exact.im.to_decimal(), loc.im);
+    // Zoom 1 is 4 units tall, so 1080 px at zoom 2500 is 675,000 px per unit.
+    assert_eq!(proj.camera.scale, 675_000.0);
+    assert!((proj.camera.rotation - 30f32.to_radians()).abs() < 1e-6);
+    assert_eq!(proj.fractal.kind, FractalKind::Mandelbrot);
+    assert_eq!(proj.fractal.max_iter, 3000);
+    assert_eq!(proj.fractal.palette.len(), 9);
+    assert_eq!(proj.fractal.palette[0].color, [1.0, 1.0, 1.0]);
+    assert_eq!(proj.fractal.palette[8].pos, 1.0);
+    assert_eq!(proj.fractal.palette[8].color, [1.0, 1.0, 1.0]);
+}
+
+#[test]
+fn keeps_the_palette_when_asked() {
+    let loc = fixture("seahorse.kfr").unwrap();
+    let mut proj = Project::default();
+    let before = proj.fractal.palette.clone();
+    loc.apply(&mut proj, false);
+    assert_eq!(proj.fractal.palette, before);
+}
+
+#[test]
+fn deep_power_three_location_warns_about_precision() {
+    let loc = fixture("deep_multibrot.kfr").unwrap();
+    assert!(loc.colors.is_empty());
+    let mut proj = Project::default();
+    let warnings = loc.apply(&mut proj, true);
+    assert_eq!(proj.fractal.kind, FractalKind::Multibrot);
+    assert_eq!(proj.fractal.power, 3.0);
+    assert!(proj.camera.scale.is_finite());
+    assert!(warnings.iter().any(|w| w.contains("SoftwareVersion")));
+    assert!(warnings.iter().any(|w| w.contains("deeper than")));
+}
+
+#[test]
+fn rejects_files_without_a_location() {
+    assert!(fixture("missing_zoom.kfr")
+        .unwrap_err()
+        .contains("missing Zoom"));
+    assert!(parse_kfr("Re: abc\nIm: 0\nZoom: 1").is_err());
+    assert!(parse_kfr("Re: 0\nIm: 0\nZoom: -1").is_err());
+}
+
+#[test]
+fn unreadable_colors_and_other_fractal_types_only_warn() {
+    let loc = parse_kfr("Re: 0\nIm: 0\nZoom: 1\nColors: 1,2\nFractalType: 7").unwrap();
+    assert!(loc.colors.is_empty());
+    let mut proj = Project::default();
+    let warnings = loc.apply(&mut proj, true);
+    assert_eq!(proj.fractal.kind, FractalKind::Mandelbrot);
+    assert_eq!(warnings.len(), 2);
+}
@@ -0,0 +1,214 @@
+//! Frames over the export's frame memory are rendered in bands and streamed to disk.
+#![cfg(unix)]
+
+use std::path::Path;
+
+use matterhorn_core::*;
+
+fn view() -> (FractalParams, Camera) {
+    let params = FractalParams::new(FractalKind::Mandelbrot).with_max_iter(40);
+    let cam = Camera::new(Complex::new(-0.6, 0.1), 150.0).with_rotation(0.3);
+    (params, cam)
+}
+
+#[test]
+fn bands_match_the_whole_frame() {
+    let (params, cam) = view();
+    let size = (500, 333);
+    let whole = render_image(size, &params, &cam, &mut CpuRenderer, 256).unwrap();
+    let mut banded = Vec::new();
+    for top in (0..size.1).step_by(70) {
+        let rows = top..(top + 70).min(size.1);
+        let (pixels, _) = render_band(size, rows, &params, &cam, &mut CpuRenderer, 256).unwrap();
+        banded.extend(pixels);
+    }
+    assert!(banded == whole);
+}
+
+#[test]
+fn streamed_png_matches_an_in_memory_one() {
+    let dir = tempfile::tempdir().unwrap();
+    let (params, cam) = view();
+    let size = (640, 480);
+    // 1.2 MiB of pixels against a 1 MiB budget.
+    let mode = FrameMode::for_size(size, 1 << 20).unwrap();
+    assert_eq!(mode, FrameMode::Banded(409));
+
+    let streamed = dir.path().join("streamed.png");
+    let stats = write_png(
+        &streamed,
+        size,
+        &params,
+        &cam,
+        &mut CpuRenderer,
+        &QualityProfile::preview(),
+        mode,
+    )
+    .unwrap();
+    assert_eq!(stats.tiles(), 2, "one tile per band");
+    let whole = dir.path().join("whole.png");
+    write_png(
+        &whole,
+        size,
+        &params,
+        &cam,
+        &mut CpuRenderer,
+        &QualityProfile::preview(),
+        FrameMode::InMemory,
+    )
+    .unwrap();
+
+    let streamed = image::open(streamed).unwrap().into_rgba8();
+    assert_eq!(streamed.dimensions(), size);
+    assert!(streamed == image::open(whole).unwrap().into_rgba8());
+}
+
+#[test]
+fn zoom_readout_is_burned_in_alike_when_streaming() {
+    let dir = tempfile::tempdir().unwrap();
+    let (params, cam) = view();
+    let size = (640, 480);
+    let write = |name: &str, mode: FrameMode, zoom_readout: bool| {
+        let path = dir.path().join(name);
+        let frame = FrameOptions {
+            mode,
+            zoom_readout,
+            annotations: &[],
+            stylize: Stylize::default(),
+            adaptive_aa: AdaptiveAa::default(),
+        };
+        write_png(
+            &path,
+            size,
+            &params,
+            &cam,
+            &mut CpuRenderer,
+            &QualityProfile::preview(),
+            frame,
+        )
+        .unwrap();
+        image::open(path).unwrap().into_rgba8()
+    };
+    let plain = write("plain.png", FrameMode::InMemory, false);
+    let whole = write("whole.png", FrameMode::InMemory, true);
+    let streamed = write("streamed.png", FrameMode::Banded(100), true);
+    assert!(streamed == whole);
+    // Only the bottom-left corner changes.
+    assert_eq!(whole.get_pixel(600, 20), plain.get_pixel(600, 20));
+    assert_ne!(whole.get_pixel(8, 470), plain.get_pixel(8, 470));
+}
+
+#[test]
+fn transparent_exteriors_keep_their_alpha_when_streaming() {
+    let dir = tempfile::tempdir().unwrap();
+    let (params, cam) = view();
+    let params = params.with_exterior(ExteriorMode::Transparent, [0.0; 3]);
+    let size = (320, 240);
+    let write = |name: &str, mode: FrameMode| {
+        let path = dir.path().join(name);
+        write_png(
+            &path,
+            size,
+            &params,
+            &cam,
+            &mut CpuRenderer,
+            &QualityProfile::preview(),
+            mode,
+        )
+        .unwrap();
+        image::open(path).unwrap().into_rgba8()
+    };
+    let whole = write("whole.png", FrameMode::InMemory);
+    assert!(write("streamed.png", FrameMode::Banded(50)) == whole);
+    let alphas: Vec<u8> = whole.pixels().map(|px| px[3]).collect();
+    assert!(alphas.contains(&0), "the exterior is transparent");
+    assert!(alphas.contains(&255), "the interior stays opaque");
+}
+
+#[test]
+fn stylized_frames_stream_alike_in_few_colors_and_blocks() {
+    let dir = tempfile::tempdir().unwrap();
+    let (params, cam) = view();
+    let size = (322, 240);
+    let stylize = Stylize {
+        enabled: true,
+        colors: 6,
+        pixel_size: 4,
+        ..Stylize::default()
+    };
+    let write = |name: &str, mode: FrameMode| {
+        let path = dir.path().join(name);
+        let frame = FrameOptions {
+            stylize,
+            ..FrameOptions::from(mode)
+        };
+        let stats = write_png(
+            &path,
+            size,
+            &params,
+            &cam,
+            &mut CpuRenderer,
+            &QualityProfile::preview(),
+            frame,
+        )
+        .unwrap();
+        // Rendered at a quarter of the size, rounded up.
+        assert_eq!(stats.width, 81);
+        image::open(path).unwrap().into_rgba8()
+    };
+    let whole = write("whole.png", FrameMode::InMemory);
+    assert_eq!(whole.dimensions(), size);
+    assert!(write("streamed.png", FrameMode::Banded(50)) == whole);
+    let mut colors: Vec<[u8; 4]> = whole.pixels().map(|px| px.0).collect();
+    colors.sort_unstable();
+    colors.dedup();
+    assert!(colors.len() <= 6, "{} colors", colors.len());
+    // 81 blocks of 4 make 324 columns, so one comes off each side: blocks start at 3.
+    assert_eq!(whole.get_pixel(3, 0), whole.get_pixel(6, 3));
+}
+
+fn project(dir: &Path, width: u32, height: u32) -> Project {
+    let (params, cam) = view();
+    let mut proj = Project::new("large").with_fractal(params).with_camera(cam);
+    proj.export = ExportSettings::new(width, height)
+        .with_fps(1)
+        .with_duration(1.0)
+        .with_out_path(dir.join("clip.mp4"))
+        .with_frame_memory_mb(1);
+    proj
+}
+
+#[test]
+fn export_streams_frames_over_the_budget() {
+    let dir = tempfile::tempdir().unwrap();
+    let proj = project(dir.path(), 640, 480);
+    assert_eq!(proj.export.frame_mode().unwrap(), FrameMode::Banded(409));
+    let progress = ExportProgress::default();
+    export_video_blocking(&proj, Path::new("true"), &progress, &mut CpuRenderer).unwrap();
+    assert_eq!(progress.fraction(), (1, 1));
+}
+
+#[test]
+fn export_refuses_impossible_sizes_up_front() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut proj = project(dir.path(), 200_000, 1080);
+    let err = export_video_blocking(
+        &proj,
+        Path::new("true"),
+        &ExportProgress::default(),
+        &mut CpuRenderer,
+    )
+    .unwrap_err();
+    assert!(
+        err.to_string().contains("larger than 65536 px per side"),
+        "{err}"
+    );
+
+    // Passes need whole frames, so they cannot be combined with streaming.
+    proj.export.width = 640;
+    proj.export.passes.iteration = true;
+    let err = proj.export.frame_mode().unwrap_err();
+    assert!(err.to_string().contains("compositing passes"), "{err}");
+    proj.export.frame_memory_mb = 512;
+    assert_eq!(proj.export.frame_mode().unwrap(), FrameMode::InMemory);
+}
@@ -0,0 +1,110 @@
+//! Compositing passes written by an export, with `true` standing in for ffmpeg.
+#![cfg(unix)]
+
+use std::path::Path;
+
+use matterhorn_core::*;
+
+fn project(dir: &Path, format: AovFormat) -> Project {
+    let mut proj = Project::new("passes")
+        .with_fractal(FractalParams::new(FractalKind::Mandelbrot).with_max_iter(60))
+        .with_camera(Camera::new(Complex::new(-0.5, 0.0), 24.0));
+    proj.fractal.orbit.enabled = true;
+    proj.export = ExportSettings::new(96, 64)
+        .with_fps(2)
+        .with_duration(1.0)
+        .with_out_path(dir.join("clip.mp4"))
+        .with_passes(AovPasses {
+            interior: true,
+            iteration: true,
+            trap: true,
+            format,
+        });
+    proj
+}
+
+#[test]
+fn export_writes_each_selected_pass_per_frame() {
+    let dir = tempfile::tempdir().unwrap();
+    let proj = project(dir.path(), AovFormat::Exr);
+    let report = export_video_blocking(
+        &proj,
+        Path::new("true"),
+        &ExportProgress::default(),
+        &mut CpuRenderer,
+    )
+    .unwrap();
+    let passes = dir.path().join("clip_passes");
+    assert_eq!(report.passes.as_deref(), Some(passes.as_path()));
+
+    let mut names: Vec<String> = std::fs::read_dir(&passes)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    assert_eq!(
+        names,
+        [
+            "frame_000000.interior.exr",
+            "frame_000000.iter.exr",
+            "frame_000000.trap.exr",
+            "frame_000001.interior.exr",
+            "frame_000001.iter.exr",
+            "frame_000001.trap.exr",
+        ]
+    );
+
+    // The float pass holds the same values the preview's samples do.
+    let frame = evaluate_frame(&proj, 0.0, EvalTarget::Export);
+    let (_, _, samples) =
+        render_image_samples((96, 64), &frame.fractal, &frame.camera, &mut CpuRenderer, 0).unwrap();
+    let values: Vec<f32> = samples
+        .iter()
+        .map(|s| AovPass::Iteration.value(s, &frame.fractal))
+        .collect();
+    let mut expected = Vec::new();
+    write_exr(&mut expected, 96, 64, &values).unwrap();
+    assert_eq!(
+        std::fs::read(passes.join("frame_000000.iter.exr")).unwrap(),
+        expected
+    );
+}
+
+#[test]
+fn png_passes_are_16_bit_masks() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut proj = project(dir.path(), AovFormat::Png);
+    proj.export.passes.iteration = false;
+    proj.export.passes.trap = false;
+    export_video_blocking(
+        &proj,
+        Path::new("true"),
+        &ExportProgress::default(),
+        &mut CpuRenderer,
+    )
+    .unwrap();
+
+    let mask = image::open(dir.path().join("clip_passes/frame_000001.interior.png"))
+        .unwrap()
+        .into_luma16();
+    assert_eq!(mask.dimensions(), (96, 64));
+    // The view is centered on the main cardioid: white inside, black around the edges.
+    assert_eq!(mask.get_pixel(48, 32)[0], u16::MAX);
+    assert_eq!(mask.get_pixel(0, 0)[0], 0);
+    assert!(mask.pixels().all(|p| p[0] == 0 || p[0] == u16::MAX));
+    assert!(!dir
+        .path()
+        .join("clip_passes/frame_000001.iter.png")
+        .exists());
+}
+
+#[test]
+fn beauty_is_unchanged_by_sampling() {
+    let params = FractalParams::new(FractalKind::Julia).with_max_iter(80);
+    let cam = Camera::new(Complex::new(0.0, 0.0), 60.0).with_rotation(0.7);
+    let (pixels, stats, samples) =
+        render_image_samples((200, 120), &params, &cam, &mut CpuRenderer, 256).unwrap();
+    assert_eq!(pixels, render_cpu((200, 120), &params, &cam));
+    assert_eq!(samples.len(), 200 * 120);
+    assert_eq!(stats.tiles(), 1);
+}
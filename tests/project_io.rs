@@ -0,0 +1,335 @@
+//! Project and palette IO errors, one per `ProjectError` variant, against the fixtures in
+//! `tests/fixtures/projects/`, and upgrades of older project files.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use matterhorn_core::*;
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/projects")
+        .join(name)
+}
+
+#[test]
+fn unknown_toml_field_points_at_the_typo() {
+    let path = fixture("seahorse.mahproj");
+    let err = load_project(&path).unwrap_err();
+    let ProjectError::Toml { at, span, message } = &err else {
+        panic!("expected a TOML error, got {err:?}");
+    };
+    assert_eq!(
+        *at,
+        Some(TextPosition {
+            line: 25,
+            column: 1
+        })
+    );
+    let text = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(&text[span.clone().unwrap()], "colour");
+    assert_eq!(message, "unknown field `colour`, did you mean `color`?");
+    assert_eq!(
+        err.at(Path::new("seahorse.mahproj")),
+        "seahorse.mahproj:25:1 — unknown field `colour`, did you mean `color`?"
+    );
+
+    // With the typo fixed, the fixture is a complete project.
+    let fixed = project_from_str(&text.replace("colour", "color"), &path).unwrap();
+    assert_eq!(fixed.name, "Seahorse valley");
+    assert!(fixed.fractal.orbit.enabled);
+}
+
+#[test]
+fn json_errors_carry_line_and_column() {
+    let err = load_project(&fixture("truncated.json")).unwrap_err();
+    let ProjectError::Json { at, message } = &err else {
+        panic!("expected a JSON error, got {err:?}");
+    };
+    assert_eq!(at.line, 8);
+    assert!(message.starts_with("EOF while parsing"), "{message}");
+    assert!(!message.contains("at line"), "{message}");
+
+    let err = load_project(&fixture("unknown_kind.json")).unwrap_err();
+    let ProjectError::Json { at, message } = &err else {
+        panic!("expected a JSON error, got {err:?}");
+    };
+    assert_eq!(at.line, 5);
+    assert_eq!(
+        message,
+        "unknown variant `mandelbort`, did you mean `mandelbrot`?"
+    );
+}
+
+#[test]
+fn newer_files_report_their_version() {
+    // Checked before fields, so the new field in the fixture is not what gets reported.
+    let err = load_project(&fixture("from_the_future.json")).unwrap_err();
+    assert!(
+        matches!(err, ProjectError::UnsupportedVersion { found: 8 }),
+        "{err:?}"
+    );
+    assert!(err.to_string().contains("format 8"));
+}
+
+#[test]
+fn extension_selects_the_format() {
+    let err = load_project(&fixture("notes.txt")).unwrap_err();
+    assert!(
+        matches!(&err, ProjectError::UnknownExtension(ext) if ext == "txt"),
+        "{err:?}"
+    );
+
+    let dir = tempfile::tempdir().unwrap();
+    let err = save_project(&Project::default(), &dir.path().join("scene.yaml")).unwrap_err();
+    assert!(matches!(err, ProjectError::UnknownExtension(_)), "{err:?}");
+    assert!(!dir.path().join("scene.yaml").exists());
+}
+
+#[test]
+fn io_failures_are_reported_not_swallowed() {
+    let err = load_project(&fixture("missing.json")).unwrap_err();
+    assert!(matches!(err, ProjectError::Io(_)), "{err:?}");
+    assert!(err
+        .at(Path::new("missing.json"))
+        .starts_with("missing.json: "));
+
+    let dir = tempfile::tempdir().unwrap();
+    let unwritable = dir.path().join("no-such-dir").join("scene.json");
+    let err = save_project(&Project::default(), &unwritable).unwrap_err();
+    assert!(matches!(err, ProjectError::Io(_)), "{err:?}");
+    let err = save_palette(&default_palette(), &unwritable.with_extension("ahpal")).unwrap_err();
+    assert!(matches!(err, ProjectError::Io(_)), "{err:?}");
+}
+
+#[test]
+fn palettes_reject_unknown_fields() {
+    let err = load_palette(&fixture("broken.ahpal")).unwrap_err();
+    let ProjectError::Json { at, message } = &err else {
+        panic!("expected a JSON error, got {err:?}");
+    };
+    assert_eq!(at.line, 3);
+    assert_eq!(message, "unknown field `colour`, did you mean `color`?");
+}
+
+#[test]
+fn saved_projects_load_back_in_both_formats() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut proj = Project::new("Round trip");
+    let mut label = Annotation::new("minibrot").on_plane(Complex::new(-1.75, 0.0), 0.01);
+    label.opacity.upsert(1.0, 0.5);
+    proj.annotations = vec![Annotation::new("Title").with_times(0.0, Some(3.0)), label];
+    proj.julia_view = JuliaView::default().with_c(Complex::new(0.285, 0.01));
+    proj.export = proj.export.with_viewport(ExportViewport::SideBySide);
+    proj.fractal = FractalParams::new(FractalKind::Phoenix)
+        .with_julia_c(Complex::new(0.56, -0.03))
+        .with_phoenix_p(Complex::new(-0.45, 0.1))
+        .with_lyapunov_sequence("AABAB");
+    for name in ["scene.json", "scene.mahproj"] {
+        let path = dir.path().join(name);
+        save_project(&proj, &path).unwrap();
+        let loaded = load_project(&path).unwrap();
+        assert_eq!(loaded.name, "Round trip");
+        assert_eq!(loaded.version, PROJECT_VERSION);
+        let [title, label] = loaded.annotations.as_slice() else {
+            panic!("{:?}", loaded.annotations);
+        };
+        assert_eq!(title.end, Some(3.0));
+        assert_eq!(label.anchor, AnnotationAnchor::Plane);
+        assert_eq!(label.end, None);
+        assert_eq!(label.opacity.keys.len(), 1);
+        assert_eq!(loaded.julia_view.c, Complex::new(0.285, 0.01));
+        assert_eq!(loaded.export.viewport, ExportViewport::SideBySide);
+        assert_eq!(loaded.fractal, proj.fractal);
+    }
+}
+
+#[test]
+fn custom_formulas_load_back_as_typed() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut proj = Project::new("Custom");
+    proj.fractal = FractalParams::new(FractalKind::Custom).with_custom_formula("sin(z) * c + 0.1i");
+    proj.fractal.compiled_formula = Some(Arc::new(
+        CustomFormula::parse(&proj.fractal.custom_formula).unwrap(),
+    ));
+    for name in ["scene.json", "scene.mahproj"] {
+        let path = dir.path().join(name);
+        save_project(&proj, &path).unwrap();
+        let loaded = load_project(&path).unwrap();
+        assert_eq!(loaded.fractal.kind, FractalKind::Custom);
+        assert_eq!(loaded.fractal.custom_formula, "sin(z) * c + 0.1i");
+        // Only the text is saved; renderers compile it again.
+        assert_eq!(loaded.fractal.compiled_formula, None);
+    }
+}
+
+#[test]
+fn old_burning_ship_traps_keep_the_legacy_fold() {
+    let mut proj = Project::default();
+    proj.fractal.kind = FractalKind::BurningShip;
+    proj.fractal.orbit.enabled = true;
+    let mut json = serde_json::to_value(&proj).unwrap();
+    json.as_object_mut().unwrap().remove("version");
+    let old = json.to_string();
+    let loaded = project_from_str(&old, Path::new("old.json")).unwrap();
+    assert!(loaded.fractal.legacy_burning_ship);
+    assert_eq!(loaded.version, PROJECT_VERSION);
+
+    // Current files, and old ones the fold makes no difference to, use the fixed formula.
+    let current = serde_json::to_string(&proj).unwrap();
+    let loaded = project_from_str(&current, Path::new("new.json")).unwrap();
+    assert!(!loaded.fractal.legacy_burning_ship);
+    json["fractal"]["orbit"]["enabled"] = false.into();
+    let loaded = project_from_str(&json.to_string(), Path::new("old.json")).unwrap();
+    assert!(!loaded.fractal.legacy_burning_ship);
+}
+
+#[test]
+fn old_projects_spread_the_palette_over_max_iter() {
+    let mut proj = Project::default();
+    proj.fractal.max_iter = 2500;
+    proj.fractal.palette_repeat = 100.0;
+    let mut json = serde_json::to_value(&proj).unwrap();
+    json["version"] = 2.into();
+    json["fractal"]
+        .as_object_mut()
+        .unwrap()
+        .remove("palette_repeat");
+    let loaded = project_from_str(&json.to_string(), Path::new("old.json")).unwrap();
+    assert_eq!(loaded.fractal.palette_repeat, 2500.0);
+
+    let current = serde_json::to_string(&proj).unwrap();
+    let loaded = project_from_str(&current, Path::new("new.json")).unwrap();
+    assert_eq!(loaded.fractal.palette_repeat, 100.0);
+}
+
+#[test]
+fn old_export_settings_move_into_a_quality_profile() {
+    let proj = Project::default();
+    let mut json = serde_json::to_value(&proj).unwrap();
+    json["version"] = 3.into();
+    let export = json["export"].as_object_mut().unwrap();
+    export.remove("quality");
+    export.insert("tile_size".into(), 2048.into());
+    let loaded = project_from_str(&json.to_string(), Path::new("old.json")).unwrap();
+    assert_eq!(loaded.export.quality, QualityProfile::final_quality());
+
+    json["export"]["tile_size"] = 512.into();
+    json["export"]["sample_pattern"] = "halton".into();
+    let loaded = project_from_str(&json.to_string(), Path::new("old.json")).unwrap();
+    let quality = &loaded.export.quality;
+    assert_eq!(quality.name, "Custom");
+    assert_eq!(quality.tile_size, 512);
+    assert_eq!(quality.sample_pattern, SamplePattern::Halton);
+
+    // The old fields are never written back.
+    let saved = serde_json::to_value(&loaded).unwrap();
+    assert!(saved["export"].get("tile_size").is_none());
+    assert_eq!(saved["export"]["quality"]["tile_size"], 512);
+}
+
+#[test]
+fn built_in_profiles_with_fixed_tiles_load_with_auto_tiles() {
+    let mut proj = Project::default();
+    proj.export.quality = QualityProfile::final_quality().with_tile_size(2048);
+    proj.quality = QualityProfile::preview().with_tile_size(2048);
+    let mut json = serde_json::to_value(&proj).unwrap();
+    json["version"] = 4.into();
+    let loaded = project_from_str(&json.to_string(), Path::new("old.json")).unwrap();
+    assert_eq!(loaded.export.quality, QualityProfile::final_quality());
+    assert_eq!(loaded.quality.tile_size, 0);
+
+    // Tiles picked in a profile of one's own are kept.
+    json["export"]["quality"]["name"] = "Mine".into();
+    let loaded = project_from_str(&json.to_string(), Path::new("old.json")).unwrap();
+    assert_eq!(loaded.export.quality.tile_size, 2048);
+}
+
+#[test]
+fn old_projects_keep_the_flat_palette_interior() {
+    let proj = Project::default();
+    assert_eq!(proj.fractal.interior_coloring, InteriorColoring::Solid);
+    let mut json = serde_json::to_value(&proj).unwrap();
+    json["version"] = 6.into();
+    let fractal = json["fractal"].as_object_mut().unwrap();
+    fractal.remove("interior_coloring");
+    fractal.remove("interior_color");
+    let loaded = project_from_str(&json.to_string(), Path::new("old.json")).unwrap();
+    assert_eq!(loaded.fractal.interior_coloring, InteriorColoring::Palette);
+
+    let current = serde_json::to_string(&proj).unwrap();
+    let loaded = project_from_str(&current, Path::new("new.json")).unwrap();
+    assert_eq!(loaded.fractal.interior_coloring, InteriorColoring::Solid);
+}
+
+#[test]
+fn nan_written_to_json_as_null_loads_repaired() {
+    let mut proj = Project::default();
+    proj.camera.scale = f64::NAN;
+    proj.anim.kf_center_x.upsert(0.0, -0.5);
+    proj.anim.kf_center_x.upsert(1.0, f32::INFINITY);
+    let json = serde_json::to_string_pretty(&proj).unwrap();
+    assert!(json.contains("null"));
+
+    let (loaded, repairs) = project_from_str_repaired(&json, Path::new("nan.json")).unwrap();
+    assert_eq!(loaded.camera.scale, Camera::default().scale);
+    assert_eq!(loaded.anim.kf_center_x.keys.len(), 1);
+    let params: Vec<&str> = repairs.iter().map(|r| r.param.as_str()).collect();
+    assert_eq!(params, ["camera.scale", "anim.kf_center_x"]);
+
+    // Saving never writes the nulls in the first place.
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("nan.json");
+    save_project(&proj, &path).unwrap();
+    let (_, repairs) = load_project_repaired(&path).unwrap();
+    assert!(repairs.is_empty());
+}
+
+#[test]
+fn deep_centers_keep_every_digit_through_a_save() {
+    let re = "-1.768573656315270993281742915329544712934";
+    let im = "0.0009640921161910352408181487472263471398";
+    let mut json = serde_json::to_value(Project::default()).unwrap();
+    json["camera"]["center"] = serde_json::json!({ "re": re, "im": im });
+    json["camera"]["scale"] = 1e30.into();
+    let proj = project_from_str(&json.to_string(), Path::new("deep.json")).unwrap();
+    assert_eq!(proj.camera.center.re, re.parse::<f64>().unwrap());
+    assert!(proj.camera.deep_center.is_some());
+
+    let dir = tempfile::tempdir().unwrap();
+    for name in ["deep.json", "deep.mahproj"] {
+        let path = dir.path().join(name);
+        save_project(&proj, &path).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.contains(&format!("\"{re}\"")), "{text}");
+        assert!(text.contains(&format!("\"{im}\"")), "{text}");
+        let loaded = load_project(&path).unwrap();
+        assert_eq!(loaded.camera.exact_center(), proj.camera.exact_center());
+    }
+
+    // Centers an f64 holds are written as it prints them.
+    let plain = serde_json::to_value(Camera::default()).unwrap();
+    assert_eq!(
+        plain["center"],
+        serde_json::json!({ "re": "-0.5", "im": "0" })
+    );
+}
+
+#[test]
+fn numeric_centers_of_older_files_still_load() {
+    let mut json = serde_json::to_value(Project::default()).unwrap();
+    json["version"] = 5.into();
+    json["camera"]["center"] = serde_json::json!({ "re": -0.7436439, "im": 0.1318259 });
+    let loaded = project_from_str(&json.to_string(), Path::new("old.json")).unwrap();
+    assert_eq!(loaded.camera.center, Complex64::new(-0.7436439, 0.1318259));
+    assert!(loaded.camera.deep_center.is_none());
+
+    json["camera"]["center"]["re"] = "-0.7.4".into();
+    let err = project_from_str(&json.to_string(), Path::new("old.json")).unwrap_err();
+    assert!(
+        err.to_string().contains("\"-0.7.4\" is not a number"),
+        "{err}"
+    );
+}
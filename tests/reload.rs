@@ -0,0 +1,100 @@
+//! Reconciling an open project with its file after an outside edit.
+
+use std::{
+    fs,
+    time::{Duration, Instant},
+};
+
+use matterhorn_core::*;
+
+/// The project as opened, and the file after someone changed its iteration count.
+fn opened_and_edited() -> (Project, Project) {
+    let base = Project::default();
+    let mut on_disk = base.clone();
+    on_disk.fractal.max_iter = 4321;
+    (base, on_disk)
+}
+
+#[test]
+fn clean_project_reloads() {
+    let (base, on_disk) = opened_and_edited();
+    let mut open = base.clone();
+    // Scrubbing and playing are not edits.
+    open.anim.t = 1.5;
+    open.anim.playing = true;
+    assert_eq!(reconcile(&open, &base, &on_disk), Reconcile::Reload);
+    let reloaded = resolve(&open, on_disk, ReloadChoice::Reload);
+    assert_eq!(reloaded.fractal.max_iter, 4321);
+    assert_eq!(reloaded.anim.t, 1.5);
+    assert!(reloaded.anim.playing);
+}
+
+#[test]
+fn dirty_project_asks_and_honors_each_choice() {
+    let (base, on_disk) = opened_and_edited();
+    let mut open = base.clone();
+    open.camera.center = Complex64::new(-0.75, 0.1);
+    open.fractal.max_iter = 99;
+    assert_eq!(reconcile(&open, &base, &on_disk), Reconcile::Conflict);
+
+    let reloaded = resolve(&open, on_disk.clone(), ReloadChoice::Reload);
+    assert_eq!(reloaded.fractal.max_iter, 4321);
+    assert_eq!(reloaded.camera.center, base.camera.center);
+
+    let kept = resolve(&open, on_disk.clone(), ReloadChoice::KeepMine);
+    assert_eq!(kept.fractal.max_iter, 99);
+
+    let merged = resolve(&open, on_disk, ReloadChoice::MergeView);
+    assert_eq!(merged.fractal.max_iter, 4321);
+    assert_eq!(merged.camera.center, open.camera.center);
+}
+
+#[test]
+fn own_saves_and_touches_change_nothing() {
+    let (base, _) = opened_and_edited();
+    let mut open = base.clone();
+    open.fractal.max_iter = 99;
+    // The app saved: the file now matches what is open, thumbnail aside.
+    let mut saved = open.clone();
+    saved.thumbnail = Some("iVBORw0KGgo=".into());
+    assert_eq!(reconcile(&open, &base, &saved), Reconcile::Unchanged);
+    // The file was rewritten with the same contents.
+    assert_eq!(reconcile(&open, &base, &base), Reconcile::Unchanged);
+}
+
+#[test]
+fn reload_goes_through_migration() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("scene.json");
+    let base = Project::default();
+    save_project(&base, &path).unwrap();
+    // An editor rewrites the file in an older format version.
+    let mut value: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    value["version"] = 2.into();
+    value["fractal"]["max_iter"] = 640.into();
+    fs::write(&path, value.to_string()).unwrap();
+    let on_disk = load_project(&path).unwrap();
+    assert_eq!(on_disk.version, PROJECT_VERSION);
+    assert_eq!(reconcile(&base, &base, &on_disk), Reconcile::Reload);
+    // Version 3 tied the palette repeat to Max Iter for older files.
+    assert_eq!(on_disk.fractal.palette_repeat, 640.0);
+}
+
+#[test]
+fn debounce_waits_for_a_quiet_file() {
+    let start = Instant::now();
+    let ms = |n| start + Duration::from_millis(n);
+    let mut debounce = Debounce::new(Duration::from_millis(300));
+    assert!(!debounce.ready(ms(0)));
+    // An editor saving in three writes.
+    debounce.changed(ms(0));
+    debounce.changed(ms(100));
+    assert!(!debounce.ready(ms(250)));
+    debounce.changed(ms(250));
+    assert!(!debounce.ready(ms(500)));
+    assert!(debounce.ready(ms(550)));
+    // Once per burst.
+    assert!(!debounce.pending());
+    assert!(!debounce.ready(ms(900)));
+}
@@ -0,0 +1,200 @@
+//! Network tile rendering against in-process workers.
+
+use std::{
+    io::{Read, Write},
+    net::TcpListener,
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+
+use matterhorn_core::*;
+
+/// Starts a CPU worker on a free local port and returns its address.
+fn spawn_worker() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    thread::spawn(move || serve(&listener, RenderBackend::Cpu));
+    addr
+}
+
+/// An address nothing listens on.
+fn dead_address() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.local_addr().unwrap().to_string()
+}
+
+/// A local renderer that must not be needed.
+struct NoLocal;
+
+impl Renderer for NoLocal {
+    fn name(&self) -> &'static str {
+        "none"
+    }
+
+    fn render_tile(
+        &mut self,
+        _tile: &TileInfo,
+        _params: &FractalParams,
+        _cam: &Camera,
+        _palette: &[[u8; 3]],
+    ) -> Result<Vec<u8>, RenderError> {
+        Err(RenderError::Failed("rendered locally".into()))
+    }
+}
+
+fn view() -> (FractalParams, Camera) {
+    (
+        FractalParams::new(FractalKind::Julia).with_max_iter(100),
+        Camera::new(Complex::new(0.0, 0.0), 120.0),
+    )
+}
+
+fn remote(workers: Vec<String>, local: Box<dyn Renderer>) -> RemoteRenderer {
+    RemoteRenderer::new(workers, local)
+        .with_job_size(256)
+        .with_timeout(Duration::from_secs(5))
+}
+
+#[test]
+fn workers_render_the_same_pixels_as_the_cpu() {
+    let (params, cam) = view();
+    let mut renderer = remote(vec![spawn_worker(), spawn_worker()], Box::new(NoLocal));
+    for _ in 0..2 {
+        // The second frame reuses the open connections.
+        let pixels = render_image((700, 400), &params, &cam, &mut renderer, 0).unwrap();
+        assert_eq!(pixels, render_cpu((700, 400), &params, &cam));
+    }
+
+    // Tiles handed to the remote renderer by render_image are split up the same way.
+    let pixels = render_image((700, 400), &params, &cam, &mut renderer, 300).unwrap();
+    assert_eq!(pixels, render_cpu((700, 400), &params, &cam));
+}
+
+#[test]
+fn failed_jobs_move_to_another_worker_or_run_locally() {
+    let (params, cam) = view();
+    let expected = render_cpu((600, 300), &params, &cam);
+
+    let mut renderer = remote(vec![dead_address(), spawn_worker()], Box::new(NoLocal));
+    let pixels = render_image((600, 300), &params, &cam, &mut renderer, 0).unwrap();
+    assert_eq!(pixels, expected);
+
+    let mut renderer = remote(vec![dead_address()], Box::new(CpuRenderer));
+    let pixels = render_image((600, 300), &params, &cam, &mut renderer, 0).unwrap();
+    assert_eq!(pixels, expected);
+
+    let mut renderer = remote(vec![dead_address()], Box::new(NoLocal));
+    assert_eq!(
+        render_image((600, 300), &params, &cam, &mut renderer, 0),
+        Err(RenderError::Failed("rendered locally".into()))
+    );
+}
+
+#[test]
+fn workers_use_the_attached_equalizer() {
+    // An equalizer measured on another view, as a time-smoothed export attaches.
+    let (params, cam) = view();
+    let params = params.with_equalized_palette();
+    let other = Camera::new(Complex::new(0.2, 0.1), 300.0);
+    let mut smoothed = params.clone();
+    smoothed.equalizer = measure_equalizer((400, 300), &params, &other).map(Arc::new);
+    assert_ne!(
+        smoothed.equalizer.as_deref(),
+        measure_equalizer((400, 300), &params, &cam).as_ref()
+    );
+
+    let mut renderer = remote(vec![spawn_worker()], Box::new(NoLocal));
+    let pixels = render_image((400, 300), &smoothed, &cam, &mut renderer, 0).unwrap();
+    assert_eq!(pixels, render_cpu((400, 300), &smoothed, &cam));
+    assert_ne!(pixels, render_cpu((400, 300), &params, &cam));
+
+    let mut conn = WorkerConnection::connect(&spawn_worker(), Duration::from_secs(5)).unwrap();
+    let mut job = TileJob::new(&smoothed, &cam, TileInfo::full(64, 64));
+    job.equalizer = Some(vec![1.0, 0.0]);
+    let err = conn.render(&job).unwrap_err();
+    assert!(
+        matches!(&err, RemoteError::Worker(m) if m.contains("invalid equalizer")),
+        "{err}"
+    );
+}
+
+#[test]
+fn workers_reject_other_versions_and_bad_tiles() {
+    let (params, cam) = view();
+    let mut conn = WorkerConnection::connect(&spawn_worker(), Duration::from_secs(5)).unwrap();
+
+    let mut job = TileJob::new(&params, &cam, TileInfo::full(64, 64));
+    job.version = 99;
+    let err = conn.render(&job).unwrap_err();
+    assert!(
+        matches!(&err, RemoteError::Worker(m) if m.contains("job version 99")),
+        "{err}"
+    );
+
+    let mut job = TileJob::new(&params, &cam, TileInfo::full(64, 64));
+    job.tile.offset_x = 32;
+    let err = conn.render(&job).unwrap_err();
+    assert!(
+        matches!(&err, RemoteError::Worker(m) if m.contains("outside the frame")),
+        "{err}"
+    );
+
+    // The connection survives refused jobs.
+    let job = TileJob::new(&params, &cam, TileInfo::full(64, 64));
+    assert_eq!(
+        conn.render(&job).unwrap(),
+        render_cpu((64, 64), &params, &cam)
+    );
+}
+
+#[test]
+fn corrupted_tiles_fail_the_checksum() {
+    // A worker that answers every job with black pixels under a checksum that does not
+    // match them.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut head = [0u8; 8];
+            while stream.read_exact(&mut head).is_ok() {
+                let len = u32::from_be_bytes([head[4], head[5], head[6], head[7]]);
+                let mut header = vec![0; len as usize];
+                stream.read_exact(&mut header).unwrap();
+                let job: TileJob = serde_json::from_slice(&header).unwrap();
+                let pixels = vec![0u8; (job.tile.tile_w * job.tile.tile_h * 4) as usize];
+                let mut encoder =
+                    flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::fast());
+                encoder.write_all(&pixels).unwrap();
+                let body = encoder.finish().unwrap();
+                let reply = serde_json::to_vec(&TileReply {
+                    version: JOB_VERSION,
+                    error: None,
+                    crc32: crc32fast::hash(&pixels) ^ 1,
+                    compressed: body.len() as u32,
+                })
+                .unwrap();
+                stream.write_all(b"MHTL").unwrap();
+                stream
+                    .write_all(&(reply.len() as u32).to_be_bytes())
+                    .unwrap();
+                stream.write_all(&reply).unwrap();
+                stream.write_all(&body).unwrap();
+            }
+        }
+    });
+
+    let (params, cam) = view();
+    let mut conn = WorkerConnection::connect(&addr, Duration::from_secs(5)).unwrap();
+    let err = conn
+        .render(&TileJob::new(&params, &cam, TileInfo::full(32, 32)))
+        .unwrap_err();
+    assert!(matches!(err, RemoteError::Checksum { .. }), "{err}");
+    drop(conn);
+
+    // Through the renderer, the rejected tiles are rendered locally instead.
+    let mut renderer = remote(vec![addr], Box::new(CpuRenderer)).with_retries(0);
+    let pixels = render_image((64, 48), &params, &cam, &mut renderer, 0).unwrap();
+    assert_eq!(pixels, render_cpu((64, 48), &params, &cam));
+}
@@ -0,0 +1,409 @@
+//! The `Renderer` trait: tile limits, the CPU fallback wrapper and its demotion, and tiled
+//! renders matching untiled ones.
+
+use std::{
+    f32::consts::PI,
+    sync::{Arc, Mutex},
+};
+
+use matterhorn_core::*;
+use proptest::prelude::*;
+
+/// Records the tiles it is asked for; fails them or refuses Julia sets on request.
+struct Probe {
+    tiles: Vec<(u32, u32)>,
+    fail: bool,
+    max_tile: u32,
+}
+
+impl Probe {
+    fn new(max_tile: u32) -> Self {
+        Self {
+            tiles: Vec::new(),
+            fail: false,
+            max_tile,
+        }
+    }
+}
+
+impl Renderer for Probe {
+    fn name(&self) -> &'static str {
+        "probe"
+    }
+
+    fn render_tile(
+        &mut self,
+        tile: &TileInfo,
+        params: &FractalParams,
+        cam: &Camera,
+        palette: &[[u8; 3]],
+    ) -> Result<Vec<u8>, RenderError> {
+        self.tiles.push((tile.tile_w, tile.tile_h));
+        if self.fail {
+            return Err(RenderError::Failed("device lost".into()));
+        }
+        CpuRenderer.render_tile(tile, params, cam, palette)
+    }
+
+    fn max_tile_size(&self) -> u32 {
+        self.max_tile
+    }
+
+    fn supports(&self, params: &FractalParams) -> bool {
+        params.kind != FractalKind::Julia
+    }
+}
+
+fn view() -> (FractalParams, Camera) {
+    (
+        FractalParams::new(FractalKind::Mandelbrot).with_max_iter(64),
+        Camera::new(Complex::new(-0.5, 0.0), 40.0),
+    )
+}
+
+#[test]
+fn tiles_never_exceed_the_renderer_limit() {
+    let (params, cam) = view();
+    let mut probe = Probe::new(256);
+    let pixels = render_image((600, 300), &params, &cam, &mut probe, 0).unwrap();
+    assert_eq!(pixels, render_cpu((600, 300), &params, &cam));
+    assert_eq!(probe.tiles.len(), 6);
+    assert!(probe.tiles.iter().all(|&(w, h)| w <= 256 && h <= 256));
+
+    // A smaller requested tile size still wins.
+    let mut probe = Probe::new(1024);
+    render_image((600, 300), &params, &cam, &mut probe, 300).unwrap();
+    assert_eq!(probe.tiles, vec![(300, 300), (300, 300)]);
+}
+
+#[test]
+fn errors_propagate_without_a_fallback() {
+    let (params, cam) = view();
+    let mut probe = Probe::new(0);
+    probe.fail = true;
+    assert_eq!(
+        render_image((32, 32), &params, &cam, &mut probe, 0),
+        Err(RenderError::Failed("device lost".into()))
+    );
+}
+
+#[test]
+fn fallback_covers_failed_and_unsupported_tiles() {
+    let (params, cam) = view();
+    let expected = render_cpu((64, 48), &params, &cam);
+    let mut failing = Probe::new(0);
+    failing.fail = true;
+    let mut renderer = FallbackRenderer::new(Box::new(failing), Box::new(CpuRenderer));
+    assert_eq!(renderer.name(), "probe");
+    assert_eq!(
+        render_image((64, 48), &params, &cam, &mut renderer, 0).unwrap(),
+        expected
+    );
+
+    let julia = FractalParams::new(FractalKind::Julia).with_max_iter(64);
+    let mut renderer = FallbackRenderer::new(Box::new(Probe::new(128)), Box::new(CpuRenderer));
+    assert!(renderer.supports(&julia));
+    assert_eq!(renderer.max_tile_size(), 128);
+    assert_eq!(renderer.precision(), Precision::F32);
+    assert_eq!(
+        render_image((64, 48), &julia, &cam, &mut renderer, 0).unwrap(),
+        render_cpu((64, 48), &julia, &cam)
+    );
+}
+
+/// Shared with the test: the error every tile fails with, and how many tiles and renderers
+/// were made.
+#[derive(Default)]
+struct FlakyState {
+    error: Option<RenderError>,
+    tiles: u32,
+    created: u32,
+}
+
+/// Fails with whatever the shared state says, like a GPU going bad mid-session.
+struct Flaky(Arc<Mutex<FlakyState>>);
+
+impl Flaky {
+    fn boxed(state: &Arc<Mutex<FlakyState>>) -> Box<dyn Renderer> {
+        state.lock().unwrap().created += 1;
+        Box::new(Flaky(state.clone()))
+    }
+}
+
+impl Renderer for Flaky {
+    fn name(&self) -> &'static str {
+        "flaky"
+    }
+
+    fn render_tile(
+        &mut self,
+        tile: &TileInfo,
+        params: &FractalParams,
+        cam: &Camera,
+        palette: &[[u8; 3]],
+    ) -> Result<Vec<u8>, RenderError> {
+        let mut state = self.0.lock().unwrap();
+        state.tiles += 1;
+        match &state.error {
+            Some(err) => Err(err.clone()),
+            None => CpuRenderer.render_tile(tile, params, cam, palette),
+        }
+    }
+}
+
+fn flaky_fallback(state: &Arc<Mutex<FlakyState>>) -> FallbackRenderer {
+    let recreate = state.clone();
+    FallbackRenderer::new(Flaky::boxed(state), Box::new(CpuRenderer))
+        .with_recreate(Box::new(move || Ok(Flaky::boxed(&recreate))))
+}
+
+fn fail_with(state: &Arc<Mutex<FlakyState>>, error: Option<RenderError>) {
+    state.lock().unwrap().error = error;
+}
+
+#[test]
+fn repeated_failures_demote_to_the_fallback() {
+    let (params, cam) = view();
+    let expected = render_cpu((32, 32), &params, &cam);
+    let state = Arc::new(Mutex::new(FlakyState::default()));
+    let mut renderer = flaky_fallback(&state);
+    let failed = RenderError::Failed("driver timeout".into());
+    fail_with(&state, Some(failed.clone()));
+
+    for _ in 1..PRIMARY_FAILURE_LIMIT {
+        assert_eq!(
+            render_image((32, 32), &params, &cam, &mut renderer, 0).unwrap(),
+            expected
+        );
+    }
+    assert_eq!(renderer.demotion(), None);
+    assert_eq!(renderer.name(), "flaky");
+
+    // A success in between starts the count over.
+    fail_with(&state, None);
+    render_image((32, 32), &params, &cam, &mut renderer, 0).unwrap();
+    fail_with(&state, Some(failed.clone()));
+    for _ in 1..PRIMARY_FAILURE_LIMIT {
+        render_image((32, 32), &params, &cam, &mut renderer, 0).unwrap();
+    }
+    assert_eq!(renderer.demotion(), None);
+
+    render_image((32, 32), &params, &cam, &mut renderer, 0).unwrap();
+    assert_eq!(
+        renderer.demotion(),
+        Some(&Demotion {
+            renderer: "flaky",
+            failures: PRIMARY_FAILURE_LIMIT,
+            error: failed,
+        })
+    );
+    assert_eq!(renderer.name(), "CPU");
+
+    // Demoted: the primary is not asked again, and ordinary failures never recreate it.
+    let tiles = state.lock().unwrap().tiles;
+    assert_eq!(
+        render_image((32, 32), &params, &cam, &mut renderer, 0).unwrap(),
+        expected
+    );
+    assert_eq!(state.lock().unwrap().tiles, tiles);
+    assert_eq!(state.lock().unwrap().created, 1);
+
+    fail_with(&state, None);
+    renderer.retry_primary().unwrap();
+    assert_eq!(renderer.demotion(), None);
+    assert_eq!(renderer.name(), "flaky");
+    render_image((32, 32), &params, &cam, &mut renderer, 0).unwrap();
+    assert_eq!(state.lock().unwrap().tiles, tiles + 1);
+}
+
+#[test]
+fn lost_devices_are_recreated_once() {
+    let (params, cam) = view();
+    let state = Arc::new(Mutex::new(FlakyState::default()));
+    let mut renderer = flaky_fallback(&state);
+    let lost = RenderError::DeviceLost("Map error: device lost".into());
+
+    fail_with(&state, Some(lost.clone()));
+    render_image((32, 32), &params, &cam, &mut renderer, 0).unwrap();
+    assert_eq!(state.lock().unwrap().created, 2);
+    assert_eq!(renderer.demotion(), None);
+
+    // The new device is lost too: no second attempt, straight to the CPU.
+    render_image((32, 32), &params, &cam, &mut renderer, 0).unwrap();
+    assert_eq!(state.lock().unwrap().created, 2);
+    assert_eq!(renderer.demotion().map(|d| &d.error), Some(&lost));
+    assert_eq!(renderer.name(), "CPU");
+
+    // Retrying builds a fresh renderer.
+    fail_with(&state, None);
+    renderer.retry_primary().unwrap();
+    assert_eq!(state.lock().unwrap().created, 3);
+    assert_eq!(renderer.name(), "flaky");
+    assert_eq!(
+        render_image((32, 32), &params, &cam, &mut renderer, 0).unwrap(),
+        render_cpu((32, 32), &params, &cam)
+    );
+}
+
+#[test]
+fn cpu_backend_is_always_available() {
+    let renderer = create_renderer(RenderBackend::Cpu).unwrap();
+    assert_eq!(renderer.name(), "CPU");
+    assert_eq!(renderer.max_tile_size(), 0);
+}
+
+/// A view that exercises rotation, exposure and the orbit trap.
+fn styled_view(
+    kind: FractalKind,
+    rotation: f32,
+    exposure: f32,
+    trap: bool,
+) -> (FractalParams, Camera) {
+    let mut params = FractalParams::new(kind).with_max_iter(48);
+    params.exposure = exposure;
+    params.orbit.enabled = trap;
+    params.orbit.kind = OrbitTrapKind::Circle;
+    let cam = Camera::new(Complex::new(-0.4, 0.1), 90.0).with_rotation(rotation);
+    (params, cam)
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(24))]
+
+    #[test]
+    fn tiled_cpu_renders_match_untiled(
+        width in 257u32..720,
+        height in 200u32..540,
+        tile in 256u32..520,
+        rotation in -PI..PI,
+        kind in prop::sample::select(vec![
+            FractalKind::Mandelbrot,
+            FractalKind::Julia,
+            FractalKind::BurningShip,
+            FractalKind::Multibrot,
+            FractalKind::Tricorn,
+            FractalKind::Newton,
+            FractalKind::Phoenix,
+            FractalKind::Celtic,
+            FractalKind::Buffalo,
+            FractalKind::Lyapunov,
+        ]),
+        exposure in 0.5f32..4.0,
+        trap in any::<bool>(),
+    ) {
+        let (params, cam) = styled_view(kind, rotation, exposure, trap);
+        let whole = render_image((width, height), &params, &cam, &mut CpuRenderer, 0).unwrap();
+        let tiled = render_image((width, height), &params, &cam, &mut CpuRenderer, tile).unwrap();
+        // Compare row by row so a failure names the first seam.
+        for (y, (a, b)) in whole
+            .chunks(width as usize * 4)
+            .zip(tiled.chunks(width as usize * 4))
+            .enumerate()
+        {
+            prop_assert!(a == b, "row {} differs with {} px tiles", y, tile);
+        }
+    }
+}
+
+/// `params` with every coloring input changed and nothing that affects the samples.
+fn recolored(params: &FractalParams) -> FractalParams {
+    let mut colors = params.clone();
+    colors.palette_phase = 0.37;
+    colors.exposure = params.exposure * 1.7;
+    colors.gamma = 1.6;
+    colors.palette.reverse();
+    colors.palette[0].color = [0.9, 0.2, 0.4];
+    colors.orbit.color = [0.1, 0.8, 0.3];
+    colors.orbit.softness *= 0.5;
+    assert!(colors.same_samples(params));
+    colors
+}
+
+#[test]
+fn recoloring_samples_matches_a_full_render() {
+    for kind in [
+        FractalKind::Mandelbrot,
+        FractalKind::Julia,
+        FractalKind::Multibrot,
+        FractalKind::Newton,
+        FractalKind::Lyapunov,
+    ] {
+        for trap in [false, true] {
+            let (params, cam) = styled_view(kind, 0.4, 1.5, trap);
+            let colors = recolored(&params);
+            for tile in [0, 256] {
+                let (_, _, samples) =
+                    render_image_samples((420, 300), &params, &cam, &mut CpuRenderer, tile)
+                        .unwrap();
+                let (fast, _) =
+                    shade_image((420, 300), &samples, &colors, &mut CpuRenderer, tile).unwrap();
+                let full = render_image((420, 300), &colors, &cam, &mut CpuRenderer, tile).unwrap();
+                assert!(fast == full, "{kind:?}, trap {trap}, {tile} px tiles");
+            }
+        }
+    }
+}
+
+#[test]
+fn samples_change_with_everything_but_colors() {
+    let (params, _) = styled_view(FractalKind::Julia, 0.0, 1.0, true);
+    let edits: [fn(&mut FractalParams); 8] = [
+        |p| p.max_iter += 1,
+        |p| p.escape_radius *= 2.0,
+        |p| p.c.im += 0.01,
+        |p| p.phoenix_p.re += 0.01,
+        |p| p.custom_formula.push_str(" + 0.01"),
+        |p| p.kind = FractalKind::Mandelbrot,
+        |p| p.orbit.radius += 0.1,
+        |p| p.orbit.enabled = false,
+    ];
+    for edit in edits {
+        let mut changed = params.clone();
+        edit(&mut changed);
+        assert!(!changed.same_samples(&params));
+    }
+}
+
+#[cfg(feature = "gpu")]
+#[test]
+fn gpu_recoloring_matches_a_full_render() {
+    let mut gpu = match create_renderer(RenderBackend::Gpu) {
+        Ok(gpu) => gpu,
+        Err(e) => {
+            eprintln!("Skipping GPU recoloring: {e}");
+            return;
+        }
+    };
+    for trap in [false, true] {
+        let (params, cam) = styled_view(FractalKind::Mandelbrot, 0.4, 1.5, trap);
+        let colors = recolored(&params);
+        let (pixels, _, samples) =
+            render_image_samples((420, 300), &params, &cam, gpu.as_mut(), 256).unwrap();
+        assert!(pixels == render_image((420, 300), &params, &cam, gpu.as_mut(), 256).unwrap());
+        let (fast, _) = shade_image((420, 300), &samples, &colors, gpu.as_mut(), 256).unwrap();
+        let full = render_image((420, 300), &colors, &cam, gpu.as_mut(), 256).unwrap();
+        assert!(fast == full, "trap {trap}");
+    }
+}
+
+#[cfg(feature = "gpu")]
+#[test]
+fn tiled_gpu_renders_match_untiled() {
+    let mut gpu = match create_renderer(RenderBackend::Gpu) {
+        Ok(gpu) => gpu,
+        Err(e) => {
+            eprintln!("Skipping GPU tiling: {e}");
+            return;
+        }
+    };
+    for rotation in [0.0, 0.3, -1.2, PI] {
+        for trap in [false, true] {
+            let (params, cam) = styled_view(FractalKind::Mandelbrot, rotation, 2.0, trap);
+            let whole = render_image((700, 530), &params, &cam, gpu.as_mut(), 0).unwrap();
+            for tile in [256, 300, 512] {
+                let tiled = render_image((700, 530), &params, &cam, gpu.as_mut(), tile).unwrap();
+                assert!(whole == tiled, "rotation {rotation}, {tile} px tiles");
+            }
+        }
+    }
+}
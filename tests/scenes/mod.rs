@@ -0,0 +1,209 @@
+//! Fixed scenes shared by the golden-image tests and the benchmarks.
+
+use matterhorn_core::{
+    Camera, ColoringMode, Complex, FractalKind, FractalParams, InteriorColoring, OrbitTrapKind,
+    SEAHORSE_REPEAT_SPOT,
+};
+
+/// Side length of golden-image renders.
+pub const GOLDEN_SIZE: u32 = 128;
+
+pub struct Scene {
+    pub name: &'static str,
+    pub params: FractalParams,
+    pub camera: Camera,
+}
+
+impl Scene {
+    fn new(name: &'static str, params: FractalParams, camera: Camera) -> Self {
+        Self {
+            name,
+            params,
+            camera,
+        }
+    }
+}
+
+/// The default Mandelbrot view, scaled to fit `size` pixels across.
+pub fn default_view(size: u32) -> Camera {
+    Camera::new(Complex::new(-0.5, 0.0), f64::from(size as f32 / 3.0))
+}
+
+fn trap(kind: OrbitTrapKind) -> FractalParams {
+    let mut params = FractalParams::new(FractalKind::Mandelbrot)
+        .with_max_iter(200)
+        .with_palette_repeat(200.0);
+    params.orbit.enabled = true;
+    params.orbit.kind = kind;
+    params
+}
+
+/// Every golden scene, framed for a `GOLDEN_SIZE` square render.
+pub fn golden_scenes() -> Vec<Scene> {
+    let overview = default_view(GOLDEN_SIZE);
+    vec![
+        Scene::new(
+            "mandelbrot",
+            FractalParams::new(FractalKind::Mandelbrot)
+                .with_max_iter(200)
+                .with_palette_repeat(200.0),
+            overview.clone(),
+        ),
+        Scene::new(
+            "julia",
+            FractalParams::new(FractalKind::Julia)
+                .with_max_iter(200)
+                .with_palette_repeat(200.0)
+                .with_julia_c(Complex::new(-0.8, 0.156)),
+            Camera::new(Complex::new(0.0, 0.0), f64::from(GOLDEN_SIZE as f32 / 3.2)),
+        ),
+        Scene::new(
+            "burning_ship",
+            FractalParams::new(FractalKind::BurningShip)
+                .with_max_iter(200)
+                .with_palette_repeat(200.0),
+            Camera::new(
+                Complex::new(-0.4, -0.5),
+                f64::from(GOLDEN_SIZE as f32 / 3.5),
+            ),
+        ),
+        // The classic ship, with its hull, masts and rigging.
+        Scene::new(
+            "burning_ship_ship",
+            FractalParams::new(FractalKind::BurningShip)
+                .with_max_iter(300)
+                .with_palette_repeat(300.0),
+            Camera::new(
+                Complex::new(-1.755, -0.03),
+                f64::from(GOLDEN_SIZE as f32 / 0.12),
+            ),
+        ),
+        Scene::new(
+            "multibrot_3",
+            FractalParams::new(FractalKind::Multibrot)
+                .with_max_iter(200)
+                .with_palette_repeat(200.0)
+                .with_power(3.0),
+            Camera::new(Complex::new(0.0, 0.0), f64::from(GOLDEN_SIZE as f32 / 3.0)),
+        ),
+        Scene::new(
+            "tricorn",
+            FractalParams::new(FractalKind::Tricorn)
+                .with_max_iter(200)
+                .with_palette_repeat(200.0),
+            Camera::new(Complex::new(-0.5, 0.0), f64::from(GOLDEN_SIZE as f32 / 3.5)),
+        ),
+        Scene::new(
+            "celtic",
+            FractalParams::new(FractalKind::Celtic)
+                .with_max_iter(200)
+                .with_palette_repeat(200.0),
+            Camera::new(Complex::new(-0.8, 0.0), f64::from(GOLDEN_SIZE as f32 / 3.2)),
+        ),
+        Scene::new(
+            "buffalo",
+            FractalParams::new(FractalKind::Buffalo)
+                .with_max_iter(200)
+                .with_palette_repeat(200.0),
+            Camera::new(
+                Complex::new(-0.5, -0.6),
+                f64::from(GOLDEN_SIZE as f32 / 3.2),
+            ),
+        ),
+        Scene::new(
+            "custom",
+            FractalParams::new(FractalKind::Custom)
+                .with_custom_formula("z^3 + c*(1 - 0.3i)")
+                .with_max_iter(200)
+                .with_palette_repeat(200.0),
+            Camera::new(Complex::new(0.0, 0.0), f64::from(GOLDEN_SIZE as f32 / 3.0)),
+        ),
+        Scene::new(
+            "lyapunov",
+            FractalParams::new(FractalKind::Lyapunov)
+                .with_lyapunov_sequence("AABAB")
+                .with_max_iter(200),
+            Camera::new(Complex::new(3.4, 3.4), f64::from(GOLDEN_SIZE as f32 / 1.2)),
+        ),
+        Scene::new(
+            "newton",
+            FractalParams::new(FractalKind::Newton).with_max_iter(100),
+            Camera::new(Complex::new(0.0, 0.0), f64::from(GOLDEN_SIZE as f32 / 4.0)),
+        ),
+        Scene::new(
+            "phoenix",
+            FractalParams::new(FractalKind::Phoenix)
+                .with_max_iter(200)
+                .with_palette_repeat(100.0),
+            Camera::new(Complex::new(0.0, 0.0), f64::from(GOLDEN_SIZE as f32 / 3.2)),
+        ),
+        Scene::new("trap_point", trap(OrbitTrapKind::Point), overview.clone()),
+        Scene::new("trap_circle", trap(OrbitTrapKind::Circle), overview.clone()),
+        Scene::new("trap_cross", trap(OrbitTrapKind::Cross), overview.clone()),
+        Scene::new(
+            "seahorse_zoom",
+            FractalParams::new(FractalKind::Mandelbrot)
+                .with_max_iter(1000)
+                .with_palette_repeat(1000.0),
+            Camera::new(SEAHORSE_REPEAT_SPOT.center, 200_000.0),
+        ),
+        Scene::new(
+            "rotated",
+            FractalParams::new(FractalKind::Mandelbrot)
+                .with_max_iter(200)
+                .with_palette_repeat(200.0)
+                .with_palette_phase(0.3),
+            overview.clone().with_rotation(0.6),
+        ),
+        // Distance estimate coloring: hairline filaments instead of an iteration glow.
+        Scene::new(
+            "distance_estimate",
+            FractalParams::new(FractalKind::Mandelbrot)
+                .with_max_iter(200)
+                .with_coloring(ColoringMode::DistanceEstimate),
+            overview.clone(),
+        ),
+        // The period-3 minibrot, its interior shaded by the orbit's final |z|.
+        Scene::new(
+            "interior_magnitude",
+            FractalParams::new(FractalKind::Mandelbrot)
+                .with_max_iter(300)
+                .with_palette_repeat(300.0)
+                .with_interior(InteriorColoring::Magnitude, [0.0; 3]),
+            Camera::new(
+                Complex::new(-1.7549, 0.0),
+                f64::from(GOLDEN_SIZE as f32 / 0.05),
+            ),
+        ),
+        // Flat dwell bands with contour lines, which compare each pixel with its neighbors.
+        Scene::new(
+            "dwell_bands",
+            FractalParams::new(FractalKind::Mandelbrot)
+                .with_max_iter(200)
+                .with_palette_repeat(20.0)
+                .with_dwell_bands(8)
+                .with_band_contours([0.0; 3]),
+            overview.clone(),
+        ),
+        // Histogram equalization, which measures the whole frame before shading it.
+        Scene::new(
+            "equalized",
+            FractalParams::new(FractalKind::Mandelbrot)
+                .with_max_iter(200)
+                .with_equalized_palette(),
+            overview.clone(),
+        ),
+        // Stripe average coloring, at the large escape radius that keeps it smooth.
+        Scene::new(
+            "stripes",
+            FractalParams {
+                escape_radius: 1000.0,
+                ..FractalParams::new(FractalKind::Mandelbrot)
+                    .with_max_iter(200)
+                    .with_palette_repeat(200.0)
+                    .with_stripes(5, 0.8)
+            },
+            overview,
+        ),
+    ]
+}